@@ -0,0 +1,13 @@
+// Copy-to-clipboard buttons for the shown-once secrets (invite links, API keys).
+// A [data-copy-target="<id>"] button copies that element's text and confirms in
+// place. Delegated, so it works in htmx-swapped content too — and it replaces the
+// old inline onclick, which the Content-Security-Policy no longer runs.
+document.addEventListener("click", (e) => {
+  const btn = e.target.closest && e.target.closest("[data-copy-target]");
+  if (!btn) return;
+  const source = document.getElementById(btn.dataset.copyTarget);
+  if (!source) return;
+  navigator.clipboard.writeText(source.textContent).then(() => {
+    btn.textContent = "Copied!";
+  });
+});
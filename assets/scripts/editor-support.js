@@ -1,5 +1,4 @@
 //From https://stackoverflow.com/a/34278578/160208
-// biome-ignore lint/correctness/noUnusedVariables: called from template inline handlers
 function addLink() {
   const el = document.getElementById("page_markdown");
   const [start, end] = [el.selectionStart, el.selectionEnd];
@@ -8,7 +7,6 @@ function addLink() {
   el.dispatchEvent(new Event("change", { bubbles: true }));
 }
 
-// biome-ignore lint/correctness/noUnusedVariables: called from template inline handlers
 function addImage() {
   const el = document.getElementById("page_markdown");
   const [start, end] = [el.selectionStart, el.selectionEnd];
@@ -17,7 +15,6 @@ function addImage() {
   el.dispatchEvent(new Event("change", { bubbles: true }));
 }
 
-// biome-ignore lint/correctness/noUnusedVariables: called from template inline handlers
 function addChildIndex() {
   const el = document.getElementById("page_markdown");
   const [start, end] = [el.selectionStart, el.selectionEnd];
@@ -28,7 +25,6 @@ function addChildIndex() {
   el.dispatchEvent(new Event("change", { bubbles: true }));
 }
 
-// biome-ignore lint/correctness/noUnusedVariables: called from template inline handlers
 function addDiagram() {
   const el = document.getElementById("page_markdown");
   const [start, end] = [el.selectionStart, el.selectionEnd];
@@ -143,6 +139,20 @@ function uploadMediaFiles(files) {
     });
 }
 
+// Toolbar insert buttons: [data-editor-insert="<kind>"]. Delegated rather than
+// inline onclick handlers, which the Content-Security-Policy blocks.
+const EDITOR_INSERTS = {
+  link: addLink,
+  image: addImage,
+  children: addChildIndex,
+  diagram: addDiagram,
+};
+document.addEventListener("click", (e) => {
+  const btn = e.target.closest && e.target.closest("[data-editor-insert]");
+  const insert = btn && EDITOR_INSERTS[btn.dataset.editorInsert];
+  if (insert) insert();
+});
+
 document.addEventListener("DOMContentLoaded", () => {
   // Library picker + the camera capture input (EB.3) share the upload path.
   for (const id of ["media-upload-input", "media-capture-input"]) {
@@ -40,6 +40,11 @@
     if (!xhr) {
       return;
     }
+    // "Confirm with your passkey" isn't a failure — step-up.js runs the
+    // re-prompt and retries (its own failures come back through app:toast).
+    if (xhr.getResponseHeader("X-Step-Up-Required")) {
+      return;
+    }
     // textContent (never innerHTML) — the body is server text, rendered as
     // text, so a stray error body can't inject markup. A short plain-text
     // reason ("A page with slug 'x' already exists") shows verbatim; a long
@@ -52,6 +57,13 @@
     show_toast(message);
   });
 
+  // Other scripts surface a message through the same toast.
+  document.body.addEventListener("app:toast", function (evt) {
+    if (evt.detail && evt.detail.message) {
+      show_toast(evt.detail.message);
+    }
+  });
+
   // A total network failure (server unreachable) fires htmx:sendError, not
   // responseError — there's no xhr status to read.
   document.body.addEventListener("htmx:sendError", function () {
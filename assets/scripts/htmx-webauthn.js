@@ -218,21 +218,34 @@
     finish_register_url,
     display_name,
   ) {
-    const register_opt_response = await fetch(
-      // encodeURIComponent so a name with ? / # % rides the path intact — a raw
-      // name silently truncated (`?`) or 404'd (`/`) the registration (DM.7).
-      // location.search forwards ?next= — see webauthn_authenticate.
-      start_register_url +
-        "/" +
-        encodeURIComponent(display_name) +
-        window.location.search,
-      {
-        method: "GET",
-        headers: {
-          "Content-Type": "application/json",
+    const start = function () {
+      return fetch(
+        // encodeURIComponent so a name with ? / # % rides the path intact — a raw
+        // name silently truncated (`?`) or 404'd (`/`) the registration (DM.7).
+        // location.search forwards ?next= — see webauthn_authenticate.
+        start_register_url +
+          "/" +
+          encodeURIComponent(display_name) +
+          window.location.search,
+        {
+          method: "GET",
+          headers: {
+            "Content-Type": "application/json",
+          },
         },
-      },
-    );
+      );
+    };
+    let register_opt_response = await start();
+    // Adding a passkey to an existing account is step-up gated: confirm with a
+    // passkey already on it (step-up.js), then start again.
+    if (
+      register_opt_response.status === 403 &&
+      register_opt_response.headers.has("X-Step-Up-Required") &&
+      globalThis.stepUpReprompt
+    ) {
+      await globalThis.stepUpReprompt();
+      register_opt_response = await start();
+    }
     if (!register_opt_response.ok) {
       // Server's body carries the reason (e.g. a 409 "that name's taken").
       await throw_from_response(
@@ -329,10 +342,26 @@
       console.log("Fired Webauthn Register for node " + evt.detail.elt);
       evt.preventDefault();
 
-      const username_field = document.getElementById("username");
+      // The same ceremony adds a passkey to an EXISTING account on /account: the
+      // form overrides the endpoints + the name field via data attributes (there
+      // the "name" is the new passkey's label). Defaults are the /login signup.
+      const form = evt.detail.elt;
+      const start_url =
+        (form && form.dataset && form.dataset.webauthnStart) ||
+        "/login/start_register";
+      const finish_url =
+        (form && form.dataset && form.dataset.webauthnFinish) ||
+        "/login/finish_register";
+      const name_field_id =
+        (form && form.dataset && form.dataset.webauthnNameField) || "username";
+      const name_prompt =
+        (form && form.dataset && form.dataset.webauthnNamePrompt) ||
+        "Please enter a username.";
+
+      const username_field = document.getElementById(name_field_id);
       const username = username_field ? username_field.value.trim() : "";
       if (!username) {
-        show_error("Please enter a username.");
+        show_error(name_prompt);
         return;
       }
 
@@ -359,11 +388,7 @@
       // Clear any stale message before a fresh attempt.
       show_error("");
 
-      webauthn_register(
-        "/login/start_register",
-        "/login/finish_register",
-        username,
-      )
+      webauthn_register(start_url, finish_url, username)
         .then(function (register) {
           if (register) {
             window.location.href =
@@ -79,6 +79,15 @@
     });
   }
   if (input) input.addEventListener("change", () => upload(input.files));
+  // The visibility picker sits inside the drop-zone label — a click on it must
+  // not open the file picker.
+  const visibility = document.getElementById("media-upload-visibility-wrap");
+  if (visibility) {
+    visibility.addEventListener("click", (e) => {
+      e.preventDefault();
+      e.stopPropagation();
+    });
+  }
 
   document.querySelectorAll(".copy-ref").forEach((btn) => {
     btn.addEventListener("click", () => {
@@ -0,0 +1,114 @@
+"use strict";
+// Step-up re-authentication. A destructive action behind `require_step_up`
+// answers an htmx request that lacks a fresh passkey assertion with a 403 +
+// `HX-Trigger: {"step-up-required": {method, path}}`. htmx fires that event on
+// the requesting element; we run the passkey re-prompt (a WebAuthn get limited
+// to this user's own credentials), then replay the SAME request from the same
+// element: the verb, path (query included), parameters and target htmx used the
+// first time, recorded as it was sent. The user held the button once; they just
+// confirm with their passkey, no second hold.
+//
+// Deferred + after htmx (document order): it calls htmx.ajax.
+(function () {
+  let inFlight = false;
+  // The last request each element sent, for the replay.
+  const sent = new WeakMap();
+
+  document.body.addEventListener("htmx:beforeRequest", function (evt) {
+    const config = evt.detail.requestConfig;
+    if (!config) {
+      return;
+    }
+    const values = {};
+    for (const key of new Set(config.formData.keys())) {
+      const all = config.formData.getAll(key);
+      values[key] = all.length === 1 ? all[0] : all;
+    }
+    sent.set(evt.detail.elt, {
+      verb: config.verb,
+      path: config.path,
+      values: values,
+      target: config.target,
+    });
+  });
+
+  // Re-issue what `source` sent, falling back to the trigger's method + path
+  // (which carries the query string) if the request wasn't seen.
+  function replay(source, detail) {
+    const config = sent.get(source);
+    if (!config) {
+      htmx.ajax(detail.method, detail.path, { source: source });
+      return;
+    }
+    htmx.ajax(config.verb, config.path, {
+      source: source,
+      target: config.target,
+      values: config.values,
+    });
+  }
+
+  function toast(message) {
+    // htmx-errors.js owns the toast; hand it the message.
+    document.body.dispatchEvent(
+      new CustomEvent("app:toast", { detail: { message: message } }),
+    );
+  }
+
+  async function reprompt() {
+    if (!globalThis.PublicKeyCredential?.parseRequestOptionsFromJSON) {
+      throw new Error(
+        "This browser can't confirm with a passkey. Try a recent Safari (16.4+), Chrome, or Firefox.",
+      );
+    }
+    const opts = await fetch("/step-up/options");
+    if (!opts.ok) {
+      throw new Error(
+        (await opts.text()).trim() || "Couldn't start the passkey check.",
+      );
+    }
+    const json = await opts.json();
+    const credential = await navigator.credentials.get({
+      publicKey: PublicKeyCredential.parseRequestOptionsFromJSON(
+        json.publicKey,
+      ),
+    });
+    const finish = await fetch("/step-up/finish", {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify(credential.toJSON()),
+    });
+    if (!finish.ok) {
+      throw new Error(
+        (await finish.text()).trim() || "The passkey check didn't go through.",
+      );
+    }
+  }
+
+  // Fetch-driven flows that htmx doesn't see (the passkey-add ceremony in
+  // htmx-webauthn.js) run the same re-prompt themselves, then retry.
+  globalThis.stepUpReprompt = reprompt;
+
+  document.body.addEventListener("step-up-required", function (evt) {
+    const detail = evt.detail || {};
+    const source = evt.target;
+    if (inFlight || !detail.method || !detail.path) {
+      return;
+    }
+    inFlight = true;
+    reprompt()
+      .then(function () {
+        replay(source, detail);
+      })
+      .catch(function (err) {
+        const name = err && err.name;
+        toast(
+          name === "NotAllowedError" || name === "AbortError"
+            ? "Passkey check cancelled — the action was not performed."
+            : (err && err.message) || "The passkey check failed.",
+        );
+      })
+      .finally(function () {
+        inFlight = false;
+      });
+  });
+})();
@@ -0,0 +1,100 @@
+#!/usr/bin/env bash
+# Generate the tiny MaxMind-format fixtures for the GeoIP tests (`src/geoip.rs`,
+# `tests/greylist.rs`, `tests/web.rs`): a GeoLite2-Country-shaped and a GeoLite2-ASN-shaped
+# `.mmdb`, IPv4-only, covering three documentation ranges (RFC 5737) on documentation ASNs
+# (RFC 5398) plus one real hosting ASN from `geoip::HOSTING_ASNS`:
+#
+#   192.0.2.0/24     US  AS16509  Amazon.com, Inc.       (a built-in hosting ASN)
+#   198.51.100.0/24  DE  AS64500  Example Residential    (not hosting)
+#   203.0.113.0/24   NL  AS64496  Example Hosting B.V.   (hosting only via `hosting_asns`)
+#
+# Everything else is absent. No writer library needed: the format is a binary search tree
+# over the address bits, a 16-byte separator, a data section and a metadata map, written
+# here directly (https://maxmind.github.io/MaxMind-DB/).
+set -euo pipefail
+here="$(cd "$(dirname "$0")" && pwd)"
+repo="$(cd "$here/../.." && pwd)"
+out="$repo/tests/fixtures/geoip"
+mkdir -p "$out"
+python3 - "$out" <<'PY'
+import ipaddress, struct, sys
+
+NETS = [
+    ("192.0.2.0/24", "US", 16509, "Amazon.com, Inc."),
+    ("198.51.100.0/24", "DE", 64500, "Example Residential"),
+    ("203.0.113.0/24", "NL", 64496, "Example Hosting B.V."),
+]
+
+def ctrl(kind, size):
+    # Sizes 29..284 spill into one extra byte after the control (and extended-type) byte.
+    assert size < 285
+    low, extra = (size, b"") if size < 29 else (29, bytes([size - 29]))
+    if kind <= 7:
+        return bytes([(kind << 5) | low]) + extra
+    return bytes([low, kind - 7]) + extra
+
+def enc(v):
+    if isinstance(v, str):
+        b = v.encode()
+        return ctrl(2, len(b)) + b
+    if isinstance(v, tuple):  # (kind, int): uint16 = 5, uint32 = 6, uint64 = 9
+        kind, n = v
+        b = n.to_bytes((n.bit_length() + 7) // 8, "big")
+        return ctrl(kind, len(b)) + b
+    if isinstance(v, list):
+        return ctrl(11, len(v)) + b"".join(enc(x) for x in v)
+    if isinstance(v, dict):
+        return ctrl(7, len(v)) + b"".join(enc(k) + enc(x) for k, x in v.items())
+    raise TypeError(v)
+
+def write(path, db_type, records):
+    # records: [(network, data dict)] -> a trie of 32-bit prefixes.
+    data, offsets, trie = b"", [], [[None, None]]
+    for net, rec in records:
+        offsets.append(len(data))
+        data += enc(rec)
+        n = ipaddress.ip_network(net)
+        bits = int(n.network_address)
+        node = 0
+        for i in range(n.prefixlen):
+            bit = (bits >> (31 - i)) & 1
+            if i == n.prefixlen - 1:
+                trie[node][bit] = ("data", len(offsets) - 1)
+            else:
+                if trie[node][bit] is None:
+                    trie.append([None, None])
+                    trie[node][bit] = ("node", len(trie) - 1)
+                node = trie[node][bit][1]
+    count = len(trie)
+    tree = b""
+    for left, right in trie:
+        for rec in (left, right):
+            if rec is None:
+                val = count
+            elif rec[0] == "node":
+                val = rec[1]
+            else:
+                val = count + 16 + offsets[rec[1]]
+            tree += val.to_bytes(3, "big")
+    meta = enc({
+        "binary_format_major_version": (5, 2),
+        "binary_format_minor_version": (5, 0),
+        "build_epoch": (9, 1_700_000_000),
+        "database_type": db_type,
+        "description": {"en": "hotchkiss-io test fixture"},
+        "ip_version": (5, 4),
+        "languages": ["en"],
+        "node_count": (6, count),
+        "record_size": (5, 24),
+    })
+    with open(path, "wb") as f:
+        f.write(tree + b"\0" * 16 + data + b"\xab\xcd\xefMaxMind.com" + meta)
+    print(f"wrote {path}")
+
+out = sys.argv[1]
+write(f"{out}/country.mmdb", "GeoLite2-Country",
+      [(net, {"country": {"iso_code": cc}}) for net, cc, _, _ in NETS])
+write(f"{out}/asn.mmdb", "GeoLite2-ASN",
+      [(net, {"autonomous_system_number": (6, asn), "autonomous_system_organization": org})
+       for net, _, asn, org in NETS])
+PY
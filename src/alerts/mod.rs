@@ -0,0 +1,207 @@
+//! Alerting (`alerts` in the settings). A detached loop checks the rules every few minutes
+//! — the 5xx rate and p95 latency over the recent request log, the stored certificates'
+//! expiry, free space on the media roots and the confirmed-dead link count — and notifies
+//! the configured sinks (SMTP, an ntfy-style topic, a webhook) when one starts or stops
+//! firing.
+//!
+//! Each rule that's over its threshold opens an episode in `alerts`; while it stays over,
+//! later passes only refresh the summary, so a long outage is one notification, not one
+//! every pass. When it drops back under — or its rule is switched off, or its media root
+//! is removed from the settings — the episode resolves, and that's notified too. A
+//! notification isn't marked sent until every sink has taken it; otherwise it's retried
+//! next pass.
+
+mod rules;
+mod sinks;
+mod smtp;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::Result;
+use sqlx::SqlitePool;
+use tokio::time::{Instant, MissedTickBehavior};
+use tracing::{Instrument, error, info, info_span, warn};
+
+use crate::db::dao::alerts::AlertDao;
+use crate::media::MediaStore;
+use crate::settings::Alerts;
+use sinks::Sinks;
+
+/// Resolved, delivered episodes are kept this long, as a record of what fired.
+const RETAIN_DAYS: i64 = 90;
+
+/// What a pass changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pass {
+    pub fired: usize,
+    pub resolved: usize,
+    pub delivered: usize,
+}
+
+/// One pass: evaluate the rules, open and resolve episodes to match, then deliver whatever
+/// hasn't been.
+pub async fn run_once(
+    pool: &SqlitePool,
+    config: &Alerts,
+    media: &MediaStore,
+    sinks: &Sinks,
+) -> Result<Pass> {
+    let mut pass = Pass::default();
+    let mut firing: HashMap<String, String> = rules::evaluate(pool, config, media)
+        .await?
+        .into_iter()
+        .map(|f| (f.rule, f.summary))
+        .collect();
+
+    for open in AlertDao::open(pool).await? {
+        match firing.remove(&open.rule) {
+            Some(summary) => AlertDao::touch(pool, open.id, &summary).await?,
+            None => {
+                AlertDao::resolve(pool, open.id).await?;
+                pass.resolved += 1;
+            }
+        }
+    }
+    for (rule, summary) in &firing {
+        AlertDao::fire(pool, rule, summary).await?;
+        pass.fired += 1;
+    }
+
+    for alert in AlertDao::undelivered(pool).await? {
+        match sinks.deliver(&alert).await {
+            Ok(()) => {
+                AlertDao::mark_notified(pool, alert.id).await?;
+                pass.delivered += 1;
+            }
+            Err(e) => warn!("alerts: {} will be retried: {e:#}", alert.rule),
+        }
+    }
+    AlertDao::prune_resolved(pool, RETAIN_DAYS).await?;
+    Ok(pass)
+}
+
+/// Start the loop. A no-op without `alerts` in the settings.
+pub fn spawn(pool: SqlitePool, config: Option<Alerts>, site: String, media: MediaStore) {
+    let Some(config) = config else {
+        return;
+    };
+    let sinks = match Sinks::new(site, config.sinks.clone()) {
+        Ok(sinks) => sinks,
+        Err(e) => {
+            error!("alerts: couldn't build the sinks, not running: {e:?}");
+            return;
+        }
+    };
+    if config.sinks.is_empty() {
+        warn!("alerts: no sinks configured; episodes are recorded but nobody is told");
+    }
+    let every = Duration::from_secs(config.every_minutes.max(1) * 60);
+    tokio::spawn(async move {
+        // The first pass waits a period, so a restart doesn't judge a window it was down for.
+        let mut ticker = tokio::time::interval_at(Instant::now() + every, every);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let pass = run_once(&pool, &config, &media, &sinks)
+                .instrument(info_span!("alerts_pass"))
+                .await;
+            match pass {
+                Ok(pass) if pass != Pass::default() => info!(
+                    "alerts: {} fired, {} resolved, {} notifications delivered",
+                    pass.fired, pass.resolved, pass.delivered
+                ),
+                Ok(_) => {}
+                Err(e) => warn!("alerts: pass failed (will retry): {e:?}"),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::dao::request_log::{NewRequestLog, RequestLogDao};
+    use crate::settings::AlertSink;
+    use axum::http::StatusCode;
+
+    async fn log(pool: &SqlitePool, status: i64, n: usize) {
+        let row = NewRequestLog {
+            method: "GET".into(),
+            path: "/".into(),
+            status,
+            ip: Some("203.0.113.7".into()),
+            user_agent: None,
+            referer: None,
+            duration_ms: 12,
+            is_bot: false,
+            challenged: false,
+            rate_limited: false,
+            visitor: None,
+            campaign: None,
+        };
+        RequestLogDao::insert_batch(pool, &vec![row; n])
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn an_error_spike_notifies_once_then_resolves(pool: SqlitePool) -> Result<()> {
+        let (url, inbox) = sinks::stand_in::spawn(StatusCode::OK).await;
+        let sinks = Sinks::new("hotchkiss.io".into(), vec![AlertSink::Webhook { url }])?;
+        let media = MediaStore::new(Vec::new(), 0);
+        let config = Alerts::default();
+
+        log(&pool, 200, 15).await;
+        log(&pool, 500, 5).await;
+        let first = run_once(&pool, &config, &media, &sinks).await?;
+        assert_eq!(
+            first,
+            Pass {
+                fired: 1,
+                resolved: 0,
+                delivered: 1
+            }
+        );
+        let second = run_once(&pool, &config, &media, &sinks).await?;
+        assert_eq!(second, Pass::default(), "still firing: no new notification");
+
+        log(&pool, 200, 200).await;
+        let third = run_once(&pool, &config, &media, &sinks).await?;
+        assert_eq!((third.resolved, third.delivered), (1, 1));
+
+        let received = inbox.lock().unwrap().clone();
+        let statuses: Vec<String> = received
+            .iter()
+            .map(|r| {
+                let v: serde_json::Value = serde_json::from_str(&r.body).unwrap();
+                format!(
+                    "{} {}",
+                    v["status"].as_str().unwrap(),
+                    v["rule"].as_str().unwrap()
+                )
+            })
+            .collect();
+        assert_eq!(statuses, ["firing error_rate", "resolved error_rate"]);
+        Ok(())
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn a_refused_notification_is_retried(pool: SqlitePool) -> Result<()> {
+        let (url, inbox) = sinks::stand_in::spawn(StatusCode::SERVICE_UNAVAILABLE).await;
+        let sinks = Sinks::new("hotchkiss.io".into(), vec![AlertSink::Webhook { url }])?;
+        let media = MediaStore::new(Vec::new(), 0);
+        let config = Alerts::default();
+
+        log(&pool, 500, 20).await;
+        assert_eq!(run_once(&pool, &config, &media, &sinks).await?.delivered, 0);
+        assert_eq!(run_once(&pool, &config, &media, &sinks).await?.delivered, 0);
+        assert_eq!(
+            inbox.lock().unwrap().len(),
+            2,
+            "tried again on the second pass"
+        );
+        assert_eq!(AlertDao::open(&pool).await?.len(), 1, "still one episode");
+        Ok(())
+    }
+}
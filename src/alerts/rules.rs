@@ -0,0 +1,243 @@
+//! The alert rules. Each reads its numbers fresh and yields a [`Firing`] while over its
+//! threshold; a rule that's off, or has too little traffic to judge, yields nothing, which
+//! is what resolves an open alert.
+
+use anyhow::Result;
+use sqlx::SqlitePool;
+use sqlx::types::chrono::{DateTime, Utc};
+
+use crate::coordinator::acme::certificate_loader::earliest_expiry;
+use crate::db::dao::certificate::CertificateDao;
+use crate::db::dao::request_log::{Audience, RequestLogDao, StatusBucketCounts, Window};
+use crate::deadlinks::LinkCheckDao;
+use crate::media::{MediaStore, RootStatus};
+use crate::settings::Alerts;
+use crate::web::util::route::percentile_sorted;
+
+const GIB: f64 = (1u64 << 30) as f64;
+
+/// A rule over its threshold this pass.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Firing {
+    /// The alert key: the rule, plus its subject for a per-subject rule (`cert:<domain>`).
+    pub rule: String,
+    pub summary: String,
+}
+
+/// Run every enabled rule.
+pub async fn evaluate(
+    pool: &SqlitePool,
+    config: &Alerts,
+    media: &MediaStore,
+) -> Result<Vec<Firing>> {
+    let mut firing = Vec::new();
+    let window = Window::last_minutes(config.window_minutes);
+
+    if let Some(threshold) = config.error_rate {
+        let counts = RequestLogDao::count_by_status_bucket(pool, &window, Audience::All).await?;
+        firing.extend(error_rate(&counts, threshold, config));
+    }
+    if let Some(limit_ms) = config.p95_ms {
+        let mut ms: Vec<i64> = RequestLogDao::latency_samples(pool, &window)
+            .await?
+            .into_iter()
+            .map(|s| s.duration_ms)
+            .collect();
+        ms.sort_unstable();
+        firing.extend(p95_latency(&ms, limit_ms, config));
+    }
+    if let Some(days) = config.cert_expiry_days {
+        let now = Utc::now();
+        for (domain, chain) in CertificateDao::chains(pool).await? {
+            match earliest_expiry(&chain) {
+                Ok(Some(not_after)) => firing.extend(cert_expiry(&domain, not_after, now, days)),
+                Ok(None) => {}
+                Err(e) => firing.push(Firing {
+                    rule: format!("cert:{domain}"),
+                    summary: format!("the stored certificate for {domain} can't be read: {e}"),
+                }),
+            }
+        }
+    }
+    if let Some(min_free) = config.media_free_bytes {
+        firing.extend(
+            media
+                .roots_status()
+                .iter()
+                .filter_map(|root| media_root(root, min_free)),
+        );
+    }
+    if let Some(threshold) = config.dead_links {
+        let problems = LinkCheckDao::problem_rows(pool).await?;
+        let dead = problems.iter().filter(|r| r.is_confirmed_dead()).count();
+        firing.extend(dead_links(dead, threshold));
+    }
+    Ok(firing)
+}
+
+fn error_rate(counts: &StatusBucketCounts, threshold: f64, config: &Alerts) -> Option<Firing> {
+    let total = counts.s2xx
+        + counts.s3xx
+        + counts.s403
+        + counts.s404
+        + counts.s429
+        + counts.s4xx
+        + counts.s5xx;
+    if total == 0 || total < config.min_requests {
+        return None;
+    }
+    let rate = counts.s5xx as f64 / total as f64;
+    (rate >= threshold).then(|| Firing {
+        rule: "error_rate".into(),
+        summary: format!(
+            "5xx rate {:.1}% over the last {} min ({} of {total} requests)",
+            rate * 100.0,
+            config.window_minutes,
+            counts.s5xx,
+        ),
+    })
+}
+
+/// `sorted_ms` ascending.
+fn p95_latency(sorted_ms: &[i64], limit_ms: i64, config: &Alerts) -> Option<Firing> {
+    let n = sorted_ms.len();
+    if n == 0 || (n as i64) < config.min_requests {
+        return None;
+    }
+    let p95 = percentile_sorted(sorted_ms, 95.0);
+    (p95 > limit_ms).then(|| Firing {
+        rule: "p95_latency".into(),
+        summary: format!(
+            "p95 latency {p95} ms over the last {} min (limit {limit_ms} ms, {n} requests)",
+            config.window_minutes,
+        ),
+    })
+}
+
+/// `not_after` as a Unix timestamp.
+fn cert_expiry(domain: &str, not_after: i64, now: DateTime<Utc>, days: i64) -> Option<Firing> {
+    let left = not_after - now.timestamp();
+    if left >= days * 86_400 {
+        return None;
+    }
+    let date = DateTime::<Utc>::from_timestamp(not_after, 0)
+        .map(|d| d.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| not_after.to_string());
+    let summary = if left <= 0 {
+        format!("the certificate for {domain} expired {date}")
+    } else {
+        let days_left = left / 86_400;
+        format!("the certificate for {domain} expires in {days_left} day(s), {date}")
+    };
+    Some(Firing {
+        rule: format!("cert:{domain}"),
+        summary,
+    })
+}
+
+fn media_root(root: &RootStatus, min_free: u64) -> Option<Firing> {
+    let path = root.path.display();
+    let summary = match root.free_bytes {
+        None => format!("media root {path} can't be read (unmounted?)"),
+        Some(free) if free < min_free => format!(
+            "media root {path} has {:.1} GiB free (alert below {:.1} GiB)",
+            free as f64 / GIB,
+            min_free as f64 / GIB,
+        ),
+        Some(_) => return None,
+    };
+    Some(Firing {
+        rule: format!("media_free:{path}"),
+        summary,
+    })
+}
+
+fn dead_links(dead: usize, threshold: i64) -> Option<Firing> {
+    (dead as i64 >= threshold.max(1)).then(|| Firing {
+        rule: "dead_links".into(),
+        summary: format!("{dead} link(s) confirmed dead, see /admin/dead-links"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn request_rules_need_enough_traffic() {
+        let config = Alerts::default();
+        let counts = StatusBucketCounts {
+            s2xx: 18,
+            s5xx: 2,
+            ..Default::default()
+        };
+        let firing = error_rate(&counts, 0.05, &config).expect("10% ≥ 5%");
+        assert_eq!(firing.rule, "error_rate");
+        assert_eq!(
+            firing.summary,
+            "5xx rate 10.0% over the last 15 min (2 of 20 requests)"
+        );
+        assert_eq!(error_rate(&counts, 0.2, &config), None);
+        let few = StatusBucketCounts {
+            s5xx: 5,
+            ..Default::default()
+        };
+        assert_eq!(
+            error_rate(&few, 0.05, &config),
+            None,
+            "5 requests is too few"
+        );
+
+        let mut ms: Vec<i64> = (1..=20).map(|i| i * 100).collect();
+        ms.sort_unstable();
+        assert!(p95_latency(&ms, 1_500, &config).is_some(), "p95 = 1900 ms");
+        assert_eq!(p95_latency(&ms, 2_000, &config), None);
+        assert_eq!(
+            p95_latency(&ms[..10], 100, &config),
+            None,
+            "too few samples"
+        );
+    }
+
+    #[test]
+    fn cert_rule_counts_days_left() {
+        let now = DateTime::<Utc>::from_timestamp(1_893_456_000, 0).unwrap(); // 2030-01-01
+        let in_days = |d: i64| now.timestamp() + d * 86_400;
+        assert_eq!(cert_expiry("hotchkiss.io", in_days(30), now, 14), None);
+        let soon = cert_expiry("hotchkiss.io", in_days(10), now, 14).expect("10 < 14");
+        assert_eq!(soon.rule, "cert:hotchkiss.io");
+        assert_eq!(
+            soon.summary,
+            "the certificate for hotchkiss.io expires in 10 day(s), 2030-01-11 00:00 UTC"
+        );
+        let gone = cert_expiry("hotchkiss.io", in_days(-1), now, 14).unwrap();
+        assert!(
+            gone.summary.contains("expired 2029-12-31"),
+            "{}",
+            gone.summary
+        );
+    }
+
+    #[test]
+    fn media_rule_flags_low_and_unreadable_roots() {
+        let root = |free: Option<u64>| RootStatus {
+            path: PathBuf::from("/Volumes/media"),
+            free_bytes: free,
+            total_bytes: free.map(|_| 1 << 40),
+            is_write_target: false,
+            below_margin: false,
+        };
+        assert_eq!(media_root(&root(Some(50 << 30)), 20 << 30), None);
+        let low = media_root(&root(Some(5 << 30)), 20 << 30).unwrap();
+        assert_eq!(low.rule, "media_free:/Volumes/media");
+        assert_eq!(
+            low.summary,
+            "media root /Volumes/media has 5.0 GiB free (alert below 20.0 GiB)"
+        );
+        assert!(media_root(&root(None), 20 << 30).is_some(), "unmounted");
+
+        assert_eq!(dead_links(0, 1), None);
+        assert!(dead_links(3, 1).is_some());
+    }
+}
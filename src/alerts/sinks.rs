@@ -0,0 +1,281 @@
+//! Delivering an alert's latest change (it fired, or it resolved) to the configured sinks.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use tracing::warn;
+
+use super::smtp;
+use crate::db::dao::alerts::Alert;
+use crate::settings::AlertSink;
+use crate::telemetry::TraceContext;
+
+/// The configured sinks and the one HTTP client they share.
+pub struct Sinks {
+    client: reqwest::Client,
+    /// Names the site in every message, so alerts from prod and beta can share a topic.
+    site: String,
+    sinks: Vec<AlertSink>,
+}
+
+/// The webhook sink's JSON body.
+#[derive(Debug, Serialize)]
+struct Payload<'a> {
+    site: &'a str,
+    /// `firing` or `resolved`.
+    status: &'a str,
+    rule: &'a str,
+    summary: &'a str,
+    fired_at: String,
+    resolved_at: Option<String>,
+}
+
+impl Sinks {
+    pub fn new(site: String, sinks: Vec<AlertSink>) -> Result<Self> {
+        let client = reqwest::ClientBuilder::new()
+            .use_rustls_tls()
+            .user_agent(format!("hotchkiss.io-alerts/{}", env!("CARGO_PKG_VERSION")))
+            .connect_timeout(Duration::from_secs(10))
+            .timeout(Duration::from_secs(15))
+            .build()?;
+        Ok(Self {
+            client,
+            site,
+            sinks,
+        })
+    }
+
+    /// Send `alert` to every sink. One refusing doesn't stop the others; the error (and a
+    /// retry of the lot next pass) comes after all have been tried.
+    pub async fn deliver(&self, alert: &Alert) -> Result<()> {
+        let mut failed = 0;
+        for sink in &self.sinks {
+            if let Err(e) = self.send(sink, alert).await {
+                warn!(
+                    "alerts: {} not delivered to {}: {e:?}",
+                    alert.rule,
+                    describe(sink)
+                );
+                failed += 1;
+            }
+        }
+        if failed > 0 {
+            bail!("{failed} of {} sinks failed", self.sinks.len());
+        }
+        Ok(())
+    }
+
+    async fn send(&self, sink: &AlertSink, alert: &Alert) -> Result<()> {
+        let status = status(alert);
+        match sink {
+            AlertSink::Smtp {
+                host,
+                port,
+                from,
+                to,
+            } => {
+                let subject = self.title(alert);
+                let body = body(alert);
+                smtp::send(host, *port, &self.site, from, to, &subject, &body).await
+            }
+            AlertSink::Ntfy { url, token } => {
+                let (priority, tags) = match status {
+                    "firing" => ("high", "warning"),
+                    _ => ("default", "white_check_mark"),
+                };
+                let mut req = self
+                    .client
+                    .post(url)
+                    .header("Title", self.title(alert))
+                    .header("Priority", priority)
+                    .header("Tags", tags)
+                    .body(body(alert));
+                if let Some(token) = token {
+                    req = req.bearer_auth(token);
+                }
+                traced(req).send().await?.error_for_status()?;
+                Ok(())
+            }
+            AlertSink::Webhook { url } => {
+                let payload = Payload {
+                    site: &self.site,
+                    status,
+                    rule: &alert.rule,
+                    summary: &alert.summary,
+                    fired_at: alert.fired_at.to_rfc3339(),
+                    resolved_at: alert.resolved_at.map(|t| t.to_rfc3339()),
+                };
+                traced(self.client.post(url).json(&payload))
+                    .send()
+                    .await
+                    .with_context(|| format!("POST {url}"))?
+                    .error_for_status()?;
+                Ok(())
+            }
+        }
+    }
+
+    /// `[hotchkiss.io] FIRING: 5xx rate 9.0% over the last 15 min (…)`.
+    fn title(&self, alert: &Alert) -> String {
+        let status = status(alert).to_uppercase();
+        format!("[{}] {status}: {}", self.site, alert.summary)
+    }
+}
+
+fn status(alert: &Alert) -> &'static str {
+    if alert.resolved_at.is_some() {
+        "resolved"
+    } else {
+        "firing"
+    }
+}
+
+fn body(alert: &Alert) -> String {
+    let mut body = format!(
+        "{}\n\nrule: {}\nfired: {}\n",
+        alert.summary,
+        alert.rule,
+        alert.fired_at.format("%Y-%m-%d %H:%M:%S UTC"),
+    );
+    if let Some(resolved) = alert.resolved_at {
+        body += &format!(
+            "last over: {}\nresolved: {}\n",
+            alert.last_seen_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            resolved.format("%Y-%m-%d %H:%M:%S UTC"),
+        );
+    }
+    body
+}
+
+/// Carry the pass's trace onward, so a receiver that's also traced (a relay on the same
+/// collector, say) joins it.
+fn traced(req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match TraceContext::current() {
+        Some(context) => req.header("traceparent", context.traceparent()),
+        None => req,
+    }
+}
+
+/// A sink for the logs — never its token.
+fn describe(sink: &AlertSink) -> String {
+    match sink {
+        AlertSink::Smtp { host, port, .. } => format!("smtp {host}:{port}"),
+        AlertSink::Ntfy { url, .. } => format!("ntfy {url}"),
+        AlertSink::Webhook { url } => format!("webhook {url}"),
+    }
+}
+
+/// A local stand-in for an HTTP sink: records every request it's sent and answers with a
+/// fixed status.
+#[cfg(test)]
+pub(super) mod stand_in {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{Router, body::Bytes, extract::State, http::HeaderMap, http::StatusCode};
+
+    #[derive(Clone, Debug)]
+    pub struct Received {
+        pub headers: HeaderMap,
+        pub body: String,
+    }
+
+    pub type Inbox = Arc<Mutex<Vec<Received>>>;
+
+    /// Serve on an ephemeral port; returns its URL and what it's received.
+    pub async fn spawn(status: StatusCode) -> (String, Inbox) {
+        let inbox = Inbox::default();
+        let app = Router::new()
+            .fallback(
+                |State((inbox, status)): State<(Inbox, StatusCode)>,
+                 headers: HeaderMap,
+                 body: Bytes| async move {
+                    let body = String::from_utf8_lossy(&body).into_owned();
+                    inbox.lock().unwrap().push(Received { headers, body });
+                    status
+                },
+            )
+            .with_state((inbox.clone(), status));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+        (url, inbox)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use sqlx::types::chrono::{Duration, TimeZone, Utc};
+
+    fn alert(resolved: bool) -> Alert {
+        let at = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        Alert {
+            id: 1,
+            rule: "error_rate".into(),
+            summary: "5xx rate 9.0% over the last 15 min (9 of 100 requests)".into(),
+            fired_at: at,
+            last_seen_at: at,
+            resolved_at: resolved.then(|| at + Duration::minutes(10)),
+        }
+    }
+
+    #[tokio::test]
+    async fn http_sinks_post_the_alert() -> Result<()> {
+        let (ntfy_url, ntfy) = stand_in::spawn(StatusCode::OK).await;
+        let (hook_url, hook) = stand_in::spawn(StatusCode::NO_CONTENT).await;
+        let sinks = Sinks::new(
+            "hotchkiss.io".into(),
+            vec![
+                AlertSink::Ntfy {
+                    url: ntfy_url,
+                    token: Some("tk_topic".into()),
+                },
+                AlertSink::Webhook { url: hook_url },
+            ],
+        )?;
+        sinks.deliver(&alert(false)).await?;
+        sinks.deliver(&alert(true)).await?;
+
+        let ntfy = ntfy.lock().unwrap().clone();
+        assert_eq!(ntfy.len(), 2);
+        let header = |i: usize, name: &str| ntfy[i].headers[name].to_str().unwrap().to_string();
+        assert_eq!(
+            header(0, "title"),
+            "[hotchkiss.io] FIRING: 5xx rate 9.0% over the last 15 min (9 of 100 requests)"
+        );
+        assert_eq!(header(0, "priority"), "high");
+        assert_eq!(header(0, "authorization"), "Bearer tk_topic");
+        assert!(header(1, "title").starts_with("[hotchkiss.io] RESOLVED: "));
+        assert!(ntfy[1].body.contains("resolved: 2026-03-01 12:10:00 UTC"));
+
+        let hook = hook.lock().unwrap().clone();
+        let fired: serde_json::Value = serde_json::from_str(&hook[0].body)?;
+        assert_eq!(fired["status"], "firing");
+        assert_eq!(fired["rule"], "error_rate");
+        assert_eq!(fired["fired_at"], "2026-03-01T12:00:00+00:00");
+        assert!(fired["resolved_at"].is_null());
+        let resolved: serde_json::Value = serde_json::from_str(&hook[1].body)?;
+        assert_eq!(resolved["status"], "resolved");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn one_refusing_sink_fails_the_delivery_but_not_the_others() -> Result<()> {
+        let (down_url, _) = stand_in::spawn(StatusCode::BAD_GATEWAY).await;
+        let (up_url, up) = stand_in::spawn(StatusCode::OK).await;
+        let sinks = Sinks::new(
+            "hotchkiss.io".into(),
+            vec![
+                AlertSink::Webhook { url: down_url },
+                AlertSink::Webhook { url: up_url },
+            ],
+        )?;
+        assert!(sinks.deliver(&alert(false)).await.is_err());
+        assert_eq!(up.lock().unwrap().len(), 1, "the second sink still got it");
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+//! Just enough SMTP to hand a plain-text message to a relay on the host or the LAN: one
+//! connection per message, no STARTTLS, no AUTH — which is why the settings refuse a relay
+//! anywhere else. Anything fancier belongs in the relay.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use sqlx::types::chrono::Utc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+
+/// Bounds the whole conversation, so a relay that stops answering can't stall a pass.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Send one message through the relay at `host:port`, greeting it as `helo` (the site's
+/// domain).
+pub async fn send(
+    host: &str,
+    port: u16,
+    helo: &str,
+    from: &str,
+    to: &[String],
+    subject: &str,
+    body: &str,
+) -> Result<()> {
+    if to.is_empty() {
+        bail!("no recipients");
+    }
+    let conversation = conversation(host, port, helo, from, to, subject, body);
+    tokio::time::timeout(TIMEOUT, conversation)
+        .await
+        .with_context(|| format!("smtp {host}:{port} timed out"))?
+}
+
+async fn conversation(
+    host: &str,
+    port: u16,
+    helo: &str,
+    from: &str,
+    to: &[String],
+    subject: &str,
+    body: &str,
+) -> Result<()> {
+    let stream = TcpStream::connect((host, port))
+        .await
+        .with_context(|| format!("connecting to {host}:{port}"))?;
+    let (read, mut write) = stream.into_split();
+    let mut read = BufReader::new(read);
+
+    expect(&mut read, 220).await?;
+    command(&mut write, &mut read, &format!("EHLO {}", clean(helo)), 250).await?;
+    command(
+        &mut write,
+        &mut read,
+        &format!("MAIL FROM:<{}>", clean(from)),
+        250,
+    )
+    .await?;
+    for rcpt in to {
+        command(
+            &mut write,
+            &mut read,
+            &format!("RCPT TO:<{}>", clean(rcpt)),
+            250,
+        )
+        .await?;
+    }
+    command(&mut write, &mut read, "DATA", 354).await?;
+    write
+        .write_all(message(from, to, subject, body).as_bytes())
+        .await?;
+    expect(&mut read, 250).await?;
+    // The relay has the message; a QUIT it fumbles doesn't unsend it.
+    let _ = command(&mut write, &mut read, "QUIT", 221).await;
+    Ok(())
+}
+
+async fn command(
+    write: &mut OwnedWriteHalf,
+    read: &mut BufReader<OwnedReadHalf>,
+    line: &str,
+    code: u16,
+) -> Result<()> {
+    write.write_all(format!("{line}\r\n").as_bytes()).await?;
+    expect(read, code)
+        .await
+        .with_context(|| format!("after {}", line.split(':').next().unwrap_or(line)))
+}
+
+/// Read one (possibly multiline) reply and check its code.
+async fn expect(read: &mut BufReader<OwnedReadHalf>, code: u16) -> Result<()> {
+    let mut reply = String::new();
+    loop {
+        let mut line = String::new();
+        if read.read_line(&mut line).await? == 0 {
+            bail!("connection closed mid-reply");
+        }
+        reply.push_str(&line);
+        // `250-…` continues the reply, `250 …` (or a bare `250`) ends it.
+        if line.as_bytes().get(3) != Some(&b'-') {
+            break;
+        }
+    }
+    let got: u16 = reply
+        .get(..3)
+        .and_then(|c| c.parse().ok())
+        .with_context(|| format!("malformed reply {:?}", reply.trim_end()))?;
+    if got != code {
+        bail!("expected {code}, got {:?}", reply.trim_end());
+    }
+    Ok(())
+}
+
+/// Headers and body, dot-stuffed, CRLF line endings, ending with the lone `.`.
+fn message(from: &str, to: &[String], subject: &str, body: &str) -> String {
+    let to: Vec<String> = to.iter().map(|t| clean(t)).collect();
+    let mut out = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\n\
+         MIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n",
+        clean(from),
+        to.join(", "),
+        clean(subject),
+        Utc::now().to_rfc2822(),
+    );
+    for line in body.lines() {
+        if line.starts_with('.') {
+            out.push('.');
+        }
+        out.push_str(line);
+        out.push_str("\r\n");
+    }
+    out.push_str(".\r\n");
+    out
+}
+
+/// A header value or address on one line: no CR/LF to smuggle in headers or commands.
+fn clean(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// A stand-in relay: plays the server side of one conversation, refusing the recipients
+    /// listed in `refuse`, and returns everything the client sent.
+    async fn relay(refuse: &'static [&'static str]) -> (u16, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (read, mut write) = stream.into_split();
+            let mut read = BufReader::new(read);
+            let mut seen = String::new();
+            write.write_all(b"220 relay ready\r\n").await.unwrap();
+            let mut in_data = false;
+            loop {
+                let mut line = String::new();
+                if read.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+                seen.push_str(&line);
+                let reply: &[u8] = if in_data {
+                    if line != ".\r\n" {
+                        continue;
+                    }
+                    in_data = false;
+                    b"250 queued\r\n"
+                } else if line.starts_with("EHLO") {
+                    b"250-relay\r\n250-8BITMIME\r\n250 SIZE 1000000\r\n"
+                } else if line.starts_with("RCPT") && refuse.iter().any(|r| line.contains(r)) {
+                    b"550 no such user\r\n"
+                } else if line.starts_with("DATA") {
+                    in_data = true;
+                    b"354 go ahead\r\n"
+                } else if line.starts_with("QUIT") {
+                    write.write_all(b"221 bye\r\n").await.unwrap();
+                    break;
+                } else {
+                    b"250 ok\r\n"
+                };
+                write.write_all(reply).await.unwrap();
+            }
+            seen
+        });
+        (port, handle)
+    }
+
+    #[tokio::test]
+    async fn hands_the_message_to_the_relay() -> Result<()> {
+        let (port, relay) = relay(&[]).await;
+        let to = vec![
+            "me@example.com".to_string(),
+            "pager@example.com".to_string(),
+        ];
+        send(
+            "127.0.0.1",
+            port,
+            "hotchkiss.io",
+            "site@example.com",
+            &to,
+            "[hotchkiss.io] FIRING: dead links\r\nBcc: evil@example.com",
+            "2 link(s) confirmed dead\n.hidden line\n",
+        )
+        .await?;
+
+        let seen = relay.await?;
+        assert!(seen.starts_with("EHLO hotchkiss.io\r\nMAIL FROM:<site@example.com>\r\n"));
+        assert!(seen.contains("RCPT TO:<me@example.com>\r\nRCPT TO:<pager@example.com>\r\n"));
+        assert!(seen.contains("To: me@example.com, pager@example.com\r\n"));
+        assert!(
+            seen.contains("Subject: [hotchkiss.io] FIRING: dead links  Bcc: evil@example.com\r\n"),
+            "a CRLF in the subject can't start a header:\n{seen}"
+        );
+        assert!(seen.contains("\r\n\r\n2 link(s) confirmed dead\r\n..hidden line\r\n.\r\n"));
+        assert!(seen.ends_with("QUIT\r\n"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn a_refused_recipient_fails_the_send() {
+        let (port, _relay) = relay(&["nobody@"]).await;
+        let to = vec!["nobody@example.com".to_string()];
+        let err = send("127.0.0.1", port, "do", "site@example.com", &to, "s", "b")
+            .await
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("550"), "{err:#}");
+    }
+}
@@ -0,0 +1,35 @@
+//! Offline account recovery for the operator: mint one recovery code straight
+//! against the site's SQLite file, for when the only admin has lost every passkey
+//! (so `/account` — where codes are normally generated — is out of reach). The
+//! code is redeemed on `/login/recover` like any other, and the mint lands in the
+//! audit log on the `cli` channel.
+//!
+//! Run with: `cargo run --bin recovery_code -- <path/to/database.sqlite> [username]`.
+//! Without a username it picks the site's admin (and refuses if there's more
+//! than one). Anyone who can run this can already read the database file.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use hotchkiss_io::mint_offline_recovery_code;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let db_path = args
+        .next()
+        .map(PathBuf::from)
+        .context("usage: recovery_code <path/to/database.sqlite> [username]")?;
+    let display_name = args.next();
+
+    let (name, code) = mint_offline_recovery_code(&db_path, display_name.as_deref()).await?;
+    println!();
+    println!("Recovery code for {name:?}:");
+    println!();
+    println!("    {code}");
+    println!();
+    println!("Use it once at /login/recover to add a new passkey, then generate a");
+    println!("fresh set of codes on /account.");
+    println!();
+    Ok(())
+}
@@ -0,0 +1,28 @@
+//! Fit the greylist scoring model offline and store it as a new version. The model is
+//! trained on the site's own history — `request_log` labelled by the rules, solved tolls
+//! and manual pins — and the running server's detection sweep then SHADOWS it next to the
+//! rules, listing disagreements on `/admin/greylist`. It never greylists anyone itself.
+//!
+//! Run with: `cargo run --release --bin train_greylist_model -- <path/to/database.sqlite> [days]`.
+//! `days` defaults to the whole retained log (90).
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use hotchkiss_io::train_greylist_model;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let db_path = args
+        .next()
+        .map(PathBuf::from)
+        .context("usage: train_greylist_model <path/to/database.sqlite> [days]")?;
+    let days = args
+        .next()
+        .map(|d| d.parse::<i64>().context("days must be a whole number"))
+        .transpose()?;
+
+    println!("{}", train_greylist_model(&db_path, days).await?);
+    Ok(())
+}
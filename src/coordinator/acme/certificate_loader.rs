@@ -47,3 +47,41 @@ impl CertificateLoader {
         }
     }
 }
+
+/// The earliest `notAfter` in a PEM chain, as a Unix timestamp — when the chain stops being
+/// servable. `None` for a chain with no certificates in it.
+pub fn earliest_expiry(certificate_chain: &str) -> Result<Option<i64>> {
+    let mut earliest: Option<i64> = None;
+    for cert in CertificateDer::pem_slice_iter(certificate_chain.as_bytes()) {
+        let cert = cert?;
+        let (_, x509) = parse_x509_certificate(&cert)?;
+        let not_after = x509.validity.not_after.timestamp();
+        earliest = Some(earliest.map_or(not_after, |e| e.min(not_after)));
+    }
+    Ok(earliest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{CertificateParams, KeyPair, date_time_ymd};
+
+    fn pem_expiring(year: i32, month: u8, day: u8) -> String {
+        let mut params = CertificateParams::new(vec!["hotchkiss.io".to_string()]).unwrap();
+        params.not_after = date_time_ymd(year, month, day);
+        let key = KeyPair::generate().unwrap();
+        params.self_signed(&key).unwrap().pem()
+    }
+
+    #[test]
+    fn earliest_expiry_is_the_chains_first_to_lapse() -> Result<()> {
+        assert_eq!(earliest_expiry("")?, None);
+        let chain = pem_expiring(2031, 6, 1) + &pem_expiring(2030, 1, 2);
+        assert_eq!(
+            earliest_expiry(&chain)?,
+            Some(1_893_542_400),
+            "2030-01-02T00:00Z"
+        );
+        Ok(())
+    }
+}
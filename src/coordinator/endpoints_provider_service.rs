@@ -1,9 +1,15 @@
 use crate::coordinator::backup;
-use crate::db::dao::request_log::RequestLogDao;
+use crate::db::dao::request_log::{RAW_RETAIN_DAYS, RequestLogDao};
+use crate::db::dao::request_log_rollup::{ROLLUP_RETAIN_DAYS, RequestLogRollupDao};
+use crate::geoip::GeoDb;
+use crate::metrics::Metrics;
 use crate::greylist::active_set::GreylistSet;
 use crate::media::MediaStore;
 use crate::settings::Settings;
-use crate::web::{app_state::AppState, router::create_router};
+use crate::web::{
+    app_state::AppState, rate_limit::RateLimiter, request_log_writer::RequestLogWriter,
+    router::create_router, trap::Traps,
+};
 use anyhow::{Context, Result, bail};
 use axum::{
     BoxError,
@@ -44,6 +50,12 @@ pub struct EndpointsProviderService {
     greylist: GreylistSet,
     resolver: hickory_resolver::TokioAsyncResolver,
     dead_links: crate::deadlinks::DeadLinkScanState,
+    csp_report_only: bool,
+    rate_limiter: RateLimiter,
+    request_log: RequestLogWriter,
+    traps: Traps,
+    geoip: GeoDb,
+    metrics: Metrics,
 }
 
 impl EndpointsProviderService {
@@ -53,6 +65,9 @@ impl EndpointsProviderService {
         greylist: GreylistSet,
         resolver: hickory_resolver::TokioAsyncResolver,
         dead_links: crate::deadlinks::DeadLinkScanState,
+        request_log: RequestLogWriter,
+        geoip: GeoDb,
+        metrics: Metrics,
     ) -> Result<Self> {
         let session_store = SqliteStore::new(pool.clone());
         session_store.migrate().await?;
@@ -90,6 +105,12 @@ impl EndpointsProviderService {
             greylist,
             resolver,
             dead_links,
+            csp_report_only: settings.csp_report_only,
+            rate_limiter: RateLimiter::new(settings.rate_limits.clone()),
+            request_log,
+            traps: Traps::new(settings.tarpit, &settings.honeypot_paths),
+            geoip,
+            metrics,
         })
     }
 
@@ -117,6 +138,12 @@ impl EndpointsProviderService {
             greylist: self.greylist.clone(),
             resolver: self.resolver.clone(),
             dead_links: self.dead_links.clone(),
+            csp_report_only: self.csp_report_only,
+            rate_limiter: self.rate_limiter.clone(),
+            request_log: self.request_log.clone(),
+            traps: self.traps.clone(),
+            geoip: self.geoip.clone(),
+            metrics: self.metrics.clone(),
         };
 
         let http_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), self.http_port);
@@ -162,6 +189,7 @@ impl EndpointsProviderService {
         // (and take the live HTTP servers down) over housekeeping. Mirrors the
         // prune + backup loops below.
         let session_store = self.session_store.clone();
+        let session_index_pool = self.pool.clone();
         set.spawn(async move {
             let mut tick = tokio::time::interval(tokio::time::Duration::from_secs(60 * 60));
             loop {
@@ -169,22 +197,55 @@ impl EndpointsProviderService {
                 if let Err(e) = session_store.delete_expired().await {
                     tracing::warn!("session GC failed: {e}");
                 }
+                // The per-user session index follows its sessions out.
+                match crate::db::dao::user_sessions::UserSessionDao::prune_stale(&session_index_pool)
+                    .await
+                {
+                    Ok(n) if n > 0 => tracing::info!("Pruned {n} stale user_sessions rows"),
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("user_sessions prune failed: {e}"),
+                }
             }
         });
 
-        // Prune request_log rows older than the retention window, daily.
+        // Tiered request_log retention, daily: roll completed days up, then prune raw rows
+        // past the raw window — only after a successful rollup, so no day is lost to both
+        // tiers — and rollups past theirs.
         let prune_pool = self.pool.clone();
         set.spawn(async move {
-            const RETAIN_DAYS: i64 = 90;
             let mut tick = tokio::time::interval(tokio::time::Duration::from_secs(60 * 60 * 24));
             loop {
                 tick.tick().await;
-                match RequestLogDao::prune_before(&prune_pool, RETAIN_DAYS).await {
-                    Ok(n) if n > 0 => {
-                        tracing::info!("Pruned {n} request_log rows older than {RETAIN_DAYS} days")
+                match RequestLogRollupDao::roll_up(&prune_pool).await {
+                    Ok(days) => {
+                        if days > 0 {
+                            tracing::info!("Rolled up {days} day(s) of request_log");
+                        }
+                        match RequestLogDao::prune_before(&prune_pool, RAW_RETAIN_DAYS).await {
+                            Ok(n) if n > 0 => tracing::info!(
+                                "Pruned {n} request_log rows older than {RAW_RETAIN_DAYS} days"
+                            ),
+                            Ok(_) => {}
+                            Err(e) => tracing::warn!("request_log prune failed: {e}"),
+                        }
                     }
+                    Err(e) => tracing::warn!("request_log rollup failed (raw rows kept): {e}"),
+                }
+                match RequestLogRollupDao::prune_before(&prune_pool, ROLLUP_RETAIN_DAYS).await {
+                    Ok(n) if n > 0 => tracing::info!("Pruned {n} day(s) of request_log rollups"),
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("request_log rollup prune failed: {e}"),
+                }
+                // The per-key usage trail rides the raw window.
+                match crate::db::dao::api_keys::ApiKeyDao::prune_usage_before(
+                    &prune_pool,
+                    RAW_RETAIN_DAYS,
+                )
+                .await
+                {
+                    Ok(n) if n > 0 => tracing::info!("Pruned {n} api_key_usage rows"),
                     Ok(_) => {}
-                    Err(e) => tracing::warn!("request_log prune failed: {e}"),
+                    Err(e) => tracing::warn!("api_key_usage prune failed: {e}"),
                 }
             }
         });
@@ -205,6 +266,22 @@ impl EndpointsProviderService {
             }
         });
 
+        // Auto-revoke API keys past their expiry, hourly. `authenticate` already
+        // refuses them at the instant they lapse; this stamps `revoked_at` so the
+        // admin page shows them revoked and the weekly purge above collects them.
+        let key_expiry_pool = self.pool.clone();
+        set.spawn(async move {
+            let mut tick = tokio::time::interval(tokio::time::Duration::from_secs(60 * 60));
+            loop {
+                tick.tick().await;
+                match crate::db::dao::api_keys::ApiKeyDao::revoke_expired(&key_expiry_pool).await {
+                    Ok(n) if n > 0 => tracing::info!("Auto-revoked {n} expired API key(s)"),
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("expired-api-key revoke failed: {e}"),
+                }
+            }
+        });
+
         // Take a dated VACUUM INTO snapshot of the DB daily (first tick fires
         // immediately at startup), then prune to a rolling window. CRITICAL:
         // this loop never returns and every fallible step is matched + logged,
@@ -232,7 +309,7 @@ impl EndpointsProviderService {
         });
 
         // None of these tasks should ever return under normal operation: the two
-        // servers serve forever and the four housekeeping loops are infinite. So
+        // servers serve forever and the housekeeping loops are infinite. So
         // the FIRST task to complete — for ANY reason — is fatal: bail so start()
         // returns Err, the coordinator's try_join! trips, and launchd restarts us.
         // (join_all() would instead block forever on the never-returning
@@ -1,4 +1,4 @@
-mod acme;
+pub mod acme;
 mod acme_provider_service;
 mod backfill_is_bot;
 mod backfill_responsive_images;
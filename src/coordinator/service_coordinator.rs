@@ -5,6 +5,7 @@ use super::dns::cloudflare_client::CloudflareClient;
 use super::dns::dns_validator::DnsValidator;
 use super::dns_provider_service::DnsProviderService;
 use super::endpoints_provider_service::EndpointsProviderService;
+use crate::media::MediaStore;
 use crate::settings::Settings;
 use crate::{
     coordinator::ip_provider_service::IpProviderService, db::database_handle::DatabaseHandle,
@@ -15,6 +16,9 @@ use tokio::sync::broadcast;
 use tokio::task::JoinHandle;
 use tracing::{error, info};
 
+/// How long shutdown waits for the request_log writer to drain.
+const SHUTDOWN_FLUSH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
 /// The goal of the coordinator is to start up the various dependancies of the server AND
 /// be able to reconfigure it automatically at runtime.
 pub struct ServiceCoordinator {
@@ -25,6 +29,8 @@ pub struct ServiceCoordinator {
     /// The shared greylist snapshot (Phase DU) — carried here so `start()` can keep its
     /// operator-allowlist synced to the server's own public IP off the IP broadcast.
     greylist_set: crate::greylist::active_set::GreylistSet,
+    /// The batched request-log writer — carried here so `start()` can flush it on shutdown.
+    request_log: crate::web::request_log_writer::RequestLogWriter,
 }
 
 impl ServiceCoordinator {
@@ -48,13 +54,27 @@ impl ServiceCoordinator {
         let greylist_set = crate::greylist::active_set::GreylistSet::new();
         // Phase DL: the shared dead-link scanner handle, threaded into BOTH the daily
         // scan loop and AppState (the "Run scan now" button + status), same pattern.
-        let dead_links = crate::deadlinks::DeadLinkScanState::new();
+        let dead_links =
+            crate::deadlinks::DeadLinkScanState::new(settings.dead_link_check_external_anchors);
+        // The GeoIP / ASN databases (if configured): the analytics and greylist pages enrich
+        // with them, the sweep's R5 reads hosting ASNs from them.
+        let geoip = crate::geoip::GeoDb::open(settings.geoip.as_ref());
+        // The batched request_log writer: the logging middleware enqueues, one task commits.
+        let request_log = crate::web::request_log_writer::RequestLogWriter::spawn(
+            pool.clone(),
+            Default::default(),
+        );
+        // The /metrics counters: fed by the request middleware and the sweep loop.
+        let metrics = crate::metrics::Metrics::new(settings.metrics_token.clone());
         let endpoints_provider_service = EndpointsProviderService::create(
             settings.clone(),
             pool.clone(),
             greylist_set.clone(),
             resolver.clone(),
             dead_links.clone(),
+            request_log.clone(),
+            geoip.clone(),
+            metrics.clone(),
         )
         .await?;
 
@@ -71,8 +91,27 @@ impl ServiceCoordinator {
         // Phase CX: the behavioral greylist detection sweep. Detached interval loop (NOT in
         // the try_join!) — a failed pass logs and retries, never takes the app down. Reuses
         // the ACME resolver for FCrDNS crawler verification, and refreshes the shared snapshot
-        // the enforcement middleware reads.
-        crate::greylist::sweep::spawn(pool.clone(), resolver.clone(), greylist_set.clone());
+        // the enforcement middleware reads (and, if configured, exports it for the firewall).
+        crate::greylist::sweep::spawn(
+            pool.clone(),
+            resolver.clone(),
+            greylist_set.clone(),
+            geoip,
+            settings.firewall_export.clone(),
+            metrics,
+        );
+
+        // Operator reputation lists feed the same greylist: a detached loop reimports a list
+        // file when it changes (no-op with none configured).
+        crate::greylist::reputation::spawn(
+            pool.clone(),
+            greylist_set.clone(),
+            settings.reputation_lists.clone(),
+        );
+
+        // Privacy window: an hourly detached loop keys legacy rows and, with a policy set,
+        // truncates or hashes logged IPs (and reduces user agents) once they age out.
+        crate::privacy::spawn(pool.clone(), settings.privacy.clone());
 
         // Phase DL: the daily dead-link scan. Detached interval loop (NOT in the
         // try_join!) — a failed pass logs and retries next tick, never takes the app
@@ -82,12 +121,22 @@ impl ServiceCoordinator {
         // same-site absolute link folds to internal on beta as well as prod.
         crate::deadlinks::spawn(pool.clone(), settings.webauthn_rp_id.clone(), dead_links);
 
+        // Alerting: a detached loop checks the error rate, latency, certificate expiry, media
+        // space and dead links every few minutes and notifies the sinks (no-op unconfigured).
+        crate::alerts::spawn(
+            pool.clone(),
+            settings.alerts.clone(),
+            settings.domain.clone(),
+            MediaStore::new(settings.media_paths.clone(), settings.media_min_free_bytes),
+        );
+
         Ok(Self {
             ip_provider_service,
             dns_provider_service,
             acme_provider_service,
             endpoints_provider_service,
             greylist_set,
+            request_log,
         })
     }
 
@@ -139,17 +188,62 @@ impl ServiceCoordinator {
             }
         }
 
-        match tokio::try_join!(
-            flatten(ips_handle),
-            flatten(dps_handle),
-            flatten(aps_handle),
-            flatten(eps_handle)
-        ) {
-            Ok(_) => unreachable!("This should only appear in the case of failure"),
+        // SIGTERM (launchd's stop) or ctrl-c ends the run cleanly: flush the queued
+        // request_log rows — bounded, so a wedged database can't hold the process up — and
+        // return, letting the caller exit. Only a failed service is an error.
+        let services = async {
+            tokio::try_join!(
+                flatten(ips_handle),
+                flatten(dps_handle),
+                flatten(aps_handle),
+                flatten(eps_handle)
+            )
+        };
+        tokio::select! {
+            result = services => match result {
+                Ok(_) => unreachable!("This should only appear in the case of failure"),
+                Err(e) => {
+                    error!("A service failed {}", e);
+                    bail!("A service failed {}", e);
+                }
+            },
+            () = shutdown_signal() => {
+                info!("Shutting down: flushing the request_log writer");
+                let flush = self.request_log.shutdown();
+                if tokio::time::timeout(SHUTDOWN_FLUSH_TIMEOUT, flush).await.is_err() {
+                    error!("request_log flush timed out; exiting anyway");
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Resolve on ctrl-c, or on SIGTERM where there is one.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("couldn't listen for ctrl-c: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
             Err(e) => {
-                error!("A service failed {}", e);
-                bail!("A service failed {}", e);
+                error!("couldn't listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
             }
         }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
     }
 }
@@ -0,0 +1,150 @@
+//! Alert episodes (`alerts`): the rules engine's memory of what's firing (see `alerts`), so
+//! a rule that stays over its threshold notifies once when it starts and once when it
+//! clears, and a notification a sink refused is retried.
+
+use anyhow::Result;
+use sqlx::SqlitePool;
+use sqlx::types::chrono::{DateTime, Utc};
+
+#[derive(Clone, Debug)]
+pub struct Alert {
+    pub id: i64,
+    /// The rule's key: `error_rate`, or `media_free:<root>` for a per-subject rule.
+    pub rule: String,
+    /// The latest pass's description of it.
+    pub summary: String,
+    pub fired_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+pub struct AlertDao;
+
+impl AlertDao {
+    /// The episodes still firing, oldest first.
+    pub async fn open(pool: &SqlitePool) -> Result<Vec<Alert>> {
+        Ok(sqlx::query_as!(
+            Alert,
+            r#"SELECT id as "id!", rule, summary,
+                      fired_at as "fired_at: DateTime<Utc>",
+                      last_seen_at as "last_seen_at: DateTime<Utc>",
+                      resolved_at as "resolved_at: DateTime<Utc>"
+               FROM alerts WHERE resolved_at IS NULL ORDER BY id"#,
+        )
+        .fetch_all(pool)
+        .await?)
+    }
+
+    /// Open an episode for `rule`. Errors if one is already open (the partial unique index).
+    pub async fn fire(pool: &SqlitePool, rule: &str, summary: &str) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO alerts (rule, summary) VALUES (?1, ?2)",
+            rule,
+            summary,
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// A pass saw an open episode still firing: refresh its summary, don't re-notify.
+    pub async fn touch(pool: &SqlitePool, id: i64, summary: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE alerts SET summary = ?2, last_seen_at = CURRENT_TIMESTAMP WHERE id = ?1",
+            id,
+            summary,
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Close an episode; the close is a change of its own to notify.
+    pub async fn resolve(pool: &SqlitePool, id: i64) -> Result<()> {
+        sqlx::query!(
+            "UPDATE alerts SET resolved_at = CURRENT_TIMESTAMP, notified = 0 WHERE id = ?1",
+            id,
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Episodes whose latest change hasn't been delivered yet, oldest first.
+    pub async fn undelivered(pool: &SqlitePool) -> Result<Vec<Alert>> {
+        Ok(sqlx::query_as!(
+            Alert,
+            r#"SELECT id as "id!", rule, summary,
+                      fired_at as "fired_at: DateTime<Utc>",
+                      last_seen_at as "last_seen_at: DateTime<Utc>",
+                      resolved_at as "resolved_at: DateTime<Utc>"
+               FROM alerts WHERE notified = 0 ORDER BY id"#,
+        )
+        .fetch_all(pool)
+        .await?)
+    }
+
+    pub async fn mark_notified(pool: &SqlitePool, id: i64) -> Result<()> {
+        sqlx::query!("UPDATE alerts SET notified = 1 WHERE id = ?1", id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Drop episodes that closed more than `retain_days` ago and have been delivered.
+    pub async fn prune_resolved(pool: &SqlitePool, retain_days: i64) -> Result<u64> {
+        let modifier = format!("-{retain_days} days");
+        Ok(sqlx::query!(
+            "DELETE FROM alerts
+             WHERE resolved_at < datetime('now', ?1) AND notified = 1",
+            modifier,
+        )
+        .execute(pool)
+        .await?
+        .rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn an_episode_opens_once_and_closes(pool: SqlitePool) -> Result<()> {
+        AlertDao::fire(&pool, "error_rate", "5xx rate 9%").await?;
+        assert!(
+            AlertDao::fire(&pool, "error_rate", "again").await.is_err(),
+            "one open episode per rule"
+        );
+        let open = AlertDao::open(&pool).await?;
+        assert_eq!(open.len(), 1);
+        let id = open[0].id;
+        assert!(open[0].resolved_at.is_none());
+        assert_eq!(AlertDao::undelivered(&pool).await?.len(), 1);
+
+        AlertDao::mark_notified(&pool, id).await?;
+        AlertDao::touch(&pool, id, "5xx rate 12%").await?;
+        assert!(
+            AlertDao::undelivered(&pool).await?.is_empty(),
+            "a touch doesn't re-notify"
+        );
+        assert_eq!(AlertDao::open(&pool).await?[0].summary, "5xx rate 12%");
+
+        AlertDao::resolve(&pool, id).await?;
+        assert!(AlertDao::open(&pool).await?.is_empty());
+        let pending = AlertDao::undelivered(&pool).await?;
+        assert_eq!(pending.len(), 1);
+        assert!(
+            pending[0].resolved_at.is_some(),
+            "the close is pending delivery"
+        );
+
+        AlertDao::fire(&pool, "error_rate", "5xx rate 7%").await?;
+        assert_eq!(
+            AlertDao::open(&pool).await?.len(),
+            1,
+            "a new episode after the close"
+        );
+        Ok(())
+    }
+}
@@ -17,6 +17,21 @@ pub struct ApiKeyDao {
     pub created_at: DateTime<Utc>,
     pub last_used_at: Option<DateTime<Utc>>,
     pub revoked_at: Option<DateTime<Utc>>,
+    /// `None` = lives until revoked. Past this instant the key no longer
+    /// authenticates; the hourly sweep then stamps `revoked_at`.
+    pub expires_at: Option<DateTime<Utc>>,
+    /// The key this one replaced via [`ApiKeyDao::rotate`], if any.
+    pub rotated_from: Option<i64>,
+}
+
+/// One authenticated request made with a key — the per-key audit trail.
+pub struct ApiKeyUsage {
+    pub key_id: i64,
+    pub ts: DateTime<Utc>,
+    pub method: String,
+    pub path: String,
+    pub status: i64,
+    pub ip: Option<String>,
 }
 
 impl ApiKeyDao {
@@ -27,18 +42,29 @@ impl ApiKeyDao {
         pool: &SqlitePool,
         user_id: &Uuid,
         label: &str,
+    ) -> Result<(String, ApiKeyDao)> {
+        Self::create_with_expiry(pool, user_id, label, None).await
+    }
+
+    /// [`Self::create`] with an optional hard expiry. `None` = lives until revoked.
+    pub async fn create_with_expiry(
+        pool: &SqlitePool,
+        user_id: &Uuid,
+        label: &str,
+        expires_at: Option<DateTime<Utc>>,
     ) -> Result<(String, ApiKeyDao)> {
         let key = generate_key()?;
         let key_hash = hash_key(pool, &key).await?;
         let user_id = user_id.to_string();
         let created_at = Utc::now();
         let row = sqlx::query!(
-            r#"INSERT INTO api_keys (user_id, key_hash, label, created_at)
-               VALUES (?1, ?2, ?3, ?4) RETURNING id as "id!""#,
+            r#"INSERT INTO api_keys (user_id, key_hash, label, created_at, expires_at)
+               VALUES (?1, ?2, ?3, ?4, ?5) RETURNING id as "id!""#,
             user_id,
             key_hash,
             label,
             created_at,
+            expires_at,
         )
         .fetch_one(pool)
         .await?;
@@ -50,19 +76,107 @@ impl ApiKeyDao {
                 created_at,
                 last_used_at: None,
                 revoked_at: None,
+                expires_at,
+                rotated_from: None,
             },
         ))
     }
 
-    /// Resolve a presented key → `(user_id, key_id)` for a LIVE (non-revoked) key,
-    /// or `None`. The lookup is by the full HMAC hash, so an attacker can't probe
-    /// without the pepper.
+    /// Rotate a live key: mint a successor (same label, same lifetime length if the
+    /// old key had one) and cut the old key's expiry down to `now + overlap_hours`
+    /// (never EXTENDED — an old key already expiring sooner keeps its date), so
+    /// whatever uses it has the overlap to switch. Scoped to `user_id` like
+    /// [`Self::revoke`]; `None` if the key isn't the user's or isn't live. Returns
+    /// the successor's PLAINTEXT, shown once.
+    pub async fn rotate(
+        pool: &SqlitePool,
+        key_id: i64,
+        user_id: &Uuid,
+        overlap_hours: i64,
+    ) -> Result<Option<(String, ApiKeyDao)>> {
+        let uid = user_id.to_string();
+        let now = Utc::now();
+        let key = generate_key()?;
+        let key_hash = hash_key(pool, &key).await?;
+
+        let mut tx = pool.begin().await?;
+        let Some(old) = sqlx::query!(
+            r#"SELECT label, created_at as "created_at!: DateTime<Utc>",
+                      expires_at as "expires_at?: DateTime<Utc>"
+               FROM api_keys
+               WHERE id = ?1 AND user_id = ?2 AND revoked_at IS NULL
+                 AND (expires_at IS NULL OR expires_at > ?3)"#,
+            key_id,
+            uid,
+            now,
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        // Timestamp math, not chrono::Duration (see purge_revoked).
+        let successor_expiry = old.expires_at.and_then(|exp| {
+            let lifetime = exp.timestamp() - old.created_at.timestamp();
+            DateTime::<Utc>::from_timestamp(now.timestamp() + lifetime.max(0), 0)
+        });
+        let overlap_end =
+            DateTime::<Utc>::from_timestamp(now.timestamp() + overlap_hours.max(0) * 3_600, 0)
+                .unwrap_or(now);
+        let old_expiry = match old.expires_at {
+            Some(exp) if exp < overlap_end => exp,
+            _ => overlap_end,
+        };
+
+        let row = sqlx::query!(
+            r#"INSERT INTO api_keys (user_id, key_hash, label, created_at, expires_at, rotated_from)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6) RETURNING id as "id!""#,
+            uid,
+            key_hash,
+            old.label,
+            now,
+            successor_expiry,
+            key_id,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"UPDATE api_keys SET expires_at = ?1 WHERE id = ?2"#,
+            old_expiry,
+            key_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(Some((
+            key,
+            ApiKeyDao {
+                id: row.id,
+                label: old.label,
+                created_at: now,
+                last_used_at: None,
+                revoked_at: None,
+                expires_at: successor_expiry,
+                rotated_from: Some(key_id),
+            },
+        )))
+    }
+
+    /// Resolve a presented key → `(user_id, key_id)` for a LIVE (non-revoked,
+    /// unexpired) key, or `None`. The lookup is by the full HMAC hash, so an
+    /// attacker can't probe without the pepper. Expiry is checked here, not left to
+    /// the hourly sweep, so a key dies at its instant rather than up to an hour late.
     pub async fn authenticate(pool: &SqlitePool, presented_key: &str) -> Result<Option<(Uuid, i64)>> {
         let key_hash = hash_key(pool, presented_key).await?;
+        let now = Utc::now();
         let row = sqlx::query!(
             r#"SELECT id as "id!", user_id FROM api_keys
-               WHERE key_hash = ?1 AND revoked_at IS NULL"#,
+               WHERE key_hash = ?1 AND revoked_at IS NULL
+                 AND (expires_at IS NULL OR expires_at > ?2)"#,
             key_hash,
+            now,
         )
         .fetch_optional(pool)
         .await?;
@@ -97,7 +211,9 @@ impl ApiKeyDao {
             r#"SELECT id as "id!", label,
                       created_at as "created_at!: DateTime<Utc>",
                       last_used_at as "last_used_at?: DateTime<Utc>",
-                      revoked_at as "revoked_at?: DateTime<Utc>"
+                      revoked_at as "revoked_at?: DateTime<Utc>",
+                      expires_at as "expires_at?: DateTime<Utc>",
+                      rotated_from as "rotated_from?: i64"
                FROM api_keys WHERE user_id = ?1 ORDER BY created_at DESC"#,
             uid,
         )
@@ -123,6 +239,88 @@ impl ApiKeyDao {
         Ok(res.rows_affected() > 0)
     }
 
+    /// Auto-revoke every key whose expiry has passed (the hourly coordinator sweep).
+    /// `authenticate` already refuses them; this stamps `revoked_at` so they read as
+    /// revoked in the UI and age out via [`Self::purge_revoked`]. Returns the count.
+    pub async fn revoke_expired(pool: &SqlitePool) -> Result<u64> {
+        let now = Utc::now();
+        let res = sqlx::query!(
+            r#"UPDATE api_keys SET revoked_at = expires_at
+               WHERE revoked_at IS NULL AND expires_at IS NOT NULL AND expires_at <= ?1"#,
+            now,
+        )
+        .execute(pool)
+        .await?;
+        Ok(res.rows_affected())
+    }
+
+    /// Append one authenticated request to the key's usage trail (best-effort — the
+    /// middleware spawns this and logs a failure, never failing the request).
+    pub async fn record_usage(
+        pool: &SqlitePool,
+        key_id: i64,
+        method: &str,
+        path: &str,
+        status: i64,
+        ip: Option<&str>,
+    ) -> Result<()> {
+        let now = Utc::now();
+        sqlx::query!(
+            r#"INSERT INTO api_key_usage (key_id, ts, method, path, status, ip)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+            key_id,
+            now,
+            method,
+            path,
+            status,
+            ip,
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// The newest `limit` usage rows for each of a user's keys, newest first — for
+    /// the per-key trail on `/admin/api-keys`.
+    pub async fn recent_usage_for_user(
+        pool: &SqlitePool,
+        user_id: &Uuid,
+        limit: i64,
+    ) -> Result<Vec<ApiKeyUsage>> {
+        let uid = user_id.to_string();
+        let rows = sqlx::query_as!(
+            ApiKeyUsage,
+            r#"SELECT key_id as "key_id!", ts as "ts!: DateTime<Utc>", method as "method!",
+                      path as "path!", status as "status!", ip as "ip?"
+               FROM (
+                   SELECT u.key_id, u.ts, u.method, u.path, u.status, u.ip,
+                          ROW_NUMBER() OVER (PARTITION BY u.key_id ORDER BY u.ts DESC, u.id DESC) AS rn
+                   FROM api_key_usage u
+                   JOIN api_keys k ON k.id = u.key_id
+                   WHERE k.user_id = ?1
+               )
+               WHERE rn <= ?2
+               ORDER BY key_id, ts DESC"#,
+            uid,
+            limit,
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Delete usage rows older than `retain_days` — rides the request_log
+    /// retention window so the trail never outlives the access log.
+    pub async fn prune_usage_before(pool: &SqlitePool, retain_days: i64) -> Result<u64> {
+        let now = Utc::now();
+        let cutoff_secs = now.timestamp() - retain_days.max(0) * 86_400;
+        let cutoff = DateTime::<Utc>::from_timestamp(cutoff_secs, 0).unwrap_or(now);
+        let res = sqlx::query!(r#"DELETE FROM api_key_usage WHERE ts < ?1"#, cutoff)
+            .execute(pool)
+            .await?;
+        Ok(res.rows_affected())
+    }
+
     /// Delete keys revoked more than [`REVOKED_RETENTION_DAYS`] ago (EC). A
     /// revoked row is an unreusable HMAC hash + metadata — a week covers the
     /// "which key did I just kill" audit window, past that it's clutter. Runs
@@ -144,25 +342,35 @@ impl ApiKeyDao {
     }
 }
 
+/// Default overlap a rotated key keeps authenticating alongside its successor.
+pub const ROTATION_OVERLAP_HOURS: i64 = 24;
+
 /// How long a revoked key stays visible in `/admin/api-keys` before the daily
 /// purge deletes it.
 pub const REVOKED_RETENTION_DAYS: i64 = 7;
 
 /// `hio_<43-char base64url>` from 32 cryptographically-random bytes (openssl).
 fn generate_key() -> Result<String> {
+    generate_secret("hio_")
+}
+
+/// `<prefix><43-char base64url>` from 32 cryptographically-random bytes — the
+/// shape of every bearer secret the server mints (API keys, invite tokens).
+pub(crate) fn generate_secret(prefix: &str) -> Result<String> {
     use base64::Engine;
     let mut raw = [0u8; 32];
-    openssl::rand::rand_bytes(&mut raw).context("generating api-key bytes")?;
+    openssl::rand::rand_bytes(&mut raw).context("generating secret bytes")?;
     Ok(format!(
-        "hio_{}",
+        "{prefix}{}",
         base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(raw)
     ))
 }
 
 /// HMAC-SHA256(server pepper, key) → lowercase hex. The pepper is `crypto_keys`
 /// id 3 (a server secret), so a leak of `key_hash` alone can't be brute-forced or
-/// verified offline. Mirrors `media::media_url_key`'s openssl HMAC pattern.
-async fn hash_key(pool: &SqlitePool, key: &str) -> Result<String> {
+/// verified offline. Mirrors `media::media_url_key`'s openssl HMAC pattern. Also
+/// hashes the other stored bearer secrets (invite tokens).
+pub(crate) async fn hash_key(pool: &SqlitePool, key: &str) -> Result<String> {
     use openssl::hash::MessageDigest;
     use openssl::pkey::PKey;
     use openssl::sign::Signer;
@@ -265,6 +473,77 @@ mod tests {
         Ok(())
     }
 
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn expired_keys_stop_authenticating_and_sweep_revokes(pool: SqlitePool) -> Result<()> {
+        let user = seed_admin(&pool).await?;
+        let past = DateTime::<Utc>::from_timestamp(Utc::now().timestamp() - 60, 0).unwrap();
+        let future = DateTime::<Utc>::from_timestamp(Utc::now().timestamp() + 3_600, 0).unwrap();
+        let (dead, dead_row) =
+            ApiKeyDao::create_with_expiry(&pool, &user.id, "lapsed", Some(past)).await?;
+        let (live, _) =
+            ApiKeyDao::create_with_expiry(&pool, &user.id, "hour-left", Some(future)).await?;
+
+        // Expiry bites at authenticate, before any sweep runs.
+        assert!(ApiKeyDao::authenticate(&pool, &dead).await?.is_none());
+        assert!(ApiKeyDao::authenticate(&pool, &live).await?.is_some());
+
+        // The sweep revokes only the lapsed one, and is idempotent.
+        assert_eq!(ApiKeyDao::revoke_expired(&pool).await?, 1);
+        assert_eq!(ApiKeyDao::revoke_expired(&pool).await?, 0);
+        let list = ApiKeyDao::list_for_user(&pool, &user.id).await?;
+        let lapsed = list.iter().find(|k| k.id == dead_row.id).unwrap();
+        assert!(lapsed.revoked_at.is_some(), "the lapsed key reads as revoked");
+        Ok(())
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn rotate_issues_successor_and_bounds_the_old_key(pool: SqlitePool) -> Result<()> {
+        let user = seed_admin(&pool).await?;
+        let (old_key, old) = ApiKeyDao::create(&pool, &user.id, "ci").await?;
+
+        let (new_key, new) = ApiKeyDao::rotate(&pool, old.id, &user.id, 24)
+            .await?
+            .expect("a live key rotates");
+        assert_ne!(new_key, old_key);
+        assert_eq!(new.label, "ci");
+        assert_eq!(new.rotated_from, Some(old.id));
+        assert!(new.expires_at.is_none(), "a never-expiring key's successor never expires");
+
+        // Both authenticate during the overlap; the old one now has an expiry.
+        assert!(ApiKeyDao::authenticate(&pool, &old_key).await?.is_some());
+        assert!(ApiKeyDao::authenticate(&pool, &new_key).await?.is_some());
+        let list = ApiKeyDao::list_for_user(&pool, &user.id).await?;
+        let old_now = list.iter().find(|k| k.id == old.id).unwrap();
+        assert!(old_now.expires_at.is_some(), "rotation bounds the old key");
+
+        // A zero overlap closes the old key at once; a revoked key can't rotate.
+        let (_, newer) = ApiKeyDao::rotate(&pool, new.id, &user.id, 0).await?.unwrap();
+        assert!(ApiKeyDao::authenticate(&pool, &new_key).await?.is_none());
+        assert!(ApiKeyDao::revoke(&pool, newer.id, &user.id).await?);
+        assert!(ApiKeyDao::rotate(&pool, newer.id, &user.id, 24).await?.is_none());
+        Ok(())
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn usage_trail_records_caps_and_prunes(pool: SqlitePool) -> Result<()> {
+        let user = seed_admin(&pool).await?;
+        let (_, row) = ApiKeyDao::create(&pool, &user.id, "ci").await?;
+        for i in 0..5 {
+            ApiKeyDao::record_usage(&pool, row.id, "GET", &format!("/p/{i}"), 200, Some("10.0.0.1"))
+                .await?;
+        }
+        let trail = ApiKeyDao::recent_usage_for_user(&pool, &user.id, 3).await?;
+        assert_eq!(trail.len(), 3, "capped per key");
+        assert_eq!(trail[0].path, "/p/4", "newest first");
+
+        // Backdate everything past the window → pruned.
+        sqlx::query!("UPDATE api_key_usage SET ts = '2000-01-01T00:00:00+00:00'")
+            .execute(&pool)
+            .await?;
+        assert_eq!(ApiKeyDao::prune_usage_before(&pool, 90).await?, 5);
+        Ok(())
+    }
+
     #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
     async fn distinct_keys_get_distinct_hashes(pool: SqlitePool) -> Result<()> {
         let user = seed_admin(&pool).await?;
@@ -0,0 +1,166 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use sqlx::types::chrono::{DateTime, Utc};
+
+/// Most rows one `/admin/audit` page (or one MCP `list_audit_log` call) returns.
+pub const MAX_AUDIT_ROWS: i64 = 500;
+
+/// One row to append — built by `web::audit::record`, which knows the actor.
+pub struct NewAuditEntry<'a> {
+    pub actor_id: Option<String>,
+    pub actor_name: Option<String>,
+    /// `session` / `api_key` / `mcp`.
+    pub channel: &'a str,
+    pub api_key_id: Option<i64>,
+    /// Dotted verb, e.g. `page.update`, `user.role`, `greylist.pin`.
+    pub action: &'a str,
+    /// What was acted on — a page path, media ref, user id, key id, IP.
+    pub target: &'a str,
+    /// JSON summaries of the entity before / after (either side may be absent: a
+    /// create has no before, a delete no after).
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// A stored audit row, newest-first in listings.
+pub struct AuditLogDao {
+    pub id: i64,
+    pub ts: DateTime<Utc>,
+    pub actor_id: Option<String>,
+    pub actor_name: Option<String>,
+    pub channel: String,
+    pub api_key_id: Option<i64>,
+    pub action: String,
+    pub target: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// Listing filters; each `None` matches everything.
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    /// Prefix match on `action` — `page.` for every page write, `user.role` for one verb.
+    pub action: Option<String>,
+    /// Exact `actor_name`.
+    pub actor: Option<String>,
+    /// Exact `channel`.
+    pub channel: Option<String>,
+    /// Substring match on `target`.
+    pub target: Option<String>,
+}
+
+impl AuditLogDao {
+    pub async fn record(pool: &SqlitePool, e: &NewAuditEntry<'_>) -> Result<()> {
+        let now = Utc::now();
+        sqlx::query!(
+            r#"INSERT INTO audit_log
+                   (ts, actor_id, actor_name, channel, api_key_id, action, target, before, after)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"#,
+            now,
+            e.actor_id,
+            e.actor_name,
+            e.channel,
+            e.api_key_id,
+            e.action,
+            e.target,
+            e.before,
+            e.after,
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// The newest rows matching `filter`, capped at `limit` (itself capped at
+    /// [`MAX_AUDIT_ROWS`]).
+    pub async fn list(
+        pool: &SqlitePool,
+        filter: &AuditFilter,
+        limit: i64,
+    ) -> Result<Vec<AuditLogDao>> {
+        let limit = limit.clamp(1, MAX_AUDIT_ROWS);
+        Ok(sqlx::query_as!(
+            AuditLogDao,
+            r#"SELECT id as "id!", ts as "ts!: DateTime<Utc>", actor_id, actor_name, channel,
+                      api_key_id, action, target, before, after
+               FROM audit_log
+               WHERE (?1 IS NULL OR action LIKE ?1 || '%')
+                 AND (?2 IS NULL OR actor_name = ?2)
+                 AND (?3 IS NULL OR channel = ?3)
+                 AND (?4 IS NULL OR instr(target, ?4) > 0)
+               ORDER BY id DESC
+               LIMIT ?5"#,
+            filter.action,
+            filter.actor,
+            filter.channel,
+            filter.target,
+            limit,
+        )
+        .fetch_all(pool)
+        .await?)
+    }
+
+    /// Distinct actor names, for the page's filter dropdown.
+    pub async fn actors(pool: &SqlitePool) -> Result<Vec<String>> {
+        Ok(sqlx::query_scalar!(
+            r#"SELECT DISTINCT actor_name as "actor_name!" FROM audit_log
+               WHERE actor_name IS NOT NULL ORDER BY actor_name"#
+        )
+        .fetch_all(pool)
+        .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry<'a>(
+        actor: &str,
+        channel: &'a str,
+        action: &'a str,
+        target: &'a str,
+    ) -> NewAuditEntry<'a> {
+        NewAuditEntry {
+            actor_id: None,
+            actor_name: Some(actor.to_string()),
+            channel,
+            api_key_id: (channel != "session").then_some(7),
+            action,
+            target,
+            before: None,
+            after: Some(r#"{"title":"x"}"#.to_string()),
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn record_and_filter(pool: SqlitePool) -> Result<()> {
+        AuditLogDao::record(&pool, &entry("chris", "session", "page.create", "blog/a")).await?;
+        AuditLogDao::record(&pool, &entry("chris", "mcp", "page.update", "blog/a")).await?;
+        AuditLogDao::record(&pool, &entry("bot", "api_key", "media.delete", "abc")).await?;
+
+        let all = AuditLogDao::list(&pool, &AuditFilter::default(), 100).await?;
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].action, "media.delete", "newest first");
+        assert_eq!(all[0].api_key_id, Some(7));
+
+        let pages = AuditFilter { action: Some("page.".into()), ..Default::default() };
+        assert_eq!(AuditLogDao::list(&pool, &pages, 100).await?.len(), 2);
+
+        let mcp = AuditFilter { channel: Some("mcp".into()), ..Default::default() };
+        let rows = AuditLogDao::list(&pool, &mcp, 100).await?;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].action, "page.update");
+
+        let by_target = AuditFilter {
+            target: Some("blog".into()),
+            actor: Some("chris".into()),
+            ..Default::default()
+        };
+        assert_eq!(AuditLogDao::list(&pool, &by_target, 100).await?.len(), 2);
+        assert_eq!(AuditLogDao::list(&pool, &AuditFilter::default(), 1).await?.len(), 1);
+
+        assert_eq!(AuditLogDao::actors(&pool).await?, vec!["bot", "chris"]);
+        Ok(())
+    }
+}
@@ -57,6 +57,20 @@ impl CertificateDao {
 
         Ok(iad)
     }
+
+    /// Every stored (domain, certificate chain), by domain — the keys stay put. For the
+    /// `/metrics` expiry gauge.
+    pub async fn chains(pool: &SqlitePool) -> Result<Vec<(String, String)>> {
+        let rows = query!(
+            r#"SELECT domain as "domain!", certificate_chain FROM certificates ORDER BY domain"#
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| (r.domain, r.certificate_chain))
+            .collect())
+    }
 }
 
 impl FromRow<'_, SqliteRow> for CertificateDao {
@@ -115,6 +129,10 @@ mod tests {
                 .await?
                 .is_none()
         );
+        assert_eq!(
+            CertificateDao::chains(&pool).await?,
+            [("hotchkiss.io".to_string(), "CHAIN-2".to_string())]
+        );
 
         Ok(())
     }
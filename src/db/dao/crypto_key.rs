@@ -64,6 +64,16 @@ impl CryptoKey {
     }
 
     pub async fn get_or_create(pool: &SqlitePool, id: i64) -> Result<CryptoKey> {
+        Self::get_or_create_with(pool, id, || Ok(Key::generate().master().to_vec())).await
+    }
+
+    /// [`Self::get_or_create`] for a key that isn't a `cookie::Key` (e.g. the OIDC
+    /// signing key's PKCS#8 DER): `generate` mints the bytes on first use.
+    pub async fn get_or_create_with(
+        pool: &SqlitePool,
+        id: i64,
+        generate: impl FnOnce() -> Result<Vec<u8>>,
+    ) -> Result<CryptoKey> {
         match Self::find_by_id(pool, id).await? {
             Some(s) => {
                 debug!("Found key");
@@ -74,7 +84,7 @@ impl CryptoKey {
 
                 let key = CryptoKey {
                     id,
-                    key_value: Key::generate().master().to_vec(),
+                    key_value: generate()?,
                 };
                 key.create(pool).await?;
                 Ok(key)
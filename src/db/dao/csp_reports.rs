@@ -0,0 +1,132 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use sqlx::types::chrono::{DateTime, Utc};
+
+/// Rows kept at most; each insert prunes the oldest beyond this.
+pub const MAX_STORED_REPORTS: i64 = 5000;
+
+/// Most groups the admin summary lists.
+const MAX_SUMMARY_GROUPS: i64 = 200;
+
+/// One violation to store, already scrubbed (query-less URLs, truncated fields)
+/// by the report endpoint.
+#[derive(Debug, Default)]
+pub struct NewCspReport {
+    /// `enforce` or `report`.
+    pub disposition: String,
+    pub document_uri: String,
+    /// The directive that fired, e.g. `script-src-elem`.
+    pub effective_directive: String,
+    /// What was blocked: a URL, or a keyword like `inline` / `eval`.
+    pub blocked_uri: String,
+    pub source_file: Option<String>,
+    pub line_number: Option<i64>,
+    pub sample: Option<String>,
+}
+
+/// Reports grouped by what was blocked under which directive — what the admin
+/// page shows, busiest first.
+pub struct CspReportGroup {
+    pub effective_directive: String,
+    pub blocked_uri: String,
+    pub count: i64,
+    pub last_seen: DateTime<Utc>,
+    /// From the group's newest report.
+    pub document_uri: String,
+    pub disposition: String,
+    pub sample: Option<String>,
+}
+
+pub struct CspReportDao;
+
+impl CspReportDao {
+    pub async fn record(pool: &SqlitePool, r: &NewCspReport) -> Result<()> {
+        let now = Utc::now();
+        sqlx::query!(
+            r#"INSERT INTO csp_reports
+                   (ts, disposition, document_uri, effective_directive, blocked_uri,
+                    source_file, line_number, sample)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"#,
+            now,
+            r.disposition,
+            r.document_uri,
+            r.effective_directive,
+            r.blocked_uri,
+            r.source_file,
+            r.line_number,
+            r.sample,
+        )
+        .execute(pool)
+        .await?;
+        sqlx::query!(
+            r#"DELETE FROM csp_reports
+               WHERE id <= (SELECT id FROM csp_reports ORDER BY id DESC LIMIT 1 OFFSET ?1)"#,
+            MAX_STORED_REPORTS,
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Stored reports grouped by `(effective_directive, blocked_uri)`.
+    pub async fn summary(pool: &SqlitePool) -> Result<Vec<CspReportGroup>> {
+        // With one MAX() in the select, SQLite fills the bare columns from the row
+        // it picked — the group's newest report.
+        Ok(sqlx::query_as!(
+            CspReportGroup,
+            r#"SELECT effective_directive, blocked_uri,
+                      COUNT(*) as "count!: i64",
+                      MAX(ts) as "last_seen!: DateTime<Utc>",
+                      document_uri as "document_uri!", disposition as "disposition!",
+                      sample
+               FROM csp_reports
+               GROUP BY effective_directive, blocked_uri
+               ORDER BY 3 DESC, 4 DESC
+               LIMIT ?1"#,
+            MAX_SUMMARY_GROUPS,
+        )
+        .fetch_all(pool)
+        .await?)
+    }
+
+    /// Drop every stored report (after fixing what they pointed at). Returns how
+    /// many went.
+    pub async fn clear(pool: &SqlitePool) -> Result<u64> {
+        Ok(sqlx::query!("DELETE FROM csp_reports")
+            .execute(pool)
+            .await?
+            .rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(directive: &str, blocked: &str) -> NewCspReport {
+        NewCspReport {
+            disposition: "report".into(),
+            document_uri: "https://hotchkiss.io/pages/a".into(),
+            effective_directive: directive.into(),
+            blocked_uri: blocked.into(),
+            ..Default::default()
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn record_group_and_clear(pool: SqlitePool) -> Result<()> {
+        CspReportDao::record(&pool, &report("script-src-elem", "inline")).await?;
+        CspReportDao::record(&pool, &report("script-src-elem", "inline")).await?;
+        CspReportDao::record(&pool, &report("img-src", "https://cdn.example")).await?;
+
+        let groups = CspReportDao::summary(&pool).await?;
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].blocked_uri, "inline", "busiest first");
+        assert_eq!(groups[0].count, 2);
+        assert_eq!(groups[1].effective_directive, "img-src");
+
+        assert_eq!(CspReportDao::clear(&pool).await?, 3);
+        assert!(CspReportDao::summary(&pool).await?.is_empty());
+        Ok(())
+    }
+}
@@ -5,8 +5,10 @@
 //!   `expires_at`; a manual pin is `manual = true` + `expires_at = None` (never lapses).
 //!   The request path reads an in-memory snapshot of the active set — the datetime()
 //!   compares here run on the sweep timer, never per request.
+//!   A row's `tier` is `Toll` (the cat-toll) or `Blocked` (a bare 403 — the clear-then-scan
+//!   escalation, `greylist::escalation`).
 //! - `greylist_clearance` — one row per solved toll (the "passing is a signal" data +
-//!   the feed for the deferred clear-then-scan escalation).
+//!   the feed for the clear-then-scan escalation, which revokes a row's `token_id`).
 //!
 //! `expires_at` is stored as text and compared with `datetime(expires_at) > datetime('now')`
 //! (NOT a raw string compare): the column can hold both the `CURRENT_TIMESTAMP` space-form
@@ -18,11 +20,21 @@ use sqlx::{
     prelude::FromRow,
     query, query_as,
     types::chrono::{DateTime, Utc},
-    SqliteExecutor,
+    SqliteExecutor, SqlitePool,
 };
+use strum::{Display, EnumString};
 
 use crate::db::dao::request_log::Window;
 
+/// What a greylisted IP gets, TEXT-persisted by variant name (strum) in `greylist.tier`.
+#[derive(Clone, Copy, Debug, Display, EnumString, PartialEq, Eq, sqlx::Type)]
+pub enum GreylistTier {
+    /// The cat-toll: solve it and get a clearance cookie.
+    Toll,
+    /// Solved the toll, then kept probing — a bare 403, no challenge, clearance revoked.
+    Blocked,
+}
+
 /// An active or lapsed greylist row. `manual` distinguishes an admin pin (never lapses)
 /// from a behavioral auto-entry (slides + expires).
 #[derive(Clone, Debug, FromRow, PartialEq, Eq)]
@@ -37,6 +49,9 @@ pub struct GreylistEntry {
     pub updated_at: DateTime<Utc>,
     /// `None` for a manual pin (never lapses); a sliding cutoff for an auto entry.
     pub expires_at: Option<DateTime<Utc>>,
+    pub tier: GreylistTier,
+    /// When the row was escalated to `Blocked` (`None` while it's a plain toll).
+    pub escalated_at: Option<DateTime<Utc>>,
 }
 
 impl GreylistEntry {
@@ -65,6 +80,19 @@ pub struct Clearance {
 /// the R1 signature list (CX.9). Refinement is human-in-the-loop by design: this surfaces the
 /// candidate, an admin adds worthy ones to `SIGNATURE_PATTERNS` + deploys (same retune-by-const
 /// pattern as the `is_bot` markers).
+/// A request a cleared IP made AFTER solving the toll, grouped by path + status — the
+/// clear-then-scan evidence `greylist::escalation` correlates. `clearance_id` / `token_id`
+/// name the clearance to revoke if it turns out to be a scraper that paid its way in.
+#[derive(Clone, Debug, FromRow, PartialEq, Eq)]
+pub struct PostClearanceHit {
+    pub clearance_id: i64,
+    pub token_id: Option<String>,
+    pub ip: String,
+    pub path: String,
+    pub status: i64,
+    pub count: i64,
+}
+
 #[derive(Clone, Debug, FromRow)]
 pub struct CandidatePath {
     pub path: String,
@@ -78,7 +106,9 @@ impl GreylistDao {
     /// Upsert a behavioral (auto) greylist row. On an existing IP the expiry SLIDES to the
     /// new cutoff, `updated_at` bumps, and reason/evidence refresh to the latest trip. Does
     /// NOT touch `manual`, so re-tripping a manually-pinned IP keeps the pin (and the pin's
-    /// `is_active` wins regardless of the expiry written here).
+    /// `is_active` wins regardless of the expiry written here). Nor does it touch `tier`: a
+    /// `Blocked` row keeps its (longer) block expiry and its escalation evidence — a re-trip
+    /// only bumps `updated_at` — so only an escalation or an admin de-escalation moves it.
     pub async fn upsert_auto(
         executor: impl SqliteExecutor<'_>,
         ip: &str,
@@ -139,6 +169,45 @@ impl GreylistDao {
             .rows_affected())
     }
 
+    /// Upsert an entry from an operator reputation list (`reason = "reputation:<list>"`).
+    /// Only ever touches its own rows: an IP already greylisted for anything else (a rule
+    /// trip, a pin, another list) keeps that row, reason and expiry untouched.
+    pub async fn upsert_listed(
+        executor: impl SqliteExecutor<'_>,
+        entry: &str,
+        reason: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        query!(
+            r#"
+            INSERT INTO greylist (ip, reason, evidence, manual, expires_at)
+            VALUES (?1, ?2, NULL, 0, ?3)
+            ON CONFLICT(ip) DO UPDATE SET
+                expires_at = excluded.expires_at,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE greylist.reason = excluded.reason AND greylist.manual = 0
+            "#,
+            entry,
+            reason,
+            expires_at,
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
+    /// The entries a reputation list currently owns (rows still carrying its `reason`).
+    pub async fn listed(executor: impl SqliteExecutor<'_>, reason: &str) -> Result<Vec<String>> {
+        Ok(
+            sqlx::query_scalar!(
+                "SELECT ip FROM greylist WHERE reason = ?1 AND manual = 0",
+                reason
+            )
+            .fetch_all(executor)
+            .await?,
+        )
+    }
+
     /// All currently-ACTIVE entries (manual pins + not-yet-expired auto rows), newest-touched
     /// first. Feeds the admin panel and the sweep's refresh of the request-path in-memory set.
     pub async fn active(executor: impl SqliteExecutor<'_>) -> Result<Vec<GreylistEntry>> {
@@ -152,7 +221,9 @@ impl GreylistDao {
                 manual as "manual!: bool",
                 created_at as "created_at!: DateTime<Utc>",
                 updated_at as "updated_at!: DateTime<Utc>",
-                expires_at as "expires_at?: DateTime<Utc>"
+                expires_at as "expires_at?: DateTime<Utc>",
+                tier as "tier!: GreylistTier",
+                escalated_at as "escalated_at?: DateTime<Utc>"
             FROM greylist
             WHERE manual = 1
                OR (expires_at IS NOT NULL AND datetime(expires_at) > datetime('now'))
@@ -163,20 +234,51 @@ impl GreylistDao {
         .await?)
     }
 
-    /// Record a solved toll. `digest_version`/`solve_ms`/`user_agent` are best-effort signal.
+    /// One entry by IP or range, active or lapsed — what the admin audit trail snapshots
+    /// around a pin, release or de-escalation.
+    pub async fn find(
+        executor: impl SqliteExecutor<'_>,
+        ip: &str,
+    ) -> Result<Option<GreylistEntry>> {
+        Ok(query_as!(
+            GreylistEntry,
+            r#"
+            SELECT
+                ip,
+                reason,
+                evidence,
+                manual as "manual!: bool",
+                created_at as "created_at!: DateTime<Utc>",
+                updated_at as "updated_at!: DateTime<Utc>",
+                expires_at as "expires_at?: DateTime<Utc>",
+                tier as "tier!: GreylistTier",
+                escalated_at as "escalated_at?: DateTime<Utc>"
+            FROM greylist
+            WHERE ip = ?1
+            "#,
+            ip,
+        )
+        .fetch_optional(executor)
+        .await?)
+    }
+
+    /// Record a solved toll. `token_id` is the minted cookie's id (what an escalation revokes);
+    /// `digest_version`/`solve_ms`/`user_agent` are best-effort signal.
     pub async fn record_clearance(
         executor: impl SqliteExecutor<'_>,
         ip: &str,
+        token_id: &str,
         solve_ms: Option<i64>,
         digest_version: Option<i64>,
         user_agent: Option<&str>,
     ) -> Result<()> {
         query!(
             r#"
-            INSERT INTO greylist_clearance (ip, solve_ms, digest_version, user_agent)
-            VALUES (?1, ?2, ?3, ?4)
+            INSERT INTO greylist_clearance (ip, token_id, solve_ms, digest_version, user_agent)
+            VALUES (?1, ?2, ?3, ?4, ?5)
             "#,
             ip,
+            token_id,
             solve_ms,
             digest_version,
             user_agent,
@@ -186,6 +288,134 @@ impl GreylistDao {
         Ok(())
     }
 
+    /// Escalate `ip` to the `Blocked` tier until `expires_at` (upserting — a cleared IP may
+    /// have aged off the greylist since it solved). A manual pin stays a pin (and never
+    /// lapses); `escalated_at` records the first escalation, not the latest.
+    pub async fn escalate(
+        executor: impl SqliteExecutor<'_>,
+        ip: &str,
+        reason: &str,
+        evidence: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let now = Utc::now();
+        query!(
+            r#"
+            INSERT INTO greylist (ip, reason, evidence, manual, expires_at, tier, escalated_at)
+            VALUES (?1, ?2, ?3, 0, ?4, 'Blocked', ?5)
+            ON CONFLICT(ip) DO UPDATE SET
+                reason       = excluded.reason,
+                evidence     = excluded.evidence,
+                expires_at   = CASE WHEN manual = 1 THEN NULL ELSE excluded.expires_at END,
+                tier         = 'Blocked',
+                escalated_at = COALESCE(escalated_at, excluded.escalated_at),
+                updated_at   = CURRENT_TIMESTAMP
+            "#,
+            ip,
+            reason,
+            evidence,
+            expires_at,
+            now,
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
+    /// Admin reversal of an escalation: back to the `Toll` tier (the IP stays greylisted —
+    /// release it separately to drop it entirely) and its revoked clearances are honored
+    /// again. Returns whether a blocked row was found.
+    pub async fn de_escalate(pool: &SqlitePool, ip: &str) -> Result<bool> {
+        let mut tx = pool.begin().await?;
+        let rows = query!(
+            r#"
+            UPDATE greylist
+            SET tier = 'Toll', escalated_at = NULL, updated_at = CURRENT_TIMESTAMP
+            WHERE ip = ?1 AND tier = 'Blocked'
+            "#,
+            ip
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        query!(
+            "UPDATE greylist_clearance SET revoked_at = NULL WHERE ip = ?1",
+            ip
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(rows > 0)
+    }
+
+    /// Requests each unrevoked clearance's IP made AFTER it solved, for clearances solved
+    /// since `since` (the cookie TTL — an older cookie has expired on its own), grouped by
+    /// path + status. Legacy clearances with no `token_id` can't be revoked, so they're skipped.
+    pub async fn post_clearance_hits(
+        executor: impl SqliteExecutor<'_>,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<PostClearanceHit>> {
+        Ok(query_as!(
+            PostClearanceHit,
+            r#"
+            SELECT
+                c.id as "clearance_id!: i64",
+                c.token_id,
+                c.ip,
+                r.path,
+                r.status as "status!: i64",
+                COUNT(*) as "count!: i64"
+            FROM greylist_clearance c
+            JOIN request_log r
+              ON r.ip = c.ip AND datetime(r.ts) > datetime(c.cleared_at)
+            WHERE c.revoked_at IS NULL
+              AND c.token_id IS NOT NULL
+              AND datetime(c.cleared_at) >= datetime(?1)
+            GROUP BY c.id, r.path, r.status
+            ORDER BY c.id
+            "#,
+            since
+        )
+        .fetch_all(executor)
+        .await?)
+    }
+
+    /// Put clearances on the revocation list. Returns rows newly revoked.
+    pub async fn revoke_clearance(executor: impl SqliteExecutor<'_>, id: i64) -> Result<u64> {
+        let now = Utc::now();
+        Ok(query!(
+            "UPDATE greylist_clearance SET revoked_at = ?2 WHERE id = ?1 AND revoked_at IS NULL",
+            id,
+            now
+        )
+        .execute(executor)
+        .await?
+        .rows_affected())
+    }
+
+    /// The revocation list: token ids of revoked clearances solved since `since` (older
+    /// tokens have expired anyway, so the list stays bounded by the cookie TTL).
+    pub async fn revoked_token_ids(
+        executor: impl SqliteExecutor<'_>,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<String>> {
+        Ok(query!(
+            r#"
+            SELECT token_id as "token_id!"
+            FROM greylist_clearance
+            WHERE revoked_at IS NOT NULL
+              AND token_id IS NOT NULL
+              AND datetime(cleared_at) >= datetime(?1)
+            "#,
+            since
+        )
+        .fetch_all(executor)
+        .await?
+        .into_iter()
+        .map(|r| r.token_id)
+        .collect())
+    }
+
     /// Most-recent clearances for the admin panel, newest first.
     pub async fn recent_clearances(
         executor: impl SqliteExecutor<'_>,
@@ -210,6 +440,47 @@ impl GreylistDao {
         .await?)
     }
 
+    /// Up to `limit` `(id, user_agent)` clearances from before the UTC day `after_days` ago
+    /// that the privacy policy (`privacy`) hasn't reduced yet. The IP stays — escalation and
+    /// the solve counts need it, like the greylist's own rows.
+    pub async fn clearances_due_for_anonymization(
+        executor: impl SqliteExecutor<'_>,
+        after_days: i64,
+        limit: i64,
+    ) -> Result<Vec<(i64, Option<String>)>> {
+        let modifier = format!("-{} days", after_days.max(0));
+        Ok(query!(
+            r#"
+            SELECT id as "id!: i64", user_agent
+            FROM greylist_clearance
+            WHERE anonymized = 0 AND cleared_at < date('now', ?1)
+            LIMIT ?2
+            "#,
+            modifier,
+            limit
+        )
+        .fetch_all(executor)
+        .await?
+        .into_iter()
+        .map(|r| (r.id, r.user_agent))
+        .collect())
+    }
+
+    pub async fn anonymize_clearance(
+        executor: impl SqliteExecutor<'_>,
+        id: i64,
+        user_agent: Option<&str>,
+    ) -> Result<()> {
+        query!(
+            "UPDATE greylist_clearance SET user_agent = ?2, anonymized = 1 WHERE id = ?1",
+            id,
+            user_agent
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
     /// Housekeeping: drop lapsed auto entries (manual pins are kept). `active()` already
     /// filters these out of reads — this just keeps the table small. Returns rows removed.
     pub async fn prune_expired(executor: impl SqliteExecutor<'_>) -> Result<u64> {
@@ -252,6 +523,26 @@ impl GreylistDao {
         .await?)
     }
 
+    /// Every IP that has ever solved the toll — the human labels for model training.
+    pub async fn cleared_ips(executor: impl SqliteExecutor<'_>) -> Result<Vec<String>> {
+        Ok(query!(r#"SELECT DISTINCT ip as "ip!" FROM greylist_clearance"#)
+            .fetch_all(executor)
+            .await?
+            .into_iter()
+            .map(|r| r.ip)
+            .collect())
+    }
+
+    /// Every manual pin (IPs and ranges) — the admin-judged bot labels for model training.
+    pub async fn pinned_entries(executor: impl SqliteExecutor<'_>) -> Result<Vec<String>> {
+        Ok(query!("SELECT ip FROM greylist WHERE manual = 1")
+            .fetch_all(executor)
+            .await?
+            .into_iter()
+            .map(|r| r.ip)
+            .collect())
+    }
+
     /// Toll SOLVES over the window (CY.8) — the "got through" numerator. `cleared_at` can hold
     /// either the space or the RFC3339 datetime form, so normalize with `datetime()`.
     pub async fn count_clearances_since(
@@ -374,8 +665,8 @@ mod tests {
 
     #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
     async fn clearances_roundtrip_newest_first(pool: SqlitePool) -> Result<()> {
-        GreylistDao::record_clearance(&pool, "2.2.2.2", Some(850), Some(1), Some("Mozilla/5.0")).await?;
-        GreylistDao::record_clearance(&pool, "3.3.3.3", None, Some(1), None).await?;
+        GreylistDao::record_clearance(&pool, "2.2.2.2", "t1", Some(850), Some(1), Some("Mozilla/5.0")).await?;
+        GreylistDao::record_clearance(&pool, "3.3.3.3", "t2", None, Some(1), None).await?;
 
         let recent = GreylistDao::recent_clearances(&pool, 10).await?;
         assert_eq!(recent.len(), 2);
@@ -388,9 +679,9 @@ mod tests {
 
     #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
     async fn clearance_counts_over_the_window(pool: SqlitePool) -> Result<()> {
-        GreylistDao::record_clearance(&pool, "1.1.1.1", Some(500), None, None).await?;
-        GreylistDao::record_clearance(&pool, "1.1.1.1", None, None, None).await?; // same IP, twice
-        GreylistDao::record_clearance(&pool, "2.2.2.2", None, None, None).await?;
+        GreylistDao::record_clearance(&pool, "1.1.1.1", "t1", Some(500), None, None).await?;
+        GreylistDao::record_clearance(&pool, "1.1.1.1", "t2", None, None, None).await?; // same IP, twice
+        GreylistDao::record_clearance(&pool, "2.2.2.2", "t3", None, None, None).await?;
 
         let all = Window::custom(None, None);
         assert_eq!(
@@ -405,4 +696,51 @@ mod tests {
         );
         Ok(())
     }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn escalation_blocks_revokes_and_reverses(pool: SqlitePool) -> Result<()> {
+        let ip = "4.4.4.4";
+        GreylistDao::record_clearance(&pool, ip, "tok-a", Some(900), None, None).await?;
+        // Backdate the solve so the probe below lands strictly after it.
+        query!("UPDATE greylist_clearance SET cleared_at = datetime('now', '-1 hour')")
+            .execute(&pool)
+            .await?;
+        for _ in 0..2 {
+            query!(
+                "INSERT INTO request_log (method, path, status, ip) VALUES ('GET', '/.env', 404, ?1)",
+                ip
+            )
+            .execute(&pool)
+            .await?;
+        }
+        let since = hours_from_now(-24);
+        let hits = GreylistDao::post_clearance_hits(&pool, since).await?;
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].path.as_str(), hits[0].status, hits[0].count), ("/.env", 404, 2));
+        assert_eq!(hits[0].token_id.as_deref(), Some("tok-a"));
+
+        assert_eq!(GreylistDao::revoke_clearance(&pool, hits[0].clearance_id).await?, 1);
+        assert_eq!(GreylistDao::revoke_clearance(&pool, hits[0].clearance_id).await?, 0);
+        assert_eq!(GreylistDao::revoked_token_ids(&pool, since).await?, vec!["tok-a"]);
+        assert!(
+            GreylistDao::post_clearance_hits(&pool, since).await?.is_empty(),
+            "a revoked clearance isn't correlated again"
+        );
+
+        GreylistDao::escalate(&pool, ip, "escalated", "sig=2", hours_from_now(24 * 30)).await?;
+        // A later auto re-trip must not downgrade the block or shorten it.
+        GreylistDao::upsert_auto(&pool, ip, "R3: flood", None, hours_from_now(1)).await?;
+        let e = GreylistDao::active(&pool).await?.remove(0);
+        assert_eq!((e.tier, e.reason.as_str()), (GreylistTier::Blocked, "escalated"));
+        assert!(e.escalated_at.is_some());
+        assert!(e.expires_at.unwrap() > hours_from_now(24 * 29));
+
+        assert!(GreylistDao::de_escalate(&pool, ip).await?);
+        assert!(!GreylistDao::de_escalate(&pool, ip).await?, "already a toll");
+        let e = GreylistDao::active(&pool).await?.remove(0);
+        assert_eq!(e.tier, GreylistTier::Toll);
+        assert!(e.escalated_at.is_none());
+        assert!(GreylistDao::revoked_token_ids(&pool, since).await?.is_empty());
+        Ok(())
+    }
 }
@@ -0,0 +1,167 @@
+//! Stored greylist scoring models and their shadow-mode disagreements with the rules.
+//!
+//! The artifact and metrics are opaque JSON here — `greylist::model` owns their shape
+//! (and refuses a `kind` it can't load), so the DAO never has to change with the model.
+
+use anyhow::Result;
+use sqlx::SqlitePool;
+use sqlx::types::chrono::{DateTime, Utc};
+
+/// Most disagreements the admin page lists.
+const MAX_LISTED_DISAGREEMENTS: i64 = 100;
+
+pub struct StoredModel {
+    pub version: i64,
+    pub kind: String,
+    pub artifact: String,
+    /// Samples it was fitted on (training split only).
+    pub samples: i64,
+    /// Holdout metrics, as JSON.
+    pub metrics: String,
+    pub trained_at: DateTime<Utc>,
+}
+
+/// One IP the rules and a model disagreed on. `rule_reason` is the rule that greylisted it,
+/// or `None` when the rules cleared it and the model would have greylisted.
+pub struct ShadowDisagreement {
+    pub ip: String,
+    pub rule_reason: Option<String>,
+    pub model_score: f64,
+    /// The feature counts both sides saw (latest pass).
+    pub evidence: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+pub struct GreylistModelDao;
+
+impl GreylistModelDao {
+    /// Store a freshly fitted model, returning its version.
+    pub async fn insert(
+        pool: &SqlitePool,
+        kind: &str,
+        artifact: &str,
+        samples: i64,
+        metrics: &str,
+    ) -> Result<i64> {
+        let now = Utc::now();
+        Ok(sqlx::query_scalar!(
+            r#"INSERT INTO greylist_model (kind, artifact, samples, metrics, trained_at)
+               VALUES (?1, ?2, ?3, ?4, ?5)
+               RETURNING version as "version!""#,
+            kind,
+            artifact,
+            samples,
+            metrics,
+            now,
+        )
+        .fetch_one(pool)
+        .await?)
+    }
+
+    /// The newest model — the one the sweep shadows.
+    pub async fn latest(pool: &SqlitePool) -> Result<Option<StoredModel>> {
+        Ok(sqlx::query_as!(
+            StoredModel,
+            r#"SELECT version as "version!", kind, artifact, samples, metrics,
+                      trained_at as "trained_at: DateTime<Utc>"
+               FROM greylist_model ORDER BY version DESC LIMIT 1"#,
+        )
+        .fetch_optional(pool)
+        .await?)
+    }
+
+    /// Record (or refresh) a disagreement for `ip` under model `version`. `first_seen`
+    /// sticks; everything else follows the latest pass.
+    pub async fn record_disagreement(
+        pool: &SqlitePool,
+        version: i64,
+        ip: &str,
+        rule_reason: Option<&str>,
+        model_score: f64,
+        evidence: &str,
+    ) -> Result<()> {
+        let now = Utc::now();
+        sqlx::query!(
+            r#"INSERT INTO greylist_shadow
+                   (model_version, ip, rule_reason, model_score, evidence, first_seen, last_seen)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+               ON CONFLICT(model_version, ip) DO UPDATE SET
+                   rule_reason = excluded.rule_reason,
+                   model_score = excluded.model_score,
+                   evidence    = excluded.evidence,
+                   last_seen   = excluded.last_seen"#,
+            version,
+            ip,
+            rule_reason,
+            model_score,
+            evidence,
+            now,
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// `version`'s disagreements, most recently seen first.
+    pub async fn disagreements(pool: &SqlitePool, version: i64) -> Result<Vec<ShadowDisagreement>> {
+        Ok(sqlx::query_as!(
+            ShadowDisagreement,
+            r#"SELECT ip, rule_reason, model_score, evidence,
+                      first_seen as "first_seen: DateTime<Utc>",
+                      last_seen as "last_seen: DateTime<Utc>"
+               FROM greylist_shadow WHERE model_version = ?1
+               ORDER BY last_seen DESC, ip LIMIT ?2"#,
+            version,
+            MAX_LISTED_DISAGREEMENTS,
+        )
+        .fetch_all(pool)
+        .await?)
+    }
+
+    /// Drop disagreements no pass has seen since `before` (the IP went quiet, or the two
+    /// sides came to agree). Returns rows removed.
+    pub async fn prune_disagreements(pool: &SqlitePool, before: DateTime<Utc>) -> Result<u64> {
+        Ok(
+            sqlx::query!("DELETE FROM greylist_shadow WHERE last_seen < ?1", before)
+                .execute(pool)
+                .await?
+                .rows_affected(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn models_version_and_disagreements_upsert(pool: SqlitePool) -> Result<()> {
+        assert!(GreylistModelDao::latest(&pool).await?.is_none());
+        let v1 = GreylistModelDao::insert(&pool, "logreg-v1", "{}", 10, "{}").await?;
+        let v2 = GreylistModelDao::insert(&pool, "logreg-v1", "{}", 20, "{}").await?;
+        assert!(v2 > v1);
+        let latest = GreylistModelDao::latest(&pool).await?.unwrap();
+        assert_eq!((latest.version, latest.samples), (v2, 20));
+
+        let ip = "203.0.113.7";
+        GreylistModelDao::record_disagreement(&pool, v2, ip, None, 0.9, "total=5").await?;
+        GreylistModelDao::record_disagreement(&pool, v2, ip, Some("R3: flood"), 0.1, "total=9")
+            .await?;
+        GreylistModelDao::record_disagreement(&pool, v1, ip, None, 0.7, "total=5").await?;
+
+        let rows = GreylistModelDao::disagreements(&pool, v2).await?;
+        assert_eq!(rows.len(), 1, "one row per (model, ip)");
+        assert_eq!(rows[0].rule_reason.as_deref(), Some("R3: flood"));
+        assert_eq!(rows[0].evidence, "total=9");
+        assert!(rows[0].first_seen <= rows[0].last_seen);
+
+        let future = DateTime::<Utc>::from_timestamp(Utc::now().timestamp() + 60, 0).unwrap();
+        assert_eq!(
+            GreylistModelDao::prune_disagreements(&pool, future).await?,
+            2
+        );
+        assert!(GreylistModelDao::disagreements(&pool, v2).await?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use sqlx::types::chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::api_keys::{generate_secret, hash_key};
+use super::roles::Role;
+
+/// Longest an invite may live — it's a bearer link that can end up in a chat log.
+pub const MAX_INVITE_DAYS: i64 = 30;
+
+/// One invite as the admin page lists it — never the token or its hash.
+pub struct InviteDao {
+    pub id: i64,
+    pub role: Role,
+    pub note: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub redeemed_at: Option<DateTime<Utc>>,
+    /// Display name of the user who redeemed it (`None` if unredeemed, or that
+    /// user has since been deleted).
+    pub redeemed_by: Option<String>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl InviteDao {
+    /// Mint an invite landing its redeemer at `role`, returning the PLAINTEXT token
+    /// (`inv_<43-char base64url>` — shown once, in the link) and the row id.
+    pub async fn create(
+        pool: &SqlitePool,
+        created_by: &Uuid,
+        role: Role,
+        note: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(String, i64)> {
+        let token = generate_secret("inv_")?;
+        let hash = hash_key(pool, &token).await?;
+        let creator = created_by.to_string();
+        let role = role.to_string();
+        let now = Utc::now();
+        let id = sqlx::query_scalar!(
+            r#"INSERT INTO invites (token_hash, role, note, created_by, created_at, expires_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+               RETURNING id as "id!""#,
+            hash,
+            role,
+            note,
+            creator,
+            now,
+            expires_at,
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok((token, id))
+    }
+
+    /// Resolve a presented token to `(invite id, role)` if it can still be
+    /// redeemed: unredeemed, unrevoked, unexpired. A miss is `None` — the caller
+    /// can't tell (and needn't) which of those it was.
+    pub async fn find_redeemable(pool: &SqlitePool, token: &str) -> Result<Option<(i64, Role)>> {
+        let hash = hash_key(pool, token).await?;
+        let now = Utc::now();
+        Ok(sqlx::query!(
+            r#"SELECT id as "id!", role as "role: Role" FROM invites
+               WHERE token_hash = ?1 AND redeemed_at IS NULL AND revoked_at IS NULL
+                 AND expires_at > ?2"#,
+            hash,
+            now,
+        )
+        .fetch_optional(pool)
+        .await?
+        .map(|r| (r.id, r.role)))
+    }
+
+    /// Claim invite `id` for `user_id`, returning its role — or `None` if it was
+    /// redeemed, revoked or expired since [`Self::find_redeemable`] (the checks are
+    /// IN the UPDATE, so two racing registrations can't both win).
+    pub async fn redeem(pool: &SqlitePool, id: i64, user_id: &Uuid) -> Result<Option<Role>> {
+        let uid = user_id.to_string();
+        let now = Utc::now();
+        Ok(sqlx::query!(
+            r#"UPDATE invites SET redeemed_at = ?1, redeemed_by = ?2
+               WHERE id = ?3 AND redeemed_at IS NULL AND revoked_at IS NULL AND expires_at > ?1
+               RETURNING role as "role: Role""#,
+            now,
+            uid,
+            id,
+        )
+        .fetch_optional(pool)
+        .await?
+        .map(|r| r.role))
+    }
+
+    /// Every invite, newest first.
+    pub async fn list(pool: &SqlitePool) -> Result<Vec<InviteDao>> {
+        Ok(sqlx::query_as!(
+            InviteDao,
+            r#"SELECT i.id as "id!", i.role as "role: Role", i.note,
+                      i.created_at as "created_at!: DateTime<Utc>",
+                      i.expires_at as "expires_at!: DateTime<Utc>",
+                      i.redeemed_at as "redeemed_at?: DateTime<Utc>",
+                      u.display_name as "redeemed_by?",
+                      i.revoked_at as "revoked_at?: DateTime<Utc>"
+               FROM invites i LEFT JOIN users u ON u.id = i.redeemed_by
+               ORDER BY i.id DESC"#
+        )
+        .fetch_all(pool)
+        .await?)
+    }
+
+    /// Revoke a still-open invite. Returns whether one was revoked (a redeemed or
+    /// already-revoked invite is left as it is).
+    pub async fn revoke(pool: &SqlitePool, id: i64) -> Result<bool> {
+        let now = Utc::now();
+        let res = sqlx::query!(
+            r#"UPDATE invites SET revoked_at = ?1
+               WHERE id = ?2 AND redeemed_at IS NULL AND revoked_at IS NULL"#,
+            now,
+            id,
+        )
+        .execute(pool)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::dao::users::UserDao;
+
+    async fn seed_user(pool: &SqlitePool, name: &str) -> Result<UserDao> {
+        let mut user = UserDao {
+            display_name: name.to_string(),
+            id: Uuid::now_v7(),
+            keys: sqlx::types::Json(vec![]),
+            role: Role::Registered,
+        };
+        user.create(pool).await?;
+        Ok(user)
+    }
+
+    fn days_from_now(days: i64) -> DateTime<Utc> {
+        DateTime::<Utc>::from_timestamp(Utc::now().timestamp() + days * 86_400, 0).unwrap()
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn mint_redeem_once_and_revoke(pool: SqlitePool) -> Result<()> {
+        let admin = seed_user(&pool, "admin").await?;
+        let (token, id) =
+            InviteDao::create(&pool, &admin.id, Role::Family, "grandma", days_from_now(7)).await?;
+        assert!(token.starts_with("inv_"), "{token}");
+
+        assert_eq!(InviteDao::find_redeemable(&pool, &token).await?, Some((id, Role::Family)));
+        assert!(InviteDao::find_redeemable(&pool, "inv_nope").await?.is_none());
+
+        // Single use: the first claim wins, the second gets nothing.
+        let grandma = seed_user(&pool, "grandma").await?;
+        assert_eq!(InviteDao::redeem(&pool, id, &grandma.id).await?, Some(Role::Family));
+        assert_eq!(InviteDao::redeem(&pool, id, &admin.id).await?, None);
+        assert!(InviteDao::find_redeemable(&pool, &token).await?.is_none());
+        // A redeemed invite can't be revoked after the fact.
+        assert!(!InviteDao::revoke(&pool, id).await?);
+
+        // Revoke kills an open one.
+        let (token2, id2) =
+            InviteDao::create(&pool, &admin.id, Role::Admin, "", days_from_now(1)).await?;
+        assert!(InviteDao::revoke(&pool, id2).await?);
+        assert!(InviteDao::find_redeemable(&pool, &token2).await?.is_none());
+        assert_eq!(InviteDao::redeem(&pool, id2, &grandma.id).await?, None);
+
+        let list = InviteDao::list(&pool).await?;
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[1].redeemed_by.as_deref(), Some("grandma"));
+        assert!(list[0].revoked_at.is_some());
+        Ok(())
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn expired_invite_is_dead(pool: SqlitePool) -> Result<()> {
+        let admin = seed_user(&pool, "admin").await?;
+        let (token, id) =
+            InviteDao::create(&pool, &admin.id, Role::Family, "", days_from_now(-1)).await?;
+        assert!(InviteDao::find_redeemable(&pool, &token).await?.is_none());
+        assert_eq!(InviteDao::redeem(&pool, id, &admin.id).await?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+//! The GeoIP / ASN lookup cache (`geoip`): one row per IP, joined against `request_log` for
+//! the analytics country and ASN panels.
+
+use anyhow::Result;
+use sqlx::{SqliteExecutor, SqlitePool};
+
+use crate::db::dao::request_log::{Audience, Window};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IpGeo {
+    pub ip: String,
+    /// ISO 3166-1 alpha-2 code.
+    pub country: Option<String>,
+    pub asn: Option<i64>,
+    /// The ASN's registered organization.
+    pub org: Option<String>,
+}
+
+impl IpGeo {
+    /// `DE · AS24940 Hetzner Online GmbH`, or as much of it as is known ("" for nothing).
+    pub fn label(&self) -> String {
+        let asn = match (self.asn, self.org.as_deref()) {
+            (Some(asn), Some(org)) => Some(format!("AS{asn} {org}")),
+            (Some(asn), None) => Some(format!("AS{asn}")),
+            (None, Some(org)) => Some(org.to_string()),
+            (None, None) => None,
+        };
+        [self.country.clone(), asn]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" · ")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CountryCount {
+    /// `None` = the database had no country for these IPs.
+    pub country: Option<String>,
+    pub requests: i64,
+    pub ips: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct AsnCount {
+    pub asn: Option<i64>,
+    pub org: Option<String>,
+    pub requests: i64,
+    pub ips: i64,
+}
+
+pub struct IpGeoDao;
+
+impl IpGeoDao {
+    /// The cached rows for `ips` (any order; IPs with no row are simply absent).
+    pub async fn get_many(pool: &SqlitePool, ips: &[String]) -> Result<Vec<IpGeo>> {
+        let ips = serde_json::to_string(ips)?;
+        Ok(sqlx::query_as!(
+            IpGeo,
+            r#"SELECT ip as "ip!", country, asn, org
+               FROM ip_geo WHERE ip IN (SELECT value FROM json_each(?1))"#,
+            ips,
+        )
+        .fetch_all(pool)
+        .await?)
+    }
+
+    pub async fn upsert(executor: impl SqliteExecutor<'_>, geo: &IpGeo) -> Result<()> {
+        sqlx::query!(
+            r#"INSERT INTO ip_geo (ip, country, asn, org) VALUES (?1, ?2, ?3, ?4)
+               ON CONFLICT(ip) DO UPDATE SET
+                   country      = excluded.country,
+                   asn          = excluded.asn,
+                   org          = excluded.org,
+                   looked_up_at = CURRENT_TIMESTAMP"#,
+            geo.ip,
+            geo.country,
+            geo.asn,
+            geo.org,
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
+    /// The distinct IPs logged over the window that have no cached row yet (anonymized rows
+    /// have no real IP left to look up).
+    pub async fn missing_ips(pool: &SqlitePool, w: &Window) -> Result<Vec<String>> {
+        Ok(sqlx::query_scalar!(
+            r#"SELECT DISTINCT r.ip as "ip!: String"
+               FROM request_log r LEFT JOIN ip_geo g ON g.ip = r.ip
+               WHERE r.ip IS NOT NULL AND r.ts >= ?1 AND r.ts < ?2 AND g.ip IS NULL
+                 AND r.anonymized = 0"#,
+            w.from,
+            w.to,
+        )
+        .fetch_all(pool)
+        .await?)
+    }
+
+    /// Drop rows looked up more than `days` ago, so they're looked up afresh (picking up a
+    /// newer database) the next time they're needed.
+    pub async fn prune_older_than(pool: &SqlitePool, days: i64) -> Result<u64> {
+        let modifier = format!("-{days} days");
+        Ok(sqlx::query!(
+            "DELETE FROM ip_geo WHERE looked_up_at < datetime('now', ?1)",
+            modifier,
+        )
+        .execute(pool)
+        .await?
+        .rows_affected())
+    }
+
+    /// Requests and distinct IPs per country over the window, busiest first. Only rows
+    /// with a cached lookup count, so run [`crate::geoip::GeoDb::enrich`] first.
+    pub async fn traffic_by_country(
+        executor: impl SqliteExecutor<'_>,
+        w: &Window,
+        audience: Audience,
+        limit: i64,
+    ) -> Result<Vec<CountryCount>> {
+        let bot = audience.as_bot_filter();
+        let ch = audience.as_challenged_filter();
+        Ok(sqlx::query_as!(
+            CountryCount,
+            r#"
+            SELECT g.country as "country?: String",
+                   COUNT(*) as "requests!: i64",
+                   COUNT(DISTINCT r.ip) as "ips!: i64"
+            FROM request_log r JOIN ip_geo g ON g.ip = r.ip
+            WHERE r.ts >= ?1 AND r.ts < ?2 AND (?3 IS NULL OR r.is_bot = ?3)
+              AND (?4 IS NULL OR r.challenged = ?4)
+            GROUP BY g.country
+            ORDER BY COUNT(*) DESC, g.country ASC
+            LIMIT ?5
+            "#,
+            w.from,
+            w.to,
+            bot,
+            ch,
+            limit
+        )
+        .fetch_all(executor)
+        .await?)
+    }
+
+    /// Requests and distinct IPs per ASN over the window, busiest first.
+    pub async fn top_asns(
+        executor: impl SqliteExecutor<'_>,
+        w: &Window,
+        audience: Audience,
+        limit: i64,
+    ) -> Result<Vec<AsnCount>> {
+        let bot = audience.as_bot_filter();
+        let ch = audience.as_challenged_filter();
+        Ok(sqlx::query_as!(
+            AsnCount,
+            r#"
+            SELECT g.asn as "asn?: i64",
+                   MAX(g.org) as "org?: String",
+                   COUNT(*) as "requests!: i64",
+                   COUNT(DISTINCT r.ip) as "ips!: i64"
+            FROM request_log r JOIN ip_geo g ON g.ip = r.ip
+            WHERE r.ts >= ?1 AND r.ts < ?2 AND (?3 IS NULL OR r.is_bot = ?3)
+              AND (?4 IS NULL OR r.challenged = ?4)
+            GROUP BY g.asn
+            ORDER BY COUNT(*) DESC, g.asn ASC
+            LIMIT ?5
+            "#,
+            w.from,
+            w.to,
+            bot,
+            ch,
+            limit
+        )
+        .fetch_all(executor)
+        .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::dao::request_log::{NewRequestLog, RequestLogDao};
+
+    fn geo(ip: &str, country: &str, asn: i64, org: &str) -> IpGeo {
+        IpGeo {
+            ip: ip.into(),
+            country: Some(country.into()),
+            asn: Some(asn),
+            org: Some(org.into()),
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn panels_aggregate_cached_lookups(pool: SqlitePool) -> Result<()> {
+        for ip in ["203.0.113.1", "203.0.113.1", "198.51.100.2", "192.0.2.3"] {
+            RequestLogDao::insert(
+                &pool,
+                &NewRequestLog {
+                    method: "GET".into(),
+                    path: "/".into(),
+                    status: 200,
+                    ip: Some(ip.into()),
+                    user_agent: None,
+                    referer: None,
+                    duration_ms: 0,
+                    is_bot: false,
+                    challenged: false,
+                    rate_limited: false,
+                    visitor: None,
+                    campaign: None,
+                },
+            )
+            .await?;
+        }
+        let window = Window::last_days(1);
+        let mut missing = IpGeoDao::missing_ips(&pool, &window).await?;
+        missing.sort();
+        assert_eq!(missing, ["192.0.2.3", "198.51.100.2", "203.0.113.1"]);
+
+        IpGeoDao::upsert(
+            &pool,
+            &geo("203.0.113.1", "DE", 24940, "Hetzner Online GmbH"),
+        )
+        .await?;
+        IpGeoDao::upsert(&pool, &geo("198.51.100.2", "US", 16509, "AMAZON-02")).await?;
+        assert_eq!(IpGeoDao::missing_ips(&pool, &window).await?, ["192.0.2.3"]);
+
+        let countries = IpGeoDao::traffic_by_country(&pool, &window, Audience::All, 10).await?;
+        let countries: Vec<(Option<&str>, i64, i64)> = countries
+            .iter()
+            .map(|c| (c.country.as_deref(), c.requests, c.ips))
+            .collect();
+        assert_eq!(countries, [(Some("DE"), 2, 1), (Some("US"), 1, 1)]);
+
+        let asns = IpGeoDao::top_asns(&pool, &window, Audience::All, 10).await?;
+        assert_eq!(asns[0].asn, Some(24940));
+        assert_eq!(asns[0].org.as_deref(), Some("Hetzner Online GmbH"));
+
+        let cached =
+            IpGeoDao::get_many(&pool, &["198.51.100.2".into(), "192.0.2.3".into()]).await?;
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached[0].label(), "US · AS16509 AMAZON-02");
+
+        assert_eq!(IpGeoDao::prune_older_than(&pool, 30).await?, 0, "all fresh");
+        Ok(())
+    }
+}
@@ -1,10 +1,22 @@
 pub mod acme_account;
+pub mod alerts;
 pub mod api_keys;
+pub mod audit_log;
 pub mod certificate;
 pub mod content_pages;
 pub mod crypto_key;
+pub mod csp_reports;
 pub mod greylist;
+pub mod greylist_model;
+pub mod invites;
+pub mod ip_geo;
 pub mod media;
+pub mod oidc;
+pub mod passkeys;
+pub mod recovery_codes;
+pub mod reputation_list;
 pub mod request_log;
+pub mod request_log_rollup;
 pub mod roles;
+pub mod user_sessions;
 pub mod users;
@@ -0,0 +1,325 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use sqlx::types::chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::api_keys::{generate_secret, hash_key};
+
+/// How long an authorization code lives between the consent redirect and the
+/// relying party's token exchange.
+pub const CODE_TTL_SECS: i64 = 60;
+
+/// One registered relying party, as the admin page lists it — never the secret
+/// or its hash.
+pub struct OidcClientDao {
+    pub id: i64,
+    pub client_id: String,
+    pub name: String,
+    /// Exact-match redirect URIs, newline-separated as stored.
+    pub redirect_uris: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl OidcClientDao {
+    /// The registered redirect URIs, one per line.
+    pub fn redirect_uris(&self) -> impl Iterator<Item = &str> {
+        self.redirect_uris
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+    }
+
+    /// Exact string match against a registered URI — no prefix or pattern.
+    pub fn allows_redirect(&self, uri: &str) -> bool {
+        self.redirect_uris().any(|r| r == uri)
+    }
+
+    /// Register a client, returning its PLAINTEXT secret (`ocs_<43-char
+    /// base64url>` — shown once) and the stored row. Only the HMAC hash is kept.
+    pub async fn create(
+        pool: &SqlitePool,
+        name: &str,
+        redirect_uris: &[String],
+    ) -> Result<(String, OidcClientDao)> {
+        let client_id = format!("oc_{}", Uuid::new_v4().simple());
+        let secret = generate_secret("ocs_")?;
+        let secret_hash = hash_key(pool, &secret).await?;
+        let redirect_uris = redirect_uris.join("\n");
+        let created_at = Utc::now();
+        let id = sqlx::query_scalar!(
+            r#"INSERT INTO oidc_clients (client_id, secret_hash, name, redirect_uris, created_at)
+               VALUES (?1, ?2, ?3, ?4, ?5)
+               RETURNING id as "id!""#,
+            client_id,
+            secret_hash,
+            name,
+            redirect_uris,
+            created_at,
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok((
+            secret,
+            OidcClientDao {
+                id,
+                client_id,
+                name: name.to_string(),
+                redirect_uris,
+                created_at,
+            },
+        ))
+    }
+
+    /// Every client, oldest first.
+    pub async fn list(pool: &SqlitePool) -> Result<Vec<OidcClientDao>> {
+        Ok(sqlx::query_as!(
+            OidcClientDao,
+            r#"SELECT id as "id!", client_id, name, redirect_uris,
+                      created_at as "created_at!: DateTime<Utc>"
+               FROM oidc_clients ORDER BY id"#
+        )
+        .fetch_all(pool)
+        .await?)
+    }
+
+    pub async fn find_by_client_id(
+        pool: &SqlitePool,
+        client_id: &str,
+    ) -> Result<Option<OidcClientDao>> {
+        Ok(sqlx::query_as!(
+            OidcClientDao,
+            r#"SELECT id as "id!", client_id, name, redirect_uris,
+                      created_at as "created_at!: DateTime<Utc>"
+               FROM oidc_clients WHERE client_id = ?1"#,
+            client_id
+        )
+        .fetch_optional(pool)
+        .await?)
+    }
+
+    /// The client if `secret` is its secret, else `None` (unknown id and wrong
+    /// secret look the same to the caller).
+    pub async fn authenticate(
+        pool: &SqlitePool,
+        client_id: &str,
+        secret: &str,
+    ) -> Result<Option<OidcClientDao>> {
+        let hash = hash_key(pool, secret).await?;
+        Ok(sqlx::query_as!(
+            OidcClientDao,
+            r#"SELECT id as "id!", client_id, name, redirect_uris,
+                      created_at as "created_at!: DateTime<Utc>"
+               FROM oidc_clients WHERE client_id = ?1 AND secret_hash = ?2"#,
+            client_id,
+            hash,
+        )
+        .fetch_optional(pool)
+        .await?)
+    }
+
+    /// Remove a client, returning the removed row. Its outstanding codes and
+    /// remembered consents go with it (ON DELETE CASCADE).
+    pub async fn delete(pool: &SqlitePool, id: i64) -> Result<Option<OidcClientDao>> {
+        Ok(sqlx::query_as!(
+            OidcClientDao,
+            r#"DELETE FROM oidc_clients WHERE id = ?1
+               RETURNING id as "id!", client_id, name, redirect_uris,
+                         created_at as "created_at!: DateTime<Utc>""#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?)
+    }
+
+    /// True when `user_id` already allowed this client every scope in `scope`
+    /// (space-separated) — the consent screen is skipped.
+    pub async fn has_consent(
+        pool: &SqlitePool,
+        user_id: &Uuid,
+        client_id: &str,
+        scope: &str,
+    ) -> Result<bool> {
+        let uid = user_id.to_string();
+        let granted = sqlx::query_scalar!(
+            r#"SELECT scope FROM oidc_consents WHERE user_id = ?1 AND client_id = ?2"#,
+            uid,
+            client_id,
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(granted.is_some_and(|g| {
+            scope
+                .split_whitespace()
+                .all(|s| g.split_whitespace().any(|h| h == s))
+        }))
+    }
+
+    /// Remember that `user_id` allowed this client `scope` (replacing any
+    /// earlier grant).
+    pub async fn grant_consent(
+        pool: &SqlitePool,
+        user_id: &Uuid,
+        client_id: &str,
+        scope: &str,
+    ) -> Result<()> {
+        let uid = user_id.to_string();
+        let now = Utc::now();
+        sqlx::query!(
+            r#"INSERT INTO oidc_consents (user_id, client_id, scope, granted_at)
+               VALUES (?1, ?2, ?3, ?4)
+               ON CONFLICT (user_id, client_id)
+               DO UPDATE SET scope = excluded.scope, granted_at = excluded.granted_at"#,
+            uid,
+            client_id,
+            scope,
+            now,
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
+
+/// What an authorization code is bound to, from the consent step to the token
+/// exchange.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OidcCodeDao {
+    pub client_id: String,
+    pub user_id: String,
+    pub redirect_uri: String,
+    /// The PKCE S256 challenge the exchange's verifier must hash to.
+    pub code_challenge: String,
+    pub nonce: Option<String>,
+    pub scope: String,
+}
+
+impl OidcCodeDao {
+    /// Store a fresh code bound to `self`, returning the PLAINTEXT (`occ_…`,
+    /// handed to the relying party in the redirect). Expired codes are swept on
+    /// the way in.
+    pub async fn create(&self, pool: &SqlitePool) -> Result<String> {
+        let code = generate_secret("occ_")?;
+        let hash = hash_key(pool, &code).await?;
+        let now = Utc::now();
+        let expires_at =
+            DateTime::<Utc>::from_timestamp(now.timestamp() + CODE_TTL_SECS, 0).unwrap_or(now);
+        sqlx::query!("DELETE FROM oidc_codes WHERE expires_at <= ?1", now)
+            .execute(pool)
+            .await?;
+        sqlx::query!(
+            r#"INSERT INTO oidc_codes
+                   (code_hash, client_id, user_id, redirect_uri, code_challenge, nonce, scope,
+                    expires_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"#,
+            hash,
+            self.client_id,
+            self.user_id,
+            self.redirect_uri,
+            self.code_challenge,
+            self.nonce,
+            self.scope,
+            expires_at,
+        )
+        .execute(pool)
+        .await?;
+        Ok(code)
+    }
+
+    /// Claim `code` for `client_id`: `None` if it's unknown, another client's,
+    /// expired, or already used. The checks are IN the UPDATE, so a replayed code
+    /// can't be exchanged twice even by racing requests.
+    pub async fn redeem(
+        pool: &SqlitePool,
+        code: &str,
+        client_id: &str,
+    ) -> Result<Option<OidcCodeDao>> {
+        let hash = hash_key(pool, code).await?;
+        let now = Utc::now();
+        Ok(sqlx::query_as!(
+            OidcCodeDao,
+            r#"UPDATE oidc_codes SET used_at = ?1
+               WHERE code_hash = ?2 AND client_id = ?3 AND used_at IS NULL AND expires_at > ?1
+               RETURNING client_id, user_id, redirect_uri, code_challenge, nonce, scope"#,
+            now,
+            hash,
+            client_id,
+        )
+        .fetch_optional(pool)
+        .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::dao::{roles::Role, users::UserDao};
+
+    async fn seed_user(pool: &SqlitePool) -> Result<UserDao> {
+        let mut user = UserDao {
+            display_name: "grandma".to_string(),
+            id: Uuid::now_v7(),
+            keys: sqlx::types::Json(vec![]),
+            role: Role::Family,
+        };
+        user.create(pool).await?;
+        Ok(user)
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn client_secret_code_and_consent(pool: SqlitePool) -> Result<()> {
+        let uris = vec![
+            "http://photos.lan/cb".to_string(),
+            "http://photos.lan/cb2".into(),
+        ];
+        let (secret, client) = OidcClientDao::create(&pool, "Photos", &uris).await?;
+        assert!(secret.starts_with("ocs_") && client.client_id.starts_with("oc_"));
+        assert!(client.allows_redirect("http://photos.lan/cb2"));
+        assert!(!client.allows_redirect("http://photos.lan/cb/evil"));
+
+        let cid = client.client_id.as_str();
+        assert!(
+            OidcClientDao::authenticate(&pool, cid, &secret)
+                .await?
+                .is_some()
+        );
+        assert!(
+            OidcClientDao::authenticate(&pool, cid, "ocs_wrong")
+                .await?
+                .is_none()
+        );
+
+        let user = seed_user(&pool).await?;
+        let bound = OidcCodeDao {
+            client_id: cid.to_string(),
+            user_id: user.id.to_string(),
+            redirect_uri: uris[0].clone(),
+            code_challenge: "challenge".to_string(),
+            nonce: Some("n-1".to_string()),
+            scope: "openid profile".to_string(),
+        };
+        let code = bound.create(&pool).await?;
+        // Another client can't exchange it, and it doesn't burn the code trying.
+        assert!(
+            OidcCodeDao::redeem(&pool, &code, "oc_other")
+                .await?
+                .is_none()
+        );
+        assert_eq!(OidcCodeDao::redeem(&pool, &code, cid).await?, Some(bound));
+        assert!(
+            OidcCodeDao::redeem(&pool, &code, cid).await?.is_none(),
+            "single use"
+        );
+
+        assert!(!OidcClientDao::has_consent(&pool, &user.id, cid, "openid").await?);
+        OidcClientDao::grant_consent(&pool, &user.id, cid, "openid").await?;
+        assert!(OidcClientDao::has_consent(&pool, &user.id, cid, "openid").await?);
+        assert!(!OidcClientDao::has_consent(&pool, &user.id, cid, "openid profile").await?);
+
+        // Deleting the client forgets its consents.
+        let removed = OidcClientDao::delete(&pool, client.id).await?;
+        assert_eq!(removed.map(|c| c.name).as_deref(), Some("Photos"));
+        assert!(!OidcClientDao::has_consent(&pool, &user.id, cid, "openid").await?);
+        assert!(OidcClientDao::list(&pool).await?.is_empty());
+        Ok(())
+    }
+}
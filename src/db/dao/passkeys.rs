@@ -0,0 +1,174 @@
+use anyhow::Result;
+use base64::Engine;
+use sqlx::{SqliteExecutor, SqlitePool};
+use sqlx::types::chrono::{DateTime, Utc};
+use uuid::Uuid;
+use webauthn_rs::prelude::CredentialID;
+
+/// Label a credential gets when nobody named it (the self-registration ceremony,
+/// and every passkey backfilled by migration 0034).
+pub const DEFAULT_PASSKEY_LABEL: &str = "Passkey";
+
+/// The human-facing side of one passkey in `users.keys`: a label plus registered /
+/// last-used dates. Keyed on the credential id; the `Passkey` blob itself stays in
+/// the users row, where the WebAuthn ceremonies read it.
+pub struct PasskeyMetaDao {
+    pub cred_id: String,
+    pub label: String,
+    /// `None` for a credential registered before tracking began.
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// A credential id in the stored form — base64url without padding, the same
+/// encoding the serialized `Passkey` JSON carries (so migration 0034's backfill
+/// and the runtime agree byte-for-byte).
+pub fn cred_id_key(cred_id: &CredentialID) -> String {
+    let bytes: &[u8] = cred_id.as_ref();
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+impl PasskeyMetaDao {
+    /// Record a freshly registered credential. An existing row for the same id (a
+    /// re-registration of a stranded credential) is overwritten.
+    pub async fn record(
+        executor: impl SqliteExecutor<'_>,
+        user_id: &Uuid,
+        cred_id: &str,
+        label: &str,
+    ) -> Result<()> {
+        let uid = user_id.to_string();
+        let now = Utc::now();
+        sqlx::query!(
+            r#"INSERT INTO passkey_meta (cred_id, user_id, label, created_at)
+               VALUES (?1, ?2, ?3, ?4)
+               ON CONFLICT(cred_id) DO UPDATE SET
+                   user_id = excluded.user_id,
+                   label = excluded.label,
+                   created_at = excluded.created_at,
+                   last_used_at = NULL"#,
+            cred_id,
+            uid,
+            label,
+            now,
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
+    /// Stamp `last_used_at` after a successful authentication ceremony
+    /// (best-effort — the caller logs, never fails the login).
+    pub async fn touch_last_used(pool: &SqlitePool, cred_id: &str) -> Result<()> {
+        let now = Utc::now();
+        sqlx::query!(
+            r#"UPDATE passkey_meta SET last_used_at = ?1 WHERE cred_id = ?2"#,
+            now,
+            cred_id,
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Every metadata row for a user, oldest first (undated backfills lead).
+    pub async fn list_for_user(pool: &SqlitePool, user_id: &Uuid) -> Result<Vec<PasskeyMetaDao>> {
+        let uid = user_id.to_string();
+        Ok(sqlx::query_as!(
+            PasskeyMetaDao,
+            r#"SELECT cred_id as "cred_id!", label,
+                      created_at as "created_at?: DateTime<Utc>",
+                      last_used_at as "last_used_at?: DateTime<Utc>"
+               FROM passkey_meta WHERE user_id = ?1
+               ORDER BY created_at IS NOT NULL, created_at"#,
+            uid,
+        )
+        .fetch_all(pool)
+        .await?)
+    }
+
+    /// Rename one of the user's credentials. Scoped to `user_id`, so nobody can
+    /// relabel another account's key; returns whether a row changed.
+    pub async fn rename(
+        executor: impl SqliteExecutor<'_>,
+        user_id: &Uuid,
+        cred_id: &str,
+        label: &str,
+    ) -> Result<bool> {
+        let uid = user_id.to_string();
+        let res = sqlx::query!(
+            r#"UPDATE passkey_meta SET label = ?1 WHERE cred_id = ?2 AND user_id = ?3"#,
+            label,
+            cred_id,
+            uid,
+        )
+        .execute(executor)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    /// Drop a credential's metadata (the caller removes the blob from `users.keys`).
+    pub async fn delete(
+        executor: impl SqliteExecutor<'_>,
+        user_id: &Uuid,
+        cred_id: &str,
+    ) -> Result<()> {
+        let uid = user_id.to_string();
+        sqlx::query!(
+            r#"DELETE FROM passkey_meta WHERE cred_id = ?1 AND user_id = ?2"#,
+            cred_id,
+            uid,
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::dao::roles::Role;
+    use crate::db::dao::users::UserDao;
+
+    async fn seed_user(pool: &SqlitePool) -> Result<UserDao> {
+        let mut user = UserDao {
+            display_name: "chris".to_string(),
+            id: Uuid::now_v7(),
+            keys: sqlx::types::Json(vec![]),
+            role: Role::Registered,
+        };
+        user.create(pool).await?;
+        Ok(user)
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn record_touch_rename_delete(pool: SqlitePool) -> Result<()> {
+        let user = seed_user(&pool).await?;
+        PasskeyMetaDao::record(&pool, &user.id, "cred-a", "phone").await?;
+        PasskeyMetaDao::record(&pool, &user.id, "cred-b", DEFAULT_PASSKEY_LABEL).await?;
+
+        let rows = PasskeyMetaDao::list_for_user(&pool, &user.id).await?;
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|r| r.created_at.is_some() && r.last_used_at.is_none()));
+
+        PasskeyMetaDao::touch_last_used(&pool, "cred-a").await?;
+        assert!(PasskeyMetaDao::rename(&pool, &user.id, "cred-b", "laptop").await?);
+        // Scoped: another user can't rename it.
+        assert!(!PasskeyMetaDao::rename(&pool, &Uuid::now_v7(), "cred-b", "evil").await?);
+
+        let rows = PasskeyMetaDao::list_for_user(&pool, &user.id).await?;
+        let a = rows.iter().find(|r| r.cred_id == "cred-a").unwrap();
+        let b = rows.iter().find(|r| r.cred_id == "cred-b").unwrap();
+        assert!(a.last_used_at.is_some());
+        assert_eq!(b.label, "laptop");
+
+        PasskeyMetaDao::delete(&pool, &user.id, "cred-a").await?;
+        assert_eq!(PasskeyMetaDao::list_for_user(&pool, &user.id).await?.len(), 1);
+
+        // Deleting the user cascades the rest.
+        UserDao::delete(&pool, &user.id).await?;
+        assert!(PasskeyMetaDao::list_for_user(&pool, &user.id).await?.is_empty());
+        Ok(())
+    }
+}
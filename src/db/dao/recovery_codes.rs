@@ -0,0 +1,252 @@
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+use sqlx::types::chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::api_keys::hash_key;
+
+/// Codes in one set generated from `/account`.
+pub const RECOVERY_CODE_SET_SIZE: usize = 10;
+
+/// Crockford base32: no I, L, O or U, so a code read off paper can't be mistyped
+/// as a lookalike. 32 symbols divide 256 evenly, so a random byte maps without bias.
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Symbols per code (16 × 5 bits = 80 bits), printed in groups of four.
+const CODE_SYMBOLS: usize = 16;
+
+/// Where a user's recovery codes stand, for `/account` — never the codes.
+pub struct RecoveryCodeSummary {
+    pub unused: i64,
+    /// When the newest code was generated; `None` = the user has none at all.
+    pub generated_at: Option<DateTime<Utc>>,
+}
+
+pub struct RecoveryCodeDao;
+
+impl RecoveryCodeDao {
+    /// Replace `user_id`'s codes with a fresh set, returning the PLAINTEXT codes
+    /// (shown once). Every earlier code — used or not — stops working.
+    pub async fn replace_set(pool: &SqlitePool, user_id: &Uuid) -> Result<Vec<String>> {
+        let minted = mint(pool, RECOVERY_CODE_SET_SIZE).await?;
+        let uid = user_id.to_string();
+        let now = Utc::now();
+        let mut tx = pool.begin().await?;
+        sqlx::query!("DELETE FROM recovery_codes WHERE user_id = ?1", uid)
+            .execute(&mut *tx)
+            .await?;
+        for (_, hash) in &minted {
+            sqlx::query!(
+                r#"INSERT INTO recovery_codes (user_id, code_hash, created_at)
+                   VALUES (?1, ?2, ?3)"#,
+                uid,
+                hash,
+                now,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(minted.into_iter().map(|(code, _)| code).collect())
+    }
+
+    /// Add ONE code for `user_id`, leaving any existing codes alone — the offline
+    /// `recovery_code` binary's path. Returns the plaintext.
+    pub async fn add_one(pool: &SqlitePool, user_id: &Uuid) -> Result<String> {
+        let (code, hash) = mint(pool, 1)
+            .await?
+            .pop()
+            .context("minting a recovery code")?;
+        let uid = user_id.to_string();
+        let now = Utc::now();
+        sqlx::query!(
+            r#"INSERT INTO recovery_codes (user_id, code_hash, created_at)
+               VALUES (?1, ?2, ?3)"#,
+            uid,
+            hash,
+            now,
+        )
+        .execute(pool)
+        .await?;
+        Ok(code)
+    }
+
+    /// Claim one of `user_id`'s unused codes, returning its row id — or `None` for a
+    /// wrong, malformed or already-used code (the caller can't tell which). The
+    /// `used_at IS NULL` check is IN the UPDATE, so a code can't be spent twice.
+    pub async fn redeem(pool: &SqlitePool, user_id: &Uuid, presented: &str) -> Result<Option<i64>> {
+        let Some(code) = normalize(presented) else {
+            return Ok(None);
+        };
+        let hash = hash_key(pool, &code).await?;
+        let uid = user_id.to_string();
+        let now = Utc::now();
+        Ok(sqlx::query_scalar!(
+            r#"UPDATE recovery_codes SET used_at = ?1
+               WHERE user_id = ?2 AND code_hash = ?3 AND used_at IS NULL
+               RETURNING id as "id!""#,
+            now,
+            uid,
+            hash,
+        )
+        .fetch_optional(pool)
+        .await?)
+    }
+
+    pub async fn summary(pool: &SqlitePool, user_id: &Uuid) -> Result<RecoveryCodeSummary> {
+        let uid = user_id.to_string();
+        let row = sqlx::query!(
+            r#"SELECT COALESCE(SUM(used_at IS NULL), 0) as "unused!: i64",
+                      MAX(created_at) as "generated_at?: DateTime<Utc>"
+               FROM recovery_codes WHERE user_id = ?1"#,
+            uid,
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(RecoveryCodeSummary {
+            unused: row.unused,
+            generated_at: row.generated_at,
+        })
+    }
+}
+
+/// `count` fresh codes, each as `(display form, stored hash)`.
+async fn mint(pool: &SqlitePool, count: usize) -> Result<Vec<(String, String)>> {
+    let mut minted = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut raw = [0u8; CODE_SYMBOLS];
+        openssl::rand::rand_bytes(&mut raw).context("generating recovery code bytes")?;
+        let symbols: String = raw
+            .iter()
+            .map(|b| char::from(ALPHABET[usize::from(b % 32)]))
+            .collect();
+        let hash = hash_key(pool, &symbols).await?;
+        minted.push((display(&symbols), hash));
+    }
+    Ok(minted)
+}
+
+/// `ABCD1234EFGH5678` → `ABCD-1234-EFGH-5678`.
+fn display(symbols: &str) -> String {
+    symbols
+        .as_bytes()
+        .chunks(4)
+        .map(|c| String::from_utf8_lossy(c).into_owned())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// A code as typed → its canonical symbols: dashes and spaces dropped, case
+/// folded, and the lookalikes Crockford reserves (`O` → `0`, `I`/`L` → `1`)
+/// mapped back. `None` if what's left can't be a code.
+fn normalize(presented: &str) -> Option<String> {
+    let symbols: String = presented
+        .chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .map(|c| match c.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            c => c,
+        })
+        .collect();
+    (symbols.len() == CODE_SYMBOLS && symbols.bytes().all(|b| ALPHABET.contains(&b)))
+        .then_some(symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::dao::roles::Role;
+    use crate::db::dao::users::UserDao;
+
+    async fn seed_user(pool: &SqlitePool, name: &str) -> Result<UserDao> {
+        let mut user = UserDao {
+            display_name: name.to_string(),
+            id: Uuid::now_v7(),
+            keys: sqlx::types::Json(vec![]),
+            role: Role::Registered,
+        };
+        user.create(pool).await?;
+        Ok(user)
+    }
+
+    #[test]
+    fn normalization_forgives_case_dashes_and_lookalikes() {
+        assert_eq!(
+            normalize("abcd-1234-efgh-5678").as_deref(),
+            Some("ABCD1234EFGH5678")
+        );
+        assert_eq!(
+            normalize(" 0O1I L000 0000 0000 ").as_deref(),
+            Some("0011100000000000")
+        );
+        assert_eq!(normalize("ABCD-1234"), None, "too short");
+        assert_eq!(
+            normalize("ABCD-1234-EFGH-567U"),
+            None,
+            "U isn't in the alphabet"
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn codes_redeem_once_and_a_new_set_replaces_the_old(pool: SqlitePool) -> Result<()> {
+        let alice = seed_user(&pool, "alice").await?;
+        let bob = seed_user(&pool, "bob").await?;
+
+        let codes = RecoveryCodeDao::replace_set(&pool, &alice.id).await?;
+        assert_eq!(codes.len(), RECOVERY_CODE_SET_SIZE);
+        assert_eq!(codes[0].len(), 19, "{}", codes[0]);
+        assert_eq!(RecoveryCodeDao::summary(&pool, &alice.id).await?.unused, 10);
+
+        // Single use, and only for its own account; typed loosely still works.
+        assert!(
+            RecoveryCodeDao::redeem(&pool, &bob.id, &codes[0])
+                .await?
+                .is_none()
+        );
+        let typed = codes[0].to_lowercase().replace('-', " ");
+        assert!(
+            RecoveryCodeDao::redeem(&pool, &alice.id, &typed)
+                .await?
+                .is_some()
+        );
+        assert!(
+            RecoveryCodeDao::redeem(&pool, &alice.id, &codes[0])
+                .await?
+                .is_none()
+        );
+        assert!(
+            RecoveryCodeDao::redeem(&pool, &alice.id, "nonsense")
+                .await?
+                .is_none()
+        );
+        assert_eq!(RecoveryCodeDao::summary(&pool, &alice.id).await?.unused, 9);
+
+        // A new set kills every old code.
+        let fresh = RecoveryCodeDao::replace_set(&pool, &alice.id).await?;
+        assert!(
+            RecoveryCodeDao::redeem(&pool, &alice.id, &codes[1])
+                .await?
+                .is_none()
+        );
+        assert_eq!(RecoveryCodeDao::summary(&pool, &alice.id).await?.unused, 10);
+
+        // The offline mint adds alongside.
+        let extra = RecoveryCodeDao::add_one(&pool, &alice.id).await?;
+        assert_eq!(RecoveryCodeDao::summary(&pool, &alice.id).await?.unused, 11);
+        assert!(
+            RecoveryCodeDao::redeem(&pool, &alice.id, &extra)
+                .await?
+                .is_some()
+        );
+        assert!(
+            RecoveryCodeDao::redeem(&pool, &alice.id, &fresh[0])
+                .await?
+                .is_some()
+        );
+
+        let none = RecoveryCodeDao::summary(&pool, &bob.id).await?;
+        assert_eq!((none.unused, none.generated_at), (0, None));
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+//! Load status of the operator's IP reputation lists (`greylist::reputation`). The listed
+//! addresses are ordinary `greylist` rows; this is the per-list bookkeeping the reload
+//! check and the admin page read.
+
+use anyhow::Result;
+use sqlx::SqlitePool;
+use sqlx::types::chrono::{DateTime, Utc};
+
+pub struct ReputationListStatus {
+    pub name: String,
+    pub path: String,
+    /// Entries imported by the last good load.
+    pub entries: i64,
+    /// Lines the last good load couldn't parse (or that covered the operator's own IP).
+    pub skipped: i64,
+    /// The file's mtime (unix milliseconds) at the last good load.
+    pub file_mtime: Option<i64>,
+    pub loaded_at: Option<DateTime<Utc>>,
+    /// Why the latest load failed, if it did.
+    pub error: Option<String>,
+}
+
+pub struct ReputationListDao;
+
+impl ReputationListDao {
+    /// Every list's status, by name.
+    pub async fn all(pool: &SqlitePool) -> Result<Vec<ReputationListStatus>> {
+        Ok(sqlx::query_as!(
+            ReputationListStatus,
+            r#"SELECT name as "name!", path, entries, skipped, file_mtime,
+                      loaded_at as "loaded_at: DateTime<Utc>", error
+               FROM reputation_list ORDER BY name"#,
+        )
+        .fetch_all(pool)
+        .await?)
+    }
+
+    pub async fn get(pool: &SqlitePool, name: &str) -> Result<Option<ReputationListStatus>> {
+        Ok(sqlx::query_as!(
+            ReputationListStatus,
+            r#"SELECT name as "name!", path, entries, skipped, file_mtime,
+                      loaded_at as "loaded_at: DateTime<Utc>", error
+               FROM reputation_list WHERE name = ?1"#,
+            name,
+        )
+        .fetch_optional(pool)
+        .await?)
+    }
+
+    /// Record a good load of `name` from `path` (clearing any earlier error).
+    pub async fn record_load(
+        pool: &SqlitePool,
+        name: &str,
+        path: &str,
+        entries: i64,
+        skipped: i64,
+        file_mtime: i64,
+    ) -> Result<()> {
+        let now = Utc::now();
+        sqlx::query!(
+            r#"INSERT INTO reputation_list (name, path, entries, skipped, file_mtime, loaded_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+               ON CONFLICT(name) DO UPDATE SET
+                   path       = excluded.path,
+                   entries    = excluded.entries,
+                   skipped    = excluded.skipped,
+                   file_mtime = excluded.file_mtime,
+                   loaded_at  = excluded.loaded_at,
+                   error      = NULL"#,
+            name,
+            path,
+            entries,
+            skipped,
+            file_mtime,
+            now,
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Record a failed load. The last good load's counts (and its greylist rows) stand.
+    pub async fn record_error(
+        pool: &SqlitePool,
+        name: &str,
+        path: &str,
+        error: &str,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"INSERT INTO reputation_list (name, path, error) VALUES (?1, ?2, ?3)
+               ON CONFLICT(name) DO UPDATE SET path = excluded.path, error = excluded.error"#,
+            name,
+            path,
+            error,
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Forget the status of every list not in `names` (dropped from the settings). Their
+    /// greylist rows are left to age out on their own expiry.
+    pub async fn retain(pool: &SqlitePool, names: &[String]) -> Result<u64> {
+        let names = serde_json::to_string(names)?;
+        Ok(sqlx::query!(
+            "DELETE FROM reputation_list WHERE name NOT IN (SELECT value FROM json_each(?1))",
+            names,
+        )
+        .execute(pool)
+        .await?
+        .rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn loads_errors_and_retain(pool: SqlitePool) -> Result<()> {
+        ReputationListDao::record_load(&pool, "drop", "/lists/drop.txt", 900, 2, 1_700_000_000)
+            .await?;
+        ReputationListDao::record_error(&pool, "drop", "/lists/drop.txt", "gone").await?;
+        let s = ReputationListDao::get(&pool, "drop").await?.unwrap();
+        assert_eq!(
+            (s.entries, s.file_mtime),
+            (900, Some(1_700_000_000)),
+            "last good load stands"
+        );
+        assert_eq!(s.error.as_deref(), Some("gone"));
+
+        ReputationListDao::record_load(&pool, "drop", "/lists/drop.txt", 901, 0, 1_700_000_100)
+            .await?;
+        assert_eq!(
+            ReputationListDao::get(&pool, "drop").await?.unwrap().error,
+            None
+        );
+
+        ReputationListDao::record_error(&pool, "old", "/lists/old.txt", "missing").await?;
+        assert_eq!(
+            ReputationListDao::retain(&pool, &["drop".to_string()]).await?,
+            1
+        );
+        let names: Vec<String> = ReputationListDao::all(&pool)
+            .await?
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names, ["drop"]);
+        Ok(())
+    }
+}
@@ -40,6 +40,41 @@ pub struct NewRequestLog {
     /// Whether this request was served the greylist toll (CX.5). A challenged request is
     /// provably-not-human, so the write path also forces `is_bot` true when this is set.
     pub challenged: bool,
+    /// Whether this request was refused by the rate limiter (a `429` from
+    /// `middleware::rate_limit`) — split from the greylist tolls in the analytics.
+    pub rate_limited: bool,
+    /// Keyed hash of the full IP (`privacy::VisitorKey`), which the distinct-visitor counts
+    /// go by so they survive the IP's later anonymization. Filled in by the writer.
+    pub visitor: Option<String>,
+    /// The campaign a tagged link carried (`web::util::campaign`), if any.
+    pub campaign: Option<Campaign>,
+}
+
+/// Where a tagged link says a visit came from: `utm_source` (or `ref`), `utm_medium` and
+/// `utm_campaign`, normalized by `web::util::campaign`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Campaign {
+    pub source: String,
+    pub medium: Option<String>,
+    pub name: Option<String>,
+}
+
+/// One campaign's tagged arrivals over a window — the analytics "Campaigns" panel.
+#[derive(Clone, Debug)]
+pub struct CampaignCount {
+    pub source: String,
+    pub medium: Option<String>,
+    pub name: Option<String>,
+    pub count: i64,
+}
+
+/// A logged request's identifying fields, for the privacy task (`privacy`).
+#[derive(Clone, Debug)]
+pub struct LoggedIdentity {
+    pub id: i64,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub visitor: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -48,6 +83,13 @@ pub struct PathCount {
     pub count: i64,
 }
 
+/// Rate-limiter refusals over a window (requests, distinct IPs).
+#[derive(Clone, Debug)]
+pub struct RateLimitedCounts {
+    pub requests: i64,
+    pub ips: i64,
+}
+
 #[derive(Clone, Debug)]
 pub struct DayCount {
     pub day: String,
@@ -69,7 +111,7 @@ pub struct RefererCount {
 /// Total / human / bot request counts over a window — the always-visible 3-chip
 /// (CQ.2). `humans + bots == all` by construction (every row classifies as exactly
 /// one, once backfilled). Directional: `is_bot` is a spoofable-User-Agent heuristic.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct AudienceCounts {
     pub all: i64,
     pub humans: i64,
@@ -170,7 +212,7 @@ pub const SCAN_DISTINCT_404_THRESHOLD: i64 = 5;
 /// conditional counts. 403 + 404 are split OUT of `s4xx` on purpose: 403 = blocked,
 /// 404 = the scanner-probe signal. Every status lands in exactly one bucket (1xx and
 /// any status outside 200–599 are not counted — the app never emits them).
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct StatusBucketCounts {
     pub s2xx: i64,
     pub s3xx: i64,
@@ -225,6 +267,18 @@ pub struct IpPathAgg {
     pub count: i64,
 }
 
+/// An [`IpPathAgg`] bucketed by UTC day — the training set for the fitted greylist model
+/// (`greylist::training`), where each IP-day becomes one sample shaped like a sweep window.
+#[derive(Clone, Debug)]
+pub struct IpDayPathAgg {
+    /// `YYYY-MM-DD`.
+    pub day: String,
+    pub ip: String,
+    pub path: String,
+    pub status: i64,
+    pub count: i64,
+}
+
 /// One timed request for latency analysis (CQ.6): the raw path + its server-handler
 /// duration. Percentiles are computed Rust-side (SQLite has no percentile fn), so this
 /// pulls the whole windowed sample set — see the SPEC latency deferral (a SQL histogram
@@ -235,6 +289,10 @@ pub struct LatencySample {
     pub duration_ms: i64,
 }
 
+/// How long raw `request_log` rows are kept. Older days live on only as daily rollups
+/// (`request_log_rollup::ROLLUP_RETAIN_DAYS`).
+pub const RAW_RETAIN_DAYS: i64 = 90;
+
 fn window(days: i64) -> String {
     // SQLite datetime modifier — `datetime('now', '-7 days')`. Still used by
     // `prune_before` (a DELETE); the analytics reads moved to `Window` (CT.1).
@@ -269,9 +327,17 @@ impl Window {
     /// (`sqlx::types::chrono` doesn't re-export the `TimeDelta`/`Duration` delta type,
     /// only the datetime types), which needs only `DateTime`/`Utc`.
     pub fn last_days(days: i64) -> Self {
+        Self::last_secs(days.max(0) * 86_400)
+    }
+
+    /// A short preset lookback, `[now - minutes, unbounded)` — the alert rules' window.
+    pub fn last_minutes(minutes: i64) -> Self {
+        Self::last_secs(minutes.max(0) * 60)
+    }
+
+    fn last_secs(secs: i64) -> Self {
         let now = Utc::now();
-        let cutoff_secs = now.timestamp() - days.max(0) * 86_400;
-        let from = DateTime::<Utc>::from_timestamp(cutoff_secs, 0).unwrap_or(now);
+        let from = DateTime::<Utc>::from_timestamp(now.timestamp() - secs, 0).unwrap_or(now);
         Self {
             from: Self::fmt(from),
             to: WINDOW_FAR_FUTURE.to_string(),
@@ -287,14 +353,38 @@ impl Window {
             to: to.map(Self::fmt).unwrap_or_else(|| WINDOW_FAR_FUTURE.to_string()),
         }
     }
+
+    /// Split at the start of `day` (`YYYY-MM-DD`) into `(before, from_day_on)`. Either half
+    /// may come out empty (`from >= to`) when the window lies wholly on one side.
+    pub fn split_at(&self, day: &str) -> (Window, Window) {
+        let at = format!("{day} 00:00:00");
+        let before = Window {
+            from: self.from.clone(),
+            to: self.to.clone().min(at.clone()),
+        };
+        let after = Window {
+            from: self.from.clone().max(at),
+            to: self.to.clone(),
+        };
+        (before, after)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.from >= self.to
+    }
 }
 
 impl RequestLogDao {
-    pub async fn insert(executor: impl SqliteExecutor<'_>, new: &NewRequestLog) -> Result<()> {
-        query!(
+    /// Returns the new row's id.
+    pub async fn insert(executor: impl SqliteExecutor<'_>, new: &NewRequestLog) -> Result<i64> {
+        let campaign = new.campaign.as_ref();
+        let source = campaign.map(|c| c.source.as_str());
+        let medium = campaign.and_then(|c| c.medium.as_deref());
+        let name = campaign.and_then(|c| c.name.as_deref());
+        Ok(query!(
             r#"
-            INSERT INTO request_log (method, path, status, ip, user_agent, referer, duration_ms, is_bot, challenged)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            INSERT INTO request_log (method, path, status, ip, user_agent, referer, duration_ms, is_bot, challenged, rate_limited, visitor, campaign_source, campaign_medium, campaign_name)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
             "#,
             new.method,
             new.path,
@@ -305,10 +395,28 @@ impl RequestLogDao {
             new.duration_ms,
             new.is_bot,
             new.challenged,
+            new.rate_limited,
+            new.visitor,
+            source,
+            medium,
+            name,
         )
         .execute(executor)
-        .await?;
-        Ok(())
+        .await?
+        .last_insert_rowid())
+    }
+
+    /// Insert `rows` in order in ONE transaction — the batched writer's commit
+    /// (`web::request_log_writer`). All-or-nothing: an error rolls the whole batch back.
+    /// Returns the new ids, in `rows` order.
+    pub async fn insert_batch(pool: &SqlitePool, rows: &[NewRequestLog]) -> Result<Vec<i64>> {
+        let mut tx = pool.begin().await?;
+        let mut ids = Vec::with_capacity(rows.len());
+        for row in rows {
+            ids.push(Self::insert(&mut *tx, row).await?);
+        }
+        tx.commit().await?;
+        Ok(ids)
     }
 
     pub async fn recent(
@@ -359,6 +467,9 @@ impl RequestLogDao {
         .count)
     }
 
+    /// Distinct visitors over the window. Counted by `visitor` — the keyed hash of the full
+    /// IP — where the row has one, so a window straddling the privacy policy's cutoff counts
+    /// an IP once on both sides of it, truncated or not. Legacy rows fall back to `ip`.
     pub async fn distinct_ip_count(
         executor: impl SqliteExecutor<'_>,
         w: &Window,
@@ -368,7 +479,7 @@ impl RequestLogDao {
         let ch = audience.as_challenged_filter();
         Ok(query!(
             r#"
-            SELECT COUNT(DISTINCT ip) as "count!: i64"
+            SELECT COUNT(DISTINCT COALESCE(visitor, ip)) as "count!: i64"
             FROM request_log
             WHERE ts >= ?1 AND ts < ?2 AND ip IS NOT NULL AND (?3 IS NULL OR is_bot = ?3) AND (?4 IS NULL OR challenged = ?4)
             "#,
@@ -407,6 +518,50 @@ impl RequestLogDao {
         .await?)
     }
 
+    /// Rate-limiter refusals over the window: how many, and from how many distinct IPs.
+    /// Not audience-filtered — like the toll numbers, an always-shown sub-metric.
+    pub async fn rate_limited_counts(
+        executor: impl SqliteExecutor<'_>,
+        w: &Window,
+    ) -> Result<RateLimitedCounts> {
+        Ok(query_as!(
+            RateLimitedCounts,
+            r#"
+            SELECT COUNT(*) as "requests!: i64", COUNT(DISTINCT COALESCE(visitor, ip)) as "ips!: i64"
+            FROM request_log
+            WHERE ts >= ?1 AND ts < ?2 AND rate_limited = 1
+            "#,
+            w.from,
+            w.to
+        )
+        .fetch_one(executor)
+        .await?)
+    }
+
+    /// The paths the rate limiter refused most over the window.
+    pub async fn rate_limited_paths(
+        executor: impl SqliteExecutor<'_>,
+        w: &Window,
+        limit: i64,
+    ) -> Result<Vec<PathCount>> {
+        Ok(query_as!(
+            PathCount,
+            r#"
+            SELECT path, COUNT(*) as "count!: i64"
+            FROM request_log
+            WHERE ts >= ?1 AND ts < ?2 AND rate_limited = 1
+            GROUP BY path
+            ORDER BY COUNT(*) DESC, path ASC
+            LIMIT ?3
+            "#,
+            w.from,
+            w.to,
+            limit
+        )
+        .fetch_all(executor)
+        .await?)
+    }
+
     /// Distinct IPs that got the greylist toll over the window (CY.8) — the denominator for the
     /// hard-vs-soft solve rate (how many WALLED IPs then solved through).
     pub async fn distinct_challenged_ips(
@@ -415,7 +570,7 @@ impl RequestLogDao {
     ) -> Result<i64> {
         Ok(query!(
             r#"
-            SELECT COUNT(DISTINCT ip) as "count!: i64"
+            SELECT COUNT(DISTINCT COALESCE(visitor, ip)) as "count!: i64"
             FROM request_log
             WHERE ts >= ?1 AND ts < ?2 AND challenged = 1 AND ip IS NOT NULL
             "#,
@@ -494,6 +649,60 @@ impl RequestLogDao {
         .count)
     }
 
+    /// Tagged arrivals per campaign over the window, audience-filtered like the top pages.
+    pub async fn count_by_campaign(
+        executor: impl SqliteExecutor<'_>,
+        w: &Window,
+        audience: Audience,
+        limit: i64,
+    ) -> Result<Vec<CampaignCount>> {
+        let bot = audience.as_bot_filter();
+        let ch = audience.as_challenged_filter();
+        Ok(query_as!(
+            CampaignCount,
+            r#"
+            SELECT campaign_source as "source!: String",
+                   campaign_medium as medium,
+                   campaign_name as name,
+                   COUNT(*) as "count!: i64"
+            FROM request_log
+            WHERE ts >= ?1 AND ts < ?2 AND campaign_source IS NOT NULL
+              AND (?3 IS NULL OR is_bot = ?3) AND (?4 IS NULL OR challenged = ?4)
+            GROUP BY campaign_source, campaign_medium, campaign_name
+            ORDER BY COUNT(*) DESC, campaign_source ASC, campaign_medium ASC, campaign_name ASC
+            LIMIT ?5
+            "#,
+            w.from,
+            w.to,
+            bot,
+            ch,
+            limit
+        )
+        .fetch_all(executor)
+        .await?)
+    }
+
+    /// The distinct campaigns logged on `day` (`YYYY-MM-DD`) — what the writer's daily
+    /// campaign budget starts from after a restart.
+    pub async fn campaigns_on(
+        executor: impl SqliteExecutor<'_>,
+        day: &str,
+    ) -> Result<Vec<Campaign>> {
+        Ok(query_as!(
+            Campaign,
+            r#"
+            SELECT DISTINCT campaign_source as "source!: String",
+                   campaign_medium as medium,
+                   campaign_name as name
+            FROM request_log
+            WHERE ts >= ?1 AND ts < date(?1, '+1 day') AND campaign_source IS NOT NULL
+            "#,
+            day
+        )
+        .fetch_all(executor)
+        .await?)
+    }
+
     pub async fn count_by_day(
         executor: impl SqliteExecutor<'_>,
         w: &Window,
@@ -519,8 +728,8 @@ impl RequestLogDao {
         .await?)
     }
 
-    /// Unique visitors (distinct IP) per day over the window. NULL-ip rows are
-    /// excluded — they can't be attributed to a visitor.
+    /// Unique visitors (distinct IP, by `visitor` as in [`Self::distinct_ip_count`]) per day
+    /// over the window. NULL-ip rows are excluded — they can't be attributed to a visitor.
     pub async fn distinct_ip_by_day(
         executor: impl SqliteExecutor<'_>,
         w: &Window,
@@ -531,7 +740,7 @@ impl RequestLogDao {
         Ok(query_as!(
             DayCount,
             r#"
-            SELECT substr(ts, 1, 10) as "day!: String", COUNT(DISTINCT ip) as "count!: i64"
+            SELECT substr(ts, 1, 10) as "day!: String", COUNT(DISTINCT COALESCE(visitor, ip)) as "count!: i64"
             FROM request_log
             WHERE ts >= ?1 AND ts < ?2 AND ip IS NOT NULL AND (?3 IS NULL OR is_bot = ?3) AND (?4 IS NULL OR challenged = ?4)
             GROUP BY substr(ts, 1, 10)
@@ -746,6 +955,34 @@ impl RequestLogDao {
         .await?)
     }
 
+    /// [`Self::ip_path_aggregates`] split by UTC day, ordered by day then ip — the offline
+    /// model trainer's input. Reads the whole retained log, so it's for the CLI, never a
+    /// request.
+    pub async fn ip_day_path_aggregates(
+        executor: impl SqliteExecutor<'_>,
+        w: &Window,
+    ) -> Result<Vec<IpDayPathAgg>> {
+        Ok(query_as!(
+            IpDayPathAgg,
+            r#"
+            SELECT
+                substr(ts, 1, 10) as "day!: String",
+                ip as "ip!: String",
+                path,
+                status,
+                COUNT(*) as "count!: i64"
+            FROM request_log
+            WHERE ip IS NOT NULL AND ts >= ?1 AND ts < ?2
+            GROUP BY substr(ts, 1, 10), ip, path, status
+            ORDER BY 1, 2
+            "#,
+            w.from,
+            w.to
+        )
+        .fetch_all(executor)
+        .await?)
+    }
+
     /// User-Agent breakdown for ONE ip over the window (CQ.4) — a rotating-UA client
     /// (many UAs from one IP) is itself a bot tell.
     pub async fn ip_user_agents(
@@ -916,11 +1153,85 @@ impl RequestLogDao {
         Ok(updated)
     }
 
-    /// Delete rows older than `retain_days`. Returns the number removed.
+    /// Up to `limit` rows with an IP but no `visitor` key — logged before keys were.
+    pub async fn unkeyed(
+        executor: impl SqliteExecutor<'_>,
+        limit: i64,
+    ) -> Result<Vec<LoggedIdentity>> {
+        Ok(query_as!(
+            LoggedIdentity,
+            r#"
+            SELECT id as "id!: i64", ip, user_agent, visitor
+            FROM request_log
+            WHERE visitor IS NULL AND ip IS NOT NULL
+            LIMIT ?1
+            "#,
+            limit
+        )
+        .fetch_all(executor)
+        .await?)
+    }
+
+    pub async fn set_visitor(
+        executor: impl SqliteExecutor<'_>,
+        id: i64,
+        visitor: &str,
+    ) -> Result<()> {
+        query!("UPDATE request_log SET visitor = ?2 WHERE id = ?1", id, visitor)
+            .execute(executor)
+            .await?;
+        Ok(())
+    }
+
+    /// Up to `limit` rows not anonymized yet from before the UTC day `after_days` ago,
+    /// oldest first. Whole days, so a day's rows are all in full or all anonymized.
+    pub async fn due_for_anonymization(
+        executor: impl SqliteExecutor<'_>,
+        after_days: i64,
+        limit: i64,
+    ) -> Result<Vec<LoggedIdentity>> {
+        let w = window(after_days);
+        Ok(query_as!(
+            LoggedIdentity,
+            r#"
+            SELECT id as "id!: i64", ip, user_agent, visitor
+            FROM request_log
+            WHERE anonymized = 0 AND ts < date('now', ?1)
+            ORDER BY ts ASC
+            LIMIT ?2
+            "#,
+            w,
+            limit
+        )
+        .fetch_all(executor)
+        .await?)
+    }
+
+    /// Overwrite a row's identifying fields with their anonymized forms and mark it done.
+    pub async fn anonymize(executor: impl SqliteExecutor<'_>, row: &LoggedIdentity) -> Result<()> {
+        query!(
+            r#"
+            UPDATE request_log SET ip = ?2, user_agent = ?3, visitor = ?4, anonymized = 1
+            WHERE id = ?1
+            "#,
+            row.id,
+            row.ip,
+            row.user_agent,
+            row.visitor
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
+    /// Delete rows from before the UTC day `retain_days` ago. Returns the number removed.
+    /// Whole days go at once, so the oldest day left is complete — the rollups
+    /// (`request_log_rollup`) take over exactly at a day boundary, and the coordinator rolls
+    /// a day up before it's pruned.
     pub async fn prune_before(executor: impl SqliteExecutor<'_>, retain_days: i64) -> Result<u64> {
         let w = window(retain_days);
         Ok(query!(
-            r#"DELETE FROM request_log WHERE ts < datetime('now', ?1)"#,
+            r#"DELETE FROM request_log WHERE ts < date('now', ?1)"#,
             w
         )
         .execute(executor)
@@ -945,6 +1256,9 @@ mod tests {
             duration_ms: 0,
             is_bot: is_bot(ua),
             challenged: false,
+            rate_limited: false,
+            visitor: None,
+            campaign: None,
         }
     }
 
@@ -1039,6 +1353,25 @@ mod tests {
         Ok(())
     }
 
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn rate_limited_counts_exclude_tolls(pool: SqlitePool) -> Result<()> {
+        let mut limited = entry("/login/finish_authentication", 429, Some("9.9.9.9"), None);
+        limited.rate_limited = true;
+        RequestLogDao::insert(&pool, &limited).await?;
+        RequestLogDao::insert(&pool, &limited).await?;
+        let mut tolled = entry("/", 429, Some("8.8.8.8"), None);
+        tolled.challenged = true;
+        RequestLogDao::insert(&pool, &tolled).await?;
+
+        let all = Window::custom(None, None);
+        let counts = RequestLogDao::rate_limited_counts(&pool, &all).await?;
+        assert_eq!((counts.requests, counts.ips), (2, 1), "the toll is not a rate limit");
+        let paths = RequestLogDao::rate_limited_paths(&pool, &all, 10).await?;
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].path, "/login/finish_authentication");
+        Ok(())
+    }
+
     #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
     async fn aggregates(pool: SqlitePool) -> Result<()> {
         seed(&pool).await?;
@@ -1061,6 +1394,49 @@ mod tests {
         Ok(())
     }
 
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn campaigns_round_trip_and_count(pool: SqlitePool) -> Result<()> {
+        let tagged = |source: &str, name: Option<&str>, ua: Option<&str>| {
+            let mut e = entry("/pages/Resume", 303, Some("1.2.3.4"), ua);
+            e.campaign = Some(Campaign {
+                source: source.to_string(),
+                medium: Some("social".to_string()),
+                name: name.map(String::from),
+            });
+            e
+        };
+        for e in [
+            tagged("linkedin", Some("job-hunt"), Some("Mozilla/5")),
+            tagged("linkedin", Some("job-hunt"), Some("Mozilla/5")),
+            tagged("linkedin", None, Some("curl/8")),
+            entry("/pages/Resume", 200, Some("1.2.3.4"), Some("Mozilla/5")),
+        ] {
+            RequestLogDao::insert(&pool, &e).await?;
+        }
+
+        let all = Window::last_days(1);
+        let counts = RequestLogDao::count_by_campaign(&pool, &all, Audience::All, 10).await?;
+        let got: Vec<_> = counts
+            .iter()
+            .map(|c| (c.source.as_str(), c.name.as_deref(), c.count))
+            .collect();
+        assert_eq!(
+            got,
+            [("linkedin", Some("job-hunt"), 2), ("linkedin", None, 1)],
+            "untagged rows aren't campaigns"
+        );
+        let humans = RequestLogDao::count_by_campaign(&pool, &all, Audience::Humans, 10).await?;
+        assert_eq!(humans.len(), 1, "the curl arrival is a bot");
+
+        let today = Utc::now().format("%Y-%m-%d").to_string();
+        let mut seen = RequestLogDao::campaigns_on(&pool, &today).await?;
+        seen.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[1].name.as_deref(), Some("job-hunt"));
+        assert_eq!(seen[1].medium.as_deref(), Some("social"));
+        Ok(())
+    }
+
     #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
     async fn window_bounds_exclude_outside_range(pool: SqlitePool) -> Result<()> {
         // A recent row (now) and an old row (100 days ago, via raw ts).
@@ -1261,6 +1637,9 @@ mod tests {
             duration_ms: 0,
             is_bot: is_bot(None),
             challenged: false,
+            rate_limited: false,
+            visitor: None,
+            campaign: None,
         };
         for e in [
             r(Some("https://news.ycombinator.com/")),
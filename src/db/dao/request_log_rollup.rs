@@ -0,0 +1,730 @@
+//! Daily rollups of `request_log` — the long tier of its retention. Raw rows live for
+//! [`RAW_RETAIN_DAYS`]; before a day ages out it's folded into per-day counts (by path,
+//! status bucket, audience, referer host and campaign, plus the day's distinct IPs), kept
+//! for [`ROLLUP_RETAIN_DAYS`]. The analytics dashboard reads these for the days the raw
+//! table no longer has, so a multi-year range still charts.
+//!
+//! The reads mirror their `RequestLogDao` namesakes over the same `Window` and audience, at
+//! day granularity: a day counts when any part of it falls in the window.
+//!
+//! [`RAW_RETAIN_DAYS`]: crate::db::dao::request_log::RAW_RETAIN_DAYS
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use sqlx::{SqliteExecutor, SqlitePool, query, query_as, query_scalar};
+use url::Url;
+
+use crate::db::dao::request_log::{
+    Audience, AudienceCounts, CampaignCount, DayCount, PathCount, RefererCount, StatusBucketCounts,
+    Window,
+};
+
+/// How long the daily rollups are kept — long enough to compare a year with the one
+/// before it.
+pub const ROLLUP_RETAIN_DAYS: i64 = 3 * 365;
+
+/// The host a referer is rolled up under: `None` for no referer (direct), `""` for one
+/// with no usable host.
+fn referer_host(referer: Option<&str>) -> Option<String> {
+    let referer = referer?;
+    Some(
+        Url::parse(referer.trim())
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default(),
+    )
+}
+
+pub struct RequestLogRollupDao;
+
+impl RequestLogRollupDao {
+    /// Roll up every completed UTC day not rolled up yet, oldest first. A day counts as
+    /// completed an hour after it ends, so the request-log writer's last batch is in.
+    /// Returns how many days were rolled up.
+    pub async fn roll_up(pool: &SqlitePool) -> Result<usize> {
+        let pending = query_scalar!(
+            r#"
+            SELECT DISTINCT substr(ts, 1, 10) as "day!: String"
+            FROM request_log
+            WHERE ts >= COALESCE((SELECT date(MAX(day), '+1 day') FROM request_log_rollup_days), '')
+              AND ts < date('now', '-1 hours')
+            ORDER BY 1
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+        for day in &pending {
+            Self::roll_up_day(pool, day).await?;
+        }
+        Ok(pending.len())
+    }
+
+    /// (Re)compute one day's rollups from the raw rows, in one transaction.
+    pub async fn roll_up_day(pool: &SqlitePool, day: &str) -> Result<()> {
+        let mut tx = pool.begin().await?;
+        query!("DELETE FROM request_log_daily_paths WHERE day = ?1", day)
+            .execute(&mut *tx)
+            .await?;
+        query!("DELETE FROM request_log_daily_referers WHERE day = ?1", day)
+            .execute(&mut *tx)
+            .await?;
+        query!("DELETE FROM request_log_daily_visitors WHERE day = ?1", day)
+            .execute(&mut *tx)
+            .await?;
+        query!(
+            "DELETE FROM request_log_daily_campaigns WHERE day = ?1",
+            day
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        query!(
+            r#"
+            INSERT INTO request_log_daily_paths (day, path, status_bucket, is_bot, challenged, requests)
+            SELECT ?1, path,
+                   CASE WHEN status IN (403, 404, 429) THEN status ELSE status / 100 * 100 END,
+                   is_bot, challenged, COUNT(*)
+            FROM request_log
+            WHERE ts >= ?1 AND ts < date(?1, '+1 day')
+            GROUP BY path,
+                     CASE WHEN status IN (403, 404, 429) THEN status ELSE status / 100 * 100 END,
+                     is_bot, challenged
+            "#,
+            day
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // The host is parsed here rather than in SQL, the same way the dashboard does it.
+        let referers = query!(
+            r#"
+            SELECT referer, is_bot, challenged, COUNT(*) as "requests!: i64"
+            FROM request_log
+            WHERE ts >= ?1 AND ts < date(?1, '+1 day')
+            GROUP BY referer, is_bot, challenged
+            "#,
+            day
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let mut by_host: HashMap<(Option<String>, Option<i64>, Option<i64>), i64> = HashMap::new();
+        for r in referers {
+            let key = (referer_host(r.referer.as_deref()), r.is_bot, r.challenged);
+            *by_host.entry(key).or_default() += r.requests;
+        }
+        for ((host, is_bot, challenged), requests) in by_host {
+            query!(
+                r#"
+                INSERT INTO request_log_daily_referers (day, referer_host, is_bot, challenged, requests)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                "#,
+                day,
+                host,
+                is_bot,
+                challenged,
+                requests
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        query!(
+            r#"
+            INSERT INTO request_log_daily_campaigns (day, source, medium, name, is_bot, challenged, requests)
+            SELECT ?1, campaign_source, campaign_medium, campaign_name, is_bot, challenged, COUNT(*)
+            FROM request_log
+            WHERE ts >= ?1 AND ts < date(?1, '+1 day') AND campaign_source IS NOT NULL
+            GROUP BY campaign_source, campaign_medium, campaign_name, is_bot, challenged
+            "#,
+            day
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        query!(
+            r#"
+            INSERT INTO request_log_daily_visitors (day, all_ips, human_ips, bot_ips, challenged_ips)
+            SELECT ?1,
+                   COUNT(DISTINCT COALESCE(visitor, ip)),
+                   COUNT(DISTINCT CASE WHEN is_bot = 0 THEN COALESCE(visitor, ip) END),
+                   COUNT(DISTINCT CASE WHEN is_bot = 1 THEN COALESCE(visitor, ip) END),
+                   COUNT(DISTINCT CASE WHEN challenged = 1 THEN COALESCE(visitor, ip) END)
+            FROM request_log
+            WHERE ts >= ?1 AND ts < date(?1, '+1 day')
+            "#,
+            day
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        query!(
+            r#"
+            INSERT INTO request_log_rollup_days (day) VALUES (?1)
+            ON CONFLICT(day) DO UPDATE SET rolled_at = CURRENT_TIMESTAMP
+            "#,
+            day
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// The first day the raw table still holds (today, if it's empty): the dashboard reads
+    /// rollups for the days before it, raw rows from it on.
+    pub async fn raw_start(executor: impl SqliteExecutor<'_>) -> Result<String> {
+        Ok(query_scalar!(
+            r#"SELECT COALESCE(substr(MIN(ts), 1, 10), date('now')) as "day!: String" FROM request_log"#
+        )
+        .fetch_one(executor)
+        .await?)
+    }
+
+    /// Drop rollups for days more than `retain_days` ago. Returns the number of days dropped.
+    pub async fn prune_before(pool: &SqlitePool, retain_days: i64) -> Result<u64> {
+        let modifier = format!("-{} days", retain_days.max(0));
+        let mut tx = pool.begin().await?;
+        query!(
+            "DELETE FROM request_log_daily_paths WHERE day < date('now', ?1)",
+            modifier
+        )
+        .execute(&mut *tx)
+        .await?;
+        query!(
+            "DELETE FROM request_log_daily_referers WHERE day < date('now', ?1)",
+            modifier
+        )
+        .execute(&mut *tx)
+        .await?;
+        query!(
+            "DELETE FROM request_log_daily_visitors WHERE day < date('now', ?1)",
+            modifier
+        )
+        .execute(&mut *tx)
+        .await?;
+        query!(
+            "DELETE FROM request_log_daily_campaigns WHERE day < date('now', ?1)",
+            modifier
+        )
+        .execute(&mut *tx)
+        .await?;
+        let days = query!(
+            "DELETE FROM request_log_rollup_days WHERE day < date('now', ?1)",
+            modifier
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        tx.commit().await?;
+        Ok(days)
+    }
+
+    pub async fn count_since(
+        executor: impl SqliteExecutor<'_>,
+        w: &Window,
+        audience: Audience,
+    ) -> Result<i64> {
+        let bot = audience.as_bot_filter();
+        let ch = audience.as_challenged_filter();
+        Ok(query_scalar!(
+            r#"
+            SELECT COALESCE(SUM(requests), 0) as "count!: i64"
+            FROM request_log_daily_paths
+            WHERE day >= substr(?1, 1, 10) AND day || ' 00:00:00' < ?2
+              AND (?3 IS NULL OR is_bot = ?3) AND (?4 IS NULL OR challenged = ?4)
+            "#,
+            w.from,
+            w.to,
+            bot,
+            ch
+        )
+        .fetch_one(executor)
+        .await?)
+    }
+
+    pub async fn audience_counts(
+        executor: impl SqliteExecutor<'_>,
+        w: &Window,
+    ) -> Result<AudienceCounts> {
+        Ok(query_as!(
+            AudienceCounts,
+            r#"
+            SELECT
+                COALESCE(SUM(requests), 0) as "all!: i64",
+                COALESCE(SUM(CASE WHEN is_bot = 0 THEN requests END), 0) as "humans!: i64",
+                COALESCE(SUM(CASE WHEN is_bot = 1 THEN requests END), 0) as "bots!: i64"
+            FROM request_log_daily_paths
+            WHERE day >= substr(?1, 1, 10) AND day || ' 00:00:00' < ?2
+            "#,
+            w.from,
+            w.to
+        )
+        .fetch_one(executor)
+        .await?)
+    }
+
+    pub async fn count_by_day(
+        executor: impl SqliteExecutor<'_>,
+        w: &Window,
+        audience: Audience,
+    ) -> Result<Vec<DayCount>> {
+        let bot = audience.as_bot_filter();
+        let ch = audience.as_challenged_filter();
+        Ok(query_as!(
+            DayCount,
+            r#"
+            SELECT day as "day!: String", SUM(requests) as "count!: i64"
+            FROM request_log_daily_paths
+            WHERE day >= substr(?1, 1, 10) AND day || ' 00:00:00' < ?2
+              AND (?3 IS NULL OR is_bot = ?3) AND (?4 IS NULL OR challenged = ?4)
+            GROUP BY day
+            ORDER BY day ASC
+            "#,
+            w.from,
+            w.to,
+            bot,
+            ch
+        )
+        .fetch_all(executor)
+        .await?)
+    }
+
+    /// Unique visitors per day, from the counts taken at rollup time. Days with none in
+    /// the audience are left out, as in the raw query.
+    pub async fn distinct_ip_by_day(
+        executor: impl SqliteExecutor<'_>,
+        w: &Window,
+        audience: Audience,
+    ) -> Result<Vec<DayCount>> {
+        let tag = audience.as_tag();
+        Ok(query_as!(
+            DayCount,
+            r#"
+            SELECT day as "day!: String", ips as "count!: i64"
+            FROM (
+                SELECT day,
+                       CASE ?3 WHEN 'humans' THEN human_ips
+                               WHEN 'bots' THEN bot_ips
+                               WHEN 'challenged' THEN challenged_ips
+                               ELSE all_ips END AS ips
+                FROM request_log_daily_visitors
+                WHERE day >= substr(?1, 1, 10) AND day || ' 00:00:00' < ?2
+            )
+            WHERE ips > 0
+            ORDER BY day ASC
+            "#,
+            w.from,
+            w.to,
+            tag
+        )
+        .fetch_all(executor)
+        .await?)
+    }
+
+    /// Top paths, as `RequestLogDao::count_by_content_path` — `max_status` compares against
+    /// the status bucket, which gives the same answer for its two ceilings (400, 10000).
+    /// Keep the static exclusions in step with that query's.
+    pub async fn count_by_content_path(
+        executor: impl SqliteExecutor<'_>,
+        w: &Window,
+        audience: Audience,
+        max_status: i64,
+        limit: i64,
+    ) -> Result<Vec<PathCount>> {
+        let bot = audience.as_bot_filter();
+        let ch = audience.as_challenged_filter();
+        Ok(query_as!(
+            PathCount,
+            r#"
+            SELECT path as "path!: String", SUM(requests) as "count!: i64"
+            FROM request_log_daily_paths
+            WHERE day >= substr(?1, 1, 10) AND day || ' 00:00:00' < ?2
+              AND status_bucket < ?3
+              AND (?4 IS NULL OR is_bot = ?4)
+              AND (?6 IS NULL OR challenged = ?6)
+              AND path NOT LIKE '/styles%'
+              AND path NOT LIKE '/vendor%'
+              AND path NOT LIKE '/scripts%'
+              AND path NOT LIKE '/images%'
+              AND path NOT LIKE '/attachments%'
+              AND path NOT LIKE '/diagram%'
+              AND path NOT IN ('/favicon.ico', '/manifest.webmanifest', '/robots.txt', '/apple-touch-icon.png')
+            GROUP BY path
+            ORDER BY SUM(requests) DESC, path ASC
+            LIMIT ?5
+            "#,
+            w.from,
+            w.to,
+            max_status,
+            bot,
+            limit,
+            ch
+        )
+        .fetch_all(executor)
+        .await?)
+    }
+
+    pub async fn count_by_status_bucket(
+        executor: impl SqliteExecutor<'_>,
+        w: &Window,
+        audience: Audience,
+    ) -> Result<StatusBucketCounts> {
+        let bot = audience.as_bot_filter();
+        let ch = audience.as_challenged_filter();
+        Ok(query_as!(
+            StatusBucketCounts,
+            r#"
+            SELECT
+                COALESCE(SUM(CASE WHEN status_bucket = 200 THEN requests END), 0) as "s2xx!: i64",
+                COALESCE(SUM(CASE WHEN status_bucket = 300 THEN requests END), 0) as "s3xx!: i64",
+                COALESCE(SUM(CASE WHEN status_bucket = 403 THEN requests END), 0) as "s403!: i64",
+                COALESCE(SUM(CASE WHEN status_bucket = 404 THEN requests END), 0) as "s404!: i64",
+                COALESCE(SUM(CASE WHEN status_bucket = 429 THEN requests END), 0) as "s429!: i64",
+                COALESCE(SUM(CASE WHEN status_bucket = 400 THEN requests END), 0) as "s4xx!: i64",
+                COALESCE(SUM(CASE WHEN status_bucket = 500 THEN requests END), 0) as "s5xx!: i64"
+            FROM request_log_daily_paths
+            WHERE day >= substr(?1, 1, 10) AND day || ' 00:00:00' < ?2
+              AND (?3 IS NULL OR is_bot = ?3) AND (?4 IS NULL OR challenged = ?4)
+            "#,
+            w.from,
+            w.to,
+            bot,
+            ch
+        )
+        .fetch_one(executor)
+        .await?)
+    }
+
+    /// Referer counts per host, as stand-in URLs (`http://host/`) so they fold through
+    /// `group_referers` alongside the raw ones and land in the same groups.
+    pub async fn referer_urls_since(
+        executor: impl SqliteExecutor<'_>,
+        w: &Window,
+    ) -> Result<Vec<RefererCount>> {
+        let rows = query!(
+            r#"
+            SELECT referer_host as "host!: String", SUM(requests) as "count!: i64"
+            FROM request_log_daily_referers
+            WHERE day >= substr(?1, 1, 10) AND day || ' 00:00:00' < ?2
+              AND referer_host IS NOT NULL
+            GROUP BY referer_host
+            ORDER BY SUM(requests) DESC, referer_host ASC
+            "#,
+            w.from,
+            w.to
+        )
+        .fetch_all(executor)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| RefererCount {
+                referer: if r.host.is_empty() {
+                    String::new()
+                } else {
+                    format!("http://{}/", r.host)
+                },
+                count: r.count,
+            })
+            .collect())
+    }
+
+    pub async fn count_by_campaign(
+        executor: impl SqliteExecutor<'_>,
+        w: &Window,
+        audience: Audience,
+        limit: i64,
+    ) -> Result<Vec<CampaignCount>> {
+        let bot = audience.as_bot_filter();
+        let ch = audience.as_challenged_filter();
+        Ok(query_as!(
+            CampaignCount,
+            r#"
+            SELECT source, medium, name, SUM(requests) as "count!: i64"
+            FROM request_log_daily_campaigns
+            WHERE day >= substr(?1, 1, 10) AND day || ' 00:00:00' < ?2
+              AND (?3 IS NULL OR is_bot = ?3) AND (?4 IS NULL OR challenged = ?4)
+            GROUP BY source, medium, name
+            ORDER BY SUM(requests) DESC, source ASC, medium ASC, name ASC
+            LIMIT ?5
+            "#,
+            w.from,
+            w.to,
+            bot,
+            ch,
+            limit
+        )
+        .fetch_all(executor)
+        .await?)
+    }
+
+    pub async fn direct_referer_count(
+        executor: impl SqliteExecutor<'_>,
+        w: &Window,
+    ) -> Result<i64> {
+        Ok(query_scalar!(
+            r#"
+            SELECT COALESCE(SUM(requests), 0) as "count!: i64"
+            FROM request_log_daily_referers
+            WHERE day >= substr(?1, 1, 10) AND day || ' 00:00:00' < ?2
+              AND referer_host IS NULL
+            "#,
+            w.from,
+            w.to
+        )
+        .fetch_one(executor)
+        .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::dao::request_log::RequestLogDao;
+    use crate::web::util::referer::group_referers;
+
+    /// One row at noon, `days_ago` UTC days back.
+    #[allow(clippy::too_many_arguments)]
+    async fn log(
+        pool: &SqlitePool,
+        days_ago: i64,
+        path: &str,
+        status: i64,
+        ip: Option<&str>,
+        referer: Option<&str>,
+        is_bot: bool,
+        challenged: bool,
+    ) -> Result<()> {
+        let modifier = format!("-{days_ago} days");
+        query!(
+            r#"
+            INSERT INTO request_log (ts, method, path, status, ip, referer, is_bot, challenged)
+            VALUES (datetime('now', ?1, 'start of day', '+12 hours'), 'GET', ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+            modifier,
+            path,
+            status,
+            ip,
+            referer,
+            is_bot,
+            challenged
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn seed(pool: &SqlitePool) -> Result<()> {
+        let hn = Some("https://news.ycombinator.com/item?id=1");
+        for (days_ago, path, status, ip, referer, bot, ch) in [
+            (5, "/", 200, Some("1.1.1.1"), hn, false, false),
+            (5, "/", 200, Some("1.1.1.1"), None, false, false),
+            (
+                5,
+                "/blog/hello",
+                200,
+                Some("2.2.2.2"),
+                Some("https://www.google.com/"),
+                false,
+                false,
+            ),
+            (5, "/wp-login.php", 404, Some("9.9.9.9"), None, true, false),
+            (
+                5,
+                "/styles/main.css",
+                200,
+                Some("1.1.1.1"),
+                Some("https://hotchkiss.io/"),
+                false,
+                false,
+            ),
+            (
+                4,
+                "/",
+                200,
+                Some("2.2.2.2"),
+                Some("http://45.33.12.9/"),
+                false,
+                false,
+            ),
+            (4, "/", 429, Some("9.9.9.9"), None, true, true),
+            (
+                4,
+                "/admin",
+                403,
+                Some("9.9.9.9"),
+                Some("not a url"),
+                true,
+                false,
+            ),
+            (4, "/login", 302, None, Some(""), true, false),
+            (2, "/blog/hello", 200, Some("3.3.3.3"), hn, false, false),
+            (2, "/.env", 404, Some("8.8.8.8"), None, true, false),
+            (2, "/api/x", 500, Some("3.3.3.3"), None, false, false),
+            (2, "/login", 401, Some("3.3.3.3"), None, false, false),
+        ] {
+            log(pool, days_ago, path, status, ip, referer, bot, ch).await?;
+        }
+        // Tagged arrivals, across days and audiences.
+        query!(
+            r#"
+            UPDATE request_log SET campaign_source = 'linkedin', campaign_medium = 'social'
+            WHERE path IN ('/blog/hello', '/wp-login.php')
+            "#
+        )
+        .execute(pool)
+        .await?;
+        query!("UPDATE request_log SET campaign_source = 'resume-pdf' WHERE status = 429")
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    fn campaigns(rows: &[CampaignCount]) -> Vec<(String, Option<String>, i64)> {
+        rows.iter()
+            .map(|c| (c.source.clone(), c.medium.clone(), c.count))
+            .collect()
+    }
+
+    fn pairs(days: &[DayCount]) -> Vec<(String, i64)> {
+        days.iter().map(|d| (d.day.clone(), d.count)).collect()
+    }
+
+    fn paths(rows: &[PathCount]) -> Vec<(String, i64)> {
+        rows.iter().map(|p| (p.path.clone(), p.count)).collect()
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn rollups_match_raw_aggregation(pool: SqlitePool) -> Result<()> {
+        seed(&pool).await?;
+        assert_eq!(RequestLogRollupDao::roll_up(&pool).await?, 3);
+        let w = Window::last_days(30);
+
+        for audience in [
+            Audience::All,
+            Audience::Humans,
+            Audience::Bots,
+            Audience::Challenged,
+        ] {
+            assert_eq!(
+                RequestLogRollupDao::count_since(&pool, &w, audience).await?,
+                RequestLogDao::count_since(&pool, &w, audience).await?,
+                "{audience:?}"
+            );
+            assert_eq!(
+                pairs(&RequestLogRollupDao::count_by_day(&pool, &w, audience).await?),
+                pairs(&RequestLogDao::count_by_day(&pool, &w, audience).await?),
+                "{audience:?}"
+            );
+            assert_eq!(
+                pairs(&RequestLogRollupDao::distinct_ip_by_day(&pool, &w, audience).await?),
+                pairs(&RequestLogDao::distinct_ip_by_day(&pool, &w, audience).await?),
+                "{audience:?}"
+            );
+            for max_status in [400, 10_000] {
+                assert_eq!(
+                    paths(
+                        &RequestLogRollupDao::count_by_content_path(
+                            &pool, &w, audience, max_status, 25
+                        )
+                        .await?
+                    ),
+                    paths(
+                        &RequestLogDao::count_by_content_path(&pool, &w, audience, max_status, 25)
+                            .await?
+                    ),
+                    "{audience:?} < {max_status}"
+                );
+            }
+            assert_eq!(
+                campaigns(&RequestLogRollupDao::count_by_campaign(&pool, &w, audience, 25).await?),
+                campaigns(&RequestLogDao::count_by_campaign(&pool, &w, audience, 25).await?),
+                "{audience:?}"
+            );
+            let rolled = RequestLogRollupDao::count_by_status_bucket(&pool, &w, audience).await?;
+            let raw = RequestLogDao::count_by_status_bucket(&pool, &w, audience).await?;
+            assert_eq!(
+                [
+                    rolled.s2xx,
+                    rolled.s3xx,
+                    rolled.s403,
+                    rolled.s404,
+                    rolled.s429,
+                    rolled.s4xx,
+                    rolled.s5xx
+                ],
+                [
+                    raw.s2xx, raw.s3xx, raw.s403, raw.s404, raw.s429, raw.s4xx, raw.s5xx
+                ],
+                "{audience:?}"
+            );
+        }
+
+        let rolled = RequestLogRollupDao::audience_counts(&pool, &w).await?;
+        let raw = RequestLogDao::audience_counts(&pool, &w).await?;
+        assert_eq!(
+            (rolled.all, rolled.humans, rolled.bots),
+            (raw.all, raw.humans, raw.bots)
+        );
+
+        let rolled = group_referers(
+            &RequestLogRollupDao::referer_urls_since(&pool, &w).await?,
+            "hotchkiss.io",
+            RequestLogRollupDao::direct_referer_count(&pool, &w).await?,
+        );
+        let raw = group_referers(
+            &RequestLogDao::referer_urls_since(&pool, &w).await?,
+            "hotchkiss.io",
+            RequestLogDao::direct_referer_count(&pool, &w).await?,
+        );
+        let external = |g: &crate::web::util::referer::GroupedReferers| {
+            g.top_external
+                .iter()
+                .map(|e| (e.host.clone(), e.count))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(external(&rolled), external(&raw));
+        assert_eq!(rolled.noise_count, raw.noise_count);
+        assert_eq!(rolled.direct_count, raw.direct_count);
+        assert_eq!(raw.direct_count, 6);
+        Ok(())
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn rolls_each_completed_day_once(pool: SqlitePool) -> Result<()> {
+        seed(&pool).await?;
+        log(&pool, 0, "/today", 200, None, None, false, false).await?;
+
+        assert_eq!(
+            RequestLogRollupDao::roll_up(&pool).await?,
+            3,
+            "today isn't over yet"
+        );
+        assert_eq!(RequestLogRollupDao::roll_up(&pool).await?, 0, "nothing new");
+
+        // The raw rows go; the rollups still answer for those days, and the dashboard's
+        // split point moves to today.
+        query!("DELETE FROM request_log WHERE path != '/today'")
+            .execute(&pool)
+            .await?;
+        let w = Window::last_days(30);
+        assert_eq!(
+            RequestLogRollupDao::count_since(&pool, &w, Audience::All).await?,
+            13
+        );
+        let today = RequestLogRollupDao::raw_start(&pool).await?;
+        let (rolled, raw) = w.split_at(&today);
+        assert_eq!(
+            RequestLogRollupDao::count_since(&pool, &rolled, Audience::All).await?
+                + RequestLogDao::count_since(&pool, &raw, Audience::All).await?,
+            14
+        );
+
+        assert_eq!(RequestLogRollupDao::prune_before(&pool, 3).await?, 2);
+        assert_eq!(
+            RequestLogRollupDao::count_since(&pool, &w, Audience::All).await?,
+            4
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use sqlx::types::chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// A `user_sessions` row older than this (by `last_seen_at`) belongs to a session
+/// that has certainly expired: the store's `OnInactivity(1 day)` expiry plus the
+/// hourly touch grain, with a day's slack.
+pub const STALE_AFTER_DAYS: i64 = 2;
+
+/// One authenticated cookie session, as the account and admin pages list it. The
+/// session itself lives in the tower-sessions store; this is the per-user index
+/// over it (see migration 0035).
+pub struct UserSessionDao {
+    pub session_id: String,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
+
+impl UserSessionDao {
+    /// Record activity on a session: inserts on first sight, otherwise bumps
+    /// `last_seen_at` and refreshes the UA/IP (a phone roams networks).
+    pub async fn touch(
+        pool: &SqlitePool,
+        session_id: &str,
+        user_id: &Uuid,
+        user_agent: Option<&str>,
+        ip: Option<&str>,
+    ) -> Result<()> {
+        let uid = user_id.to_string();
+        let now = Utc::now();
+        sqlx::query!(
+            r#"INSERT INTO user_sessions (session_id, user_id, created_at, last_seen_at, user_agent, ip)
+               VALUES (?1, ?2, ?3, ?3, ?4, ?5)
+               ON CONFLICT(session_id) DO UPDATE SET
+                   last_seen_at = excluded.last_seen_at,
+                   user_agent = COALESCE(excluded.user_agent, user_agent),
+                   ip = COALESCE(excluded.ip, ip)"#,
+            session_id,
+            uid,
+            now,
+            user_agent,
+            ip,
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// A user's sessions, most recently active first. May include sessions the
+    /// store has already dropped — the caller checks each against the store.
+    pub async fn list_for_user(pool: &SqlitePool, user_id: &Uuid) -> Result<Vec<UserSessionDao>> {
+        let uid = user_id.to_string();
+        Ok(sqlx::query_as!(
+            UserSessionDao,
+            r#"SELECT session_id as "session_id!",
+                      created_at as "created_at!: DateTime<Utc>",
+                      last_seen_at as "last_seen_at!: DateTime<Utc>",
+                      user_agent, ip
+               FROM user_sessions WHERE user_id = ?1
+               ORDER BY last_seen_at DESC"#,
+            uid,
+        )
+        .fetch_all(pool)
+        .await?)
+    }
+
+    /// Session counts per user id (string form), for the admin user list.
+    pub async fn counts_by_user(pool: &SqlitePool) -> Result<Vec<(String, i64)>> {
+        Ok(sqlx::query!(
+            r#"SELECT user_id, COUNT(*) as "n!: i64" FROM user_sessions GROUP BY user_id"#
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|r| (r.user_id, r.n))
+        .collect())
+    }
+
+    /// Whether `session_id` is one of `user_id`'s — the ownership check before a
+    /// revoke, so nobody can kill a session that isn't theirs.
+    pub async fn belongs_to(pool: &SqlitePool, session_id: &str, user_id: &Uuid) -> Result<bool> {
+        let uid = user_id.to_string();
+        Ok(sqlx::query!(
+            r#"SELECT 1 as "x!: i64" FROM user_sessions WHERE session_id = ?1 AND user_id = ?2"#,
+            session_id,
+            uid,
+        )
+        .fetch_optional(pool)
+        .await?
+        .is_some())
+    }
+
+    /// Drop one row (the caller deletes the store record).
+    pub async fn delete(pool: &SqlitePool, session_id: &str) -> Result<()> {
+        sqlx::query!(r#"DELETE FROM user_sessions WHERE session_id = ?1"#, session_id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Sweep rows whose session has certainly expired ([`STALE_AFTER_DAYS`]).
+    pub async fn prune_stale(pool: &SqlitePool) -> Result<u64> {
+        // Timestamp math, not chrono::Duration (the CT gotcha).
+        let now = Utc::now();
+        let cutoff_secs = now.timestamp() - STALE_AFTER_DAYS * 86_400;
+        let cutoff = DateTime::<Utc>::from_timestamp(cutoff_secs, 0).unwrap_or(now);
+        let res = sqlx::query!(r#"DELETE FROM user_sessions WHERE last_seen_at < ?1"#, cutoff)
+            .execute(pool)
+            .await?;
+        Ok(res.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::dao::roles::Role;
+    use crate::db::dao::users::UserDao;
+
+    async fn seed_user(pool: &SqlitePool, name: &str) -> Result<UserDao> {
+        let mut user = UserDao {
+            display_name: name.to_string(),
+            id: Uuid::now_v7(),
+            keys: sqlx::types::Json(vec![]),
+            role: Role::Registered,
+        };
+        user.create(pool).await?;
+        Ok(user)
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn touch_list_ownership_and_cascade(pool: SqlitePool) -> Result<()> {
+        let alice = seed_user(&pool, "alice").await?;
+        let bob = seed_user(&pool, "bob").await?;
+        UserSessionDao::touch(&pool, "s1", &alice.id, Some("Firefox"), Some("10.0.0.1")).await?;
+        UserSessionDao::touch(&pool, "s2", &alice.id, None, None).await?;
+        UserSessionDao::touch(&pool, "s3", &bob.id, None, None).await?;
+
+        // A re-touch without UA/IP keeps what was known.
+        UserSessionDao::touch(&pool, "s1", &alice.id, None, Some("10.0.0.2")).await?;
+        let rows = UserSessionDao::list_for_user(&pool, &alice.id).await?;
+        assert_eq!(rows.len(), 2);
+        let s1 = rows.iter().find(|r| r.session_id == "s1").unwrap();
+        assert_eq!(s1.user_agent.as_deref(), Some("Firefox"));
+        assert_eq!(s1.ip.as_deref(), Some("10.0.0.2"));
+        assert!(s1.last_seen_at >= s1.created_at);
+
+        assert!(UserSessionDao::belongs_to(&pool, "s1", &alice.id).await?);
+        assert!(!UserSessionDao::belongs_to(&pool, "s3", &alice.id).await?);
+
+        let counts = UserSessionDao::counts_by_user(&pool).await?;
+        assert!(counts.contains(&(alice.id.to_string(), 2)));
+
+        UserSessionDao::delete(&pool, "s2").await?;
+        assert_eq!(UserSessionDao::list_for_user(&pool, &alice.id).await?.len(), 1);
+        // Nothing is stale yet.
+        assert_eq!(UserSessionDao::prune_stale(&pool).await?, 0);
+
+        UserDao::delete(&pool, &alice.id).await?;
+        assert!(UserSessionDao::list_for_user(&pool, &alice.id).await?.is_empty());
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use super::roles::Role;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use sqlx::{query, query_as, FromRow, SqlitePool};
+use sqlx::{query, query_as, FromRow, SqliteConnection, SqliteExecutor, SqlitePool};
 use std::fmt::Display;
 use uuid::Uuid;
 use webauthn_rs::prelude::Passkey;
@@ -24,6 +24,16 @@ pub struct UserSummary {
     pub api_key_count: i64,
 }
 
+/// What [`UserDao::remove_key`] did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyRemoval {
+    Removed,
+    /// The user has no such credential.
+    NotFound,
+    /// It's the user's only one — left in place.
+    LastKey,
+}
+
 impl UserDao {
     pub async fn create(&mut self, pool: &SqlitePool) -> Result<()> {
         let id = self.id.to_string();
@@ -106,7 +116,10 @@ impl UserDao {
         .await?)
     }
 
-    pub async fn find_by_uuid(pool: &SqlitePool, uuid: &Uuid) -> Result<Option<UserDao>> {
+    pub async fn find_by_uuid(
+        executor: impl SqliteExecutor<'_>,
+        uuid: &Uuid,
+    ) -> Result<Option<UserDao>> {
         let temp_uuid = uuid.to_string();
         Ok(query_as!(
             UserDao,
@@ -122,7 +135,7 @@ impl UserDao {
     "#,
             temp_uuid
         )
-        .fetch_optional(pool)
+        .fetch_optional(executor)
         .await?)
     }
 
@@ -152,6 +165,78 @@ impl UserDao {
         Ok(())
     }
 
+    /// Append `passkey` to the user's `keys` in one statement — no read-modify-write, so a
+    /// passkey added or removed concurrently isn't lost. Returns whether the user exists.
+    pub async fn add_key(
+        executor: impl SqliteExecutor<'_>,
+        id: &Uuid,
+        passkey: &Passkey,
+    ) -> Result<bool> {
+        let id = id.to_string();
+        let passkey = serde_json::to_string(passkey)?;
+        let res = query!(
+            "UPDATE users SET keys = json_insert(keys, '$[#]', json(?1)) WHERE id = ?2",
+            passkey,
+            id
+        )
+        .execute(executor)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    /// Whether the credential `cred_id` (base64url, as `passkeys::cred_id_key` makes it) is
+    /// one of the user's — read from the row, not a session's cached copy.
+    pub async fn has_key(
+        executor: impl SqliteExecutor<'_>,
+        id: &Uuid,
+        cred_id: &str,
+    ) -> Result<bool> {
+        let id = id.to_string();
+        Ok(query!(
+            r#"SELECT EXISTS (
+                   SELECT 1 FROM users u, json_each(u.keys) k
+                   WHERE u.id = ?1 AND json_extract(k.value, '$.cred.cred_id') = ?2
+               ) as "found!: bool""#,
+            id,
+            cred_id
+        )
+        .fetch_one(executor)
+        .await?
+        .found)
+    }
+
+    /// Remove the credential `cred_id` from the user's `keys` — unless it's their last one,
+    /// checked in the same UPDATE, so two concurrent removals can't both pass the check and
+    /// leave the account with no way to sign in.
+    pub async fn remove_key(
+        conn: &mut SqliteConnection,
+        id: &Uuid,
+        cred_id: &str,
+    ) -> Result<KeyRemoval> {
+        let uid = id.to_string();
+        let res = query!(
+            r#"UPDATE users SET keys = (
+                   SELECT json_group_array(json(k.value)) FROM json_each(users.keys) k
+                   WHERE json_extract(k.value, '$.cred.cred_id') IS NOT ?2
+               )
+               WHERE id = ?1
+                 AND json_array_length(keys) > 1
+                 AND EXISTS (SELECT 1 FROM json_each(users.keys) k
+                             WHERE json_extract(k.value, '$.cred.cred_id') = ?2)"#,
+            uid,
+            cred_id
+        )
+        .execute(&mut *conn)
+        .await?;
+        if res.rows_affected() > 0 {
+            Ok(KeyRemoval::Removed)
+        } else if Self::has_key(&mut *conn, id, cred_id).await? {
+            Ok(KeyRemoval::LastKey)
+        } else {
+            Ok(KeyRemoval::NotFound)
+        }
+    }
+
     /// Every user as a lightweight summary for the admin list — role plus passkey
     /// count (the `keys` JSON array length) and live (non-revoked) API-key count.
     /// No passkey blobs.
@@ -413,4 +498,35 @@ mod tests {
 
         Ok(())
     }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn keys_are_added_and_removed_in_place(pool: SqlitePool) -> Result<()> {
+        // The sample, and a copy under another credential id.
+        let a = "Abr4cz81v7rNJR7OnKUJeB297HaWkpwUeEPAWAGTkAWA62e0fw20tf6LDL6CWmsZ3yVse9Yw1tpXpNLK5q7e2Po";
+        let b = &a.replace("Abr4", "Zzz9");
+        let first: Passkey = serde_json::from_str(SAMPLE_PASSKEY)?;
+        let second: Passkey = serde_json::from_str(&SAMPLE_PASSKEY.replace(a, b))?;
+
+        let mut u = UserDao {
+            display_name: "somebody".to_string(),
+            id: Uuid::new_v4(),
+            keys: sqlx::types::Json(vec![first]),
+            role: Role::Registered,
+        };
+        u.create(&pool).await?;
+        assert!(UserDao::add_key(&pool, &u.id, &second).await?);
+        assert!(!UserDao::add_key(&pool, &Uuid::new_v4(), &second).await?, "no such user");
+        assert!(UserDao::has_key(&pool, &u.id, b).await?);
+        assert!(!UserDao::has_key(&pool, &Uuid::new_v4(), b).await?, "scoped to the user");
+
+        let mut conn = pool.acquire().await?;
+        assert_eq!(UserDao::remove_key(&mut conn, &u.id, "nope").await?, KeyRemoval::NotFound);
+        assert_eq!(UserDao::remove_key(&mut conn, &u.id, a).await?, KeyRemoval::Removed);
+        assert_eq!(UserDao::remove_key(&mut conn, &u.id, b).await?, KeyRemoval::LastKey);
+
+        let found = UserDao::find_by_uuid(&pool, &u.id).await?.unwrap();
+        assert_eq!(found.keys.len(), 1);
+        assert!(UserDao::has_key(&pool, &u.id, b).await?, "the last key stays");
+        Ok(())
+    }
 }
@@ -0,0 +1,25 @@
+-- API-key lifecycle: an optional expiry set at mint, a rotate action that issues a
+-- successor and closes the old key after an overlap window, and a per-request usage
+-- trail. `expires_at` NULL = lives until revoked (the Phase CA behavior). An expired
+-- key stops authenticating at once (authenticate filters on it); the hourly
+-- coordinator sweep then stamps `revoked_at` so it ages out through the normal
+-- revoked-key purge. `rotated_from` links a successor to the key it replaced, so the
+-- admin page can show the pair during the overlap.
+ALTER TABLE api_keys ADD COLUMN expires_at TEXT;
+ALTER TABLE api_keys ADD COLUMN rotated_from INTEGER REFERENCES api_keys (id) ON DELETE SET NULL;
+
+-- One row per request authenticated by a key (method/path/status/ip). Written
+-- fire-and-forget from the api_key_auth middleware, pruned on the request_log
+-- retention window, and cascade-deleted with its key by the revoked-key purge.
+CREATE TABLE IF NOT EXISTS api_key_usage (
+    id      INTEGER PRIMARY KEY,
+    key_id  INTEGER NOT NULL REFERENCES api_keys (id) ON DELETE CASCADE,
+    ts      TEXT    NOT NULL,
+    method  TEXT    NOT NULL,
+    path    TEXT    NOT NULL,
+    status  INTEGER NOT NULL,
+    ip      TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_key_usage_key_ts ON api_key_usage (key_id, ts);
+CREATE INDEX IF NOT EXISTS idx_api_key_usage_ts ON api_key_usage (ts);
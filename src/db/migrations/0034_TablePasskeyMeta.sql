@@ -0,0 +1,23 @@
+-- Self-service passkey management (/account): the human-facing metadata for each
+-- credential in `users.keys`. The passkey blobs stay where they are (the JSON
+-- vector the WebAuthn ceremonies read); this table keys on the credential id
+-- (base64url, no padding — the same encoding the serialized `Passkey` carries at
+-- `$.cred.cred_id`) and adds a user-chosen label, when it was registered, and when
+-- it last completed an authentication ceremony.
+CREATE TABLE IF NOT EXISTS passkey_meta (
+    cred_id      TEXT PRIMARY KEY,
+    user_id      TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    label        TEXT NOT NULL,
+    created_at   TEXT,
+    last_used_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_passkey_meta_user ON passkey_meta (user_id);
+
+-- Backfill every credential registered before this table existed. Their creation
+-- date is unknown (NULL renders as "before tracking"); the label is a placeholder
+-- the user can rename.
+INSERT OR IGNORE INTO passkey_meta (cred_id, user_id, label)
+SELECT json_extract(k.value, '$.cred.cred_id'), u.id, 'Passkey'
+FROM users u, json_each(u.keys) k
+WHERE json_extract(k.value, '$.cred.cred_id') IS NOT NULL;
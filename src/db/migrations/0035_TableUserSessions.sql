@@ -0,0 +1,20 @@
+-- A per-user index over the cookie sessions. The session store (tower_sessions)
+-- keeps each session as an opaque blob keyed by id, so "which sessions does this
+-- user have?" can't be asked of it. One row here per authenticated session, keyed
+-- by the store's session id: written by the `refresh_session_role` middleware when
+-- it first sees a session after login and again on its hourly touch (so
+-- `last_seen_at` is accurate to the hour, the same grain as the expiry). Revoking
+-- deletes the store record AND this row; rows outliving their session (expired,
+-- logged out) are filtered against the store when listed and swept by the hourly
+-- session GC.
+CREATE TABLE IF NOT EXISTS user_sessions (
+    session_id   TEXT PRIMARY KEY NOT NULL,
+    user_id      TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at   TEXT NOT NULL,
+    last_seen_at TEXT NOT NULL,
+    user_agent   TEXT,
+    ip           TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_sessions_user ON user_sessions (user_id);
+CREATE INDEX IF NOT EXISTS idx_user_sessions_last_seen ON user_sessions (last_seen_at);
@@ -0,0 +1,18 @@
+-- Admin-minted invite links: single-use, expiring, each carrying the role the new
+-- user lands at (e.g. Family) so nobody has to register as Registered and wait to
+-- be promoted. Only the HMAC hash of the token is stored (the API-key pepper,
+-- crypto_keys id 3); the plaintext is shown once, in the link. Redeemed by the
+-- registration ceremony: start_registration checks it, finish_registration claims
+-- it atomically (`redeemed_at IS NULL` in the UPDATE) after the user row exists.
+CREATE TABLE IF NOT EXISTS invites (
+    id          INTEGER PRIMARY KEY,
+    token_hash  TEXT    NOT NULL UNIQUE,
+    role        TEXT    NOT NULL,
+    note        TEXT    NOT NULL DEFAULT '',
+    created_by  TEXT REFERENCES users (id) ON DELETE SET NULL,
+    created_at  TEXT    NOT NULL,
+    expires_at  TEXT    NOT NULL,
+    redeemed_at TEXT,
+    redeemed_by TEXT REFERENCES users (id) ON DELETE SET NULL,
+    revoked_at  TEXT
+);
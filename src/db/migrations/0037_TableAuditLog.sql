@@ -0,0 +1,27 @@
+-- Who changed what: one row per privileged mutation (page writes, media, users /
+-- roles, API keys, invites, greylist pin/release). `request_log` has the method and
+-- path but neither the actor nor the payload, and can't tell an API-key or MCP write
+-- from a browser one; this table can.
+--
+-- `actor_id` goes NULL if the user is later deleted, so `actor_name` keeps the
+-- display name the row was written with. `channel` is how the actor authenticated:
+-- 'session' (cookie), 'api_key' (a Bearer key — `api_key_id` says which) or 'mcp'
+-- (a tool call on /mcp, also keyed). `api_key_id` carries no FK: the revoked-key
+-- purge must not erase or rewrite history. `before` / `after` are small JSON
+-- summaries of the changed entity (never secrets, never a full page body).
+CREATE TABLE IF NOT EXISTS audit_log (
+    id          INTEGER PRIMARY KEY,
+    ts          TEXT    NOT NULL,
+    actor_id    TEXT REFERENCES users (id) ON DELETE SET NULL,
+    actor_name  TEXT,
+    channel     TEXT    NOT NULL,
+    api_key_id  INTEGER,
+    action      TEXT    NOT NULL,
+    target      TEXT    NOT NULL,
+    before      TEXT,
+    after       TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_ts ON audit_log (ts);
+CREATE INDEX IF NOT EXISTS idx_audit_log_action_ts ON audit_log (action, ts);
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor_ts ON audit_log (actor_id, ts);
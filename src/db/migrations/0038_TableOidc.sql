@@ -0,0 +1,38 @@
+-- Built-in OpenID Connect provider: household apps on the LAN sign in with the
+-- site's passkeys. `oidc_clients` is the admin-managed registry — each client has
+-- a minted `client_id`, the HMAC hash of its secret (the API-key pepper,
+-- crypto_keys id 3; the plaintext is shown once), and its exact-match redirect
+-- URIs, newline-separated. `oidc_codes` holds authorization codes (hashed the same
+-- way) between the consent redirect and the token exchange: single use (`used_at`
+-- is claimed in the UPDATE), a minute's life, bound to the client, redirect URI
+-- and PKCE challenge. `oidc_consents` remembers a user's "Allow" per client so the
+-- consent screen isn't shown on every sign-in; deleting the client forgets it.
+-- The ID-token signing key itself is crypto_keys id 4 (RSA, PKCS#8 DER).
+CREATE TABLE IF NOT EXISTS oidc_clients (
+    id            INTEGER PRIMARY KEY,
+    client_id     TEXT    NOT NULL UNIQUE,
+    secret_hash   TEXT    NOT NULL,
+    name          TEXT    NOT NULL,
+    redirect_uris TEXT    NOT NULL,
+    created_at    TEXT    NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS oidc_codes (
+    code_hash      TEXT PRIMARY KEY,
+    client_id      TEXT NOT NULL REFERENCES oidc_clients (client_id) ON DELETE CASCADE,
+    user_id        TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    redirect_uri   TEXT NOT NULL,
+    code_challenge TEXT NOT NULL,
+    nonce          TEXT,
+    scope          TEXT NOT NULL,
+    expires_at     TEXT NOT NULL,
+    used_at        TEXT
+);
+
+CREATE TABLE IF NOT EXISTS oidc_consents (
+    user_id    TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    client_id  TEXT NOT NULL REFERENCES oidc_clients (client_id) ON DELETE CASCADE,
+    scope      TEXT NOT NULL,
+    granted_at TEXT NOT NULL,
+    PRIMARY KEY (user_id, client_id)
+);
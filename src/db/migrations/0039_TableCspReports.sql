@@ -0,0 +1,20 @@
+-- Content-Security-Policy violations the browsers report to `/csp-report`, kept
+-- for the admin view that tells a report-only rollout when it's safe to enforce.
+-- URLs are stored WITHOUT query string or fragment (an invite token or an OIDC
+-- code can ride in either), and every text field is truncated. The table is
+-- capped (see `CspReportDao::record`), so a noisy extension can't grow it.
+CREATE TABLE IF NOT EXISTS csp_reports (
+    id                  INTEGER PRIMARY KEY,
+    ts                  TEXT    NOT NULL,
+    disposition         TEXT    NOT NULL,
+    document_uri        TEXT    NOT NULL,
+    effective_directive TEXT    NOT NULL,
+    blocked_uri         TEXT    NOT NULL,
+    source_file         TEXT,
+    line_number         INTEGER,
+    sample              TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_csp_reports_ts ON csp_reports (ts);
+CREATE INDEX IF NOT EXISTS idx_csp_reports_directive_blocked
+    ON csp_reports (effective_directive, blocked_uri);
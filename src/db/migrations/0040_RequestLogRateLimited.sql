@@ -0,0 +1,5 @@
+-- `request_log.rate_limited` (0/1) marks a request the token-bucket rate limiter
+-- refused with a 429, so the analytics can tell throttling apart from the greylist
+-- toll (the other 429). Existing rows predate the limiter: 0.
+ALTER TABLE request_log ADD COLUMN rate_limited INTEGER NOT NULL DEFAULT 0;
+CREATE INDEX IF NOT EXISTS idx_request_log_ts_rate_limited ON request_log (ts, rate_limited);
@@ -0,0 +1,15 @@
+-- One-time account recovery codes: the fallback for a user who has lost every
+-- passkey. Generated in sets on /account (a new set replaces the old one) or
+-- minted one at a time by the offline `recovery_code` binary. Only the HMAC hash
+-- of the normalized code is stored (the API-key pepper, crypto_keys id 3).
+-- Redeeming a code on /login claims it atomically (`used_at IS NULL` in the
+-- UPDATE) and lets that visitor register a new passkey for the account.
+CREATE TABLE IF NOT EXISTS recovery_codes (
+    id         INTEGER PRIMARY KEY,
+    user_id    TEXT    NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    code_hash  TEXT    NOT NULL UNIQUE,
+    created_at TEXT    NOT NULL,
+    used_at    TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_recovery_codes_user ON recovery_codes (user_id);
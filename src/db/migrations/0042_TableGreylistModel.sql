@@ -0,0 +1,32 @@
+-- Fitted greylist scoring models (see `greylist::model`). Each offline training run
+-- (`train_greylist_model` binary) appends a row; `version` is the artifact's identity and
+-- only ever grows, so a shadow disagreement always names the exact model that made it.
+-- `kind` is the artifact format (`logreg-v1`) — a future model type gets a new kind, and
+-- the sweep ignores a kind it can't load rather than misreading its JSON.
+CREATE TABLE IF NOT EXISTS greylist_model (
+    version    INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind       TEXT    NOT NULL,
+    artifact   TEXT    NOT NULL,
+    samples    INTEGER NOT NULL,
+    metrics    TEXT    NOT NULL,
+    trained_at TEXT    NOT NULL
+);
+
+-- Shadow mode: every sweep pass scores each IP with BOTH the rules and the latest model,
+-- and records the IPs they disagree on. One row per (model, ip), refreshed while the
+-- disagreement persists; the sweep prunes rows it hasn't touched lately. Nothing here is
+-- ever enforced — it's the evidence for deciding whether the model may.
+CREATE TABLE IF NOT EXISTS greylist_shadow (
+    id            INTEGER PRIMARY KEY,
+    model_version INTEGER NOT NULL REFERENCES greylist_model (version) ON DELETE CASCADE,
+    ip            TEXT    NOT NULL,
+    rule_reason   TEXT,
+    model_score   REAL    NOT NULL,
+    evidence      TEXT    NOT NULL,
+    first_seen    TEXT    NOT NULL,
+    last_seen     TEXT    NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_greylist_shadow_model_ip
+    ON greylist_shadow (model_version, ip);
+CREATE INDEX IF NOT EXISTS idx_greylist_shadow_last_seen ON greylist_shadow (last_seen);
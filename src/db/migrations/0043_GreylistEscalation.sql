@@ -0,0 +1,17 @@
+-- Clear-then-scan escalation (see `greylist::escalation`). A client that SOLVED the toll
+-- and then kept probing signature paths is a headless scraper that paid its way in: the
+-- sweep revokes its clearance and escalates its greylist row to the `Blocked` tier, which
+-- gets a bare 403 instead of the toll.
+--
+-- `tier` is TEXT by variant name (`Toll` / `Blocked`, strum — same as `users.app_role`).
+-- `token_id` is the clearance token's nonce, recorded at mint so a revocation can name the
+-- exact cookie; rows from before this migration have none (their tokens can't be revoked
+-- and simply age out within the 7-day clearance TTL). `revoked_at` set = the token is on
+-- the revocation list the request path checks.
+ALTER TABLE greylist ADD COLUMN tier TEXT NOT NULL DEFAULT 'Toll';
+ALTER TABLE greylist ADD COLUMN escalated_at TEXT;
+
+ALTER TABLE greylist_clearance ADD COLUMN token_id TEXT;
+ALTER TABLE greylist_clearance ADD COLUMN revoked_at TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_greylist_clearance_token_id ON greylist_clearance (token_id);
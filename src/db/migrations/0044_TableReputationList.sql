@@ -0,0 +1,14 @@
+-- Operator-supplied IP reputation lists (see `greylist::reputation`). The listed addresses
+-- themselves live in `greylist` with `reason = 'reputation:<name>'`; this table is the
+-- per-list load status: what was imported when, and from which version of the file.
+-- `file_mtime` (unix milliseconds) is how a reload notices the file changed; `error` holds the
+-- last failed load, cleared by the next good one.
+CREATE TABLE IF NOT EXISTS reputation_list (
+    name       TEXT    PRIMARY KEY,
+    path       TEXT    NOT NULL,
+    entries    INTEGER NOT NULL DEFAULT 0,
+    skipped    INTEGER NOT NULL DEFAULT 0,
+    file_mtime INTEGER,
+    loaded_at  TEXT,
+    error      TEXT
+);
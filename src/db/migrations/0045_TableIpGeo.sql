@@ -0,0 +1,17 @@
+-- Cached GeoIP / ASN lookups (see `geoip`), one row per IP, from the operator's local
+-- MaxMind-format databases. A cache, not a record: a row is re-derivable from the .mmdb at
+-- any time, so rows older than the cache lifetime are dropped and looked up again (which is
+-- also how a monthly database update reaches old IPs). Keyed on the same IP text
+-- `request_log.ip` carries, so the analytics panels aggregate with a plain join.
+--
+-- Every column but `ip` is nullable: a database may have no answer for an address (or only
+-- one of the two databases may be configured), and "looked up, nothing known" is cached too.
+CREATE TABLE IF NOT EXISTS ip_geo (
+    ip           TEXT    PRIMARY KEY,
+    country      TEXT,
+    asn          INTEGER,
+    org          TEXT,
+    looked_up_at TEXT    NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_ip_geo_looked_up_at ON ip_geo (looked_up_at);
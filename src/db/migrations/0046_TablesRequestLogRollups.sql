@@ -0,0 +1,48 @@
+-- Daily rollups of `request_log` (see `db::dao::request_log_rollup`): the long tier of the
+-- tiered retention. Raw rows are pruned after the raw window; before they go, each
+-- completed UTC day is folded into these tables, which are kept for years, so the
+-- analytics dashboard can still chart a range older than the raw window.
+--
+-- `request_log_rollup_days` records which days have been rolled up. Days are rolled in
+-- order, so its latest day is the high-water mark the next run resumes from.
+CREATE TABLE IF NOT EXISTS request_log_rollup_days (
+    day       TEXT PRIMARY KEY,
+    rolled_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Requests per day, path, status bucket and audience. `status_bucket` is the status
+-- rounded down to its hundred (200 = any 2xx, 400 = a 4xx other than the three kept
+-- exact), except 403 / 404 / 429, which the dashboard splits out. `is_bot` and
+-- `challenged` carry the raw columns' values, NULLs included.
+CREATE TABLE IF NOT EXISTS request_log_daily_paths (
+    day           TEXT    NOT NULL,
+    path          TEXT    NOT NULL,
+    status_bucket INTEGER NOT NULL,
+    is_bot        INTEGER,
+    challenged    INTEGER,
+    requests      INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_request_log_daily_paths_day ON request_log_daily_paths (day);
+
+-- Requests per day, referer host and audience. `referer_host` is NULL for no referer
+-- (direct) and '' for a referer with no usable host (empty, malformed, `mailto:` …).
+CREATE TABLE IF NOT EXISTS request_log_daily_referers (
+    day          TEXT    NOT NULL,
+    referer_host TEXT,
+    is_bot       INTEGER,
+    challenged   INTEGER,
+    requests     INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_request_log_daily_referers_day ON request_log_daily_referers (day);
+
+-- Distinct IPs per day, one column per dashboard audience — distinct counts don't sum
+-- across the buckets above, so they're taken once per day at rollup time.
+CREATE TABLE IF NOT EXISTS request_log_daily_visitors (
+    day            TEXT    PRIMARY KEY,
+    all_ips        INTEGER NOT NULL,
+    human_ips      INTEGER NOT NULL,
+    bot_ips        INTEGER NOT NULL,
+    challenged_ips INTEGER NOT NULL
+);
@@ -0,0 +1,18 @@
+-- The request-log privacy policy (see `privacy`).
+--
+-- `request_log.visitor` is a keyed hash of the request's full IP, written with the row (and
+-- backfilled for older rows), so distinct-visitor counts don't change when an IP is later
+-- truncated or hashed. `anonymized` marks rows already past the privacy window — done once,
+-- so a truncated IP is never truncated again or a reduced user agent re-reduced.
+ALTER TABLE request_log ADD COLUMN visitor TEXT;
+ALTER TABLE request_log ADD COLUMN anonymized INTEGER NOT NULL DEFAULT 0;
+
+-- Partial indexes over just the rows the task still has to visit.
+CREATE INDEX IF NOT EXISTS idx_request_log_unanonymized ON request_log (ts) WHERE anonymized = 0;
+CREATE INDEX IF NOT EXISTS idx_request_log_unkeyed ON request_log (id)
+    WHERE visitor IS NULL AND ip IS NOT NULL;
+
+-- A solve's recorded user agent follows the same policy.
+ALTER TABLE greylist_clearance ADD COLUMN anonymized INTEGER NOT NULL DEFAULT 0;
+CREATE INDEX IF NOT EXISTS idx_greylist_clearance_unanonymized ON greylist_clearance (cleared_at)
+    WHERE anonymized = 0;
@@ -0,0 +1,21 @@
+-- Alerts raised by the rules engine (see `alerts`). A row is one episode of one rule: it
+-- opens when the rule starts firing, is touched on every pass it keeps firing (so a
+-- still-firing rule never notifies twice), and closes when it stops. `rule` is the rule's
+-- key — `error_rate`, or `media_free:<root>` for a per-subject rule — and at most one
+-- episode per key is open at a time.
+--
+-- `notified` = the latest change (the opening, or the close once `resolved_at` is set) has
+-- reached every sink. A pass retries the rows where it's still 0.
+CREATE TABLE IF NOT EXISTS alerts (
+    id           INTEGER PRIMARY KEY,
+    rule         TEXT    NOT NULL,
+    summary      TEXT    NOT NULL,
+    fired_at     TEXT    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_seen_at TEXT    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resolved_at  TEXT,
+    notified     INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_alerts_open_rule ON alerts (rule) WHERE resolved_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_alerts_unnotified ON alerts (notified) WHERE notified = 0;
@@ -0,0 +1,26 @@
+-- Campaign attribution (see `web::util::campaign`): the `utm_source` (or `ref`),
+-- `utm_medium` and `utm_campaign` a tagged link arrived with, normalized. All NULL for an
+-- untagged request; a tagged one always has a source (`(not set)` if only the others were
+-- given).
+ALTER TABLE request_log ADD COLUMN campaign_source TEXT;
+ALTER TABLE request_log ADD COLUMN campaign_medium TEXT;
+ALTER TABLE request_log ADD COLUMN campaign_name TEXT;
+
+-- Only a sliver of rows are tagged; the campaigns panel and the writer's start-up read
+-- just visit those.
+CREATE INDEX IF NOT EXISTS idx_request_log_campaign ON request_log (ts)
+    WHERE campaign_source IS NOT NULL;
+
+-- Tagged requests per day, campaign and audience — the rollup tier's campaigns panel
+-- (see `request_log_rollup`).
+CREATE TABLE IF NOT EXISTS request_log_daily_campaigns (
+    day        TEXT    NOT NULL,
+    source     TEXT    NOT NULL,
+    medium     TEXT,
+    name       TEXT,
+    is_bot     INTEGER,
+    challenged INTEGER,
+    requests   INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_request_log_daily_campaigns_day ON request_log_daily_campaigns (day);
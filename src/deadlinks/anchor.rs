@@ -0,0 +1,162 @@
+//! DL.10 — `#fragment` validation. A link's target can exist while the heading it
+//! points into is gone (renamed, cut in an edit), and the browser just lands at the
+//! top of the page — a quieter rot than a 404, but rot. So a link with a fragment is
+//! also checked against the anchors the target actually has:
+//!
+//! - **internal** — the ids the transformer renders for the target page (its heading
+//!   slugs plus any authored `id`s), from the same render the page serves, so the
+//!   check can't drift from what a visitor gets;
+//! - **external** — the `id` / `name` attributes in the fetched HTML. Opt-in
+//!   (`dead_link_check_external_anchors`): it pulls the whole page instead of one
+//!   byte, and a client-rendered page has no anchors in its HTML at all.
+//!
+//! A miss is `CheckClass::BrokenAnchor`, not `Dead`: the page is there, the link still
+//! works, it just doesn't land where it says.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+
+use crate::web::markdown::{render_cache::cached_transform, title::strip_leading_h1};
+
+/// The anchor a link points into: its fragment, percent-decoded. `None` when there's
+/// nothing to check — no fragment, an empty one, a text fragment (`#:~:text=`), or a
+/// client-side route (`#/path`, `#!/path`) that no HTML id will ever match.
+pub fn fragment(url: &str) -> Option<String> {
+    let (_, raw) = url.split_once('#')?;
+    if raw.is_empty() || raw.starts_with(":~:") || raw.starts_with('/') || raw.starts_with('!') {
+        return None;
+    }
+    Some(percent_decode(raw))
+}
+
+/// Every `id` / `name` attribute value in `html`. A scan, not a parse — it only has to
+/// find `id="…"`-shaped attributes inside tags, and it never fails on messy markup.
+pub fn anchors_in(html: &str) -> HashSet<String> {
+    let mut anchors = HashSet::new();
+    let mut rest = html;
+    while let Some(open) = rest.find('<') {
+        let tag = &rest[open + 1..];
+        let end = tag.find('>').unwrap_or(tag.len());
+        collect_attrs(&tag[..end], &mut anchors);
+        rest = &tag[end..];
+    }
+    anchors
+}
+
+/// The anchors a content page's markdown renders to — the same strip-the-title +
+/// transform its page route serves.
+pub fn page_anchors(markdown: &str) -> Result<HashSet<String>> {
+    Ok(anchors_in(&cached_transform(&strip_leading_h1(markdown))?))
+}
+
+/// Pull `id=` / `name=` values out of one tag's inside (`a href="…" id="x"`), quoted
+/// either way or bare.
+fn collect_attrs(tag: &str, anchors: &mut HashSet<String>) {
+    let lower = tag.to_ascii_lowercase();
+    for attr in ["id", "name"] {
+        for (at, _) in lower.match_indices(attr) {
+            // Whole attribute names only: `data-id=` and `grid=` aren't anchors.
+            let preceded = lower[..at]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_ascii_whitespace());
+            let after = lower[at + attr.len()..].trim_start();
+            if !preceded || !after.starts_with('=') {
+                continue;
+            }
+            let value_at = tag.len() - after.len() + 1;
+            let value = tag[value_at..].trim_start();
+            let value = match value.chars().next() {
+                Some(q @ ('"' | '\'')) => value[1..].split(q).next(),
+                _ => value
+                    .split(|c: char| c.is_ascii_whitespace() || c == '/')
+                    .next(),
+            };
+            if let Some(value) = value.filter(|v| !v.is_empty()) {
+                anchors.insert(value.to_string());
+            }
+        }
+    }
+}
+
+/// `%XX` escapes decoded (lossily, for a malformed one) — `#caf%C3%A9` matches the
+/// `id="café"` a browser would scroll to.
+fn percent_decode(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = raw
+            .get(i + 1..i + 3)
+            .filter(|h| h.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fragments_worth_checking() {
+        assert_eq!(fragment("/blog/post#setup").as_deref(), Some("setup"));
+        assert_eq!(
+            fragment("https://x.example/a?b=1#caf%C3%A9").as_deref(),
+            Some("café")
+        );
+        assert_eq!(fragment("/blog/post"), None);
+        assert_eq!(fragment("/blog/post#"), None);
+        assert_eq!(fragment("https://x.example/#:~:text=hello"), None);
+        assert_eq!(fragment("https://app.example/#/settings"), None);
+        assert_eq!(fragment("https://app.example/#!/settings"), None);
+        assert_eq!(
+            fragment("/a#50%").as_deref(),
+            Some("50%"),
+            "a stray % survives"
+        );
+    }
+
+    #[test]
+    fn ids_and_names_are_found_in_any_quoting() {
+        let html = r#"<h2 id="setup">Setup</h2>
+            <a name='legacy'></a><div class="x" ID=bare data-id="nope" grid="no">
+            <span id = "spaced" /><p>id="in-text"</p>"#;
+        let found = anchors_in(html);
+        for id in ["setup", "legacy", "bare", "spaced"] {
+            assert!(found.contains(id), "{id} missing from {found:?}");
+        }
+        assert!(!found.contains("nope"), "data-id isn't an anchor");
+        assert!(!found.contains("no"), "grid= isn't id=");
+        assert!(
+            !found.contains("in-text"),
+            "text outside a tag isn't an attribute"
+        );
+    }
+
+    #[test]
+    fn a_page_renders_its_heading_slugs() {
+        let anchors =
+            page_anchors("# Title\n\n## Getting Started\n\n<a id=\"custom\"></a>\n").unwrap();
+        assert!(anchors.contains("getting-started"), "{anchors:?}");
+        assert!(
+            anchors.contains("custom"),
+            "an authored id counts: {anchors:?}"
+        );
+        assert!(
+            !anchors.contains("title"),
+            "the leading H1 is the page title, not in the body: {anchors:?}"
+        );
+    }
+}
@@ -45,6 +45,9 @@ impl LinkKind {
 ///   likely works in a browser, so it's surfaced for MANUAL review, not called dead.
 /// - `Unknown` — an internal route the resolver's hand-maintained map doesn't
 ///   recognize. Review, not dead.
+/// - `BrokenAnchor` — the page is there but its `#fragment` isn't (DL.10). Advances
+///   the streak and confirms like `Dead`, under its own label: the link still works,
+///   it just lands at the top of the page.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CheckClass {
     Ok,
@@ -52,6 +55,7 @@ pub enum CheckClass {
     Transient,
     Blocked,
     Unknown,
+    BrokenAnchor,
 }
 
 impl CheckClass {
@@ -63,6 +67,7 @@ impl CheckClass {
             CheckClass::Transient => "transient",
             CheckClass::Blocked => "blocked",
             CheckClass::Unknown => "unknown",
+            CheckClass::BrokenAnchor => "broken-anchor",
         }
     }
 
@@ -74,15 +79,19 @@ impl CheckClass {
             "dead" => CheckClass::Dead,
             "transient" => CheckClass::Transient,
             "blocked" => CheckClass::Blocked,
+            "broken-anchor" => CheckClass::BrokenAnchor,
             _ => CheckClass::Unknown,
         }
     }
 
-    /// Does this class advance the consecutive-failure streak? `Dead` and
-    /// `Transient` do; `Ok` resets it; `Blocked`/`Unknown` are orthogonal review
-    /// states that leave the streak untouched (we couldn't determine liveness).
+    /// Does this class advance the consecutive-failure streak? `Dead`, `Transient`
+    /// and `BrokenAnchor` do; `Ok` resets it; `Blocked`/`Unknown` are orthogonal
+    /// review states that leave the streak untouched (we couldn't determine liveness).
     pub fn counts_as_failure(self) -> bool {
-        matches!(self, CheckClass::Dead | CheckClass::Transient)
+        matches!(
+            self,
+            CheckClass::Dead | CheckClass::Transient | CheckClass::BrokenAnchor
+        )
     }
 }
 
@@ -92,6 +101,7 @@ impl From<InternalVerdict> for CheckClass {
             InternalVerdict::Ok => CheckClass::Ok,
             InternalVerdict::Dead => CheckClass::Dead,
             InternalVerdict::Unknown => CheckClass::Unknown,
+            InternalVerdict::BrokenAnchor => CheckClass::BrokenAnchor,
         }
     }
 }
@@ -108,6 +118,7 @@ mod tests {
             CheckClass::Transient,
             CheckClass::Blocked,
             CheckClass::Unknown,
+            CheckClass::BrokenAnchor,
         ] {
             assert_eq!(CheckClass::from_stored(c.as_str()), c);
         }
@@ -123,9 +134,10 @@ mod tests {
     }
 
     #[test]
-    fn only_dead_and_transient_count_as_failure() {
+    fn only_dead_transient_and_broken_anchor_count_as_failure() {
         assert!(CheckClass::Dead.counts_as_failure());
         assert!(CheckClass::Transient.counts_as_failure());
+        assert!(CheckClass::BrokenAnchor.counts_as_failure());
         assert!(!CheckClass::Ok.counts_as_failure());
         assert!(!CheckClass::Blocked.counts_as_failure());
         assert!(!CheckClass::Unknown.counts_as_failure());
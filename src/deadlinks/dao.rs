@@ -55,9 +55,19 @@ impl LinkCheckRow {
         self.class() == CheckClass::Dead && self.consecutive_failures >= CONFIRM_THRESHOLD
     }
 
-    /// Failing (dead or transient) but not YET confirmed — the early-warning bucket.
+    /// A missing `#fragment` for `CONFIRM_THRESHOLD` consecutive daily passes, the
+    /// latest verdict still `broken-anchor` — its own bucket, never "confirmed dead".
+    pub fn is_confirmed_broken_anchor(&self) -> bool {
+        self.class() == CheckClass::BrokenAnchor
+            && self.consecutive_failures >= CONFIRM_THRESHOLD
+    }
+
+    /// Failing (dead, transient or a missing anchor) but not YET confirmed — the
+    /// early-warning bucket.
     pub fn is_failing(&self) -> bool {
-        self.class().counts_as_failure() && !self.is_confirmed_dead()
+        self.class().counts_as_failure()
+            && !self.is_confirmed_dead()
+            && !self.is_confirmed_broken_anchor()
     }
 
     /// Bot-walled (`blocked`) or an unrecognized internal route (`unknown`) — the
@@ -71,8 +81,8 @@ impl LinkCheckRow {
 /// (no DB, no clock) so the streak logic is exhaustively testable.
 ///
 /// - `Ok` → streak 0, stamp `last_ok_at`, clear `first_failed_at`.
-/// - `Dead`/`Transient` → advance the streak (at most once per `min_streak_interval`),
-///   keep/stamp `first_failed_at`.
+/// - `Dead`/`Transient`/`BrokenAnchor` → advance the streak (at most once per
+///   `min_streak_interval`), keep/stamp `first_failed_at`.
 /// - `Blocked`/`Unknown` → hold the streak + failure timestamps (orthogonal: we
 ///   couldn't determine liveness, so neither advance nor reset).
 #[allow(clippy::too_many_arguments)]
@@ -401,6 +411,21 @@ mod tests {
         assert!(!blocked.is_failing());
     }
 
+    #[test]
+    fn broken_anchor_confirms_under_its_own_label() {
+        let mut row = next_state("u#a", LinkKind::Internal, None, CheckClass::BrokenAnchor, None, "", at(0), INTERVAL);
+        for d in 1..3 {
+            row = next_state("u#a", LinkKind::Internal, Some(&row), CheckClass::BrokenAnchor, None, "", at(d * DAY), INTERVAL);
+        }
+        assert!(row.is_confirmed_broken_anchor(), "3 daily passes = confirmed");
+        assert!(!row.is_confirmed_dead(), "a missing anchor is never called dead");
+        assert!(!row.is_failing() && !row.needs_review());
+
+        // The heading comes back: the streak resets like any recovery.
+        let fixed = next_state("u#a", LinkKind::Internal, Some(&row), CheckClass::Ok, None, "", at(3 * DAY), INTERVAL);
+        assert_eq!(fixed.consecutive_failures, 0);
+    }
+
     #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
     async fn record_roundtrips_and_confirms_over_days(pool: SqlitePool) {
         for d in 0..3 {
@@ -6,6 +6,7 @@
 
 use std::time::Duration;
 
+use super::anchor::{anchors_in, fragment};
 use super::class::CheckClass;
 
 /// The result of one external check: verdict + HTTP status (if any) + a short human
@@ -48,16 +49,23 @@ pub fn classify_status(status: u16) -> CheckClass {
     }
 }
 
+/// The most of a page read looking for an anchor. Past it the check gives up and
+/// calls the link ok, rather than flag an anchor further down as missing.
+const MAX_ANCHOR_BODY: usize = 2 * 1024 * 1024;
+
 /// The real reqwest-backed checker. Holds ONE reused `Client` (built with an
 /// identifying UA, explicit timeouts, redirects followed).
 pub struct ReqwestChecker {
     client: reqwest::Client,
+    /// Also check a `#fragment` against the page's `id`/`name` attributes (DL.10,
+    /// `dead_link_check_external_anchors`).
+    check_anchors: bool,
 }
 
 impl ReqwestChecker {
     /// Build the checker's client. Redirects are followed (a 301→200 is healthy),
     /// timeouts bound a slow host, TLS is rustls (matches the rest of the app).
-    pub fn new() -> anyhow::Result<Self> {
+    pub fn new(check_anchors: bool) -> anyhow::Result<Self> {
         let client = reqwest::ClientBuilder::new()
             .use_rustls_tls()
             .user_agent(user_agent())
@@ -65,7 +73,10 @@ impl ReqwestChecker {
             .timeout(Duration::from_secs(15))
             .redirect(reqwest::redirect::Policy::limited(10))
             .build()?;
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            check_anchors,
+        })
     }
 
     async fn send(&self, method: reqwest::Method, url: &str) -> Result<u16, reqwest::Error> {
@@ -85,10 +96,50 @@ impl ReqwestChecker {
         }
         Ok(req.send().await?.status().as_u16())
     }
+
+    /// GET the whole page for its anchors: the status, plus the body when it's HTML
+    /// that fit under `MAX_ANCHOR_BODY` (`None` otherwise — nothing to judge).
+    async fn fetch_html(&self, url: &str) -> Result<(u16, Option<String>), reqwest::Error> {
+        let mut resp = self
+            .client
+            .get(url)
+            .header(reqwest::header::ACCEPT, "text/html,application/xhtml+xml")
+            .send()
+            .await?;
+        let status = resp.status().as_u16();
+        let is_html = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.contains("html"));
+        if !is_html {
+            return Ok((status, None));
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = resp.chunk().await? {
+            body.extend_from_slice(&chunk);
+            if body.len() > MAX_ANCHOR_BODY {
+                return Ok((status, None));
+            }
+        }
+        Ok((status, Some(String::from_utf8_lossy(&body).into_owned())))
+    }
 }
 
 impl ExternalChecker for ReqwestChecker {
     async fn check(&self, url: &str) -> CheckOutcome {
+        // An anchor needs the page itself, so a single full GET replaces the probes.
+        if self.check_anchors
+            && let Some(anchor) = fragment(url)
+        {
+            return match self.fetch_html(url).await {
+                Ok((status, Some(html))) if classify_status(status) == CheckClass::Ok => {
+                    anchor_outcome(status, &anchor, &html)
+                }
+                Ok((status, _)) => outcome_from_status(status),
+                Err(e) => classify_error(&e),
+            };
+        }
         // HEAD first (cheap); GET fallback if HEAD isn't a clean 2xx/3xx (many
         // servers 405 or lie about HEAD).
         if let Ok(status) = self.send(reqwest::Method::HEAD, url).await
@@ -103,6 +154,18 @@ impl ExternalChecker for ReqwestChecker {
     }
 }
 
+/// A page that loaded: `Ok` if `anchor` is among its ids, else `BrokenAnchor`.
+fn anchor_outcome(status: u16, anchor: &str, html: &str) -> CheckOutcome {
+    if anchors_in(html).contains(anchor) {
+        return outcome_from_status(status);
+    }
+    CheckOutcome {
+        class: CheckClass::BrokenAnchor,
+        status: Some(status),
+        detail: format!("HTTP {status}, but no #{anchor} on the page"),
+    }
+}
+
 fn outcome_from_status(status: u16) -> CheckOutcome {
     CheckOutcome {
         class: classify_status(status),
@@ -170,6 +233,17 @@ mod tests {
     #[test]
     fn reqwest_checker_builds() {
         // The client config is valid (UA + timeouts + redirect policy compile+build).
-        assert!(ReqwestChecker::new().is_ok());
+        assert!(ReqwestChecker::new(false).is_ok());
+    }
+
+    #[test]
+    fn a_missing_anchor_is_its_own_class() {
+        let html = r#"<h2 id="install">Install</h2><a name="faq"></a>"#;
+        assert_eq!(anchor_outcome(200, "install", html).class, CheckClass::Ok);
+        assert_eq!(anchor_outcome(200, "faq", html).class, CheckClass::Ok);
+        let missing = anchor_outcome(200, "usage", html);
+        assert_eq!(missing.class, CheckClass::BrokenAnchor);
+        assert_eq!(missing.status, Some(200));
+        assert!(missing.detail.contains("#usage"), "{}", missing.detail);
     }
 }
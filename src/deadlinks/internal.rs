@@ -9,6 +9,7 @@
 use anyhow::Result;
 use sqlx::SqlitePool;
 
+use super::anchor::{fragment, page_anchors};
 use crate::db::dao::content_pages::ContentPageDao;
 use crate::db::dao::media::{MediaDao, MediaVariantDao};
 
@@ -16,12 +17,14 @@ use crate::db::dao::media::{MediaDao, MediaVariantDao};
 /// recognize this route — review it"), distinct from `Dead` (the route exists and
 /// the target is genuinely missing): the route map here is hand-maintained and can
 /// drift from the real router, so an unrecognized `/…` must never read as a
-/// confident "broken".
+/// confident "broken". `BrokenAnchor` is a live content page whose rendered body has
+/// no anchor matching the link's `#fragment` (DL.10).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InternalVerdict {
     Ok,
     Dead,
     Unknown,
+    BrokenAnchor,
 }
 
 /// Exact routes that always exist: home, the special-page section indexes, the
@@ -46,7 +49,9 @@ const OK_EXACT: &[&str] = &[
     "/robots.txt",
 ];
 
-/// Resolve `raw_path` (a root-relative internal link, query/fragment tolerated).
+/// Resolve `raw_path` (a root-relative internal link, query/fragment tolerated). A
+/// `#fragment` is only checked on a content page (`/pages/…`, `/blog/…`) — the code
+/// routes' anchors aren't in the DB, and a media fragment (`#t=30`) isn't an anchor.
 pub async fn resolve_internal(pool: &SqlitePool, raw_path: &str) -> Result<InternalVerdict> {
     // Existence doesn't depend on the query or fragment.
     let path = raw_path.split(['?', '#']).next().unwrap_or("");
@@ -111,7 +116,18 @@ pub async fn resolve_internal(pool: &SqlitePool, raw_path: &str) -> Result<Inter
         // find_by_path returns the full ancestor chain on success, empty on any
         // miss — a partial walk (len < segments) is a dead leaf under a live parent.
         let found = ContentPageDao::find_by_path(pool, &segs).await?;
-        return Ok(hit_or_dead(found.len() == segs.len()));
+        if found.len() != segs.len() {
+            return Ok(InternalVerdict::Dead);
+        }
+        // A special page is a redirect alias (no body of its own to anchor into), and
+        // a page that won't render can't be judged — either way the link stands.
+        if let (Some(anchor), Some(page)) = (fragment(raw_path), found.last())
+            && !page.special_page
+            && page_anchors(&page.page_markdown).is_ok_and(|ids| !ids.contains(&anchor))
+        {
+            return Ok(InternalVerdict::BrokenAnchor);
+        }
+        return Ok(InternalVerdict::Ok);
     }
 
     // Unrecognized internal route → surface for review, never a hard "dead".
@@ -233,6 +249,42 @@ mod tests {
         );
     }
 
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn fragments_resolve_against_the_rendered_headings(pool: SqlitePool) {
+        let blog = ContentPageDao::find_by_name(&pool, None, "blog")
+            .await
+            .unwrap()
+            .expect("blog special page seeded by migration 0010");
+        ContentPageDao::create(
+            &pool,
+            Some(blog.page_id),
+            "post".to_string(),
+            None,
+            "# Post\n\n## Setup Steps\n\nbody".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            resolve_internal(&pool, "/blog/post#setup-steps").await.unwrap(),
+            InternalVerdict::Ok
+        );
+        assert_eq!(
+            resolve_internal(&pool, "/blog/post#teardown").await.unwrap(),
+            InternalVerdict::BrokenAnchor
+        );
+        // A missing page is still plain dead, fragment or not; a code route's and a
+        // media item's fragments aren't checked.
+        assert_eq!(
+            resolve_internal(&pool, "/blog/ghost#setup-steps").await.unwrap(),
+            InternalVerdict::Dead
+        );
+        assert_eq!(
+            resolve_internal(&pool, "/resume#experience").await.unwrap(),
+            InternalVerdict::Ok
+        );
+    }
+
     #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
     async fn media_miss_is_dead(pool: SqlitePool) {
         // No media seeded → any ref / url_key is a miss = Dead.
@@ -10,6 +10,7 @@
 //! own host — a role-gated / scheduled page correctly 404s an anonymous fetch, so
 //! a self-fetch would false-positive a live-but-gated page as dead.
 
+mod anchor;
 mod class;
 mod classify;
 mod dao;
@@ -23,5 +24,4 @@ mod scan;
 // reached through these — callers use the values via method returns without naming
 // the types.
 pub use dao::{LinkCheckDao, LinkCheckRow, LinkRefDao};
-pub use external::ReqwestChecker;
 pub use scan::{recheck_one, spawn, trigger_now, DeadLinkScanState};
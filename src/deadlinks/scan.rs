@@ -15,6 +15,7 @@ use futures::stream::{self, StreamExt};
 use serde::Serialize;
 use sqlx::types::chrono::{DateTime, Utc};
 use sqlx::SqlitePool;
+use tracing::Instrument;
 
 use super::class::{CheckClass, LinkKind};
 use super::classify::classify;
@@ -42,16 +43,20 @@ pub struct ScanSummary {
     pub pages_scanned: usize,
     pub links_checked: usize,
     pub confirmed_dead: usize,
+    pub broken_anchors: usize,
     pub failing: usize,
     pub needs_review: usize,
 }
 
 /// Shared runtime handle (mirrors greylist's `GreylistSet`): a single-flight guard
 /// so the daily tick and a manual trigger can't overlap, plus the last-run status
-/// the admin page shows. Cloned coordinator→loop + coordinator→AppState.
+/// the admin page shows. Cloned coordinator→loop + coordinator→AppState. Also
+/// carries the one scan setting every checker is built with, so the loop, "Run scan
+/// now" and the per-link re-check all check the same way.
 #[derive(Clone, Default, Debug)]
 pub struct DeadLinkScanState {
     inner: Arc<Mutex<Inner>>,
+    external_anchors: bool,
 }
 
 #[derive(Default, Debug)]
@@ -72,8 +77,18 @@ pub struct DeadLinkStatus {
 }
 
 impl DeadLinkScanState {
-    pub fn new() -> Self {
-        Self::default()
+    /// `external_anchors`: also check external links' `#fragment`s (DL.10, from
+    /// `Settings.dead_link_check_external_anchors`).
+    pub fn new(external_anchors: bool) -> Self {
+        Self {
+            external_anchors,
+            ..Self::default()
+        }
+    }
+
+    /// A checker configured for this scanner.
+    pub fn checker(&self) -> anyhow::Result<ReqwestChecker> {
+        ReqwestChecker::new(self.external_anchors)
     }
 
     /// Claim the single-flight slot. `true` = you may scan (marked running);
@@ -200,6 +215,10 @@ pub async fn run_scan<C: ExternalChecker>(
         pages_scanned,
         links_checked,
         confirmed_dead: problems.iter().filter(|r| r.is_confirmed_dead()).count(),
+        broken_anchors: problems
+            .iter()
+            .filter(|r| r.is_confirmed_broken_anchor())
+            .count(),
         failing: problems.iter().filter(|r| r.is_failing()).count(),
         needs_review: problems.iter().filter(|r| r.needs_review()).count(),
     })
@@ -240,7 +259,7 @@ pub async fn recheck_one<C: ExternalChecker>(
 /// pass can't take the app down). Builds one reused checker, ticks daily.
 pub fn spawn(pool: SqlitePool, site_host: String, scanner: DeadLinkScanState) {
     tokio::spawn(async move {
-        let checker = match ReqwestChecker::new() {
+        let checker = match scanner.checker() {
             Ok(c) => c,
             Err(e) => {
                 tracing::error!("dead-link checker client build failed; scans disabled: {e:?}");
@@ -260,7 +279,7 @@ pub fn spawn(pool: SqlitePool, site_host: String, scanner: DeadLinkScanState) {
 /// result — no-op if a scan is already running (the guard).
 pub fn trigger_now(pool: SqlitePool, site_host: String, scanner: DeadLinkScanState) {
     tokio::spawn(async move {
-        let checker = match ReqwestChecker::new() {
+        let checker = match scanner.checker() {
             Ok(c) => c,
             Err(e) => {
                 tracing::error!("dead-link checker client build failed: {e:?}");
@@ -283,7 +302,9 @@ async fn run_guarded<C: ExternalChecker>(
         tracing::info!("dead-link scan already running; skipping this trigger");
         return;
     }
-    let result = run_scan(pool, checker, site_host, now).await;
+    let result = run_scan(pool, checker, site_host, now)
+        .instrument(tracing::info_span!("dead_link_scan"))
+        .await;
     let finished = Utc::now();
     match result {
         Ok(summary) => {
@@ -315,6 +336,7 @@ fn internal_detail(verdict: InternalVerdict) -> &'static str {
         InternalVerdict::Ok => "resolved",
         InternalVerdict::Dead => "no such page or media",
         InternalVerdict::Unknown => "unrecognized internal route",
+        InternalVerdict::BrokenAnchor => "page exists, but no such heading or anchor",
     }
 }
 
@@ -411,6 +433,31 @@ mod tests {
         assert_eq!(summary.confirmed_dead, 1, "3 daily dead passes → confirmed");
     }
 
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn a_missing_heading_confirms_as_a_broken_anchor_not_dead(pool: SqlitePool) {
+        ContentPageDao::create(&pool, None, "guide".to_string(), None, "# Guide\n\n## Install\n".to_string(), None)
+            .await
+            .unwrap();
+        let md = "[ok](/pages/guide#install)\n[gone](/pages/guide#usage)\n";
+        ContentPageDao::create(&pool, None, "post".to_string(), None, md.to_string(), None)
+            .await
+            .unwrap();
+        let stub = StubChecker(HashMap::new());
+        const DAY: i64 = 24 * 3600;
+        let mut summary = ScanSummary::default();
+        for d in 0..3 {
+            summary = run_scan(&pool, &stub, "hotchkiss.io", at(d * DAY)).await.unwrap();
+        }
+        assert_eq!(
+            LinkCheckDao::get(&pool, "/pages/guide#install").await.unwrap().unwrap().class(),
+            CheckClass::Ok
+        );
+        let gone = LinkCheckDao::get(&pool, "/pages/guide#usage").await.unwrap().unwrap();
+        assert_eq!(gone.class(), CheckClass::BrokenAnchor);
+        assert_eq!(summary.broken_anchors, 1, "3 daily passes → confirmed");
+        assert_eq!(summary.confirmed_dead, 0, "the page is there: not dead");
+    }
+
     #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
     async fn refs_track_the_referencing_page(pool: SqlitePool) {
         let page = ContentPageDao::create(&pool, None, "p".to_string(), None, "[d](https://dead.example/)".to_string(), None)
@@ -426,7 +473,7 @@ mod tests {
 
     #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
     async fn single_flight_guard_blocks_overlap(pool: SqlitePool) {
-        let scanner = DeadLinkScanState::new();
+        let scanner = DeadLinkScanState::new(false);
         assert!(scanner.try_begin(at(0)), "first claim succeeds");
         assert!(!scanner.try_begin(at(1)), "second claim blocked while running");
         scanner.finish(at(2), Some(ScanSummary::default()));
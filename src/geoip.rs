@@ -0,0 +1,325 @@
+//! GeoIP / ASN enrichment from the operator's local MaxMind-format databases (`geoip` in the
+//! settings: a GeoLite2-Country or -City `.mmdb`, a GeoLite2-ASN `.mmdb`, or both). Nothing
+//! is fetched — the operator keeps the files current (e.g. `geoipupdate`); they're read at
+//! startup.
+//!
+//! Lookups are cached in `ip_geo` (see the migration) so the analytics panels aggregate with
+//! a plain SQL join instead of a lookup per row. The cache fills lazily — for the window the
+//! analytics dashboard shows, and for whatever IPs the drill-down and greylist pages list —
+//! and rows older than [`CACHE_DAYS`] are dropped and looked up again.
+//!
+//! With `weigh_hosting_asns`, the sweep's R5 (`greylist::detection::score_hosting`) applies
+//! lower bars to IPs on a hosting provider's network: [`HOSTING_ASNS`] plus the operator's
+//! `hosting_asns`.
+//!
+//! Unconfigured (or with neither file readable) every call is a cheap no-op, and the pages
+//! hide their GeoIP columns and panels.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Result;
+use maxminddb::{Reader, geoip2};
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
+use crate::db::dao::ip_geo::{IpGeo, IpGeoDao};
+use crate::db::dao::request_log::Window;
+use crate::settings::GeoIp;
+
+/// How long a cached lookup is trusted before it's redone against the (possibly updated)
+/// database.
+pub const CACHE_DAYS: i64 = 30;
+
+/// Networks that are overwhelmingly servers, not people: the big clouds and VPS hosts
+/// scanner traffic comes from. Extend per deployment with `geoip.hosting_asns`.
+pub const HOSTING_ASNS: &[u32] = &[
+    16509,  // Amazon (AWS)
+    14618,  // Amazon (AWS, us-east-1)
+    15169,  // Google
+    396982, // Google Cloud
+    8075,   // Microsoft (Azure)
+    31898,  // Oracle Cloud
+    45102,  // Alibaba Cloud
+    132203, // Tencent Cloud
+    14061,  // DigitalOcean
+    16276,  // OVH
+    24940,  // Hetzner
+    63949,  // Akamai (Linode)
+    20473,  // Vultr
+    51167,  // Contabo
+    12876,  // Scaleway
+    60781,  // LeaseWeb
+    9009,   // M247
+    36352,  // ColoCrossing
+];
+
+struct Databases {
+    country: Option<Reader<Vec<u8>>>,
+    asn: Option<Reader<Vec<u8>>>,
+    /// The hosting ASNs R5 weighs — `None` unless `weigh_hosting_asns` is on.
+    hosting: Option<HashSet<u32>>,
+}
+
+/// The opened databases, shared (`Arc`) between the request path and the sweep.
+#[derive(Clone, Default)]
+pub struct GeoDb {
+    dbs: Option<Arc<Databases>>,
+}
+
+impl fmt::Debug for GeoDb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GeoDb")
+            .field("enabled", &self.is_enabled())
+            .finish()
+    }
+}
+
+fn open_db(path: Option<&Path>, kind: &str) -> Option<Reader<Vec<u8>>> {
+    let path = path?;
+    match Reader::open_readfile(path) {
+        Ok(reader) => {
+            info!("geoip: loaded the {kind} database {path:?}");
+            Some(reader)
+        }
+        Err(e) => {
+            warn!("geoip: couldn't open the {kind} database {path:?} (going without it): {e}");
+            None
+        }
+    }
+}
+
+impl GeoDb {
+    /// Open the configured databases. A missing or unreadable file only warns: enrichment
+    /// carries on with the other one, or is off.
+    pub fn open(config: Option<&GeoIp>) -> Self {
+        let Some(config) = config else {
+            return Self::default();
+        };
+        let country = open_db(config.country_db.as_deref(), "country");
+        let asn = open_db(config.asn_db.as_deref(), "ASN");
+        if country.is_none() && asn.is_none() {
+            return Self::default();
+        }
+        let hosting = config.weigh_hosting_asns.then(|| {
+            HOSTING_ASNS
+                .iter()
+                .chain(&config.hosting_asns)
+                .copied()
+                .collect()
+        });
+        Self {
+            dbs: Some(Arc::new(Databases {
+                country,
+                asn,
+                hosting,
+            })),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.dbs.is_some()
+    }
+
+    /// Look `entry` up — an IP, or a CIDR range by its network address. `None` when
+    /// enrichment is off or `entry` doesn't parse; an address the databases don't know
+    /// comes back with every field empty.
+    pub fn lookup(&self, entry: &str) -> Option<IpGeo> {
+        let dbs = self.dbs.as_ref()?;
+        let addr: IpAddr = entry.split('/').next()?.parse().ok()?;
+        let country = dbs
+            .country
+            .as_ref()
+            .and_then(|r| r.lookup::<geoip2::Country>(addr).ok())
+            .and_then(|c| c.country?.iso_code.map(str::to_string));
+        let asn = dbs
+            .asn
+            .as_ref()
+            .and_then(|r| r.lookup::<geoip2::Asn>(addr).ok());
+        Some(IpGeo {
+            ip: entry.to_string(),
+            country,
+            asn: asn
+                .as_ref()
+                .and_then(|a| a.autonomous_system_number)
+                .map(i64::from),
+            org: asn
+                .and_then(|a| a.autonomous_system_organization)
+                .map(str::to_string),
+        })
+    }
+
+    /// The network R5 names in its evidence, when it applies to `ip`: the weighting is on
+    /// and the IP's ASN is a hosting provider's.
+    pub fn hosting_network(&self, ip: &str) -> Option<String> {
+        let hosting = self.dbs.as_ref()?.hosting.as_ref()?;
+        let geo = self.lookup(ip)?;
+        let asn = u32::try_from(geo.asn?).ok()?;
+        hosting.contains(&asn).then(|| geo.label())
+    }
+
+    /// Look up and cache `ips`, in one transaction.
+    async fn cache(&self, pool: &SqlitePool, ips: &[String]) -> Result<Vec<IpGeo>> {
+        let rows: Vec<IpGeo> = ips.iter().filter_map(|ip| self.lookup(ip)).collect();
+        if rows.is_empty() {
+            return Ok(rows);
+        }
+        let mut tx = pool.begin().await?;
+        for row in &rows {
+            IpGeoDao::upsert(&mut *tx, row).await?;
+        }
+        tx.commit().await?;
+        Ok(rows)
+    }
+
+    /// Make sure every IP logged over `window` has a cached lookup (after dropping expired
+    /// ones), so the analytics panels' join sees them all. Returns how many were looked up.
+    pub async fn enrich(&self, pool: &SqlitePool, window: &Window) -> Result<usize> {
+        if !self.is_enabled() {
+            return Ok(0);
+        }
+        IpGeoDao::prune_older_than(pool, CACHE_DAYS).await?;
+        let missing = IpGeoDao::missing_ips(pool, window).await?;
+        Ok(self.cache(pool, &missing).await?.len())
+    }
+
+    /// `entries` (IPs or CIDR ranges) to their lookups, from the cache where it has them.
+    /// Empty when enrichment is off.
+    pub async fn describe(
+        &self,
+        pool: &SqlitePool,
+        entries: &[String],
+    ) -> Result<HashMap<String, IpGeo>> {
+        if !self.is_enabled() || entries.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let mut out: HashMap<String, IpGeo> = IpGeoDao::get_many(pool, entries)
+            .await?
+            .into_iter()
+            .map(|g| (g.ip.clone(), g))
+            .collect();
+        let mut missing: Vec<String> = entries
+            .iter()
+            .filter(|e| !out.contains_key(*e))
+            .cloned()
+            .collect();
+        missing.sort();
+        missing.dedup();
+        let looked_up = self.cache(pool, &missing).await?;
+        out.extend(looked_up.into_iter().map(|g| (g.ip.clone(), g)));
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fixture_geoip;
+    use std::path::PathBuf;
+
+    #[test]
+    fn unconfigured_or_unreadable_is_off() {
+        assert!(!GeoDb::open(None).is_enabled());
+
+        let missing = GeoIp {
+            country_db: Some(PathBuf::from("/nonexistent/GeoLite2-Country.mmdb")),
+            asn_db: Some(PathBuf::from("/nonexistent/GeoLite2-ASN.mmdb")),
+            weigh_hosting_asns: true,
+            hosting_asns: vec![64_496],
+        };
+        let geo = GeoDb::open(Some(&missing));
+        assert!(!geo.is_enabled(), "neither file opened");
+        assert_eq!(geo.lookup("203.0.113.9"), None);
+        assert_eq!(geo.hosting_network("203.0.113.9"), None);
+    }
+
+    #[test]
+    fn lookup_reads_both_databases() {
+        let geo = GeoDb::open(Some(&fixture_geoip(false)));
+        assert!(geo.is_enabled());
+
+        let hit = geo.lookup("198.51.100.7").unwrap();
+        assert_eq!(hit.country.as_deref(), Some("DE"));
+        assert_eq!(hit.asn, Some(64_500));
+        assert_eq!(hit.org.as_deref(), Some("Example Residential"));
+        assert_eq!(hit.label(), "DE · AS64500 Example Residential");
+
+        let range = geo.lookup("203.0.113.0/24").unwrap();
+        assert_eq!(range.ip, "203.0.113.0/24", "a range is keyed as given");
+        assert_eq!(
+            range.country.as_deref(),
+            Some("NL"),
+            "looked up by its network"
+        );
+
+        let unknown = geo.lookup("8.8.8.8").unwrap();
+        assert_eq!(
+            (unknown.country, unknown.asn, unknown.org),
+            (None, None, None)
+        );
+        assert_eq!(geo.lookup("not-an-ip"), None);
+    }
+
+    #[test]
+    fn only_one_database_still_enriches() {
+        let config = GeoIp {
+            country_db: None,
+            ..fixture_geoip(false)
+        };
+        let hit = GeoDb::open(Some(&config)).lookup("192.0.2.1").unwrap();
+        assert_eq!(hit.country, None);
+        assert_eq!(hit.asn, Some(16_509));
+    }
+
+    #[test]
+    fn hosting_network_needs_the_weighting_and_a_hosting_asn() {
+        let off = GeoDb::open(Some(&fixture_geoip(false)));
+        assert_eq!(off.hosting_network("192.0.2.1"), None, "weighting off");
+
+        let on = GeoDb::open(Some(&fixture_geoip(true)));
+        assert_eq!(
+            on.hosting_network("192.0.2.1").as_deref(),
+            Some("US · AS16509 Amazon.com, Inc."),
+            "a built-in hosting ASN"
+        );
+        assert_eq!(
+            on.hosting_network("203.0.113.9").as_deref(),
+            Some("NL · AS64496 Example Hosting B.V."),
+            "an operator-added one"
+        );
+        assert_eq!(
+            on.hosting_network("198.51.100.7"),
+            None,
+            "not a hosting ASN"
+        );
+        assert_eq!(on.hosting_network("8.8.8.8"), None, "no ASN known");
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn describe_caches_what_it_looks_up(pool: SqlitePool) -> Result<()> {
+        let geo = GeoDb::open(Some(&fixture_geoip(false)));
+        let entries = vec!["192.0.2.1".to_string(), "8.8.8.8".to_string()];
+        let described = geo.describe(&pool, &entries).await?;
+        assert_eq!(described["192.0.2.1"].country.as_deref(), Some("US"));
+        assert_eq!(described["8.8.8.8"].asn, None);
+
+        let cached = IpGeoDao::get_many(&pool, &entries).await?;
+        assert_eq!(cached.len(), 2, "both lookups were cached");
+        Ok(())
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn off_means_no_cache_writes(pool: SqlitePool) -> Result<()> {
+        let geo = GeoDb::default();
+        assert_eq!(geo.enrich(&pool, &Window::last_days(1)).await?, 0);
+        assert!(
+            geo.describe(&pool, &["203.0.113.9".into()])
+                .await?
+                .is_empty()
+        );
+        Ok(())
+    }
+}
@@ -4,16 +4,88 @@
 //! enforcement middleware answers "is this IP greylisted?" from memory with no per-request DB
 //! hit. It's a per-instance `Arc` (NOT a process global) so each test server is isolated —
 //! seeding one test's set can't leak into another.
+//!
+//! Entries are single IPs or CIDR ranges (see `greylist::prefix`). A lookup is an exact-IP
+//! hash probe, then one masked probe per distinct prefix length present, longest first — a
+//! handful of hash lookups however many ranges are listed.
+//!
+//! Alongside it ride the clear-then-scan escalation's two lists (`greylist::escalation`): the
+//! IPs escalated to the `Blocked` tier (a bare 403, no toll) and the revoked clearance token
+//! ids `verify_clearance` refuses. Both refresh with the sweep and update immediately on an
+//! escalation or an admin de-escalation.
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::net::IpAddr;
 use std::sync::{Arc, RwLock};
 
-use crate::db::dao::greylist::GreylistEntry;
+use crate::db::dao::greylist::{GreylistEntry, GreylistTier};
+use crate::greylist::prefix::{IpPrefix, is_prefix_entry};
+
+#[derive(Default, Debug)]
+struct Snapshot {
+    ips: HashSet<String>,
+    /// Range entries by prefix length. Both families share a length's set — an IPv4 and an
+    /// IPv6 prefix never compare equal.
+    prefixes: BTreeMap<u8, HashSet<IpPrefix>>,
+    /// Exact IPs on the `Blocked` tier (escalation is per IP — a clearance names one IP).
+    blocked: HashSet<String>,
+}
+
+impl Snapshot {
+    fn add(&mut self, entry: &str) {
+        if is_prefix_entry(entry) {
+            if let Some(p) = IpPrefix::parse(entry) {
+                self.prefixes.entry(p.prefix_len()).or_default().insert(p);
+            }
+        } else {
+            self.ips.insert(entry.to_string());
+        }
+    }
+
+    fn remove(&mut self, entry: &str) {
+        if is_prefix_entry(entry) {
+            if let Some(p) = IpPrefix::parse(entry)
+                && let Some(set) = self.prefixes.get_mut(&p.prefix_len())
+            {
+                set.remove(&p);
+                if set.is_empty() {
+                    self.prefixes.remove(&p.prefix_len());
+                }
+            }
+        } else {
+            self.ips.remove(entry);
+        }
+    }
+
+    /// The most specific entry covering `ip`: the IP itself, else its longest listed prefix.
+    fn lookup(&self, ip: &str) -> Option<String> {
+        if self.ips.contains(ip) {
+            return Some(ip.to_string());
+        }
+        if self.prefixes.is_empty() {
+            return None;
+        }
+        let addr: IpAddr = ip.parse().ok()?;
+        self.prefixes.iter().rev().find_map(|(len, set)| {
+            IpPrefix::new(addr, *len)
+                .filter(|p| set.contains(p))
+                .map(|p| p.to_string())
+        })
+    }
+}
+
+/// How much the snapshot holds, for `/metrics`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GreylistCounts {
+    pub ips: usize,
+    pub prefixes: usize,
+    pub blocked: usize,
+    pub revoked: usize,
+}
 
 #[derive(Clone, Default, Debug)]
 pub struct GreylistSet {
-    inner: Arc<RwLock<HashSet<String>>>,
+    inner: Arc<RwLock<Snapshot>>,
     /// The operator allowlist — the server's OWN public IP(s), fed from the
     /// `IpProviderService` broadcast (the same set that drives the Cloudflare DNS
     /// updates). The mini lives on the operator's home network, so its public IP IS
@@ -21,6 +93,8 @@ pub struct GreylistSet {
     /// (follows a residential IP rotation), zero config — from any OTHER network the
     /// operator just authenticates (an authenticated session is never tolled). Phase DU.
     allow: Arc<RwLock<HashSet<String>>>,
+    /// Revoked clearance token ids — the server-side revocation list.
+    revoked: Arc<RwLock<HashSet<String>>>,
 }
 
 impl GreylistSet {
@@ -30,8 +104,14 @@ impl GreylistSet {
 
     /// Replace the snapshot with the currently-active entries (called by the sweep after a pass).
     pub fn refresh(&self, entries: &[GreylistEntry]) {
-        let set: HashSet<String> = entries.iter().map(|e| e.ip.clone()).collect();
-        *self.inner.write().unwrap() = set;
+        let mut snapshot = Snapshot::default();
+        for e in entries {
+            snapshot.add(&e.ip);
+            if e.tier == GreylistTier::Blocked {
+                snapshot.blocked.insert(e.ip.clone());
+            }
+        }
+        *self.inner.write().unwrap() = snapshot;
     }
 
     /// Set the operator allowlist to the server's current public IP(s) (Phase DU) — called
@@ -53,23 +133,79 @@ impl GreylistSet {
         v
     }
 
-    /// Whether `ip` is greylisted in the latest snapshot. A short read lock; no DB. The
-    /// operator allowlist WINS over any entry (a snapshot row OR a manual pin) — defense in
-    /// depth so the operator's own network can never be tolled even by a stale/pinned entry.
+    /// Whether `ip` is greylisted in the latest snapshot — listed itself, or inside a listed
+    /// range. A short read lock; no DB. The operator allowlist WINS over any entry (a snapshot
+    /// row, a manual pin, or a range covering it) — defense in depth so the operator's own
+    /// network can never be tolled even by a stale/pinned entry.
     pub fn is_greylisted(&self, ip: &str) -> bool {
-        !self.is_allowlisted(ip) && self.inner.read().unwrap().contains(ip)
+        self.matching_entry(ip).is_some()
+    }
+
+    /// The entry that greylists `ip` (longest prefix wins; the IP's own entry beats any
+    /// range), or `None` if it isn't greylisted or is allowlisted.
+    pub fn matching_entry(&self, ip: &str) -> Option<String> {
+        if self.is_allowlisted(ip) {
+            return None;
+        }
+        self.inner.read().unwrap().lookup(ip)
+    }
+
+    /// Add an IP or CIDR entry directly — used by tests and the admin manual-pin path to
+    /// reflect a change immediately instead of waiting for the next sweep refresh.
+    pub fn insert(&self, entry: &str) {
+        self.inner.write().unwrap().add(entry);
+    }
+
+    /// Remove an IP or CIDR entry immediately (on admin release) so the un-toll takes effect
+    /// without waiting for the next sweep refresh. Releasing a blocked IP unblocks it too.
+    pub fn remove(&self, entry: &str) {
+        let mut snapshot = self.inner.write().unwrap();
+        snapshot.remove(entry);
+        snapshot.blocked.remove(entry);
+    }
+
+    /// Whether `ip` is on the `Blocked` tier (gets a bare 403, no toll). The allowlist wins
+    /// here too.
+    pub fn is_blocked(&self, ip: &str) -> bool {
+        !self.is_allowlisted(ip) && self.inner.read().unwrap().blocked.contains(ip)
+    }
+
+    /// Escalate `ip` immediately (it's greylisted too, so de-escalating leaves it tolled).
+    pub fn block(&self, ip: &str) {
+        let mut snapshot = self.inner.write().unwrap();
+        snapshot.add(ip);
+        snapshot.blocked.insert(ip.to_string());
     }
 
-    /// Add a single IP directly — used by tests and the admin manual-pin path to reflect a change
-    /// immediately instead of waiting for the next sweep refresh.
-    pub fn insert(&self, ip: &str) {
-        self.inner.write().unwrap().insert(ip.to_string());
+    /// De-escalate `ip` back to the toll immediately.
+    pub fn unblock(&self, ip: &str) {
+        self.inner.write().unwrap().blocked.remove(ip);
     }
 
-    /// Remove an IP immediately (on admin release) so the un-toll takes effect without waiting
-    /// for the next sweep refresh.
-    pub fn remove(&self, ip: &str) {
-        self.inner.write().unwrap().remove(ip);
+    /// Replace the revocation list (called by the sweep after a pass).
+    pub fn set_revoked(&self, token_ids: impl IntoIterator<Item = String>) {
+        *self.revoked.write().unwrap() = token_ids.into_iter().collect();
+    }
+
+    /// Revoke one clearance token id immediately.
+    pub fn revoke(&self, token_id: &str) {
+        self.revoked.write().unwrap().insert(token_id.to_string());
+    }
+
+    /// Whether a clearance token id has been revoked — `verify_clearance`'s `is_revoked`.
+    pub fn is_revoked(&self, token_id: &str) -> bool {
+        self.revoked.read().unwrap().contains(token_id)
+    }
+
+    /// Listed IPs and ranges, blocked IPs and revoked clearances in the current snapshot.
+    pub fn counts(&self) -> GreylistCounts {
+        let snapshot = self.inner.read().unwrap();
+        GreylistCounts {
+            ips: snapshot.ips.len(),
+            prefixes: snapshot.prefixes.values().map(HashSet::len).sum(),
+            blocked: snapshot.blocked.len(),
+            revoked: self.revoked.read().unwrap().len(),
+        }
     }
 }
 
@@ -101,4 +237,63 @@ mod tests {
         assert!(!set.is_allowlisted(op));
         assert!(set.is_greylisted(op), "the previous public IP is no longer exempt after rotation");
     }
+
+    #[test]
+    fn ranges_match_longest_prefix_first() {
+        let set = GreylistSet::new();
+        set.insert("185.177.72.0/24");
+        set.insert("185.177.0.0/16");
+        set.insert("2001:db8:1:2::/64");
+
+        assert_eq!(set.matching_entry("185.177.72.9").as_deref(), Some("185.177.72.0/24"));
+        assert_eq!(set.matching_entry("185.177.9.9").as_deref(), Some("185.177.0.0/16"));
+        assert_eq!(set.matching_entry("185.178.0.1"), None);
+        assert!(set.is_greylisted("2001:db8:1:2:dead::beef"));
+        assert!(!set.is_greylisted("2001:db8:1:3::1"));
+
+        // An IP's own entry is the most specific of all.
+        set.insert("185.177.72.9");
+        assert_eq!(set.matching_entry("185.177.72.9").as_deref(), Some("185.177.72.9"));
+
+        // Releasing a range leaves the wider one (and the IP entry) in force.
+        set.remove("185.177.72.0/24");
+        assert_eq!(set.matching_entry("185.177.72.10").as_deref(), Some("185.177.0.0/16"));
+        set.remove("185.177.0.0/16");
+        assert!(!set.is_greylisted("185.177.72.10"));
+        assert!(set.is_greylisted("185.177.72.9"));
+
+        // The operator allowlist wins over a covering range too.
+        set.insert("203.0.113.0/24");
+        set.set_public_ips(&HashSet::from(["203.0.113.9".parse::<IpAddr>().unwrap()]));
+        assert!(!set.is_greylisted("203.0.113.9"));
+        assert!(set.is_greylisted("203.0.113.10"));
+    }
+
+    #[test]
+    fn blocked_ips_and_revoked_tokens() {
+        let set = GreylistSet::new();
+        set.block("198.51.100.4");
+        assert!(set.is_blocked("198.51.100.4"));
+        assert!(set.is_greylisted("198.51.100.4"), "a blocked IP is greylisted too");
+        set.unblock("198.51.100.4");
+        assert!(!set.is_blocked("198.51.100.4"));
+        assert!(set.is_greylisted("198.51.100.4"), "de-escalation leaves the toll");
+
+        set.block("203.0.113.9");
+        set.set_public_ips(&HashSet::from(["203.0.113.9".parse::<IpAddr>().unwrap()]));
+        assert!(!set.is_blocked("203.0.113.9"), "the allowlist wins over a block");
+
+        set.revoke("tok-a");
+        assert!(set.is_revoked("tok-a"));
+        set.set_revoked(["tok-b".to_string()]);
+        assert!(!set.is_revoked("tok-a"), "a refresh replaces the list");
+        assert!(set.is_revoked("tok-b"));
+
+        set.insert("185.177.72.0/24");
+        let counts = set.counts();
+        assert_eq!(
+            (counts.ips, counts.prefixes, counts.blocked, counts.revoked),
+            (2, 1, 1, 1)
+        );
+    }
 }
@@ -129,30 +129,49 @@ pub fn verify_answer(
     Ok(ct_eq(&expected, submitted_answer))
 }
 
-/// Mint a bearer clearance token `"<expiry_unix>.<hex_mac>"`, `mac = HMAC(server_key, expiry)`.
-/// Deliberately NOT IP-bound (mobile IPs churn — design doc). The caller sets
-/// HttpOnly+Secure+SameSite on the `Set-Cookie`.
-pub fn mint_clearance(server_key: &[u8], expiry: i64) -> Result<String> {
-    let mac = hmac_sha256(server_key, &expiry.to_le_bytes())?;
-    Ok(format!("{expiry}.{}", to_hex(&mac)))
+/// Mint a bearer clearance token `"<expiry_unix>.<token_id>.<hex_mac>"`,
+/// `mac = HMAC(server_key, expiry ‖ token_id)`. `token_id` is a fresh random id per solve —
+/// it's what the clear-then-scan escalation revokes, so it must be unique (two solves in the
+/// same second would otherwise mint the SAME token). Deliberately NOT IP-bound (mobile IPs
+/// churn — design doc). The caller sets HttpOnly+Secure+SameSite on the `Set-Cookie`.
+pub fn mint_clearance(server_key: &[u8], expiry: i64, token_id: &str) -> Result<String> {
+    let mac = clearance_mac(server_key, expiry, token_id)?;
+    Ok(format!("{expiry}.{token_id}.{}", to_hex(&mac)))
 }
 
-/// Verify a clearance token: re-derive the MAC over the CLAIMED expiry, constant-time compare,
-/// and require `expiry > now`. Any malformed / expired / forged token → `false`.
-pub fn verify_clearance(server_key: &[u8], token: &str, now: i64) -> bool {
-    let Some((exp_s, mac_hex)) = token.split_once('.') else {
+fn clearance_mac(server_key: &[u8], expiry: i64, token_id: &str) -> Result<[u8; 32]> {
+    let mut msg = Vec::with_capacity(8 + token_id.len());
+    msg.extend_from_slice(&expiry.to_le_bytes());
+    msg.extend_from_slice(token_id.as_bytes());
+    hmac_sha256(server_key, &msg)
+}
+
+/// Verify a clearance token: re-derive the MAC over the CLAIMED expiry + id, constant-time
+/// compare, require `expiry > now`, and require the id NOT be on the revocation list
+/// (`is_revoked` — the escalation's server-side list, see `active_set::GreylistSet`). Any
+/// malformed / expired / forged / revoked token → `false`. A pre-escalation two-part token
+/// (no id) is malformed: it can't be revoked, so it's not honored.
+pub fn verify_clearance(
+    server_key: &[u8],
+    token: &str,
+    now: i64,
+    is_revoked: impl Fn(&str) -> bool,
+) -> bool {
+    let mut parts = token.splitn(3, '.');
+    let (Some(exp_s), Some(token_id), Some(mac_hex)) = (parts.next(), parts.next(), parts.next())
+    else {
         return false;
     };
     let Ok(expiry) = exp_s.parse::<i64>() else {
         return false;
     };
-    if expiry <= now {
+    if expiry <= now || token_id.is_empty() {
         return false;
     }
-    let Ok(expected) = hmac_sha256(server_key, &expiry.to_le_bytes()) else {
+    let Ok(expected) = clearance_mac(server_key, expiry, token_id) else {
         return false;
     };
-    ct_eq(mac_hex.as_bytes(), to_hex(&expected).as_bytes())
+    ct_eq(mac_hex.as_bytes(), to_hex(&expected).as_bytes()) && !is_revoked(token_id)
 }
 
 #[cfg(test)]
@@ -230,17 +249,31 @@ mod tests {
     }
 
     #[test]
-    fn clearance_roundtrips_and_rejects_forgery_and_expiry() {
-        let token = mint_clearance(KEY, 5000).unwrap();
-        assert!(verify_clearance(KEY, &token, 4000), "valid + unexpired");
-        assert!(!verify_clearance(KEY, &token, 5001), "expired");
-        assert!(!verify_clearance(b"wrong-key-wrong-key-wrong-key-42", &token, 4000), "wrong key");
-
-        // Attacker bumps the expiry but can't recompute the MAC.
-        let forged = format!("9999999999.{}", &token.split_once('.').unwrap().1);
-        assert!(!verify_clearance(KEY, &forged, 4000), "expiry tamper fails the MAC");
-        // Garbage.
-        assert!(!verify_clearance(KEY, "not-a-token", 4000));
-        assert!(!verify_clearance(KEY, "5000.zzzz", 4000));
+    fn clearance_roundtrips_and_rejects_forgery_expiry_and_revocation() {
+        let never = |_: &str| false;
+        let token = mint_clearance(KEY, 5000, "id-a").unwrap();
+        assert!(verify_clearance(KEY, &token, 4000, never), "valid + unexpired");
+        assert!(!verify_clearance(KEY, &token, 5001, never), "expired");
+        let wrong_key = b"wrong-key-wrong-key-wrong-key-42";
+        assert!(!verify_clearance(wrong_key, &token, 4000, never), "wrong key");
+
+        // Attacker bumps the expiry, or swaps in another id, but can't recompute the MAC.
+        let (_, rest) = token.split_once('.').unwrap();
+        let bumped = format!("9999999999.{rest}");
+        assert!(!verify_clearance(KEY, &bumped, 4000, never), "expiry tamper");
+        let mac = token.rsplit_once('.').unwrap().1;
+        assert!(!verify_clearance(KEY, &format!("5000.id-b.{mac}"), 4000, never), "id tamper");
+
+        // Same expiry, different ids → different tokens, and revocation is per id.
+        let other = mint_clearance(KEY, 5000, "id-b").unwrap();
+        assert_ne!(token, other);
+        let revoked = |id: &str| id == "id-a";
+        assert!(!verify_clearance(KEY, &token, 4000, revoked), "revoked id");
+        assert!(verify_clearance(KEY, &other, 4000, revoked), "other ids unaffected");
+
+        // Garbage, and the old id-less format.
+        assert!(!verify_clearance(KEY, "not-a-token", 4000, never));
+        assert!(!verify_clearance(KEY, "5000.zzzz", 4000, never));
+        assert!(!verify_clearance(KEY, "5000..zzzz", 4000, never));
     }
 }
@@ -8,11 +8,20 @@
 //! exemption (FCrDNS, CX.3) is applied by the SWEEP for the rules that carry it
 //! ([`Rule::exempts_verified_crawlers`]); R1 (signature probe) never exempts, because
 //! nothing legitimate probes `wp-login.php`.
+//!
+//! Scanner farms rotate through a rented block so no single IP trips much; R4 catches them
+//! one level up — [`group_by_prefix`] aggregates the IPs that DID trip by /24 (v4) or /64
+//! (v6), and [`score_prefix`] greylists the whole range once enough siblings have.
+//!
+//! R5 (opt-in, `geoip.weigh_hosting_asns`) rescores an IP the other rules cleared when it
+//! sits on a hosting provider's network: humans rarely browse from a datacenter, so the same
+//! shape of traffic trips on lower counts there ([`score_hosting`]).
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::net::IpAddr;
 
 use crate::db::dao::request_log::IpPathAgg;
+use crate::greylist::prefix::IpPrefix;
 
 /// Path fragments this site NEVER serves — a hit is a dead-certain scanner tell, matched
 /// case-insensitively as a substring of the request path. Single source: also used by the
@@ -82,6 +91,16 @@ pub struct IpFeatures {
     pub signature_hits: i64,
 }
 
+impl IpFeatures {
+    /// The counts as one line — the `evidence` stored with a verdict.
+    pub fn evidence(&self) -> String {
+        format!(
+            "signature_hits={} distinct_404={} errors={} total={} distinct_paths={}",
+            self.signature_hits, self.distinct_404, self.errors, self.total, self.distinct_paths
+        )
+    }
+}
+
 /// Group raw `(ip, path, status, count)` aggregates into per-IP features, dropping IPs that
 /// shouldn't be evaluated (loopback / private / unparseable) up front.
 pub fn build_features(rows: &[IpPathAgg]) -> Vec<IpFeatures> {
@@ -155,6 +174,21 @@ pub const R2_DISTINCT_404_MIN: i64 = 40;
 /// Verified crawlers exempt.
 pub const R3_FLOOD_MIN: i64 = 1000;
 
+/// R4 — scanner farm: this many IPs in one /24 (v4) or /64 (v6) greylisted by R1–R3 in the
+/// same sweep window greylists the whole prefix. The snapshot's worst block ran 8+ IPs in a
+/// /24, each tripping R1 independently; 3 needs a real pattern (two unlucky neighbours on a
+/// shared NAT pool don't qualify) yet catches the farm before it finishes rotating.
+pub const R4_SIBLINGS_MIN: usize = 3;
+
+/// R5 — hosting-ASN scanner: the R1–R3 bars, lowered for an IP on a hosting provider's
+/// network. One signature probe from a datacenter is no stray referred link, and a quarter
+/// of the R2/R3 counts still sits far above what the operator's own browsing produces —
+/// while a VPN user on a cloud exit node stays well clear of either. Verified crawlers
+/// (which mostly run from their owners' clouds) stay exempt.
+pub const R5_SIGNATURE_MIN: i64 = 1;
+pub const R5_DISTINCT_404_MIN: i64 = R2_DISTINCT_404_MIN / 4;
+pub const R5_FLOOD_MIN: i64 = R3_FLOOD_MIN / 4;
+
 /// Which rule tripped — carried on the verdict so the sweep knows whether the verified-crawler
 /// exemption applies and so the evidence names the rule.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -162,14 +196,17 @@ pub enum Rule {
     SignatureProbe,
     Distinct404Burst,
     Flood,
+    SiblingScanners,
+    HostingNetwork,
 }
 
 impl Rule {
     /// Whether an FCrDNS-verified search crawler is EXEMPT from this rule. R1 never exempts
     /// (nothing legitimate probes signature paths); the blunt rules do (a real crawler can
-    /// trip a 404 burst or look like volume after a restructure).
+    /// trip a 404 burst or look like volume after a restructure). R4 doesn't either: it only
+    /// counts siblings that were greylisted AFTER their own exemption check.
     pub fn exempts_verified_crawlers(self) -> bool {
-        !matches!(self, Rule::SignatureProbe)
+        !matches!(self, Rule::SignatureProbe | Rule::SiblingScanners)
     }
 
     pub fn label(self) -> &'static str {
@@ -177,6 +214,8 @@ impl Rule {
             Rule::SignatureProbe => "R1: signature probe",
             Rule::Distinct404Burst => "R2: 404 burst",
             Rule::Flood => "R3: flood",
+            Rule::SiblingScanners => "R4: scanner farm",
+            Rule::HostingNetwork => "R5: hosting-network scanner",
         }
     }
 }
@@ -208,15 +247,88 @@ pub fn score(f: &IpFeatures) -> Verdict {
         Some(rule) => Verdict::Greylist {
             rule,
             reason: rule.label().to_string(),
-            evidence: format!(
-                "signature_hits={} distinct_404={} errors={} total={} distinct_paths={}",
-                f.signature_hits, f.distinct_404, f.errors, f.total, f.distinct_paths
-            ),
+            evidence: f.evidence(),
         },
         None => Verdict::Clear,
     }
 }
 
+/// Score an IP the rules cleared against the lowered [`R5_SIGNATURE_MIN`] /
+/// [`R5_DISTINCT_404_MIN`] / [`R5_FLOOD_MIN`] bars. Only for an IP on a hosting network —
+/// `network` (e.g. `AS24940 Hetzner Online GmbH`) is the caller's lookup, named in the
+/// evidence so the admin view shows why the lower bar applied.
+pub fn score_hosting(f: &IpFeatures, network: &str) -> Verdict {
+    if f.signature_hits >= R5_SIGNATURE_MIN
+        || f.distinct_404 >= R5_DISTINCT_404_MIN
+        || f.total >= R5_FLOOD_MIN
+    {
+        Verdict::Greylist {
+            rule: Rule::HostingNetwork,
+            reason: Rule::HostingNetwork.label().to_string(),
+            evidence: format!("{} network={network}", f.evidence()),
+        }
+    } else {
+        Verdict::Clear
+    }
+}
+
+/// The IPs in one prefix that tripped a per-IP rule this window — the input to
+/// [`score_prefix`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrefixFeatures {
+    pub prefix: IpPrefix,
+    /// `(ip, rule)` per tripped member, sorted by IP.
+    pub members: Vec<(String, Rule)>,
+    /// Requests across those members over the window.
+    pub total: i64,
+}
+
+/// Group the IPs the sweep greylisted this pass by their detection prefix (/24 or /64).
+/// Unparseable IPs are dropped (can't place them in a range).
+pub fn group_by_prefix(tripped: &[(&IpFeatures, Rule)]) -> Vec<PrefixFeatures> {
+    let mut map: BTreeMap<IpPrefix, PrefixFeatures> = BTreeMap::new();
+    for (f, rule) in tripped {
+        let Ok(ip) = f.ip.parse::<IpAddr>() else { continue };
+        let prefix = IpPrefix::group_of(ip);
+        let acc = map.entry(prefix).or_insert_with(|| PrefixFeatures {
+            prefix,
+            members: Vec::new(),
+            total: 0,
+        });
+        acc.members.push((f.ip.clone(), *rule));
+        acc.total += f.total;
+    }
+    map.into_values()
+        .map(|mut p| {
+            p.members.sort_by(|a, b| a.0.cmp(&b.0));
+            p
+        })
+        .collect()
+}
+
+/// Score one prefix: [`Rule::SiblingScanners`] once [`R4_SIBLINGS_MIN`] members tripped. The
+/// evidence names every member and its rule, so the admin view shows WHY a range is listed.
+pub fn score_prefix(p: &PrefixFeatures) -> Verdict {
+    if p.members.len() < R4_SIBLINGS_MIN {
+        return Verdict::Clear;
+    }
+    let members: Vec<String> = p
+        .members
+        .iter()
+        .map(|(ip, rule)| format!("{ip}({})", &rule.label()[..2]))
+        .collect();
+    Verdict::Greylist {
+        rule: Rule::SiblingScanners,
+        reason: Rule::SiblingScanners.label().to_string(),
+        evidence: format!(
+            "siblings={} total={} members={}",
+            p.members.len(),
+            p.total,
+            members.join(",")
+        ),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -350,6 +462,47 @@ mod tests {
         assert!(!Rule::SignatureProbe.exempts_verified_crawlers());
         assert!(Rule::Distinct404Burst.exempts_verified_crawlers());
         assert!(Rule::Flood.exempts_verified_crawlers());
+        assert!(!Rule::SiblingScanners.exempts_verified_crawlers());
+    }
+
+    #[test]
+    fn r4_greylists_a_prefix_once_enough_siblings_trip() {
+        let at = |ip: &str| IpFeatures {
+            ip: ip.into(),
+            ..features(R1_SIGNATURE_MIN, 0, 10)
+        };
+        let farm: Vec<IpFeatures> = (1..=R4_SIBLINGS_MIN)
+            .map(|i| at(&format!("185.177.72.{i}")))
+            .collect();
+        let lone = at("198.51.100.9");
+        let v6 = at("2001:db8:1:2::9");
+
+        let mut tripped: Vec<(&IpFeatures, Rule)> =
+            farm.iter().map(|f| (f, Rule::SignatureProbe)).collect();
+        tripped.push((&lone, Rule::Flood));
+        tripped.push((&v6, Rule::SignatureProbe));
+        let groups = group_by_prefix(&tripped);
+        assert_eq!(groups.len(), 3, "{groups:?}");
+
+        let farm_group = groups
+            .iter()
+            .find(|g| g.prefix.to_string() == "185.177.72.0/24")
+            .unwrap();
+        assert_eq!(farm_group.members.len(), R4_SIBLINGS_MIN);
+        assert_eq!(farm_group.total, 10 * R4_SIBLINGS_MIN as i64);
+        let Verdict::Greylist { rule, evidence, .. } = score_prefix(farm_group) else {
+            panic!("the farm's /24 should trip R4");
+        };
+        assert_eq!(rule, Rule::SiblingScanners);
+        assert!(evidence.contains("185.177.72.1(R1)"), "{evidence}");
+
+        // One short of the threshold stays clear.
+        let mut short = farm_group.clone();
+        short.members.pop();
+        assert_eq!(score_prefix(&short), Verdict::Clear);
+        for g in groups.iter().filter(|g| g.prefix != farm_group.prefix) {
+            assert_eq!(score_prefix(g), Verdict::Clear, "{g:?}");
+        }
     }
 
     #[test]
@@ -363,4 +516,27 @@ mod tests {
             Verdict::Clear
         );
     }
+
+    #[test]
+    fn hosting_networks_trip_on_lower_counts() {
+        let net = "AS24940 Hetzner Online GmbH";
+        // Clear by the ordinary rules, but over every R5 bar on its own.
+        for f in [
+            features(R5_SIGNATURE_MIN, 0, 0),
+            features(0, R5_DISTINCT_404_MIN, 0),
+            features(0, 0, R5_FLOOD_MIN),
+        ] {
+            assert_eq!(score(&f), Verdict::Clear);
+            let Verdict::Greylist { rule, evidence, .. } = score_hosting(&f, net) else {
+                panic!("{f:?} should trip R5");
+            };
+            assert_eq!(rule, Rule::HostingNetwork);
+            assert!(rule.exempts_verified_crawlers());
+            assert!(evidence.ends_with("network=AS24940 Hetzner Online GmbH"), "{evidence}");
+        }
+        assert_eq!(
+            score_hosting(&features(0, R5_DISTINCT_404_MIN - 1, R5_FLOOD_MIN - 1), net),
+            Verdict::Clear
+        );
+    }
 }
@@ -0,0 +1,222 @@
+//! Clear-then-scan escalation: the toll's answer to a scraper that pays its way in.
+//!
+//! Solving the cat-toll is a strong human signal — but a headless browser can solve it too,
+//! and the clearance is a bearer cookie, so a paid-up scanner could keep probing for a week.
+//! Each sweep pass correlates every live `greylist_clearance` row with the requests its IP
+//! made AFTER solving; a clearance followed by R1-grade signature probing (the same count and
+//! the same zero-false-positive paths as R1 itself) is revoked — its token id goes on the
+//! revocation list `verify_clearance` consults — and the IP is escalated to the `Blocked` tier,
+//! which gets a bare 403 with no challenge to solve again.
+//!
+//! Escalations are listed on `/admin/greylist` and reversible there (de-escalate → back to
+//! the toll, clearances honored again).
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use sqlx::SqlitePool;
+use sqlx::types::chrono::{DateTime, Utc};
+use tracing::info;
+
+use crate::db::dao::greylist::{GreylistDao, PostClearanceHit};
+use crate::greylist::active_set::GreylistSet;
+use crate::greylist::detection::{R1_SIGNATURE_MIN, is_signature_path, should_evaluate};
+use crate::retention::CLEARANCE_TTL_DAYS;
+
+/// How long an escalated IP stays blocked. Longer than a plain toll's slide: it already
+/// proved it can solve the toll, so letting it lapse back soon just re-opens the door.
+pub const BLOCK_TTL_DAYS: i64 = 30;
+
+/// The `reason` an escalated greylist row carries.
+pub const ESCALATION_REASON: &str = "escalated: probed after clearing the toll";
+
+/// A clearance whose IP went on to probe signature paths.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Escalation {
+    pub clearance_id: i64,
+    pub token_id: String,
+    pub ip: String,
+    pub signature_hits: i64,
+    /// The probed paths, for the admin view.
+    pub evidence: String,
+}
+
+/// Pick the clearances to revoke out of their post-clearance hits: signature-path errors
+/// (4xx/5xx — a served path never counts) totalling at least [`R1_SIGNATURE_MIN`]. Pure, so
+/// the threshold is testable without a database.
+pub fn correlate(hits: &[PostClearanceHit]) -> Vec<Escalation> {
+    let mut by_clearance: BTreeMap<i64, Escalation> = BTreeMap::new();
+    let mut paths: BTreeMap<i64, Vec<&str>> = BTreeMap::new();
+    for h in hits {
+        let Some(token_id) = &h.token_id else {
+            continue;
+        };
+        if h.status < 400 || !is_signature_path(&h.path) {
+            continue;
+        }
+        by_clearance
+            .entry(h.clearance_id)
+            .or_insert_with(|| Escalation {
+                clearance_id: h.clearance_id,
+                token_id: token_id.clone(),
+                ip: h.ip.clone(),
+                signature_hits: 0,
+                evidence: String::new(),
+            })
+            .signature_hits += h.count;
+        paths.entry(h.clearance_id).or_default().push(&h.path);
+    }
+    by_clearance
+        .into_values()
+        .filter(|e| e.signature_hits >= R1_SIGNATURE_MIN)
+        .map(|mut e| {
+            let mut probed = paths.remove(&e.clearance_id).unwrap_or_default();
+            probed.sort_unstable();
+            probed.dedup();
+            e.evidence = format!(
+                "signature_hits={} after clearing: {}",
+                e.signature_hits,
+                probed.join(" ")
+            );
+            e
+        })
+        .collect()
+}
+
+fn days_from(now: DateTime<Utc>, days: i64) -> DateTime<Utc> {
+    DateTime::<Utc>::from_timestamp(now.timestamp() + days * 86_400, 0).unwrap_or(now)
+}
+
+/// The sweep step: revoke + escalate every clearance [`correlate`] flags (never the operator's
+/// own IP, nor a private one the sweep wouldn't score), then reload the revocation list into
+/// `set`. Returns the clearances escalated.
+pub async fn run_once(pool: &SqlitePool, set: &GreylistSet) -> Result<usize> {
+    let now = Utc::now();
+    let live_since = days_from(now, -CLEARANCE_TTL_DAYS);
+    let hits = GreylistDao::post_clearance_hits(pool, live_since).await?;
+
+    let mut escalated = 0;
+    for e in correlate(&hits) {
+        if !should_evaluate(&e.ip) || set.is_allowlisted(&e.ip) {
+            continue;
+        }
+        GreylistDao::revoke_clearance(pool, e.clearance_id).await?;
+        GreylistDao::escalate(
+            pool,
+            &e.ip,
+            ESCALATION_REASON,
+            &e.evidence,
+            days_from(now, BLOCK_TTL_DAYS),
+        )
+        .await?;
+        set.revoke(&e.token_id);
+        set.block(&e.ip);
+        info!(
+            "greylist sweep: escalated {} to blocked ({})",
+            e.ip, e.evidence
+        );
+        escalated += 1;
+    }
+
+    refresh_revocations(pool, set).await?;
+    Ok(escalated)
+}
+
+/// Reload the revocation list into `set` (every pass, and at boot before the first one).
+pub async fn refresh_revocations(pool: &SqlitePool, set: &GreylistSet) -> Result<()> {
+    let live_since = days_from(Utc::now(), -CLEARANCE_TTL_DAYS);
+    set.set_revoked(GreylistDao::revoked_token_ids(pool, live_since).await?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::net::IpAddr;
+
+    fn hit(clearance_id: i64, path: &str, status: i64, count: i64) -> PostClearanceHit {
+        PostClearanceHit {
+            clearance_id,
+            token_id: Some(format!("tok-{clearance_id}")),
+            ip: format!("203.0.113.{clearance_id}"),
+            path: path.into(),
+            status,
+            count,
+        }
+    }
+
+    #[test]
+    fn only_signature_probing_after_a_clear_escalates() {
+        let hits = vec![
+            // 1: reads pages, then trips over one stray probe — below R1.
+            hit(1, "/pages/home", 200, 30),
+            hit(1, "/.env", 404, 1),
+            // 2: clears, then scans.
+            hit(2, "/wp-login.php", 404, 1),
+            hit(2, "/.env", 404, 2),
+            hit(2, "/", 200, 1),
+            // 3: a signature-looking path that was SERVED doesn't count.
+            hit(3, "/.vscode", 200, 5),
+        ];
+        let out = correlate(&hits);
+        assert_eq!(out.len(), 1);
+        let e = &out[0];
+        assert_eq!((e.clearance_id, e.signature_hits), (2, 3));
+        assert_eq!(
+            (e.ip.as_str(), e.token_id.as_str()),
+            ("203.0.113.2", "tok-2")
+        );
+        assert_eq!(
+            e.evidence,
+            "signature_hits=3 after clearing: /.env /wp-login.php"
+        );
+
+        // A legacy clearance with no token id can't be revoked, so it's not flagged.
+        let mut legacy = hit(4, "/.env", 404, 5);
+        legacy.token_id = None;
+        assert!(correlate(&[legacy]).is_empty());
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn escalation_revokes_and_blocks_but_spares_the_operator(pool: SqlitePool) -> Result<()> {
+        let scraper = "203.0.113.80";
+        let operator = "203.0.113.81";
+        for ip in [scraper, operator] {
+            GreylistDao::record_clearance(&pool, ip, &format!("tok-{ip}"), None, None, None)
+                .await?;
+        }
+        sqlx::query!("UPDATE greylist_clearance SET cleared_at = datetime('now', '-1 hour')")
+            .execute(&pool)
+            .await?;
+        for ip in [scraper, operator] {
+            for p in ["/wp-login.php", "/.env"] {
+                sqlx::query!(
+                    "INSERT INTO request_log (method, path, status, ip) VALUES ('GET', ?1, 404, ?2)",
+                    p,
+                    ip
+                )
+                .execute(&pool)
+                .await?;
+            }
+        }
+
+        let set = GreylistSet::new();
+        set.set_public_ips(&HashSet::from([operator.parse::<IpAddr>().unwrap()]));
+        assert_eq!(run_once(&pool, &set).await?, 1);
+        assert!(set.is_blocked(scraper));
+        assert!(set.is_revoked(&format!("tok-{scraper}")));
+        assert!(!set.is_revoked(&format!("tok-{operator}")));
+
+        let active = GreylistDao::active(&pool).await?;
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].ip, scraper);
+        assert_eq!(active[0].reason, ESCALATION_REASON);
+
+        // Idempotent: the revoked clearance isn't correlated again, the list survives a reload.
+        let fresh = GreylistSet::new();
+        assert_eq!(run_once(&pool, &fresh).await?, 0);
+        assert!(fresh.is_revoked(&format!("tok-{scraper}")));
+        Ok(())
+    }
+}
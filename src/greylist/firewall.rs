@@ -0,0 +1,355 @@
+//! Firewall export of the active greylist. The toll only acts at the HTTP layer, so a
+//! listed scanner still costs a TCP + TLS handshake per request before it's turned away;
+//! handing the set to the host firewall moves the cheapest cases below that.
+//!
+//! Opt-in via `firewall_export` in the settings file. After each timed sweep pass the
+//! active entries are rendered as two sets — every active entry, and the `Blocked` tier
+//! alone — so the operator's ruleset can rate-limit the toll and drop the blocked:
+//!
+//! - **nftables**: an `nft -f` script that (idempotently) declares the table and
+//!   `greylist_v4` / `greylist_v6` / `greylist_blocked_v4` / `greylist_blocked_v6` interval
+//!   sets, then flushes and refills them. `nft -f` applies a file as one transaction, and
+//!   only the sets are touched, so rules the operator keeps in the same table survive.
+//! - **pf**: `table <greylist> persist { … }` and `table <greylist_blocked> persist { … }`
+//!   definitions, for an `include` in pf.conf or `pfctl -a <anchor> -f`.
+//!
+//! The file is replaced atomically (write a sibling, then rename) and only when its
+//! contents change, and the optional hook (an argv, not a shell line — e.g.
+//! `["/usr/sbin/nft", "-f", "/etc/nftables.d/greylist.nft"]`) runs after each rewrite. The
+//! operator allowlist wins here as it does on the request path: an allowlisted IP, or a
+//! range holding one, is never exported. A failed export only warns — the HTTP toll still
+//! enforces.
+
+use std::fmt::Write as _;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use sqlx::SqlitePool;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use crate::db::dao::greylist::{GreylistDao, GreylistEntry, GreylistTier};
+use crate::greylist::active_set::GreylistSet;
+use crate::greylist::prefix::{IpPrefix, entry_covers_any};
+use crate::settings::{FirewallExport, FirewallFormat};
+
+/// The set holding every active entry; the `Blocked` tier's set is this plus `_blocked`.
+pub const SET_NAME: &str = "greylist";
+
+/// How long the post-write hook may run before it's killed.
+pub const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The entries split by address family, each list sorted and de-duplicated so the
+/// rendered file (and so the change check) is stable across passes.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Families {
+    v4: Vec<String>,
+    v6: Vec<String>,
+}
+
+impl Families {
+    fn of<'a>(entries: impl Iterator<Item = &'a str>) -> Self {
+        let mut out = Self::default();
+        for entry in entries {
+            let addr = match entry.split_once('/') {
+                Some((addr, _)) if IpPrefix::parse(entry).is_some() => addr,
+                Some(_) => continue,
+                None => entry,
+            };
+            let Ok(ip) = addr.parse::<IpAddr>() else {
+                continue;
+            };
+            if ip.is_ipv4() {
+                out.v4.push(entry.to_string());
+            } else {
+                out.v6.push(entry.to_string());
+            }
+        }
+        for list in [&mut out.v4, &mut out.v6] {
+            list.sort();
+            list.dedup();
+        }
+        out
+    }
+
+    fn all(&self) -> impl Iterator<Item = &String> {
+        self.v4.iter().chain(&self.v6)
+    }
+}
+
+/// Render `entries` (already filtered to the active ones) in `format`. Pure, so the output
+/// is unit-testable.
+pub fn render(
+    format: FirewallFormat,
+    nft_table: &str,
+    entries: &[GreylistEntry],
+    allowlisted: &[IpAddr],
+) -> String {
+    let exported: Vec<&GreylistEntry> = entries
+        .iter()
+        .filter(|e| !entry_covers_any(&e.ip, allowlisted))
+        .collect();
+    let active = Families::of(exported.iter().map(|e| e.ip.as_str()));
+    let blocked = Families::of(
+        exported
+            .iter()
+            .filter(|e| e.tier == GreylistTier::Blocked)
+            .map(|e| e.ip.as_str()),
+    );
+    let blocked_name = format!("{SET_NAME}_blocked");
+    match format {
+        FirewallFormat::Nftables => {
+            let mut out = String::from("# Active greylist, written by the sweep. Do not edit.\n");
+            let _ = writeln!(out, "add table {nft_table}");
+            for (name, families) in [(SET_NAME, &active), (blocked_name.as_str(), &blocked)] {
+                nft_set(
+                    &mut out,
+                    nft_table,
+                    &format!("{name}_v4"),
+                    "ipv4_addr",
+                    &families.v4,
+                );
+                nft_set(
+                    &mut out,
+                    nft_table,
+                    &format!("{name}_v6"),
+                    "ipv6_addr",
+                    &families.v6,
+                );
+            }
+            out
+        }
+        FirewallFormat::Pf => {
+            let mut out = String::from("# Active greylist, written by the sweep. Do not edit.\n");
+            for (name, families) in [(SET_NAME, &active), (blocked_name.as_str(), &blocked)] {
+                pf_table(&mut out, name, families);
+            }
+            out
+        }
+    }
+}
+
+fn nft_set(out: &mut String, table: &str, name: &str, kind: &str, elements: &[String]) {
+    let _ = writeln!(
+        out,
+        "add set {table} {name} {{ type {kind}; flags interval; auto-merge; }}"
+    );
+    let _ = writeln!(out, "flush set {table} {name}");
+    // An empty element list is a syntax error; a flushed set already says "nobody".
+    if !elements.is_empty() {
+        let _ = writeln!(
+            out,
+            "add element {table} {name} {{ {} }}",
+            elements.join(", ")
+        );
+    }
+}
+
+fn pf_table(out: &mut String, name: &str, families: &Families) {
+    let entries: Vec<&str> = families.all().map(String::as_str).collect();
+    if entries.is_empty() {
+        let _ = writeln!(out, "table <{name}> persist");
+    } else {
+        let _ = writeln!(out, "table <{name}> persist {{ {} }}", entries.join(" "));
+    }
+}
+
+/// Replace `path` with `contents` atomically: write a sibling temp file, sync it, rename it
+/// over. A reader (or a firewall reload racing the sweep) sees the old file or the new one,
+/// never half of one.
+async fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("firewall export path {path:?} has no file name"))?;
+    let mut tmp_name = file_name.to_os_string();
+    tmp_name.push(".tmp");
+    let tmp: PathBuf = path.with_file_name(tmp_name);
+
+    let mut file = tokio::fs::File::create(&tmp)
+        .await
+        .with_context(|| format!("creating {tmp:?}"))?;
+    tokio::io::AsyncWriteExt::write_all(&mut file, contents.as_bytes()).await?;
+    file.sync_all().await?;
+    drop(file);
+    tokio::fs::rename(&tmp, path)
+        .await
+        .with_context(|| format!("renaming {tmp:?} over {path:?}"))?;
+    Ok(())
+}
+
+/// Run the hook, killing it if it outlives `timeout` — a hung hook left running would be
+/// joined by another one every sweep.
+async fn run_hook(argv: &[String], timeout: Duration) -> Result<()> {
+    let Some((bin, args)) = argv.split_first() else {
+        return Ok(());
+    };
+    let mut child = Command::new(bin)
+        .args(args)
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("failed to run firewall hook {bin}"))?;
+    let Ok(status) = tokio::time::timeout(timeout, child.wait()).await else {
+        // Kill and reap it here rather than leave that to the drop.
+        let _ = child.kill().await;
+        bail!("firewall hook {bin} timed out after {timeout:?} and was killed");
+    };
+    let status = status.with_context(|| format!("failed to wait on firewall hook {bin}"))?;
+    if !status.success() {
+        bail!("firewall hook {bin} exited with {status}");
+    }
+    Ok(())
+}
+
+/// Write the active set per `config` (and run its hook) if it changed since the last
+/// write. Returns whether the file was rewritten.
+pub async fn export(pool: &SqlitePool, set: &GreylistSet, config: &FirewallExport) -> Result<bool> {
+    let active = GreylistDao::active(pool).await?;
+    let allowlisted: Vec<IpAddr> = set
+        .allowlisted()
+        .iter()
+        .filter_map(|ip| ip.parse().ok())
+        .collect();
+    let contents = render(config.format, &config.nft_table, &active, &allowlisted);
+
+    if tokio::fs::read_to_string(&config.path)
+        .await
+        .is_ok_and(|existing| existing == contents)
+    {
+        return Ok(false);
+    }
+    write_atomic(&config.path, &contents).await?;
+    info!(
+        "greylist: exported {} active entries to {:?}",
+        active.len(),
+        config.path
+    );
+    if let Some(hook) = &config.hook {
+        run_hook(hook, HOOK_TIMEOUT).await?;
+    }
+    Ok(true)
+}
+
+/// [`export`] for the sweep loop: failures warn and retry on the next pass.
+pub async fn export_or_warn(pool: &SqlitePool, set: &GreylistSet, config: &FirewallExport) {
+    if let Err(e) = export(pool, set, config).await {
+        warn!("greylist: firewall export failed (will retry next pass): {e:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::types::chrono::Utc;
+
+    fn entry(ip: &str, tier: GreylistTier) -> GreylistEntry {
+        GreylistEntry {
+            ip: ip.into(),
+            reason: "R1: signature probe".into(),
+            evidence: None,
+            manual: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            expires_at: None,
+            tier,
+            escalated_at: None,
+        }
+    }
+
+    fn sample() -> Vec<GreylistEntry> {
+        vec![
+            entry("203.0.113.9", GreylistTier::Toll),
+            entry("185.177.72.0/24", GreylistTier::Toll),
+            entry("198.51.100.7", GreylistTier::Blocked),
+            entry("2001:db8::1", GreylistTier::Toll),
+            // The operator's own range — never exported.
+            entry("192.0.2.0/24", GreylistTier::Toll),
+        ]
+    }
+
+    fn operator() -> Vec<IpAddr> {
+        vec!["192.0.2.10".parse().unwrap()]
+    }
+
+    #[test]
+    fn nftables_declares_flushes_and_fills_each_set() {
+        let out = render(FirewallFormat::Nftables, "inet gl", &sample(), &operator());
+        assert_eq!(
+            out.lines().collect::<Vec<_>>(),
+            [
+                "# Active greylist, written by the sweep. Do not edit.",
+                "add table inet gl",
+                "add set inet gl greylist_v4 { type ipv4_addr; flags interval; auto-merge; }",
+                "flush set inet gl greylist_v4",
+                "add element inet gl greylist_v4 { 185.177.72.0/24, 198.51.100.7, 203.0.113.9 }",
+                "add set inet gl greylist_v6 { type ipv6_addr; flags interval; auto-merge; }",
+                "flush set inet gl greylist_v6",
+                "add element inet gl greylist_v6 { 2001:db8::1 }",
+                concat!(
+                    "add set inet gl greylist_blocked_v4 ",
+                    "{ type ipv4_addr; flags interval; auto-merge; }"
+                ),
+                "flush set inet gl greylist_blocked_v4",
+                "add element inet gl greylist_blocked_v4 { 198.51.100.7 }",
+                concat!(
+                    "add set inet gl greylist_blocked_v6 ",
+                    "{ type ipv6_addr; flags interval; auto-merge; }"
+                ),
+                // Nothing blocked over v6: flushed, no (invalid) empty element list.
+                "flush set inet gl greylist_blocked_v6",
+            ]
+        );
+    }
+
+    #[test]
+    fn pf_writes_persistent_tables() {
+        let out = render(FirewallFormat::Pf, "inet filter", &sample(), &operator());
+        assert_eq!(
+            out.lines().collect::<Vec<_>>(),
+            [
+                "# Active greylist, written by the sweep. Do not edit.",
+                "table <greylist> persist { 185.177.72.0/24 198.51.100.7 203.0.113.9 2001:db8::1 }",
+                "table <greylist_blocked> persist { 198.51.100.7 }",
+            ]
+        );
+
+        let empty = render(FirewallFormat::Pf, "inet filter", &[], &[]);
+        assert!(empty.contains("table <greylist> persist\n"), "{empty}");
+    }
+
+    #[tokio::test]
+    async fn write_atomic_replaces_the_file() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("greylist.nft");
+        write_atomic(&path, "one\n").await?;
+        write_atomic(&path, "two\n").await?;
+        assert_eq!(tokio::fs::read_to_string(&path).await?, "two\n");
+        assert!(!dir.path().join("greylist.nft.tmp").exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn a_hung_hook_is_killed_at_the_timeout() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pid_file = dir.path().join("hook.pid");
+        let script = format!("echo $$ > {}; exec sleep 30", pid_file.display());
+        let argv = ["sh", "-c", script.as_str()].map(String::from);
+
+        let started = std::time::Instant::now();
+        let err = run_hook(&argv, Duration::from_millis(500))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{err:?}");
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        // The sleep is gone, not left running behind the sweep.
+        let pid = tokio::fs::read_to_string(&pid_file).await?;
+        let alive = std::process::Command::new("kill")
+            .args(["-0", pid.trim()])
+            .status()?
+            .success();
+        assert!(!alive, "hook {} still running", pid.trim());
+        Ok(())
+    }
+}
@@ -8,8 +8,14 @@ pub mod active_set;
 pub mod challenge;
 pub mod crawler;
 pub mod detection;
+pub mod escalation;
+pub mod firewall;
 pub mod image;
+pub mod model;
+pub mod prefix;
+pub mod reputation;
 pub mod sweep;
+pub mod training;
 
 use std::sync::Arc;
 
@@ -0,0 +1,326 @@
+//! A fitted scoring model — the classifier [`detection::score`] was built to be swapped for.
+//!
+//! Logistic regression over the same [`IpFeatures`] the rules read (log-scaled counts plus
+//! the error ratio), fitted offline by the `train_greylist_model` binary (see
+//! `greylist::training`) and stored as a versioned artifact in `greylist_model`. It does NOT
+//! decide anything yet: the sweep scores every IP with it in SHADOW next to the rules and
+//! records where they disagree, for the admin to read on `/admin/greylist` before it's ever
+//! trusted to greylist on its own.
+//!
+//! Pure, deterministic and dependency-free on purpose: the same samples always fit the same
+//! weights, so a retrain that changes nothing produces an identical artifact.
+//!
+//! [`detection::score`]: crate::greylist::detection::score
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::greylist::detection::{IpFeatures, Rule, Verdict, score};
+
+/// The artifact format the sweep knows how to load. Bump it (and keep the old loader, or
+/// retrain) when [`inputs`] or [`Model`] change shape.
+pub const MODEL_KIND: &str = "logreg-v1";
+
+const INPUTS: usize = 6;
+
+/// What each weight multiplies, in order — shown next to the weights on the admin page.
+pub const INPUT_NAMES: [&str; INPUTS] = [
+    "ln(total)",
+    "ln(distinct paths)",
+    "ln(distinct 404s)",
+    "ln(errors)",
+    "ln(signature hits)",
+    "error ratio",
+];
+
+/// The model's view of one IP. Counts are log-scaled so a 10 000-request flood doesn't
+/// swamp every other input, and the error ratio carries what the counts alone can't.
+fn inputs(f: &IpFeatures) -> [f64; INPUTS] {
+    let ln = |x: i64| (x.max(0) as f64).ln_1p();
+    let ratio = if f.total > 0 {
+        f.errors as f64 / f.total as f64
+    } else {
+        0.0
+    };
+    [
+        ln(f.total),
+        ln(f.distinct_paths),
+        ln(f.distinct_404),
+        ln(f.errors),
+        ln(f.signature_hits),
+        ratio,
+    ]
+}
+
+fn sigmoid(z: f64) -> f64 {
+    1.0 / (1.0 + (-z).exp())
+}
+
+/// A fitted logistic regression: `P(bot) = sigmoid(weights · inputs + bias)`, greylisting
+/// at or above `threshold`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Model {
+    pub weights: Vec<f64>,
+    pub bias: f64,
+    pub threshold: f64,
+}
+
+impl Model {
+    /// Load a stored artifact, refusing a kind or shape this build can't score with.
+    pub fn from_artifact(kind: &str, artifact: &str) -> Result<Self> {
+        if kind != MODEL_KIND {
+            bail!("unsupported greylist model kind {kind:?} (this build reads {MODEL_KIND})");
+        }
+        let model: Model = serde_json::from_str(artifact)?;
+        if model.weights.len() != INPUTS {
+            bail!(
+                "greylist model has {} weights, expected {INPUTS}",
+                model.weights.len()
+            );
+        }
+        Ok(model)
+    }
+
+    pub fn to_artifact(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn probability(&self, f: &IpFeatures) -> f64 {
+        let z: f64 = self.weights.iter().zip(inputs(f)).map(|(w, x)| w * x).sum();
+        sigmoid(z + self.bias)
+    }
+
+    pub fn greylists(&self, f: &IpFeatures) -> bool {
+        self.probability(f) >= self.threshold
+    }
+}
+
+/// One labelled training example: an IP's features over one day, and whether it was a bot.
+#[derive(Clone, Debug)]
+pub struct Sample {
+    pub features: IpFeatures,
+    pub bot: bool,
+}
+
+/// The label for one IP-day. The history is rule-labelled, so the rules' verdict is the
+/// default — except where something better than the rules spoke:
+///
+/// 1. an R1 signature probe is a bot, full stop (zero false positives in the tuning data);
+/// 2. otherwise an IP that SOLVED the toll is human — it ran the JS, which no scanner does;
+/// 3. otherwise an admin's manual pin is a bot (a human judged it);
+/// 4. otherwise whatever R2/R3 said.
+pub fn label(f: &IpFeatures, cleared: bool, pinned: bool) -> bool {
+    match score(f) {
+        Verdict::Greylist {
+            rule: Rule::SignatureProbe,
+            ..
+        } => true,
+        _ if cleared => false,
+        _ if pinned => true,
+        verdict => verdict != Verdict::Clear,
+    }
+}
+
+/// Gradient-descent settings. The defaults converge on site-sized datasets (thousands of
+/// IP-days) in well under a second.
+#[derive(Clone, Copy, Debug)]
+pub struct TrainParams {
+    pub epochs: usize,
+    pub learning_rate: f64,
+    /// L2 penalty on the weights (not the bias), keeping them small where the data is thin.
+    pub l2: f64,
+}
+
+impl Default for TrainParams {
+    fn default() -> Self {
+        Self {
+            epochs: 2000,
+            learning_rate: 0.5,
+            l2: 1e-3,
+        }
+    }
+}
+
+/// Fit a model by full-batch gradient descent on the log loss. Bots are a small minority of
+/// IP-days, so each class is weighted to contribute equally — otherwise "never greylist"
+/// would score well. Starts from zero, so the result is deterministic.
+pub fn train(samples: &[Sample], params: &TrainParams) -> Model {
+    let n = samples.len().max(1) as f64;
+    let bots = samples.iter().filter(|s| s.bot).count() as f64;
+    let humans = samples.len() as f64 - bots;
+    let bot_weight = if bots > 0.0 { n / (2.0 * bots) } else { 1.0 };
+    let human_weight = if humans > 0.0 {
+        n / (2.0 * humans)
+    } else {
+        1.0
+    };
+    let xs: Vec<[f64; INPUTS]> = samples.iter().map(|s| inputs(&s.features)).collect();
+
+    let mut weights = [0.0; INPUTS];
+    let mut bias = 0.0;
+    for _ in 0..params.epochs {
+        let mut grad = [0.0; INPUTS];
+        let mut grad_bias = 0.0;
+        for (x, s) in xs.iter().zip(samples) {
+            let z: f64 = weights.iter().zip(x).map(|(w, x)| w * x).sum::<f64>() + bias;
+            let (target, class_weight) = if s.bot {
+                (1.0, bot_weight)
+            } else {
+                (0.0, human_weight)
+            };
+            let err = class_weight * (sigmoid(z) - target);
+            for (g, x) in grad.iter_mut().zip(x) {
+                *g += err * x;
+            }
+            grad_bias += err;
+        }
+        for (w, g) in weights.iter_mut().zip(grad) {
+            *w -= params.learning_rate * (g / n + params.l2 * *w);
+        }
+        bias -= params.learning_rate * grad_bias / n;
+    }
+    Model {
+        weights: weights.to_vec(),
+        bias,
+        threshold: 0.5,
+    }
+}
+
+/// How a model does against labelled samples — stored with the artifact and shown on the
+/// admin page. Precision is the number that matters before promotion: a false positive
+/// tolls a human.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+    pub samples: usize,
+    pub bots: usize,
+    pub accuracy: f64,
+    pub precision: f64,
+    pub recall: f64,
+}
+
+pub fn evaluate(model: &Model, samples: &[Sample]) -> Metrics {
+    let (mut tp, mut fp, mut tn, mut fn_) = (0usize, 0usize, 0usize, 0usize);
+    for s in samples {
+        match (model.greylists(&s.features), s.bot) {
+            (true, true) => tp += 1,
+            (true, false) => fp += 1,
+            (false, false) => tn += 1,
+            (false, true) => fn_ += 1,
+        }
+    }
+    let ratio = |num: usize, den: usize| {
+        if den == 0 {
+            0.0
+        } else {
+            num as f64 / den as f64
+        }
+    };
+    Metrics {
+        samples: samples.len(),
+        bots: tp + fn_,
+        accuracy: ratio(tp + tn, samples.len()),
+        precision: ratio(tp, tp + fp),
+        recall: ratio(tp, tp + fn_),
+    }
+}
+
+/// Every `HOLDOUT_EVERY`th sample is held out of training to measure the fit honestly.
+const HOLDOUT_EVERY: usize = 5;
+
+/// Split samples into (training, holdout) — deterministic, so a retrain on the same data
+/// reports the same metrics.
+pub fn split_holdout(samples: Vec<Sample>) -> (Vec<Sample>, Vec<Sample>) {
+    let mut train = Vec::with_capacity(samples.len());
+    let mut holdout = Vec::with_capacity(samples.len() / HOLDOUT_EVERY + 1);
+    for (i, s) in samples.into_iter().enumerate() {
+        if i % HOLDOUT_EVERY == HOLDOUT_EVERY - 1 {
+            holdout.push(s);
+        } else {
+            train.push(s);
+        }
+    }
+    (train, holdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::greylist::detection::{R1_SIGNATURE_MIN, R2_DISTINCT_404_MIN};
+
+    fn features(signature_hits: i64, distinct_404: i64, total: i64) -> IpFeatures {
+        IpFeatures {
+            ip: "203.0.113.7".into(),
+            total,
+            distinct_paths: distinct_404.max(1),
+            distinct_404,
+            errors: signature_hits + distinct_404,
+            signature_hits,
+        }
+    }
+
+    fn sample(signature_hits: i64, distinct_404: i64, total: i64, bot: bool) -> Sample {
+        Sample {
+            features: features(signature_hits, distinct_404, total),
+            bot,
+        }
+    }
+
+    #[test]
+    fn labels_prefer_r1_then_clearance_then_pins_then_rules() {
+        let probe = features(R1_SIGNATURE_MIN, 0, 5);
+        let burst = features(0, R2_DISTINCT_404_MIN, 60);
+        let quiet = features(0, 0, 5);
+
+        assert!(
+            label(&probe, true, false),
+            "a cleared IP still probing is a bot"
+        );
+        assert!(!label(&burst, true, false), "solving the toll outranks R2");
+        assert!(label(&burst, false, false));
+        assert!(
+            label(&quiet, false, true),
+            "an admin pin labels a quiet day"
+        );
+        assert!(!label(&quiet, false, false));
+    }
+
+    #[test]
+    fn training_separates_scanners_from_readers() {
+        let mut samples = Vec::new();
+        for i in 0..40 {
+            samples.push(sample(2 + i % 5, 3 + i % 7, 10 + i, true));
+            samples.push(sample(0, i % 2, 3 + i % 9, false));
+        }
+        let model = train(&samples, &TrainParams::default());
+        let m = evaluate(&model, &samples);
+        assert_eq!((m.samples, m.bots), (80, 40));
+        assert!(m.accuracy > 0.95, "{m:?}");
+        assert!(model.greylists(&features(6, 10, 30)));
+        assert!(!model.greylists(&features(0, 0, 4)));
+
+        // Deterministic: the same samples fit the same weights.
+        assert_eq!(train(&samples, &TrainParams::default()), model);
+    }
+
+    #[test]
+    fn artifacts_round_trip_and_refuse_unknown_shapes() {
+        let model = Model {
+            weights: vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6],
+            bias: -1.0,
+            threshold: 0.5,
+        };
+        let artifact = model.to_artifact().unwrap();
+        assert_eq!(Model::from_artifact(MODEL_KIND, &artifact).unwrap(), model);
+        assert!(Model::from_artifact("tree-v1", &artifact).is_err());
+        let short = r#"{"weights":[1.0],"bias":0.0,"threshold":0.5}"#;
+        assert!(Model::from_artifact(MODEL_KIND, short).is_err());
+    }
+
+    #[test]
+    fn holdout_takes_every_fifth_sample() {
+        let samples: Vec<Sample> = (0..10).map(|i| sample(0, 0, i, false)).collect();
+        let (train, holdout) = split_holdout(samples);
+        assert_eq!((train.len(), holdout.len()), (8, 2));
+        assert_eq!(holdout[0].features.total, 4);
+    }
+}
@@ -0,0 +1,147 @@
+//! IP prefixes (CIDR ranges) for subnet-level greylisting. A scanner farm rents a block —
+//! the tuning snapshot's `185.177.72.0/24` ran 8+ IPs — so once enough siblings trip on
+//! their own, the sweep greylists the whole range ([`detection::score_prefix`]). Detection
+//! groups IPv4 by /24 and IPv6 by /64 (one customer's allocation); the admin can pin any
+//! range down to [`MIN_PIN_V4_LEN`] / [`MIN_PIN_V6_LEN`].
+//!
+//! A prefix entry lives in the `greylist` table like an IP entry, its `ip` column holding
+//! the CIDR text (`185.177.72.0/24`) — the `/` is what tells them apart.
+//!
+//! [`detection::score_prefix`]: crate::greylist::detection::score_prefix
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// The prefix detection groups IPv4 siblings by.
+pub const V4_GROUP_LEN: u8 = 24;
+/// The prefix detection groups IPv6 siblings by — the usual single-customer allocation.
+pub const V6_GROUP_LEN: u8 = 64;
+
+/// Shortest range the admin may pin: wider than this starts catching whole ISPs.
+pub const MIN_PIN_V4_LEN: u8 = 16;
+pub const MIN_PIN_V6_LEN: u8 = 32;
+
+/// A network address with its host bits zeroed, plus the prefix length. Equal prefixes
+/// hash equal however they were written (`185.177.72.9/24` == `185.177.72.0/24`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IpPrefix {
+    network: IpAddr,
+    len: u8,
+}
+
+impl IpPrefix {
+    /// The `len`-bit prefix containing `ip`, or `None` if `len` is longer than the family.
+    pub fn new(ip: IpAddr, len: u8) -> Option<Self> {
+        let network = match ip {
+            IpAddr::V4(v4) if len <= 32 => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(len)).unwrap_or(0);
+                IpAddr::V4(Ipv4Addr::from(u32::from(v4) & mask))
+            }
+            IpAddr::V6(v6) if len <= 128 => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(len)).unwrap_or(0);
+                IpAddr::V6(Ipv6Addr::from(u128::from(v6) & mask))
+            }
+            _ => return None,
+        };
+        Some(Self { network, len })
+    }
+
+    /// The sibling group detection aggregates `ip` into (/24 or /64).
+    pub fn group_of(ip: IpAddr) -> Self {
+        let len = if ip.is_ipv4() {
+            V4_GROUP_LEN
+        } else {
+            V6_GROUP_LEN
+        };
+        Self::new(ip, len).expect("group lengths fit their family")
+    }
+
+    /// Parse `a.b.c.d/n` or `x::/n`. A bare address is NOT a prefix here — IP entries
+    /// stay IP entries.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (addr, len) = s.trim().split_once('/')?;
+        Self::new(addr.parse().ok()?, len.parse().ok()?)
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.len
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        Self::new(ip, self.len).is_some_and(|p| p == *self)
+    }
+
+    /// Whether this is narrow enough for an admin pin.
+    pub fn is_pinnable(&self) -> bool {
+        let min = if self.network.is_ipv4() {
+            MIN_PIN_V4_LEN
+        } else {
+            MIN_PIN_V6_LEN
+        };
+        self.len >= min
+    }
+}
+
+impl fmt::Display for IpPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.len)
+    }
+}
+
+/// Whether a `greylist.ip` value is a prefix entry rather than a single IP.
+pub fn is_prefix_entry(entry: &str) -> bool {
+    entry.contains('/')
+}
+
+/// Whether the `greylist.ip` value `entry` is one of `ips`, or a range holding one — how the
+/// operator allowlist is kept out of anything written from the table.
+pub fn entry_covers_any(entry: &str, ips: &[IpAddr]) -> bool {
+    if is_prefix_entry(entry) {
+        IpPrefix::parse(entry).is_some_and(|p| ips.iter().any(|ip| p.contains(*ip)))
+    } else {
+        entry.parse::<IpAddr>().is_ok_and(|ip| ips.contains(&ip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn prefixes_normalize_and_contain() {
+        let p = IpPrefix::parse("185.177.72.9/24").unwrap();
+        assert_eq!(p.to_string(), "185.177.72.0/24");
+        assert_eq!(p, IpPrefix::group_of(ip("185.177.72.200")));
+        assert!(p.contains(ip("185.177.72.1")));
+        assert!(!p.contains(ip("185.177.73.1")));
+        assert!(!p.contains(ip("2001:db8::1")), "families never match");
+
+        let v6 = IpPrefix::group_of(ip("2001:db8:1:2:aaaa::1"));
+        assert_eq!(v6.to_string(), "2001:db8:1:2::/64");
+        assert!(v6.contains(ip("2001:db8:1:2:ffff::9")));
+        assert!(!v6.contains(ip("2001:db8:1:3::1")));
+
+        assert_eq!(
+            IpPrefix::new(ip("1.2.3.4"), 0).unwrap().to_string(),
+            "0.0.0.0/0"
+        );
+        assert!(IpPrefix::parse("1.2.3.4/33").is_none());
+        assert!(
+            IpPrefix::parse("1.2.3.4").is_none(),
+            "a bare IP isn't a prefix"
+        );
+        assert!(IpPrefix::parse("nope/24").is_none());
+    }
+
+    #[test]
+    fn pins_refuse_isp_sized_ranges() {
+        assert!(IpPrefix::parse("185.177.0.0/16").unwrap().is_pinnable());
+        assert!(!IpPrefix::parse("185.0.0.0/8").unwrap().is_pinnable());
+        assert!(IpPrefix::parse("2001:db8::/32").unwrap().is_pinnable());
+        assert!(!IpPrefix::parse("2001::/16").unwrap().is_pinnable());
+    }
+}
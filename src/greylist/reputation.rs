@@ -0,0 +1,344 @@
+//! Operator-supplied IP reputation lists. Detection is behavioural — an IP has to misbehave
+//! here first — but published blocklists (Spamhaus DROP, a feed a cron job pulls down, a
+//! hand-kept file) already know about whole hijacked ranges. `reputation_lists` in the
+//! settings names local files; each is imported into the greylist as ordinary rows with
+//! `reason = "reputation:<name>"`, so the toll, the firewall export and the admin view treat
+//! them like any other entry.
+//!
+//! Formats, auto-detected per line:
+//! - plain: one IP or CIDR per line, `#` comments;
+//! - Spamhaus DROP text: `1.10.16.0/20 ; SBL256894`, `;` comments;
+//! - Spamhaus DROP JSON lines: `{"cidr":"1.10.16.0/20","sblid":…}` (the metadata line is
+//!   ignored).
+//!
+//! A detached loop checks each file's mtime every [`RELOAD_CHECK_INTERVAL`] and reimports
+//! one that changed: entries are (re)listed with the list's own `expire_days`, and entries
+//! the new file dropped are released. A list whose file stops being refreshed therefore ages
+//! out on its own — a dead feed doesn't pin stale ranges forever. An import only ever touches
+//! its own rows: an IP the rules or the admin already greylisted keeps that row.
+//!
+//! The operator allowlist is never overridden: an entry that is (or covers) the server's own
+//! public IP is skipped at import, on top of the request path's allowlist check.
+
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+use sqlx::types::chrono::{DateTime, Utc};
+use tracing::{Instrument, info, info_span, warn};
+
+use crate::db::dao::greylist::GreylistDao;
+use crate::db::dao::reputation_list::ReputationListDao;
+use crate::greylist::active_set::GreylistSet;
+use crate::greylist::prefix::{IpPrefix, entry_covers_any, is_prefix_entry};
+use crate::settings::ReputationList;
+
+/// How often the loop checks the files for changes (a stat each — cheap).
+pub const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The greylist `reason` a list's entries carry.
+pub fn reason(list_name: &str) -> String {
+    format!("reputation:{list_name}")
+}
+
+/// A parsed list: its normalized entries (sorted, de-duplicated) and the lines that
+/// weren't an address or a range.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ParsedList {
+    pub entries: Vec<String>,
+    pub skipped: usize,
+}
+
+/// Normalize one address or range to its `greylist.ip` form: a CIDR with host bits
+/// zeroed, and a single-host range (`/32`, `/128`) as the bare IP.
+fn normalize(token: &str) -> Option<String> {
+    if !is_prefix_entry(token) {
+        return token.parse::<IpAddr>().ok().map(|ip| ip.to_string());
+    }
+    let prefix = IpPrefix::parse(token)?;
+    let (addr, _) = token.split_once('/')?;
+    let ip: IpAddr = addr.trim().parse().ok()?;
+    let host_len = if ip.is_ipv4() { 32 } else { 128 };
+    if prefix.prefix_len() == host_len {
+        Some(ip.to_string())
+    } else {
+        Some(prefix.to_string())
+    }
+}
+
+/// Parse a list in any of the supported formats (see the module docs).
+pub fn parse(text: &str) -> ParsedList {
+    let mut out = ParsedList::default();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        let token = if line.starts_with('{') {
+            match serde_json::from_str::<serde_json::Value>(line) {
+                // The feed's metadata line carries no `cidr`.
+                Ok(v) => match v.get("cidr").and_then(|c| c.as_str()) {
+                    Some(cidr) => cidr.to_string(),
+                    None => continue,
+                },
+                Err(_) => {
+                    out.skipped += 1;
+                    continue;
+                }
+            }
+        } else {
+            let data = line.split([';', '#']).next().unwrap_or_default();
+            data.split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        };
+        match normalize(&token) {
+            Some(entry) => out.entries.push(entry),
+            None => out.skipped += 1,
+        }
+    }
+    out.entries.sort();
+    out.entries.dedup();
+    out
+}
+
+fn mtime_millis(path: &Path) -> Result<i64> {
+    let modified = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .with_context(|| format!("can't stat {path:?}"))?;
+    let since_epoch = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+    Ok(i64::try_from(since_epoch.as_millis()).unwrap_or(i64::MAX))
+}
+
+/// Import `list` (read from disk now) into the greylist in one transaction. Returns the
+/// entries listed and the lines skipped.
+async fn import(
+    pool: &SqlitePool,
+    set: &GreylistSet,
+    list: &ReputationList,
+) -> Result<(usize, usize)> {
+    let text = tokio::fs::read_to_string(&list.path)
+        .await
+        .with_context(|| format!("can't read {:?}", list.path))?;
+    let parsed = parse(&text);
+    let allowlisted: Vec<IpAddr> = set
+        .allowlisted()
+        .iter()
+        .filter_map(|ip| ip.parse().ok())
+        .collect();
+    let (entries, spared): (Vec<String>, Vec<String>) = parsed
+        .entries
+        .into_iter()
+        .partition(|e| !entry_covers_any(e, &allowlisted));
+    for e in &spared {
+        info!(
+            "reputation list {}: not listing {e} — it holds the operator IP",
+            list.name
+        );
+    }
+
+    let reason = reason(&list.name);
+    let now = Utc::now().timestamp();
+    let expires = DateTime::<Utc>::from_timestamp(now + list.expire_days * 86_400, 0)
+        .unwrap_or_else(Utc::now);
+    let keep: HashSet<&str> = entries.iter().map(String::as_str).collect();
+
+    let mut tx = pool.begin().await?;
+    for e in &entries {
+        GreylistDao::upsert_listed(&mut *tx, e, &reason, expires).await?;
+    }
+    for stale in GreylistDao::listed(&mut *tx, &reason).await? {
+        if !keep.contains(stale.as_str()) {
+            GreylistDao::release(&mut *tx, &stale).await?;
+        }
+    }
+    tx.commit().await?;
+    Ok((entries.len(), parsed.skipped + spared.len()))
+}
+
+/// Reimport every list whose file changed (or moved) since its last good load, then refresh
+/// `set` if any did. A list that fails records the error and keeps its last good rows.
+/// Returns the lists reloaded.
+pub async fn check_once(
+    pool: &SqlitePool,
+    set: &GreylistSet,
+    lists: &[ReputationList],
+) -> Result<usize> {
+    let mut reloaded = 0;
+    for list in lists {
+        let path = list.path.to_string_lossy();
+        let loaded = async {
+            let mtime = mtime_millis(&list.path)?;
+            let status = ReputationListDao::get(pool, &list.name).await?;
+            if status.is_some_and(|s| s.path == path && s.file_mtime == Some(mtime)) {
+                return Ok(false);
+            }
+            let (entries, skipped) = import(pool, set, list).await?;
+            ReputationListDao::record_load(
+                pool,
+                &list.name,
+                &path,
+                entries as i64,
+                skipped as i64,
+                mtime,
+            )
+            .await?;
+            info!(
+                "reputation list {}: loaded {entries} entries ({skipped} skipped) from {path}",
+                list.name
+            );
+            anyhow::Ok(true)
+        }
+        .await;
+        match loaded {
+            Ok(true) => reloaded += 1,
+            Ok(false) => {}
+            Err(e) => {
+                warn!("reputation list {}: load failed: {e:?}", list.name);
+                ReputationListDao::record_error(pool, &list.name, &path, &format!("{e:#}")).await?;
+            }
+        }
+    }
+    if reloaded > 0 {
+        set.refresh(&GreylistDao::active(pool).await?);
+    }
+    Ok(reloaded)
+}
+
+/// Spawn the reload loop (detached, like the sweep — a failed check logs and retries).
+/// Forgets the status of lists no longer configured; with none configured it stops there.
+pub fn spawn(pool: SqlitePool, set: GreylistSet, lists: Vec<ReputationList>) {
+    tokio::spawn(async move {
+        let names: Vec<String> = lists.iter().map(|l| l.name.clone()).collect();
+        if let Err(e) = ReputationListDao::retain(&pool, &names).await {
+            warn!("reputation lists: couldn't prune dropped lists: {e:?}");
+        }
+        if lists.is_empty() {
+            return;
+        }
+        let mut ticker = tokio::time::interval(RELOAD_CHECK_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let checked = check_once(&pool, &set, &lists)
+                .instrument(info_span!("reputation_check"))
+                .await;
+            if let Err(e) = checked {
+                warn!("reputation lists: check failed (will retry): {e:?}");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::SystemTime;
+
+    #[test]
+    fn parses_plain_drop_and_json_lines() {
+        let text = "\
+; Spamhaus DROP List 2026/10/18 - (c) 2026 The Spamhaus Project
+1.10.16.0/20 ; SBL256894
+1.19.0.0/16 ; SBL434604
+# a hand-kept entry
+203.0.113.7
+203.0.113.9/32   # one host, written as a range
+2001:db8:bad::/48
+2001:db8::1/128
+{\"type\":\"metadata\",\"timestamp\":1760745600,\"size\":2}
+{\"cidr\":\"185.177.72.9/24\",\"sblid\":\"SBL1\",\"rir\":\"ripencc\"}
+not-an-ip
+10.0.0.0/33
+";
+        let parsed = parse(text);
+        assert_eq!(
+            parsed.entries,
+            [
+                "1.10.16.0/20",
+                "1.19.0.0/16",
+                "185.177.72.0/24",
+                "2001:db8::1",
+                "2001:db8:bad::/48",
+                "203.0.113.7",
+                "203.0.113.9",
+            ]
+        );
+        assert_eq!(parsed.skipped, 2);
+    }
+
+    fn touch(path: &Path, contents: &str, mtime_secs: u64) {
+        std::fs::write(path, contents).unwrap();
+        let when = SystemTime::UNIX_EPOCH + Duration::from_secs(mtime_secs);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(when)
+            .unwrap();
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn imports_reloads_on_change_and_spares_other_rows(pool: SqlitePool) -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("drop.txt");
+        let list = ReputationList {
+            name: "drop".into(),
+            path: path.clone(),
+            expire_days: 7,
+        };
+        let lists = [list];
+
+        // A rule already greylisted one listed IP; the operator's own IP is in a listed range.
+        let r1_expiry = DateTime::<Utc>::from_timestamp(Utc::now().timestamp() + 3600, 0).unwrap();
+        GreylistDao::upsert_auto(&pool, "203.0.113.7", "R1: signature probe", None, r1_expiry)
+            .await?;
+        let set = GreylistSet::new();
+        set.set_public_ips(&HashSet::from(["192.0.2.10".parse::<IpAddr>().unwrap()]));
+
+        touch(&path, "203.0.113.7\n198.51.100.0/24\n192.0.2.0/24\n", 1_000);
+        assert_eq!(check_once(&pool, &set, &lists).await?, 1);
+        assert!(
+            set.is_greylisted("198.51.100.44"),
+            "the snapshot picked up the range"
+        );
+        assert!(
+            !set.is_greylisted("192.0.2.11"),
+            "a range holding the operator IP is skipped"
+        );
+        let active = GreylistDao::active(&pool).await?;
+        let reason_of = |ip: &str| active.iter().find(|e| e.ip == ip).map(|e| e.reason.clone());
+        assert_eq!(
+            reason_of("198.51.100.0/24").as_deref(),
+            Some("reputation:drop")
+        );
+        assert_eq!(
+            reason_of("203.0.113.7").as_deref(),
+            Some("R1: signature probe"),
+            "a rule's row keeps its reason"
+        );
+        let status = ReputationListDao::get(&pool, "drop").await?.unwrap();
+        assert_eq!((status.entries, status.skipped), (2, 1));
+
+        // Unchanged file: nothing to do.
+        assert_eq!(check_once(&pool, &set, &lists).await?, 0);
+
+        // The feed drops the range: released on the next change.
+        touch(&path, "203.0.113.7\n", 2_000);
+        assert_eq!(check_once(&pool, &set, &lists).await?, 1);
+        assert!(!set.is_greylisted("198.51.100.44"));
+        assert!(set.is_greylisted("203.0.113.7"));
+
+        // A vanished file records the error and keeps the last good rows.
+        std::fs::remove_file(&path)?;
+        assert_eq!(check_once(&pool, &set, &lists).await?, 0);
+        let status = ReputationListDao::get(&pool, "drop").await?.unwrap();
+        assert!(status.error.is_some());
+        assert_eq!(status.entries, 1);
+        Ok(())
+    }
+}
@@ -6,23 +6,47 @@
 //! rows, but nothing serves the toll yet, so early rows have no visible effect. That's
 //! deliberate: it lets detection be validated (rows appearing on beta) before the challenge
 //! ships.
+//!
+//! After the per-IP pass it looks one level up: the IPs it just greylisted are grouped by
+//! /24 (v4) or /64 (v6), and a prefix with enough tripped siblings (R4) gets its own CIDR
+//! entry, so the farm's NEXT address is tolled before it trips anything.
+//!
+//! When a fitted model has been trained (`greylist::model`), every pass also scores each IP
+//! with it in SHADOW and records where it disagrees with the rules. The model never decides:
+//! shadow failures log and move on, and only the rules' verdicts reach the greylist.
+//!
+//! Last, it runs the clear-then-scan escalation (`greylist::escalation`): a clearance whose
+//! IP went on to probe is revoked and the IP blocked outright.
+//!
+//! With `firewall_export` configured, the timed loop then writes the active set out for the
+//! host firewall (`greylist::firewall`).
 
-use std::time::Duration;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use hickory_resolver::TokioAsyncResolver;
 use sqlx::types::chrono::{DateTime, Utc};
 use sqlx::SqlitePool;
-use tracing::{error, info, warn};
+use tracing::{Instrument, error, info, info_span, warn};
 
 use crate::db::dao::greylist::GreylistDao;
+use crate::db::dao::greylist_model::GreylistModelDao;
 use crate::db::dao::request_log::{RequestLogDao, Window};
 use crate::greylist::active_set::GreylistSet;
 use crate::greylist::crawler::{CrawlerCache, CrawlerDns, CrawlerVerdict};
-use crate::greylist::detection::{build_features, score, Verdict};
+use crate::geoip::GeoDb;
+use crate::greylist::detection::{
+    build_features, group_by_prefix, score, score_hosting, score_prefix, IpFeatures, Rule,
+    Verdict,
+};
+use crate::greylist::escalation;
+use crate::greylist::firewall;
+use crate::greylist::model::Model;
+use crate::metrics::{Metrics, TASK_GREYLIST_SWEEP};
+use crate::retention::SWEEP_WINDOW_DAYS;
+use crate::settings::FirewallExport;
 
-/// Lookback the sweep evaluates each pass (the R2/R3 counts accumulate over this).
-pub const SWEEP_WINDOW_DAYS: i64 = 1;
 /// Sliding lifetime of an auto greylist entry from its last trip.
 pub const GREYLIST_TTL_DAYS: i64 = 7;
 /// Per-DNS-lookup timeout during FCrDNS (the sweep is off the request path, so this is generous).
@@ -31,6 +55,8 @@ pub const DNS_TIMEOUT: Duration = Duration::from_secs(3);
 pub const SWEEP_INTERVAL: Duration = Duration::from_secs(15 * 60);
 /// Crawler-verdict cache lifetime — crawler IP ranges are stable, so hours is fine.
 pub const CRAWLER_CACHE_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+/// A shadow disagreement no pass has re-seen in this long is dropped.
+pub const SHADOW_RETAIN_DAYS: i64 = 14;
 
 /// Outcome of one sweep pass — returned so the "Run sweep now" admin action (CX.12) can report it.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
@@ -39,6 +65,58 @@ pub struct SweepReport {
     pub greylisted: usize,
     pub exempted_crawlers: usize,
     pub skipped_unknown_dns: usize,
+    /// Prefixes greylisted by R4 this pass.
+    pub prefixes: usize,
+    /// IPs the shadow model scored differently from the rules (0 with no model trained).
+    pub shadow_disagreements: usize,
+    /// Clearances revoked (and their IPs blocked) for probing after solving the toll.
+    pub escalated: usize,
+}
+
+/// The latest fitted model and its version, or `None` if none is trained or it can't be
+/// loaded (a kind from a newer build) — shadowing is optional, so that only warns.
+async fn load_shadow_model(pool: &SqlitePool) -> Option<(i64, Model)> {
+    let stored = match GreylistModelDao::latest(pool).await {
+        Ok(stored) => stored?,
+        Err(e) => {
+            warn!("greylist sweep: couldn't read the shadow model: {e:?}");
+            return None;
+        }
+    };
+    match Model::from_artifact(&stored.kind, &stored.artifact) {
+        Ok(model) => Some((stored.version, model)),
+        Err(e) => {
+            warn!("greylist sweep: not shadowing model v{}: {e:?}", stored.version);
+            None
+        }
+    }
+}
+
+/// Score `f` with the shadow model and record it if the model disagrees with the rules'
+/// `verdict`. Returns whether they disagreed.
+async fn shadow(
+    pool: &SqlitePool,
+    version: i64,
+    model: &Model,
+    f: &IpFeatures,
+    verdict: &Verdict,
+) -> bool {
+    let p = model.probability(f);
+    let rule_reason = match verdict {
+        Verdict::Greylist { reason, .. } => Some(reason.as_str()),
+        Verdict::Clear => None,
+    };
+    if (p >= model.threshold) == rule_reason.is_some() {
+        return false;
+    }
+    let evidence = f.evidence();
+    if let Err(e) =
+        GreylistModelDao::record_disagreement(pool, version, &f.ip, rule_reason, p, &evidence)
+            .await
+    {
+        warn!("greylist sweep: failed recording a shadow disagreement for {}: {e:?}", f.ip);
+    }
+    true
 }
 
 fn ttl_expiry(now: DateTime<Utc>) -> DateTime<Utc> {
@@ -50,12 +128,14 @@ fn ttl_expiry(now: DateTime<Utc>) -> DateTime<Utc> {
 /// One sweep pass. Injectable pool + resolver + cache so it tests offline. Greylists via R1
 /// unconditionally (signature probes need no DNS); for the blunt rules (R2/R3) it consults
 /// FCrDNS and EXEMPTS a verified crawler, SKIPS on inconclusive DNS (fail-safe), and greylists
-/// a confirmed non-crawler.
+/// a confirmed non-crawler. An IP the rules clear is rescored by R5 when `geo` weighs
+/// hosting networks and it's on one.
 pub async fn run_once<D: CrawlerDns>(
     pool: &SqlitePool,
     dns: &D,
     cache: &CrawlerCache,
     set: &GreylistSet,
+    geo: &GeoDb,
 ) -> Result<SweepReport> {
     let window = Window::last_days(SWEEP_WINDOW_DAYS);
     let rows = RequestLogDao::ip_path_aggregates(pool, &window).await?;
@@ -66,6 +146,8 @@ pub async fn run_once<D: CrawlerDns>(
         ..Default::default()
     };
     let expires = ttl_expiry(Utc::now());
+    let mut tripped: Vec<(&IpFeatures, Rule)> = Vec::new();
+    let shadow_model = load_shadow_model(pool).await;
 
     for f in &features {
         // Never score the operator's own public IP (Phase DU) — `build_features` skips
@@ -74,11 +156,22 @@ pub async fn run_once<D: CrawlerDns>(
         if set.is_allowlisted(&f.ip) {
             continue;
         }
+        let mut verdict = score(f);
+        if verdict == Verdict::Clear
+            && let Some(network) = geo.hosting_network(&f.ip)
+        {
+            verdict = score_hosting(f, &network);
+        }
+        if let Some((version, model)) = &shadow_model
+            && shadow(pool, *version, model, f, &verdict).await
+        {
+            report.shadow_disagreements += 1;
+        }
         let Verdict::Greylist {
             rule,
             reason,
             evidence,
-        } = score(f)
+        } = verdict
         else {
             continue;
         };
@@ -104,6 +197,25 @@ pub async fn run_once<D: CrawlerDns>(
 
         GreylistDao::upsert_auto(pool, &f.ip, &reason, Some(&evidence), expires).await?;
         report.greylisted += 1;
+        tripped.push((f, rule));
+    }
+
+    // R4: a range whose siblings tripped on their own gets a CIDR entry. Never one covering
+    // the operator's own public IP — enforcement would exempt that IP anyway, but its
+    // neighbours share the operator's ISP block and shouldn't be collateral.
+    let allowlisted: Vec<IpAddr> =
+        set.allowlisted().iter().filter_map(|ip| ip.parse().ok()).collect();
+    for group in group_by_prefix(&tripped) {
+        let Verdict::Greylist { reason, evidence, .. } = score_prefix(&group) else {
+            continue;
+        };
+        if allowlisted.iter().any(|ip| group.prefix.contains(*ip)) {
+            info!("greylist sweep: not greylisting {} — it holds the operator IP", group.prefix);
+            continue;
+        }
+        let entry = group.prefix.to_string();
+        GreylistDao::upsert_auto(pool, &entry, &reason, Some(&evidence), expires).await?;
+        report.prefixes += 1;
     }
 
     // Phase DU: the operator's own public IP(s) never persist — release a stale/pinned row
@@ -118,34 +230,64 @@ pub async fn run_once<D: CrawlerDns>(
         }
     }
 
+    if shadow_model.is_some() {
+        let cutoff_secs = Utc::now().timestamp() - SHADOW_RETAIN_DAYS * 86_400;
+        let cutoff = DateTime::<Utc>::from_timestamp(cutoff_secs, 0).unwrap_or_else(Utc::now);
+        if let Err(e) = GreylistModelDao::prune_disagreements(pool, cutoff).await {
+            warn!("greylist sweep: failed pruning shadow disagreements: {e:?}");
+        }
+    }
+
+    // Before the refresh, so a fresh escalation is in the snapshot it builds.
+    report.escalated = escalation::run_once(pool, set).await?;
+
     // Trim lapsed rows, then refresh the request-path snapshot from the active set.
     let pruned = GreylistDao::prune_expired(pool).await?;
     let active = GreylistDao::active(pool).await?;
     set.refresh(&active);
 
     info!(
-        "greylist sweep: evaluated {} IPs, greylisted {}, exempted {} crawlers, skipped {} (DNS inconclusive), pruned {}, active {}",
-        report.evaluated, report.greylisted, report.exempted_crawlers, report.skipped_unknown_dns, pruned, active.len()
+        "greylist sweep: evaluated {} IPs, greylisted {} (+{} prefixes), exempted {} crawlers, skipped {} (DNS inconclusive), shadow disagreed on {}, escalated {}, pruned {}, active {}",
+        report.evaluated, report.greylisted, report.prefixes, report.exempted_crawlers, report.skipped_unknown_dns, report.shadow_disagreements, report.escalated, pruned, active.len()
     );
     Ok(report)
 }
 
 /// Spawn the sweep as a detached interval loop (NOT in the coordinator `try_join!`, so a failure
 /// can't take the app down — it logs and retries next tick). Runs once at boot, then every
-/// [`SWEEP_INTERVAL`].
-pub fn spawn(pool: SqlitePool, resolver: TokioAsyncResolver, set: GreylistSet) {
+/// [`SWEEP_INTERVAL`], exporting the active set to `export` (if configured) after each pass.
+/// Each pass is timed into `metrics` for `/metrics`.
+pub fn spawn(
+    pool: SqlitePool,
+    resolver: TokioAsyncResolver,
+    set: GreylistSet,
+    geo: GeoDb,
+    export: Option<FirewallExport>,
+    metrics: Metrics,
+) {
     tokio::spawn(async move {
         // Enforce persisted entries from t=0 (before the first detection pass runs).
         if let Ok(active) = GreylistDao::active(&pool).await {
             set.refresh(&active);
         }
+        if let Err(e) = escalation::refresh_revocations(&pool, &set).await {
+            warn!("greylist: couldn't load the clearance revocation list: {e:?}");
+        }
         let cache = CrawlerCache::new(CRAWLER_CACHE_TTL);
         let mut ticker = tokio::time::interval(SWEEP_INTERVAL);
         loop {
             ticker.tick().await; // fires immediately on the first tick, then every interval
-            if let Err(e) = run_once(&pool, &resolver, &cache, &set).await {
+            let started = Instant::now();
+            let pass = run_once(&pool, &resolver, &cache, &set, &geo)
+                .instrument(info_span!("greylist_sweep"))
+                .await;
+            metrics.observe_task(TASK_GREYLIST_SWEEP, started.elapsed(), pass.is_ok());
+            if let Err(e) = pass {
                 error!("greylist sweep pass failed (will retry next tick): {e:?}");
             }
+            if let Some(export) = &export {
+                firewall::export_or_warn(&pool, &set, export).await;
+            }
         }
     });
 }
@@ -155,7 +297,6 @@ mod tests {
     use super::*;
     use crate::db::dao::request_log::NewRequestLog;
     use std::collections::{HashMap, HashSet};
-    use std::net::IpAddr;
 
     /// Minimal offline resolver for the sweep test: verifies exactly the IPs whose PTR+forward
     /// are configured, everything else is NotCrawler.
@@ -184,6 +325,9 @@ mod tests {
             duration_ms: 0,
             is_bot: false,
             challenged: false,
+            rate_limited: false,
+            visitor: None,
+            campaign: None,
         }
     }
 
@@ -213,7 +357,7 @@ mod tests {
 
         let cache = CrawlerCache::new(Duration::from_secs(3600));
         let set = GreylistSet::new();
-        let report = run_once(&pool, &dns, &cache, &set).await?;
+        let report = run_once(&pool, &dns, &cache, &set, &GeoDb::default()).await?;
 
         let active = GreylistDao::active(&pool).await?;
         let ips: Vec<&str> = active.iter().map(|e| e.ip.as_str()).collect();
@@ -257,7 +401,7 @@ mod tests {
         let set = GreylistSet::new();
         set.set_public_ips(&HashSet::from([operator.parse::<IpAddr>().unwrap()]));
 
-        let report = run_once(&pool, &dns, &cache, &set).await?;
+        let report = run_once(&pool, &dns, &cache, &set, &GeoDb::default()).await?;
 
         let active = GreylistDao::active(&pool).await?;
         assert!(
@@ -268,4 +412,112 @@ mod tests {
         assert_eq!(report.greylisted, 0, "the R1 trip did not greylist the allowlisted operator IP");
         Ok(())
     }
+
+    /// R5: the same dozen dead paths greylist an IP on a hosting network (via the fixture
+    /// `.mmdb`s, with the weighting on) and leave a residential one — and the hosting IP
+    /// itself, with the weighting off — alone.
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn sweep_weighs_hosting_networks_when_asked(pool: SqlitePool) -> Result<()> {
+        let hosted = "203.0.113.9"; // AS64496, hosting via the fixture's `hosting_asns`
+        let residential = "198.51.100.7"; // AS64500
+        for ip in [hosted, residential] {
+            for i in 0..12 {
+                RequestLogDao::insert(&pool, &req(&format!("/gone-{i}"), 404, ip)).await?;
+            }
+        }
+
+        let dns = MockDns::default();
+        let cache = CrawlerCache::new(Duration::from_secs(3600));
+        let set = GreylistSet::new();
+
+        let unweighted = GeoDb::open(Some(&crate::test_support::fixture_geoip(false)));
+        let report = run_once(&pool, &dns, &cache, &set, &unweighted).await?;
+        assert_eq!(report.greylisted, 0, "below R2's bar and R5 is off");
+
+        let weighted = GeoDb::open(Some(&crate::test_support::fixture_geoip(true)));
+        let report = run_once(&pool, &dns, &cache, &set, &weighted).await?;
+        assert_eq!(report.greylisted, 1, "only the hosting IP clears R5's lower bar");
+
+        let active = GreylistDao::active(&pool).await?;
+        let row = active.iter().find(|e| e.ip == hosted).expect("hosting IP greylisted");
+        assert!(row.reason.starts_with("R5"), "reason names R5: {}", row.reason);
+        assert!(
+            row.evidence
+                .as_deref()
+                .is_some_and(|e| e.contains("network=NL · AS64496 Example Hosting B.V.")),
+            "evidence names the network: {:?}",
+            row.evidence
+        );
+        assert!(active.iter().all(|e| e.ip != residential), "residential IP left alone");
+        Ok(())
+    }
+
+    /// R4: three /24 siblings each tripping R1 get the whole /24 listed, so a fourth address
+    /// from the farm is tolled on sight; a lone scanner elsewhere doesn't drag its range in.
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn sweep_greylists_a_scanner_farm_prefix(pool: SqlitePool) -> Result<()> {
+        let farm = ["185.177.72.10", "185.177.72.11", "185.177.72.12"];
+        let lone = "198.51.100.40";
+        for ip in farm.iter().chain([&lone]) {
+            for p in ["/wp-login.php", "/.env"] {
+                RequestLogDao::insert(&pool, &req(p, 404, ip)).await?;
+            }
+        }
+
+        let dns = MockDns::default();
+        let cache = CrawlerCache::new(Duration::from_secs(3600));
+        let set = GreylistSet::new();
+        let report = run_once(&pool, &dns, &cache, &set, &GeoDb::default()).await?;
+        assert_eq!((report.greylisted, report.prefixes), (4, 1));
+
+        let active = GreylistDao::active(&pool).await?;
+        let row = active.iter().find(|e| e.ip == "185.177.72.0/24").expect("the farm's /24");
+        assert!(row.reason.starts_with("R4"), "{}", row.reason);
+        assert!(row.evidence.as_deref().unwrap_or_default().contains("185.177.72.11(R1)"));
+        assert!(
+            active.iter().all(|e| e.ip != "198.51.100.0/24"),
+            "a lone scanner stays an IP entry"
+        );
+
+        assert_eq!(set.matching_entry("185.177.72.99").as_deref(), Some("185.177.72.0/24"));
+        assert!(!set.is_greylisted("198.51.100.41"));
+        Ok(())
+    }
+
+    /// Shadow mode: a trained model is scored next to the rules and its disagreements are
+    /// recorded — but only the rules' verdicts ever reach the greylist.
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn sweep_shadows_the_model_without_letting_it_decide(pool: SqlitePool) -> Result<()> {
+        let scanner = "203.0.113.60";
+        let quiet = "198.51.100.60";
+        for p in ["/wp-login.php", "/.env"] {
+            RequestLogDao::insert(&pool, &req(p, 404, scanner)).await?;
+        }
+        RequestLogDao::insert(&pool, &req("/", 200, quiet)).await?;
+
+        // A model that greylists everything: it agrees on the scanner, not on the reader.
+        let paranoid = Model {
+            weights: vec![0.0; 6],
+            bias: 10.0,
+            threshold: 0.5,
+        };
+        let artifact = paranoid.to_artifact()?;
+        let version =
+            GreylistModelDao::insert(&pool, crate::greylist::model::MODEL_KIND, &artifact, 1, "{}")
+                .await?;
+
+        let dns = MockDns::default();
+        let cache = CrawlerCache::new(Duration::from_secs(3600));
+        let set = GreylistSet::new();
+        let report = run_once(&pool, &dns, &cache, &set, &GeoDb::default()).await?;
+        assert_eq!((report.greylisted, report.shadow_disagreements), (1, 1));
+
+        let rows = GreylistModelDao::disagreements(&pool, version).await?;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].ip, quiet);
+        assert_eq!(rows[0].rule_reason, None, "the rules cleared it");
+        assert!(rows[0].model_score > 0.99);
+        assert!(!set.is_greylisted(quiet), "the model's verdict is never enforced");
+        Ok(())
+    }
 }
@@ -0,0 +1,229 @@
+//! Offline training for the fitted greylist model (`train_greylist_model` binary).
+//!
+//! Builds the labelled dataset from the history the site already keeps — `request_log`
+//! bucketed into IP-days (one sample per IP per UTC day, the shape of a sweep window),
+//! labelled by [`model::label`] from the rules, `greylist_clearance` (a solved toll is a
+//! human) and the admin's manual pins — then fits, measures on a holdout, and stores the
+//! result as the next `greylist_model` version. Storing a model changes nothing the
+//! visitor sees: the sweep only SHADOWS it.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::net::IpAddr;
+
+use anyhow::{Result, bail};
+use sqlx::SqlitePool;
+
+use crate::db::dao::greylist::GreylistDao;
+use crate::db::dao::greylist_model::GreylistModelDao;
+use crate::db::dao::request_log::{IpDayPathAgg, IpPathAgg, RequestLogDao, Window};
+use crate::greylist::detection::build_features;
+use crate::greylist::model::{
+    self, MODEL_KIND, Metrics, Sample, TrainParams, evaluate, split_holdout, train,
+};
+use crate::greylist::prefix::{IpPrefix, is_prefix_entry};
+
+/// Default lookback: everything `request_log` retains (it's pruned at 90 days).
+pub const TRAINING_WINDOW_DAYS: i64 = 90;
+
+/// Fewer samples of either class than this and the fit means nothing — refuse to store it.
+const MIN_SAMPLES_PER_CLASS: usize = 10;
+
+/// What a training run produced — printed by the binary.
+#[derive(Debug)]
+pub struct TrainReport {
+    pub version: i64,
+    pub trained_on: usize,
+    pub holdout: Metrics,
+}
+
+impl fmt::Display for TrainReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let h = &self.holdout;
+        write!(
+            f,
+            "stored greylist model v{} ({MODEL_KIND}), fitted on {} IP-days; holdout of {} \
+             ({} bots): accuracy {:.3}, precision {:.3}, recall {:.3}",
+            self.version, self.trained_on, h.samples, h.bots, h.accuracy, h.precision, h.recall
+        )
+    }
+}
+
+/// Manual pins, as matchable IPs and ranges.
+struct Pins {
+    ips: HashSet<String>,
+    prefixes: Vec<IpPrefix>,
+}
+
+impl Pins {
+    fn new(entries: Vec<String>) -> Self {
+        let (ranges, ips): (Vec<_>, Vec<_>) = entries.into_iter().partition(|e| is_prefix_entry(e));
+        Self {
+            ips: ips.into_iter().collect(),
+            prefixes: ranges.iter().filter_map(|r| IpPrefix::parse(r)).collect(),
+        }
+    }
+
+    fn contains(&self, ip: &str) -> bool {
+        self.ips.contains(ip)
+            || ip
+                .parse::<IpAddr>()
+                .is_ok_and(|addr| self.prefixes.iter().any(|p| p.contains(addr)))
+    }
+}
+
+/// Turn per-day aggregates (ordered by day, as the DAO returns them) into labelled samples.
+/// Private and loopback IPs drop out in `build_features`, as they do in the sweep.
+fn samples_from(rows: Vec<IpDayPathAgg>, cleared: &HashSet<String>, pins: &Pins) -> Vec<Sample> {
+    let mut samples = Vec::new();
+    let mut rows = rows.into_iter().peekable();
+    while let Some(first) = rows.next() {
+        let day = first.day.clone();
+        let mut aggs = vec![first];
+        while let Some(next) = rows.next_if(|r| r.day == day) {
+            aggs.push(next);
+        }
+        let aggs: Vec<IpPathAgg> = aggs
+            .into_iter()
+            .map(|r| IpPathAgg {
+                ip: r.ip,
+                path: r.path,
+                status: r.status,
+                count: r.count,
+            })
+            .collect();
+        let mut features = build_features(&aggs);
+        // `build_features` groups through a HashMap; sort so the holdout split is stable.
+        features.sort_by(|a, b| a.ip.cmp(&b.ip));
+        samples.extend(features.into_iter().map(|f| {
+            let bot = model::label(&f, cleared.contains(&f.ip), pins.contains(&f.ip));
+            Sample { features: f, bot }
+        }));
+    }
+    samples
+}
+
+/// Build the dataset over the last `days`, fit, and store the model as a new version.
+pub async fn train_and_store(pool: &SqlitePool, days: i64) -> Result<TrainReport> {
+    let rows = RequestLogDao::ip_day_path_aggregates(pool, &Window::last_days(days)).await?;
+    let cleared: HashSet<String> = GreylistDao::cleared_ips(pool).await?.into_iter().collect();
+    let pins = Pins::new(GreylistDao::pinned_entries(pool).await?);
+    let samples = samples_from(rows, &cleared, &pins);
+
+    let bots = samples.iter().filter(|s| s.bot).count();
+    let humans = samples.len() - bots;
+    if bots < MIN_SAMPLES_PER_CLASS || humans < MIN_SAMPLES_PER_CLASS {
+        bail!(
+            "not enough history to train on: {bots} bot and {humans} human IP-days over the \
+             last {days} days (need {MIN_SAMPLES_PER_CLASS} of each)"
+        );
+    }
+
+    let (training, holdout) = split_holdout(samples);
+    let fitted = train(&training, &TrainParams::default());
+    let metrics = evaluate(&fitted, &holdout);
+    let version = GreylistModelDao::insert(
+        pool,
+        MODEL_KIND,
+        &fitted.to_artifact()?,
+        training.len() as i64,
+        &serde_json::to_string(&metrics)?,
+    )
+    .await?;
+    Ok(TrainReport {
+        version,
+        trained_on: training.len(),
+        holdout: metrics,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::dao::request_log::NewRequestLog;
+    use crate::greylist::model::Model;
+
+    fn req(path: &str, status: i64, ip: &str) -> NewRequestLog {
+        NewRequestLog {
+            method: "GET".into(),
+            path: path.into(),
+            status,
+            ip: Some(ip.into()),
+            user_agent: None,
+            referer: None,
+            duration_ms: 0,
+            is_bot: false,
+            challenged: false,
+            rate_limited: false,
+            visitor: None,
+            campaign: None,
+        }
+    }
+
+    #[test]
+    fn samples_split_by_day_and_take_clearance_and_pin_labels() {
+        let agg = |day: &str, ip: &str, path: &str, status: i64| IpDayPathAgg {
+            day: day.into(),
+            ip: ip.into(),
+            path: path.into(),
+            status,
+            count: 2,
+        };
+        let rows = vec![
+            agg("2026-10-01", "203.0.113.1", "/wp-login.php", 404),
+            agg("2026-10-01", "203.0.113.2", "/", 200),
+            agg("2026-10-01", "10.0.0.1", "/.env", 404),
+            agg("2026-10-02", "203.0.113.1", "/", 200),
+            agg("2026-10-02", "198.51.100.7", "/", 200),
+        ];
+        let cleared = HashSet::from(["203.0.113.1".to_string()]);
+        let pins = Pins::new(vec!["198.51.100.0/24".into()]);
+        let samples = samples_from(rows, &cleared, &pins);
+
+        let labels: Vec<(&str, bool)> = samples
+            .iter()
+            .map(|s| (s.features.ip.as_str(), s.bot))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("203.0.113.1", true),  // day 1: R1 beats the later clearance
+                ("203.0.113.2", false), // a reader
+                ("198.51.100.7", true), // inside a pinned range
+                ("203.0.113.1", false), // day 2: quiet and cleared — human
+            ]
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn training_refuses_thin_history_then_stores_a_loadable_model(
+        pool: SqlitePool,
+    ) -> Result<()> {
+        assert!(
+            train_and_store(&pool, 1).await.is_err(),
+            "an empty log can't train"
+        );
+
+        for i in 0..15 {
+            let scanner = format!("203.0.113.{i}");
+            for p in ["/wp-login.php", "/.env", "/xmlrpc.php"] {
+                RequestLogDao::insert(&pool, &req(p, 404, &scanner)).await?;
+            }
+            RequestLogDao::insert(&pool, &req("/", 200, &format!("198.51.100.{i}"))).await?;
+        }
+        let report = train_and_store(&pool, 1).await?;
+        assert_eq!(report.trained_on + report.holdout.samples, 30);
+
+        let stored = GreylistModelDao::latest(&pool).await?.unwrap();
+        assert_eq!(stored.version, report.version);
+        let fitted = Model::from_artifact(&stored.kind, &stored.artifact)?;
+        let probe = build_features(&[IpPathAgg {
+            ip: "192.0.2.1".into(),
+            path: "/wp-login.php".into(),
+            status: 404,
+            count: 3,
+        }]);
+        assert!(fitted.greylists(&probe[0]));
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use std::{env, io, sync::Arc};
+use std::{env, io, path::Path, sync::Arc};
 
 use rustls::crypto::ring;
 use tracing::{Level, error, info};
@@ -7,13 +7,22 @@ use tracing_subscriber::{Layer, filter, fmt, layer::SubscriberExt, util::Subscri
 use tray_wrapper::{ContinueRunning, ServerGeneratorResult, create_tray_wrapper};
 
 use crate::web::router::BUILD_TIME_CACHE_BUST;
-use crate::{coordinator::service_coordinator::ServiceCoordinator, settings::Settings};
+use crate::{
+    coordinator::service_coordinator::ServiceCoordinator, db::database_handle::DatabaseHandle,
+    settings::Settings,
+};
+mod alerts;
 mod coordinator;
 mod db;
 mod deadlinks;
+mod geoip;
 mod greylist;
 mod media;
+mod metrics;
+mod privacy;
+mod retention;
 mod settings;
+mod telemetry;
 pub mod test_support;
 mod web;
 
@@ -38,6 +47,13 @@ pub fn global_init() -> anyhow::Result<()> {
     let access_rolling =
         RollingFileAppender::new(Rotation::DAILY, &settings.log_path, "access.log");
 
+    // Trace export, when configured, under a filter of its own: it takes sqlx's
+    // per-statement events, which the log files leave out.
+    let otlp = match &settings.otlp {
+        Some(config) => Some(telemetry::layer(config)?.with_filter(telemetry::filter())),
+        None => None,
+    };
+
     tracing_subscriber::registry()
         .with(
             fmt::layer()
@@ -60,6 +76,7 @@ pub fn global_init() -> anyhow::Result<()> {
                 .with_writer(access_rolling)
                 .with_filter(access_filter),
         )
+        .with(otlp)
         .init();
     Ok(())
 }
@@ -86,8 +103,10 @@ fn create_server() -> ServerGeneratorResult {
         };
 
         info!("Starting up the coordinator");
+        // `start` only returns `Ok` on a shutdown signal, after flushing: end the process
+        // rather than have the wrapper start the server again.
         match coordinator.start().await {
-            Ok(_) => ContinueRunning::Continue,
+            Ok(()) => ContinueRunning::Exit,
             Err(e) => {
                 error!("Service Coordinator Failed {}", e);
                 ContinueRunning::ExitWithError("Service Coordinator Failed".to_string())
@@ -100,11 +119,43 @@ fn create_server() -> ServerGeneratorResult {
 pub fn real_main() -> anyhow::Result<()> {
     global_init()?;
 
-    create_tray_wrapper(
+    let wrapped = create_tray_wrapper(
         include_bytes!("../assets/images/HotchkissLogox1024.png"),
         Some(VERSION.to_string()),
         Arc::new(&create_server),
-    )?;
+    );
+    // The server has stopped (or never started): send the spans still queued.
+    telemetry::shutdown();
+    wrapped?;
 
     Ok(())
 }
+
+/// Offline account recovery for `src/bin/recovery_code.rs`: open the SQLite file at
+/// `db_path` directly (the server may be running or not) and mint one recovery
+/// code for `display_name` — or the only admin — so an operator who lost every
+/// passkey can sign back in. Returns the account's name and the plaintext code.
+pub async fn mint_offline_recovery_code(
+    db_path: &Path,
+    display_name: Option<&str>,
+) -> anyhow::Result<(String, String)> {
+    // Connecting would otherwise create an empty database at a mistyped path.
+    anyhow::ensure!(db_path.is_file(), "no database at {}", db_path.display());
+    let pool = DatabaseHandle::create(db_path).await?;
+    let minted = web::features::recovery::mint_offline(&pool, display_name).await;
+    pool.close().await;
+    minted
+}
+
+/// Offline model training for `src/bin/train_greylist_model.rs`: fit the greylist scoring
+/// model on the last `days` of history (default: all `request_log` retains) in the SQLite
+/// file at `db_path` and store it as the next version, which the running server's sweep
+/// starts shadowing on its next pass. Returns a one-line summary of the fit.
+pub async fn train_greylist_model(db_path: &Path, days: Option<i64>) -> anyhow::Result<String> {
+    anyhow::ensure!(db_path.is_file(), "no database at {}", db_path.display());
+    let days = days.unwrap_or(greylist::training::TRAINING_WINDOW_DAYS);
+    let pool = DatabaseHandle::create(db_path).await?;
+    let trained = greylist::training::train_and_store(&pool, days).await;
+    pool.close().await;
+    Ok(trained?.to_string())
+}
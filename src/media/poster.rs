@@ -34,6 +34,7 @@ pub fn generate_poster(video_path: &Path) -> Result<Vec<u8>> {
 
 /// One PNG frame on stdout. `seek` is an optional `-ss` start time.
 fn grab_frame(bin: &str, video_path: &Path, seek: Option<&str>) -> Result<Vec<u8>> {
+    let _span = tracing::info_span!("ffmpeg", process.executable.path = bin, seek).entered();
     let mut cmd = Command::new(bin);
     cmd.args(["-v", "error"]);
     if let Some(s) = seek {
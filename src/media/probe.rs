@@ -142,6 +142,7 @@ pub fn probe(path: &Path, original_name: &str) -> Result<Probed> {
     let bin = FFPROBE_BIN
         .as_deref()
         .ok_or_else(|| anyhow!("ffprobe not found — `brew install ffmpeg` (looked at $FFPROBE_BIN, /opt/homebrew/bin, /usr/local/bin, PATH)"))?;
+    let _span = tracing::info_span!("ffprobe", process.executable.path = bin).entered();
     let out = Command::new(bin)
         .args(["-v", "error", "-print_format", "json", "-show_format", "-show_streams", "-show_chapters"])
         .arg(path)
@@ -227,6 +227,7 @@ fn decode_native_oriented(path: &Path) -> Result<DynamicImage> {
 /// NOT passed explicitly: `-autorotate` is a bare flag whose argument-less form
 /// can't be pinned "on" without ffmpeg misparsing the next token as an output.
 fn ffmpeg_first_frame_png(bin: &str, path: &Path) -> Result<Vec<u8>> {
+    let _span = tracing::info_span!("ffmpeg", process.executable.path = bin).entered();
     let out = Command::new(bin)
         .args(["-v", "error"])
         .arg("-i")
@@ -0,0 +1,348 @@
+//! In-process metrics for the Prometheus scrape endpoint (`GET /metrics`, see
+//! `web::features::metrics`).
+//!
+//! Only what can't be read back at scrape time lives here: request counts and latency
+//! histograms by route (fed by `web::middleware::metrics`) and the last run of each timed
+//! background task. Everything else — pool, media roots, greylist, certificates, the
+//! request-log queue — is a gauge the handler reads when it's scraped.
+//!
+//! Routes are labelled by their matched template (`/pages/{page_name}`), never the raw
+//! path, so a scanner walking random URLs can't grow the label set: anything that matched
+//! no route is `unmatched`.
+//!
+//! Per-instance (an `Arc` in `AppState`, like `GreylistSet`) so each test server counts
+//! only its own requests.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use sqlx::types::chrono::{DateTime, Utc};
+
+/// Prefix on every exported series.
+pub const PREFIX: &str = "hio";
+
+/// The Prometheus text exposition format's content type.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Upper bounds (seconds) of the request-latency histogram buckets; `+Inf` is implied.
+pub const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The `route` label for a request that matched no route (the 404 fallback).
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// The background tasks whose runs are timed.
+pub const TASK_GREYLIST_SWEEP: &str = "greylist_sweep";
+pub const TASK_DEAD_LINK_SCAN: &str = "dead_link_scan";
+
+#[derive(Clone, Debug, Default)]
+struct Histogram {
+    /// Per-bucket (not cumulative) counts; cumulated when rendered.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(i) = LATENCY_BUCKETS.iter().position(|le| seconds <= *le) {
+            self.buckets[i] += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+/// The last run of a timed background task.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TaskRun {
+    pub task: &'static str,
+    pub last_duration: Duration,
+    pub last_finished: DateTime<Utc>,
+    pub last_ok: bool,
+    pub runs: u64,
+    pub failures: u64,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    /// (method, route, status) → requests.
+    requests: BTreeMap<(String, String, u16), u64>,
+    /// (method, route) → latency.
+    latency: BTreeMap<(String, String), Histogram>,
+    tasks: BTreeMap<&'static str, TaskRun>,
+}
+
+#[derive(Clone, Default)]
+pub struct Metrics {
+    registry: Arc<Mutex<Registry>>,
+    /// The bearer token a scraper may present instead of an admin identity
+    /// (`metrics_token` in the settings). `None` = admins only.
+    token: Option<Arc<str>>,
+}
+
+impl fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Metrics")
+            .field("token", &self.token.as_ref().map(|_| "<redacted>"))
+            .finish_non_exhaustive()
+    }
+}
+
+/// Methods outside the usual set share one label, so they can't grow the label set either.
+fn method_label(method: &str) -> &str {
+    match method {
+        "GET" | "HEAD" | "POST" | "PUT" | "PATCH" | "DELETE" | "OPTIONS" => method,
+        _ => "OTHER",
+    }
+}
+
+impl Metrics {
+    pub fn new(token: Option<String>) -> Self {
+        Self {
+            registry: Arc::default(),
+            token: token.filter(|t| !t.is_empty()).map(Arc::from),
+        }
+    }
+
+    /// Whether `presented` is the configured scrape token (constant-time). Always `false`
+    /// with none configured.
+    pub fn accepts_token(&self, presented: &str) -> bool {
+        self.token.as_deref().is_some_and(|token| {
+            token.len() == presented.len()
+                && openssl::memcmp::eq(token.as_bytes(), presented.as_bytes())
+        })
+    }
+
+    /// Count one served request. `route` is the matched route template, or `None` for one
+    /// that matched nothing.
+    pub fn observe_request(
+        &self,
+        method: &str,
+        route: Option<&str>,
+        status: u16,
+        elapsed: Duration,
+    ) {
+        let method = method_label(method).to_string();
+        let route = route.unwrap_or(UNMATCHED_ROUTE).to_string();
+        let mut registry = self.registry.lock().unwrap();
+        *registry
+            .requests
+            .entry((method.clone(), route.clone(), status))
+            .or_default() += 1;
+        registry
+            .latency
+            .entry((method, route))
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Record a finished run of a timed background task.
+    pub fn observe_task(&self, task: &'static str, elapsed: Duration, ok: bool) {
+        let now = Utc::now();
+        let mut registry = self.registry.lock().unwrap();
+        let (runs, failures) = registry
+            .tasks
+            .get(task)
+            .map_or((0, 0), |run| (run.runs, run.failures));
+        registry.tasks.insert(
+            task,
+            TaskRun {
+                task,
+                last_duration: elapsed,
+                last_finished: now,
+                last_ok: ok,
+                runs: runs + 1,
+                failures: failures + u64::from(!ok),
+            },
+        );
+    }
+
+    /// The recorded task runs, by task name.
+    pub fn tasks(&self) -> Vec<TaskRun> {
+        self.registry
+            .lock()
+            .unwrap()
+            .tasks
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Write the request counter and latency histogram families.
+    pub fn write_requests(&self, out: &mut Exposition) {
+        let registry = self.registry.lock().unwrap();
+
+        out.family(
+            "http_requests_total",
+            "counter",
+            "Requests served, by route and status.",
+        );
+        for ((method, route, status), n) in &registry.requests {
+            let status = status.to_string();
+            out.sample(
+                "http_requests_total",
+                &[
+                    ("method", method.as_str()),
+                    ("route", route.as_str()),
+                    ("status", status.as_str()),
+                ],
+                *n as f64,
+            );
+        }
+
+        out.family(
+            "http_request_duration_seconds",
+            "histogram",
+            "Server-side handling time, by route.",
+        );
+        for ((method, route), h) in &registry.latency {
+            let labels = [("method", method.as_str()), ("route", route.as_str())];
+            let mut cumulative = 0;
+            for (le, n) in LATENCY_BUCKETS.iter().zip(h.buckets) {
+                cumulative += n;
+                let le = le.to_string();
+                out.sample(
+                    "http_request_duration_seconds_bucket",
+                    &[labels[0], labels[1], ("le", le.as_str())],
+                    cumulative as f64,
+                );
+            }
+            out.sample(
+                "http_request_duration_seconds_bucket",
+                &[labels[0], labels[1], ("le", "+Inf")],
+                h.count as f64,
+            );
+            out.sample("http_request_duration_seconds_sum", &labels, h.sum);
+            out.sample(
+                "http_request_duration_seconds_count",
+                &labels,
+                h.count as f64,
+            );
+        }
+    }
+}
+
+/// A Prometheus text-format (0.0.4) body under construction. Names are given without
+/// [`PREFIX`]; it's added here.
+#[derive(Debug, Default)]
+pub struct Exposition {
+    body: String,
+}
+
+/// Escape a label value: backslash, double quote and newline.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+impl Exposition {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a metric family: its `# HELP` and `# TYPE` lines.
+    pub fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.body, "# HELP {PREFIX}_{name} {help}");
+        let _ = writeln!(self.body, "# TYPE {PREFIX}_{name} {kind}");
+    }
+
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let _ = write!(self.body, "{PREFIX}_{name}");
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(k, v)| format!("{k}=\"{}\"", escape(v)))
+                .collect();
+            let _ = write!(self.body, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.body, " {value}");
+    }
+
+    /// A family with a single unlabelled sample.
+    pub fn gauge(&mut self, name: &str, help: &str, value: f64) {
+        self.family(name, "gauge", help);
+        self.sample(name, &[], value);
+    }
+
+    pub fn finish(self) -> String {
+        self.body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_render_as_counters_and_cumulative_buckets() {
+        let metrics = Metrics::new(None);
+        let ms = Duration::from_millis;
+        metrics.observe_request("GET", Some("/pages/{page_name}"), 200, ms(3));
+        metrics.observe_request("GET", Some("/pages/{page_name}"), 200, ms(40));
+        metrics.observe_request("GET", Some("/pages/{page_name}"), 404, ms(30_000));
+        metrics.observe_request("PROPFIND", None, 404, ms(1));
+
+        let mut out = Exposition::new();
+        metrics.write_requests(&mut out);
+        let body = out.finish();
+
+        let page = r#"method="GET",route="/pages/{page_name}""#;
+        for line in [
+            "# TYPE hio_http_requests_total counter".to_string(),
+            format!("hio_http_requests_total{{{page},status=\"200\"}} 2"),
+            format!("hio_http_requests_total{{{page},status=\"404\"}} 1"),
+            r#"hio_http_requests_total{method="OTHER",route="unmatched",status="404"} 1"#
+                .to_string(),
+            format!("hio_http_request_duration_seconds_bucket{{{page},le=\"0.005\"}} 1"),
+            format!("hio_http_request_duration_seconds_bucket{{{page},le=\"0.05\"}} 2"),
+            format!("hio_http_request_duration_seconds_bucket{{{page},le=\"10\"}} 2"),
+            format!("hio_http_request_duration_seconds_bucket{{{page},le=\"+Inf\"}} 3"),
+            format!("hio_http_request_duration_seconds_count{{{page}}} 3"),
+        ] {
+            assert!(
+                body.lines().any(|l| l == line),
+                "missing {line:?} in:\n{body}"
+            );
+        }
+    }
+
+    #[test]
+    fn tasks_keep_the_last_run_and_count_failures() {
+        let metrics = Metrics::new(None);
+        metrics.observe_task(TASK_GREYLIST_SWEEP, Duration::from_secs(2), true);
+        metrics.observe_task(TASK_GREYLIST_SWEEP, Duration::from_secs(5), false);
+
+        let tasks = metrics.tasks();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].last_duration, Duration::from_secs(5));
+        assert!(!tasks[0].last_ok);
+        assert_eq!((tasks[0].runs, tasks[0].failures), (2, 1));
+    }
+
+    #[test]
+    fn token_is_optional_and_exact() {
+        assert!(!Metrics::new(None).accepts_token(""));
+        assert!(!Metrics::new(Some(String::new())).accepts_token(""));
+        let metrics = Metrics::new(Some("s3cret".into()));
+        assert!(metrics.accepts_token("s3cret"));
+        assert!(!metrics.accepts_token("s3cre"));
+        assert!(!metrics.accepts_token("s3cret!"));
+        assert!(!format!("{metrics:?}").contains("s3cret"));
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        let mut out = Exposition::new();
+        out.sample("x", &[("root", "/Volumes/\"odd\"\\name")], 1.0);
+        assert_eq!(
+            out.finish(),
+            "hio_x{root=\"/Volumes/\\\"odd\\\"\\\\name\"} 1\n"
+        );
+    }
+}
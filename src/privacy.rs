@@ -0,0 +1,394 @@
+//! The request-log privacy policy (`privacy` in the settings). A request is logged in full —
+//! the greylist sweep and the per-IP drill-down need the real address — but once it's
+//! `after_days` old its IP is cut to its network (IPv4 /24, IPv6 /48) or replaced with a
+//! keyed hash, and with `reduce_user_agents` its user agent becomes a family ("Firefox",
+//! "Googlebot"). Solves in `greylist_clearance` get the same user-agent treatment.
+//!
+//! Distinct-visitor counts don't move when that happens: every row also carries `visitor`,
+//! an HMAC of its full IP under a server key (`crypto_keys` id [`VISITOR_KEY_ID`]), written
+//! by the request-log writer and backfilled here for rows logged before it. The counts go
+//! by that, so the same address is one visitor on both sides of the cutoff.
+//!
+//! A detached hourly loop; without a policy it only backfills keys.
+
+use std::fmt;
+use std::net::{IpAddr, Ipv6Addr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use sqlx::SqlitePool;
+use tracing::{Instrument, info, info_span, warn};
+
+use crate::db::dao::crypto_key::CryptoKey;
+use crate::db::dao::greylist::GreylistDao;
+use crate::db::dao::request_log::{LoggedIdentity, RequestLogDao, is_bot};
+use crate::settings::{IpAnonymization, Privacy};
+
+/// `crypto_keys` id for the visitor-key HMAC secret. Auto-generated on first use.
+pub const VISITOR_KEY_ID: i64 = 5;
+
+const RUN_EVERY: Duration = Duration::from_secs(60 * 60);
+
+/// Rows per transaction, so a large first run doesn't hold the write lock for long.
+const BATCH: i64 = 1_000;
+
+/// (lowercased marker, family) — first match wins, so the bots and browsers that mention
+/// Chrome or Safari in their user agent come before those two.
+const UA_FAMILIES: &[(&str, &str)] = &[
+    ("googlebot", "Googlebot"),
+    ("bingbot", "Bingbot"),
+    ("applebot", "Applebot"),
+    ("duckduckbot", "DuckDuckBot"),
+    ("yandexbot", "YandexBot"),
+    ("baiduspider", "Baiduspider"),
+    ("gptbot", "GPTBot"),
+    ("claudebot", "ClaudeBot"),
+    ("facebookexternalhit", "facebookexternalhit"),
+    ("headlesschrome", "HeadlessChrome"),
+    ("curl/", "curl"),
+    ("wget/", "Wget"),
+    ("python-requests", "python-requests"),
+    ("go-http-client", "Go-http-client"),
+    ("edg/", "Edge"),
+    ("opr/", "Opera"),
+    ("firefox/", "Firefox"),
+    ("chrome/", "Chrome"),
+    ("safari/", "Safari"),
+];
+
+/// A user agent's family: a known browser or bot, else "Other bot" / "Other" by the
+/// `is_bot` markers — so a reduced row still classifies the same way.
+pub fn ua_family(user_agent: &str) -> String {
+    let lower = user_agent.to_ascii_lowercase();
+    match UA_FAMILIES
+        .iter()
+        .find(|(marker, _)| lower.contains(marker))
+    {
+        Some((_, family)) => family.to_string(),
+        None if is_bot(Some(user_agent)) => "Other bot".to_string(),
+        None => "Other".to_string(),
+    }
+}
+
+/// `ip` cut to its network in CIDR notation (IPv4 /24, IPv6 /48); `None` if it doesn't
+/// parse.
+pub fn truncate_ip(ip: &str) -> Option<String> {
+    match ip.parse::<IpAddr>().ok()? {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            Some(format!("{a}.{b}.{c}.0/24"))
+        }
+        IpAddr::V6(v6) => {
+            let [a, b, c, ..] = v6.segments();
+            Some(format!("{}/48", Ipv6Addr::new(a, b, c, 0, 0, 0, 0, 0)))
+        }
+    }
+}
+
+/// The server key visitor hashes are taken under. Its `Debug` redacts the key.
+#[derive(Clone)]
+pub struct VisitorKey(Arc<Vec<u8>>);
+
+impl fmt::Debug for VisitorKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("VisitorKey(<redacted>)")
+    }
+}
+
+impl VisitorKey {
+    pub async fn load(pool: &SqlitePool) -> Result<Self> {
+        let key = CryptoKey::get_or_create(pool, VISITOR_KEY_ID)
+            .await?
+            .key_value;
+        Ok(Self(Arc::new(key)))
+    }
+
+    /// HMAC-SHA256(key, ip), first 16 bytes as hex.
+    pub fn of(&self, ip: &str) -> Result<String> {
+        let pkey = PKey::hmac(&self.0).context("building the visitor HMAC key")?;
+        let mut signer = Signer::new(MessageDigest::sha256(), &pkey).context("visitor signer")?;
+        signer
+            .update(ip.as_bytes())
+            .context("visitor HMAC update")?;
+        let mac = signer.sign_to_vec().context("visitor HMAC sign")?;
+        Ok(mac[..16].iter().map(|b| format!("{b:02x}")).collect())
+    }
+}
+
+/// What one pass changed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Pass {
+    /// Older rows given their `visitor` key.
+    pub keyed: u64,
+    pub requests: u64,
+    pub clearances: u64,
+}
+
+/// `row` as the policy leaves it. The visitor key is taken from the full IP first.
+fn anonymized(row: &LoggedIdentity, policy: &Privacy, key: &VisitorKey) -> Result<LoggedIdentity> {
+    let visitor = match (&row.visitor, &row.ip) {
+        (Some(v), _) => Some(v.clone()),
+        (None, Some(ip)) => Some(key.of(ip)?),
+        (None, None) => None,
+    };
+    let ip = match policy.ip {
+        IpAnonymization::Truncate => row.ip.as_deref().and_then(truncate_ip),
+        IpAnonymization::Hash => visitor.as_ref().map(|v| format!("anon-{}", &v[..12])),
+    };
+    let user_agent = if policy.reduce_user_agents {
+        row.user_agent.as_deref().map(ua_family)
+    } else {
+        row.user_agent.clone()
+    };
+    Ok(LoggedIdentity {
+        id: row.id,
+        ip,
+        user_agent,
+        visitor,
+    })
+}
+
+/// One pass: backfill missing visitor keys, then (with a policy) anonymize everything past
+/// the window.
+pub async fn run_once(
+    pool: &SqlitePool,
+    policy: Option<&Privacy>,
+    key: &VisitorKey,
+) -> Result<Pass> {
+    let mut pass = Pass::default();
+    loop {
+        let rows = RequestLogDao::unkeyed(pool, BATCH).await?;
+        if rows.is_empty() {
+            break;
+        }
+        let mut tx = pool.begin().await?;
+        for row in &rows {
+            if let Some(ip) = &row.ip {
+                RequestLogDao::set_visitor(&mut *tx, row.id, &key.of(ip)?).await?;
+            }
+        }
+        tx.commit().await?;
+        pass.keyed += rows.len() as u64;
+    }
+
+    let Some(policy) = policy else {
+        return Ok(pass);
+    };
+    loop {
+        let rows = RequestLogDao::due_for_anonymization(pool, policy.after_days, BATCH).await?;
+        if rows.is_empty() {
+            break;
+        }
+        let mut tx = pool.begin().await?;
+        for row in &rows {
+            RequestLogDao::anonymize(&mut *tx, &anonymized(row, policy, key)?).await?;
+        }
+        tx.commit().await?;
+        pass.requests += rows.len() as u64;
+    }
+
+    if policy.reduce_user_agents {
+        loop {
+            let rows =
+                GreylistDao::clearances_due_for_anonymization(pool, policy.after_days, BATCH)
+                    .await?;
+            if rows.is_empty() {
+                break;
+            }
+            let mut tx = pool.begin().await?;
+            for (id, user_agent) in &rows {
+                let family = user_agent.as_deref().map(ua_family);
+                GreylistDao::anonymize_clearance(&mut *tx, *id, family.as_deref()).await?;
+            }
+            tx.commit().await?;
+            pass.clearances += rows.len() as u64;
+        }
+    }
+    Ok(pass)
+}
+
+/// Spawn the hourly loop (detached — a failed pass logs and retries next hour).
+pub fn spawn(pool: SqlitePool, policy: Option<Privacy>) {
+    tokio::spawn(async move {
+        let key = match VisitorKey::load(&pool).await {
+            Ok(key) => key,
+            Err(e) => {
+                warn!("privacy: couldn't load the visitor key, not running: {e:?}");
+                return;
+            }
+        };
+        let mut ticker = tokio::time::interval(RUN_EVERY);
+        loop {
+            ticker.tick().await;
+            let pass = run_once(&pool, policy.as_ref(), &key)
+                .instrument(info_span!("privacy_pass"))
+                .await;
+            match pass {
+                Ok(pass) if pass != Pass::default() => info!(
+                    "privacy: keyed {} rows, anonymized {} requests and {} clearances",
+                    pass.keyed, pass.requests, pass.clearances
+                ),
+                Ok(_) => {}
+                Err(e) => warn!("privacy: pass failed (will retry): {e:?}"),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::dao::request_log::{Audience, NewRequestLog, Window};
+    use sqlx::query;
+
+    #[test]
+    fn truncates_to_the_network() {
+        assert_eq!(
+            truncate_ip("203.0.113.77").as_deref(),
+            Some("203.0.113.0/24")
+        );
+        assert_eq!(
+            truncate_ip("2001:db8:1234:5678::1").as_deref(),
+            Some("2001:db8:1234::/48")
+        );
+        assert_eq!(truncate_ip("not an ip"), None);
+    }
+
+    #[test]
+    fn families_keep_the_bot_classification() {
+        let firefox = "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0";
+        let edge = "Mozilla/5.0 (Windows NT 10.0) AppleWebKit/537.36 (KHTML, like Gecko) \
+                    Chrome/126.0 Safari/537.36 Edg/126.0";
+        let googlebot = "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+        assert_eq!(ua_family(firefox), "Firefox");
+        assert_eq!(ua_family(edge), "Edge");
+        assert_eq!(ua_family(googlebot), "Googlebot");
+        assert_eq!(ua_family("masscan/1.3"), "Other bot");
+        for ua in [firefox, edge, googlebot, "curl/8.4.0", "zgrab/0.x"] {
+            assert_eq!(
+                is_bot(Some(&ua_family(ua))),
+                is_bot(Some(ua)),
+                "{ua} keeps its classification"
+            );
+        }
+    }
+
+    async fn log(pool: &SqlitePool, days_ago: i64, ip: &str, key: Option<&VisitorKey>) {
+        let row = NewRequestLog {
+            method: "GET".into(),
+            path: "/".into(),
+            status: 200,
+            ip: Some(ip.into()),
+            user_agent: Some("Mozilla/5.0 (X11; Linux x86_64) Firefox/128.0".into()),
+            referer: None,
+            duration_ms: 0,
+            is_bot: false,
+            challenged: false,
+            rate_limited: false,
+            visitor: key.map(|k| k.of(ip).unwrap()),
+            campaign: None,
+        };
+        RequestLogDao::insert(pool, &row).await.unwrap();
+        let modifier = format!("-{days_ago} days");
+        query!(
+            "UPDATE request_log SET ts = datetime('now', ?1) WHERE id = (SELECT MAX(id) FROM request_log)",
+            modifier
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn distinct_counts_hold_across_the_cutoff(pool: SqlitePool) -> Result<()> {
+        let key = VisitorKey::load(&pool).await?;
+        // One visitor on both sides of a 7-day window, a second only in the old part, and a
+        // third on the first one's /24 — truncation alone would fold it into the first.
+        log(&pool, 10, "203.0.113.7", None).await;
+        log(&pool, 10, "203.0.113.8", None).await;
+        log(&pool, 9, "198.51.100.4", None).await;
+        log(&pool, 1, "203.0.113.7", Some(&key)).await;
+
+        let policy = Privacy {
+            after_days: 7,
+            ip: IpAnonymization::Truncate,
+            reduce_user_agents: true,
+        };
+        // The rows logged before keys existed get theirs first; then the count is settled.
+        let window = Window::last_days(30);
+        let pass = run_once(&pool, None, &key).await?;
+        assert_eq!((pass.keyed, pass.requests), (3, 0));
+        assert_eq!(
+            RequestLogDao::distinct_ip_count(&pool, &window, Audience::All).await?,
+            3
+        );
+
+        let pass = run_once(&pool, Some(&policy), &key).await?;
+        assert_eq!((pass.keyed, pass.requests), (0, 3));
+        assert_eq!(
+            RequestLogDao::distinct_ip_count(&pool, &window, Audience::All).await?,
+            3,
+            "the same visitors on both sides of the cutoff"
+        );
+
+        let old = query!(
+            r#"SELECT ip as "ip!", user_agent as "user_agent!" FROM request_log
+               WHERE ts < date('now', '-7 days') ORDER BY ip"#
+        )
+        .fetch_all(&pool)
+        .await?;
+        let old: Vec<(&str, &str)> = old
+            .iter()
+            .map(|r| (r.ip.as_str(), r.user_agent.as_str()))
+            .collect();
+        assert_eq!(
+            old,
+            [
+                ("198.51.100.0/24", "Firefox"),
+                ("203.0.113.0/24", "Firefox"),
+                ("203.0.113.0/24", "Firefox")
+            ]
+        );
+        let recent = RequestLogDao::recent(&pool, 1).await?;
+        assert_eq!(
+            recent[0].ip.as_deref(),
+            Some("203.0.113.7"),
+            "inside the window"
+        );
+
+        assert_eq!(
+            run_once(&pool, Some(&policy), &key).await?,
+            Pass::default(),
+            "each row is done once"
+        );
+        Ok(())
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn hashing_replaces_the_ip(pool: SqlitePool) -> Result<()> {
+        let key = VisitorKey::load(&pool).await?;
+        log(&pool, 10, "203.0.113.7", None).await;
+        let policy = Privacy {
+            after_days: 7,
+            ip: IpAnonymization::Hash,
+            reduce_user_agents: false,
+        };
+        run_once(&pool, Some(&policy), &key).await?;
+
+        let row =
+            query!(r#"SELECT ip as "ip!", user_agent, visitor as "visitor!" FROM request_log"#)
+                .fetch_one(&pool)
+                .await?;
+        assert_eq!(row.visitor, key.of("203.0.113.7")?);
+        assert_eq!(row.ip, format!("anon-{}", &row.visitor[..12]));
+        assert!(
+            row.user_agent.is_some_and(|ua| ua.contains("Mozilla")),
+            "kept in full"
+        );
+        Ok(())
+    }
+}
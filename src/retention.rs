@@ -0,0 +1,19 @@
+//! How far back the greylist reads full request-log IPs, and so how soon the privacy
+//! policy may anonymize them. Kept free of imports so the settings can check
+//! `privacy.after_days` against it without depending on the greylist or the web layer.
+
+/// Lookback the sweep evaluates each pass (the R2/R3 counts accumulate over this).
+pub const SWEEP_WINDOW_DAYS: i64 = 1;
+
+/// Lifetime of a toll clearance cookie. An escalation reads the holder's requests back
+/// this far.
+pub const CLEARANCE_TTL_DAYS: i64 = 7;
+
+/// The shortest `after_days` the settings accept. The greylist reads full IPs back over
+/// the sweep window (detection) and over a clearance's lifetime (escalation); a row
+/// anonymized inside either would silently drop out of both.
+pub const MIN_AFTER_DAYS: i64 = if SWEEP_WINDOW_DAYS + 1 > CLEARANCE_TTL_DAYS {
+    SWEEP_WINDOW_DAYS + 1
+} else {
+    CLEARANCE_TTL_DAYS
+};
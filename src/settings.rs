@@ -7,10 +7,255 @@ use std::{
 };
 use tracing::info;
 
+use crate::retention::MIN_AFTER_DAYS;
+
 /// Default media-root free-space headroom: don't write to a root with less than
 /// 10 GiB free — fall to the next root instead. Overridable via `media_min_free_bytes`.
 const DEFAULT_MEDIA_MIN_FREE_BYTES: u64 = 10 * 1024 * 1024 * 1024;
 
+/// One route group's token bucket (see `web::rate_limit`): up to `burst` requests
+/// back to back, refilled at `per_minute`.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+/// Rate limits per route group. A group left out of the settings file keeps its
+/// default; one set to `null` is unlimited.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RateLimits {
+    /// The WebAuthn ceremony (`/login/*`). Keyed by IP — the caller isn't anyone yet.
+    pub login: Option<RateLimit>,
+    /// Media writes (`/media` uploads and edits, the bulk import upload).
+    pub media_upload: Option<RateLimit>,
+    /// MCP tool calls (`/mcp`), usually keyed by API key.
+    pub mcp: Option<RateLimit>,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            // A sign-in is two or three requests; a dozen attempts a minute is a human
+            // fumbling, not a human.
+            login: Some(RateLimit { burst: 20, per_minute: 10 }),
+            // Dropping a whole album at once is normal.
+            media_upload: Some(RateLimit { burst: 60, per_minute: 60 }),
+            mcp: Some(RateLimit { burst: 60, per_minute: 120 }),
+        }
+    }
+}
+
+/// Tarpit for signature-path probes (see `web::trap`): the probe gets its 404 a chunk
+/// at a time, `drip_every_ms` apart, for `hold_secs`, with at most `max_concurrent`
+/// probes held at once (past that they get the plain 404).
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Tarpit {
+    pub max_concurrent: usize,
+    pub drip_every_ms: u64,
+    pub hold_secs: u64,
+}
+
+impl Default for Tarpit {
+    fn default() -> Self {
+        Self {
+            // Each held probe is one idle connection and a sleeping task — cheap, but a
+            // cap keeps a scanner farm from turning it into a file-descriptor drain.
+            max_concurrent: 64,
+            drip_every_ms: 2_000,
+            hold_secs: 60,
+        }
+    }
+}
+
+/// The file format `firewall_export` writes (see `greylist::firewall`).
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FirewallFormat {
+    /// An `nft -f` script that refills the greylist sets.
+    Nftables,
+    /// pf.conf `table <…> persist { … }` definitions.
+    Pf,
+}
+
+/// Where (and how) the sweep exports the active greylist for the host firewall.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct FirewallExport {
+    pub format: FirewallFormat,
+    /// The file to (atomically) replace after each sweep pass.
+    pub path: PathBuf,
+    /// nftables only: the `<family> <name>` table the sets live in.
+    #[serde(default = "default_nft_table")]
+    pub nft_table: String,
+    /// Run after each rewrite, as an argv (no shell) — e.g. `["nft", "-f", "<path>"]`.
+    #[serde(default)]
+    pub hook: Option<Vec<String>>,
+}
+
+fn default_nft_table() -> String {
+    "inet filter".into()
+}
+
+/// An operator-supplied IP reputation list (see `greylist::reputation`): a local file of
+/// addresses / CIDR ranges, reloaded when it changes.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct ReputationList {
+    /// Short name — the greylist reason becomes `reputation:<name>`.
+    pub name: String,
+    pub path: PathBuf,
+    /// How long an imported entry stays listed without the file being refreshed.
+    #[serde(default = "default_reputation_expire_days")]
+    pub expire_days: i64,
+}
+
+fn default_reputation_expire_days() -> i64 {
+    7
+}
+
+/// Local MaxMind-format databases for GeoIP / ASN enrichment (see `geoip`). Either may be
+/// omitted; with neither, enrichment is off.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct GeoIp {
+    /// A GeoLite2-Country (or -City) `.mmdb`.
+    pub country_db: Option<PathBuf>,
+    /// A GeoLite2-ASN `.mmdb`.
+    pub asn_db: Option<PathBuf>,
+    /// Let the sweep's hosting-ASN rule (R5) greylist on lower counts from datacenter
+    /// networks. Off unless set.
+    pub weigh_hosting_asns: bool,
+    /// ASNs treated as hosting providers on top of the built-in list.
+    pub hosting_asns: Vec<u32>,
+}
+
+/// What an IP becomes once a request ages past the privacy window (see `privacy`).
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IpAnonymization {
+    /// Cut to its network: the IPv4 /24, the IPv6 /48.
+    #[default]
+    Truncate,
+    /// Replaced with a keyed hash — one per address, so it still counts distinctly.
+    Hash,
+}
+
+/// The request-log privacy policy (see `privacy`): after `after_days`, IPs are anonymized
+/// and, with `reduce_user_agents`, user agents cut to their browser or bot family.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Privacy {
+    /// Days before a request is anonymized — at least [`MIN_AFTER_DAYS`] (7), so the
+    /// greylist's detection and escalation windows still see full IPs.
+    pub after_days: i64,
+    #[serde(default)]
+    pub ip: IpAnonymization,
+    #[serde(default)]
+    pub reduce_user_agents: bool,
+}
+
+/// Where alert notifications go (see `alerts`), tagged by `kind`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum AlertSink {
+    /// Plain SMTP to a relay on the host or the LAN (no STARTTLS or AUTH) — so `host` must
+    /// be `localhost` or a loopback, private or link-local address.
+    Smtp {
+        host: String,
+        #[serde(default = "default_smtp_port")]
+        port: u16,
+        from: String,
+        to: Vec<String>,
+    },
+    /// An ntfy-style topic: the message is the POST body, the title and priority ride in
+    /// headers.
+    Ntfy { url: String, token: Option<String> },
+    /// A JSON POST of the alert.
+    Webhook { url: String },
+}
+
+fn default_smtp_port() -> u16 {
+    25
+}
+
+/// Whether an SMTP sink's `host` keeps the message on the host or the LAN: the sink sends
+/// in the clear, so a name that could resolve anywhere is refused too.
+fn is_local_relay(host: &str) -> bool {
+    if host.eq_ignore_ascii_case("localhost") {
+        return true;
+    }
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        Ok(IpAddr::V6(ip)) => {
+            ip.is_loopback() || ip.is_unique_local() || ip.is_unicast_link_local()
+        }
+        Err(_) => false,
+    }
+}
+
+/// Alert rules and where they're delivered (see `alerts`). A threshold left out keeps its
+/// default; one set to `null` switches its rule off.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Alerts {
+    /// How often the rules are checked.
+    pub every_minutes: u64,
+    /// The lookback for the request rules.
+    pub window_minutes: i64,
+    /// Below this many requests in the window the request rules don't judge.
+    pub min_requests: i64,
+    /// Alert when at least this share of requests (0–1) are 5xx.
+    pub error_rate: Option<f64>,
+    /// Alert when the p95 handling time passes this.
+    pub p95_ms: Option<i64>,
+    /// Alert when a stored certificate expires within this many days (ACME renews at 30).
+    pub cert_expiry_days: Option<i64>,
+    /// Alert when a media root has less than this free, or can't be read at all.
+    pub media_free_bytes: Option<u64>,
+    /// Alert when at least this many links are confirmed dead.
+    pub dead_links: Option<i64>,
+    pub sinks: Vec<AlertSink>,
+}
+
+impl Default for Alerts {
+    fn default() -> Self {
+        Self {
+            every_minutes: 5,
+            window_minutes: 15,
+            // A handful of requests is too few for a rate or a p95 to mean anything.
+            min_requests: 20,
+            error_rate: Some(0.05),
+            p95_ms: Some(2_000),
+            // Renewal starts 30 days out, so two weeks left means it's been failing.
+            cert_expiry_days: Some(14),
+            // Twice the default write headroom: warn before uploads start falling over.
+            media_free_bytes: Some(2 * DEFAULT_MEDIA_MIN_FREE_BYTES),
+            dead_links: Some(1),
+            sinks: Vec::new(),
+        }
+    }
+}
+
+/// OpenTelemetry trace export over OTLP/HTTP (see `telemetry`). `{}` sends to a collector
+/// on this host's default OTLP/HTTP port.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Otlp {
+    /// The collector's traces endpoint (Jaeger and Tempo both take OTLP/HTTP).
+    pub endpoint: String,
+    /// `service.name` on every exported span.
+    pub service_name: String,
+}
+
+impl Default for Otlp {
+    fn default() -> Self {
+        Self {
+            endpoint: "http://127.0.0.1:4318/v1/traces".into(),
+            service_name: "hotchkiss.io".into(),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Settings {
     pub cloudflare_token: String,
@@ -40,6 +285,42 @@ pub struct Settings {
     pub http_port: u16,
     pub https_port: u16,
     pub static_ip: Option<IpAddr>,
+    /// Send the Content-Security-Policy as `…-Report-Only` (violations are reported
+    /// to `/csp-report`, nothing is blocked). Defaults ON — the rollout mode; set
+    /// `false` to enforce once `/admin/csp-reports` has stayed quiet.
+    pub csp_report_only: bool,
+    /// Per-route-group token-bucket limits (`rate_limits` in the settings file; any
+    /// group omitted keeps its default).
+    pub rate_limits: RateLimits,
+    /// Export the active greylist for the host firewall after each sweep pass
+    /// (`firewall_export` in the settings file; off when omitted).
+    pub firewall_export: Option<FirewallExport>,
+    /// IP reputation lists to import into the greylist (`reputation_lists` in the
+    /// settings file; none when omitted).
+    pub reputation_lists: Vec<ReputationList>,
+    /// Slow-drip signature-path probes (`tarpit` in the settings file; off when
+    /// omitted, `{}` for the defaults).
+    pub tarpit: Option<Tarpit>,
+    /// Honeypot paths: linked invisibly and disallowed in robots.txt, so the first
+    /// request for one greylists the IP (`honeypot_paths`; none when omitted).
+    pub honeypot_paths: Vec<String>,
+    /// GeoIP / ASN enrichment (`geoip` in the settings file; off when omitted).
+    pub geoip: Option<GeoIp>,
+    /// Anonymize logged IPs / user agents after a privacy window (`privacy` in the
+    /// settings file; kept in full until the prune when omitted).
+    pub privacy: Option<Privacy>,
+    /// A bearer token a Prometheus scraper presents for `/metrics` (`metrics_token` in
+    /// the settings file). Admins can always read it; omitted, only they can.
+    pub metrics_token: Option<String>,
+    /// Alert rules and sinks (`alerts` in the settings file; no alerting when omitted).
+    pub alerts: Option<Alerts>,
+    /// Export spans to an OpenTelemetry collector (`otlp` in the settings file; off when
+    /// omitted).
+    pub otlp: Option<Otlp>,
+    /// Have the dead-link scan also check external links' `#fragment`s against the
+    /// fetched page (`dead_link_check_external_anchors`; off when omitted). It downloads
+    /// the whole page, and a client-rendered one has no anchors to find.
+    pub dead_link_check_external_anchors: bool,
 }
 
 #[derive(Deserialize)]
@@ -56,6 +337,18 @@ struct RawSettings {
     http_port: Option<u16>,
     https_port: Option<u16>,
     static_ip: Option<IpAddr>,
+    csp_report_only: Option<bool>,
+    rate_limits: Option<RateLimits>,
+    firewall_export: Option<FirewallExport>,
+    reputation_lists: Option<Vec<ReputationList>>,
+    tarpit: Option<Tarpit>,
+    honeypot_paths: Option<Vec<String>>,
+    geoip: Option<GeoIp>,
+    privacy: Option<Privacy>,
+    metrics_token: Option<String>,
+    alerts: Option<Alerts>,
+    otlp: Option<Otlp>,
+    dead_link_check_external_anchors: Option<bool>,
 }
 
 impl Settings {
@@ -79,16 +372,39 @@ impl Settings {
             format!("Failed to parse settings file to settings struct content:{config}")
         })?;
 
-        Ok(Self::resolve(raw, &home))
+        Self::resolve(raw, &home)
     }
 
-    fn resolve(raw: RawSettings, home: &Path) -> Settings {
+    fn resolve(raw: RawSettings, home: &Path) -> Result<Settings> {
+        if let Some(privacy) = &raw.privacy {
+            anyhow::ensure!(
+                privacy.after_days >= MIN_AFTER_DAYS,
+                "privacy.after_days is {} but must be at least {MIN_AFTER_DAYS}: the greylist \
+                 needs full IPs that far back",
+                privacy.after_days
+            );
+        }
+        let smtp_hosts = raw
+            .alerts
+            .iter()
+            .flat_map(|a| &a.sinks)
+            .filter_map(|sink| match sink {
+                AlertSink::Smtp { host, .. } => Some(host),
+                _ => None,
+            });
+        for host in smtp_hosts {
+            anyhow::ensure!(
+                is_local_relay(host),
+                "alerts: smtp host {host:?} must be localhost or a loopback or private \
+                 address: the sink has no STARTTLS or AUTH"
+            );
+        }
         let app_support = home
             .join("Library")
             .join("Application Support")
             .join("io.hotchkiss.web");
         let domain = raw.domain;
-        Settings {
+        Ok(Settings {
             cloudflare_token: raw.cloudflare_token,
             webauthn_rp_id: raw.webauthn_rp_id.unwrap_or_else(|| domain.clone()),
             domain,
@@ -119,7 +435,21 @@ impl Settings {
             http_port: raw.http_port.unwrap_or(80),
             https_port: raw.https_port.unwrap_or(443),
             static_ip: raw.static_ip,
-        }
+            csp_report_only: raw.csp_report_only.unwrap_or(true),
+            rate_limits: raw.rate_limits.unwrap_or_default(),
+            firewall_export: raw.firewall_export,
+            reputation_lists: raw.reputation_lists.unwrap_or_default(),
+            tarpit: raw.tarpit,
+            honeypot_paths: raw.honeypot_paths.unwrap_or_default(),
+            geoip: raw.geoip,
+            privacy: raw.privacy,
+            metrics_token: raw.metrics_token,
+            alerts: raw.alerts,
+            otlp: raw.otlp,
+            dead_link_check_external_anchors: raw
+                .dead_link_check_external_anchors
+                .unwrap_or(false),
+        })
     }
 
     fn make_config_path(parent_path: &Path) -> Result<PathBuf> {
@@ -190,6 +520,9 @@ mod test {
         assert_eq!(s.backup_path, PathBuf::from("bp"));
         assert_eq!(s.http_port, 80);
         assert_eq!(s.https_port, 443);
+        assert!(s.csp_report_only, "CSP starts in report-only rollout mode");
+        assert_eq!(s.rate_limits, RateLimits::default());
+        assert_eq!(s.firewall_export, None, "the firewall export is opt-in");
 
         Ok(())
     }
@@ -205,7 +538,31 @@ mod test {
                 "cloudflare_token": "ctoken",
                 "domain": "do",
                 "http_port": 8080,
-                "https_port": 8443
+                "https_port": 8443,
+                "tarpit": {{ "hold_secs": 20 }},
+                "honeypot_paths": ["/staff-directory"],
+                "geoip": {{ "asn_db": "/geo/GeoLite2-ASN.mmdb", "weigh_hosting_asns": true }},
+                "privacy": {{ "after_days": 7, "ip": "hash" }},
+                "metrics_token": "scrape-me",
+                "otlp": {{ "endpoint": "http://jaeger.lan:4318/v1/traces" }},
+                "dead_link_check_external_anchors": true,
+                "alerts": {{
+                    "p95_ms": null,
+                    "window_minutes": 30,
+                    "sinks": [
+                        {{
+                            "kind": "smtp", "host": "127.0.0.1",
+                            "from": "site@do", "to": ["me@do"]
+                        }},
+                        {{ "kind": "ntfy", "url": "https://ntfy.sh/do-alerts" }},
+                        {{ "kind": "webhook", "url": "http://127.0.0.1:9000/hook" }}
+                    ]
+                }},
+                "reputation_lists": [
+                    {{ "name": "drop", "path": "/lists/drop.txt" }},
+                    {{ "name": "local", "path": "/lists/local.txt", "expire_days": 2 }}
+                ],
+                "rate_limits": {{ "login": {{ "burst": 5, "per_minute": 1 }}, "mcp": null }}
             }}
             "#
         )?;
@@ -217,6 +574,45 @@ mod test {
         assert_eq!(s.https_port, 8443);
         // rp_id defaults to the domain when omitted
         assert_eq!(s.webauthn_rp_id, "do");
+        // A rate-limit group can be tuned or switched off; the rest keep defaults.
+        assert_eq!(s.rate_limits.login, Some(RateLimit { burst: 5, per_minute: 1 }));
+        assert_eq!(s.rate_limits.mcp, None);
+        assert_eq!(s.rate_limits.media_upload, RateLimits::default().media_upload);
+        // Reputation lists keep their order; expiry defaults per list.
+        let lists: Vec<(&str, i64)> = s
+            .reputation_lists
+            .iter()
+            .map(|l| (l.name.as_str(), l.expire_days))
+            .collect();
+        assert_eq!(lists, [("drop", 7), ("local", 2)]);
+        let tarpit = s.tarpit.expect("tarpit on");
+        assert_eq!((tarpit.hold_secs, tarpit.max_concurrent), (20, 64));
+        assert_eq!(s.honeypot_paths, ["/staff-directory"]);
+        let geoip = s.geoip.expect("geoip set");
+        assert_eq!(geoip.asn_db, Some(PathBuf::from("/geo/GeoLite2-ASN.mmdb")));
+        assert_eq!(geoip.country_db, None);
+        assert!(geoip.weigh_hosting_asns && geoip.hosting_asns.is_empty());
+        let privacy = s.privacy.expect("privacy set");
+        assert_eq!((privacy.after_days, privacy.ip), (7, IpAnonymization::Hash));
+        assert!(!privacy.reduce_user_agents);
+        assert_eq!(s.metrics_token.as_deref(), Some("scrape-me"));
+        let alerts = s.alerts.expect("alerts set");
+        assert_eq!((alerts.window_minutes, alerts.p95_ms), (30, None), "null turns a rule off");
+        assert_eq!(alerts.error_rate, Alerts::default().error_rate);
+        assert_eq!(
+            alerts.sinks[0],
+            AlertSink::Smtp {
+                host: "127.0.0.1".into(),
+                port: 25,
+                from: "site@do".into(),
+                to: vec!["me@do".into()],
+            }
+        );
+        assert_eq!(alerts.sinks.len(), 3);
+        let otlp = s.otlp.expect("otlp set");
+        assert_eq!(otlp.endpoint, "http://jaeger.lan:4318/v1/traces");
+        assert_eq!(otlp.service_name, "hotchkiss.io");
+        assert!(s.dead_link_check_external_anchors);
 
         Ok(())
     }
@@ -237,9 +633,21 @@ mod test {
             http_port: None,
             https_port: None,
             static_ip: None,
+            csp_report_only: None,
+            rate_limits: None,
+            firewall_export: None,
+            reputation_lists: None,
+            tarpit: None,
+            honeypot_paths: None,
+            geoip: None,
+            privacy: None,
+            metrics_token: None,
+            alerts: None,
+            otlp: None,
+            dead_link_check_external_anchors: None,
         };
 
-        let s = Settings::resolve(raw, &home);
+        let s = Settings::resolve(raw, &home).unwrap();
         assert_eq!(s.cloudflare_token, "ctoken");
         assert_eq!(s.domain, "do");
         assert_eq!(s.webauthn_rp_id, "do");
@@ -292,8 +700,20 @@ mod test {
             http_port: None,
             https_port: None,
             static_ip: None,
+            csp_report_only: None,
+            rate_limits: None,
+            firewall_export: None,
+            reputation_lists: None,
+            tarpit: None,
+            honeypot_paths: None,
+            geoip: None,
+            privacy: None,
+            metrics_token: None,
+            alerts: None,
+            otlp: None,
+            dead_link_check_external_anchors: None,
         };
-        let s = Settings::resolve(raw, &home);
+        let s = Settings::resolve(raw, &home).unwrap();
         // ordered, primary first — not collapsed or reordered
         assert_eq!(
             s.media_paths,
@@ -328,6 +748,105 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn privacy_window_shorter_than_the_greylist_needs_is_refused() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+
+        writeln!(
+            file,
+            r#"
+            {{
+                "cloudflare_token": "ctoken",
+                "domain": "do",
+                "privacy": {{ "after_days": 1 }}
+            }}
+            "#
+        )?;
+
+        let args: Vec<String> = vec![" ".into(), file.path().to_string_lossy().to_string()];
+
+        let err = Settings::load(args.into_iter()).unwrap_err();
+        assert!(err.to_string().contains("at least 7"), "{err}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn smtp_relay_off_the_lan_is_refused() -> Result<()> {
+        for host in ["smtp.example.com", "203.0.113.25", "2001:db8::25"] {
+            let mut file = NamedTempFile::new()?;
+            writeln!(
+                file,
+                r#"
+                {{
+                    "cloudflare_token": "ctoken",
+                    "domain": "do",
+                    "alerts": {{
+                        "sinks": [{{ "kind": "smtp", "host": "{host}", "from": "s@do", "to": ["me@do"] }}]
+                    }}
+                }}
+                "#
+            )?;
+            let args: Vec<String> = vec![" ".into(), file.path().to_string_lossy().to_string()];
+            let err = Settings::load(args.into_iter()).unwrap_err();
+            assert!(err.to_string().contains("no STARTTLS"), "{host}: {err}");
+        }
+        for host in [
+            "localhost",
+            "127.0.0.1",
+            "10.0.0.25",
+            "192.168.1.25",
+            "::1",
+            "fd00::25",
+        ] {
+            assert!(is_local_relay(host), "{host}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn load_with_firewall_export() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+
+        writeln!(
+            file,
+            r#"
+            {{
+                "cloudflare_token": "ctoken",
+                "domain": "do",
+                "firewall_export": {{
+                    "format": "pf",
+                    "path": "/etc/pf.greylist.conf",
+                    "hook": ["/sbin/pfctl", "-a", "greylist", "-f", "/etc/pf.greylist.conf"]
+                }}
+            }}
+            "#
+        )?;
+
+        let args: Vec<String> = vec![" ".into(), file.path().to_string_lossy().to_string()];
+
+        let s = Settings::load(args.into_iter()).unwrap();
+        let export = s.firewall_export.expect("firewall_export set");
+        assert_eq!(export.format, FirewallFormat::Pf);
+        assert_eq!(export.path, PathBuf::from("/etc/pf.greylist.conf"));
+        assert_eq!(export.nft_table, "inet filter", "nft_table defaults");
+        assert_eq!(export.hook.map(|h| h.len()), Some(5));
+        assert!(s.reputation_lists.is_empty());
+        assert_eq!(s.tarpit, None, "the tarpit is opt-in");
+        assert!(s.honeypot_paths.is_empty());
+        assert_eq!(s.geoip, None, "GeoIP enrichment is opt-in");
+        assert_eq!(s.privacy, None, "anonymization is opt-in");
+        assert_eq!(s.metrics_token, None, "/metrics is admin-only by default");
+        assert_eq!(s.alerts, None, "alerting is opt-in");
+        assert_eq!(s.otlp, None, "trace export is opt-in");
+        assert!(
+            !s.dead_link_check_external_anchors,
+            "external anchor checks are opt-in"
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn load_with_webauthn_rp_id() -> Result<()> {
         let mut file = NamedTempFile::new()?;
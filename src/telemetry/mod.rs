@@ -0,0 +1,392 @@
+//! OpenTelemetry trace export (`otlp` in the settings; off when omitted). The
+//! `tracing-opentelemetry` layer turns spans into OpenTelemetry spans, and the SDK's batch
+//! processor sends them through `opentelemetry-otlp`, as OTLP/HTTP protobuf, to a local
+//! collector such as Jaeger or Tempo. The processor exports from a thread of its own (the
+//! subscriber is installed before the app's runtime exists); [`shutdown`] flushes it on the
+//! way out. Export is best-effort: what the collector refuses or never gets is dropped.
+//!
+//! What becomes a span is whatever already is one: the per-request span (made by
+//! [`MakeRequestSpan`] for the `TraceLayer`), the background loops' passes and the
+//! ffmpeg / d2 / weasyprint shell-outs. Events inside a span — sqlx's per-statement
+//! `sqlx::query` events included — ride along as span events, and an `ERROR` event marks
+//! its span failed. `otel.kind` sets a span's kind.
+//!
+//! A request that arrives with a W3C `traceparent` continues the caller's trace; the alert
+//! sinks send one onward. A request's `request_log` row commits after its response has gone,
+//! so the writer holds the request span until then and records the row's id on it as
+//! `request_log.id` ([`record_request_log_id`]) — a trace and its log row can be found from
+//! each other. Holding the span delays its export, not its end time, which is the last time
+//! the request was polled.
+//!
+//! The request headers, cookies and bearer tokens included, are logged as an event of their
+//! own target, which [`filter`] keeps out of the export.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::Result;
+use axum::http::Request;
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::{SpanContext, TraceContextExt, TracerProvider as _};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_http::HeaderExtractor;
+use opentelemetry_otlp::{Protocol, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+use tower_http::trace::MakeSpan;
+use tracing::level_filters::LevelFilter;
+use tracing::{Level, Span, Subscriber, field, warn};
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::settings::Otlp;
+
+/// The target the request headers are logged under: the access log keeps them, the export
+/// never sees them.
+const HEADERS_TARGET: &str = "tower_http::trace::headers";
+
+/// How long one export POST may take.
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The provider behind the installed layer, kept for [`shutdown`].
+static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// A span's identity within its trace — what a `traceparent` header carries.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceContext(SpanContext);
+
+impl TraceContext {
+    /// The innermost exported span around the caller, if export is on.
+    pub fn current() -> Option<Self> {
+        let context = Span::current().context();
+        let span = context.span();
+        let span_context = span.span_context();
+        span_context.is_valid().then(|| Self(span_context.clone()))
+    }
+
+    /// `00-<trace id>-<span id>-<flags>`: version 0.
+    pub fn traceparent(&self) -> String {
+        format!(
+            "00-{}-{}-{:02x}",
+            self.0.trace_id(),
+            self.0.span_id(),
+            self.0.trace_flags().to_u8()
+        )
+    }
+}
+
+/// The request span a `request_log` row is logged in, if it's exported — held by the
+/// writer until the row commits.
+pub fn request_span() -> Option<Span> {
+    TraceContext::current().map(|_| Span::current())
+}
+
+/// Put a committed `request_log` row's id on the request span it was logged in, whose
+/// `request_log.id` field [`MakeRequestSpan`] leaves empty for it.
+pub fn record_request_log_id(request: &Span, id: i64) {
+    request.record("request_log.id", id);
+}
+
+/// Build the layer and the exporter behind it.
+pub fn layer<S>(config: &Otlp) -> Result<OpenTelemetryLayer<S, SdkTracer>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let provider = provider(config)?;
+    let layer = layer_for(&provider);
+    let _ = PROVIDER.set(provider);
+    Ok(layer)
+}
+
+fn provider(config: &Otlp) -> Result<SdkTracerProvider> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_protocol(Protocol::HttpBinary)
+        .with_endpoint(&config.endpoint)
+        .with_timeout(EXPORT_TIMEOUT)
+        .build()?;
+    let resource = Resource::builder()
+        .with_service_name(config.service_name.clone())
+        .with_attribute(KeyValue::new("service.version", env!("CARGO_PKG_VERSION")))
+        .build();
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource)
+        .build())
+}
+
+fn layer_for<S>(provider: &SdkTracerProvider) -> OpenTelemetryLayer<S, SdkTracer>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+}
+
+/// Flush the spans still queued and stop the exporter, once the server has stopped. A no-op
+/// with export off.
+pub fn shutdown() {
+    if let Some(provider) = PROVIDER.get()
+        && let Err(e) = provider.shutdown()
+    {
+        warn!("otlp: flushing the exporter on shutdown failed: {e}");
+    }
+}
+
+/// What's exported: the app's own spans and events, the request span (`tower_http`, less
+/// its headers) and sqlx's statements; only warnings and errors from anything else.
+pub fn filter() -> Targets {
+    Targets::new()
+        .with_target("hotchkiss_io", Level::DEBUG)
+        .with_target("tower_http", Level::DEBUG)
+        .with_target(HEADERS_TARGET, LevelFilter::OFF)
+        .with_target("sqlx::query", Level::DEBUG)
+        .with_default(Level::WARN)
+}
+
+/// The `TraceLayer`'s request span: `DefaultMakeSpan`'s, plus what the export needs — the
+/// server kind, the caller's `traceparent` as its parent and an empty `request_log.id` for
+/// [`record_request_log_id`]. Keeps `DefaultMakeSpan`'s
+/// target so the span is still only in the access log, and logs the headers there as an
+/// event rather than a field.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MakeRequestSpan;
+
+impl<B> MakeSpan<B> for MakeRequestSpan {
+    fn make_span(&mut self, request: &Request<B>) -> Span {
+        let span = tracing::debug_span!(
+            target: "tower_http::trace::make_span",
+            "request",
+            method = %request.method(),
+            uri = %request.uri(),
+            version = ?request.version(),
+            otel.kind = "server",
+            request_log.id = field::Empty,
+        );
+        let caller = TraceContextPropagator::new()
+            .extract_with_context(&Context::new(), &HeaderExtractor(request.headers()));
+        if caller.has_active_span() {
+            let _ = span.set_parent(caller);
+        }
+        tracing::debug!(target: HEADERS_TARGET, parent: &span, headers = ?request.headers());
+        span
+    }
+}
+
+/// Wrap a `spawn_blocking` closure so it runs inside the caller's span — a blocking
+/// thread doesn't inherit it, and the shell-outs there should land in the request's trace.
+pub fn in_current_span<F, R>(f: F) -> impl FnOnce() -> R
+where
+    F: FnOnce() -> R,
+{
+    let span = Span::current();
+    move || span.in_scope(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Bytes;
+    use axum::http::HeaderMap;
+    use axum::{Router, extract::State, routing::post};
+    use opentelemetry::trace::{SpanId, SpanKind, Status, TraceId};
+    use opentelemetry::{StringValue, Value};
+    use opentelemetry_sdk::error::OTelSdkResult;
+    use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+    use std::sync::{Arc, Mutex};
+    use tracing::{error, info, info_span};
+    use tracing_subscriber::Layer;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    const REMOTE: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    /// Keeps what it's handed.
+    #[derive(Clone, Debug, Default)]
+    struct Collected(Arc<Mutex<Vec<SpanData>>>);
+
+    impl SpanExporter for Collected {
+        async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+            self.0.lock().unwrap().extend(batch);
+            Ok(())
+        }
+    }
+
+    /// A provider that exports each span as it ends, into the returned list.
+    fn collecting() -> (SdkTracerProvider, Collected) {
+        let collected = Collected::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(collected.clone())
+            .build();
+        (provider, collected)
+    }
+
+    fn attribute<'a>(span: &'a SpanData, key: &str) -> Option<&'a Value> {
+        span.attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| &kv.value)
+    }
+
+    #[test]
+    fn requests_continue_a_remote_parent_and_nest_their_spans() {
+        let (provider, collected) = collecting();
+        let subscriber =
+            tracing_subscriber::registry().with(layer_for(&provider).with_filter(filter()));
+        let request = Request::builder()
+            .uri("/resume")
+            .header("traceparent", REMOTE)
+            .header("cookie", "id=secret")
+            .body(())
+            .unwrap();
+
+        let (outer, inner) = tracing::subscriber::with_default(subscriber, || {
+            let span = MakeRequestSpan.make_span(&request);
+            let _guard = span.enter();
+            let outer = TraceContext::current().expect("inside an exported span");
+            let inner = info_span!("d2", bytes = 42).in_scope(|| {
+                info!(rows = 3, "query done");
+                error!("d2 failed");
+                TraceContext::current().unwrap()
+            });
+            (outer, inner)
+        });
+
+        let spans = collected.0.lock().unwrap();
+        assert_eq!(spans.len(), 2, "both ended");
+        let (d2, req) = (&spans[0], &spans[1]);
+
+        assert_eq!(req.name, "request");
+        assert_eq!(req.span_kind, SpanKind::Server);
+        assert_eq!(req.span_context, outer.0);
+        assert_eq!(
+            req.span_context.trace_id(),
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            "continues the caller's trace"
+        );
+        assert_eq!(
+            req.parent_span_id,
+            SpanId::from_hex("00f067aa0ba902b7").unwrap()
+        );
+        assert_eq!(
+            attribute(req, "method"),
+            Some(&Value::String(StringValue::from("GET")))
+        );
+        assert_eq!(attribute(req, "headers"), None);
+        assert!(req.events.is_empty(), "the headers event isn't exported");
+        assert_eq!(req.status, Status::Unset);
+
+        assert_eq!(d2.span_context, inner.0);
+        assert_eq!(d2.span_context.trace_id(), req.span_context.trace_id());
+        assert_eq!(d2.parent_span_id, req.span_context.span_id());
+        assert_eq!(attribute(d2, "bytes"), Some(&Value::I64(42)));
+        assert_eq!(d2.events.len(), 2);
+        assert_eq!(d2.events[0].name, "query done");
+        assert_eq!(d2.events[1].name, "d2 failed");
+        assert!(
+            matches!(d2.status, Status::Error { .. }),
+            "the ERROR event fails it"
+        );
+    }
+
+    #[test]
+    fn request_log_ids_land_on_the_request_span() {
+        let (provider, collected) = collecting();
+        let subscriber =
+            tracing_subscriber::registry().with(layer_for(&provider).with_filter(filter()));
+        let request = Request::builder().uri("/").body(()).unwrap();
+
+        let done = tracing::subscriber::with_default(subscriber, || {
+            let held = {
+                let span = MakeRequestSpan.make_span(&request);
+                let _guard = span.enter();
+                request_span().expect("inside an exported span")
+            };
+            let done = std::time::SystemTime::now();
+            assert!(
+                collected.0.lock().unwrap().is_empty(),
+                "held until the row commits"
+            );
+            record_request_log_id(&held, 7);
+            drop(held);
+            done
+        });
+
+        let spans = collected.0.lock().unwrap();
+        assert_eq!(spans.len(), 1, "no span of its own");
+        let req = &spans[0];
+        assert_eq!(req.name, "request");
+        assert_eq!(attribute(req, "request_log.id"), Some(&Value::I64(7)));
+        assert!(
+            req.end_time <= done,
+            "ends with the request, not the commit"
+        );
+    }
+
+    #[test]
+    fn traceparent_names_the_current_span() {
+        let (provider, _) = collecting();
+        let subscriber = tracing_subscriber::registry().with(layer_for(&provider));
+        tracing::subscriber::with_default(subscriber, || {
+            let _guard = info_span!("pass").entered();
+            let context = TraceContext::current().unwrap();
+            assert_eq!(
+                context.traceparent(),
+                format!("00-{}-{}-01", context.0.trace_id(), context.0.span_id())
+            );
+            assert_eq!(context.traceparent().len(), REMOTE.len());
+        });
+    }
+
+    #[test]
+    fn no_context_without_the_layer() {
+        let subscriber = tracing_subscriber::registry();
+        tracing::subscriber::with_default(subscriber, || {
+            let _guard = info_span!("request").entered();
+            assert_eq!(TraceContext::current(), None);
+            assert!(request_span().is_none());
+        });
+    }
+
+    type Received = Arc<Mutex<Vec<(HeaderMap, Bytes)>>>;
+
+    /// The stand-in collector's `/v1/traces`.
+    async fn collect(State(received): State<Received>, headers: HeaderMap, body: Bytes) {
+        received.lock().unwrap().push((headers, body));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn shutdown_flushes_to_the_collector() {
+        let received = Received::default();
+        let app = Router::new()
+            .route("/v1/traces", post(collect))
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/v1/traces", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+
+        let config = Otlp {
+            endpoint,
+            ..Otlp::default()
+        };
+        let provider = provider(&config).unwrap();
+        let subscriber = tracing_subscriber::registry().with(layer_for(&provider));
+        tracing::subscriber::with_default(subscriber, || {
+            info_span!("pass").in_scope(|| info!("swept"));
+        });
+        assert!(received.lock().unwrap().is_empty(), "still batched");
+
+        // The exporter's client blocks; keep it off the runtime's threads.
+        tokio::task::spawn_blocking(move || provider.shutdown())
+            .await
+            .unwrap()
+            .unwrap();
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (headers, body) = &received[0];
+        assert_eq!(headers["content-type"], "application/x-protobuf");
+        assert!(!body.is_empty());
+    }
+}
@@ -24,6 +24,26 @@ use crate::{
     web::{app_state::AppState, router::create_router},
 };
 
+/// The test server's one honeypot path (see `web::trap`).
+pub const TEST_HONEYPOT: &str = "/staff-directory";
+
+/// The `/metrics` scrape token the test server accepts.
+pub const TEST_METRICS_TOKEN: &str = "test-scrape-token";
+
+/// The committed GeoIP fixtures (`tests/fixtures/geoip`, from
+/// `build/geoip-fixture/make-mmdb.sh`): 192.0.2.0/24 is US on AS16509 (Amazon, a built-in
+/// hosting ASN), 198.51.100.0/24 DE on AS64500, 203.0.113.0/24 NL on AS64496; nothing else
+/// is known. `weigh_hosting_asns` as asked, with AS64496 added to the hosting list.
+pub(crate) fn fixture_geoip(weigh_hosting_asns: bool) -> crate::settings::GeoIp {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/geoip");
+    crate::settings::GeoIp {
+        country_db: Some(dir.join("country.mmdb")),
+        asn_db: Some(dir.join("asn.mmdb")),
+        weigh_hosting_asns,
+        hosting_asns: vec![64_496],
+    }
+}
+
 /// A running test instance. `Drop` aborts the server task and deletes the temp DB.
 pub struct TestServer {
     /// e.g. `http://localhost:54321` (no trailing slash). Hit it via `localhost`,
@@ -263,7 +283,7 @@ pub async fn spawn_test_server() -> Result<TestServer> {
     session_store.migrate().await?;
 
     let greylist = crate::greylist::active_set::GreylistSet::new();
-    let dead_links = crate::deadlinks::DeadLinkScanState::new();
+    let dead_links = crate::deadlinks::DeadLinkScanState::new(false);
     let app_state = AppState {
         pool: pool.clone(),
         session_store,
@@ -283,6 +303,28 @@ pub async fn spawn_test_server() -> Result<TestServer> {
             Default::default(),
         ),
         dead_links: dead_links.clone(),
+        // Enforce, so the browser tests run under the policy production will ship.
+        csp_report_only: false,
+        // The shipped limits, so the tests exercise what production throttles.
+        rate_limiter: crate::web::rate_limit::RateLimiter::new(Default::default()),
+        // The shipped batching, so tests that poll request_log see production's write lag.
+        request_log: crate::web::request_log_writer::RequestLogWriter::spawn(
+            pool.clone(),
+            Default::default(),
+        ),
+        // A short hold and a cap of two, so the tarpit tests can fill it and watch it drain.
+        traps: crate::web::trap::Traps::new(
+            Some(crate::settings::Tarpit {
+                max_concurrent: 2,
+                drip_every_ms: 250,
+                hold_secs: 1,
+            }),
+            &[TEST_HONEYPOT.to_string()],
+        ),
+        // The fixture databases, so the country / ASN panels render. R5's weighting stays off:
+        // the sweep tests score the documentation ranges without it.
+        geoip: crate::geoip::GeoDb::open(Some(&fixture_geoip(false))),
+        metrics: crate::metrics::Metrics::new(Some(TEST_METRICS_TOKEN.to_string())),
     };
     let router = create_router(app_state).await?;
 
@@ -35,4 +35,22 @@ pub struct AppState {
     /// guard + last-run status the `/admin/dead-links` page shows and the "Run scan
     /// now" button triggers. Shared with the detached daily scan loop.
     pub dead_links: crate::deadlinks::DeadLinkScanState,
+    /// Send the CSP as `Content-Security-Policy-Report-Only` (the rollout mode)
+    /// rather than enforcing it. From `Settings.csp_report_only`.
+    pub csp_report_only: bool,
+    /// Per-route-group token buckets (login ceremony, media writes, MCP), enforced by
+    /// the `rate_limit` middleware. Limits from `Settings.rate_limits`.
+    pub rate_limiter: crate::web::rate_limit::RateLimiter,
+    /// The batched `request_log` writer the logging middleware enqueues onto. Shared
+    /// with the coordinator, which flushes it on shutdown.
+    pub request_log: crate::web::request_log_writer::RequestLogWriter,
+    /// The signature-path tarpit (and its concurrency cap) and the honeypot paths, enforced
+    /// by the `trap` middleware. From `Settings.tarpit` / `Settings.honeypot_paths`.
+    pub traps: crate::web::trap::Traps,
+    /// The GeoIP / ASN databases the analytics and greylist pages enrich IPs from (off
+    /// unless `Settings.geoip` names them). Shared with the sweep's R5.
+    pub geoip: crate::geoip::GeoDb,
+    /// Request counts / latency by route and the timed background tasks' last runs, for
+    /// `/metrics` — plus the scrape token it accepts (`Settings.metrics_token`).
+    pub metrics: crate::metrics::Metrics,
 }
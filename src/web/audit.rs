@@ -0,0 +1,122 @@
+//! The audit trail's write side: WHO is acting (an [`Actor`], extracted per request or
+//! built from an MCP tool call's `Parts`) and [`record`], which appends one
+//! `audit_log` row. Recording is fail-soft — the mutation already happened, so a
+//! failed audit insert is logged loudly rather than turned into a 500 that would
+//! make the caller retry a write that succeeded.
+
+use std::convert::Infallible;
+
+use axum::extract::FromRequestParts;
+use http::request::Parts;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::db::dao::audit_log::{AuditLogDao, NewAuditEntry};
+use crate::web::{middleware::api_key_auth::ApiKeyId, session::SessionData};
+
+/// How the actor authenticated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthChannel {
+    /// A cookie session (the browser UI).
+    Session,
+    /// A Bearer API key, by id.
+    ApiKey(i64),
+    /// A tool call on `/mcp` — keyed in practice, so it carries the key id when present.
+    Mcp(Option<i64>),
+    /// The operator at the server's console (the offline `recovery_code` binary).
+    Cli,
+}
+
+impl AuthChannel {
+    /// The stored `channel` column value.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthChannel::Session => "session",
+            AuthChannel::ApiKey(_) => "api_key",
+            AuthChannel::Mcp(_) => "mcp",
+            AuthChannel::Cli => "cli",
+        }
+    }
+
+    fn api_key_id(&self) -> Option<i64> {
+        match *self {
+            AuthChannel::Session | AuthChannel::Cli => None,
+            AuthChannel::ApiKey(id) => Some(id),
+            AuthChannel::Mcp(id) => id,
+        }
+    }
+}
+
+/// Who performed a mutation. `user_id`/`display_name` are `None` only for an
+/// unauthenticated caller, which the mutation layer never lets this far.
+#[derive(Debug, Clone)]
+pub struct Actor {
+    pub user_id: Option<Uuid>,
+    pub display_name: Option<String>,
+    pub channel: AuthChannel,
+}
+
+impl Actor {
+    fn from_session(session_data: &SessionData, channel: AuthChannel) -> Self {
+        let user = session_data.auth_state.user();
+        Self {
+            user_id: user.map(|u| u.id),
+            display_name: user.map(|u| u.display_name.clone()),
+            channel,
+        }
+    }
+
+    /// The actor behind an MCP tool call. rmcp hands tools the request `Parts`,
+    /// where `api_key_auth` left the key's identity.
+    pub fn mcp(parts: &Parts) -> Self {
+        let session_data = parts.extensions.get::<SessionData>().cloned().unwrap_or_default();
+        let key = parts.extensions.get::<ApiKeyId>().map(|k| k.0);
+        Self::from_session(&session_data, AuthChannel::Mcp(key))
+    }
+}
+
+impl<S> FromRequestParts<S> for Actor
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let channel = match parts.extensions.get::<ApiKeyId>() {
+            Some(ApiKeyId(id)) => AuthChannel::ApiKey(*id),
+            None => AuthChannel::Session,
+        };
+        // `SessionData`'s own extraction already falls back to Anonymous.
+        let session_data = SessionData::from_request_parts(parts, state)
+            .await
+            .unwrap_or_default();
+        Ok(Actor::from_session(&session_data, channel))
+    }
+}
+
+/// Append one audit row: `action` on `target` by `actor`, with optional JSON
+/// summaries of the entity before and after. Never fails the caller.
+pub async fn record(
+    pool: &SqlitePool,
+    actor: &Actor,
+    action: &str,
+    target: &str,
+    before: Option<Value>,
+    after: Option<Value>,
+) {
+    let entry = NewAuditEntry {
+        actor_id: actor.user_id.map(|id| id.to_string()),
+        actor_name: actor.display_name.clone(),
+        channel: actor.channel.as_str(),
+        api_key_id: actor.channel.api_key_id(),
+        action,
+        target,
+        before: before.map(|v| v.to_string()),
+        after: after.map(|v| v.to_string()),
+    };
+    if let Err(e) = AuditLogDao::record(pool, &entry).await {
+        warn!("audit: failed to record {action} on {target}: {e:#}");
+    }
+}
@@ -0,0 +1,96 @@
+//! Self-service account page (`/account`): every signed-in user — not just the
+//! admin — manages their own passkeys, active sessions and recovery codes here.
+//! Reads are gated in the handlers (an anonymous visit goes to
+//! `/login?next=/account`); the mutations ride the fail-closed layer's
+//! role-scoped allowlist at `Registered`, with every id in the request body and
+//! per-user scoping inside the DAO calls.
+
+use askama::Template;
+use axum::{
+    extract::State,
+    middleware::from_fn,
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Router,
+};
+
+use tower_sessions::Session;
+
+use crate::db::dao::recovery_codes::RecoveryCodeDao;
+use crate::web::{
+    app_error::AppError, app_state::AppState, authentication_state::AuthenticationState,
+    features::top_bar::TopBar, html_template::HtmlTemplate,
+    middleware::require_step_up::require_step_up, session::SessionData,
+};
+
+pub mod passkeys;
+pub mod recovery_codes;
+pub mod sessions;
+
+pub fn account_router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(show_account))
+        // Add a passkey to THIS account: the same two-step ceremony as
+        // /login/start_register, keyed on the new credential's label. A new
+        // passkey answers /step-up from then on, so adding one needs a fresh
+        // assertion itself — a stolen cookie can't enroll its own.
+        .route(
+            "/passkeys/start/{label}",
+            get(passkeys::start_add_passkey).layer(from_fn(require_step_up)),
+        )
+        .route(
+            "/passkeys/finish",
+            post(passkeys::finish_add_passkey).layer(from_fn(require_step_up)),
+        )
+        // Ids ride the form body (the role-scoped allowlist is exact-path).
+        .route("/passkeys/rename", post(passkeys::rename_passkey))
+        // Removing one can strip the owner's other devices: step-up gated too.
+        .route(
+            "/passkeys/delete",
+            post(passkeys::delete_passkey).layer(from_fn(require_step_up)),
+        )
+        .route("/sessions/revoke", post(sessions::revoke_own_session))
+        .route("/sessions/revoke_others", post(sessions::revoke_other_sessions))
+        // A new set of recovery codes is a new way in: demand a fresh assertion.
+        .route(
+            "/recovery-codes/generate",
+            post(recovery_codes::generate_recovery_codes).layer(from_fn(require_step_up)),
+        )
+}
+
+#[derive(Template)]
+#[template(path = "account/index.html")]
+pub struct AccountTemplate {
+    pub top_bar: TopBar,
+    pub auth_state: AuthenticationState,
+    pub passkeys: Vec<passkeys::PasskeyView>,
+    pub sessions: Vec<sessions::SessionView>,
+    pub recovery_codes: recovery_codes::RecoveryCodesView,
+}
+
+/// Where an anonymous visitor is sent: sign in, then land back here.
+pub(crate) fn login_redirect() -> Response {
+    Redirect::to("/login?next=%2Faccount").into_response()
+}
+
+async fn show_account(
+    State(state): State<AppState>,
+    session: Session,
+    session_data: SessionData,
+) -> Result<Response, AppError> {
+    let Some(user) = session_data.auth_state.user() else {
+        return Ok(login_redirect());
+    };
+    let passkeys = passkeys::list_views(&state, user).await?;
+    let current = session.id().map(|id| id.to_string());
+    let sessions = sessions::live_sessions(&state, &user.id, current.as_deref()).await?;
+    let recovery_codes = RecoveryCodeDao::summary(&state.pool, &user.id).await?.into();
+    let template = AccountTemplate {
+        top_bar: TopBar::create(&state.pool, "account", session_data.auth_state.role()).await?,
+        auth_state: session_data.auth_state,
+        passkeys,
+        sessions,
+        recovery_codes,
+    };
+    Ok(HtmlTemplate(template).into_response())
+}
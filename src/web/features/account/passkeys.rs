@@ -0,0 +1,246 @@
+//! Passkey management on `/account`: list, label, add (a WebAuthn registration
+//! ceremony against the EXISTING account), and delete — never the last one, which
+//! would lock the account out with no way back in.
+
+use anyhow::Context;
+use axum::{
+    Form, Json,
+    extract::{Path, State},
+    response::{IntoResponse, Redirect, Response},
+};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use tower_sessions::Session;
+use tracing::{info, warn};
+use webauthn_rs::prelude::{PasskeyRegistration, RegisterPublicKeyCredential};
+
+use super::login_redirect;
+use crate::db::dao::{
+    passkeys::{cred_id_key, PasskeyMetaDao, DEFAULT_PASSKEY_LABEL},
+    users::{KeyRemoval, UserDao},
+};
+use crate::web::{
+    app_error::AppError, app_state::AppState, htmx_responses::htmx_refresh, session::SessionData,
+};
+
+/// Session key for an in-flight "add a passkey" ceremony. Kept apart from
+/// `SessionData.auth_state` so the account stays `Authenticated` throughout.
+const PENDING_PASSKEY_KEY: &str = "account_pending_passkey";
+
+/// Longest label accepted — it's display text, not a document.
+const MAX_LABEL_CHARS: usize = 64;
+
+#[derive(Deserialize, Serialize)]
+struct PendingPasskey {
+    registration: PasskeyRegistration,
+    label: String,
+}
+
+/// One registered credential as the page shows it.
+pub struct PasskeyView {
+    pub cred_id: String,
+    pub label: String,
+    pub created: String,
+    pub last_used: String,
+}
+
+/// The user's credentials in `users.keys` order, each paired with its metadata.
+/// A credential with no metadata row (shouldn't happen after migration 0034)
+/// still lists, under the default label, so it can be seen and removed.
+pub(super) async fn list_views(
+    state: &AppState,
+    user: &UserDao,
+) -> anyhow::Result<Vec<PasskeyView>> {
+    let meta = PasskeyMetaDao::list_for_user(&state.pool, &user.id).await?;
+    Ok(user
+        .keys
+        .iter()
+        .map(|pk| {
+            let cred_id = cred_id_key(pk.cred_id());
+            let m = meta.iter().find(|m| m.cred_id == cred_id);
+            PasskeyView {
+                label: m
+                    .map(|m| m.label.clone())
+                    .unwrap_or_else(|| DEFAULT_PASSKEY_LABEL.to_string()),
+                created: m
+                    .and_then(|m| m.created_at)
+                    .map(|t| t.format("%Y-%m-%d").to_string())
+                    .unwrap_or_else(|| "before tracking".to_string()),
+                last_used: m
+                    .and_then(|m| m.last_used_at)
+                    .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+                    .unwrap_or_else(|| "never".to_string()),
+                cred_id,
+            }
+        })
+        .collect())
+}
+
+pub(crate) fn validate_label(label: &str) -> Result<(), &'static str> {
+    if label.is_empty() {
+        return Err("Please name the passkey (e.g. \"phone\").");
+    }
+    if label.chars().count() > MAX_LABEL_CHARS {
+        return Err("That name is too long (64 characters max).");
+    }
+    if label.chars().any(char::is_control) {
+        return Err("That name contains invalid characters.");
+    }
+    Ok(())
+}
+
+/// `GET /account/passkeys/start/{label}` — begin registering another passkey for
+/// the signed-in user. Every existing credential is excluded, so an authenticator
+/// that already holds one for this account refuses instead of minting a duplicate.
+pub async fn start_add_passkey(
+    State(state): State<AppState>,
+    session: Session,
+    session_data: SessionData,
+    Path(label): Path<String>,
+) -> Result<Response, AppError> {
+    let Some(user) = session_data.auth_state.user() else {
+        return Ok((StatusCode::UNAUTHORIZED, "Sign in first.").into_response());
+    };
+    let label = label.trim().to_string();
+    if let Err(msg) = validate_label(&label) {
+        return Ok((StatusCode::BAD_REQUEST, msg).into_response());
+    }
+
+    let exclude = user.keys.iter().map(|k| k.cred_id().clone()).collect();
+    let (ccr, registration) = state
+        .webauthn
+        .start_passkey_registration(user.id, &user.display_name, &user.display_name, Some(exclude))
+        .context("Failed to start passkey registration.")?;
+
+    session
+        .insert(PENDING_PASSKEY_KEY, PendingPasskey { registration, label })
+        .await?;
+    Ok(Json(ccr).into_response())
+}
+
+/// `POST /account/passkeys/finish` — verify the new credential and append it to
+/// the account. Redirects to `/account` on success (the ceremony JS treats the
+/// redirect as the success signal).
+pub async fn finish_add_passkey(
+    State(state): State<AppState>,
+    session: Session,
+    session_data: SessionData,
+    Json(rpc): Json<RegisterPublicKeyCredential>,
+) -> Result<Response, AppError> {
+    let Some(user_id) = session_data.auth_state.user().map(|u| u.id) else {
+        return Ok((StatusCode::UNAUTHORIZED, "Sign in first.").into_response());
+    };
+    let Some(pending) = session.remove::<PendingPasskey>(PENDING_PASSKEY_KEY).await? else {
+        warn!("account passkey finish called with no ceremony in progress");
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            "Adding a passkey wasn't started, or your session expired — please try again.",
+        )
+            .into_response());
+    };
+
+    let passkey = state
+        .webauthn
+        .finish_passkey_registration(&rpc, &pending.registration)?;
+    if UserDao::find_by_passkey(&state.pool, &passkey).await?.is_some() {
+        return Ok((StatusCode::CONFLICT, "That passkey is already registered.").into_response());
+    }
+
+    // Append in place (another tab may have changed the list since this session's
+    // snapshot), with its label, in one transaction.
+    let cred_id = cred_id_key(passkey.cred_id());
+    let mut tx = state.pool.begin().await?;
+    if !UserDao::add_key(&mut *tx, &user_id, &passkey).await? {
+        return Ok((StatusCode::UNAUTHORIZED, "Sign in first.").into_response());
+    }
+    PasskeyMetaDao::record(&mut *tx, &user_id, &cred_id, &pending.label).await?;
+    tx.commit().await?;
+
+    info!("passkey added for user {user_id} ({})", pending.label);
+    Ok(Redirect::to("/account").into_response())
+}
+
+#[derive(Deserialize)]
+pub struct RenameForm {
+    pub cred_id: String,
+    pub label: String,
+}
+
+/// `POST /account/passkeys/rename` — relabel one of your own credentials.
+pub async fn rename_passkey(
+    State(state): State<AppState>,
+    session_data: SessionData,
+    Form(form): Form<RenameForm>,
+) -> Result<Response, AppError> {
+    let Some(user) = session_data.auth_state.user() else {
+        return Ok(login_redirect());
+    };
+    let label = form.label.trim();
+    if let Err(msg) = validate_label(label) {
+        return Ok((StatusCode::BAD_REQUEST, msg).into_response());
+    }
+    // Ownership from the row, not the session's snapshot of it: a passkey removed in
+    // another tab must not get a fresh metadata row here.
+    let mut tx = state.pool.begin().await?;
+    if !UserDao::has_key(&mut *tx, &user.id, &form.cred_id).await? {
+        return Ok((StatusCode::NOT_FOUND, "No such passkey").into_response());
+    }
+    // A credential without a metadata row gets one on first rename.
+    if !PasskeyMetaDao::rename(&mut *tx, &user.id, &form.cred_id, label).await? {
+        PasskeyMetaDao::record(&mut *tx, &user.id, &form.cred_id, label).await?;
+    }
+    tx.commit().await?;
+    Ok(htmx_refresh())
+}
+
+#[derive(Deserialize)]
+pub struct DeleteForm {
+    pub cred_id: String,
+}
+
+/// `POST /account/passkeys/delete` — remove one of your own credentials. Refuses
+/// (409) to remove the last one: a passkey-only account with none left can never
+/// sign in again.
+pub async fn delete_passkey(
+    State(state): State<AppState>,
+    session_data: SessionData,
+    Form(form): Form<DeleteForm>,
+) -> Result<Response, AppError> {
+    let Some(user_id) = session_data.auth_state.user().map(|u| u.id) else {
+        return Ok(login_redirect());
+    };
+    // The last-one check and the removal are one UPDATE (see `UserDao::remove_key`), so
+    // two tabs deleting different passkeys at once can't both pass it.
+    let mut tx = state.pool.begin().await?;
+    match UserDao::remove_key(&mut tx, &user_id, &form.cred_id).await? {
+        KeyRemoval::Removed => {}
+        KeyRemoval::NotFound => {
+            return Ok((StatusCode::NOT_FOUND, "No such passkey").into_response());
+        }
+        KeyRemoval::LastKey => {
+            return Ok((
+                StatusCode::CONFLICT,
+                "That's your only passkey — add another before removing it.",
+            )
+                .into_response());
+        }
+    }
+    PasskeyMetaDao::delete(&mut *tx, &user_id, &form.cred_id).await?;
+    tx.commit().await?;
+    info!("passkey removed for user {user_id}");
+    Ok(htmx_refresh())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_validation() {
+        assert!(validate_label("phone").is_ok());
+        assert!(validate_label("Chris's YubiKey 5C").is_ok());
+        assert!(validate_label("").is_err());
+        assert!(validate_label(&"x".repeat(65)).is_err());
+        assert!(validate_label("a\nb").is_err());
+    }
+}
@@ -0,0 +1,79 @@
+//! Recovery codes on `/account`: the fallback for losing every passkey. A set is
+//! generated here and shown ONCE; redeeming one happens on the sign-in page (see
+//! `web::features::recovery`). Generating is step-up gated — a stolen session
+//! cookie must not be able to mint itself a way back in after it's revoked.
+
+use askama::Template;
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+};
+use http::StatusCode;
+use serde_json::json;
+use tracing::info;
+
+use super::login_redirect;
+use crate::db::dao::recovery_codes::{RecoveryCodeDao, RecoveryCodeSummary};
+use crate::web::{
+    app_error::AppError,
+    app_state::AppState,
+    audit::{self, Actor, AuthChannel},
+    html_template::HtmlTemplate,
+    session::SessionData,
+};
+
+/// The section's status line on the account page.
+pub struct RecoveryCodesView {
+    pub unused: i64,
+    pub generated: Option<String>,
+}
+
+impl From<RecoveryCodeSummary> for RecoveryCodesView {
+    fn from(s: RecoveryCodeSummary) -> Self {
+        Self {
+            unused: s.unused,
+            generated: s.generated_at.map(|t| t.format("%Y-%m-%d").to_string()),
+        }
+    }
+}
+
+/// The freshly generated set, swapped into the account page's recovery section.
+#[derive(Template)]
+#[template(path = "account/recovery_codes_new.html")]
+pub struct NewRecoveryCodesTemplate {
+    pub codes: Vec<String>,
+}
+
+/// `POST /account/recovery-codes/generate` — replace the signed-in user's codes
+/// with a fresh set and show it, once. Never over an API key, whatever the route's
+/// layers say: a leaked key must not be able to mint a way back into the account.
+pub async fn generate_recovery_codes(
+    State(state): State<AppState>,
+    actor: Actor,
+    session_data: SessionData,
+) -> Result<Response, AppError> {
+    if let AuthChannel::ApiKey(_) = actor.channel {
+        return Ok((
+            StatusCode::FORBIDDEN,
+            "Recovery codes can only be generated from a signed-in browser, not an API key.",
+        )
+            .into_response());
+    }
+    let Some(user) = session_data.auth_state.user() else {
+        return Ok(login_redirect());
+    };
+    let codes = RecoveryCodeDao::replace_set(&state.pool, &user.id).await?;
+    let target = user.id.to_string();
+    let after = json!({ "codes": codes.len() });
+    audit::record(
+        &state.pool,
+        &actor,
+        "recovery_code.generate",
+        &target,
+        None,
+        Some(after),
+    )
+    .await;
+    info!("recovery codes generated for user {:?}", user.display_name);
+    Ok(HtmlTemplate(NewRecoveryCodesTemplate { codes }).into_response())
+}
@@ -0,0 +1,154 @@
+//! Active sessions on `/account`: where this user is signed in (created, last
+//! activity, user agent, IP), with revoke-one and revoke-all-others. The admin's
+//! per-user controls on `/admin/users` and the role-change / delete handlers use
+//! the same revoke helpers, so "sign them out everywhere" means one thing.
+//!
+//! A session is only truly gone once its tower-sessions RECORD is deleted — the
+//! `user_sessions` row is just the index that finds it. Every revoke deletes both.
+
+use std::str::FromStr;
+
+use axum::{
+    Form,
+    extract::State,
+    response::{IntoResponse, Response},
+};
+use http::StatusCode;
+use serde::Deserialize;
+use tower_sessions::{
+    Session, SessionStore,
+    session::{Id, Record},
+};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::login_redirect;
+use crate::db::dao::user_sessions::UserSessionDao;
+use crate::web::{
+    app_error::AppError, app_state::AppState, htmx_responses::htmx_refresh, session::SessionData,
+};
+
+/// One live session as the account / admin pages show it.
+pub struct SessionView {
+    pub session_id: String,
+    pub created: String,
+    pub last_seen: String,
+    pub user_agent: String,
+    pub ip: String,
+    /// The session making this request ("this device").
+    pub is_current: bool,
+}
+
+/// Whether a store record is still an Authenticated session for `user_id` — a
+/// logged-out session survives in the store as Anonymous and must not list.
+fn record_is_user(record: &Record, user_id: &Uuid) -> bool {
+    record
+        .data
+        .get(SessionData::SESSION_DATA_KEY)
+        .and_then(|v| serde_json::from_value::<SessionData>(v.clone()).ok())
+        .and_then(|d| d.auth_state.user().map(|u| u.id))
+        .is_some_and(|id| id == *user_id)
+}
+
+/// `user_id`'s live sessions, most recently active first. Each index row is
+/// checked against the store; rows whose session expired or signed out are
+/// dropped on the way (the hourly GC would get them anyway).
+pub(crate) async fn live_sessions(
+    state: &AppState,
+    user_id: &Uuid,
+    current: Option<&str>,
+) -> anyhow::Result<Vec<SessionView>> {
+    let mut views = Vec::new();
+    for row in UserSessionDao::list_for_user(&state.pool, user_id).await? {
+        let live = match Id::from_str(&row.session_id) {
+            Ok(id) => match state.session_store.load(&id).await {
+                Ok(Some(record)) => record_is_user(&record, user_id),
+                Ok(None) => false,
+                Err(e) => {
+                    // Can't tell — show it rather than hide a session that may be live.
+                    warn!("session store load failed: {e}");
+                    true
+                }
+            },
+            Err(_) => false,
+        };
+        if !live {
+            UserSessionDao::delete(&state.pool, &row.session_id).await?;
+            continue;
+        }
+        views.push(SessionView {
+            is_current: current == Some(row.session_id.as_str()),
+            created: row.created_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+            last_seen: row.last_seen_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+            user_agent: row.user_agent.unwrap_or_else(|| "unknown".to_string()),
+            ip: row.ip.unwrap_or_else(|| "unknown".to_string()),
+            session_id: row.session_id,
+        });
+    }
+    Ok(views)
+}
+
+/// Delete one session: the store record (which signs it out on its next request)
+/// and its index row.
+pub(crate) async fn revoke_session(state: &AppState, session_id: &str) -> anyhow::Result<()> {
+    if let Ok(id) = Id::from_str(session_id) {
+        state.session_store.delete(&id).await?;
+    }
+    UserSessionDao::delete(&state.pool, session_id).await
+}
+
+/// Delete every session `user_id` has, except `keep` (the caller's own, for
+/// "sign out everywhere else"). Returns how many were revoked.
+pub(crate) async fn revoke_user_sessions(
+    state: &AppState,
+    user_id: &Uuid,
+    keep: Option<&str>,
+) -> anyhow::Result<usize> {
+    let mut revoked = 0;
+    for row in UserSessionDao::list_for_user(&state.pool, user_id).await? {
+        if keep == Some(row.session_id.as_str()) {
+            continue;
+        }
+        revoke_session(state, &row.session_id).await?;
+        revoked += 1;
+    }
+    Ok(revoked)
+}
+
+#[derive(Deserialize)]
+pub struct RevokeForm {
+    pub session_id: String,
+}
+
+/// `POST /account/sessions/revoke` — sign out one of your own sessions (another
+/// device, or this one). Someone else's id is a 404, never a revoke.
+pub async fn revoke_own_session(
+    State(state): State<AppState>,
+    session_data: SessionData,
+    Form(form): Form<RevokeForm>,
+) -> Result<Response, AppError> {
+    let Some(user) = session_data.auth_state.user() else {
+        return Ok(login_redirect());
+    };
+    if !UserSessionDao::belongs_to(&state.pool, &form.session_id, &user.id).await? {
+        return Ok((StatusCode::NOT_FOUND, "No such session").into_response());
+    }
+    revoke_session(&state, &form.session_id).await?;
+    info!("user {:?} revoked one of their sessions", user.display_name);
+    Ok(htmx_refresh())
+}
+
+/// `POST /account/sessions/revoke_others` — sign out everywhere except here.
+pub async fn revoke_other_sessions(
+    State(state): State<AppState>,
+    session: Session,
+    session_data: SessionData,
+) -> Result<Response, AppError> {
+    let Some(user) = session_data.auth_state.user() else {
+        return Ok(login_redirect());
+    };
+    let current = session.id().map(|id| id.to_string());
+    let n = revoke_user_sessions(&state, &user.id, current.as_deref()).await?;
+    info!("user {:?} revoked {n} other session(s)", user.display_name);
+    Ok(htmx_refresh())
+}
@@ -9,9 +9,12 @@ use sqlx::types::chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 
 use crate::{
     db::dao::request_log::{
-        Audience, AudienceCounts, DayCount, IpPathStatus, NoisyIp, PathCount, RequestLogDao,
-        StatusBucketCounts, UserAgentCount, Window, SCAN_DISTINCT_404_THRESHOLD,
+        Audience, AudienceCounts, CampaignCount, DayCount, IpPathStatus, NoisyIp, PathCount,
+        RateLimitedCounts,
+        RefererCount, RequestLogDao, StatusBucketCounts, UserAgentCount, Window,
+        RAW_RETAIN_DAYS, SCAN_DISTINCT_404_THRESHOLD,
     },
+    db::dao::request_log_rollup::{RequestLogRollupDao, ROLLUP_RETAIN_DAYS},
     web::{
         app_error::AppError, app_state::AppState, authentication_state::AuthenticationState,
         features::top_bar::TopBar, html_template::HtmlTemplate,
@@ -24,6 +27,7 @@ use crate::{
 };
 
 use crate::db::dao::greylist::GreylistDao;
+use crate::db::dao::ip_geo::{AsnCount, CountryCount, IpGeoDao};
 
 #[derive(Deserialize)]
 pub struct AnalyticsQuery {
@@ -134,6 +138,144 @@ pub struct AnalyticsTemplate {
     pub challenged_ips: i64,
     pub cleared_ips: i64,
     pub solve_rate_pct: Option<i64>,
+    /// Rate-limiter refusals over the window (429s that are NOT tolls) and the paths
+    /// they hit most — the section stays hidden while this is zero.
+    pub rate_limited: RateLimitedCounts,
+    pub rate_limited_paths: Vec<PathCount>,
+    /// GeoIP enrichment is configured — shows the country / ASN panels, which are
+    /// audience-bucketed like Top pages.
+    pub geo_enabled: bool,
+    pub by_country: Vec<CountryCount>,
+    pub by_asn: Vec<AsnCount>,
+    /// Tagged arrivals (`utm_*` / `ref` links, `web::util::campaign`) per campaign,
+    /// audience-bucketed like Top pages.
+    pub campaigns: Vec<CampaignCount>,
+    /// The first day the raw log still holds, when the window reaches back before it: the
+    /// chart, headline counts, top pages, status mix, campaigns and referrers include the
+    /// daily rollups for the older days; the other panels only cover the raw days.
+    pub rollups_before: Option<String>,
+}
+
+/// The daily-rollup side of the dashboard: the rollup-aware reads over the part of the
+/// window before the raw log starts, added into their raw counterparts after the fan-out.
+#[derive(Default)]
+struct RolledUp {
+    total_requests: i64,
+    audience_counts: AudienceCounts,
+    total_by_day: Vec<DayCount>,
+    unique_by_day: Vec<DayCount>,
+    challenged_by_day: Vec<DayCount>,
+    by_path: Vec<PathCount>,
+    status_buckets: StatusBucketCounts,
+    campaigns: Vec<CampaignCount>,
+    referer_urls: Vec<RefererCount>,
+    direct_count: i64,
+    tolls_served: i64,
+}
+
+async fn rolled_up(
+    pool: &sqlx::SqlitePool,
+    w: &Window,
+    audience: Audience,
+    max_status: i64,
+) -> anyhow::Result<RolledUp> {
+    if w.is_empty() {
+        return Ok(RolledUp::default());
+    }
+    let (
+        total_requests,
+        audience_counts,
+        total_by_day,
+        unique_by_day,
+        challenged_by_day,
+        by_path,
+        status_buckets,
+        campaigns,
+        referer_urls,
+        direct_count,
+        tolls_served,
+    ) = tokio::try_join!(
+        RequestLogRollupDao::count_since(pool, w, audience),
+        RequestLogRollupDao::audience_counts(pool, w),
+        RequestLogRollupDao::count_by_day(pool, w, audience),
+        RequestLogRollupDao::distinct_ip_by_day(pool, w, audience),
+        RequestLogRollupDao::count_by_day(pool, w, Audience::Challenged),
+        RequestLogRollupDao::count_by_content_path(pool, w, audience, max_status, 25),
+        RequestLogRollupDao::count_by_status_bucket(pool, w, audience),
+        RequestLogRollupDao::count_by_campaign(pool, w, audience, 25),
+        RequestLogRollupDao::referer_urls_since(pool, w),
+        RequestLogRollupDao::direct_referer_count(pool, w),
+        RequestLogRollupDao::count_since(pool, w, Audience::Challenged),
+    )?;
+    Ok(RolledUp {
+        total_requests,
+        audience_counts,
+        total_by_day,
+        unique_by_day,
+        challenged_by_day,
+        by_path,
+        status_buckets,
+        campaigns,
+        referer_urls,
+        direct_count,
+        tolls_served,
+    })
+}
+
+/// Sum two top-path lists by path and re-rank. Each side is already cut to `limit`, so a
+/// path just outside both cuts can be missed — fine for a leaderboard.
+fn merge_paths(a: Vec<PathCount>, b: Vec<PathCount>, limit: usize) -> Vec<PathCount> {
+    let mut sums: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
+    for p in a.into_iter().chain(b) {
+        *sums.entry(p.path).or_default() += p.count;
+    }
+    let mut merged: Vec<PathCount> = sums
+        .into_iter()
+        .map(|(path, count)| PathCount { path, count })
+        .collect();
+    merged.sort_by(|x, y| y.count.cmp(&x.count).then_with(|| x.path.cmp(&y.path)));
+    merged.truncate(limit);
+    merged
+}
+
+/// `merge_paths` for the campaigns panel, keyed by the whole campaign.
+fn merge_campaigns(
+    a: Vec<CampaignCount>,
+    b: Vec<CampaignCount>,
+    limit: usize,
+) -> Vec<CampaignCount> {
+    let mut sums: std::collections::HashMap<_, i64> = std::collections::HashMap::new();
+    for c in a.into_iter().chain(b) {
+        *sums.entry((c.source, c.medium, c.name)).or_default() += c.count;
+    }
+    let mut merged: Vec<CampaignCount> = sums
+        .into_iter()
+        .map(|((source, medium, name), count)| CampaignCount {
+            source,
+            medium,
+            name,
+            count,
+        })
+        .collect();
+    merged.sort_by(|x, y| {
+        y.count
+            .cmp(&x.count)
+            .then_with(|| (&x.source, &x.medium, &x.name).cmp(&(&y.source, &y.medium, &y.name)))
+    });
+    merged.truncate(limit);
+    merged
+}
+
+fn add_status_buckets(a: StatusBucketCounts, b: &StatusBucketCounts) -> StatusBucketCounts {
+    StatusBucketCounts {
+        s2xx: a.s2xx + b.s2xx,
+        s3xx: a.s3xx + b.s3xx,
+        s403: a.s403 + b.s403,
+        s404: a.s404 + b.s404,
+        s429: a.s429 + b.s429,
+        s4xx: a.s4xx + b.s4xx,
+        s5xx: a.s5xx + b.s5xx,
+    }
 }
 
 /// `GET /admin/analytics` — gated by the `require_admin` layer on the `admin`
@@ -144,7 +286,7 @@ pub async fn show_analytics(
     session_data: SessionData,
     Query(q): Query<AnalyticsQuery>,
 ) -> Result<Response, AppError> {
-    let since_days = q.since.unwrap_or(30).clamp(1, 365);
+    let since_days = q.since.unwrap_or(30).clamp(1, ROLLUP_RETAIN_DAYS);
     // Top-pages filter: "content" (successful loads only) hides the 404 scanner
     // probes; "all" surfaces them (status ceiling raised) so chris can see what's
     // attacking the site. Static assets are excluded either way.
@@ -196,6 +338,18 @@ pub async fn show_analytics(
         format!("since={since_days}")
     };
 
+    // GeoIP (if configured): cache a lookup for every IP in the window BEFORE the fan-out, so
+    // the country / ASN panels' join counts them all. A failure only leaves those partial.
+    if let Err(e) = state.geoip.enrich(&state.pool, &window).await {
+        tracing::warn!("analytics: GeoIP enrichment failed: {e:?}");
+    }
+
+    // Tiered retention: days before the raw log's first day come from the daily rollups
+    // (`request_log_rollup`). The rollup-aware reads take the raw half of the window and
+    // get the rollup half added in below; the rest only have raw rows to read anyway.
+    let raw_start = RequestLogRollupDao::raw_start(&state.pool).await?;
+    let (older_window, raw_window) = window.split_at(&raw_start);
+
     // Run every independent read CONCURRENTLY (CR.3): WAL + the connection pool (≤10)
     // let these fan out across connections, so the page's wall-clock is ~the slowest
     // query instead of the SUM of ~15 windowed scans (the ~7s → sub-1s win). TopBar
@@ -222,33 +376,64 @@ pub async fn show_analytics(
         challenged_ips,
         clearances,
         cleared_ips,
+        rate_limited,
+        rate_limited_paths,
+        by_country,
+        by_asn,
+        campaigns,
+        older,
     ) = tokio::try_join!(
         TopBar::create(&state.pool, "admin", session_data.auth_state.role()),
-        RequestLogDao::count_since(&state.pool, &window, audience),
+        RequestLogDao::count_since(&state.pool, &raw_window, audience),
         RequestLogDao::distinct_ip_count(&state.pool, &window, audience),
-        RequestLogDao::audience_counts(&state.pool, &window),
-        RequestLogDao::count_by_day(&state.pool, &window, audience),
-        RequestLogDao::distinct_ip_by_day(&state.pool, &window, audience),
+        RequestLogDao::audience_counts(&state.pool, &raw_window),
+        RequestLogDao::count_by_day(&state.pool, &raw_window, audience),
+        RequestLogDao::distinct_ip_by_day(&state.pool, &raw_window, audience),
         // Tolls/day overlay (CY.2) — always challenged=1, INDEPENDENT of the audience filter.
-        RequestLogDao::count_by_day(&state.pool, &window, Audience::Challenged),
-        RequestLogDao::count_by_content_path(&state.pool, &window, audience, max_status, 25),
-        RequestLogDao::count_by_status_bucket(&state.pool, &window, audience),
+        RequestLogDao::count_by_day(&state.pool, &raw_window, Audience::Challenged),
+        RequestLogDao::count_by_content_path(&state.pool, &raw_window, audience, max_status, 25),
+        RequestLogDao::count_by_status_bucket(&state.pool, &raw_window, audience),
         RequestLogDao::noisy_ips(&state.pool, &window, 0, 25),
         RequestLogDao::never_succeeded_paths(&state.pool, &window, 25),
         RequestLogDao::count_by_user_agent(&state.pool, &window, 25),
-        RequestLogDao::referer_urls_since(&state.pool, &window),
-        RequestLogDao::direct_referer_count(&state.pool, &window),
+        RequestLogDao::referer_urls_since(&state.pool, &raw_window),
+        RequestLogDao::direct_referer_count(&state.pool, &raw_window),
         RequestLogDao::latency_samples(&state.pool, &window),
         RequestLogDao::slowest_requests(&state.pool, &window, 15),
         RequestLogDao::recent(&state.pool, 50),
         // Greylist toll activity (CY.2/CY.8) — window-scoped, INDEPENDENT of the selected
         // audience (like `audience_counts`, these are always-shown sub-metrics).
-        RequestLogDao::count_since(&state.pool, &window, Audience::Challenged),
+        RequestLogDao::count_since(&state.pool, &raw_window, Audience::Challenged),
         RequestLogDao::distinct_challenged_ips(&state.pool, &window),
         GreylistDao::count_clearances_since(&state.pool, &window),
         GreylistDao::distinct_cleared_ips_since(&state.pool, &window),
+        // Rate-limiter refusals — window-scoped, audience-independent like the tolls.
+        RequestLogDao::rate_limited_counts(&state.pool, &window),
+        RequestLogDao::rate_limited_paths(&state.pool, &window, 10),
+        IpGeoDao::traffic_by_country(&state.pool, &window, audience, 20),
+        IpGeoDao::top_asns(&state.pool, &window, audience, 20),
+        RequestLogDao::count_by_campaign(&state.pool, &raw_window, audience, 25),
+        rolled_up(&state.pool, &older_window, audience, max_status),
     )?;
 
+    // Fold the rollup half in. Its days all precede the raw ones, so the daily series
+    // just concatenate.
+    let total_requests = total_requests + older.total_requests;
+    let tolls_served = tolls_served + older.tolls_served;
+    let audience_counts = AudienceCounts {
+        all: audience_counts.all + older.audience_counts.all,
+        humans: audience_counts.humans + older.audience_counts.humans,
+        bots: audience_counts.bots + older.audience_counts.bots,
+    };
+    let total_by_day = [older.total_by_day, total_by_day].concat();
+    let unique_by_day = [older.unique_by_day, unique_by_day].concat();
+    let challenged_by_day = [older.challenged_by_day, challenged_by_day].concat();
+    let by_path = merge_paths(by_path, older.by_path, 25);
+    let status_buckets = add_status_buckets(status_buckets, &older.status_buckets);
+    let campaigns = merge_campaigns(campaigns, older.campaigns, 25);
+    let referer_urls = [older.referer_urls, referer_urls].concat();
+    let direct_count = direct_count + older.direct_count;
+
     // Derived, Rust-side (cheap): the chart island (both daily series overlaid — the gap
     // is the repeat/scanner signal, `<`-escaped for the XSS boundary), the referer fold,
     // and the latency percentiles (SQLite has no percentile fn).
@@ -298,6 +483,13 @@ pub async fn show_analytics(
         challenged_ips,
         cleared_ips,
         solve_rate_pct,
+        rate_limited,
+        rate_limited_paths,
+        geo_enabled: state.geoip.is_enabled(),
+        by_country,
+        by_asn,
+        campaigns,
+        rollups_before: (!older_window.is_empty()).then_some(raw_start),
     };
     Ok(HtmlTemplate(tmpl).into_response())
 }
@@ -323,6 +515,9 @@ pub struct IpDetailTemplate {
     pub path_status: Vec<IpPathStatus>,
     pub user_agents: Vec<UserAgentCount>,
     pub recent: Vec<RequestLogDao>,
+    /// Country and network from the GeoIP databases (`None` without them, or if they know
+    /// nothing about this IP).
+    pub geo: Option<String>,
 }
 
 /// `GET /admin/analytics/ip/{ip}` — per-IP drill-down (CQ.4), gated by the `/admin`
@@ -338,13 +533,20 @@ pub async fn show_ip_detail(
     if ip.parse::<std::net::IpAddr>().is_err() {
         return Ok((StatusCode::BAD_REQUEST, "Not a valid IP address").into_response());
     }
-    // The retention window — show everything we still have for this IP.
-    let since_days = 90;
+    // The raw retention window — show everything we still have for this IP.
+    let since_days = RAW_RETAIN_DAYS;
     let window = Window::last_days(since_days);
 
     let path_status = RequestLogDao::ip_path_status(&state.pool, &ip, &window).await?;
     let user_agents = RequestLogDao::ip_user_agents(&state.pool, &ip, &window).await?;
     let recent = RequestLogDao::ip_recent(&state.pool, &ip, 100).await?;
+    let geo = state
+        .geoip
+        .describe(&state.pool, std::slice::from_ref(&ip))
+        .await?
+        .remove(&ip)
+        .map(|g| g.label())
+        .filter(|label| !label.is_empty());
 
     // Derive the header, status mix, and 404 wordlist from the one query — no extra
     // round-trips.
@@ -399,6 +601,7 @@ pub async fn show_ip_detail(
         path_status,
         user_agents,
         recent,
+        geo,
     };
     Ok(HtmlTemplate(tmpl).into_response())
 }
@@ -6,13 +6,20 @@ use axum::{
 };
 use http::StatusCode;
 use serde::Deserialize;
+use serde_json::json;
+use sqlx::types::chrono::{DateTime, Utc};
 
-use crate::db::dao::api_keys::ApiKeyDao;
+use crate::db::dao::api_keys::{ApiKeyDao, ROTATION_OVERLAP_HOURS};
 use crate::web::features::top_bar::TopBar;
 use crate::web::htmx_responses::htmx_refresh;
+use crate::web::util::deserialize::empty_string_as_none;
 use crate::web::{
-    app_error::AppError, app_state::AppState, authentication_state::AuthenticationState,
-    html_template::HtmlTemplate, session::SessionData,
+    app_error::AppError,
+    app_state::AppState,
+    audit::{self, Actor},
+    authentication_state::AuthenticationState,
+    html_template::HtmlTemplate,
+    session::SessionData,
 };
 
 #[derive(Template)]
@@ -33,11 +40,42 @@ pub struct ApiKeyView {
     pub created: String,
     pub last_used: String,
     pub revoked: Option<String>,
+    /// `Some` while the key has an expiry still ahead of it.
+    pub expires: Option<String>,
+    /// Creation date of the key this one replaced, while that key is still listed.
+    pub rotated_from: Option<String>,
+    /// The newest usage rows for this key (capped by [`USAGE_ROWS_PER_KEY`]).
+    pub usage: Vec<UsageView>,
 }
 
+/// One row of a key's request trail.
+pub struct UsageView {
+    pub when: String,
+    pub method: String,
+    pub path: String,
+    pub status: i64,
+    pub ip: String,
+}
+
+/// How many recent requests each key's trail shows on the page.
+const USAGE_ROWS_PER_KEY: i64 = 20;
+
+/// Longest expiry the mint form accepts — anything longer should just not expire.
+const MAX_EXPIRY_DAYS: i64 = 3_650;
+
 #[derive(Deserialize)]
 pub struct CreateKeyForm {
     pub label: String,
+    /// Days until the key expires; blank = never.
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub expires_in_days: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct RotateKeyForm {
+    /// Hours the old key keeps working beside its successor; blank = the default.
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub overlap_hours: Option<String>,
 }
 
 pub async fn show_api_keys(
@@ -49,6 +87,7 @@ pub async fn show_api_keys(
 
 pub async fn create_api_key(
     State(state): State<AppState>,
+    actor: Actor,
     session_data: SessionData,
     Form(form): Form<CreateKeyForm>,
 ) -> Result<Response, AppError> {
@@ -59,13 +98,59 @@ pub async fn create_api_key(
     if label.is_empty() {
         return Ok((StatusCode::BAD_REQUEST, "A label is required").into_response());
     }
-    let (key, _) = ApiKeyDao::create(&state.pool, &user_id, label).await?;
+    let expires_at = match form.expires_in_days.as_deref().map(str::parse::<i64>) {
+        None => None,
+        Some(Ok(days)) if (1..=MAX_EXPIRY_DAYS).contains(&days) => {
+            DateTime::<Utc>::from_timestamp(Utc::now().timestamp() + days * 86_400, 0)
+        }
+        Some(_) => {
+            return Ok((StatusCode::BAD_REQUEST, "Expiry must be a whole number of days")
+                .into_response());
+        }
+    };
+    let (key, row) =
+        ApiKeyDao::create_with_expiry(&state.pool, &user_id, label, expires_at).await?;
+    let expires = expires_at.map(|t| t.to_rfc3339());
+    let after = json!({ "label": label, "expires_at": expires });
+    let target = row.id.to_string();
+    audit::record(&state.pool, &actor, "api_key.create", &target, None, Some(after)).await;
     // Re-render the page carrying the plaintext — the ONE time it's shown.
     render_page(&state, session_data, Some(key)).await
 }
 
+/// Issue a successor for one of your keys; the old one keeps working for the
+/// overlap window, then expires (and the hourly sweep revokes it).
+pub async fn rotate_api_key(
+    State(state): State<AppState>,
+    actor: Actor,
+    session_data: SessionData,
+    Path(id): Path<i64>,
+    Form(form): Form<RotateKeyForm>,
+) -> Result<Response, AppError> {
+    let Some(user_id) = session_data.auth_state.user().map(|u| u.id) else {
+        return Ok((StatusCode::FORBIDDEN, "Not authenticated").into_response());
+    };
+    let overlap_hours = match form.overlap_hours.as_deref().map(str::parse::<i64>) {
+        None => ROTATION_OVERLAP_HOURS,
+        Some(Ok(h)) if h >= 0 => h,
+        Some(_) => {
+            return Ok((StatusCode::BAD_REQUEST, "Overlap must be a whole number of hours")
+                .into_response());
+        }
+    };
+    // Scoped to the user inside the DAO, like revoke.
+    let Some((key, successor)) = ApiKeyDao::rotate(&state.pool, id, &user_id, overlap_hours).await?
+    else {
+        return Ok((StatusCode::NOT_FOUND, "No live key to rotate").into_response());
+    };
+    let after = json!({ "successor": successor.id, "overlap_hours": overlap_hours });
+    audit::record(&state.pool, &actor, "api_key.rotate", &id.to_string(), None, Some(after)).await;
+    render_page(&state, session_data, Some(key)).await
+}
+
 pub async fn revoke_api_key(
     State(state): State<AppState>,
+    actor: Actor,
     session_data: SessionData,
     Path(id): Path<i64>,
 ) -> Result<Response, AppError> {
@@ -73,7 +158,9 @@ pub async fn revoke_api_key(
         return Ok((StatusCode::FORBIDDEN, "Not authenticated").into_response());
     };
     // Scoped to the user inside the DAO, so you can only revoke your own.
-    ApiKeyDao::revoke(&state.pool, id, &user_id).await?;
+    if ApiKeyDao::revoke(&state.pool, id, &user_id).await? {
+        audit::record(&state.pool, &actor, "api_key.revoke", &id.to_string(), None, None).await;
+    }
     Ok(htmx_refresh())
 }
 
@@ -85,18 +172,43 @@ async fn render_page(
     let Some(user_id) = session_data.auth_state.user().map(|u| u.id) else {
         return Ok((StatusCode::FORBIDDEN, "Not authenticated").into_response());
     };
-    let keys = ApiKeyDao::list_for_user(&state.pool, &user_id)
-        .await?
+    let rows = ApiKeyDao::list_for_user(&state.pool, &user_id).await?;
+    let mut usage = ApiKeyDao::recent_usage_for_user(&state.pool, &user_id, USAGE_ROWS_PER_KEY)
+        .await?;
+    let created: std::collections::HashMap<i64, String> = rows
+        .iter()
+        .map(|k| (k.id, k.created_at.format("%Y-%m-%d").to_string()))
+        .collect();
+    let now = Utc::now();
+    let keys = rows
         .into_iter()
-        .map(|k| ApiKeyView {
-            id: k.id,
-            label: k.label,
-            created: k.created_at.format("%Y-%m-%d").to_string(),
-            last_used: k
-                .last_used_at
-                .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
-                .unwrap_or_else(|| "never".to_string()),
-            revoked: k.revoked_at.map(|t| t.format("%Y-%m-%d").to_string()),
+        .map(|k| {
+            let key_usage = usage
+                .extract_if(.., |u| u.key_id == k.id)
+                .map(|u| UsageView {
+                    when: u.ts.format("%Y-%m-%d %H:%M UTC").to_string(),
+                    method: u.method,
+                    path: u.path,
+                    status: u.status,
+                    ip: u.ip.unwrap_or_else(|| "—".to_string()),
+                })
+                .collect();
+            ApiKeyView {
+                id: k.id,
+                label: k.label,
+                created: k.created_at.format("%Y-%m-%d").to_string(),
+                last_used: k
+                    .last_used_at
+                    .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+                    .unwrap_or_else(|| "never".to_string()),
+                revoked: k.revoked_at.map(|t| t.format("%Y-%m-%d").to_string()),
+                expires: k
+                    .expires_at
+                    .filter(|t| k.revoked_at.is_none() && *t > now)
+                    .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string()),
+                rotated_from: k.rotated_from.and_then(|id| created.get(&id).cloned()),
+                usage: key_usage,
+            }
         })
         .collect();
 
@@ -0,0 +1,104 @@
+//! `/admin/audit` — the audit trail of privileged mutations, newest first, filterable
+//! by action prefix, actor, auth channel and target. Read-only; the rows are written
+//! where the mutations happen (see `web::audit`).
+
+use askama::Template;
+use axum::{
+    extract::{Query, State},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+use crate::db::dao::audit_log::{AuditFilter, AuditLogDao};
+use crate::web::util::deserialize::empty_string_as_none;
+use crate::web::{
+    app_error::AppError, app_state::AppState, authentication_state::AuthenticationState,
+    features::top_bar::TopBar, html_template::HtmlTemplate, session::SessionData,
+};
+
+/// Rows one page view shows.
+const PAGE_ROWS: i64 = 200;
+
+/// The channel filter's options, as stored.
+const CHANNELS: [&str; 4] = ["session", "api_key", "mcp", "cli"];
+
+#[derive(Deserialize)]
+pub struct AuditQuery {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub action: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub actor: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub channel: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub target: Option<String>,
+}
+
+/// One rendered audit row.
+pub struct AuditRow {
+    pub when: String,
+    pub actor: String,
+    /// `session`, or `api_key #7` / `mcp #7` with the key id.
+    pub channel: String,
+    pub action: String,
+    pub target: String,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Template)]
+#[template(path = "admin/audit.html")]
+pub struct AuditTemplate {
+    pub top_bar: TopBar,
+    pub auth_state: AuthenticationState,
+    pub rows: Vec<AuditRow>,
+    pub actors: Vec<String>,
+    pub channels: Vec<String>,
+    /// The active filters, echoed back into the form ("" = unset).
+    pub action: String,
+    pub actor: String,
+    pub channel: String,
+    pub target: String,
+}
+
+pub async fn show_audit(
+    State(state): State<AppState>,
+    session_data: SessionData,
+    Query(q): Query<AuditQuery>,
+) -> Result<Response, AppError> {
+    let filter = AuditFilter {
+        action: q.action.map(|s| s.trim().to_string()),
+        actor: q.actor,
+        channel: q.channel,
+        target: q.target.map(|s| s.trim().to_string()),
+    };
+    let rows = AuditLogDao::list(&state.pool, &filter, PAGE_ROWS)
+        .await?
+        .into_iter()
+        .map(|r| AuditRow {
+            when: r.ts.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+            actor: r.actor_name.unwrap_or_else(|| "—".to_string()),
+            channel: match r.api_key_id {
+                Some(id) => format!("{} #{id}", r.channel),
+                None => r.channel,
+            },
+            action: r.action,
+            target: r.target,
+            before: r.before.unwrap_or_default(),
+            after: r.after.unwrap_or_default(),
+        })
+        .collect();
+
+    let tmpl = AuditTemplate {
+        top_bar: TopBar::create(&state.pool, "admin", session_data.auth_state.role()).await?,
+        auth_state: session_data.auth_state,
+        rows,
+        actors: AuditLogDao::actors(&state.pool).await?,
+        channels: CHANNELS.iter().map(|c| c.to_string()).collect(),
+        action: filter.action.unwrap_or_default(),
+        actor: filter.actor.unwrap_or_default(),
+        channel: filter.channel.unwrap_or_default(),
+        target: filter.target.unwrap_or_default(),
+    };
+    Ok(HtmlTemplate(tmpl).into_response())
+}
@@ -12,6 +12,7 @@ use crate::{
     web::{
         app_error::AppError,
         app_state::AppState,
+        audit::Actor,
         authentication_state::AuthenticationState,
         features::pages::write::{self, PageUpdate, PageWriteError},
         features::top_bar::TopBar,
@@ -100,6 +101,7 @@ pub struct CaptureForm {
 
 pub async fn capture_post(
     State(state): State<AppState>,
+    actor: Actor,
     client: ClientKind,
     Form(form): Form<CaptureForm>,
 ) -> Result<Response, AppError> {
@@ -122,7 +124,7 @@ pub async fn capture_post(
             // Second-precision title so rapid-fire captures can't slug-collide
             // (and after the first shot the client auto-switches to append).
             let title = format!("Capture {}", Utc::now().format("%Y-%m-%d %H:%M:%S"));
-            let w = match write::create_page(&state.pool, &["blog"], &title).await {
+            let w = match write::create_page(&state.pool, &actor, &["blog"], &title).await {
                 Ok(w) => w,
                 Err(PageWriteError::DuplicateSlug { slug, .. }) => {
                     return Ok((
@@ -143,6 +145,7 @@ pub async fn capture_post(
             };
             write::update_page(
                 &state.pool,
+                &actor,
                 &state.site_host,
                 &["blog", &w.slug],
                 PageUpdate {
@@ -187,6 +190,7 @@ pub async fn capture_post(
             markdown.push('\n');
             write::update_page(
                 &state.pool,
+                &actor,
                 &state.site_host,
                 &["blog", target],
                 PageUpdate {
@@ -0,0 +1,89 @@
+//! Content-Security-Policy violations (see `web::middleware::csp`), grouped by
+//! directive and blocked resource. During the report-only rollout this is the
+//! checklist: once nothing legitimate shows up here, flip `csp_report_only` off
+//! and the policy enforces. "Clear" empties the table after a fix so the next
+//! round of reports starts clean.
+
+use askama::Template;
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+
+use crate::db::dao::csp_reports::CspReportDao;
+use crate::web::{
+    app_error::AppError,
+    app_state::AppState,
+    audit::{self, Actor},
+    authentication_state::AuthenticationState,
+    features::top_bar::TopBar,
+    html_template::HtmlTemplate,
+    htmx_responses::htmx_refresh,
+    session::SessionData,
+};
+
+const TS_FMT: &str = "%Y-%m-%d %H:%M";
+
+#[derive(Template)]
+#[template(path = "admin/csp_reports.html")]
+pub struct CspReportsTemplate {
+    pub top_bar: TopBar,
+    pub auth_state: AuthenticationState,
+    pub report_only: bool,
+    pub groups: Vec<ReportGroupView>,
+}
+
+pub struct ReportGroupView {
+    pub directive: String,
+    pub blocked: String,
+    pub count: i64,
+    pub last_seen: String,
+    pub document: String,
+    pub disposition: String,
+    pub sample: Option<String>,
+}
+
+pub async fn show_csp_reports(
+    State(state): State<AppState>,
+    session_data: SessionData,
+) -> Result<Response, AppError> {
+    let groups = CspReportDao::summary(&state.pool)
+        .await?
+        .into_iter()
+        .map(|g| ReportGroupView {
+            last_seen: g.last_seen.format(TS_FMT).to_string(),
+            directive: g.effective_directive,
+            blocked: g.blocked_uri,
+            count: g.count,
+            document: g.document_uri,
+            disposition: g.disposition,
+            sample: g.sample,
+        })
+        .collect();
+    let template = CspReportsTemplate {
+        top_bar: TopBar::create(&state.pool, "admin", session_data.auth_state.role()).await?,
+        auth_state: session_data.auth_state,
+        report_only: state.csp_report_only,
+        groups,
+    };
+    Ok(HtmlTemplate(template).into_response())
+}
+
+/// `POST /admin/csp-reports/clear` — drop every stored report.
+pub async fn clear_csp_reports(
+    State(state): State<AppState>,
+    actor: Actor,
+) -> Result<Response, AppError> {
+    let cleared = CspReportDao::clear(&state.pool).await?;
+    audit::record(
+        &state.pool,
+        &actor,
+        "csp_reports.clear",
+        "csp_reports",
+        Some(json!({ "reports": cleared })),
+        None,
+    )
+    .await;
+    Ok(htmx_refresh())
+}
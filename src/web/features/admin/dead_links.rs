@@ -15,7 +15,7 @@ use serde::Deserialize;
 use sqlx::types::chrono::Utc;
 
 use crate::db::dao::content_pages::ContentPageDao;
-use crate::deadlinks::{LinkCheckDao, LinkCheckRow, LinkRefDao, ReqwestChecker};
+use crate::deadlinks::{LinkCheckDao, LinkCheckRow, LinkRefDao};
 use crate::web::{
     app_error::AppError, app_state::AppState, authentication_state::AuthenticationState,
     features::top_bar::TopBar, html_template::HtmlTemplate, htmx_responses::htmx_refresh,
@@ -28,6 +28,7 @@ const TS_FMT: &str = "%Y-%m-%d %H:%M";
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum DeadLinkBucket {
     Confirmed,
+    BrokenAnchor,
     Failing,
     Review,
 }
@@ -36,6 +37,8 @@ impl DeadLinkBucket {
     fn from_row(r: &LinkCheckRow) -> Self {
         if r.is_confirmed_dead() {
             DeadLinkBucket::Confirmed
+        } else if r.is_confirmed_broken_anchor() {
+            DeadLinkBucket::BrokenAnchor
         } else if r.is_failing() {
             DeadLinkBucket::Failing
         } else {
@@ -46,6 +49,7 @@ impl DeadLinkBucket {
     pub fn label(self) -> &'static str {
         match self {
             DeadLinkBucket::Confirmed => "Confirmed dead",
+            DeadLinkBucket::BrokenAnchor => "Broken anchor",
             DeadLinkBucket::Failing => "Failing",
             DeadLinkBucket::Review => "Review",
         }
@@ -54,7 +58,8 @@ impl DeadLinkBucket {
     /// Severity for sorting (worst first).
     pub fn rank(self) -> u8 {
         match self {
-            DeadLinkBucket::Confirmed => 3,
+            DeadLinkBucket::Confirmed => 4,
+            DeadLinkBucket::BrokenAnchor => 3,
             DeadLinkBucket::Failing => 2,
             DeadLinkBucket::Review => 1,
         }
@@ -64,6 +69,7 @@ impl DeadLinkBucket {
     pub fn badge_class(self) -> &'static str {
         match self {
             DeadLinkBucket::Confirmed => "bg-red-700 text-white",
+            DeadLinkBucket::BrokenAnchor => "bg-navy text-yellow",
             DeadLinkBucket::Failing => "bg-yellow text-navy",
             DeadLinkBucket::Review => "bg-navy/20 text-navy",
         }
@@ -95,6 +101,9 @@ pub struct DeadLinksTemplate {
     pub running: bool,
     pub last_checked: String,
     pub confirmed: usize,
+    /// Links whose page is there but whose `#fragment` has been missing for several
+    /// passes (DL.10) — counted apart from the dead ones.
+    pub broken_anchors: usize,
     pub failing: usize,
     pub review: usize,
     pub total_tracked: i64,
@@ -139,6 +148,10 @@ pub async fn show_dead_links(
     });
 
     let confirmed = rows.iter().filter(|r| r.is_confirmed_dead()).count();
+    let broken_anchors = rows
+        .iter()
+        .filter(|r| r.is_confirmed_broken_anchor())
+        .count();
     let failing = rows.iter().filter(|r| r.is_failing()).count();
     let review = rows.iter().filter(|r| r.needs_review()).count();
     let ignored: Vec<DeadLinkItem> = LinkCheckDao::ignored_rows(&state.pool)
@@ -158,6 +171,7 @@ pub async fn show_dead_links(
         running: state.dead_links.status().running,
         last_checked,
         confirmed,
+        broken_anchors,
         failing,
         review,
         total_tracked,
@@ -193,7 +207,7 @@ pub async fn recheck(
     State(state): State<AppState>,
     Form(form): Form<RecheckForm>,
 ) -> Result<Response, AppError> {
-    let checker = ReqwestChecker::new()?;
+    let checker = state.dead_links.checker()?;
     crate::deadlinks::recheck_one(
         &state.pool,
         &checker,
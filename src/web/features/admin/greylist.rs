@@ -1,7 +1,18 @@
-//! Greylist management (Phase CX.7): view active entries + recent clearances, manually pin an IP,
-//! or release one. Admin-gated by the `/admin` nest's `require_admin`. Pin/release update the
-//! in-memory snapshot IMMEDIATELY (not just the DB), so the toll starts/stops without waiting for
-//! the next sweep refresh.
+//! Greylist management (Phase CX.7): view active entries + recent clearances, manually pin an IP
+//! or a CIDR range, or release one. Admin-gated by the `/admin` nest's `require_admin`.
+//! Pin/release update the in-memory snapshot IMMEDIATELY (not just the DB), so the toll
+//! starts/stops without waiting for the next sweep refresh. A range entry lists the individually
+//! greylisted IPs inside it, next to the R4 evidence that put it there.
+//!
+//! The page also reports the fitted model the sweep shadows (`greylist::model`): its holdout
+//! metrics and every IP it currently disagrees with the rules on — the evidence for deciding
+//! whether it's ever allowed to greylist by itself.
+//!
+//! Clear-then-scan escalations (`greylist::escalation`) show as `blocked` rows with a
+//! de-escalate action that sends the IP back to the toll and un-revokes its clearances.
+//!
+//! Entries imported from reputation lists (`greylist::reputation`) can run to thousands of
+//! ranges, so they're summarized per list (size, last load, last error) rather than listed.
 
 use std::net::IpAddr;
 
@@ -13,12 +24,22 @@ use axum::{
 };
 use http::StatusCode;
 use serde::Deserialize;
+use serde_json::{Value, json};
 
-use crate::db::dao::greylist::{CandidatePath, GreylistDao};
+use crate::db::dao::greylist::{CandidatePath, GreylistDao, GreylistEntry, GreylistTier};
+use crate::db::dao::greylist_model::{GreylistModelDao, ShadowDisagreement};
+use crate::db::dao::reputation_list::ReputationListDao;
 use crate::greylist::detection::is_signature_path;
+use crate::greylist::model::{INPUT_NAMES, Metrics, Model};
+use crate::greylist::prefix::IpPrefix;
 use crate::web::{
-    app_error::AppError, app_state::AppState, authentication_state::AuthenticationState,
-    features::top_bar::TopBar, html_template::HtmlTemplate, htmx_responses::htmx_refresh,
+    app_error::AppError,
+    app_state::AppState,
+    audit::{self, Actor},
+    authentication_state::AuthenticationState,
+    features::top_bar::TopBar,
+    html_template::HtmlTemplate,
+    htmx_responses::htmx_refresh,
     session::SessionData,
 };
 
@@ -32,6 +53,17 @@ pub struct GreylistRow {
     pub updated_at: String,
     /// Formatted expiry, or "never (pinned)" for a manual pin.
     pub expires_at: String,
+    /// `ip` as a single path segment (a CIDR's `/` percent-encoded) for the release URL.
+    pub release_path: String,
+    /// For a range entry: the active single-IP entries inside it. Empty for an IP entry.
+    pub members: Vec<String>,
+    pub is_prefix: bool,
+    /// Escalated to the `Blocked` tier (a bare 403, no toll).
+    pub blocked: bool,
+    /// When it was escalated, or empty.
+    pub escalated_at: String,
+    /// Country and network from GeoIP (a range by its first address), or empty.
+    pub geo: String,
 }
 
 pub struct ClearanceRow {
@@ -39,6 +71,61 @@ pub struct ClearanceRow {
     pub cleared_at: String,
     pub solve: String,
     pub user_agent: String,
+    /// Country and network from GeoIP, or empty.
+    pub geo: String,
+}
+
+pub struct ReputationRow {
+    pub name: String,
+    pub path: String,
+    pub entries: i64,
+    pub skipped: i64,
+    /// Formatted time of the last good load, or "never".
+    pub loaded_at: String,
+    pub error: Option<String>,
+}
+
+/// The model the sweep shadows, as the page reports it.
+pub struct ShadowModelView {
+    pub version: i64,
+    pub trained_at: String,
+    pub samples: i64,
+    /// Holdout metrics as a sentence; `None` if they don't parse (written by a newer build).
+    pub metrics: Option<String>,
+    /// Each input's weight (`ln(total) +0.42`), then the bias — what the model leans on.
+    pub weights: Vec<String>,
+    /// Why the sweep can't shadow it (an unknown kind), if it can't.
+    pub load_error: Option<String>,
+}
+
+pub struct ShadowRow {
+    pub ip: String,
+    /// The rule that greylisted it, or "clear".
+    pub rules: String,
+    /// "greylist" or "clear", with the model's probability.
+    pub model: String,
+    pub evidence: String,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+impl ShadowRow {
+    fn new(d: ShadowDisagreement) -> Self {
+        // A disagreement means the model said the opposite of the rules.
+        let model_verdict = if d.rule_reason.is_some() {
+            "clear"
+        } else {
+            "greylist"
+        };
+        Self {
+            ip: d.ip,
+            rules: d.rule_reason.unwrap_or_else(|| "clear".to_string()),
+            model: format!("{model_verdict} (p = {:.2})", d.model_score),
+            evidence: d.evidence,
+            first_seen: d.first_seen.format(TS_FMT).to_string(),
+            last_seen: d.last_seen.format(TS_FMT).to_string(),
+        }
+    }
 }
 
 #[derive(Template)]
@@ -57,32 +144,71 @@ pub struct GreylistTemplate {
     /// visible WHY the operator's home network is never greylisted. Empty until the IP broadcast
     /// lands (or in debug, `127.0.0.1`).
     pub allowlisted: Vec<String>,
+    /// Active entries from reputation lists — counted here, not listed in `entries`.
+    pub reputation_listed: usize,
+    /// Each configured reputation list's load status.
+    pub reputation_lists: Vec<ReputationRow>,
+    /// The latest fitted model, if one has been trained.
+    pub shadow_model: Option<ShadowModelView>,
+    /// Where that model and the rules currently disagree, most recent first.
+    pub disagreements: Vec<ShadowRow>,
 }
 
 pub async fn show_greylist(
     State(state): State<AppState>,
     session: SessionData,
 ) -> Result<Response, AppError> {
-    let entries = GreylistDao::active(&state.pool)
+    let (listed, active): (Vec<_>, Vec<_>) = GreylistDao::active(&state.pool)
         .await?
         .into_iter()
-        .map(|e| GreylistRow {
-            ip: e.ip,
-            reason: e.reason,
-            evidence: e.evidence.unwrap_or_default(),
-            manual: e.manual,
-            updated_at: e.updated_at.format(TS_FMT).to_string(),
-            expires_at: e
-                .expires_at
-                .map(|x| x.format(TS_FMT).to_string())
-                .unwrap_or_else(|| "never (pinned)".to_string()),
+        .partition(|e| e.reason.starts_with("reputation:"));
+    let ips: Vec<IpAddr> = active.iter().filter_map(|e| e.ip.parse().ok()).collect();
+    let recent_clearances = GreylistDao::recent_clearances(&state.pool, 50).await?;
+    // Country / network for every listed IP and range (nothing without GeoIP configured).
+    let geo_keys: Vec<String> = active
+        .iter()
+        .map(|e| e.ip.clone())
+        .chain(recent_clearances.iter().map(|c| c.ip.clone()))
+        .collect();
+    let geo = state.geoip.describe(&state.pool, &geo_keys).await?;
+    let geo_label = |ip: &str| geo.get(ip).map(|g| g.label()).unwrap_or_default();
+    let entries = active
+        .into_iter()
+        .map(|e| {
+            let prefix = IpPrefix::parse(&e.ip);
+            let members = prefix
+                .map(|p| {
+                    let inside = ips.iter().filter(|ip| p.contains(**ip));
+                    inside.map(ToString::to_string).collect()
+                })
+                .unwrap_or_default();
+            GreylistRow {
+                release_path: e.ip.replace('/', "%2F"),
+                geo: geo_label(&e.ip),
+                members,
+                is_prefix: prefix.is_some(),
+                blocked: e.tier == GreylistTier::Blocked,
+                escalated_at: e
+                    .escalated_at
+                    .map(|x| x.format(TS_FMT).to_string())
+                    .unwrap_or_default(),
+                ip: e.ip,
+                reason: e.reason,
+                evidence: e.evidence.unwrap_or_default(),
+                manual: e.manual,
+                updated_at: e.updated_at.format(TS_FMT).to_string(),
+                expires_at: e
+                    .expires_at
+                    .map(|x| x.format(TS_FMT).to_string())
+                    .unwrap_or_else(|| "never (pinned)".to_string()),
+            }
         })
         .collect();
 
-    let clearances = GreylistDao::recent_clearances(&state.pool, 50)
-        .await?
+    let clearances = recent_clearances
         .into_iter()
         .map(|c| ClearanceRow {
+            geo: geo_label(&c.ip),
             ip: c.ip,
             cleared_at: c.cleared_at.format(TS_FMT).to_string(),
             solve: c.solve_ms.map(|ms| format!("{ms} ms")).unwrap_or_default(),
@@ -103,6 +229,58 @@ pub async fn show_greylist(
         .take(25)
         .collect();
 
+    let reputation_lists = ReputationListDao::all(&state.pool)
+        .await?
+        .into_iter()
+        .map(|l| ReputationRow {
+            name: l.name,
+            path: l.path,
+            entries: l.entries,
+            skipped: l.skipped,
+            loaded_at: l
+                .loaded_at
+                .map(|x| x.format(TS_FMT).to_string())
+                .unwrap_or_else(|| "never".to_string()),
+            error: l.error,
+        })
+        .collect();
+
+    let stored = GreylistModelDao::latest(&state.pool).await?;
+    let disagreements = match &stored {
+        Some(m) => GreylistModelDao::disagreements(&state.pool, m.version)
+            .await?
+            .into_iter()
+            .map(ShadowRow::new)
+            .collect(),
+        None => Vec::new(),
+    };
+    let shadow_model = stored.map(|m| {
+        let loaded = Model::from_artifact(&m.kind, &m.artifact);
+        let weights = match &loaded {
+            Ok(model) => INPUT_NAMES
+                .iter()
+                .zip(&model.weights)
+                .map(|(name, w)| format!("{name} {w:+.2}"))
+                .chain([format!("bias {:+.2}", model.bias)])
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        ShadowModelView {
+            version: m.version,
+            trained_at: m.trained_at.format(TS_FMT).to_string(),
+            samples: m.samples,
+            metrics: serde_json::from_str::<Metrics>(&m.metrics).ok().map(|x| {
+                format!(
+                    "holdout of {} IP-days ({} bots): precision {:.3}, recall {:.3}, \
+                     accuracy {:.3}",
+                    x.samples, x.bots, x.precision, x.recall, x.accuracy
+                )
+            }),
+            weights,
+            load_error: loaded.err().map(|e| e.to_string()),
+        }
+    });
+
     Ok(HtmlTemplate(GreylistTemplate {
         top_bar: TopBar::create(&state.pool, "admin", session.auth_state.role()).await?,
         auth_state: session.auth_state,
@@ -111,6 +289,10 @@ pub async fn show_greylist(
         challenged_count,
         candidates,
         allowlisted: state.greylist.allowlisted(),
+        reputation_listed: listed.len(),
+        reputation_lists,
+        shadow_model,
+        disagreements,
     })
     .into_response())
 }
@@ -120,32 +302,98 @@ pub struct PinForm {
     pub ip: String,
 }
 
-/// `POST /admin/greylist/pin` — manually greylist an IP (never lapses until released). Also
-/// inserts into the in-memory snapshot so the toll bites on the client's very next request.
+/// `POST /admin/greylist/pin` — manually greylist an IP or a CIDR range (never lapses until
+/// released). A range is normalized to its network address (`185.177.72.9/24` pins
+/// `185.177.72.0/24`) and may not be wider than /16 (v4) or /32 (v6). Also inserts into the
+/// in-memory snapshot so the toll bites on the client's very next request.
 pub async fn pin_ip(
     State(state): State<AppState>,
+    actor: Actor,
     Form(form): Form<PinForm>,
 ) -> Result<Response, AppError> {
-    let ip = form.ip.trim();
-    if ip.parse::<IpAddr>().is_err() {
+    let input = form.ip.trim();
+    let entry = if input.contains('/') {
+        match IpPrefix::parse(input) {
+            Some(p) if p.is_pinnable() => p.to_string(),
+            Some(_) => {
+                return Ok((
+                    StatusCode::BAD_REQUEST,
+                    "Range too wide — pin /16 or narrower (IPv6: /32 or narrower)",
+                )
+                    .into_response());
+            }
+            None => {
+                return Ok((StatusCode::BAD_REQUEST, "Not a valid CIDR range").into_response());
+            }
+        }
+    } else if input.parse::<IpAddr>().is_ok() {
+        input.to_string()
+    } else {
         return Ok((StatusCode::BAD_REQUEST, "Not a valid IP address").into_response());
-    }
-    GreylistDao::pin_manual(&state.pool, ip, "manual").await?;
-    state.greylist.insert(ip);
+    };
+    let mut tx = state.pool.begin().await?;
+    let before = GreylistDao::find(&mut *tx, &entry).await?;
+    GreylistDao::pin_manual(&mut *tx, &entry, "manual").await?;
+    let after = GreylistDao::find(&mut *tx, &entry).await?;
+    tx.commit().await?;
+    state.greylist.insert(&entry);
+    let before = before.as_ref().map(audit_state);
+    let after = after.as_ref().map(audit_state);
+    audit::record(&state.pool, &actor, "greylist.pin", &entry, before, after).await;
     Ok(htmx_refresh())
 }
 
-/// `POST /admin/greylist/{ip}/release` — remove an IP from the greylist (a false positive, or a
-/// pin you're done with). Removes it from the snapshot too, so the toll stops immediately.
+/// `POST /admin/greylist/{ip}/release` — remove an IP or range from the greylist (a false
+/// positive, or a pin you're done with); a range arrives with its `/` as `%2F`. Removes it from
+/// the snapshot too, so the toll stops immediately. Releasing a range leaves the IP entries
+/// inside it alone.
 pub async fn release_ip(
     State(state): State<AppState>,
+    actor: Actor,
     Path(ip): Path<String>,
 ) -> Result<Response, AppError> {
-    GreylistDao::release(&state.pool, &ip).await?;
+    let mut tx = state.pool.begin().await?;
+    let before = GreylistDao::find(&mut *tx, &ip).await?;
+    GreylistDao::release(&mut *tx, &ip).await?;
+    tx.commit().await?;
     state.greylist.remove(&ip);
+    let before = before.as_ref().map(audit_state);
+    audit::record(&state.pool, &actor, "greylist.release", &ip, before, None).await;
     Ok(htmx_refresh())
 }
 
+/// `POST /admin/greylist/{ip}/de-escalate` — reverse a clear-then-scan escalation: the IP goes
+/// back to the toll (still greylisted — release it to drop it entirely) and its revoked
+/// clearances are honored again, in the DB and the in-memory lists alike.
+pub async fn de_escalate_ip(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path(ip): Path<String>,
+) -> Result<Response, AppError> {
+    let before = GreylistDao::find(&state.pool, &ip).await?;
+    if !GreylistDao::de_escalate(&state.pool, &ip).await? {
+        return Ok((StatusCode::NOT_FOUND, "Not an escalated IP").into_response());
+    }
+    state.greylist.unblock(&ip);
+    crate::greylist::escalation::refresh_revocations(&state.pool, &state.greylist).await?;
+    let after = GreylistDao::find(&state.pool, &ip).await?;
+    let before = before.as_ref().map(audit_state);
+    let after = after.as_ref().map(audit_state);
+    let action = "greylist.de_escalate";
+    audit::record(&state.pool, &actor, action, &ip, before, after).await;
+    Ok(htmx_refresh())
+}
+
+/// What an audit row keeps of an entry on either side of a change.
+fn audit_state(entry: &GreylistEntry) -> Value {
+    json!({
+        "reason": entry.reason,
+        "manual": entry.manual,
+        "expires_at": entry.expires_at.map(|t| t.to_rfc3339()),
+        "tier": entry.tier.to_string(),
+    })
+}
+
 /// `POST /admin/greylist/run-sweep` — force a detection pass NOW instead of waiting for the 15-min
 /// timer. Release-safe (no `debug_assertions` seam), so it works on beta: `curl` a signature path
 /// a couple times, click this, and your IP appears greylisted. Refreshes the page to show the
@@ -153,8 +401,14 @@ pub async fn release_ip(
 pub async fn run_sweep(State(state): State<AppState>) -> Result<Response, AppError> {
     let cache = crate::greylist::crawler::CrawlerCache::new(std::time::Duration::from_secs(60));
     let report =
-        crate::greylist::sweep::run_once(&state.pool, &state.resolver, &cache, &state.greylist)
-            .await?;
+        crate::greylist::sweep::run_once(
+            &state.pool,
+            &state.resolver,
+            &cache,
+            &state.greylist,
+            &state.geoip,
+        )
+        .await?;
     tracing::info!("manual greylist sweep: {report:?}");
     Ok(htmx_refresh())
 }
@@ -0,0 +1,166 @@
+//! Invite links: the admin mints a single-use, expiring link carrying the role
+//! its redeemer lands at (`/login?invite=…`), lists them, and revokes open ones.
+//! The token is shown exactly once, in the link, right after minting — only its
+//! HMAC hash is stored (see `InviteDao`).
+
+use askama::Template;
+use axum::{
+    Form,
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+};
+use http::StatusCode;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::types::chrono::{DateTime, Utc};
+
+use crate::db::dao::{
+    invites::{InviteDao, MAX_INVITE_DAYS},
+    roles::Role,
+};
+use crate::web::{
+    app_error::AppError,
+    app_state::AppState,
+    audit::{self, Actor},
+    authentication_state::AuthenticationState,
+    features::top_bar::TopBar,
+    html_template::HtmlTemplate,
+    htmx_responses::htmx_refresh,
+    session::SessionData,
+};
+
+/// Roles an invite may carry — the assignable set, as on `/admin/users`.
+const INVITE_ROLES: [Role; 3] = [Role::Family, Role::Registered, Role::Admin];
+
+/// Longest note accepted — a reminder of who the link was for.
+const MAX_NOTE_CHARS: usize = 100;
+
+#[derive(Template)]
+#[template(path = "admin/invites.html")]
+pub struct InvitesTemplate {
+    pub top_bar: TopBar,
+    pub auth_state: AuthenticationState,
+    pub roles: Vec<Role>,
+    pub invites: Vec<InviteView>,
+    /// The full link — set ONLY on the response right after minting.
+    pub new_link: Option<String>,
+}
+
+pub struct InviteView {
+    pub id: i64,
+    pub role: Role,
+    pub note: String,
+    pub created: String,
+    /// "open until …", "redeemed by … on …", "expired …", "revoked …".
+    pub status: String,
+    /// Still redeemable → offer Revoke.
+    pub open: bool,
+}
+
+#[derive(Deserialize)]
+pub struct CreateInviteForm {
+    pub role: Role,
+    pub expires_in_days: i64,
+    #[serde(default)]
+    pub note: String,
+}
+
+pub async fn show_invites(
+    State(state): State<AppState>,
+    session_data: SessionData,
+) -> Result<Response, AppError> {
+    render_page(&state, session_data, None).await
+}
+
+/// `POST /admin/invites` — mint an invite and show its link once.
+pub async fn create_invite(
+    State(state): State<AppState>,
+    actor: Actor,
+    session_data: SessionData,
+    Form(form): Form<CreateInviteForm>,
+) -> Result<Response, AppError> {
+    let Some(user_id) = session_data.auth_state.user().map(|u| u.id) else {
+        return Ok((StatusCode::FORBIDDEN, "Not authenticated").into_response());
+    };
+    if !INVITE_ROLES.contains(&form.role) {
+        return Ok((StatusCode::BAD_REQUEST, "Not an assignable role").into_response());
+    }
+    if !(1..=MAX_INVITE_DAYS).contains(&form.expires_in_days) {
+        return Ok((StatusCode::BAD_REQUEST, "Expiry must be 1–30 days").into_response());
+    }
+    let note = form.note.trim();
+    if note.chars().count() > MAX_NOTE_CHARS || note.chars().any(char::is_control) {
+        return Ok((StatusCode::BAD_REQUEST, "Invalid note").into_response());
+    }
+    let Some(expires_at) =
+        DateTime::<Utc>::from_timestamp(Utc::now().timestamp() + form.expires_in_days * 86_400, 0)
+    else {
+        return Ok((StatusCode::BAD_REQUEST, "Invalid expiry").into_response());
+    };
+
+    let (token, id) = InviteDao::create(&state.pool, &user_id, form.role, note, expires_at).await?;
+    let after = json!({
+        "role": form.role.to_string(),
+        "note": note,
+        "expires_at": expires_at.to_rfc3339(),
+    });
+    audit::record(&state.pool, &actor, "invite.create", &id.to_string(), None, Some(after)).await;
+    let link = format!("https://{}/login?invite={token}", state.site_host);
+    render_page(&state, session_data, Some(link)).await
+}
+
+/// `POST /admin/invites/{id}/revoke` — kill an open invite (404 if it was
+/// already redeemed, revoked, or never existed).
+pub async fn revoke_invite(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> Result<Response, AppError> {
+    if !InviteDao::revoke(&state.pool, id).await? {
+        return Ok((StatusCode::NOT_FOUND, "No open invite").into_response());
+    }
+    audit::record(&state.pool, &actor, "invite.revoke", &id.to_string(), None, None).await;
+    Ok(htmx_refresh())
+}
+
+async fn render_page(
+    state: &AppState,
+    session_data: SessionData,
+    new_link: Option<String>,
+) -> Result<Response, AppError> {
+    let now = Utc::now();
+    let fmt = |t: DateTime<Utc>| t.format("%Y-%m-%d %H:%M UTC").to_string();
+    let invites = InviteDao::list(&state.pool)
+        .await?
+        .into_iter()
+        .map(|i| {
+            let (status, open) = if let Some(at) = i.redeemed_at {
+                let who = i.redeemed_by.as_deref().unwrap_or("a deleted user");
+                (format!("redeemed by {who} on {}", fmt(at)), false)
+            } else if let Some(at) = i.revoked_at {
+                (format!("revoked {}", fmt(at)), false)
+            } else if i.expires_at <= now {
+                (format!("expired {}", fmt(i.expires_at)), false)
+            } else {
+                (format!("open until {}", fmt(i.expires_at)), true)
+            };
+            InviteView {
+                id: i.id,
+                role: i.role,
+                note: i.note,
+                created: i.created_at.format("%Y-%m-%d").to_string(),
+                status,
+                open,
+            }
+        })
+        .collect();
+
+    let template = InvitesTemplate {
+        top_bar: TopBar::create(&state.pool, "admin", session_data.auth_state.role()).await?,
+        auth_state: session_data.auth_state,
+        roles: INVITE_ROLES.to_vec(),
+        invites,
+        new_link,
+    };
+    Ok(HtmlTemplate(template).into_response())
+}
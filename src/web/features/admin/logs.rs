@@ -20,7 +20,8 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::web::{
     app_error::AppError, app_state::AppState, authentication_state::AuthenticationState,
-    features::top_bar::TopBar, html_template::HtmlTemplate, session::SessionData,
+    features::top_bar::TopBar, html_template::HtmlTemplate,
+    request_log_writer::WriterCounters, session::SessionData,
 };
 
 /// Read at most the last 256 KiB of EACH log file — NEVER slurp a multi-GB file.
@@ -87,6 +88,8 @@ pub struct LogsTemplate {
     pub lines: Vec<String>,
     /// The file we tailed (e.g. `hotchkiss.io.log.2026-06-29`), or "" if none.
     pub source: String,
+    /// The batched request_log writer's totals since boot.
+    pub writer: WriterCounters,
 }
 
 /// `GET /admin/logs?level=all|warn|error` — gated by the `/admin` `require_admin`
@@ -108,6 +111,7 @@ pub async fn show_logs(
         level: level.as_str().to_string(),
         lines,
         source,
+        writer: state.request_log.counters(),
     };
     Ok(HtmlTemplate(tmpl).into_response())
 }
@@ -26,6 +26,7 @@ use crate::db::dao::content_pages::ContentPageDao;
 use crate::media::MediaStore;
 use crate::web::app_error::AppError;
 use crate::web::app_state::AppState;
+use crate::web::audit::Actor;
 use crate::web::authentication_state::AuthenticationState;
 use crate::web::features::admin::media::ingest_stored_file;
 use crate::web::features::pages::write::{create_page, update_page, PageUpdate, PageWriteError};
@@ -261,6 +262,7 @@ async fn series_has_volume_with_sha(
 /// bad EPUB is a `Failed` entry, never an aborted batch.
 pub async fn ingest_volumes(
     state: &AppState,
+    actor: &Actor,
     series: &ContentPageDao,
     series_path: &[&str],
     mut staged: Vec<StagedVolume>,
@@ -270,7 +272,7 @@ pub async fn ingest_volumes(
     staged.sort_by(|a, b| a.filename.cmp(&b.filename));
     let mut report = IngestReport::default();
     for (idx, vol) in staged.iter().enumerate() {
-        let outcome = match ingest_one(state, series, series_path, vol, idx as i64).await {
+        let outcome = match ingest_one(state, actor, series, series_path, vol, idx as i64).await {
             Ok(o) => o,
             Err(e) => VolumeOutcome::Failed {
                 filename: vol.filename.clone(),
@@ -284,6 +286,7 @@ pub async fn ingest_volumes(
 
 async fn ingest_one(
     state: &AppState,
+    actor: &Actor,
     series: &ContentPageDao,
     series_path: &[&str],
     vol: &StagedVolume,
@@ -300,7 +303,7 @@ async fn ingest_one(
 
     // 2. Reserve the volume page (inherits the series gate). A slug collision means a
     //    same-numbered DIFFERENT file already owns this page — a soft skip, no media.
-    let written = match create_page(&state.pool, series_path, &parsed.title).await {
+    let written = match create_page(&state.pool, actor, series_path, &parsed.title).await {
         Ok(w) => w,
         Err(PageWriteError::DuplicateSlug { slug, .. }) => {
             return Ok(VolumeOutcome::SkippedExisting {
@@ -346,6 +349,7 @@ async fn ingest_one(
     let vpath: Vec<&str> = written.path_segments.iter().map(String::as_str).collect();
     update_page(
         &state.pool,
+        actor,
         &state.site_host,
         &vpath,
         PageUpdate {
@@ -371,6 +375,7 @@ async fn ingest_one(
 /// lists its children when viewed directly at `/pages/library/manga[/…]`.
 pub async fn resolve_or_create_series(
     state: &AppState,
+    actor: &Actor,
     series_name: &str,
 ) -> Result<(ContentPageDao, Vec<String>)> {
     let series_title = series_name.trim();
@@ -385,6 +390,7 @@ pub async fn resolve_or_create_series(
     // the `/library/manga` section route's ordering).
     let manga = ensure_child(
         state,
+        actor,
         &["library"],
         Some(library.page_id),
         MANGA_SECTION_TITLE,
@@ -396,6 +402,7 @@ pub async fn resolve_or_create_series(
     // parsed volume number), so 1..N read in order.
     let series = ensure_child(
         state,
+        actor,
         &["library", "manga"],
         Some(manga.page_id),
         series_title,
@@ -417,6 +424,7 @@ pub async fn resolve_or_create_series(
 /// concurrent create) re-finds rather than erroring.
 async fn ensure_child(
     state: &AppState,
+    actor: &Actor,
     parent_path: &[&str],
     parent_id: Option<i64>,
     title: &str,
@@ -428,7 +436,7 @@ async fn ensure_child(
     {
         return Ok(existing);
     }
-    let written = match create_page(&state.pool, parent_path, title).await {
+    let written = match create_page(&state.pool, actor, parent_path, title).await {
         Ok(w) => w,
         Err(PageWriteError::DuplicateSlug { .. }) => {
             return ContentPageDao::find_by_name(&state.pool, parent_id, slug.as_str())
@@ -441,6 +449,7 @@ async fn ensure_child(
     let path: Vec<&str> = written.path_segments.iter().map(String::as_str).collect();
     update_page(
         &state.pool,
+        actor,
         &state.site_host,
         &path,
         PageUpdate {
@@ -568,6 +577,7 @@ pub struct FilesystemIngestForm {
 
 pub async fn ingest_filesystem(
     State(state): State<AppState>,
+    actor: Actor,
     session_data: SessionData,
     Form(form): Form<FilesystemIngestForm>,
 ) -> Result<Response, AppError> {
@@ -587,7 +597,7 @@ pub async fn ingest_filesystem(
         return render_console(&state, &session_data, Some(msg), None).await;
     }
     let count = books.len();
-    let (series, series_path) = match resolve_or_create_series(&state, &series_name).await {
+    let (series, series_path) = match resolve_or_create_series(&state, &actor, &series_name).await {
         Ok(s) => s,
         Err(e) => {
             let msg = format!("Could not resolve the series: {e:#}");
@@ -607,7 +617,7 @@ pub async fn ingest_filesystem(
             folder.display()
         );
         let path_refs: Vec<&str> = series_path.iter().map(String::as_str).collect();
-        let report = ingest_folder(&st, &series, &path_refs, books).await;
+        let report = ingest_folder(&st, &actor, &series, &path_refs, books).await;
         tracing::info!(
             "manga ingest into `{series_name_log}` done: {} created, {} skipped, {} failed",
             report.created(),
@@ -632,6 +642,7 @@ pub async fn ingest_filesystem(
 /// 27 GB series must go through the filesystem path), returning the per-file report.
 pub async fn ingest_upload(
     State(state): State<AppState>,
+    actor: Actor,
     session_data: SessionData,
     mut multipart: Multipart,
 ) -> Result<Response, AppError> {
@@ -677,9 +688,9 @@ pub async fn ingest_upload(
     if staged.is_empty() {
         return render_console(&state, &session_data, Some("No .epub or .cbz files in the upload.".into()), None).await;
     }
-    let (series, series_path) = resolve_or_create_series(&state, &series_name).await?;
+    let (series, series_path) = resolve_or_create_series(&state, &actor, &series_name).await?;
     let path_refs: Vec<&str> = series_path.iter().map(String::as_str).collect();
-    let report = ingest_volumes(&state, &series, &path_refs, staged).await;
+    let report = ingest_volumes(&state, &actor, &series, &path_refs, staged).await;
     let view = report_view(&report);
     let flash = format!(
         "Ingested into “{series_name}”: {} created, {} skipped, {} failed.",
@@ -694,6 +705,7 @@ pub async fn ingest_upload(
 /// position fallback (an un-numbered file) is deterministic.
 async fn ingest_folder(
     state: &AppState,
+    actor: &Actor,
     series: &ContentPageDao,
     series_path: &[&str],
     mut epubs: Vec<PathBuf>,
@@ -709,7 +721,7 @@ async fn ingest_folder(
         let outcome = match stage_file(&state.media_store, path).await {
             Ok((sha, len, root)) => {
                 let vol = StagedVolume { sha, len, root, filename: filename.clone() };
-                match ingest_one(state, series, series_path, &vol, idx as i64).await {
+                match ingest_one(state, actor, series, series_path, &vol, idx as i64).await {
                     Ok(o) => o,
                     Err(e) => VolumeOutcome::Failed { filename, error: format!("{e:#}") },
                 }
@@ -16,6 +16,7 @@ use axum::response::{IntoResponse, Response};
 use axum::{Form, Json};
 use http::{header, StatusCode};
 use serde::Deserialize;
+use serde_json::{Value, json};
 use sqlx::SqlitePool;
 
 use crate::db::dao::crypto_key::CryptoKey;
@@ -25,6 +26,8 @@ use crate::media::poster::generate_poster;
 use crate::media::probe::{probe, Probed};
 use crate::media::resize::{responsive_avif_variants, ResizeResult};
 use crate::media::{media_url_key, MediaStore};
+use crate::telemetry::in_current_span;
+use crate::web::audit::{self, Actor};
 use crate::web::authentication_state::AuthenticationState;
 use crate::web::features::media::{build_manifest, render_embed_html};
 use crate::web::features::media_select;
@@ -44,6 +47,24 @@ fn created_manifest(media: &MediaDao, variants: &[MediaVariantDao]) -> Response
         .into_response()
 }
 
+/// An item as the audit trail records it: title, kind, gate, edit params and the
+/// variant count — never chapter lists or byte hashes.
+fn audit_summary(media: &MediaDao, variants: usize) -> Value {
+    json!({
+        "title": media.title,
+        "kind": media.kind,
+        "min_role": media.min_role,
+        "edit": media.meta().edit,
+        "variants": variants,
+    })
+}
+
+/// The item's summary as it stands in the DB right now (variant count included).
+async fn current_summary(pool: &SqlitePool, media: &MediaDao) -> Result<Value> {
+    let variants = MediaVariantDao::find_by_media_id(pool, media.media_id).await?;
+    Ok(audit_summary(media, variants.len()))
+}
+
 /// CryptoKey row id for the media-URL HMAC secret (session signing key is id 1).
 const MEDIA_HMAC_KEY_ID: i64 = 2;
 
@@ -550,10 +571,16 @@ pub(crate) async fn ingest_stored_file(
 /// fallback). The admin library (DR) + the inline editor upload both drive this.
 pub async fn create_media(
     State(state): State<AppState>,
+    actor: Actor,
     multipart: Multipart,
 ) -> Result<Response, AppError> {
     match ingest_new_item(&state, multipart).await? {
-        Some((media, variants)) => Ok(created_manifest(&media, &variants)),
+        Some((media, variants)) => {
+            let after = audit_summary(&media, variants.len());
+            audit::record(&state.pool, &actor, "media.create", &media.media_ref, None, Some(after))
+                .await;
+            Ok(created_manifest(&media, &variants))
+        }
         None => Ok((StatusCode::BAD_REQUEST, "No files in the upload").into_response()),
     }
 }
@@ -571,12 +598,14 @@ pub async fn create_media(
 /// admin fallback) — NOT the `/admin` nest's `require_admin`.
 pub async fn replace_media_variants(
     State(state): State<AppState>,
+    actor: Actor,
     Path(media_ref): Path<String>,
     multipart: Multipart,
 ) -> Result<Response, AppError> {
     let Some(item) = MediaDao::find_by_ref(&state.pool, &media_ref).await? else {
         return Ok((StatusCode::NOT_FOUND, "No such media item").into_response());
     };
+    let before = current_summary(&state.pool, &item).await?;
 
     let (ingested, _fields) = ingest_multipart(&state.media_store, multipart).await?;
     // A complete replace needs at least one file — replacing to zero variants is a
@@ -643,6 +672,16 @@ pub async fn replace_media_variants(
     // Reflect the final variant set back (the manifest) so fab-gui can confirm the swap.
     let item = MediaDao::find_by_ref(&state.pool, &media_ref).await?.unwrap_or(item);
     let variants = MediaVariantDao::find_by_media_id(&state.pool, item.media_id).await?;
+    let after = audit_summary(&item, variants.len());
+    audit::record(
+        &state.pool,
+        &actor,
+        "media.replace_variants",
+        &media_ref,
+        Some(before),
+        Some(after),
+    )
+    .await;
     Ok(Json(build_manifest(&item, &variants, Role::Admin)).into_response())
 }
 
@@ -727,16 +766,21 @@ async fn append_variants(
 /// unknown ref, `400` empty body. (The admin library's "+ add encode" drives this.)
 pub async fn add_media_variant(
     State(state): State<AppState>,
+    actor: Actor,
     Path(media_ref): Path<String>,
     multipart: Multipart,
 ) -> Result<Response, AppError> {
     let Some(item) = MediaDao::find_by_ref(&state.pool, &media_ref).await? else {
         return Ok((StatusCode::NOT_FOUND, "No such media item").into_response());
     };
+    let before = current_summary(&state.pool, &item).await?;
     if !append_variants(&state, item.media_id, multipart).await? {
         return Ok((StatusCode::BAD_REQUEST, "No file in the upload").into_response());
     }
     let variants = MediaVariantDao::find_by_media_id(&state.pool, item.media_id).await?;
+    let after = audit_summary(&item, variants.len());
+    audit::record(&state.pool, &actor, "media.add_variant", &media_ref, Some(before), Some(after))
+        .await;
     Ok(created_manifest(&item, &variants))
 }
 
@@ -757,12 +801,14 @@ pub struct MetadataBody {
 /// silently loosens). Returns the manifest; `404` unknown ref.
 pub async fn update_media_metadata(
     State(state): State<AppState>,
+    actor: Actor,
     Path(media_ref): Path<String>,
     Json(body): Json<MetadataBody>,
 ) -> Result<Response, AppError> {
     let Some(item) = MediaDao::find_by_ref(&state.pool, &media_ref).await? else {
         return Ok((StatusCode::NOT_FOUND, "No such media item").into_response());
     };
+    let before = current_summary(&state.pool, &item).await?;
     if let Some(title) = &body.title {
         MediaDao::update_title(&state.pool, item.media_id, title).await?;
     }
@@ -775,6 +821,9 @@ pub async fn update_media_metadata(
     }
     let item = MediaDao::find_by_ref(&state.pool, &media_ref).await?.unwrap_or(item);
     let variants = MediaVariantDao::find_by_media_id(&state.pool, item.media_id).await?;
+    let after = audit_summary(&item, variants.len());
+    audit::record(&state.pool, &actor, "media.update", &media_ref, Some(before), Some(after))
+        .await;
     Ok(Json(build_manifest(&item, &variants, Role::Admin)).into_response())
 }
 
@@ -782,12 +831,15 @@ pub async fn update_media_metadata(
 /// `404` for an unknown ref.
 pub async fn delete_media_item(
     State(state): State<AppState>,
+    actor: Actor,
     Path(media_ref): Path<String>,
 ) -> Result<Response, AppError> {
     let Some(item) = MediaDao::find_by_ref(&state.pool, &media_ref).await? else {
         return Ok((StatusCode::NOT_FOUND, "No such media item").into_response());
     };
+    let before = current_summary(&state.pool, &item).await?;
     MediaDao::delete_by_id(&state.pool, item.media_id).await?;
+    audit::record(&state.pool, &actor, "media.delete", &media_ref, Some(before), None).await;
     Ok(StatusCode::NO_CONTENT.into_response())
 }
 
@@ -795,12 +847,15 @@ pub async fn delete_media_item(
 /// `404` if the ref OR the key (within that item) is unknown.
 pub async fn delete_media_variant(
     State(state): State<AppState>,
+    actor: Actor,
     Path((media_ref, url_key)): Path<(String, String)>,
 ) -> Result<Response, AppError> {
     let Some(item) = MediaDao::find_by_ref(&state.pool, &media_ref).await? else {
         return Ok((StatusCode::NOT_FOUND, "No such media item").into_response());
     };
     if MediaVariantDao::delete_by_url_key_in_item(&state.pool, item.media_id, &url_key).await? {
+        let target = format!("{media_ref}/variants/{url_key}");
+        audit::record(&state.pool, &actor, "media.delete_variant", &target, None, None).await;
         Ok(StatusCode::NO_CONTENT.into_response())
     } else {
         Ok((StatusCode::NOT_FOUND, "No such variant").into_response())
@@ -816,12 +871,12 @@ async fn probe_stored(
     filename: String,
     hint: Option<String>,
 ) -> Result<Probed> {
-    tokio::task::spawn_blocking(move || {
+    tokio::task::spawn_blocking(in_current_span(move || {
         let path = store
             .resolve_path(&sha, hint.as_deref())
             .ok_or_else(|| anyhow!("just-stored media {sha} not found in any media root"))?;
         probe(&path, &filename)
-    })
+    }))
     .await
     .map_err(|e| anyhow!("probe task panicked: {e}"))?
 }
@@ -863,12 +918,12 @@ async fn maybe_add_poster(state: &AppState, hmac_key: &[u8], media_id: i64, vide
     let store = state.media_store.clone();
     let result: Result<(String, i64, std::path::PathBuf)> = async {
         let path_store = store.clone();
-        let avif = tokio::task::spawn_blocking(move || -> Result<Vec<u8>> {
+        let avif = tokio::task::spawn_blocking(in_current_span(move || -> Result<Vec<u8>> {
             let path = path_store
                 .resolve_path(&video_sha, None)
                 .ok_or_else(|| anyhow!("poster source {video_sha} not found in any media root"))?;
             generate_poster(&path)
-        })
+        }))
         .await
         .map_err(|e| anyhow!("poster task panicked: {e}"))??;
         let len = avif.len() as i64;
@@ -1110,6 +1165,7 @@ pub async fn rederive_media(
 /// CLEAR the edit entirely — a full undo.
 pub async fn rotate_media(
     State(state): State<AppState>,
+    actor: Actor,
     Path(media_ref): Path<String>,
     Form(form): Form<RotateForm>,
 ) -> Result<Response, AppError> {
@@ -1128,7 +1184,10 @@ pub async fn rotate_media(
     let mut edit = meta.edit.unwrap_or_default();
     edit.rotate = (edit.rotate + delta) % 4;
     meta.edit = (edit.rotate != 0 || edit.corners.is_some()).then_some(edit);
+    let (before, after) = (json!(media.meta().edit), json!(meta.edit));
     MediaDao::set_metadata(&state.pool, media.media_id, meta.to_stored()).await?;
+    audit::record(&state.pool, &actor, "media.rotate", &media.media_ref, Some(before), Some(after))
+        .await;
 
     if let Some(err) = drop_rungs_and_respawn(&state, &media).await? {
         return Ok(err);
@@ -1151,6 +1210,7 @@ pub struct RotateForm {
 /// `apply_edit`'s degenerate-quad fallback is only belt-and-suspenders.
 pub async fn crop_media(
     State(state): State<AppState>,
+    actor: Actor,
     Path(media_ref): Path<String>,
     Json(form): Json<CropForm>,
 ) -> Result<Response, AppError> {
@@ -1177,7 +1237,10 @@ pub async fn crop_media(
     let mut edit = meta.edit.unwrap_or_default();
     edit.corners = form.corners;
     meta.edit = (edit.rotate != 0 || edit.corners.is_some()).then_some(edit);
+    let (before, after) = (json!(media.meta().edit), json!(meta.edit));
     MediaDao::set_metadata(&state.pool, media.media_id, meta.to_stored()).await?;
+    audit::record(&state.pool, &actor, "media.crop", &media.media_ref, Some(before), Some(after))
+        .await;
 
     if let Some(err) = drop_rungs_and_respawn(&state, &media).await? {
         return Ok(err);
@@ -1244,12 +1307,12 @@ async fn maybe_add_responsive_variants(
             .map(|m| m.meta().edit.unwrap_or_default())
             .unwrap_or_default();
         let path_store = store.clone();
-        let resized = tokio::task::spawn_blocking(move || -> Result<ResizeResult> {
+        let resized = tokio::task::spawn_blocking(in_current_span(move || -> Result<ResizeResult> {
             let path = path_store
                 .resolve_path(&original_sha, None)
                 .ok_or_else(|| anyhow!("resize source {original_sha} not found in any media root"))?;
             responsive_avif_variants(&path, &edit)
-        })
+        }))
         .await
         .map_err(|e| anyhow!("resize task panicked: {e}"))??;
 
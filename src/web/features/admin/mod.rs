@@ -4,16 +4,23 @@ use axum::{
     Router,
 };
 
-use crate::web::{app_state::AppState, middleware::require_admin::require_admin};
+use crate::web::{
+    app_state::AppState,
+    middleware::{require_admin::require_admin, require_step_up::require_step_up},
+};
 
 pub mod analytics;
 pub mod api_keys;
+pub mod audit;
 pub mod capture;
+pub mod csp_reports;
 pub mod dead_links;
 pub mod greylist;
+pub mod invites;
 pub mod logs;
 pub mod manga_ingest;
 pub mod media;
+pub mod oidc_clients;
 pub mod pages;
 pub mod users;
 
@@ -38,6 +45,9 @@ pub fn admin_router() -> Router<AppState> {
         // Child-index widget drag-reorder (DV.12) — a fixed two-segment path, so it
         // never collides with `/pages/{page_id}/...`.
         .route("/pages/reorder-children", post(pages::reorder_children))
+        // Mint a campaign-tagged share link from the page editor — also a fixed
+        // two-segment path.
+        .route("/pages/share-link", post(pages::mint_share_link))
         // Toggle a page's landing "Featured" pin (13.8). Two path segments, so it
         // never collides with the static `/pages/reorder`.
         .route("/pages/{page_id}/feature", post(pages::toggle_feature))
@@ -72,20 +82,53 @@ pub fn admin_router() -> Router<AppState> {
             "/api-keys",
             get(api_keys::show_api_keys).post(api_keys::create_api_key),
         )
-        .route("/api-keys/{id}", delete(api_keys::revoke_api_key))
+        // Destructive actions carry a per-route step-up layer: a fresh passkey
+        // assertion, on top of the admin gate below.
+        .route(
+            "/api-keys/{id}",
+            delete(api_keys::revoke_api_key).layer(from_fn(require_step_up)),
+        )
+        // Rotate: mint a successor, the old key expires after the overlap window.
+        .route("/api-keys/{id}/rotate", post(api_keys::rotate_api_key))
         // User management (Phase CC): list / promote-demote / delete.
         .route("/users", get(users::show_users))
-        .route("/users/{id}/role", post(users::set_user_role))
-        .route("/users/{id}", delete(users::delete_user))
+        .route(
+            "/users/{id}/role",
+            post(users::set_user_role).layer(from_fn(require_step_up)),
+        )
+        .route(
+            "/users/{id}",
+            delete(users::delete_user).layer(from_fn(require_step_up)),
+        )
+        .route("/users/{id}/sessions/revoke", post(users::revoke_user_session))
+        .route("/users/{id}/sessions/revoke_all", post(users::revoke_all_user_sessions))
+        // Invite links: mint (shown once) / list / revoke.
+        .route("/invites", get(invites::show_invites).post(invites::create_invite))
+        .route("/invites/{id}/revoke", post(invites::revoke_invite))
+        // OIDC client registry: the household apps that sign in with site passkeys.
+        .route(
+            "/oidc-clients",
+            get(oidc_clients::show_oidc_clients).post(oidc_clients::create_oidc_client),
+        )
+        .route(
+            "/oidc-clients/{id}",
+            delete(oidc_clients::delete_oidc_client).layer(from_fn(require_step_up)),
+        )
         // Server log tail (Phase CO): manual-refresh viewer; excluded from
         // request_log (request_log.rs) so a self-view never feeds the access log.
         .route("/logs", get(logs::show_logs))
+        // Audit trail of privileged mutations (who, how, before/after), filterable.
+        .route("/audit", get(audit::show_audit))
+        // Content-Security-Policy violation reports: the grouped view + clear.
+        .route("/csp-reports", get(csp_reports::show_csp_reports))
+        .route("/csp-reports/clear", post(csp_reports::clear_csp_reports))
         // Greylist management (Phase CX): view/pin/release. `/pin` is a fixed segment
         // (one path element), `{ip}/release` is two — no collision.
         .route("/greylist", get(greylist::show_greylist))
         .route("/greylist/pin", post(greylist::pin_ip))
         .route("/greylist/run-sweep", post(greylist::run_sweep))
         .route("/greylist/{ip}/release", post(greylist::release_ip))
+        .route("/greylist/{ip}/de-escalate", post(greylist::de_escalate_ip))
         // Dead-link checker (Phase DL): the report, a full re-scan, a per-link re-check.
         .route("/dead-links", get(dead_links::show_dead_links))
         .route("/dead-links/run-scan", post(dead_links::run_scan))
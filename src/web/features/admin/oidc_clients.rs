@@ -0,0 +1,185 @@
+//! The OIDC client registry: the household apps allowed to "Sign in with" this
+//! site. The admin registers a name and its exact redirect URIs; the server mints
+//! the client id and secret, showing the secret exactly once — only its HMAC hash
+//! is stored (see `OidcClientDao`). Deleting a client kills its outstanding codes
+//! and remembered consents; tokens already issued run out their hour.
+
+use askama::Template;
+use axum::{
+    Form,
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+};
+use http::StatusCode;
+use serde::Deserialize;
+use serde_json::json;
+use url::Url;
+
+use crate::db::dao::oidc::OidcClientDao;
+use crate::web::{
+    app_error::AppError,
+    app_state::AppState,
+    audit::{self, Actor},
+    authentication_state::AuthenticationState,
+    features::top_bar::TopBar,
+    html_template::HtmlTemplate,
+    htmx_responses::htmx_refresh,
+    session::SessionData,
+};
+
+/// Longest app name accepted.
+const MAX_NAME_CHARS: usize = 60;
+
+/// Most redirect URIs one client may register.
+const MAX_REDIRECT_URIS: usize = 10;
+
+#[derive(Template)]
+#[template(path = "admin/oidc_clients.html")]
+pub struct OidcClientsTemplate {
+    pub top_bar: TopBar,
+    pub auth_state: AuthenticationState,
+    pub issuer: String,
+    pub clients: Vec<ClientView>,
+    /// `(client_id, secret)` — set ONLY on the response right after registering.
+    pub new_client: Option<(String, String)>,
+}
+
+pub struct ClientView {
+    pub id: i64,
+    pub name: String,
+    pub client_id: String,
+    pub redirect_uris: Vec<String>,
+    pub created: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateClientForm {
+    pub name: String,
+    /// One URI per line.
+    pub redirect_uris: String,
+}
+
+pub async fn show_oidc_clients(
+    State(state): State<AppState>,
+    session_data: SessionData,
+) -> Result<Response, AppError> {
+    render_page(&state, session_data, None).await
+}
+
+/// `POST /admin/oidc-clients` — register a client and show its secret once.
+pub async fn create_oidc_client(
+    State(state): State<AppState>,
+    actor: Actor,
+    session_data: SessionData,
+    Form(form): Form<CreateClientForm>,
+) -> Result<Response, AppError> {
+    let name = form.name.trim();
+    if name.is_empty()
+        || name.chars().count() > MAX_NAME_CHARS
+        || name.chars().any(char::is_control)
+    {
+        return Ok((StatusCode::BAD_REQUEST, "Invalid app name").into_response());
+    }
+    let uris: Vec<String> = form
+        .redirect_uris
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect();
+    if uris.is_empty() || uris.len() > MAX_REDIRECT_URIS {
+        return Ok((StatusCode::BAD_REQUEST, "Give 1–10 redirect URIs").into_response());
+    }
+    if let Some(bad) = uris.iter().find(|u| !valid_redirect_uri(u)) {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            format!("Not a usable redirect URI: {bad}"),
+        )
+            .into_response());
+    }
+
+    let (secret, client) = OidcClientDao::create(&state.pool, name, &uris).await?;
+    let after = json!({ "name": name, "client_id": client.client_id, "redirect_uris": uris });
+    let target = client.id.to_string();
+    audit::record(
+        &state.pool,
+        &actor,
+        "oidc_client.create",
+        &target,
+        None,
+        Some(after),
+    )
+    .await;
+    render_page(&state, session_data, Some((client.client_id, secret))).await
+}
+
+/// `DELETE /admin/oidc-clients/{id}` (step-up gated) — unregister a client.
+pub async fn delete_oidc_client(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> Result<Response, AppError> {
+    let Some(client) = OidcClientDao::delete(&state.pool, id).await? else {
+        return Ok((StatusCode::NOT_FOUND, "No such client").into_response());
+    };
+    let before = json!({ "name": client.name, "client_id": client.client_id });
+    audit::record(
+        &state.pool,
+        &actor,
+        "oidc_client.delete",
+        &id.to_string(),
+        Some(before),
+        None,
+    )
+    .await;
+    Ok(htmx_refresh())
+}
+
+/// An absolute http(s) URL without a fragment (RFC 6749 §3.1.2). Plain http is
+/// allowed: the household's apps mostly live on the LAN without certificates.
+fn valid_redirect_uri(raw: &str) -> bool {
+    Url::parse(raw).is_ok_and(|u| {
+        matches!(u.scheme(), "http" | "https") && u.has_host() && u.fragment().is_none()
+    })
+}
+
+async fn render_page(
+    state: &AppState,
+    session_data: SessionData,
+    new_client: Option<(String, String)>,
+) -> Result<Response, AppError> {
+    let clients = OidcClientDao::list(&state.pool)
+        .await?
+        .into_iter()
+        .map(|c| ClientView {
+            id: c.id,
+            redirect_uris: c.redirect_uris().map(str::to_string).collect(),
+            created: c.created_at.format("%Y-%m-%d").to_string(),
+            name: c.name,
+            client_id: c.client_id,
+        })
+        .collect();
+
+    let template = OidcClientsTemplate {
+        top_bar: TopBar::create(&state.pool, "admin", session_data.auth_state.role()).await?,
+        auth_state: session_data.auth_state,
+        issuer: format!("https://{}", state.site_host),
+        clients,
+        new_client,
+    };
+    Ok(HtmlTemplate(template).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::valid_redirect_uri;
+
+    #[test]
+    fn redirect_uri_rules() {
+        assert!(valid_redirect_uri("http://photos.lan/auth/callback"));
+        assert!(valid_redirect_uri("https://grocy.example.com/cb?x=1"));
+        assert!(!valid_redirect_uri("/relative/cb"));
+        assert!(!valid_redirect_uri("javascript:alert(1)"));
+        assert!(!valid_redirect_uri("https://photos.lan/cb#frag"));
+    }
+}
@@ -1,9 +1,14 @@
 use crate::{
-    db::dao::content_pages::ContentPageDao,
+    db::dao::{content_pages::ContentPageDao, request_log::Campaign},
     web::{
-        app_error::AppError, app_state::AppState, authentication_state::AuthenticationState,
-        features::top_bar::TopBar, html_template::HtmlTemplate, htmx_responses::htmx_refresh,
-        session::SessionData, util::category,
+        app_error::AppError,
+        app_state::AppState,
+        authentication_state::AuthenticationState,
+        features::top_bar::TopBar,
+        html_template::HtmlTemplate,
+        htmx_responses::htmx_refresh,
+        session::SessionData,
+        util::{campaign, category},
     },
 };
 use askama::Template;
@@ -16,6 +21,7 @@ use axum_extra::extract::Form;
 use serde::Deserialize;
 use sqlx::types::chrono::{DateTime, Utc};
 use std::collections::HashSet;
+use url::Url;
 
 #[derive(Template)]
 #[template(path = "admin/pages.html")]
@@ -180,3 +186,50 @@ pub async fn unpublish(
     ContentPageDao::set_creation_date(&state.pool, page_id, draft_sentinel).await?;
     Ok(htmx_refresh())
 }
+
+#[derive(Template)]
+#[template(path = "admin/share_link.html")]
+pub struct ShareLinkTemplate {
+    pub link: String,
+    pub campaign: Campaign,
+}
+
+/// The editor's "Share link" form: the page's canonical URL plus the tags to put on it.
+#[derive(Deserialize)]
+pub struct ShareLinkForm {
+    pub url: String,
+    pub source: String,
+    #[serde(default)]
+    pub medium: String,
+    #[serde(default)]
+    pub campaign: String,
+}
+
+/// Mint a campaign-tagged link to a page on this site (see `util::campaign`), returned as a
+/// fragment with a copy button. The values are normalized here exactly as the request log
+/// will record them, so what the form shows is what the Campaigns panel will. Any tags
+/// already on the URL are replaced. Admin-gated.
+pub async fn mint_share_link(
+    State(state): State<AppState>,
+    Form(form): Form<ShareLinkForm>,
+) -> Result<Response, AppError> {
+    let Ok(mut url) = Url::parse(form.url.trim()) else {
+        return Ok((StatusCode::BAD_REQUEST, "Not a link to this site").into_response());
+    };
+    if url.host_str() != Some(state.site_host.as_str()) {
+        return Ok((StatusCode::BAD_REQUEST, "Not a link to this site").into_response());
+    }
+    if let Some(rest) = url.query().and_then(campaign::strip) {
+        url.set_query((!rest.is_empty()).then_some(rest.as_str()));
+    }
+    let Some(source) = campaign::normalize(&form.source) else {
+        return Ok((StatusCode::BAD_REQUEST, "A share link needs a source").into_response());
+    };
+    let campaign = Campaign {
+        source,
+        medium: campaign::normalize(&form.medium),
+        name: campaign::normalize(&form.campaign),
+    };
+    let link = campaign::tag(url, &campaign);
+    Ok(HtmlTemplate(ShareLinkTemplate { link, campaign }).into_response())
+}
@@ -1,9 +1,12 @@
 //! User management (Phase CC, three-way since CZ): list users and move them
 //! between Registered / Family / Admin, or delete. Admin-gated by the `/admin`
 //! nest's `require_admin` layer. The last admin is protected from demote/delete
-//! (no lockout); role + delete take effect immediately on a live session via
-//! the `refresh_session_role` middleware. `Anonymous` is a sentinel, never an
-//! assignable role — the handler rejects it.
+//! (no lockout). A role change or delete signs the user out everywhere (their
+//! sessions are revoked, so the next sign-in starts clean at the new role); the
+//! `refresh_session_role` middleware still re-derives the role per request as
+//! the backstop. Each row also lists the user's live sessions with revoke-one /
+//! revoke-all. `Anonymous` is a sentinel, never an assignable role — the handler
+//! rejects it.
 
 use askama::Template;
 use axum::{
@@ -13,13 +16,25 @@ use axum::{
 };
 use http::StatusCode;
 use serde::Deserialize;
+use serde_json::json;
 use uuid::Uuid;
 
+use tower_sessions::Session;
+use tracing::info;
+
 use crate::{
-    db::dao::{roles::Role, users::UserDao},
+    db::dao::{roles::Role, user_sessions::UserSessionDao, users::UserDao},
     web::{
-        app_error::AppError, app_state::AppState, authentication_state::AuthenticationState,
-        features::top_bar::TopBar, html_template::HtmlTemplate, htmx_responses::htmx_refresh,
+        app_error::AppError,
+        app_state::AppState,
+        audit::{self, Actor},
+        authentication_state::AuthenticationState,
+        features::{
+            account::sessions::{self, RevokeForm, SessionView},
+            top_bar::TopBar,
+        },
+        html_template::HtmlTemplate,
+        htmx_responses::htmx_refresh,
         session::SessionData,
     },
 };
@@ -39,6 +54,8 @@ pub struct UserRow {
     pub role_targets: Vec<Role>,
     pub passkey_count: i64,
     pub api_key_count: i64,
+    /// Live sessions (checked against the store), most recent first.
+    pub sessions: Vec<SessionView>,
     /// This row is the currently logged-in admin (label "(you)").
     pub is_self: bool,
     /// Admin AND the only admin — protected from demote/delete (no lockout); the
@@ -68,32 +85,39 @@ pub struct UsersTemplate {
 
 pub async fn show_users(
     State(state): State<AppState>,
+    session: Session,
     session_data: SessionData,
 ) -> Result<Response, AppError> {
     let admin_count = UserDao::count_admins(&state.pool).await?;
     let me = session_data.auth_state.user().map(|u| u.id);
+    let current = session.id().map(|id| id.to_string());
+    // Only users with an indexed session pay for the per-session store check.
+    let with_sessions = UserSessionDao::counts_by_user(&state.pool).await?;
 
-    let users = UserDao::list_summaries(&state.pool)
-        .await?
-        .into_iter()
-        .map(|s| {
-            let is_last_admin = s.role == Role::Admin && admin_count <= 1;
-            UserRow {
-                is_self: Some(s.id) == me,
-                is_last_admin,
-                display_name: s.display_name,
-                id: s.id.to_string(),
-                role_targets: if is_last_admin {
-                    vec![]
-                } else {
-                    ASSIGNABLE_ROLES.into_iter().filter(|r| *r != s.role).collect()
-                },
-                role: s.role,
-                passkey_count: s.passkey_count,
-                api_key_count: s.api_key_count,
-            }
-        })
-        .collect();
+    let mut users = Vec::new();
+    for s in UserDao::list_summaries(&state.pool).await? {
+        let sessions = if with_sessions.iter().any(|(id, _)| *id == s.id.to_string()) {
+            sessions::live_sessions(&state, &s.id, current.as_deref()).await?
+        } else {
+            vec![]
+        };
+        let is_last_admin = s.role == Role::Admin && admin_count <= 1;
+        users.push(UserRow {
+            is_self: Some(s.id) == me,
+            is_last_admin,
+            display_name: s.display_name,
+            id: s.id.to_string(),
+            role_targets: if is_last_admin {
+                vec![]
+            } else {
+                ASSIGNABLE_ROLES.into_iter().filter(|r| *r != s.role).collect()
+            },
+            role: s.role,
+            passkey_count: s.passkey_count,
+            api_key_count: s.api_key_count,
+            sessions,
+        });
+    }
 
     let tmpl = UsersTemplate {
         top_bar: TopBar::create(&state.pool, "admin", session_data.auth_state.role()).await?,
@@ -109,11 +133,12 @@ pub struct RoleForm {
 }
 
 /// `POST /admin/users/{id}/role` — move a user between Registered / Family /
-/// Admin. Rejects `Anonymous` as a target (a sentinel, not an account level)
-/// and demoting the final admin (the UI already hides those actions; these are
-/// the defense-in-depth guards).
+/// Admin, then revoke all of their sessions. Rejects `Anonymous` as a target (a
+/// sentinel, not an account level) and demoting the final admin (the UI already
+/// hides those actions; these are the defense-in-depth guards).
 pub async fn set_user_role(
     State(state): State<AppState>,
+    actor: Actor,
     Path(id): Path<Uuid>,
     Form(form): Form<RoleForm>,
 ) -> Result<Response, AppError> {
@@ -133,13 +158,27 @@ pub async fn set_user_role(
     }
 
     UserDao::set_role(&state.pool, &id, form.role).await?;
+    audit::record(
+        &state.pool,
+        &actor,
+        "user.role",
+        &id.to_string(),
+        Some(json!({ "name": target.display_name, "role": target.role.to_string() })),
+        Some(json!({ "name": target.display_name, "role": form.role.to_string() })),
+    )
+    .await;
+    if target.role != form.role {
+        let n = sessions::revoke_user_sessions(&state, &id, None).await?;
+        info!("role change for {:?} revoked {n} session(s)", target.display_name);
+    }
     Ok(htmx_refresh())
 }
 
-/// `DELETE /admin/users/{id}` — delete a user (cascades their API keys).
-/// Rejects deleting the final admin.
+/// `DELETE /admin/users/{id}` — delete a user (cascades their API keys) and
+/// revoke their sessions. Rejects deleting the final admin.
 pub async fn delete_user(
     State(state): State<AppState>,
+    actor: Actor,
     Path(id): Path<Uuid>,
 ) -> Result<Response, AppError> {
     let Some(target) = UserDao::find_by_uuid(&state.pool, &id).await? else {
@@ -150,7 +189,49 @@ pub async fn delete_user(
         return Ok((StatusCode::CONFLICT, "Can't delete the last admin").into_response());
     }
 
+    // Revoke first: the delete cascades the session index rows away.
+    sessions::revoke_user_sessions(&state, &id, None).await?;
     UserDao::delete(&state.pool, &id).await?;
+    audit::record(
+        &state.pool,
+        &actor,
+        "user.delete",
+        &id.to_string(),
+        Some(json!({ "name": target.display_name, "role": target.role.to_string() })),
+        None,
+    )
+    .await;
+    Ok(htmx_refresh())
+}
+
+/// `POST /admin/users/{id}/sessions/revoke` — sign one of the user's sessions
+/// out. The session must be theirs (404 otherwise).
+pub async fn revoke_user_session(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+    Form(form): Form<RevokeForm>,
+) -> Result<Response, AppError> {
+    if !UserSessionDao::belongs_to(&state.pool, &form.session_id, &id).await? {
+        return Ok((StatusCode::NOT_FOUND, "No such session").into_response());
+    }
+    sessions::revoke_session(&state, &form.session_id).await?;
+    audit::record(&state.pool, &actor, "user.sessions_revoke", &id.to_string(), None, None).await;
+    Ok(htmx_refresh())
+}
+
+/// `POST /admin/users/{id}/sessions/revoke_all` — sign the user out everywhere
+/// (the acting admin's own session included, when it's their own row).
+pub async fn revoke_all_user_sessions(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let n = sessions::revoke_user_sessions(&state, &id, None).await?;
+    info!("admin revoked {n} session(s) for user {id}");
+    let after = json!({ "revoked": n });
+    audit::record(&state.pool, &actor, "user.sessions_revoke", &id.to_string(), None, Some(after))
+        .await;
     Ok(htmx_refresh())
 }
 
@@ -27,12 +27,13 @@ use crate::db::dao::greylist::GreylistDao;
 use crate::greylist::challenge::{
     derive_seed, mint_clearance, verify_answer, ChallengeParams, FRESHNESS_WINDOW,
 };
+use crate::retention::CLEARANCE_TTL_DAYS;
 use crate::web::app_error::AppError;
 use crate::web::app_state::AppState;
 
-/// The clearance cookie name + lifetime. Bearer token, NOT IP-bound (design doc).
+/// The clearance cookie name (lifetime: [`CLEARANCE_TTL_DAYS`]). Bearer token, NOT IP-bound
+/// (design doc).
 pub const CLEARANCE_COOKIE: &str = "hio_toll";
-pub const CLEARANCE_TTL_DAYS: i64 = 7;
 
 pub fn challenge_router() -> Router<AppState> {
     Router::new()
@@ -222,12 +223,18 @@ async fn verify_challenge(
     let ua = headers
         .get(header::USER_AGENT)
         .and_then(|v| v.to_str().ok());
-    if let Err(e) = GreylistDao::record_clearance(&state.pool, &ip, q.ms, None, ua).await {
+    // A fresh id per solve — what the clear-then-scan escalation revokes.
+    let mut id = [0u8; 16];
+    openssl::rand::rand_bytes(&mut id)?;
+    let token_id = URL_SAFE_NO_PAD.encode(id);
+    if let Err(e) =
+        GreylistDao::record_clearance(&state.pool, &ip, &token_id, q.ms, None, ua).await
+    {
         tracing::warn!("failed to record toll clearance for {ip}: {e}");
     }
 
     let expiry = now + CLEARANCE_TTL_DAYS * 86_400;
-    let cookie = mint_clearance(&ch.key, expiry)?;
+    let cookie = mint_clearance(&ch.key, expiry, &token_id)?;
     Ok(redirect(&redir, Some(&clearance_cookie(&cookie))))
 }
 
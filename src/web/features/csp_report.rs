@@ -0,0 +1,167 @@
+//! `POST /csp-report` — where browsers send Content-Security-Policy violations
+//! (see `web::middleware::csp`). Both wire formats arrive: the legacy `report-uri`
+//! body (`{"csp-report": {…}}`, `application/csp-report`) and the Reporting API's
+//! batched `report-to` array (`application/reports+json`). Each violation is
+//! scrubbed — URLs lose their query and fragment, text is truncated — and stored
+//! for `/admin/csp-reports`.
+//!
+//! Anonymous by necessity (any visitor's browser reports), so it's on the mutation
+//! layer's allowlist; the body cap, the per-batch cap and the DAO's row cap keep a
+//! flood from costing more than a few kilobytes each.
+
+use axum::{
+    Router,
+    body::Bytes,
+    extract::{DefaultBodyLimit, State},
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use http::StatusCode;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::db::dao::csp_reports::{CspReportDao, NewCspReport};
+use crate::web::app_state::AppState;
+
+/// Largest report body accepted.
+const MAX_BODY_BYTES: usize = 16 * 1024;
+
+/// Most violations stored from one Reporting API batch.
+const MAX_REPORTS_PER_BATCH: usize = 20;
+
+/// Longest stored text field, in characters.
+const MAX_FIELD_CHARS: usize = 512;
+
+pub fn csp_report_routes() -> Router<AppState> {
+    Router::new().route(
+        "/csp-report",
+        post(receive_report).layer(DefaultBodyLimit::max(MAX_BODY_BYTES)),
+    )
+}
+
+async fn receive_report(State(state): State<AppState>, body: Bytes) -> Response {
+    let Ok(json) = serde_json::from_slice::<Value>(&body) else {
+        return (StatusCode::BAD_REQUEST, "Not JSON").into_response();
+    };
+    let reports = parse_reports(&json);
+    if reports.is_empty() {
+        return (StatusCode::BAD_REQUEST, "No CSP violation in the body").into_response();
+    }
+    for report in &reports {
+        if let Err(e) = CspReportDao::record(&state.pool, report).await {
+            warn!("Storing a CSP report failed: {e:#}");
+        }
+    }
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// The violations in either wire format; anything unrecognised yields none.
+fn parse_reports(json: &Value) -> Vec<NewCspReport> {
+    if let Some(legacy) = json.get("csp-report") {
+        return vec![from_fields(legacy, Format::Legacy)];
+    }
+    json.as_array()
+        .map(|batch| {
+            batch
+                .iter()
+                .filter(|r| r["type"] == "csp-violation")
+                .filter_map(|r| r.get("body"))
+                .take(MAX_REPORTS_PER_BATCH)
+                .map(|body| from_fields(body, Format::ReportingApi))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    /// `report-uri`: kebab-case keys.
+    Legacy,
+    /// `report-to`: camelCase keys.
+    ReportingApi,
+}
+
+fn from_fields(body: &Value, format: Format) -> NewCspReport {
+    let field = |legacy: &str, api: &str| {
+        let key = match format {
+            Format::Legacy => legacy,
+            Format::ReportingApi => api,
+        };
+        body.get(key).and_then(Value::as_str).map(truncate)
+    };
+    let url = |legacy: &str, api: &str| field(legacy, api).map(|u| strip_query(&u));
+    let effective_directive = field("effective-directive", "effectiveDirective")
+        .or_else(|| field("violated-directive", "effectiveDirective"))
+        // Old reports name the whole directive value ("script-src 'self'").
+        .map(|d| d.split_whitespace().next().unwrap_or_default().to_string())
+        .unwrap_or_default();
+    let line_key = match format {
+        Format::Legacy => "line-number",
+        Format::ReportingApi => "lineNumber",
+    };
+    NewCspReport {
+        disposition: field("disposition", "disposition").unwrap_or_else(|| "enforce".into()),
+        document_uri: url("document-uri", "documentURL").unwrap_or_default(),
+        effective_directive,
+        blocked_uri: url("blocked-uri", "blockedURL").unwrap_or_default(),
+        source_file: url("source-file", "sourceFile"),
+        line_number: body.get(line_key).and_then(Value::as_i64),
+        sample: field("script-sample", "sample").filter(|s| !s.is_empty()),
+    }
+}
+
+/// `https://host/path?secret#frag` → `https://host/path`. Keywords like `inline`
+/// pass through unchanged.
+fn strip_query(url: &str) -> String {
+    url.split(['?', '#']).next().unwrap_or_default().to_string()
+}
+
+fn truncate(s: &str) -> String {
+    s.chars().take(MAX_FIELD_CHARS).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn legacy_report_is_scrubbed() {
+        let body = json!({ "csp-report": {
+            "document-uri": "https://hotchkiss.io/login/invite?token=hinv_secret#x",
+            "violated-directive": "script-src-elem 'nonce-abc'",
+            "blocked-uri": "inline",
+            "line-number": 12,
+            "disposition": "report",
+        }});
+        let reports = parse_reports(&body);
+        assert_eq!(reports.len(), 1);
+        let r = &reports[0];
+        assert_eq!(r.document_uri, "https://hotchkiss.io/login/invite");
+        assert_eq!(r.effective_directive, "script-src-elem");
+        assert_eq!(
+            (r.blocked_uri.as_str(), r.line_number),
+            ("inline", Some(12))
+        );
+        assert_eq!(r.disposition, "report");
+    }
+
+    #[test]
+    fn reporting_api_batch_keeps_only_csp_violations() {
+        let body = json!([
+            { "type": "csp-violation", "body": {
+                "documentURL": "https://hotchkiss.io/pages/a",
+                "effectiveDirective": "img-src",
+                "blockedURL": "https://cdn.example/x.png?sig=1",
+                "sample": "",
+            }},
+            { "type": "deprecation", "body": { "id": "x" } },
+        ]);
+        let reports = parse_reports(&body);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].blocked_uri, "https://cdn.example/x.png");
+        assert_eq!(reports[0].disposition, "enforce");
+        assert_eq!(reports[0].sample, None);
+        assert!(parse_reports(&json!({ "hello": 1 })).is_empty());
+    }
+}
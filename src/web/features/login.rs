@@ -1,8 +1,17 @@
 use crate::web::app_error::AppError;
 use crate::web::authentication_state::AuthenticationState;
 use crate::{
-    db::dao::{roles::Role, users::UserDao},
-    web::{app_state::AppState, html_template::HtmlTemplate, session::SessionData},
+    db::dao::{
+        invites::InviteDao,
+        passkeys::{cred_id_key, PasskeyMetaDao, DEFAULT_PASSKEY_LABEL},
+        roles::Role,
+        user_sessions::UserSessionDao,
+        users::UserDao,
+    },
+    web::{
+        app_state::AppState, html_template::HtmlTemplate,
+        middleware::require_step_up::mark_stepped_up, session::SessionData,
+    },
 };
 use anyhow::{anyhow, Context};
 use askama::Template;
@@ -22,7 +31,7 @@ use webauthn_rs::prelude::{
     DiscoverableKey, PublicKeyCredential, RegisterPublicKeyCredential, RequestChallengeResponse,
 };
 
-use super::top_bar::TopBar;
+use super::{recovery, top_bar::TopBar};
 
 pub fn login_router() -> Router<AppState> {
     Router::new()
@@ -33,6 +42,9 @@ pub fn login_router() -> Router<AppState> {
         .route("/start_register/{:display_name}", get(start_registration))
         .route("/finish_register", post(finish_registration))
         .route("/ceremony_error", post(ceremony_error))
+        // Lost every passkey: trade a recovery code for one new passkey.
+        .route("/recover", get(recovery::recover_page).post(recovery::redeem_recovery_code))
+        .route("/recover/start/{label}", get(recovery::start_recovery_registration))
         .route("/logout", get(logout))
 }
 
@@ -85,6 +97,9 @@ struct LoginTemplate {
     /// browser that blocked the passkey ceremony (JS off / htmx failed to load)
     /// lands instead of silently reloading.
     error: Option<String>,
+    /// The role a valid `?invite=` link lands the registrant at (banner only —
+    /// the ceremony re-checks the token itself).
+    invite_role: Option<Role>,
 }
 
 /// The single hard client requirement the ceremony can't work around: a name.
@@ -127,6 +142,21 @@ struct NextQuery {
     next: Option<String>,
 }
 
+/// Session key for the invite a registration ceremony is redeeming: set by
+/// `start_registration` from a valid `?invite=`, claimed by `finish_registration`.
+const INVITE_KEY: &str = "registration_invite";
+
+/// `?invite=<token>` on `/login` and `/login/start_register` — an admin-minted
+/// invite link (`/login?invite=…`). The ceremony JS forwards the page's query
+/// string to `start_register`, so the token rides along with no extra plumbing.
+#[derive(Deserialize, Default)]
+struct InviteQuery {
+    invite: Option<String>,
+}
+
+const INVITE_INVALID: &str =
+    "This invite link is invalid, has expired, or was already used — ask for a new one.";
+
 /// Stash a VALID `?next` into the session; invalid/absent values never write
 /// (an open-redirect string must not survive to the pop, and an absent param
 /// must not clobber a stash from an earlier hop of the same flow).
@@ -156,12 +186,21 @@ async fn login_page(
     session: Session,
     session_data: SessionData,
     Query(query): Query<NextQuery>,
+    Query(invite): Query<InviteQuery>,
 ) -> Result<HtmlTemplate<LoginTemplate>, AppError> {
     stash_next(&session, &query).await?;
+    let (invite_role, error) = match invite.invite.as_deref() {
+        None => (None, None),
+        Some(token) => match InviteDao::find_redeemable(&state.pool, token).await? {
+            Some((_, role)) => (Some(role), None),
+            None => (None, Some(INVITE_INVALID.to_string())),
+        },
+    };
     let template = LoginTemplate {
         top_bar: TopBar::create(&state.pool, "login", session_data.auth_state.role()).await?,
         auth_state: session_data.auth_state,
-        error: None,
+        error,
+        invite_role,
     };
 
     Ok(HtmlTemplate(template))
@@ -183,6 +222,7 @@ async fn js_required_page(
              load). Enable JavaScript and use a recent Safari (16.4+), Chrome, or Firefox."
                 .to_string(),
         ),
+        invite_role: None,
     };
 
     Ok(HtmlTemplate(template))
@@ -247,11 +287,19 @@ async fn finish_authentication(
 
             user.update(&state.pool).await?;
         }
+        // The /account page's "last used" column — best-effort, never fails a login.
+        if let Err(e) =
+            PasskeyMetaDao::touch_last_used(&state.pool, &cred_id_key(auth_result.cred_id())).await
+        {
+            warn!("passkey last_used stamp failed (non-fatal): {e}");
+        }
 
         debug!("Logged in {:#?}", user);
 
         session.cycle_id().await?;
         info!("login succeeded: {} user {:?}", user.role, user.display_name);
+        // The login assertion IS a fresh one — no immediate step-up re-prompt.
+        mark_stepped_up(&session, user.id).await?;
         session_data.auth_state = AuthenticationState::Authenticated(user);
         SessionData::update_session(&session, &session_data).await?;
 
@@ -275,6 +323,7 @@ async fn start_registration(
     Path(display_name): Path<String>,
     mut session_data: SessionData,
     Query(query): Query<NextQuery>,
+    Query(invite): Query<InviteQuery>,
 ) -> Result<Response, AppError> {
     stash_next(&session, &query).await?;
 
@@ -301,6 +350,25 @@ async fn start_registration(
             .into_response());
     }
 
+    // An invite is checked BEFORE the passkey is minted (a dead link is a clear
+    // 410, not a surprise Registered account) and only claimed at finish. No
+    // invite clears any stash from an abandoned invited attempt.
+    match invite.invite.as_deref() {
+        Some(token) => match InviteDao::find_redeemable(&state.pool, token).await? {
+            Some((invite_id, role)) => {
+                info!("registration for {display_name:?} is redeeming invite {invite_id} ({role})");
+                session.insert(INVITE_KEY, invite_id).await?;
+            }
+            None => {
+                info!("registration rejected: invite invalid, expired or used");
+                return Ok((StatusCode::GONE, INVITE_INVALID).into_response());
+            }
+        },
+        None => {
+            session.remove::<i64>(INVITE_KEY).await?;
+        }
+    }
+
     let user_unique_id = Uuid::new_v4();
 
     // Initiate a basic registration flow, allowing any cryptographic authenticator to proceed.
@@ -351,6 +419,23 @@ async fn finish_registration(
                 .into_response());
         };
 
+        // A recovery ceremony (`web::features::recovery`) registers against the
+        // EXISTING account its redeemed code unlocked: append, don't create.
+        if let Some(label) = recovery::take_grant(&session, &user.id).await? {
+            let Some(user) =
+                recovery::add_recovered_passkey(&state.pool, &user.id, passkey, &label).await?
+            else {
+                return Ok((StatusCode::GONE, "That account no longer exists.").into_response());
+            };
+            session.cycle_id().await?;
+            info!("recovery succeeded: {} user {:?}", user.role, user.display_name);
+            mark_stepped_up(&session, user.id).await?;
+            session_data.auth_state = AuthenticationState::Authenticated(user);
+            SessionData::update_session(&session, &session_data).await?;
+            return Ok(Redirect::to("/account").into_response());
+        }
+
+        let cred_id = cred_id_key(passkey.cred_id());
         user.keys = sqlx::types::Json(vec![passkey]);
         user.role = Role::Registered;
 
@@ -375,12 +460,29 @@ async fn finish_registration(
             }
             Err(e) => return Err(e.into()),
         }
+        PasskeyMetaDao::record(&state.pool, &user.id, &cred_id, DEFAULT_PASSKEY_LABEL).await?;
+
+        // Claim the invite now the user row exists. Losing the race (redeemed or
+        // revoked mid-ceremony) just leaves them Registered — the account is real.
+        if let Some(invite_id) = session.remove::<i64>(INVITE_KEY).await? {
+            match InviteDao::redeem(&state.pool, invite_id, &user.id).await? {
+                Some(role) => {
+                    UserDao::set_role(&state.pool, &user.id, role).await?;
+                    user.role = role;
+                }
+                None => warn!(
+                    "invite {invite_id} was no longer redeemable at finish; {:?} stays {}",
+                    user.display_name, user.role
+                ),
+            }
+        }
 
         session.cycle_id().await?;
         info!(
             "registration succeeded: new {} user {:?} ({})",
             user.role, user.display_name, user.id
         );
+        mark_stepped_up(&session, user.id).await?;
         session_data.auth_state = AuthenticationState::Authenticated(user);
 
         SessionData::update_session(&session, &session_data).await?;
@@ -396,9 +498,19 @@ async fn finish_registration(
     }
 }
 
-async fn logout(session: Session, mut session_data: SessionData) -> Result<Redirect, AppError> {
+async fn logout(
+    State(state): State<AppState>,
+    session: Session,
+    mut session_data: SessionData,
+) -> Result<Redirect, AppError> {
     debug!("Logging out {:#?}", session_data.auth_state);
     session_data.auth_state = AuthenticationState::Anonymous;
     SessionData::update_session(&session, &session_data).await?;
+    // Drop it from the /account session list now rather than at the next listing.
+    if let Some(id) = session.id()
+        && let Err(e) = UserSessionDao::delete(&state.pool, &id.to_string()).await
+    {
+        warn!("user_sessions delete on logout failed: {e}");
+    }
     Ok(Redirect::to("/"))
 }
@@ -36,10 +36,12 @@ use rmcp::{
 };
 use sqlx::types::chrono::{DateTime, Utc};
 
+use crate::db::dao::audit_log::{AuditFilter, AuditLogDao, MAX_AUDIT_ROWS};
 use crate::db::dao::content_pages::ContentPageDao;
 use crate::db::dao::media::MediaDao;
 use crate::db::dao::roles::Role;
 use crate::web::app_state::AppState;
+use crate::web::audit::Actor;
 use crate::web::features::pages::write::{self, PageUpdate, PageWriteError, WrittenPage};
 use crate::web::session::SessionData;
 use crate::web::util::category;
@@ -162,6 +164,51 @@ pub struct MediaSummary {
     pub min_role: Option<String>,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ListAuditLogParams {
+    /// Action prefix, e.g. "page." for every page write or "user.role" for one verb.
+    #[serde(default)]
+    pub action: Option<String>,
+    /// Exact actor display name.
+    #[serde(default)]
+    pub actor: Option<String>,
+    /// "session" / "api_key" / "mcp".
+    #[serde(default)]
+    pub channel: Option<String>,
+    /// Substring of the target (a page path, media ref, user id, key id, IP).
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Most rows to return, newest first (default 50, max 500).
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, serde::Serialize, schemars::JsonSchema)]
+pub struct AuditEntry {
+    pub id: i64,
+    /// When (RFC3339).
+    pub ts: String,
+    /// The actor's display name when the change was made.
+    pub actor: Option<String>,
+    /// How the actor authenticated: session / api_key / mcp.
+    pub channel: String,
+    /// The API key used, for api_key and mcp rows.
+    pub api_key_id: Option<i64>,
+    pub action: String,
+    pub target: String,
+    /// Summary of the entity before the change (null for a create).
+    pub before: Option<serde_json::Value>,
+    /// Summary after the change (null for a delete).
+    pub after: Option<serde_json::Value>,
+}
+
+/// `list_audit_log` output — object-wrapped like `ListPagesResult`.
+#[derive(Debug, serde::Serialize, schemars::JsonSchema)]
+pub struct ListAuditLogResult {
+    pub entries: Vec<AuditEntry>,
+}
+
 /// `list_pages` output — object-wrapped because the MCP spec requires a tool's
 /// outputSchema ROOT to be an object, not a bare array (a `Json<Vec<_>>` panics at
 /// router construction).
@@ -226,15 +273,6 @@ pub struct UpdatePageParams {
     pub cover_ref: Option<String>,
 }
 
-#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
-#[serde(deny_unknown_fields)]
-pub struct DeletePageParams {
-    /// The page's tree path.
-    pub path: String,
-    /// Must be true — delete is destructive.
-    pub confirm: bool,
-}
-
 /// A create/update tool's result — the page's identity after the write.
 #[derive(Debug, serde::Serialize, schemars::JsonSchema)]
 pub struct PageWriteResult {
@@ -248,11 +286,6 @@ pub struct PageWriteResult {
     pub featured: bool,
 }
 
-#[derive(Debug, serde::Serialize, schemars::JsonSchema)]
-pub struct DeleteResult {
-    pub deleted: String,
-}
-
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct PagePathParam {
@@ -320,6 +353,7 @@ fn map_write_err(e: PageWriteError) -> ErrorData {
 /// cover_ref: absent = keep the current cover, "" = clear, a ref = set.
 async fn apply_page_update(
     state: &AppState,
+    actor: &Actor,
     path: &[&str],
     f: WriteFields,
 ) -> Result<PageWriteResult, ErrorData> {
@@ -343,7 +377,7 @@ async fn apply_page_update(
         min_role: f.min_role,
         cover_ref,
     };
-    let w = write::update_page(&state.pool, &state.site_host, path, input)
+    let w = write::update_page(&state.pool, actor, &state.site_host, path, input)
         .await
         .map_err(map_write_err)?;
     Ok(write_result(w))
@@ -523,13 +557,60 @@ impl McpServer {
         Ok(Json(ListMediaResult { media: out }))
     }
 
+    #[tool(
+        description = "Read the audit log of privileged mutations (page writes, media, users/roles, API keys, invites, greylist), newest first. Each entry names the actor, how they authenticated (session / api_key / mcp), the action, its target and before/after summaries. Filters are optional. Admin-only."
+    )]
+    async fn list_audit_log(
+        &self,
+        Extension(parts): Extension<Parts>,
+        Parameters(p): Parameters<ListAuditLogParams>,
+    ) -> Result<Json<ListAuditLogResult>, ErrorData> {
+        // Who-did-what is admin information whatever tier /mcp is opened to later
+        // (the same belt-and-suspenders as list_media).
+        if viewer_role(&parts) != Role::Admin {
+            return Err(ErrorData::invalid_request(
+                "reading the audit log requires the Admin role",
+                None,
+            ));
+        }
+        let nonempty =
+            |v: Option<String>| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        let filter = AuditFilter {
+            action: nonempty(p.action),
+            actor: nonempty(p.actor),
+            channel: nonempty(p.channel),
+            target: nonempty(p.target),
+        };
+        let limit = p.limit.unwrap_or(50).clamp(1, MAX_AUDIT_ROWS);
+        let parse = |v: Option<String>| v.and_then(|s| serde_json::from_str(&s).ok());
+        let entries = AuditLogDao::list(&self.state.pool, &filter, limit)
+            .await
+            .map_err(internal)?
+            .into_iter()
+            .map(|r| AuditEntry {
+                id: r.id,
+                ts: r.ts.to_rfc3339(),
+                actor: r.actor_name,
+                channel: r.channel,
+                api_key_id: r.api_key_id,
+                action: r.action,
+                target: r.target,
+                before: parse(r.before),
+                after: parse(r.after),
+            })
+            .collect();
+        Ok(Json(ListAuditLogResult { entries }))
+    }
+
     #[tool(
         description = "Create a page under parent_path (empty = top-level) from a title (the slug is derived). Optionally set markdown / min_role / creation_date / category / cover_ref in the same call. Inherits the parent's visibility gate unless min_role is given. Returns the new page's path + url."
     )]
     async fn create_page(
         &self,
+        Extension(parts): Extension<Parts>,
         Parameters(p): Parameters<CreatePageParams>,
     ) -> Result<Json<PageWriteResult>, ErrorData> {
+        let actor = Actor::mcp(&parts);
         // The write is authorized by the transport (/mcp is Admin-gated); the tool
         // reuses the same PageWrite service the editor does, so slug / link-rewrite /
         // min_role / inherit-on-create policy is identical.
@@ -540,7 +621,7 @@ impl McpServer {
             .filter(|s| !s.is_empty())
             .map(|s| s.split('/').collect())
             .unwrap_or_default();
-        let created = write::create_page(&self.state.pool, &parent, &p.title)
+        let created = write::create_page(&self.state.pool, &actor, &parent, &p.title)
             .await
             .map_err(map_write_err)?;
 
@@ -562,7 +643,7 @@ impl McpServer {
             creation_date: p.creation_date,
             cover_ref: p.cover_ref,
         };
-        Ok(Json(apply_page_update(&self.state, &segs, fields).await?))
+        Ok(Json(apply_page_update(&self.state, &actor, &segs, fields).await?))
     }
 
     #[tool(
@@ -570,8 +651,10 @@ impl McpServer {
     )]
     async fn update_page(
         &self,
+        Extension(parts): Extension<Parts>,
         Parameters(p): Parameters<UpdatePageParams>,
     ) -> Result<Json<PageWriteResult>, ErrorData> {
+        let actor = Actor::mcp(&parts);
         let segs: Vec<&str> = p.path.split('/').filter(|s| !s.is_empty()).collect();
         let fields = WriteFields {
             title: p.title,
@@ -581,37 +664,7 @@ impl McpServer {
             creation_date: p.creation_date,
             cover_ref: p.cover_ref,
         };
-        Ok(Json(apply_page_update(&self.state, &segs, fields).await?))
-    }
-
-    #[tool(
-        description = "Delete a page by path. Requires confirm=true (destructive). Special pages (blog / projects / resume / library) cannot be deleted."
-    )]
-    async fn delete_page(
-        &self,
-        Parameters(DeletePageParams { path, confirm }): Parameters<DeletePageParams>,
-    ) -> Result<Json<DeleteResult>, ErrorData> {
-        if !confirm {
-            return Err(ErrorData::invalid_params(
-                "set confirm=true to delete a page",
-                None,
-            ));
-        }
-        let segs: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
-        let chain = ContentPageDao::find_by_path(&self.state.pool, &segs)
-            .await
-            .map_err(internal)?;
-        let lp = chain
-            .last()
-            .ok_or_else(|| ErrorData::resource_not_found("page not found", None))?;
-        if lp.special_page {
-            return Err(ErrorData::invalid_params(
-                "special pages cannot be deleted",
-                None,
-            ));
-        }
-        lp.delete(&self.state.pool).await.map_err(internal)?;
-        Ok(Json(DeleteResult { deleted: segs.join("/") }))
+        Ok(Json(apply_page_update(&self.state, &actor, &segs, fields).await?))
     }
 
     #[tool(
@@ -706,9 +759,11 @@ impl ServerHandler for McpServer {
         info.server_info.title = Some("hotchkiss.io publishing".to_string());
         info.capabilities = ServerCapabilities::builder().enable_tools().build();
         info.instructions = Some(
-            "hotchkiss.io publishing server. Read: list_pages, get_page, list_media. Write: \
-             create_page, update_page, delete_page. All Admin-gated; reads honor the visibility \
-             gate, and create/update take a min_role to gate content."
+            "hotchkiss.io publishing server. Read: list_pages, get_page, list_media, \
+             list_audit_log. Write: create_page, update_page. All Admin-gated; reads honor the \
+             visibility gate, and create/update take a min_role to gate content. Every write is \
+             recorded in the audit log. Deleting a page needs a passkey confirmation, so it's \
+             done in the browser editor, not here."
                 .to_string(),
         );
         info
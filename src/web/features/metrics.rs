@@ -0,0 +1,271 @@
+//! `GET /metrics` — the Prometheus scrape endpoint (text format 0.0.4). Admin-only like
+//! `/admin/*`, or open to a scraper presenting `metrics_token` as a bearer token: a scrape
+//! job can't do a passkey ceremony, and an admin API key would hand it write access too.
+//!
+//! Request counts, latency and the timed tasks' last runs come from `crate::metrics`;
+//! everything else is read at scrape time — a few cheap queries and one statfs per media
+//! root — so the numbers are never staler than the scrape.
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, header},
+    response::{IntoResponse, Response},
+};
+use sqlx::SqlitePool;
+use tracing::warn;
+
+use crate::coordinator::acme::certificate_loader::earliest_expiry;
+use crate::db::dao::certificate::CertificateDao;
+use crate::metrics::{CONTENT_TYPE, Exposition, TASK_DEAD_LINK_SCAN};
+use crate::web::{
+    app_error::AppError,
+    app_state::AppState,
+    error_page::{forbidden_response, unauthorized_response},
+    session::SessionData,
+};
+
+pub async fn show_metrics(
+    State(state): State<AppState>,
+    session_data: SessionData,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let scraper = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer "))
+        .is_some_and(|token| state.metrics.accepts_token(token));
+    if !scraper && !session_data.auth_state.is_admin() {
+        return Ok(if session_data.auth_state.is_authenticated() {
+            forbidden_response(&headers)
+        } else {
+            unauthorized_response(&headers)
+        });
+    }
+
+    let mut out = Exposition::new();
+    out.family("build_info", "gauge", "The running build; always 1.");
+    out.sample("build_info", &[("version", env!("CARGO_PKG_VERSION"))], 1.0);
+    state.metrics.write_requests(&mut out);
+    write_tasks(&mut out, &state);
+    write_pool(&mut out, &state.pool);
+    write_media_roots(&mut out, &state);
+    write_greylist(&mut out, &state);
+    write_certificates(&mut out, &state.pool).await?;
+    write_request_log(&mut out, &state);
+
+    Ok(([(header::CONTENT_TYPE, CONTENT_TYPE)], out.finish()).into_response())
+}
+
+/// The timed background tasks: the greylist sweep (recorded by its loop) and the dead-link
+/// scan (read off its shared scan state, which already keeps start and finish).
+fn write_tasks(out: &mut Exposition, state: &AppState) {
+    let recorded = state.metrics.tasks();
+    let mut last: Vec<(&str, f64, i64)> = recorded
+        .iter()
+        .map(|run| {
+            let seconds = run.last_duration.as_secs_f64();
+            (run.task, seconds, run.last_finished.timestamp())
+        })
+        .collect();
+    let scan = state.dead_links.status();
+    if let (Some(started), Some(finished)) = (scan.last_started, scan.last_finished)
+        && finished >= started
+    {
+        let seconds = (finished - started).num_milliseconds() as f64 / 1000.0;
+        last.push((TASK_DEAD_LINK_SCAN, seconds, finished.timestamp()));
+    }
+
+    out.family(
+        "task_last_duration_seconds",
+        "gauge",
+        "How long the task's last run took.",
+    );
+    for &(task, seconds, _) in &last {
+        out.sample("task_last_duration_seconds", &[("task", task)], seconds);
+    }
+    out.family(
+        "task_last_run_timestamp_seconds",
+        "gauge",
+        "When the task's last run finished (Unix time).",
+    );
+    for &(task, _, finished) in &last {
+        out.sample(
+            "task_last_run_timestamp_seconds",
+            &[("task", task)],
+            finished as f64,
+        );
+    }
+    out.family("task_runs_total", "counter", "Runs since startup.");
+    for run in &recorded {
+        out.sample("task_runs_total", &[("task", run.task)], run.runs as f64);
+    }
+    out.family(
+        "task_failures_total",
+        "counter",
+        "Failed runs since startup.",
+    );
+    for run in &recorded {
+        out.sample(
+            "task_failures_total",
+            &[("task", run.task)],
+            run.failures as f64,
+        );
+    }
+    out.family("task_running", "gauge", "1 while a run is in flight.");
+    out.sample(
+        "task_running",
+        &[("task", TASK_DEAD_LINK_SCAN)],
+        f64::from(u8::from(scan.running)),
+    );
+}
+
+fn write_pool(out: &mut Exposition, pool: &SqlitePool) {
+    let size = pool.size();
+    let idle = u32::try_from(pool.num_idle()).unwrap_or(size);
+    out.family(
+        "db_pool_connections",
+        "gauge",
+        "Open SQLite pool connections, by state.",
+    );
+    out.sample("db_pool_connections", &[("state", "idle")], f64::from(idle));
+    out.sample(
+        "db_pool_connections",
+        &[("state", "in_use")],
+        f64::from(size.saturating_sub(idle)),
+    );
+    out.gauge(
+        "db_pool_max_connections",
+        "The pool's connection cap.",
+        f64::from(pool.options().get_max_connections()),
+    );
+}
+
+/// The media roots as the admin media panel shows them. A root that can't be statted
+/// (unmounted) reports `present 0` and no space samples.
+fn write_media_roots(out: &mut Exposition, state: &AppState) {
+    let roots = state.media_store.roots_status();
+    let labelled: Vec<(String, &_)> = roots
+        .iter()
+        .map(|r| (r.path.display().to_string(), r))
+        .collect();
+
+    out.family(
+        "media_root_present",
+        "gauge",
+        "1 if the media root can be statted.",
+    );
+    for (root, r) in &labelled {
+        let present = f64::from(u8::from(r.free_bytes.is_some()));
+        out.sample("media_root_present", &[("root", root.as_str())], present);
+    }
+    out.family(
+        "media_root_free_bytes",
+        "gauge",
+        "Free space on the media root's volume.",
+    );
+    for (root, r) in &labelled {
+        if let Some(free) = r.free_bytes {
+            out.sample(
+                "media_root_free_bytes",
+                &[("root", root.as_str())],
+                free as f64,
+            );
+        }
+    }
+    out.family(
+        "media_root_size_bytes",
+        "gauge",
+        "Size of the media root's volume.",
+    );
+    for (root, r) in &labelled {
+        if let Some(total) = r.total_bytes {
+            out.sample(
+                "media_root_size_bytes",
+                &[("root", root.as_str())],
+                total as f64,
+            );
+        }
+    }
+    out.family(
+        "media_root_write_target",
+        "gauge",
+        "1 for the root the next upload lands on.",
+    );
+    for (root, r) in &labelled {
+        let target = f64::from(u8::from(r.is_write_target));
+        out.sample(
+            "media_root_write_target",
+            &[("root", root.as_str())],
+            target,
+        );
+    }
+}
+
+fn write_greylist(out: &mut Exposition, state: &AppState) {
+    let counts = state.greylist.counts();
+    out.family(
+        "greylist_entries",
+        "gauge",
+        "Active greylist entries, by kind.",
+    );
+    out.sample("greylist_entries", &[("kind", "ip")], counts.ips as f64);
+    out.sample(
+        "greylist_entries",
+        &[("kind", "prefix")],
+        counts.prefixes as f64,
+    );
+    out.gauge(
+        "greylist_blocked_ips",
+        "IPs escalated past the toll to a bare 403.",
+        counts.blocked as f64,
+    );
+    out.gauge(
+        "greylist_revoked_clearances",
+        "Clearance tokens revoked by escalation.",
+        counts.revoked as f64,
+    );
+}
+
+/// The stored ACME certificates' expiry. A chain that won't parse is left out (and warned
+/// about) rather than failing the scrape.
+async fn write_certificates(out: &mut Exposition, pool: &SqlitePool) -> Result<(), AppError> {
+    out.family(
+        "tls_certificate_expiry_timestamp_seconds",
+        "gauge",
+        "When the domain's stored certificate chain expires (Unix time).",
+    );
+    for (domain, chain) in CertificateDao::chains(pool).await? {
+        match earliest_expiry(&chain) {
+            Ok(Some(not_after)) => out.sample(
+                "tls_certificate_expiry_timestamp_seconds",
+                &[("domain", domain.as_str())],
+                not_after as f64,
+            ),
+            Ok(None) => {}
+            Err(e) => warn!("metrics: couldn't read the certificate for {domain}: {e:?}"),
+        }
+    }
+    Ok(())
+}
+
+/// The request-log writer's queue — the one background backlog that can build up.
+fn write_request_log(out: &mut Exposition, state: &AppState) {
+    let counters = state.request_log.counters();
+    out.gauge(
+        "request_log_queued",
+        "Rows waiting for the request_log writer.",
+        counters.queued as f64,
+    );
+    out.family(
+        "request_log_flushed_total",
+        "counter",
+        "Rows committed to request_log.",
+    );
+    out.sample("request_log_flushed_total", &[], counters.flushed as f64);
+    out.family(
+        "request_log_dropped_total",
+        "counter",
+        "Rows shed because the writer's queue was full.",
+    );
+    out.sample("request_log_dropped_total", &[], counters.dropped as f64);
+}
@@ -1,7 +1,9 @@
+pub mod account;
 pub mod admin;
 pub mod blog;
 pub mod challenge;
 pub mod child_index;
+pub mod csp_report;
 pub mod diagram;
 pub mod feed;
 pub mod home;
@@ -11,10 +13,14 @@ pub mod login;
 pub mod mcp;
 pub mod media;
 pub mod media_select;
+pub mod metrics;
 pub mod not_found;
+pub mod oidc;
 pub mod pages;
+pub mod recovery;
 pub mod resume;
 pub mod seo;
+pub mod step_up;
 #[cfg(debug_assertions)]
 pub mod test_login;
 pub mod three_d;
@@ -0,0 +1,493 @@
+//! A minimal OpenID Connect provider, so the household's other self-hosted apps
+//! sign in with the passkeys people already have here. Authorization code flow
+//! only, PKCE (S256) mandatory, confidential clients registered by an admin at
+//! `/admin/oidc-clients`:
+//!
+//! - `GET /.well-known/openid-configuration` + `GET /oidc/jwks` — discovery.
+//! - `GET /oidc/authorize` — validates the request, sends an anonymous visitor
+//!   through `/login?next=…`, then shows the consent screen (or, once allowed,
+//!   skips it) and redirects back with a single-use code.
+//! - `POST /oidc/authorize` — the consent screen's Allow / Deny.
+//! - `POST /oidc/token` — code + verifier + client secret → ID and access tokens.
+//! - `GET /oidc/userinfo` — the access token's claims, read live from the user row.
+//!
+//! Tokens are RS256 JWTs (see [`signing`]); the ID token carries the site `Role`
+//! as a `role` claim so a relying party can map the household tiers onto its own.
+
+pub mod signing;
+
+use askama::Template;
+use axum::{
+    Form, Json, Router,
+    extract::{OriginalUri, Query, State},
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use http::{
+    HeaderMap, HeaderValue, StatusCode,
+    header::{AUTHORIZATION, CACHE_CONTROL, WWW_AUTHENTICATE},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::types::chrono::Utc;
+use tower_sessions::Session;
+use tracing::info;
+use url::Url;
+use uuid::Uuid;
+
+use crate::db::dao::{
+    oidc::{OidcClientDao, OidcCodeDao},
+    users::UserDao,
+};
+use crate::web::{
+    app_error::AppError, app_state::AppState, authentication_state::AuthenticationState,
+    features::top_bar::TopBar, html_template::HtmlTemplate, session::SessionData,
+    util::urlencode::urlencode,
+};
+use signing::{ACCESS_TOKEN_TYP, ALG, ID_TOKEN_TYP, SigningKey, pkce_s256_matches};
+
+/// Lifetime of the ID and access tokens.
+const TOKEN_TTL_SECS: i64 = 3600;
+
+/// The scopes this provider knows; anything else in a request is ignored.
+const SUPPORTED_SCOPES: [&str; 2] = ["openid", "profile"];
+
+/// Session key for the validated request the consent screen is answering.
+const PENDING_AUTHORIZE_KEY: &str = "oidc_pending";
+
+pub fn oidc_router() -> Router<AppState> {
+    Router::new()
+        .route("/authorize", get(authorize).post(decide))
+        .route("/token", post(token))
+        .route("/userinfo", get(userinfo))
+        .route("/jwks", get(jwks))
+}
+
+/// The issuer: the site's public origin (the `iss` of every token).
+fn issuer(state: &AppState) -> String {
+    format!("https://{}", state.site_host)
+}
+
+/// `GET /.well-known/openid-configuration`.
+pub async fn discovery(State(state): State<AppState>) -> Json<Value> {
+    let iss = issuer(&state);
+    Json(json!({
+        "issuer": iss,
+        "authorization_endpoint": format!("{iss}/oidc/authorize"),
+        "token_endpoint": format!("{iss}/oidc/token"),
+        "userinfo_endpoint": format!("{iss}/oidc/userinfo"),
+        "jwks_uri": format!("{iss}/oidc/jwks"),
+        "response_types_supported": ["code"],
+        "grant_types_supported": ["authorization_code"],
+        "subject_types_supported": ["public"],
+        "id_token_signing_alg_values_supported": [ALG],
+        "scopes_supported": SUPPORTED_SCOPES,
+        "token_endpoint_auth_methods_supported": ["client_secret_basic", "client_secret_post"],
+        "code_challenge_methods_supported": ["S256"],
+        "claims_supported": [
+            "sub", "iss", "aud", "exp", "iat", "nonce", "name", "preferred_username", "role"
+        ],
+    }))
+}
+
+/// `GET /oidc/jwks` — the public half of the signing key.
+async fn jwks(State(state): State<AppState>) -> Result<Json<Value>, AppError> {
+    let key = SigningKey::load(&state.pool).await?;
+    Ok(Json(json!({ "keys": [key.jwk()?] })))
+}
+
+#[derive(Deserialize)]
+struct AuthorizeQuery {
+    response_type: Option<String>,
+    client_id: Option<String>,
+    redirect_uri: Option<String>,
+    scope: Option<String>,
+    state: Option<String>,
+    nonce: Option<String>,
+    code_challenge: Option<String>,
+    code_challenge_method: Option<String>,
+}
+
+/// A validated authorization request, parked in the session while the consent
+/// screen is up — the POST answers THIS, not whatever a form might resubmit.
+#[derive(Clone, Serialize, Deserialize)]
+struct PendingAuthorize {
+    client_id: String,
+    client_name: String,
+    redirect_uri: String,
+    /// The supported subset of the requested scopes, space-separated.
+    scope: String,
+    state: Option<String>,
+    nonce: Option<String>,
+    code_challenge: String,
+}
+
+#[derive(Template)]
+#[template(path = "oidc/consent.html")]
+struct ConsentTemplate {
+    top_bar: TopBar,
+    auth_state: AuthenticationState,
+    client_name: String,
+    /// Where the user will be sent back to — shown so a look-alike name can't hide it.
+    redirect_host: String,
+    share_profile: bool,
+}
+
+/// `GET /oidc/authorize`.
+async fn authorize(
+    State(state): State<AppState>,
+    session: Session,
+    session_data: SessionData,
+    OriginalUri(uri): OriginalUri,
+    Query(q): Query<AuthorizeQuery>,
+) -> Result<Response, AppError> {
+    let request = match validate(&state, q).await? {
+        Ok(request) => request,
+        Err(rejection) => return Ok(rejection),
+    };
+    let Some(user) = session_data.auth_state.user() else {
+        // Sign in (or register) first, then land straight back on this request.
+        let next = uri
+            .path_and_query()
+            .map_or("/oidc/authorize", |p| p.as_str());
+        return Ok(Redirect::to(&format!("/login?next={}", urlencode(next))).into_response());
+    };
+
+    if OidcClientDao::has_consent(&state.pool, &user.id, &request.client_id, &request.scope).await?
+    {
+        return issue_code(&state, &request, &user.id).await;
+    }
+
+    let template = ConsentTemplate {
+        top_bar: TopBar::create(&state.pool, "oidc", session_data.auth_state.role()).await?,
+        auth_state: session_data.auth_state.clone(),
+        client_name: request.client_name.clone(),
+        redirect_host: Url::parse(&request.redirect_uri)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+            .unwrap_or_default(),
+        share_profile: request.scope.split(' ').any(|s| s == "profile"),
+    };
+    session.insert(PENDING_AUTHORIZE_KEY, request).await?;
+    Ok(HtmlTemplate(template).into_response())
+}
+
+#[derive(Deserialize)]
+struct DecisionForm {
+    decision: String,
+}
+
+/// `POST /oidc/authorize` — Allow remembers the consent and issues a code; anything
+/// else sends the relying party `access_denied`.
+async fn decide(
+    State(state): State<AppState>,
+    session: Session,
+    session_data: SessionData,
+    Form(form): Form<DecisionForm>,
+) -> Result<Response, AppError> {
+    let Some(user) = session_data.auth_state.user() else {
+        return Ok((StatusCode::UNAUTHORIZED, "Sign in first.").into_response());
+    };
+    let Some(request) = session
+        .remove::<PendingAuthorize>(PENDING_AUTHORIZE_KEY)
+        .await?
+    else {
+        return Ok((StatusCode::BAD_REQUEST, "No sign-in request is waiting.").into_response());
+    };
+    // The client may have been deleted while the consent screen was up.
+    let still_registered = OidcClientDao::find_by_client_id(&state.pool, &request.client_id)
+        .await?
+        .is_some_and(|c| c.allows_redirect(&request.redirect_uri));
+    if !still_registered {
+        return Ok((StatusCode::BAD_REQUEST, "That app is no longer registered.").into_response());
+    }
+
+    if form.decision != "allow" {
+        info!("oidc: {} denied {}", user.display_name, request.client_name);
+        return Ok(redirect_with(&request, &[("error", "access_denied")]));
+    }
+    OidcClientDao::grant_consent(&state.pool, &user.id, &request.client_id, &request.scope).await?;
+    info!(
+        "oidc: {} allowed {} ({})",
+        user.display_name, request.client_name, request.scope
+    );
+    issue_code(&state, &request, &user.id).await
+}
+
+/// Check an authorization request. Until the client and redirect URI are known
+/// good, a problem is a plain 400 here — never a redirect to an unvetted URI;
+/// after that, errors go back to the relying party as `?error=`.
+async fn validate(
+    state: &AppState,
+    q: AuthorizeQuery,
+) -> Result<Result<PendingAuthorize, Response>, AppError> {
+    let client = match q.client_id.as_deref() {
+        Some(id) => OidcClientDao::find_by_client_id(&state.pool, id).await?,
+        None => None,
+    };
+    let Some(client) = client else {
+        return Ok(Err(
+            (StatusCode::BAD_REQUEST, "Unknown app (client_id).").into_response()
+        ));
+    };
+    let Some(redirect_uri) = q.redirect_uri.filter(|r| client.allows_redirect(r)) else {
+        return Ok(Err((
+            StatusCode::BAD_REQUEST,
+            "That redirect_uri isn't registered for this app.",
+        )
+            .into_response()));
+    };
+
+    let mut request = PendingAuthorize {
+        client_id: client.client_id,
+        client_name: client.name,
+        redirect_uri,
+        scope: String::new(),
+        state: q.state,
+        nonce: q.nonce,
+        code_challenge: String::new(),
+    };
+    let fail = |request: &PendingAuthorize, error: &str, description: &str| {
+        Ok(Err(redirect_with(
+            request,
+            &[("error", error), ("error_description", description)],
+        )))
+    };
+    if q.response_type.as_deref() != Some("code") {
+        return fail(
+            &request,
+            "unsupported_response_type",
+            "only the code flow is supported",
+        );
+    }
+    let requested = q.scope.unwrap_or_default();
+    let scopes: Vec<&str> = SUPPORTED_SCOPES
+        .into_iter()
+        .filter(|s| requested.split_whitespace().any(|r| r == *s))
+        .collect();
+    if !scopes.contains(&"openid") {
+        return fail(&request, "invalid_scope", "the openid scope is required");
+    }
+    request.scope = scopes.join(" ");
+    match (q.code_challenge, q.code_challenge_method.as_deref()) {
+        (Some(challenge), Some("S256")) if challenge.len() == 43 => {
+            request.code_challenge = challenge;
+        }
+        _ => return fail(&request, "invalid_request", "PKCE with S256 is required"),
+    }
+    Ok(Ok(request))
+}
+
+/// Mint a code for `user_id` and send the browser back to the relying party.
+async fn issue_code(
+    state: &AppState,
+    request: &PendingAuthorize,
+    user_id: &Uuid,
+) -> Result<Response, AppError> {
+    let code = OidcCodeDao {
+        client_id: request.client_id.clone(),
+        user_id: user_id.to_string(),
+        redirect_uri: request.redirect_uri.clone(),
+        code_challenge: request.code_challenge.clone(),
+        nonce: request.nonce.clone(),
+        scope: request.scope.clone(),
+    }
+    .create(&state.pool)
+    .await?;
+    Ok(redirect_with(request, &[("code", &code)]))
+}
+
+/// A 303 to the request's redirect URI with `params` (and the client's `state`,
+/// echoed) appended to its query.
+fn redirect_with(request: &PendingAuthorize, params: &[(&str, &str)]) -> Response {
+    let Ok(mut url) = Url::parse(&request.redirect_uri) else {
+        return (StatusCode::BAD_REQUEST, "Unusable redirect_uri.").into_response();
+    };
+    {
+        let mut query = url.query_pairs_mut();
+        for (k, v) in params {
+            query.append_pair(k, v);
+        }
+        if let Some(state) = &request.state {
+            query.append_pair("state", state);
+        }
+    }
+    Redirect::to(url.as_str()).into_response()
+}
+
+#[derive(Deserialize)]
+struct TokenForm {
+    grant_type: String,
+    code: Option<String>,
+    redirect_uri: Option<String>,
+    code_verifier: Option<String>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+}
+
+/// An RFC 6749 §5.2 error body; never cached.
+fn token_error(status: StatusCode, error: &str) -> Response {
+    (
+        status,
+        [(CACHE_CONTROL, "no-store")],
+        Json(json!({ "error": error })),
+    )
+        .into_response()
+}
+
+/// The client's `(id, secret)`, from HTTP Basic (`client_secret_basic`, each half
+/// form-urlencoded per RFC 6749 §2.3.1) or the form body (`client_secret_post`).
+fn client_credentials(headers: &HeaderMap, form: &TokenForm) -> Option<(String, String)> {
+    let basic = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Basic "))
+        .and_then(|b| STANDARD.decode(b.trim()).ok())
+        .and_then(|raw| String::from_utf8(raw).ok());
+    if let Some(basic) = basic {
+        let (id, secret) = basic.split_once(':')?;
+        let decode = |s: &str| -> String {
+            url::form_urlencoded::parse(s.as_bytes())
+                .map(|(k, _)| k)
+                .collect()
+        };
+        return Some((decode(id), decode(secret)));
+    }
+    Some((form.client_id.clone()?, form.client_secret.clone()?))
+}
+
+/// `POST /oidc/token` — the authorization-code exchange. Anonymous at the
+/// mutation layer: the client authenticates itself here, with its secret.
+async fn token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(form): Form<TokenForm>,
+) -> Result<Response, AppError> {
+    let Some((client_id, secret)) = client_credentials(&headers, &form) else {
+        return Ok(token_error(StatusCode::UNAUTHORIZED, "invalid_client"));
+    };
+    let Some(client) = OidcClientDao::authenticate(&state.pool, &client_id, &secret).await? else {
+        return Ok(token_error(StatusCode::UNAUTHORIZED, "invalid_client"));
+    };
+    if form.grant_type != "authorization_code" {
+        return Ok(token_error(
+            StatusCode::BAD_REQUEST,
+            "unsupported_grant_type",
+        ));
+    }
+    let Some(code) = form.code.as_deref() else {
+        return Ok(token_error(StatusCode::BAD_REQUEST, "invalid_request"));
+    };
+    // Redeeming burns the code whatever follows: a wrong verifier or redirect URI
+    // doesn't leave it open for another guess.
+    let Some(grant) = OidcCodeDao::redeem(&state.pool, code, &client.client_id).await? else {
+        return Ok(token_error(StatusCode::BAD_REQUEST, "invalid_grant"));
+    };
+    let verified = form.redirect_uri.as_deref() == Some(grant.redirect_uri.as_str())
+        && form
+            .code_verifier
+            .as_deref()
+            .is_some_and(|v| pkce_s256_matches(v, &grant.code_challenge));
+    let user = match Uuid::parse_str(&grant.user_id) {
+        Ok(id) => UserDao::find_by_uuid(&state.pool, &id).await?,
+        Err(_) => None,
+    };
+    let (true, Some(user)) = (verified, user) else {
+        return Ok(token_error(StatusCode::BAD_REQUEST, "invalid_grant"));
+    };
+
+    let key = SigningKey::load(&state.pool).await?;
+    let iss = issuer(&state);
+    let now = Utc::now().timestamp();
+    let mut id_claims = json!({
+        "iss": iss,
+        "sub": user.id.to_string(),
+        "aud": client.client_id,
+        "iat": now,
+        "exp": now + TOKEN_TTL_SECS,
+        "role": user.role.to_string(),
+    });
+    if let Some(nonce) = &grant.nonce {
+        id_claims["nonce"] = json!(nonce);
+    }
+    if grant.scope.split(' ').any(|s| s == "profile") {
+        id_claims["name"] = json!(user.display_name);
+        id_claims["preferred_username"] = json!(user.display_name);
+    }
+    let access_claims = json!({
+        "iss": iss,
+        "sub": user.id.to_string(),
+        "aud": client.client_id,
+        "iat": now,
+        "exp": now + TOKEN_TTL_SECS,
+        "scope": grant.scope,
+        "token_use": "access",
+    });
+    info!(
+        "oidc: issued tokens for {} to {}",
+        user.display_name, client.name
+    );
+
+    let body = json!({
+        "access_token": key.sign(ACCESS_TOKEN_TYP, &access_claims)?,
+        "token_type": "Bearer",
+        "expires_in": TOKEN_TTL_SECS,
+        "id_token": key.sign(ID_TOKEN_TYP, &id_claims)?,
+        "scope": grant.scope,
+    });
+    Ok(([(CACHE_CONTROL, "no-store")], Json(body)).into_response())
+}
+
+/// `GET /oidc/userinfo` — claims for the access token's user, read live (a role
+/// change, a deleted account or an unregistered client shows up here before the
+/// token expires). Only an access token will do: an ID token is typed apart.
+async fn userinfo(State(state): State<AppState>, headers: HeaderMap) -> Result<Response, AppError> {
+    let unauthorized = || {
+        (
+            StatusCode::UNAUTHORIZED,
+            [(
+                WWW_AUTHENTICATE,
+                HeaderValue::from_static(r#"Bearer error="invalid_token""#),
+            )],
+        )
+            .into_response()
+    };
+    let Some(token) = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+    else {
+        return Ok(unauthorized());
+    };
+    let key = SigningKey::load(&state.pool).await?;
+    let Ok(claims) = key.verify(ACCESS_TOKEN_TYP, token) else {
+        return Ok(unauthorized());
+    };
+    let live = claims["token_use"] == "access"
+        && claims["iss"] == issuer(&state).as_str()
+        && claims["exp"]
+            .as_i64()
+            .is_some_and(|exp| exp > Utc::now().timestamp());
+    let client = match claims["aud"].as_str() {
+        Some(aud) if live => OidcClientDao::find_by_client_id(&state.pool, aud).await?,
+        _ => None,
+    };
+    let user = match claims["sub"].as_str().map(Uuid::parse_str) {
+        Some(Ok(id)) if client.is_some() => UserDao::find_by_uuid(&state.pool, &id).await?,
+        _ => None,
+    };
+    let Some(user) = user else {
+        return Ok(unauthorized());
+    };
+
+    let mut body = json!({ "sub": user.id.to_string(), "role": user.role.to_string() });
+    if claims["scope"]
+        .as_str()
+        .is_some_and(|s| s.split(' ').any(|s| s == "profile"))
+    {
+        body["name"] = json!(user.display_name);
+        body["preferred_username"] = json!(user.display_name);
+    }
+    Ok(([(CACHE_CONTROL, "no-store")], Json(body)).into_response())
+}
@@ -0,0 +1,167 @@
+//! The provider's token signing: an RS256 key kept in `crypto_keys` (id 4, PKCS#8
+//! DER, minted on first use), the JWKS entry relying parties verify with, and the
+//! compact-JWS encode/verify the ID and access tokens ride in. Hand-rolled on
+//! openssl rather than a JWT crate — one algorithm, one key, a few dozen lines.
+
+use anyhow::{Context, Result, bail};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use openssl::{
+    hash::MessageDigest,
+    pkey::{PKey, Private},
+    rsa::Rsa,
+    sha::sha256,
+    sign::{Signer, Verifier},
+};
+use serde_json::{Value, json};
+use sqlx::SqlitePool;
+
+use crate::db::dao::crypto_key::CryptoKey;
+
+/// CryptoKey row id for the OIDC signing key (1 = session signing, 2 = media URL
+/// key, 3 = API-key pepper, 4 = this).
+const OIDC_SIGNING_KEY_ID: i64 = 4;
+
+/// The one signing algorithm — RS256 is the one every relying party supports.
+pub const ALG: &str = "RS256";
+
+/// The JWS `typ` of an ID token.
+pub const ID_TOKEN_TYP: &str = "JWT";
+
+/// The JWS `typ` of an access token (RFC 9068), so neither kind passes for the other.
+pub const ACCESS_TOKEN_TYP: &str = "at+jwt";
+
+pub struct SigningKey {
+    pkey: PKey<Private>,
+    /// JWKS key id: a digest of the public key, so a future rotation changes it.
+    pub kid: String,
+}
+
+impl SigningKey {
+    /// Load the provider key, minting a 2048-bit RSA key the first time.
+    pub async fn load(pool: &SqlitePool) -> Result<SigningKey> {
+        let stored = CryptoKey::get_or_create_with(pool, OIDC_SIGNING_KEY_ID, generate).await?;
+        Self::from_pkcs8(&stored.key_value)
+    }
+
+    fn from_pkcs8(der: &[u8]) -> Result<SigningKey> {
+        let pkey = PKey::private_key_from_pkcs8(der).context("stored OIDC key is not PKCS#8")?;
+        let public = pkey
+            .public_key_to_der()
+            .context("encoding the OIDC public key")?;
+        let kid = URL_SAFE_NO_PAD.encode(&sha256(&public)[..12]);
+        Ok(SigningKey { pkey, kid })
+    }
+
+    /// This key's JWKS entry (public half only).
+    pub fn jwk(&self) -> Result<Value> {
+        let rsa = self.pkey.rsa().context("OIDC key is not RSA")?;
+        Ok(json!({
+            "kty": "RSA",
+            "use": "sig",
+            "alg": ALG,
+            "kid": self.kid,
+            "n": URL_SAFE_NO_PAD.encode(rsa.n().to_vec()),
+            "e": URL_SAFE_NO_PAD.encode(rsa.e().to_vec()),
+        }))
+    }
+
+    /// `claims` as a compact JWS of type `typ`: `header.payload.signature`, base64url.
+    pub fn sign(&self, typ: &str, claims: &Value) -> Result<String> {
+        let header = json!({ "alg": ALG, "typ": typ, "kid": self.kid });
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let mut signer = Signer::new(MessageDigest::sha256(), &self.pkey)?;
+        signer.update(signing_input.as_bytes())?;
+        let signature = signer.sign_to_vec()?;
+        Ok(format!(
+            "{signing_input}.{}",
+            URL_SAFE_NO_PAD.encode(signature)
+        ))
+    }
+
+    /// The claims of a `typ` token THIS key signed, or an error for anything else
+    /// (malformed, another type, alg or kid, bad signature). Expiry and audience
+    /// are the caller's to check.
+    pub fn verify(&self, typ: &str, token: &str) -> Result<Value> {
+        let mut parts = token.split('.');
+        let (Some(header), Some(payload), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            bail!("not a compact JWS");
+        };
+        let header: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header)?)?;
+        if header["alg"] != ALG || header["typ"] != typ || header["kid"] != self.kid.as_str() {
+            bail!("unexpected JWS header");
+        }
+        let signature = URL_SAFE_NO_PAD.decode(signature)?;
+        let mut verifier = Verifier::new(MessageDigest::sha256(), &self.pkey)?;
+        verifier.update(token[..token.rfind('.').unwrap_or(0)].as_bytes())?;
+        if !verifier.verify(&signature)? {
+            bail!("bad JWS signature");
+        }
+        Ok(serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload)?)?)
+    }
+}
+
+/// A fresh 2048-bit RSA key as PKCS#8 DER — the stored form.
+fn generate() -> Result<Vec<u8>> {
+    let pkey = PKey::from_rsa(Rsa::generate(2048)?)?;
+    Ok(pkey.private_key_to_pkcs8()?)
+}
+
+/// PKCE (RFC 7636) S256: does `verifier` hash to `challenge`? The verifier must
+/// be 43–128 unreserved characters; anything else fails.
+pub fn pkce_s256_matches(verifier: &str, challenge: &str) -> bool {
+    let well_formed = (43..=128).contains(&verifier.len())
+        && verifier
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~'));
+    let computed = URL_SAFE_NO_PAD.encode(sha256(verifier.as_bytes()));
+    well_formed
+        && computed.len() == challenge.len()
+        && openssl::memcmp::eq(computed.as_bytes(), challenge.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_verify_and_reject_tampering() -> Result<()> {
+        let key = SigningKey::from_pkcs8(&generate()?)?;
+        let token = key.sign(ID_TOKEN_TYP, &json!({ "sub": "abc", "role": "Family" }))?;
+        assert_eq!(key.verify(ID_TOKEN_TYP, &token)?["role"], "Family");
+        assert!(
+            key.verify(ACCESS_TOKEN_TYP, &token).is_err(),
+            "an ID token isn't an access token"
+        );
+
+        // A payload swapped under the original signature fails.
+        let mut parts: Vec<&str> = token.split('.').collect();
+        let forged = URL_SAFE_NO_PAD.encode(r#"{"sub":"abc","role":"Admin"}"#);
+        parts[1] = &forged;
+        assert!(key.verify(ID_TOKEN_TYP, &parts.join(".")).is_err());
+
+        // Another key's token fails on the kid before the signature.
+        let other = SigningKey::from_pkcs8(&generate()?)?;
+        let theirs = other.sign(ID_TOKEN_TYP, &json!({ "sub": "abc" }))?;
+        assert!(key.verify(ID_TOKEN_TYP, &theirs).is_err());
+        assert_eq!(key.jwk()?["kid"], key.kid.as_str());
+        Ok(())
+    }
+
+    #[test]
+    fn pkce_rfc7636_vector() {
+        let verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+        let challenge = "E9Melhoa2OwvFrEMTJguCQaVi-LjHvNCgnD-4nGbn-8";
+        assert!(pkce_s256_matches(verifier, challenge));
+        assert!(!pkce_s256_matches(
+            verifier,
+            "E9Melhoa2OwvFrEMTJguCQaVi-LjHvNCgnD-4nGbn-9"
+        ));
+        assert!(!pkce_s256_matches("too-short", challenge));
+    }
+}
@@ -2,9 +2,13 @@ use crate::web::util::deserialize::empty_string_as_none;
 use crate::{
     db::dao::{content_pages::ContentPageDao, roles::Role},
     web::{
-        app_error::AppError, app_state::AppState, authentication_state::AuthenticationState,
+        app_error::AppError,
+        app_state::AppState,
+        audit::{self, Actor},
+        authentication_state::AuthenticationState,
         html_template::HtmlTemplate,
         markdown::{render_cache::cached_transform, title::strip_leading_h1},
+        middleware::require_step_up::require_step_up,
         responder::{ClientKind, WriteOutcome},
         session::SessionData,
     },
@@ -13,6 +17,8 @@ use askama::Template;
 use axum::{
     Form, Router,
     extract::{Path, Query, State},
+    handler::Handler,
+    middleware::from_fn,
     response::{IntoResponse, Redirect, Response},
     routing::get,
 };
@@ -38,7 +44,8 @@ pub fn pages_router() -> Router<AppState> {
         .route(
             "/{*page_path}",
             get(get_page_path)
-                .delete(delete_page_path)
+                // Page delete needs a fresh passkey assertion (step-up).
+                .delete(delete_page_path.layer(from_fn(require_step_up)))
                 .put(put_page_path)
                 .post(post_page_path),
         )
@@ -315,6 +322,7 @@ pub async fn get_page_path(
 
 pub async fn delete_page_path(
     State(state): State<AppState>,
+    actor: Actor,
     Path(page_path): Path<String>,
     client: ClientKind,
 ) -> Result<Response, AppError> {
@@ -330,6 +338,9 @@ pub async fn delete_page_path(
             }
 
             lp.delete(&state.pool).await?;
+            let summary = write::audit_summary(lp);
+            audit::record(&state.pool, &actor, "page.delete", &page_path, Some(summary), None)
+                .await;
 
             // The page is gone → send the client to the parent (or the index).
             let (_, parent_paths) = page_names.split_last().unwrap();
@@ -369,6 +380,7 @@ pub struct PutPageForm {
 
 pub async fn put_page_path(
     State(state): State<AppState>,
+    actor: Actor,
     Path(page_path): Path<String>,
     client: ClientKind,
     Form(put_page_form): Form<PutPageForm>,
@@ -383,7 +395,7 @@ pub async fn put_page_path(
         min_role: put_page_form.min_role,
         cover_ref: put_page_form.page_cover_media_ref,
     };
-    match update_page(&state.pool, &state.site_host, &page_names, input).await {
+    match update_page(&state.pool, &actor, &state.site_host, &page_names, input).await {
         Ok(w) => Ok(WriteOutcome::refresh(Some(w)).into_response(client)),
         Err(PageWriteError::NotFound) => Ok((StatusCode::NOT_FOUND, "No such page").into_response()),
         Err(PageWriteError::Internal(e)) => Err(e.into()),
@@ -407,20 +419,22 @@ pub struct PostPageForm {
 
 pub async fn post_top_level_page_path(
     State(state): State<AppState>,
+    actor: Actor,
     client: ClientKind,
     Form(post_page_form): Form<PostPageForm>,
 ) -> Result<Response, AppError> {
-    create_and_redirect(&state, &[], &post_page_form.page_title, client).await
+    create_and_redirect(&state, &actor, &[], &post_page_form.page_title, client).await
 }
 
 pub async fn post_page_path(
     State(state): State<AppState>,
+    actor: Actor,
     Path(page_path): Path<String>,
     client: ClientKind,
     Form(post_page_form): Form<PostPageForm>,
 ) -> Result<Response, AppError> {
     let page_names: Vec<&str> = page_path.split("/").collect();
-    create_and_redirect(&state, &page_names, &post_page_form.page_title, client).await
+    create_and_redirect(&state, &actor, &page_names, &post_page_form.page_title, client).await
 }
 
 /// Create a child (or top-level, EMPTY `parent_path`) page from a title, then land
@@ -430,11 +444,12 @@ pub async fn post_page_path(
 /// native 303 / JSON).
 async fn create_and_redirect(
     state: &AppState,
+    actor: &Actor,
     parent_path: &[&str],
     title: &str,
     client: ClientKind,
 ) -> Result<Response, AppError> {
-    match create_page(&state.pool, parent_path, title).await {
+    match create_page(&state.pool, actor, parent_path, title).await {
         Ok(w) => {
             let target = format!("{}?edit=1", w.pages_url());
             Ok(WriteOutcome::navigate(target, Some(w)).into_response(client))
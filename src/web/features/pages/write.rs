@@ -3,14 +3,17 @@
 //! two-write, inherit-on-create) used to live inline in the axum handlers; it now
 //! lives HERE so the editor handlers AND the MCP tools (Phase DI) share ONE code
 //! path and can't drift. Handlers keep only HTTP concerns (form extraction, the
-//! response); the typed outcome is `WrittenPage`.
+//! response); the typed outcome is `WrittenPage`. Every successful write is also
+//! recorded in the audit trail here, against the caller-supplied [`Actor`].
 
 use serde::Serialize;
+use serde_json::{Value, json};
 use sqlx::SqlitePool;
 use sqlx::types::chrono::{DateTime, NaiveDateTime, Utc};
 
 use crate::db::dao::content_pages::ContentPageDao;
 use crate::db::dao::roles::MinRole;
+use crate::web::audit::{self, Actor};
 use crate::web::features::media::resolve_cover_media_id;
 use crate::web::markdown::links::rewrite_site_links;
 use crate::web::util::slug::Slug;
@@ -50,6 +53,19 @@ impl WrittenPage {
     }
 }
 
+/// A page as the audit trail records it: identity, gate and dates, plus the body's
+/// SIZE — never the body itself (the log would otherwise hold every revision).
+pub fn audit_summary(cp: &ContentPageDao) -> Value {
+    json!({
+        "title": cp.page_title,
+        "category": cp.page_category,
+        "min_role": cp.min_role,
+        "order": cp.page_order,
+        "created": cp.page_creation_date.to_rfc3339(),
+        "markdown_bytes": cp.page_markdown.len(),
+    })
+}
+
 /// Why a write couldn't complete. The caller maps each to ITS OWN response (an
 /// axum handler → status + message; an MCP tool → a JSON-RPC error), so the exact
 /// per-context wording stays with the caller.
@@ -92,6 +108,7 @@ pub fn parse_local_datetime(s: &str) -> Option<DateTime<Utc>> {
 /// `min_role` is INHERITED from the parent (top-level is born public — no parent).
 pub async fn create_page(
     pool: &SqlitePool,
+    actor: &Actor,
     parent_path: &[&str],
     title: &str,
 ) -> Result<WrittenPage, PageWriteError> {
@@ -134,6 +151,8 @@ pub async fn create_page(
     cp.update(pool).await.map_err(PageWriteError::Internal)?;
 
     segments.push(slug.into_string());
+    let target = segments.join("/");
+    audit::record(pool, actor, "page.create", &target, None, Some(audit_summary(&cp))).await;
     Ok(WrittenPage::from_dao(&cp, segments))
 }
 
@@ -166,6 +185,7 @@ pub struct PageUpdate {
 
 pub async fn update_page(
     pool: &SqlitePool,
+    actor: &Actor,
     site_host: &str,
     path: &[&str],
     input: PageUpdate,
@@ -183,6 +203,7 @@ pub async fn update_page(
         .await
         .map_err(PageWriteError::Internal)?;
     let mut lp = pages_path.last().ok_or(PageWriteError::NotFound)?.to_owned();
+    let before = audit_summary(&lp);
 
     lp.page_title = input.title;
     lp.page_category = input.category;
@@ -212,6 +233,15 @@ pub async fn update_page(
             .map_err(PageWriteError::Internal)?;
     }
 
+    audit::record(
+        pool,
+        actor,
+        "page.update",
+        &path.join("/"),
+        Some(before),
+        Some(audit_summary(&lp)),
+    )
+    .await;
     Ok(WrittenPage::from_dao(
         &lp,
         path.iter().map(|s| (*s).to_string()).collect(),
@@ -221,6 +251,16 @@ pub async fn update_page(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::dao::audit_log::{AuditFilter, AuditLogDao};
+    use crate::web::audit::AuthChannel;
+
+    fn actor() -> Actor {
+        Actor {
+            user_id: None,
+            display_name: Some("chris".to_string()),
+            channel: AuthChannel::Session,
+        }
+    }
 
     async fn fetch(pool: &SqlitePool, path: &[&str]) -> ContentPageDao {
         ContentPageDao::find_by_path(pool, path)
@@ -232,7 +272,7 @@ mod tests {
 
     #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
     async fn create_top_level_is_public_and_slugged(pool: SqlitePool) {
-        let w = create_page(&pool, &[], "My New Page!").await.unwrap();
+        let w = create_page(&pool, &actor(), &[], "My New Page!").await.unwrap();
         assert_eq!(w.slug, "my-new-page");
         assert_eq!(w.title, "My New Page!");
         assert_eq!(w.min_role, None);
@@ -242,21 +282,22 @@ mod tests {
 
     #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
     async fn create_empty_title_rejected(pool: SqlitePool) {
-        let err = create_page(&pool, &[], "   !!!  ").await.unwrap_err();
+        let err = create_page(&pool, &actor(), &[], "   !!!  ").await.unwrap_err();
         assert!(matches!(err, PageWriteError::EmptyTitle));
     }
 
     #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
     async fn create_under_missing_parent_is_not_found(pool: SqlitePool) {
-        let err = create_page(&pool, &["nope"], "Child").await.unwrap_err();
+        let err = create_page(&pool, &actor(), &["nope"], "Child").await.unwrap_err();
         assert!(matches!(err, PageWriteError::NotFound));
     }
 
     #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
     async fn child_inherits_parent_gate(pool: SqlitePool) {
-        create_page(&pool, &[], "Vault").await.unwrap();
+        create_page(&pool, &actor(), &[], "Vault").await.unwrap();
         update_page(
             &pool,
+            &actor(),
             "hotchkiss.io",
             &["vault"],
             PageUpdate {
@@ -267,13 +308,13 @@ mod tests {
         )
         .await
         .unwrap();
-        let child = create_page(&pool, &["vault"], "Secret").await.unwrap();
+        let child = create_page(&pool, &actor(), &["vault"], "Secret").await.unwrap();
         assert_eq!(child.min_role.as_deref(), Some("Family"));
     }
 
     #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
     async fn update_min_role_is_three_valued(pool: SqlitePool) {
-        create_page(&pool, &[], "P").await.unwrap();
+        create_page(&pool, &actor(), &[], "P").await.unwrap();
         let up = |min_role: Option<String>| PageUpdate {
             title: Some("P".into()),
             markdown: "body".into(),
@@ -281,20 +322,20 @@ mod tests {
             ..Default::default()
         };
         // set
-        update_page(&pool, "h", &["p"], up(Some("Registered".into())))
+        update_page(&pool, &actor(), "h", &["p"], up(Some("Registered".into())))
             .await
             .unwrap();
         assert_eq!(fetch(&pool, &["p"]).await.min_role.as_deref(), Some("Registered"));
         // keep (absent)
-        update_page(&pool, "h", &["p"], up(None)).await.unwrap();
+        update_page(&pool, &actor(), "h", &["p"], up(None)).await.unwrap();
         assert_eq!(fetch(&pool, &["p"]).await.min_role.as_deref(), Some("Registered"));
         // keep (unrecognized — must never silently loosen)
-        update_page(&pool, "h", &["p"], up(Some("Bogus".into())))
+        update_page(&pool, &actor(), "h", &["p"], up(Some("Bogus".into())))
             .await
             .unwrap();
         assert_eq!(fetch(&pool, &["p"]).await.min_role.as_deref(), Some("Registered"));
         // clear (Public)
-        update_page(&pool, "h", &["p"], up(Some("Public".into())))
+        update_page(&pool, &actor(), "h", &["p"], up(Some("Public".into())))
             .await
             .unwrap();
         assert_eq!(fetch(&pool, &["p"]).await.min_role, None);
@@ -302,9 +343,10 @@ mod tests {
 
     #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
     async fn update_backdates_creation_date(pool: SqlitePool) {
-        create_page(&pool, &[], "P").await.unwrap();
+        create_page(&pool, &actor(), &[], "P").await.unwrap();
         update_page(
             &pool,
+            &actor(),
             "h",
             &["p"],
             PageUpdate {
@@ -325,7 +367,7 @@ mod tests {
 
     #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
     async fn update_missing_page_is_not_found(pool: SqlitePool) {
-        let err = update_page(&pool, "h", &["ghost"], PageUpdate::default())
+        let err = update_page(&pool, &actor(), "h", &["ghost"], PageUpdate::default())
             .await
             .unwrap_err();
         assert!(matches!(err, PageWriteError::NotFound));
@@ -333,10 +375,11 @@ mod tests {
 
     #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
     async fn update_unresolvable_cover_is_a_noop_not_an_error(pool: SqlitePool) {
-        create_page(&pool, &[], "P").await.unwrap();
+        create_page(&pool, &actor(), &[], "P").await.unwrap();
         // A garbage cover ref must SKIP (preserve), never error or wipe.
         update_page(
             &pool,
+            &actor(),
             "h",
             &["p"],
             PageUpdate {
@@ -349,4 +392,40 @@ mod tests {
         .await
         .unwrap();
     }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn writes_land_in_the_audit_log(pool: SqlitePool) {
+        create_page(&pool, &actor(), &[], "P").await.unwrap();
+        update_page(
+            &pool,
+            &actor(),
+            "h",
+            &["p"],
+            PageUpdate {
+                title: Some("Renamed".into()),
+                markdown: "body".into(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        // A failed write records nothing.
+        update_page(&pool, &actor(), "h", &["ghost"], PageUpdate::default())
+            .await
+            .unwrap_err();
+
+        let rows = AuditLogDao::list(&pool, &AuditFilter::default(), 10).await.unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].action, "page.update");
+        assert_eq!(rows[0].target, "p");
+        assert_eq!(rows[0].channel, "session");
+        assert_eq!(rows[0].actor_name.as_deref(), Some("chris"));
+        let before: Value = serde_json::from_str(rows[0].before.as_deref().unwrap()).unwrap();
+        let after: Value = serde_json::from_str(rows[0].after.as_deref().unwrap()).unwrap();
+        assert_eq!(before["title"], "P");
+        assert_eq!(after["title"], "Renamed");
+        assert_eq!(after["markdown_bytes"], 4);
+        assert_eq!(rows[1].action, "page.create");
+        assert!(rows[1].before.is_none());
+    }
 }
@@ -0,0 +1,295 @@
+//! Account recovery on the sign-in page: a user who has lost every passkey trades
+//! one of their recovery codes (generated on `/account`, or minted offline by the
+//! `recovery_code` binary) for the right to register ONE new passkey on their
+//! existing account.
+//!
+//! Redeeming (`POST /login/recover`) burns the code, audits it, and stashes a
+//! short-lived [`RecoveryGrant`] in the session — it does NOT sign anyone in. The
+//! grant is only good for the registration ceremony it unlocks
+//! (`/login/recover/start/{label}` → the ordinary `/login/finish_register`, which
+//! appends the passkey to the account instead of creating one); signing in
+//! happens when that ceremony completes. Every `/login/*` route is in the login
+//! rate-limit group, so guessing is throttled per IP on top of the codes' 80 bits.
+
+use anyhow::Context;
+use askama::Template;
+use axum::{
+    Form, Json,
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::SqlitePool;
+use tower_sessions::Session;
+use tracing::{info, warn};
+use uuid::Uuid;
+use webauthn_rs::prelude::Passkey;
+
+use crate::db::dao::{
+    passkeys::{PasskeyMetaDao, cred_id_key},
+    recovery_codes::RecoveryCodeDao,
+    roles::Role,
+    users::UserDao,
+};
+use crate::web::{
+    app_error::AppError,
+    app_state::AppState,
+    audit::{self, Actor, AuthChannel},
+    authentication_state::AuthenticationState,
+    features::{account::passkeys::validate_label, top_bar::TopBar},
+    html_template::HtmlTemplate,
+    session::SessionData,
+};
+
+/// Session key for the grant a redeemed code leaves behind.
+const RECOVERY_GRANT_KEY: &str = "recovery_grant";
+
+/// How long a redeemed code's grant lasts — long enough to find a security key.
+const GRANT_WINDOW_SECS: i64 = 10 * 60;
+
+/// Label of the passkey a recovery registers when none was given.
+const RECOVERED_PASSKEY_LABEL: &str = "Recovered passkey";
+
+const CODE_REJECTED: &str =
+    "That username and recovery code don't match an unused code — check both and try again.";
+
+/// "This session redeemed a code for `user_id` at `at` (unix seconds)." The label
+/// is filled in when the ceremony starts.
+#[derive(Deserialize, Serialize)]
+struct RecoveryGrant {
+    user_id: Uuid,
+    at: i64,
+    label: Option<String>,
+}
+
+impl RecoveryGrant {
+    fn is_fresh(&self) -> bool {
+        time::OffsetDateTime::now_utc().unix_timestamp() - self.at <= GRANT_WINDOW_SECS
+    }
+}
+
+#[derive(Template)]
+#[template(path = "login_recover.html")]
+struct RecoverTemplate {
+    top_bar: TopBar,
+    auth_state: AuthenticationState,
+    /// The account a redeemed code unlocked: the page offers the passkey ceremony
+    /// instead of the code form.
+    granted_to: Option<String>,
+    error: Option<String>,
+}
+
+async fn render(
+    state: &AppState,
+    session_data: SessionData,
+    granted_to: Option<String>,
+    error: Option<String>,
+) -> Result<HtmlTemplate<RecoverTemplate>, AppError> {
+    Ok(HtmlTemplate(RecoverTemplate {
+        top_bar: TopBar::create(&state.pool, "login", session_data.auth_state.role()).await?,
+        auth_state: session_data.auth_state,
+        granted_to,
+        error,
+    }))
+}
+
+/// `GET /login/recover` — the code form.
+pub async fn recover_page(
+    State(state): State<AppState>,
+    session_data: SessionData,
+) -> Result<Response, AppError> {
+    Ok(render(&state, session_data, None, None)
+        .await?
+        .into_response())
+}
+
+#[derive(Deserialize)]
+pub struct RecoverForm {
+    pub display_name: String,
+    pub code: String,
+}
+
+/// `POST /login/recover` — burn a recovery code and grant this session one
+/// passkey registration for its account. A wrong name and a wrong code get the
+/// same answer.
+pub async fn redeem_recovery_code(
+    State(state): State<AppState>,
+    session: Session,
+    session_data: SessionData,
+    Form(form): Form<RecoverForm>,
+) -> Result<Response, AppError> {
+    let display_name = form.display_name.trim();
+    let user = UserDao::find_by_display_name(&state.pool, display_name).await?;
+    let redeemed = match &user {
+        Some(user) => RecoveryCodeDao::redeem(&state.pool, &user.id, &form.code).await?,
+        None => None,
+    };
+    let (Some(user), Some(code_id)) = (user, redeemed) else {
+        warn!("recovery code rejected for username {display_name:?}");
+        let page = render(&state, session_data, None, Some(CODE_REJECTED.to_string())).await?;
+        return Ok((StatusCode::UNAUTHORIZED, page).into_response());
+    };
+
+    // Nobody is signed in yet, so the actor is the account the code belongs to.
+    let actor = Actor {
+        user_id: Some(user.id),
+        display_name: Some(user.display_name.clone()),
+        channel: AuthChannel::Session,
+    };
+    let after = json!({ "code_id": code_id });
+    let target = user.id.to_string();
+    audit::record(
+        &state.pool,
+        &actor,
+        "recovery_code.use",
+        &target,
+        None,
+        Some(after),
+    )
+    .await;
+    warn!(
+        "recovery code {code_id} used for user {:?}",
+        user.display_name
+    );
+
+    let at = time::OffsetDateTime::now_utc().unix_timestamp();
+    let grant = RecoveryGrant {
+        user_id: user.id,
+        at,
+        label: None,
+    };
+    session.insert(RECOVERY_GRANT_KEY, grant).await?;
+    Ok(render(&state, session_data, Some(user.display_name), None)
+        .await?
+        .into_response())
+}
+
+/// `GET /login/recover/start/{label}` — begin registering the new passkey for the
+/// account this session's grant unlocked.
+pub async fn start_recovery_registration(
+    State(state): State<AppState>,
+    session: Session,
+    mut session_data: SessionData,
+    Path(label): Path<String>,
+) -> Result<Response, AppError> {
+    let Some(mut grant) = session
+        .get::<RecoveryGrant>(RECOVERY_GRANT_KEY)
+        .await?
+        .filter(RecoveryGrant::is_fresh)
+    else {
+        return Ok((
+            StatusCode::GONE,
+            "Your recovery code has expired for this session — enter another one.",
+        )
+            .into_response());
+    };
+    let label = label.trim().to_string();
+    if let Err(msg) = validate_label(&label) {
+        return Ok((StatusCode::BAD_REQUEST, msg).into_response());
+    }
+    let Some(user) = UserDao::find_by_uuid(&state.pool, &grant.user_id).await? else {
+        return Ok((StatusCode::GONE, "That account no longer exists.").into_response());
+    };
+
+    let exclude = user.keys.iter().map(|k| k.cred_id().clone()).collect();
+    let (ccr, registration) = state
+        .webauthn
+        .start_passkey_registration(
+            user.id,
+            &user.display_name,
+            &user.display_name,
+            Some(exclude),
+        )
+        .context("Failed to start recovery passkey registration.")?;
+
+    grant.label = Some(label);
+    session.insert(RECOVERY_GRANT_KEY, grant).await?;
+    session_data.auth_state = AuthenticationState::RegistrationStarted((registration, user));
+    SessionData::update_session(&session, &session_data).await?;
+    Ok(Json(ccr).into_response())
+}
+
+/// Claim this session's grant if it covers `user_id`: the label the new passkey
+/// gets, or `None` when the registration isn't a recovery (the ordinary sign-up
+/// path). One-shot — the grant is gone either way.
+pub(crate) async fn take_grant(
+    session: &Session,
+    user_id: &Uuid,
+) -> anyhow::Result<Option<String>> {
+    Ok(session
+        .remove::<RecoveryGrant>(RECOVERY_GRANT_KEY)
+        .await?
+        .filter(|g| g.user_id == *user_id && g.is_fresh())
+        .map(|g| {
+            g.label
+                .unwrap_or_else(|| RECOVERED_PASSKEY_LABEL.to_string())
+        }))
+}
+
+/// Append a recovery's new passkey to the account and label it, in one transaction:
+/// the append is a single UPDATE (a passkey added or removed since the grant isn't
+/// clobbered) and the blob never lands without its metadata row. Returns the account
+/// as it now stands; `None` if it's gone.
+pub(crate) async fn add_recovered_passkey(
+    pool: &SqlitePool,
+    user_id: &Uuid,
+    passkey: Passkey,
+    label: &str,
+) -> anyhow::Result<Option<UserDao>> {
+    let cred_id = cred_id_key(passkey.cred_id());
+    let mut tx = pool.begin().await?;
+    if !UserDao::add_key(&mut *tx, user_id, &passkey).await? {
+        return Ok(None);
+    }
+    PasskeyMetaDao::record(&mut *tx, user_id, &cred_id, label).await?;
+    let Some(user) = UserDao::find_by_uuid(&mut *tx, user_id).await? else {
+        return Ok(None);
+    };
+    tx.commit().await?;
+    info!(
+        "recovery passkey {label:?} added for user {:?}",
+        user.display_name
+    );
+    Ok(Some(user))
+}
+
+/// The offline path (`src/bin/recovery_code.rs`): mint one recovery code for the
+/// account named `display_name` — or, given `None`, the site's only admin — and
+/// audit it on the `cli` channel. Returns the account's name and the plaintext.
+pub async fn mint_offline(
+    pool: &SqlitePool,
+    display_name: Option<&str>,
+) -> anyhow::Result<(String, String)> {
+    let user = match display_name {
+        Some(name) => UserDao::find_by_display_name(pool, name)
+            .await?
+            .with_context(|| format!("no user named {name:?}"))?,
+        None => {
+            let admins: Vec<_> = UserDao::list_summaries(pool)
+                .await?
+                .into_iter()
+                .filter(|u| u.role == Role::Admin)
+                .collect();
+            let [admin] = admins.as_slice() else {
+                anyhow::bail!(
+                    "found {} admins — name the account to recover explicitly",
+                    admins.len()
+                );
+            };
+            UserDao::find_by_display_name(pool, &admin.display_name)
+                .await?
+                .context("the admin vanished mid-lookup")?
+        }
+    };
+    let code = RecoveryCodeDao::add_one(pool, &user.id).await?;
+    let actor = Actor {
+        user_id: None,
+        display_name: None,
+        channel: AuthChannel::Cli,
+    };
+    let target = user.id.to_string();
+    audit::record(pool, &actor, "recovery_code.mint", &target, None, None).await;
+    Ok((user.display_name, code))
+}
@@ -210,6 +210,7 @@ fn weasyprint(html: &str) -> Result<Vec<u8>> {
     let bin = WEASYPRINT_BIN.as_deref().ok_or_else(|| {
         anyhow!("weasyprint not found — `brew install weasyprint` (looked at $WEASYPRINT_BIN, /opt/homebrew/bin, /usr/local/bin, PATH)")
     })?;
+    let _span = tracing::info_span!("weasyprint", process.executable.path = bin).entered();
     let mut child = Command::new(bin)
         .arg("-")
         .arg("-")
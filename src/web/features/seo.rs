@@ -224,11 +224,20 @@ pub async fn robots_txt(State(state): State<AppState>, headers: HeaderMap, uri:
     let canonical = crate::web::util::host::is_canonical_host(&host, &state.site_host);
 
     let body = if canonical {
+        // The honeypots (see `web::trap`) are disallowed too, so a crawler that honours
+        // this file never trips one.
+        let honeypots: String = state
+            .traps
+            .honeypots()
+            .iter()
+            .map(|path| format!("Disallow: {path}\n"))
+            .collect();
         format!(
             "User-agent: *\n\
              Allow: /\n\
              Disallow: /admin/\n\
              Disallow: /login/\n\
+             {honeypots}\
              \n\
              Sitemap: {scheme}://{host}/sitemap.xml\n"
         )
@@ -0,0 +1,103 @@
+//! The step-up re-prompt (`/step-up`): a signed-in user proves possession of one
+//! of THEIR passkeys again, refreshing the session's step-up stamp that
+//! `require_step_up` checks before destructive actions. Same two-step ceremony
+//! as login, but non-discoverable — the challenge lists only this user's
+//! credentials, so another account's passkey can't answer it.
+
+use axum::{
+    Json, Router,
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use http::StatusCode;
+use tower_sessions::Session;
+use tracing::{info, warn};
+use webauthn_rs::prelude::{PasskeyAuthentication, PublicKeyCredential};
+
+use crate::db::dao::{
+    passkeys::{PasskeyMetaDao, cred_id_key},
+    users::UserDao,
+};
+use crate::web::{
+    app_error::AppError, app_state::AppState, middleware::require_step_up::mark_stepped_up,
+    session::SessionData,
+};
+
+/// Session key for an in-flight re-prompt. Kept apart from `SessionData` so the
+/// session stays `Authenticated` throughout.
+const PENDING_STEP_UP_KEY: &str = "step_up_pending";
+
+pub fn step_up_router() -> Router<AppState> {
+    Router::new()
+        .route("/options", get(step_up_options))
+        .route("/finish", post(finish_step_up))
+}
+
+/// `GET /step-up/options` — a challenge restricted to the caller's passkeys.
+async fn step_up_options(
+    State(state): State<AppState>,
+    session: Session,
+    session_data: SessionData,
+) -> Result<Response, AppError> {
+    let Some(user_id) = session_data.auth_state.user().map(|u| u.id) else {
+        return Ok((StatusCode::UNAUTHORIZED, "Sign in first.").into_response());
+    };
+    // Keys from the row, not the session's login-time snapshot: a passkey removed
+    // since (say, from another tab) must not answer the re-prompt.
+    let Some(user) = UserDao::find_by_uuid(&state.pool, &user_id).await? else {
+        return Ok((StatusCode::UNAUTHORIZED, "Sign in first.").into_response());
+    };
+    if user.keys.is_empty() {
+        return Ok((
+            StatusCode::CONFLICT,
+            "This account has no passkey to confirm with.",
+        )
+            .into_response());
+    }
+    let (rcr, pending) = state.webauthn.start_passkey_authentication(&user.keys)?;
+    session.insert(PENDING_STEP_UP_KEY, pending).await?;
+    Ok(Json(rcr).into_response())
+}
+
+/// `POST /step-up/finish` — verify the assertion and stamp the session.
+async fn finish_step_up(
+    State(state): State<AppState>,
+    session: Session,
+    session_data: SessionData,
+    Json(pkc): Json<PublicKeyCredential>,
+) -> Result<Response, AppError> {
+    let Some(user_id) = session_data.auth_state.user().map(|u| u.id) else {
+        return Ok((StatusCode::UNAUTHORIZED, "Sign in first.").into_response());
+    };
+    let Some(pending) = session
+        .remove::<PasskeyAuthentication>(PENDING_STEP_UP_KEY)
+        .await?
+    else {
+        warn!("step-up finish called with no re-prompt in progress");
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            "The confirmation wasn't started, or your session expired — please try again.",
+        )
+            .into_response());
+    };
+
+    let auth_result = state.webauthn.finish_passkey_authentication(&pkc, &pending)?;
+    if auth_result.needs_update()
+        && let Some(mut user) = UserDao::find_by_uuid(&state.pool, &user_id).await?
+    {
+        user.keys.iter_mut().for_each(|k| {
+            k.update_credential(&auth_result);
+        });
+        user.update(&state.pool).await?;
+    }
+    if let Err(e) =
+        PasskeyMetaDao::touch_last_used(&state.pool, &cred_id_key(auth_result.cred_id())).await
+    {
+        warn!("passkey last_used stamp failed (non-fatal): {e}");
+    }
+
+    mark_stepped_up(&session, user_id).await?;
+    info!("step-up succeeded for user {user_id}");
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
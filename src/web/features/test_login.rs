@@ -20,6 +20,7 @@ use crate::{
     db::dao::{roles::Role, users::UserDao},
     web::{
         app_error::AppError, app_state::AppState, authentication_state::AuthenticationState,
+        middleware::require_step_up::{clear_step_up, mark_stepped_up},
         session::SessionData,
     },
 };
@@ -27,11 +28,20 @@ use crate::{
 pub fn test_router() -> Router<AppState> {
     Router::new()
         .route("/login", post(login_as))
+        // Lapse this session's step-up stamp, as if the window had passed.
+        .route("/step-up/clear", post(clear_step_up_stamp))
         // Exercises the CatchPanicLayer: a handler panic must surface as a styled
         // 500, NOT a dropped connection.
         .route("/panic", get(trigger_panic))
 }
 
+/// `POST /test/step-up/clear` — drop the session's fresh-assertion stamp so a
+/// step-up-gated route demands the re-prompt.
+async fn clear_step_up_stamp(session: Session) -> Result<Response, AppError> {
+    clear_step_up(&session).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
 /// Always panics — for the CatchPanicLayer integration test only.
 async fn trigger_panic() -> Response {
     panic!("intentional test panic (CatchPanicLayer)")
@@ -40,17 +50,36 @@ async fn trigger_panic() -> Response {
 #[derive(Deserialize)]
 struct TestLoginQuery {
     role: Option<Role>,
+    /// Sign an EXISTING user in again (a second device) instead of minting one.
+    user: Option<Uuid>,
 }
 
 /// `POST /test/login` — no `role` ⇒ `Admin` (the useful default for poking at
 /// admin pages); `?role=Registered` / `?role=Family` for non-admin sessions.
 /// (`role` is case-sensitive — the strum variant names, e.g. `Admin`, `Family`.)
-/// Always creates a fresh user; a test DB is fresh per `spawn_test_server`.
+/// Creates a fresh user (a test DB is fresh per `spawn_test_server`), unless
+/// `?user=<uuid>` names an existing one — then that user gets another session.
 async fn login_as(
     State(state): State<AppState>,
     session: Session,
     Query(q): Query<TestLoginQuery>,
 ) -> Result<Response, AppError> {
+    if let Some(id) = q.user {
+        let Some(user) = UserDao::find_by_uuid(&state.pool, &id).await? else {
+            return Ok((StatusCode::NOT_FOUND, "no such user").into_response());
+        };
+        let role = user.role;
+        mark_stepped_up(&session, user.id).await?;
+        SessionData::update_session(
+            &session,
+            &SessionData {
+                auth_state: AuthenticationState::Authenticated(user),
+            },
+        )
+        .await?;
+        return Ok((StatusCode::OK, format!("logged in as {role}")).into_response());
+    }
+
     let role = q.role.unwrap_or(Role::Admin);
     let id = Uuid::new_v4();
     let display_name = format!("test-{role}");
@@ -72,6 +101,8 @@ async fn login_as(
         keys: sqlx::types::Json(Vec::new()),
         role,
     };
+    // Like a real login, a test login counts as a fresh passkey assertion.
+    mark_stepped_up(&session, id).await?;
     SessionData::update_session(
         &session,
         &SessionData {
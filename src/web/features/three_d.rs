@@ -188,6 +188,8 @@ struct EditorTemplate {
 /// rest of the site (content pages, media embeds, the model gallery) is never
 /// cross-origin isolated. Every asset the page pulls (main.css, the nav's inline
 /// icons, self-hosted fonts) is SAME-ORIGIN, so it survives COEP:require-corp.
+/// Its Content-Security-Policy (the site's, plus `'wasm-unsafe-eval'`) is added by
+/// `web::middleware::csp`.
 async fn editor_document(
     State(state): State<AppState>,
     session_data: SessionData,
@@ -150,6 +150,7 @@ fn render_d2(source: &str) -> Result<String> {
         anyhow!("d2 not found — run `brew install d2` (looked at $D2_BIN, /opt/homebrew/bin, /usr/local/bin, PATH)")
     })?;
 
+    let _span = tracing::info_span!("d2", process.executable.path = bin).entered();
     let mut child = Command::new(bin)
         .arg("-") // read D2 from stdin
         .arg("-") // write SVG to stdout
@@ -9,9 +9,11 @@ use markdown::Constructs;
 use markdown::Options;
 use markdown::ParseOptions;
 use mdast_util_to_markdown::to_markdown;
+use std::collections::HashSet;
 use std::collections::VecDeque;
 
 use crate::web::markdown::diagram;
+use crate::web::util::slug::slugify;
 
 /// Render markdown → HTML, HARDENED. The underlying parser/serializer
 /// (`markdown-rs` + `mdast_util_to_markdown`, both alpha) can PANIC on edge-case
@@ -197,9 +199,72 @@ role=\"button\" aria-label=\"Zoom image\" src=\"{}\" alt=\"{}\" />",
         to_markdown(&ast).map_err(|m| anyhow!("AST to Markdown failed {}", m))?;
 
     to_html_with_options(&transformed_markdown, &html_opts)
+        .map(|html| add_heading_ids(&html))
         .map_err(|m: markdown::message::Message| anyhow!("Failed to stringify markdown {}", m))
 }
 
+/// Give every bare `<h1>`…`<h6>` an `id` so `/blog/post#setup` lands on its heading: the
+/// heading's text, slugified like page names (`util::slug`), with `-1`, `-2`, … on a
+/// repeat. A heading that slugs to nothing gets none. The dead-link checker validates
+/// internal `#fragment`s against the ids rendered here, so the two can't drift.
+fn add_heading_ids(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut used: HashSet<String> = HashSet::new();
+    let mut rest = html;
+    while let Some(start) = heading_open(rest) {
+        let (before, tail) = rest.split_at(start);
+        let close = format!("</h{}>", &tail[2..3]);
+        let Some(len) = tail[4..].find(&close) else {
+            break;
+        };
+        let inner = &tail[4..4 + len];
+        out.push_str(before);
+        let slug = slugify(&text_of(inner));
+        if slug.is_empty() {
+            out.push_str(&tail[..4]);
+        } else {
+            let mut id = slug.clone();
+            let mut n = 1;
+            while used.contains(&id) {
+                id = format!("{slug}-{n}");
+                n += 1;
+            }
+            out.push_str(&format!("<h{} id=\"{id}\">", &tail[2..3]));
+            used.insert(id);
+        }
+        out.push_str(inner);
+        out.push_str(&close);
+        rest = &tail[4 + len + close.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Offset of the next attribute-less `<hN>` open tag (the compiler's; author-written
+/// headings with their own attributes are left alone).
+fn heading_open(html: &str) -> Option<usize> {
+    let bytes = html.as_bytes();
+    html.match_indices("<h").map(|(i, _)| i).find(|&i| {
+        matches!(bytes.get(i + 2), Some(b'1'..=b'6')) && bytes.get(i + 3) == Some(&b'>')
+    })
+}
+
+/// A heading's visible text for slugging: tags and entities dropped (`Q&amp;A` → `QA`).
+fn text_of(inner: &str) -> String {
+    let mut text = String::with_capacity(inner.len());
+    let mut skip_until = None;
+    for c in inner.chars() {
+        match (skip_until, c) {
+            (Some(end), c) if c == end => skip_until = None,
+            (Some(_), _) => {}
+            (None, '<') => skip_until = Some('>'),
+            (None, '&') => skip_until = Some(';'),
+            (None, c) => text.push(c),
+        }
+    }
+    text
+}
+
 /// In-flow cap for content images — matches the diagram cap so the two read
 /// consistently; click-to-zoom (diagram-zoom.js) reveals the full image.
 const MAX_IMAGE_HEIGHT_PX: u32 = 480;
@@ -377,6 +442,25 @@ mod tests {
         assert!(rendered.contains("<td>1</td>"), "correct table source sliced: {rendered}");
     }
 
+    #[test]
+    fn headings_get_unique_slug_ids() -> Result<()> {
+        let input = "## Setup\n\ntext\n\n## Setup\n\n### Q&A: `cargo` *run*\n\n## !!!\n";
+
+        let rendered = transform(input)?;
+
+        assert!(rendered.contains("<h2 id=\"setup\">Setup</h2>"), "{rendered}");
+        assert!(
+            rendered.contains("<h2 id=\"setup-1\">Setup</h2>"),
+            "a repeat is numbered: {rendered}"
+        );
+        assert!(
+            rendered.contains("<h3 id=\"qa-cargo-run\">"),
+            "text inside inline markup and entities slugs cleanly: {rendered}"
+        );
+        assert!(rendered.contains("<h2>!!!</h2>"), "nothing to slug, no id: {rendered}");
+        Ok(())
+    }
+
     #[test]
     fn pathological_content_never_unwinds_into_the_caller() {
         // A Wayback-recovered 2012 post (smart quotes + escaped angle brackets in a
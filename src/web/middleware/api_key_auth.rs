@@ -1,5 +1,7 @@
+use std::net::SocketAddr;
+
 use axum::{
-    extract::{Request, State},
+    extract::{ConnectInfo, Request, State},
     middleware::Next,
     response::Response,
 };
@@ -10,6 +12,11 @@ use crate::web::{
     app_state::AppState, authentication_state::AuthenticationState, session::SessionData,
 };
 
+/// Request extension: this request was authenticated by the API key with this id
+/// (not a cookie session).
+#[derive(Clone, Copy, Debug)]
+pub struct ApiKeyId(pub i64);
+
 /// API-key authentication (Phase CA). Resolves an `Authorization: Bearer hio_…`
 /// key and, if it's a live key, injects an Authenticated `SessionData` for the
 /// key's user into the request. The `SessionData` extractor reads that injection
@@ -18,8 +25,14 @@ use crate::web::{
 /// / unknown / revoked key injects nothing, leaving the request on the normal
 /// cookie-session path (→ Anonymous → a mutation 403s).
 ///
+/// Every request a key authenticates is appended to that key's `api_key_usage`
+/// trail (method, path, final status, client IP) after the response is built —
+/// spawned fire-and-forget like the request log, so the trail never adds latency.
+///
 /// Wired with `from_fn_with_state` (it needs the pool) and layered OUTER to the
 /// authz + session layers so the injection is present when `SessionData` is read.
+/// Alongside the identity it injects [`ApiKeyId`], so later layers can tell a
+/// key-authenticated request from a cookie session.
 pub async fn api_key_auth(State(state): State<AppState>, mut req: Request, next: Next) -> Response {
     // Pull the token out as an owned String before any await — don't hold a header
     // borrow across the DB lookup or the extensions insert.
@@ -31,25 +44,50 @@ pub async fn api_key_auth(State(state): State<AppState>, mut req: Request, next:
         .filter(|t| t.starts_with("hio_"))
         .map(str::to_string);
 
-    if let Some(token) = token {
-        if let Some(session_data) = resolve(&state, &token).await {
-            req.extensions_mut().insert(session_data);
+    let Some(token) = token else {
+        return next.run(req).await;
+    };
+    let Some((session_data, key_id)) = resolve(&state, &token).await else {
+        return next.run(req).await;
+    };
+    req.extensions_mut().insert(session_data);
+    req.extensions_mut().insert(ApiKeyId(key_id));
+
+    let method = req.method().to_string();
+    let path = req.uri().path().to_string();
+    let ip = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ci| ci.0.ip().to_string());
+
+    let response = next.run(req).await;
+
+    let status = i64::from(response.status().as_u16());
+    let pool = state.pool.clone();
+    tokio::spawn(async move {
+        if let Err(e) =
+            ApiKeyDao::record_usage(&pool, key_id, &method, &path, status, ip.as_deref()).await
+        {
+            tracing::warn!("api-key usage record failed (non-fatal): {e}");
         }
-    }
+    });
 
-    next.run(req).await
+    response
 }
 
-/// Look up a live key → its user → an Authenticated `SessionData`. Errors are
-/// swallowed to `None` (fail-closed: a broken/unknown key auths as nobody, never
-/// 500s the request).
-async fn resolve(state: &AppState, token: &str) -> Option<SessionData> {
+/// Look up a live key → its user → an Authenticated `SessionData` (plus the key id
+/// for the usage trail). Errors are swallowed to `None` (fail-closed: a
+/// broken/unknown key auths as nobody, never 500s the request).
+async fn resolve(state: &AppState, token: &str) -> Option<(SessionData, i64)> {
     let (user_id, key_id) = ApiKeyDao::authenticate(&state.pool, token).await.ok()??;
     let user = UserDao::find_by_uuid(&state.pool, &user_id).await.ok()??;
     if let Err(e) = ApiKeyDao::touch_last_used(&state.pool, key_id).await {
         tracing::warn!("api-key last_used stamp failed (non-fatal): {e}");
     }
-    Some(SessionData {
-        auth_state: AuthenticationState::Authenticated(user),
-    })
+    Some((
+        SessionData {
+            auth_state: AuthenticationState::Authenticated(user),
+        },
+        key_id,
+    ))
 }
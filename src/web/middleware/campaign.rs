@@ -0,0 +1,85 @@
+use axum::{
+    extract::Request,
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Redirect, Response},
+};
+
+use crate::web::util::campaign;
+
+/// Redirects a `GET` / `HEAD` that carries campaign parameters (`utm_*`, `ref`) to the same
+/// URL without them, the rest of the query kept. The tag has been recorded by then —
+/// `log_requests` (outer) reads it off this request — so the clean URL is what the visitor
+/// bookmarks or passes on, and a tagged link never lands in a search index.
+///
+/// A `303`, not a `301`: a browser caches a permanent redirect, and would skip the server
+/// (and the count) the next time the same tagged link is clicked.
+///
+/// Wired via `axum::middleware::from_fn(strip_campaign)`, INNER to the rate limiter and the
+/// greylist toll so a flood of tagged URLs is cut like any other.
+pub async fn strip_campaign(req: Request, next: Next) -> Response {
+    if matches!(*req.method(), Method::GET | Method::HEAD)
+        && let Some(rest) = req.uri().query().and_then(campaign::strip)
+    {
+        // A `//host` path would make the Location a protocol-relative URL to `host` — an
+        // open redirect. One leading slash keeps it on this site.
+        let path = format!("/{}", req.uri().path().trim_start_matches('/'));
+        let location = if rest.is_empty() {
+            path
+        } else {
+            format!("{path}?{rest}")
+        };
+        return Redirect::to(&location).into_response();
+    }
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, http::StatusCode};
+    use tower::ServiceExt;
+
+    async fn send(method: Method, uri: &str) -> Response {
+        let app = Router::new()
+            .fallback(|| async { "page" })
+            .layer(axum::middleware::from_fn(strip_campaign));
+        app.oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    fn location(resp: &Response) -> &str {
+        resp.headers()["location"].to_str().unwrap()
+    }
+
+    #[tokio::test]
+    async fn tagged_gets_redirect_to_the_clean_url() {
+        let resp = send(Method::GET, "/pages/resume?utm_source=linkedin&edit=1").await;
+        assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+        assert_eq!(location(&resp), "/pages/resume?edit=1");
+
+        let resp = send(Method::HEAD, "/blog/post?ref=newsletter").await;
+        assert_eq!(location(&resp), "/blog/post");
+    }
+
+    #[tokio::test]
+    async fn a_double_slash_path_stays_on_site() {
+        let resp = send(Method::GET, "//evil.example/x?utm_source=a").await;
+        assert_eq!(location(&resp), "/evil.example/x");
+    }
+
+    #[tokio::test]
+    async fn untagged_and_non_get_requests_pass_through() {
+        let resp = send(Method::GET, "/pages/resume?edit=1").await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = send(Method::POST, "/pages/resume?utm_source=linkedin").await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
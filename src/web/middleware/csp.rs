@@ -0,0 +1,198 @@
+//! Content-Security-Policy. Every request gets a fresh nonce, held in a task-local
+//! for the life of the request so any template can stamp it on its `<script>` tags
+//! (`nonce="{{ crate::web::middleware::csp::nonce() }}"`) without threading a field
+//! through every template struct. The response's policy is picked per route family:
+//!
+//! - **Site documents** — a strict, nonce-based script policy (`'strict-dynamic'`,
+//!   so a nonced script's own imports — the three.js import map, foliate's modules —
+//!   load without listing them). No inline handlers, no `eval`. Styles stay
+//!   `'unsafe-inline'`: KaTeX and the markdown renderer emit `style` attributes, and
+//!   EPUB chapters carry their own `<style>` into foliate's blob frames.
+//! - **The fab-gui editor** (`/3d/editor`) — the same, plus `'wasm-unsafe-eval'` to
+//!   instantiate the slicer. Its COOP/COEP stay on its own handler.
+//! - **Served SVG** — an uploaded SVG opened directly is a document too; it gets a
+//!   sandboxed, script-less policy.
+//!
+//! Other responses (JSON, media bytes, scripts) carry no policy. Violations are
+//! reported to `/csp-report`; in the report-only rollout mode
+//! (`AppState.csp_report_only`) the same policy is sent as `…-Report-Only`.
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use http::{
+    HeaderName, HeaderValue,
+    header::{CONTENT_SECURITY_POLICY, CONTENT_SECURITY_POLICY_REPORT_ONLY, CONTENT_TYPE},
+};
+
+use crate::web::app_state::AppState;
+
+tokio::task_local! {
+    static NONCE: String;
+}
+
+/// The Reporting API endpoint group the policies name in `report-to`.
+const REPORT_GROUP: &str = "csp";
+
+/// This request's CSP nonce, for `nonce="…"` on script tags. Empty outside a
+/// request (e.g. a template rendered in a unit test) — the script just won't run
+/// under an enforced policy, which is the safe failure.
+pub fn nonce() -> String {
+    NONCE.try_with(Clone::clone).unwrap_or_default()
+}
+
+/// Which policy a response gets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PolicyFamily {
+    Site,
+    Editor,
+    Svg,
+}
+
+impl PolicyFamily {
+    /// The family for a response to `path` with `content_type`, or `None` for a
+    /// response no policy applies to.
+    fn classify(path: &str, content_type: &str) -> Option<PolicyFamily> {
+        let mime = content_type.split(';').next().unwrap_or("").trim();
+        match mime {
+            "image/svg+xml" => Some(PolicyFamily::Svg),
+            "text/html" if path == "/3d/editor" => Some(PolicyFamily::Editor),
+            "text/html" => Some(PolicyFamily::Site),
+            _ => None,
+        }
+    }
+
+    fn policy(self, nonce: &str) -> String {
+        let report = format!("report-uri /csp-report; report-to {REPORT_GROUP}");
+        if self == PolicyFamily::Svg {
+            return format!(
+                "default-src 'none'; style-src 'unsafe-inline'; img-src data:; sandbox; {report}"
+            );
+        }
+        let wasm = if self == PolicyFamily::Editor {
+            " 'wasm-unsafe-eval'"
+        } else {
+            ""
+        };
+        [
+            "default-src 'self'".to_string(),
+            format!("script-src 'nonce-{nonce}' 'strict-dynamic' 'self'{wasm}"),
+            "style-src 'self' 'unsafe-inline' blob:".to_string(),
+            "img-src 'self' data: blob:".to_string(),
+            "font-src 'self' data: blob:".to_string(),
+            "media-src 'self' blob:".to_string(),
+            "connect-src 'self'".to_string(),
+            "worker-src 'self' blob:".to_string(),
+            "frame-src 'self' blob:".to_string(),
+            "object-src 'none'".to_string(),
+            "base-uri 'none'".to_string(),
+            "frame-ancestors 'self'".to_string(),
+            report,
+        ]
+        .join("; ")
+    }
+}
+
+/// 16 random bytes, base64url — unguessable per response.
+fn generate_nonce() -> String {
+    let mut raw = [0u8; 16];
+    // openssl's CSPRNG failing is not something a request can recover from; an
+    // empty nonce still yields a valid (just script-less) policy.
+    if openssl::rand::rand_bytes(&mut raw).is_err() {
+        return String::new();
+    }
+    URL_SAFE_NO_PAD.encode(raw)
+}
+
+/// Mint the nonce, run the request with it in scope, then attach the policy its
+/// response's family calls for. Layered OUTER to the panic catcher so even the
+/// styled 500 renders inside the nonce scope. A handler that set its own policy
+/// keeps it.
+pub async fn csp(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let nonce = generate_nonce();
+    let path = req.uri().path().to_string();
+    let mut response = NONCE.scope(nonce.clone(), next.run(req)).await;
+
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    let Some(family) = PolicyFamily::classify(&path, content_type) else {
+        return response;
+    };
+    let headers = response.headers_mut();
+    if headers.contains_key(CONTENT_SECURITY_POLICY)
+        || headers.contains_key(CONTENT_SECURITY_POLICY_REPORT_ONLY)
+    {
+        return response;
+    }
+    let name = if state.csp_report_only {
+        CONTENT_SECURITY_POLICY_REPORT_ONLY
+    } else {
+        CONTENT_SECURITY_POLICY
+    };
+    if let Ok(value) = HeaderValue::from_str(&family.policy(&nonce)) {
+        headers.insert(name, value);
+        headers.insert(
+            HeaderName::from_static("reporting-endpoints"),
+            HeaderValue::from_static(r#"csp="/csp-report""#),
+        );
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn families_by_route_and_type() {
+        let classify = PolicyFamily::classify;
+        assert_eq!(
+            classify("/", "text/html; charset=utf-8"),
+            Some(PolicyFamily::Site)
+        );
+        assert_eq!(
+            classify("/3d/editor", "text/html"),
+            Some(PolicyFamily::Editor)
+        );
+        assert_eq!(
+            classify("/media/abc", "image/svg+xml"),
+            Some(PolicyFamily::Svg)
+        );
+        assert_eq!(classify("/3d/editor/1/fab_gui.js", "text/javascript"), None);
+        assert_eq!(classify("/media", "application/json"), None);
+    }
+
+    #[test]
+    fn only_the_editor_may_compile_wasm() {
+        let site = PolicyFamily::Site.policy("abc");
+        assert!(
+            site.contains("script-src 'nonce-abc' 'strict-dynamic' 'self';"),
+            "{site}"
+        );
+        assert!(!site.contains("unsafe-eval") && site.contains("object-src 'none'"));
+        assert!(
+            PolicyFamily::Editor
+                .policy("abc")
+                .contains("'wasm-unsafe-eval'")
+        );
+        assert!(
+            PolicyFamily::Svg
+                .policy("abc")
+                .starts_with("default-src 'none'")
+        );
+    }
+
+    #[tokio::test]
+    async fn nonce_is_request_scoped() {
+        assert_eq!(nonce(), "");
+        let inside = NONCE.scope("n0nce".to_string(), async { nonce() }).await;
+        assert_eq!(inside, "n0nce");
+        assert_eq!(generate_nonce().len(), 22);
+    }
+}
@@ -4,6 +4,10 @@
 //!
 //! A non-browser (JSON) client — an MCP client, an API caller — gets a machine-readable greylist
 //! notice instead of the JS proof-of-work interstitial it has no engine to solve (Phase DI).
+//!
+//! An IP escalated to the `Blocked` tier (it cleared the toll, then kept probing — see
+//! `greylist::escalation`) gets a bare `403` instead: no toll to re-solve, and its clearance
+//! cookie is on the revocation list anyway.
 
 use std::net::SocketAddr;
 
@@ -26,6 +30,11 @@ use crate::web::session::SessionData;
 #[derive(Clone, Copy)]
 pub struct Challenged;
 
+/// Marker inserted on a blocked IP's bare `403` so the request-log middleware forces `is_bot`
+/// (but not `challenged` — there was no toll to solve).
+#[derive(Clone, Copy)]
+pub struct Blocked;
+
 /// Prefixes exempt from the toll even for a greylisted IP: the toll itself + its endpoints (MUST
 /// stay reachable or a greylisted client could never solve it), the interstitial's own static
 /// assets (or the page can't render), and the operational well-knowns. `/media` is deliberately
@@ -77,6 +86,17 @@ pub async fn greylist_challenge(
         return next.run(req).await;
     }
 
+    // Blocked (escalated) → a bare 403 on everything but sign-in: no toll to solve again. An
+    // authenticated human sharing the IP still gets through.
+    if state.greylist.is_blocked(&ip) {
+        if session.auth_state.is_authenticated() || req.uri().path().starts_with("/login") {
+            return next.run(req).await;
+        }
+        let mut resp = StatusCode::FORBIDDEN.into_response();
+        resp.extensions_mut().insert(Blocked);
+        return resp;
+    }
+
     // Exempt paths (the toll + its assets + operational well-knowns) always pass.
     let path = req.uri().path().to_string();
     if is_exempt_path(&path) {
@@ -88,10 +108,14 @@ pub async fn greylist_challenge(
         return next.run(req).await;
     }
 
-    // A valid clearance cookie passes.
+    // A valid, unrevoked clearance cookie passes.
     let now = Utc::now().timestamp();
     let cleared = cookie_value(req.headers(), CLEARANCE_COOKIE)
-        .map(|token| verify_clearance(&state.challenge.key, &token, now))
+        .map(|token| {
+            verify_clearance(&state.challenge.key, &token, now, |id| {
+                state.greylist.is_revoked(id)
+            })
+        })
         .unwrap_or(false);
     if cleared {
         return next.run(req).await;
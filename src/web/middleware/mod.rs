@@ -1,6 +1,12 @@
 pub mod api_key_auth;
+pub mod campaign;
+pub mod csp;
 pub mod greylist_challenge;
+pub mod metrics;
+pub mod rate_limit;
 pub mod refresh_session_role;
 pub mod request_log;
 pub mod require_admin;
 pub mod require_admin_for_mutations;
+pub mod require_step_up;
+pub mod trap;
@@ -0,0 +1,85 @@
+//! Rate-limit enforcement: take a token from the caller's bucket for the request's
+//! route group (see `web::rate_limit`) and answer `429` + `Retry-After` when it's
+//! empty. Layered INNER to `api_key_auth` / `refresh_session_role` so the caller's
+//! API key or user is known (an authenticated caller gets their own budget rather
+//! than their IP's), and OUTER to the authz layer so an anonymous flood is throttled
+//! before it costs anything.
+//!
+//! The operator's own network (`GreylistSet::is_allowlisted`) is never limited. A
+//! limited response carries the [`RateLimited`] marker so the request log stamps
+//! `rate_limited` for the analytics.
+
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::info;
+
+use crate::web::app_state::AppState;
+use crate::web::middleware::api_key_auth::ApiKeyId;
+use crate::web::rate_limit::RouteGroup;
+use crate::web::responder::ClientKind;
+use crate::web::session::SessionData;
+
+/// Marker inserted on a rate-limited `429` so the (outer) request-log middleware
+/// stamps `rate_limited`.
+#[derive(Clone, Copy)]
+pub struct RateLimited;
+
+pub async fn rate_limit(
+    State(state): State<AppState>,
+    session: SessionData,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let Some(group) = RouteGroup::classify(req.method(), req.uri().path()) else {
+        return next.run(req).await;
+    };
+    let ip = peer.ip().to_string();
+    if state.greylist.is_allowlisted(&ip) {
+        return next.run(req).await;
+    }
+
+    let key = if let Some(ApiKeyId(id)) = req.extensions().get::<ApiKeyId>() {
+        format!("key:{id}")
+    } else if let Some(user) = session.auth_state.user() {
+        format!("user:{}", user.id)
+    } else {
+        format!("ip:{ip}")
+    };
+    let Err(retry_after) = state.rate_limiter.check(group, &key) else {
+        return next.run(req).await;
+    };
+
+    let retry_secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    info!(
+        group = group.as_str(),
+        key,
+        path = req.uri().path(),
+        retry_secs,
+        "rate limited (429)"
+    );
+    let mut resp = if ClientKind::from_headers(req.headers()) == ClientKind::Json {
+        let body = serde_json::json!({
+            "error": "rate_limited",
+            "message": format!("Too many {} requests — retry in {retry_secs}s.", group.as_str()),
+            "retry_after": retry_secs,
+        });
+        (StatusCode::TOO_MANY_REQUESTS, axum::Json(body)).into_response()
+    } else {
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            format!("Too many requests — try again in {retry_secs} seconds."),
+        )
+            .into_response()
+    };
+    resp.headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(retry_secs));
+    resp.extensions_mut().insert(RateLimited);
+    resp
+}
@@ -1,11 +1,14 @@
+use std::net::SocketAddr;
+
 use axum::{
-    extract::{Request, State},
+    extract::{ConnectInfo, Request, State},
+    http::header,
     middleware::Next,
     response::Response,
 };
 use tower_sessions::Session;
 
-use crate::db::dao::users::UserDao;
+use crate::db::dao::{user_sessions::UserSessionDao, users::UserDao};
 use crate::web::{
     app_state::AppState, authentication_state::AuthenticationState, session::SessionData,
 };
@@ -32,6 +35,10 @@ use crate::web::{
 const TOUCHED_AT_KEY: &str = "touched_at";
 /// Touch at most hourly — see the THROTTLED comment below.
 const SESSION_TOUCH_INTERVAL_SECS: i64 = 3600;
+/// Session key holding the id this session was last indexed under in
+/// `user_sessions`. Login cycles the id, so a mismatch means "first request of a
+/// fresh sign-in" → index it now rather than at the next hourly touch.
+const INDEXED_ID_KEY: &str = "indexed_session_id";
 
 pub async fn refresh_session_role(
     State(state): State<AppState>,
@@ -92,11 +99,51 @@ pub async fn refresh_session_role(
                 let now = time::OffsetDateTime::now_utc().unix_timestamp();
                 let touched_at: Option<i64> =
                     session.get(TOUCHED_AT_KEY).await.unwrap_or_default();
-                if touched_at.is_none_or(|t| now - t >= SESSION_TOUCH_INTERVAL_SECS)
-                    && let Err(e) = session.insert(TOUCHED_AT_KEY, now).await
-                {
+                let touch_due =
+                    touched_at.is_none_or(|t| now - t >= SESSION_TOUCH_INTERVAL_SECS);
+                if touch_due && let Err(e) = session.insert(TOUCHED_AT_KEY, now).await {
                     tracing::warn!("session touch failed (expiry not extended): {e}");
                 }
+                // The per-user session index (`user_sessions`, the /account
+                // "where am I signed in" list) rides the same throttle, plus the
+                // first request after login (new id). Fire-and-forget: a failed
+                // index write never costs the request anything.
+                if let Some(id) = session.id().map(|id| id.to_string()) {
+                    let indexed: Option<String> =
+                        session.get(INDEXED_ID_KEY).await.unwrap_or_default();
+                    let first_sight = indexed.as_deref() != Some(id.as_str());
+                    if first_sight
+                        && let Err(e) = session.insert(INDEXED_ID_KEY, id.clone()).await
+                    {
+                        tracing::warn!("session index mark failed: {e}");
+                    }
+                    if touch_due || first_sight {
+                        let pool = state.pool.clone();
+                        let user_id = user.id;
+                        let user_agent = req
+                            .headers()
+                            .get(header::USER_AGENT)
+                            .and_then(|v| v.to_str().ok())
+                            .map(|s| s.chars().take(512).collect::<String>());
+                        let ip = req
+                            .extensions()
+                            .get::<ConnectInfo<SocketAddr>>()
+                            .map(|ci| ci.0.ip().to_string());
+                        tokio::spawn(async move {
+                            if let Err(e) = UserSessionDao::touch(
+                                &pool,
+                                &id,
+                                &user_id,
+                                user_agent.as_deref(),
+                                ip.as_deref(),
+                            )
+                            .await
+                            {
+                                tracing::warn!("user_sessions touch failed: {e}");
+                            }
+                        });
+                    }
+                }
                 // Refresh the role (and display name) from the DB.
                 req.extensions_mut().insert(refreshed);
             }
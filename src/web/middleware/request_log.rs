@@ -6,19 +6,24 @@ use axum::{
     middleware::Next,
     response::Response,
 };
-use sqlx::SqlitePool;
-use tracing::warn;
 
-use crate::db::dao::request_log::{NewRequestLog, RequestLogDao};
+use crate::db::dao::request_log::NewRequestLog;
+use crate::web::request_log_writer::RequestLogWriter;
+use crate::web::util::campaign;
 
 /// Records every request — method, path, response status, client IP (from
-/// `ConnectInfo`, if the serving stack supplies it), `User-Agent`, `Referer` —
-/// to the `request_log` table. The insert is `tokio::spawn`'d fire-and-forget:
-/// logging never adds latency to nor fails a response; an insert error is
-/// logged and dropped.
+/// `ConnectInfo`, if the serving stack supplies it), `User-Agent`, `Referer`, the
+/// campaign a tagged link carried — to the `request_log` table. The row is handed to
+/// the batched writer (`web::request_log_writer`), which never waits: logging never
+/// adds latency to nor fails a response, and a backed-up queue sheds rows rather than
+/// block.
 ///
-/// Wired via `axum::middleware::from_fn_with_state(pool, log_requests)`.
-pub async fn log_requests(State(pool): State<SqlitePool>, req: Request, next: Next) -> Response {
+/// Wired via `axum::middleware::from_fn_with_state(writer, log_requests)`.
+pub async fn log_requests(
+    State(writer): State<RequestLogWriter>,
+    req: Request,
+    next: Next,
+) -> Response {
     let method = req.method().to_string();
     let path = req.uri().path().to_string();
 
@@ -37,6 +42,9 @@ pub async fn log_requests(State(pool): State<SqlitePool>, req: Request, next: Ne
         .get(header::REFERER)
         .and_then(|v| v.to_str().ok())
         .map(str::to_string);
+    // A tagged link's campaign — `middleware::campaign` (inner) answers it with the
+    // redirect to the clean URL, so the tag lands on that row.
+    let campaign = req.uri().query().and_then(campaign::from_query);
 
     // The livereload long-poll is pure noise in dev; /admin/logs is excluded ALWAYS so
     // the log viewer never feeds the access log it tails (Phase CO). /admin/analytics is
@@ -59,17 +67,21 @@ pub async fn log_requests(State(pool): State<SqlitePool>, req: Request, next: Ne
     // detection sweep (an IP probing /mcp can earn a greylist). Legit agent traffic is
     // authenticated + low-volume (discrete tool calls, not media streaming), so it doesn't
     // swamp the signal.
+    // /metrics is a Prometheus scrape every 15s or so — machine polling, excluded like the
+    // dashboard so it can't flood top-paths (it's counted in its own request metrics).
     #[cfg(debug_assertions)]
     let skip = path.starts_with("/tower-livereload")
         || path.starts_with("/admin/logs")
         || path.starts_with("/admin/analytics")
         || path.starts_with("/challenge")
-        || path.starts_with("/media/file/");
+        || path.starts_with("/media/file/")
+        || path == "/metrics";
     #[cfg(not(debug_assertions))]
     let skip = path.starts_with("/admin/logs")
         || path.starts_with("/admin/analytics")
         || path.starts_with("/challenge")
-        || path.starts_with("/media/file/");
+        || path.starts_with("/media/file/")
+        || path == "/metrics";
 
     // SERVER-handler processing time — the inner stack + handler, measured at the
     // outermost log layer. NOT client page-load/LCP (no TLS/network/download), and it
@@ -88,9 +100,27 @@ pub async fn log_requests(State(pool): State<SqlitePool>, req: Request, next: Ne
             .extensions()
             .get::<crate::web::middleware::greylist_challenge::Challenged>()
             .is_some();
+        // An escalated IP's bare 403 (`Blocked`) is just as provably a bot.
+        let blocked = response
+            .extensions()
+            .get::<crate::web::middleware::greylist_challenge::Blocked>()
+            .is_some();
+        // So is a honeypot hit or a tarpitted probe (`Trapped`).
+        let trapped = response
+            .extensions()
+            .get::<crate::web::middleware::trap::Trapped>()
+            .is_some();
         // Stamp the bot classification at write (CR.2) so the dashboard's audience
         // filter is a cheap indexed count, not a per-row 25-LIKE scan.
-        let is_bot = challenged || crate::db::dao::request_log::is_bot(user_agent.as_deref());
+        let is_bot = challenged
+            || blocked
+            || trapped
+            || crate::db::dao::request_log::is_bot(user_agent.as_deref());
+        // A rate-limited 429 (not a toll) is marked by `middleware::rate_limit`.
+        let rate_limited = response
+            .extensions()
+            .get::<crate::web::middleware::rate_limit::RateLimited>()
+            .is_some();
         let entry = NewRequestLog {
             method,
             path,
@@ -101,12 +131,12 @@ pub async fn log_requests(State(pool): State<SqlitePool>, req: Request, next: Ne
             duration_ms,
             is_bot,
             challenged,
+            rate_limited,
+            // Keyed by the writer, off the request path.
+            visitor: None,
+            campaign,
         };
-        tokio::spawn(async move {
-            if let Err(e) = RequestLogDao::insert(&pool, &entry).await {
-                warn!("failed to record request to request_log: {e}");
-            }
-        });
+        writer.log(entry);
     }
 
     response
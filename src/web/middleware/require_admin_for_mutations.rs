@@ -95,14 +95,26 @@ pub async fn require_admin_for_mutations(
 /// stay exact-match, never a path pattern), and **per-resource authorization
 /// beyond the coarse role gate lives in the handler** (e.g. a progress save
 /// re-checks the media's own `min_role`). Entries are code, reviewed like the
-/// WebAuthn ones above. Shipped EMPTY — Phase DF adds the first entry
-/// (`POST /library/progress` at `Role::Family`).
-const ROLE_SCOPED_MUTATIONS: &[(Method, &str, Role)] = &[];
+/// WebAuthn ones above. The entries today are the `/account` self-service
+/// mutations — any signed-in user manages their OWN passkeys and sessions (the
+/// handlers scope every change to the session's user) — the step-up
+/// re-prompt's finish, which only ever stamps the caller's own session, and the
+/// OIDC consent answer, which only ever grants for the caller.
+const ROLE_SCOPED_MUTATIONS: &[(Method, &str, Role)] = &[
+    (Method::POST, "/account/passkeys/finish", Role::Registered),
+    (Method::POST, "/account/passkeys/rename", Role::Registered),
+    (Method::POST, "/account/passkeys/delete", Role::Registered),
+    (Method::POST, "/account/sessions/revoke", Role::Registered),
+    (Method::POST, "/account/sessions/revoke_others", Role::Registered),
+    (Method::POST, "/account/recovery-codes/generate", Role::Registered),
+    (Method::POST, "/step-up/finish", Role::Registered),
+    (Method::POST, "/oidc/authorize", Role::Registered),
+];
 
 /// True when `(method, path)` has a role-scoped entry AND the viewer's rank
 /// meets it. Exact match only — a prefix or sibling never qualifies.
 /// Parameterized on the table so tests exercise the matching with a fixture
-/// while the shipped table stays empty.
+/// independent of what the shipped table holds.
 fn allowed_by_role_scope(
     table: &[(Method, &str, Role)],
     method: &Method,
@@ -120,7 +132,12 @@ fn allowed_by_role_scope(
 /// *finish*), the anonymous ceremony-failure BEACON (`/login/ceremony_error`, a
 /// pre-login client telemetry POST — DM follow-up), plus the debug-only
 /// `/test/login` seam (`cfg`-gated to match the route, which is absent from
-/// release). The ceremony's GET steps (`/login`, `/login/get_auth_opts`,
+/// release), and the OIDC token endpoint (`/oidc/token` — the relying party is
+/// no site user; it authenticates itself there with its client secret), the
+/// CSP violation sink (`/csp-report` — any visitor's browser posts there), and
+/// the recovery-code redemption (`/login/recover` — its caller has, by
+/// definition, no passkey left to sign in with). The
+/// ceremony's GET steps (`/login`, `/login/get_auth_opts`,
 /// `/login/start_register/{name}`, `/login/logout`) are covered by the GET-public
 /// rule, so they need no entry. CAVEAT: if logout or start_register is ever changed
 /// to a POST it MUST be added here or non-admins can't log in/out.
@@ -130,6 +147,8 @@ fn is_anonymous_auth_endpoint(method: &Method, path: &str) -> bool {
     }
     match path {
         "/login/finish_authentication" | "/login/finish_register" | "/login/ceremony_error" => true,
+        "/login/recover" => true,
+        "/oidc/token" | "/csp-report" => true,
         #[cfg(debug_assertions)]
         "/test/login" => true,
         _ => false,
@@ -156,6 +175,16 @@ mod tests {
             &Method::POST,
             "/login/ceremony_error"
         ));
+        // The OIDC token exchange (the client authenticates itself).
+        assert!(is_anonymous_auth_endpoint(&Method::POST, "/oidc/token"));
+        // Browsers' CSP violation reports.
+        assert!(is_anonymous_auth_endpoint(&Method::POST, "/csp-report"));
+        // Recovery-code redemption (the caller has no passkey to sign in with).
+        assert!(is_anonymous_auth_endpoint(&Method::POST, "/login/recover"));
+        assert!(!is_anonymous_auth_endpoint(
+            &Method::POST,
+            "/login/recover/start/phone"
+        ));
 
         // POST-only: a different verb to the same path is NOT exempt.
         assert!(!is_anonymous_auth_endpoint(
@@ -178,12 +207,35 @@ mod tests {
         assert!(is_anonymous_auth_endpoint(&Method::POST, "/test/login"));
     }
 
-    /// The shipped table is EMPTY by design in Phase CZ — the machinery lands
-    /// inert; Phase DF adds the first entry. If an entry appears here, move it
-    /// into the fixture coverage below too.
+    /// Every shipped entry is a self-service POST (`/account`, `/step-up`, the OIDC
+    /// consent answer) at
+    /// `Registered` — the tier that "has an account". A new entry fails here until it's
+    /// consciously added to this pin.
     #[test]
-    fn shipped_role_scope_table_is_empty() {
-        assert!(ROLE_SCOPED_MUTATIONS.is_empty());
+    fn shipped_role_scope_table_is_self_service() {
+        for (method, path, role) in ROLE_SCOPED_MUTATIONS {
+            assert_eq!(*method, Method::POST, "{path}");
+            assert!(
+                path.starts_with("/account/")
+                    || path.starts_with("/step-up/")
+                    || *path == "/oidc/authorize",
+                "{path}"
+            );
+            assert_eq!(*role, Role::Registered, "{path}");
+        }
+        // Anonymous never qualifies; a Registered session does.
+        assert!(!allowed_by_role_scope(
+            ROLE_SCOPED_MUTATIONS,
+            &Method::POST,
+            "/account/passkeys/delete",
+            Role::Anonymous
+        ));
+        assert!(allowed_by_role_scope(
+            ROLE_SCOPED_MUTATIONS,
+            &Method::POST,
+            "/account/passkeys/delete",
+            Role::Registered
+        ));
     }
 
     #[test]
@@ -0,0 +1,113 @@
+use axum::{extract::Request, middleware::Next, response::IntoResponse, response::Response};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use tower_sessions::Session;
+use uuid::Uuid;
+
+use crate::web::{
+    error_page::unauthorized_response, middleware::api_key_auth::ApiKeyId, session::SessionData,
+};
+
+/// How recent a WebAuthn assertion must be for a step-up-gated action to pass.
+pub const STEP_UP_WINDOW_SECS: i64 = 5 * 60;
+
+/// Session key holding the last fresh assertion ([`StepUp`]).
+const STEP_UP_KEY: &str = "step_up";
+
+/// Response header marking a 403 as "re-authenticate and retry" rather than a
+/// real denial — `step-up.js` and `htmx-errors.js` key on it.
+pub const STEP_UP_REQUIRED_HEADER: &str = "X-Step-Up-Required";
+
+/// A fresh WebAuthn assertion: who made it, and when (unix seconds). Keyed on
+/// the user so a session that changes hands can't inherit someone's step-up.
+#[derive(Deserialize, Serialize)]
+struct StepUp {
+    user_id: Uuid,
+    at: i64,
+}
+
+/// Record a fresh assertion by `user_id` on this session. Called by every
+/// ceremony that proves possession of a passkey — login, registration, and the
+/// `/step-up` re-prompt — so a just-signed-in admin isn't asked again at once.
+pub async fn mark_stepped_up(session: &Session, user_id: Uuid) -> anyhow::Result<()> {
+    let at = time::OffsetDateTime::now_utc().unix_timestamp();
+    session.insert(STEP_UP_KEY, StepUp { user_id, at }).await?;
+    Ok(())
+}
+
+/// Forget this session's step-up stamp (the debug test seam uses it to simulate
+/// the window lapsing).
+pub async fn clear_step_up(session: &Session) -> anyhow::Result<()> {
+    session.remove::<StepUp>(STEP_UP_KEY).await?;
+    Ok(())
+}
+
+/// Whether this session holds an assertion by `user_id` inside the window.
+async fn is_stepped_up(session: &Session, user_id: Uuid) -> bool {
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    matches!(
+        session.get::<StepUp>(STEP_UP_KEY).await,
+        Ok(Some(s)) if s.user_id == user_id && now - s.at <= STEP_UP_WINDOW_SECS
+    )
+}
+
+/// Step-up gate for destructive actions: a cookie session must hold a WebAuthn
+/// assertion from the last [`STEP_UP_WINDOW_SECS`], so a stolen session cookie
+/// alone can't delete a user or a page. Layered per ROUTE, next to (inside)
+/// `require_admin` — `delete(handler).layer(from_fn(require_step_up))` — so the
+/// role gate has already run and this only decides "recent enough?".
+///
+/// Without a fresh assertion the request gets a 403 carrying
+/// [`STEP_UP_REQUIRED_HEADER`]. For an htmx request it also carries
+/// `HX-Trigger: step-up-required` with the method + path (query included), which
+/// `step-up.js` answers by running the passkey re-prompt (`/step-up/options` →
+/// `/step-up/finish`) and replaying the original request from the same element.
+///
+/// A request authenticated by an API key is refused outright (a plain 403, no
+/// step-up header): a key has no passkey to re-prompt, so letting it through
+/// would make a leaked key strictly more powerful than a stolen session. These
+/// actions are for a person at a browser.
+pub async fn require_step_up(session_data: SessionData, req: Request, next: Next) -> Response {
+    if req.extensions().get::<ApiKeyId>().is_some() {
+        tracing::info!(
+            method = %req.method(),
+            path = req.uri().path(),
+            "step-up-gated action refused for an API key"
+        );
+        return (
+            StatusCode::FORBIDDEN,
+            "This needs a passkey confirmation in a browser session; API keys can't do it.",
+        )
+            .into_response();
+    }
+    let Some(user_id) = session_data.auth_state.user().map(|u| u.id) else {
+        return unauthorized_response(req.headers());
+    };
+    if let Some(session) = req.extensions().get::<Session>()
+        && is_stepped_up(session, user_id).await
+    {
+        return next.run(req).await;
+    }
+
+    tracing::info!(
+        method = %req.method(),
+        path = req.uri().path(),
+        "step-up required (no fresh passkey assertion)"
+    );
+    let mut resp = (
+        StatusCode::FORBIDDEN,
+        "Confirm it's you with your passkey, then try again.",
+    )
+        .into_response();
+    resp.headers_mut()
+        .insert(STEP_UP_REQUIRED_HEADER, http::HeaderValue::from_static("1"));
+    if req.headers().contains_key("HX-Request") {
+        let trigger = serde_json::json!({
+            "step-up-required": { "method": req.method().as_str(), "path": req.uri().to_string() }
+        });
+        if let Ok(v) = http::HeaderValue::from_str(&trigger.to_string()) {
+            resp.headers_mut().insert("HX-Trigger", v);
+        }
+    }
+    resp
+}
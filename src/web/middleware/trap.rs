@@ -0,0 +1,79 @@
+//! Trap enforcement (see `web::trap`): greylist an IP on its first honeypot hit, and
+//! tarpit signature-path probes. Layered INNER to `refresh_session_role` so an
+//! authenticated caller is known (and never trapped), and OUTER to the greylist toll so an
+//! already-greylisted scanner's probes are held too instead of getting the interstitial.
+//!
+//! Also scopes the honeypot paths for the request, so `base.html` can link them.
+
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use sqlx::types::chrono::{DateTime, Utc};
+use tracing::{info, warn};
+
+use crate::db::dao::greylist::GreylistDao;
+use crate::greylist::detection::is_signature_path;
+use crate::greylist::sweep::GREYLIST_TTL_DAYS;
+use crate::web::app_state::AppState;
+use crate::web::session::SessionData;
+use crate::web::trap::{HONEYPOT_REASON, with_honeypots};
+
+/// Marker inserted on a trapped response (a honeypot hit, a tarpitted probe) so the
+/// request-log middleware forces `is_bot`.
+#[derive(Clone, Copy)]
+pub struct Trapped;
+
+pub async fn trap(
+    State(state): State<AppState>,
+    session: SessionData,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let honeypots = state.traps.honeypots();
+    let path = req.uri().path().to_string();
+    let ip = peer.ip().to_string();
+    let exempt = session.auth_state.is_authenticated() || state.greylist.is_allowlisted(&ip);
+
+    if !exempt && state.traps.is_honeypot(&path) {
+        let user_agent = req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("-");
+        let evidence = format!(
+            "requested {path} (user-agent family: {})",
+            crate::privacy::ua_family(user_agent)
+        );
+        let expires =
+            DateTime::<Utc>::from_timestamp(Utc::now().timestamp() + GREYLIST_TTL_DAYS * 86_400, 0)
+                .unwrap_or_else(Utc::now);
+        match GreylistDao::upsert_auto(&state.pool, &ip, HONEYPOT_REASON, Some(&evidence), expires)
+            .await
+        {
+            Ok(()) => info!("honeypot: greylisted {ip} ({evidence})"),
+            Err(e) => warn!("honeypot: failed greylisting {ip}: {e:?}"),
+        }
+        // Served the same cat-404 anything unknown gets — nothing tells it it was caught —
+        // and tolled from its very next request, not the next sweep refresh.
+        let mut resp = with_honeypots(honeypots, next.run(req)).await;
+        state.greylist.insert(&ip);
+        resp.extensions_mut().insert(Trapped);
+        return resp;
+    }
+
+    if !exempt
+        && is_signature_path(&path)
+        && let Some(mut resp) = state.traps.tarpit()
+    {
+        resp.extensions_mut().insert(Trapped);
+        return resp;
+    }
+
+    with_honeypots(honeypots, next.run(req)).await
+}
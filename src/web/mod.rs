@@ -1,5 +1,6 @@
 pub mod app_error;
 pub mod app_state;
+pub mod audit;
 pub mod authentication_state;
 pub mod error_page;
 pub mod features;
@@ -7,8 +8,11 @@ pub mod html_template;
 pub mod htmx_responses;
 pub mod markdown;
 pub mod middleware;
+pub mod rate_limit;
+pub mod request_log_writer;
 pub mod responder;
 pub mod router;
 mod session;
 mod static_content;
+pub mod trap;
 pub mod util;
@@ -0,0 +1,208 @@
+//! In-memory token-bucket rate limiting per route group. The greylist only acts
+//! after its periodic sweep reads `request_log`; this bites on the request itself,
+//! so a burst of login ceremonies, uploads or MCP calls is throttled in the
+//! meantime. Buckets are keyed by who is calling — an API key, else a signed-in
+//! user, else the client IP — so a household behind one NAT address doesn't share
+//! one signed-in budget. The limits come from `Settings.rate_limits`.
+//!
+//! Like the greylist snapshot, it's a per-instance `Arc` (not a process global),
+//! so each test server starts with empty buckets.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use http::Method;
+
+use crate::settings::{RateLimit, RateLimits};
+
+/// Past this many live buckets, a check first drops the ones that have refilled
+/// (an idle caller's full bucket is indistinguishable from no bucket).
+const PRUNE_ABOVE_BUCKETS: usize = 10_000;
+
+/// The route families that get their own limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteGroup {
+    Login,
+    MediaUpload,
+    Mcp,
+}
+
+impl RouteGroup {
+    /// The group a request falls in, or `None` for an unlimited route.
+    pub fn classify(method: &Method, path: &str) -> Option<RouteGroup> {
+        let write = !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+        if path.starts_with("/login/") {
+            Some(RouteGroup::Login)
+        } else if path == "/mcp" || path.starts_with("/mcp/") {
+            Some(RouteGroup::Mcp)
+        } else if write
+            && (path == "/media" || path.starts_with("/media/") || path.starts_with("/admin/media"))
+        {
+            Some(RouteGroup::MediaUpload)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RouteGroup::Login => "login",
+            RouteGroup::MediaUpload => "media_upload",
+            RouteGroup::Mcp => "mcp",
+        }
+    }
+
+    fn limit(self, limits: &RateLimits) -> Option<RateLimit> {
+        match self {
+            RouteGroup::Login => limits.login,
+            RouteGroup::MediaUpload => limits.media_upload,
+            RouteGroup::Mcp => limits.mcp,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    /// Top the bucket up for the time since its last refill.
+    fn refill(&mut self, limit: RateLimit, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+        let rate = f64::from(limit.per_minute) / 60.0;
+        self.tokens = (self.tokens + elapsed * rate).min(f64::from(limit.burst));
+        self.refilled_at = now;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    limits: RateLimits,
+    buckets: Arc<Mutex<HashMap<(RouteGroup, String), Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            limits,
+            buckets: Arc::default(),
+        }
+    }
+
+    /// Take one token from `key`'s bucket in `group`. `Err` carries how long until
+    /// the next token — the `Retry-After`. An unlimited group always passes.
+    pub fn check(&self, group: RouteGroup, key: &str) -> Result<(), Duration> {
+        self.check_at(group, key, Instant::now())
+    }
+
+    fn check_at(&self, group: RouteGroup, key: &str, now: Instant) -> Result<(), Duration> {
+        let Some(limit) = group.limit(&self.limits) else {
+            return Ok(());
+        };
+        // A zero limit shuts the group: nothing would ever refill.
+        if limit.burst == 0 || limit.per_minute == 0 {
+            return Err(Duration::from_secs(60));
+        }
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > PRUNE_ABOVE_BUCKETS {
+            buckets.retain(|(g, _), b| {
+                let Some(l) = g.limit(&self.limits) else {
+                    return false;
+                };
+                b.refill(l, now);
+                b.tokens < f64::from(l.burst)
+            });
+        }
+        let bucket = buckets
+            .entry((group, key.to_string()))
+            .or_insert_with(|| Bucket {
+                tokens: f64::from(limit.burst),
+                refilled_at: now,
+            });
+        bucket.refill(limit, now);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let rate = f64::from(limit.per_minute) / 60.0;
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn route_groups() {
+        let classify = RouteGroup::classify;
+        assert_eq!(
+            classify(&Method::POST, "/login/finish_authentication"),
+            Some(RouteGroup::Login)
+        );
+        assert_eq!(
+            classify(&Method::GET, "/login/get_auth_opts"),
+            Some(RouteGroup::Login)
+        );
+        assert_eq!(
+            classify(&Method::GET, "/login"),
+            None,
+            "the sign-in page itself"
+        );
+        assert_eq!(classify(&Method::POST, "/mcp"), Some(RouteGroup::Mcp));
+        assert_eq!(
+            classify(&Method::POST, "/media"),
+            Some(RouteGroup::MediaUpload)
+        );
+        assert_eq!(
+            classify(&Method::POST, "/admin/media/import/upload"),
+            Some(RouteGroup::MediaUpload)
+        );
+        assert_eq!(
+            classify(&Method::GET, "/media/file/abc"),
+            None,
+            "reads are unlimited"
+        );
+        assert_eq!(classify(&Method::GET, "/"), None);
+    }
+
+    #[test]
+    fn bucket_drains_then_refills() {
+        let limits = RateLimits {
+            login: Some(RateLimit {
+                burst: 2,
+                per_minute: 60,
+            }),
+            media_upload: None,
+            mcp: None,
+        };
+        let limiter = RateLimiter::new(limits);
+        let t0 = Instant::now();
+        assert!(limiter.check_at(RouteGroup::Login, "ip:a", t0).is_ok());
+        assert!(limiter.check_at(RouteGroup::Login, "ip:a", t0).is_ok());
+        let retry = limiter.check_at(RouteGroup::Login, "ip:a", t0).unwrap_err();
+        assert!(
+            (retry.as_secs_f64() - 1.0).abs() < 1e-9,
+            "one token a second"
+        );
+        assert!(
+            limiter.check_at(RouteGroup::Login, "ip:b", t0).is_ok(),
+            "per key"
+        );
+
+        let later = t0 + Duration::from_secs(1);
+        assert!(limiter.check_at(RouteGroup::Login, "ip:a", later).is_ok());
+        assert!(limiter.check_at(RouteGroup::Login, "ip:a", later).is_err());
+
+        // An unlimited group never throttles.
+        for _ in 0..100 {
+            assert!(limiter.check_at(RouteGroup::Mcp, "ip:a", t0).is_ok());
+        }
+    }
+}
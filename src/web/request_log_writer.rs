@@ -0,0 +1,395 @@
+//! Batched, back-pressured writer for `request_log`. The logging middleware used to
+//! `tokio::spawn` one single-row INSERT per request; under a scanner burst that's hundreds
+//! of tiny write transactions a second contending for SQLite's one writer lock (the
+//! `SQLITE_BUSY` trigger the greylist design doc named). Now the middleware only enqueues
+//! onto a bounded channel, and ONE writer task commits multi-row transactions — every
+//! `batch_rows` rows or every `flush_every`, whichever comes first. A single consumer
+//! draining a FIFO in order means rows land in the order they were logged.
+//!
+//! Logging still never adds latency to nor fails a response. When the queue backs up the
+//! writer sheds load instead of blocking: past `shed_above` queued rows only every
+//! `sample_every`th row is admitted (so a flood still leaves a sampled trace for the
+//! analytics and the greylist sweep), and a completely full queue drops outright. Both are
+//! counted, next to the rows flushed; `/admin/logs` shows the counters.
+//!
+//! Like the rate limiter, it's a per-instance `Arc` (not a process global), so each test
+//! server gets its own writer. `shutdown` drains whatever is queued before returning — the
+//! coordinator calls it on SIGTERM / ctrl-c so a restart doesn't lose the tail.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use sqlx::SqlitePool;
+use sqlx::types::chrono::Utc;
+use tokio::sync::{Notify, mpsc};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{Span, warn};
+
+use crate::db::dao::request_log::{NewRequestLog, RequestLogDao};
+use crate::privacy::VisitorKey;
+use crate::telemetry;
+use crate::web::util::campaign::Budget;
+
+/// A queued row, with the exported request span it was logged in (trace export only).
+type Queued = (NewRequestLog, Option<Span>);
+
+/// Queue and batching limits. The defaults hold several seconds of a heavy scanner burst
+/// while keeping a row's wait for its commit to a quarter second.
+#[derive(Clone, Copy, Debug)]
+pub struct WriterConfig {
+    /// Rows the queue holds before dropping outright.
+    pub capacity: usize,
+    /// Commit as soon as this many rows are pending.
+    pub batch_rows: usize,
+    /// Commit whatever is pending at least this often.
+    pub flush_every: Duration,
+    /// Past this many queued rows, start sampling.
+    pub shed_above: usize,
+    /// While sampling, admit one row in this many.
+    pub sample_every: u64,
+}
+
+impl Default for WriterConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 8192,
+            batch_rows: 256,
+            flush_every: Duration::from_millis(250),
+            shed_above: 6144,
+            sample_every: 10,
+        }
+    }
+}
+
+/// A point-in-time read of the writer's counters (totals since boot).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WriterCounters {
+    /// Rows committed to `request_log`.
+    pub flushed: u64,
+    /// Rows shed by the sampler, refused by a full (or shut) queue, or lost to a failed commit.
+    pub dropped: u64,
+    /// Rows waiting in the queue right now.
+    pub queued: usize,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    flushed: AtomicU64,
+    dropped: AtomicU64,
+    /// Rows offered while over `shed_above` — the sampler's clock.
+    sampled: AtomicU64,
+}
+
+#[derive(Clone, Debug)]
+pub struct RequestLogWriter {
+    tx: mpsc::Sender<Queued>,
+    config: WriterConfig,
+    counters: Arc<Counters>,
+    shutdown: Arc<Notify>,
+    task: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl RequestLogWriter {
+    /// Start the writer task on `pool`.
+    pub fn spawn(pool: SqlitePool, config: WriterConfig) -> Self {
+        let (writer, rx) = Self::unstarted(config);
+        writer.start(pool, rx);
+        writer
+    }
+
+    fn unstarted(config: WriterConfig) -> (Self, mpsc::Receiver<Queued>) {
+        let (tx, rx) = mpsc::channel(config.capacity.max(1));
+        let writer = Self {
+            tx,
+            config,
+            counters: Arc::default(),
+            shutdown: Arc::default(),
+            task: Arc::default(),
+        };
+        (writer, rx)
+    }
+
+    fn start(&self, pool: SqlitePool, rx: mpsc::Receiver<Queued>) {
+        let task = tokio::spawn(run(
+            pool,
+            rx,
+            self.config,
+            self.counters.clone(),
+            self.shutdown.clone(),
+        ));
+        *self.task.lock().unwrap() = Some(task);
+    }
+
+    /// Enqueue one row. Never waits: a backed-up queue samples, a full one drops. Called
+    /// inside the request span, which the queue holds so the committed row's id can go on it.
+    pub fn log(&self, entry: NewRequestLog) {
+        let queued = self.queued();
+        if queued >= self.config.shed_above {
+            let n = self.counters.sampled.fetch_add(1, Ordering::Relaxed);
+            if n % self.config.sample_every.max(1) != 0 {
+                self.dropped(1);
+                return;
+            }
+        }
+        if self
+            .tx
+            .try_send((entry, telemetry::request_span()))
+            .is_err()
+        {
+            self.dropped(1);
+        }
+    }
+
+    pub fn counters(&self) -> WriterCounters {
+        WriterCounters {
+            flushed: self.counters.flushed.load(Ordering::Relaxed),
+            dropped: self.counters.dropped.load(Ordering::Relaxed),
+            queued: self.queued(),
+        }
+    }
+
+    /// Stop accepting rows, commit everything already queued, and wait for the writer task
+    /// to finish. Later `log` calls count as dropped. Idempotent.
+    pub async fn shutdown(&self) {
+        self.shutdown.notify_one();
+        let task = self.task.lock().unwrap().take();
+        if let Some(task) = task
+            && let Err(e) = task.await
+        {
+            warn!("request_log writer task failed: {e}");
+        }
+    }
+
+    fn queued(&self) -> usize {
+        self.tx.max_capacity() - self.tx.capacity()
+    }
+
+    fn dropped(&self, n: u64) {
+        note_dropped(&self.counters, n);
+    }
+}
+
+/// Count dropped rows, warning at each power of two so a sustained flood logs a handful of
+/// lines rather than one per row.
+fn note_dropped(counters: &Counters, n: u64) {
+    let before = counters.dropped.fetch_add(n, Ordering::Relaxed);
+    let after = before + n;
+    if (before + 1..=after).any(u64::is_power_of_two) {
+        warn!("request_log writer is shedding load: {after} rows dropped so far");
+    }
+}
+
+async fn run(
+    pool: SqlitePool,
+    mut rx: mpsc::Receiver<Queued>,
+    config: WriterConfig,
+    counters: Arc<Counters>,
+    shutdown: Arc<Notify>,
+) {
+    // Rows are written with their visitor key (see `privacy`). Without the key they're
+    // still written; the privacy task backfills them.
+    let key = match VisitorKey::load(&pool).await {
+        Ok(key) => Some(key),
+        Err(e) => {
+            warn!("request_log writer: no visitor key, writing rows without it: {e:?}");
+            None
+        }
+    };
+    let key = key.as_ref();
+    // Campaigns are capped per day (see `web::util::campaign`); a restart picks the day's
+    // count up where it left off.
+    let today = today();
+    let seen = RequestLogDao::campaigns_on(&pool, &today)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("request_log writer: couldn't read today's campaigns: {e:?}");
+            Vec::new()
+        });
+    let mut campaigns = Budget::new(today, seen);
+    let batch_rows = config.batch_rows.max(1);
+    let mut batch = Vec::with_capacity(batch_rows);
+    let mut tick = tokio::time::interval(config.flush_every);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            biased;
+            _ = shutdown.notified() => break,
+            _ = tick.tick() => flush(&pool, key, &mut campaigns, &mut batch, &counters).await,
+            row = rx.recv() => match row {
+                Some(row) => {
+                    batch.push(row);
+                    if batch.len() >= batch_rows {
+                        flush(&pool, key, &mut campaigns, &mut batch, &counters).await;
+                    }
+                }
+                None => break,
+            },
+        }
+    }
+
+    // Shutdown: refuse new rows, then drain what's already queued.
+    rx.close();
+    while let Some(row) = rx.recv().await {
+        batch.push(row);
+        if batch.len() >= batch_rows {
+            flush(&pool, key, &mut campaigns, &mut batch, &counters).await;
+        }
+    }
+    flush(&pool, key, &mut campaigns, &mut batch, &counters).await;
+}
+
+/// Commit the pending rows as one transaction. A failed commit loses the batch (counted as
+/// dropped) — logging is best-effort, and retrying a poisoned batch would stall the queue.
+async fn flush(
+    pool: &SqlitePool,
+    key: Option<&VisitorKey>,
+    campaigns: &mut Budget,
+    batch: &mut Vec<Queued>,
+    counters: &Counters,
+) {
+    if batch.is_empty() {
+        return;
+    }
+    let (mut rows, spans): (Vec<NewRequestLog>, Vec<Option<Span>>) = batch.drain(..).unzip();
+    if let Some(key) = key {
+        for row in rows.iter_mut().filter(|r| r.visitor.is_none()) {
+            row.visitor = row.ip.as_deref().and_then(|ip| key.of(ip).ok());
+        }
+    }
+    let today = today();
+    for row in &mut rows {
+        row.campaign = row.campaign.take().map(|c| campaigns.admit(&today, c));
+    }
+    let n = rows.len() as u64;
+    match RequestLogDao::insert_batch(pool, &rows).await {
+        Ok(ids) => {
+            counters.flushed.fetch_add(n, Ordering::Relaxed);
+            for (id, span) in ids.into_iter().zip(spans) {
+                if let Some(span) = span {
+                    telemetry::record_request_log_id(&span, id);
+                }
+            }
+        }
+        Err(e) => {
+            warn!("failed to write {n} rows to request_log: {e}");
+            note_dropped(counters, n);
+        }
+    }
+}
+
+/// The UTC day, as `request_log.ts` dates it.
+fn today() -> String {
+    Utc::now().format("%Y-%m-%d").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(path: &str) -> NewRequestLog {
+        NewRequestLog {
+            method: "GET".into(),
+            path: path.into(),
+            status: 200,
+            ip: Some("203.0.113.1".into()),
+            user_agent: None,
+            referer: None,
+            duration_ms: 0,
+            is_bot: false,
+            challenged: false,
+            rate_limited: false,
+            visitor: None,
+            campaign: None,
+        }
+    }
+
+    async fn logged_paths(pool: &SqlitePool) -> Vec<String> {
+        sqlx::query_scalar!("SELECT path FROM request_log ORDER BY id")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn rows_land_in_order_across_batches(pool: SqlitePool) {
+        let writer = RequestLogWriter::spawn(
+            pool.clone(),
+            WriterConfig {
+                batch_rows: 4,
+                flush_every: Duration::from_millis(5),
+                ..Default::default()
+            },
+        );
+        let paths: Vec<String> = (0..10).map(|i| format!("/p{i}")).collect();
+        for p in &paths {
+            writer.log(row(p));
+        }
+
+        // Two full batches commit on size, the last two on the timer.
+        for _ in 0..200 {
+            if writer.counters().flushed == 10 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(writer.counters().flushed, 10);
+        assert_eq!(logged_paths(&pool).await, paths);
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn shutdown_flushes_the_queue_then_refuses(pool: SqlitePool) {
+        // Neither limit would ever fire on its own: only the shutdown drain writes these.
+        let writer = RequestLogWriter::spawn(
+            pool.clone(),
+            WriterConfig {
+                batch_rows: 1000,
+                flush_every: Duration::from_secs(3600),
+                ..Default::default()
+            },
+        );
+        for i in 0..5 {
+            writer.log(row(&format!("/p{i}")));
+        }
+        writer.shutdown().await;
+        assert_eq!(logged_paths(&pool).await.len(), 5);
+        assert_eq!(writer.counters().flushed, 5);
+
+        writer.log(row("/late"));
+        writer.shutdown().await;
+        let c = writer.counters();
+        assert_eq!(
+            (c.flushed, c.dropped),
+            (5, 1),
+            "a row after shutdown is dropped"
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::db::database_handle::MIGRATOR")]
+    async fn a_backed_up_queue_samples_then_drops(pool: SqlitePool) {
+        // Writer not started yet, so nothing drains: the queue fills deterministically.
+        let (writer, rx) = RequestLogWriter::unstarted(WriterConfig {
+            capacity: 8,
+            shed_above: 4,
+            sample_every: 3,
+            ..Default::default()
+        });
+        for i in 0..30 {
+            writer.log(row(&format!("/p{i}")));
+        }
+        // p0..p3 fill to the shed mark; then one in three is admitted (p4, p7, p10, p13)
+        // until the queue is full, and everything after that is dropped.
+        let c = writer.counters();
+        assert_eq!((c.queued, c.dropped, c.flushed), (8, 22, 0));
+
+        writer.start(pool.clone(), rx);
+        writer.shutdown().await;
+        assert_eq!(writer.counters().flushed, 8);
+        let expected: Vec<String> = [0, 1, 2, 3, 4, 7, 10, 13]
+            .iter()
+            .map(|i| format!("/p{i}"))
+            .collect();
+        assert_eq!(logged_paths(&pool).await, expected);
+    }
+}
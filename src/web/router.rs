@@ -1,6 +1,7 @@
 use super::{app_state::AppState, features::login::login_router, static_content::static_content};
 use crate::{
     db::dao::crypto_key::CryptoKey,
+    telemetry::MakeRequestSpan,
     web::{
         features::{
             admin::admin_router,
@@ -11,7 +12,7 @@ use crate::{
             three_d::three_d_router,
         },
         middleware::{
-            api_key_auth::api_key_auth, refresh_session_role::refresh_session_role,
+            api_key_auth::api_key_auth, csp::csp, refresh_session_role::refresh_session_role,
             request_log::log_requests, require_admin_for_mutations::require_admin_for_mutations,
         },
     },
@@ -26,7 +27,7 @@ use tower_http::{
         predicate::{DefaultPredicate, NotForContentType, Predicate},
         CompressionLayer,
     },
-    trace::{DefaultMakeSpan, DefaultOnRequest, TraceLayer},
+    trace::{DefaultOnRequest, TraceLayer},
 };
 use tower_livereload::LiveReloadLayer;
 use tower_sessions::{cookie::SameSite, Expiry, SessionManagerLayer};
@@ -53,20 +54,32 @@ pub async fn create_router(app_state: AppState) -> anyhow::Result<Router> {
         .with_signed(key.key()?);
 
     debug!("Making router");
-    let log_pool = app_state.pool.clone();
+    let log_writer = app_state.request_log.clone();
+    // The /metrics request counters (see `crate::metrics`).
+    let metrics = app_state.metrics.clone();
     // API-key middleware needs the full state (the pool) — clone before app_state
     // is moved into `.with_state`.
     let api_key_state = app_state.clone();
     // Live role-recheck middleware (Phase CC) also needs the pool.
     let refresh_state = app_state.clone();
+    // Rate limiting needs the buckets + the operator allowlist.
+    let rate_limit_state = app_state.clone();
+    // The CSP layer reads the report-only rollout flag.
+    let csp_state = app_state.clone();
     // Greylist enforcement (Phase CX) needs the challenge key + the active-set snapshot.
     let greylist_state = app_state.clone();
+    // The tarpit / honeypots need their cap + paths, the pool and the active set.
+    let trap_state = app_state.clone();
     // MCP publishing server (Phase DI, spike): the streamable-http service needs the
     // state at construction; clone before app_state is moved into `.with_state`.
     let mcp_state = app_state.clone();
     let router = Router::new()
         .route("/", get(show_home))
         .nest("/login", login_router())
+        // Self-service account page: a signed-in user's own passkeys and sessions.
+        .nest("/account", crate::web::features::account::account_router())
+        // Passkey re-prompt for step-up-gated actions (require_step_up).
+        .nest("/step-up", crate::web::features::step_up::step_up_router())
         .nest("/pages", pages_router())
         .nest("/projects", projects_router())
         .nest("/3d", three_d_router())
@@ -86,6 +99,13 @@ pub async fn create_router(app_state: AppState) -> anyhow::Result<Router> {
             "/mcp",
             crate::web::features::mcp::mcp_service(mcp_state),
         )
+        // Built-in OpenID Connect provider: household apps sign in with site passkeys.
+        // Discovery sits at the well-known root path; the endpoints live under /oidc.
+        .nest("/oidc", crate::web::features::oidc::oidc_router())
+        .route(
+            "/.well-known/openid-configuration",
+            get(crate::web::features::oidc::discovery),
+        )
         // Greylist bot-toll challenge (Phase CX): the interstitial + its endpoints.
         // Public GETs; the enforcement middleware exempts /challenge/* so a greylisted
         // client can actually reach the toll to solve it.
@@ -96,6 +116,9 @@ pub async fn create_router(app_state: AppState) -> anyhow::Result<Router> {
             "/diagram/{hash}",
             get(crate::web::features::diagram::render_registered_diagram),
         )
+        // Content-Security-Policy violation reports (anonymous POST; see
+        // web/middleware/csp.rs for the policies that point here).
+        .merge(crate::web::features::csp_report::csp_report_routes())
         // /resume + /resume.pdf (the latter generated via weasyprint) — top-level.
         .merge(crate::web::features::resume::resume_routes())
         // Unified Atom feed (blog posts + project pages). `/blog/feed.xml` serves
@@ -104,7 +127,10 @@ pub async fn create_router(app_state: AppState) -> anyhow::Result<Router> {
         // SEO: dynamic sitemap + robots (host-correct Sitemap directive, beta
         // de-indexed) — see web/features/seo.rs.
         .route("/sitemap.xml", get(crate::web::features::seo::sitemap_xml))
-        .route("/robots.txt", get(crate::web::features::seo::robots_txt));
+        .route("/robots.txt", get(crate::web::features::seo::robots_txt))
+        // Prometheus scrape endpoint: admin or `metrics_token` bearer (see
+        // web/features/metrics.rs).
+        .route("/metrics", get(crate::web::features::metrics::show_metrics));
 
     // Debug-only test-login seam (absent from release builds = prod).
     #[cfg(debug_assertions)]
@@ -130,7 +156,16 @@ pub async fn create_router(app_state: AppState) -> anyhow::Result<Router> {
 
     let router = router.layer(
         ServiceBuilder::new()
-            // OUTERMOST: log every request + its final response status. Layered
+            // The request span, outermost so everything below runs inside it: the request
+            // log stamps its row onto the exported trace (see `telemetry`), and a panic-500
+            // still closes the span as a failure.
+            .layer(
+                TraceLayer::new_for_http()
+                    .make_span_with(MakeRequestSpan)
+                    .on_request(DefaultOnRequest::new().level(Level::DEBUG))
+                    .on_response(()),
+            )
+            // Log every request + its final response status. Layered
             // OUTER to CatchPanicLayer ON PURPOSE (CQ.1.1) — a handler panic unwinds
             // PAST this layer's post-`next.run` insert, so inside the catch it would
             // never see (nor log) the resulting 500. Outside it, `next.run` returns
@@ -138,19 +173,23 @@ pub async fn create_router(app_state: AppState) -> anyhow::Result<Router> {
             // so panic-500s show up in the access log + analytics instead of being an
             // invisible blind spot. (log_requests does no fallible work that would
             // panic, so nothing above it needs the catch.)
-            .layer(axum::middleware::from_fn_with_state(log_pool, log_requests))
+            .layer(axum::middleware::from_fn_with_state(log_writer, log_requests))
+            // Per-route request counts + latency for /metrics. Outer to CatchPanicLayer for
+            // the same reason as log_requests, so panic-500s are counted too.
+            .layer(axum::middleware::from_fn_with_state(
+                metrics,
+                crate::web::middleware::metrics::track_requests,
+            ))
+            // Content-Security-Policy (per-request nonce + per-route-family policy).
+            // Also OUTER to CatchPanicLayer, so the styled panic 500 still renders
+            // inside the nonce scope and carries the policy.
+            .layer(axum::middleware::from_fn_with_state(csp_state, csp))
             // Turn ANY handler (or inner-middleware) panic into a styled 500 instead
             // of a dropped connection (an uncaught panic resets the connection — a
             // `000`, no response). Belt-and-suspenders behind transform()'s own
             // catch_unwind: no content or handler bug should crash a request or,
             // via the feed (which transforms every post), blank the whole feed.
             .layer(CatchPanicLayer::custom(handle_panic))
-            .layer(
-                TraceLayer::new_for_http()
-                    .make_span_with(DefaultMakeSpan::new().include_headers(true))
-                    .on_request(DefaultOnRequest::new().level(Level::DEBUG))
-                    .on_response(()),
-            )
             .layer(session_layer)
             // API-key auth (Phase CA): resolve `Authorization: Bearer hio_…` and
             // inject an Authenticated SessionData. OUTER to the authz layer so the
@@ -168,6 +207,14 @@ pub async fn create_router(app_state: AppState) -> anyhow::Result<Router> {
                 refresh_state,
                 refresh_session_role,
             ))
+            // Traps: a honeypot hit greylists the IP on the spot; a signature-path probe is
+            // tarpitted. INNER to refresh_session_role (a signed-in caller is never trapped),
+            // OUTER to the greylist toll (a listed scanner's probes are held too). Marked
+            // `Trapped` so the request log stamps is_bot.
+            .layer(axum::middleware::from_fn_with_state(
+                trap_state,
+                crate::web::middleware::trap::trap,
+            ))
             // Greylist toll (Phase CX): a greylisted IP that isn't exempt / cleared /
             // authenticated gets the 429 interstitial. INNER to refresh_session_role (so
             // SessionData reflects the live role / API key), OUTER to the authz layer. Its
@@ -176,6 +223,20 @@ pub async fn create_router(app_state: AppState) -> anyhow::Result<Router> {
                 greylist_state,
                 crate::web::middleware::greylist_challenge::greylist_challenge,
             ))
+            // Rate limiting: per-route-group token buckets keyed by API key / user /
+            // IP. INNER to the identity layers (so a signed-in caller gets their own
+            // budget), OUTER to the authz layer (so an anonymous flood is cut first).
+            // A limited 429 is marked so the request log stamps `rate_limited`.
+            .layer(axum::middleware::from_fn_with_state(
+                rate_limit_state,
+                crate::web::middleware::rate_limit::rate_limit,
+            ))
+            // Campaign links: a tagged GET (`utm_*` / `ref`) is redirected to its clean
+            // URL. INNER to log_requests, which records the tag off the original request,
+            // and to the rate limiter and toll, so tagged floods are cut like any other.
+            .layer(axum::middleware::from_fn(
+                crate::web::middleware::campaign::strip_campaign,
+            ))
             // Fail-closed authz (Phase E): GET/HEAD/OPTIONS public; every other
             // method requires admin by default (except the anonymous auth
             // ceremony). INNER to session_layer so SessionData is populated.
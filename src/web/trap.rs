@@ -0,0 +1,159 @@
+//! Traps for scanners: a tarpit for signature-path probes and honeypot URLs.
+//!
+//! **Tarpit.** A `/wp-login.php`-style probe ([`is_signature_path`]) normally gets the
+//! cat-404 at full speed, and the scanner moves straight on to its next thousand paths.
+//! With `tarpit` configured it gets the same 404 status — so R1 and the escalation count it
+//! exactly as before — but the body drips out a few bytes at a time, holding the scanner's
+//! connection for `hold_secs`. A held probe costs this server one idle socket and a sleeping
+//! task; a global semaphore caps how many are held at once, and past the cap a probe just
+//! gets the plain 404.
+//!
+//! **Honeypots.** `honeypot_paths` are linked invisibly from every page (`base.html`) and
+//! disallowed in `robots.txt`, so nothing a human clicks or a polite crawler fetches ever
+//! requests one. The first request for one greylists the IP on the spot — no waiting for
+//! the sweep — with the path and user agent as evidence. The operator allowlist and an
+//! authenticated session are exempt.
+//!
+//! Like the rate limiter, it's a per-instance `Arc`, so each test server has its own cap.
+//!
+//! [`is_signature_path`]: crate::greylist::detection::is_signature_path
+
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::{Body, Bytes};
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::settings::Tarpit;
+
+/// The greylist reason a honeypot hit is recorded under.
+pub const HONEYPOT_REASON: &str = "honeypot: requested a hidden trap URL";
+
+/// What the tarpit drips: an HTML comment, so a client that renders it shows nothing.
+const DRIP: &[u8] = b"<!-- -->\n";
+
+tokio::task_local! {
+    static HONEYPOTS: Arc<[String]>;
+}
+
+/// This request's honeypot paths, for the invisible links in `base.html`. Empty outside a
+/// request (or with none configured).
+pub fn honeypot_paths() -> Vec<String> {
+    HONEYPOTS
+        .try_with(|paths| paths.to_vec())
+        .unwrap_or_default()
+}
+
+/// Run `fut` with the honeypot paths in scope for [`honeypot_paths`].
+pub async fn with_honeypots<F: Future>(paths: Arc<[String]>, fut: F) -> F::Output {
+    HONEYPOTS.scope(paths, fut).await
+}
+
+#[derive(Clone, Debug)]
+pub struct Traps {
+    tarpit: Option<Tarpit>,
+    permits: Arc<Semaphore>,
+    honeypots: Arc<[String]>,
+}
+
+impl Traps {
+    /// `honeypots` are request paths; one written without its leading `/` gets one.
+    pub fn new(tarpit: Option<Tarpit>, honeypots: &[String]) -> Self {
+        let honeypots: Vec<String> = honeypots
+            .iter()
+            .map(|p| p.trim())
+            .filter(|p| !p.is_empty())
+            .map(|p| {
+                if p.starts_with('/') {
+                    p.to_string()
+                } else {
+                    format!("/{p}")
+                }
+            })
+            .collect();
+        Self {
+            permits: Arc::new(Semaphore::new(tarpit.map_or(0, |t| t.max_concurrent))),
+            tarpit,
+            honeypots: honeypots.into(),
+        }
+    }
+
+    pub fn honeypots(&self) -> Arc<[String]> {
+        self.honeypots.clone()
+    }
+
+    pub fn is_honeypot(&self, path: &str) -> bool {
+        self.honeypots.iter().any(|p| p == path)
+    }
+
+    /// A slow-drip 404 if the tarpit is on and under its cap, else `None` (serve normally).
+    pub fn tarpit(&self) -> Option<Response> {
+        let config = self.tarpit?;
+        let permit = self.permits.clone().try_acquire_owned().ok()?;
+        let body = drip(permit, config);
+        Some(
+            (
+                StatusCode::NOT_FOUND,
+                [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+                body,
+            )
+                .into_response(),
+        )
+    }
+}
+
+/// The tarpit body: one [`DRIP`] now, then one every `drip_every_ms` until `hold_secs` is
+/// up. The permit rides in the stream, so it's released when the body finishes OR the
+/// client hangs up (the stream is dropped).
+fn drip(permit: OwnedSemaphorePermit, config: Tarpit) -> Body {
+    let every = Duration::from_millis(config.drip_every_ms.max(1));
+    let chunks = (config.hold_secs * 1_000 / config.drip_every_ms.max(1)).max(1);
+    let stream = futures::stream::unfold((0u64, permit), move |(sent, permit)| async move {
+        if sent >= chunks {
+            return None;
+        }
+        if sent > 0 {
+            tokio::time::sleep(every).await;
+        }
+        Some((
+            Ok::<_, Infallible>(Bytes::from_static(DRIP)),
+            (sent + 1, permit),
+        ))
+    });
+    Body::from_stream(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn honeypots_normalize_and_match_exactly() {
+        let traps = Traps::new(None, &["/staff-directory".into(), " old-admin ".into()]);
+        assert!(traps.is_honeypot("/staff-directory"));
+        assert!(traps.is_honeypot("/old-admin"));
+        assert!(!traps.is_honeypot("/staff-directory/x"));
+        assert!(traps.tarpit().is_none(), "no tarpit configured");
+    }
+
+    #[tokio::test]
+    async fn the_tarpit_holds_at_most_its_cap() {
+        let traps = Traps::new(
+            Some(Tarpit {
+                max_concurrent: 1,
+                drip_every_ms: 10,
+                hold_secs: 1,
+            }),
+            &[],
+        );
+        let held = traps.tarpit().expect("under the cap");
+        assert_eq!(held.status(), StatusCode::NOT_FOUND);
+        assert!(traps.tarpit().is_none(), "at the cap");
+
+        drop(held);
+        assert!(traps.tarpit().is_some(), "a dropped body frees its slot");
+    }
+}
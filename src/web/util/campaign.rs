@@ -0,0 +1,267 @@
+//! Campaign attribution. Links shared off-site — on the résumé PDF, LinkedIn, in an email
+//! — mostly arrive with no referer, so the referer analysis (`referer`) counts them as
+//! "direct". Tagging the link with `utm_source` / `utm_medium` / `utm_campaign` (or a bare
+//! `ref`) says where it was shared: the request log records the tag, and
+//! `middleware::campaign` redirects to the same URL without it, so the tagged form isn't
+//! what gets bookmarked, passed on or indexed.
+//!
+//! Tags are visitor-supplied, so cardinality is bounded twice over: each value is held to
+//! a small alphabet and [`MAX_LEN`], and at most [`MAX_PER_DAY`] distinct campaigns are
+//! recorded a day — past that, new ones are logged as [`OVERFLOW`]. A flood of made-up
+//! tags can't grow the panel or the rollups past that.
+
+use std::collections::HashSet;
+
+use url::{Url, form_urlencoded};
+
+use crate::db::dao::request_log::Campaign;
+
+/// The query parameters that carry a campaign, all stripped by the redirect. Only the
+/// first three (and `ref`) are recorded; `utm_term` / `utm_content` / `utm_id` vary per
+/// ad or per link and would only add cardinality.
+pub const PARAMS: &[&str] = &[
+    "utm_source",
+    "utm_medium",
+    "utm_campaign",
+    "utm_term",
+    "utm_content",
+    "utm_id",
+    "ref",
+];
+
+/// Longest recorded value, in characters; longer ones are cut.
+pub const MAX_LEN: usize = 40;
+
+/// Distinct campaigns recorded per UTC day before new ones fold into [`OVERFLOW`].
+pub const MAX_PER_DAY: usize = 100;
+
+/// The source recorded for a campaign over the day's budget. Outside the normalized
+/// alphabet, so no real tag can collide with it.
+pub const OVERFLOW: &str = "(other)";
+
+/// The source recorded when a link has a medium or campaign but no source.
+const NOT_SET: &str = "(not set)";
+
+/// Lowercase, with anything outside `[a-z0-9._-]` turned into a single `-`, trimmed of
+/// dashes and cut to [`MAX_LEN`]. `None` if nothing's left.
+pub fn normalize(raw: &str) -> Option<String> {
+    let mut out = String::new();
+    for c in raw.trim().chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() || matches!(c, '.' | '_') {
+            out.push(c);
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+        if out.len() >= MAX_LEN {
+            break;
+        }
+    }
+    let out = out.trim_end_matches('-');
+    (!out.is_empty()).then(|| out.to_string())
+}
+
+/// The campaign a request's query string carries, if any. The first usable value of a
+/// repeated parameter wins; `utm_source` wins over `ref`.
+pub fn from_query(query: &str) -> Option<Campaign> {
+    let (mut source, mut referrer, mut medium, mut name) = (None, None, None, None);
+    for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+        let slot = match key.as_ref() {
+            "utm_source" => &mut source,
+            "ref" => &mut referrer,
+            "utm_medium" => &mut medium,
+            "utm_campaign" => &mut name,
+            _ => continue,
+        };
+        if slot.is_none() {
+            *slot = normalize(&value);
+        }
+    }
+    let source = source.or(referrer);
+    if source.is_none() && medium.is_none() && name.is_none() {
+        return None;
+    }
+    Some(Campaign {
+        source: source.unwrap_or_else(|| NOT_SET.to_string()),
+        medium,
+        name,
+    })
+}
+
+/// `query` without its campaign parameters, the others kept as they were sent. `None` when
+/// there were none to strip.
+pub fn strip(query: &str) -> Option<String> {
+    let mut stripped = false;
+    let kept: Vec<&str> = query
+        .split('&')
+        .filter(|pair| {
+            let is_campaign = form_urlencoded::parse(pair.as_bytes())
+                .next()
+                .is_some_and(|(key, _)| PARAMS.contains(&key.as_ref()));
+            stripped |= is_campaign;
+            !is_campaign && !pair.is_empty()
+        })
+        .collect();
+    stripped.then(|| kept.join("&"))
+}
+
+/// `url` tagged with `campaign` — the share links the page editor mints.
+pub fn tag(mut url: Url, campaign: &Campaign) -> String {
+    {
+        let mut query = url.query_pairs_mut();
+        query.append_pair("utm_source", &campaign.source);
+        if let Some(medium) = &campaign.medium {
+            query.append_pair("utm_medium", medium);
+        }
+        if let Some(name) = &campaign.name {
+            query.append_pair("utm_campaign", name);
+        }
+    }
+    url.to_string()
+}
+
+/// The day's distinct campaigns so far, held by the request-log writer (the one place
+/// every row passes through, in order).
+#[derive(Debug)]
+pub struct Budget {
+    day: String,
+    seen: HashSet<Campaign>,
+}
+
+impl Budget {
+    /// Starting on `day` (`YYYY-MM-DD`) with the campaigns already logged that day.
+    pub fn new(day: String, seen: impl IntoIterator<Item = Campaign>) -> Self {
+        Self {
+            day,
+            seen: seen.into_iter().filter(|c| c.source != OVERFLOW).collect(),
+        }
+    }
+
+    /// `campaign` as it should be recorded on `day`: itself if it's been seen today or
+    /// there's still room, otherwise the [`OVERFLOW`] bucket.
+    pub fn admit(&mut self, day: &str, campaign: Campaign) -> Campaign {
+        if self.day != day {
+            self.day = day.to_string();
+            self.seen.clear();
+        }
+        if self.seen.contains(&campaign) {
+            return campaign;
+        }
+        if self.seen.len() >= MAX_PER_DAY {
+            return Campaign {
+                source: OVERFLOW.to_string(),
+                medium: None,
+                name: None,
+            };
+        }
+        self.seen.insert(campaign.clone());
+        campaign
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn campaign(source: &str, medium: Option<&str>, name: Option<&str>) -> Campaign {
+        Campaign {
+            source: source.to_string(),
+            medium: medium.map(String::from),
+            name: name.map(String::from),
+        }
+    }
+
+    #[test]
+    fn values_are_normalized_and_bounded() {
+        assert_eq!(normalize(" LinkedIn ").as_deref(), Some("linkedin"));
+        assert_eq!(
+            normalize("Spring Job Hunt!!").as_deref(),
+            Some("spring-job-hunt")
+        );
+        assert_eq!(normalize("résumé_pdf").as_deref(), Some("r-sum-_pdf"));
+        assert_eq!(normalize("<script>").as_deref(), Some("script"));
+        assert_eq!(normalize(" -- "), None);
+        assert_eq!(normalize(&"a".repeat(500)).map(|v| v.len()), Some(MAX_LEN));
+    }
+
+    #[test]
+    fn query_parameters_become_a_campaign() {
+        assert_eq!(
+            from_query("utm_source=LinkedIn&utm_medium=social&utm_campaign=Job+Hunt&page=2"),
+            Some(campaign("linkedin", Some("social"), Some("job-hunt")))
+        );
+        assert_eq!(
+            from_query("ref=resume-pdf&utm_source="),
+            Some(campaign("resume-pdf", None, None)),
+            "an empty utm_source falls back to ref"
+        );
+        assert_eq!(
+            from_query("utm_source=a&utm_source=b"),
+            Some(campaign("a", None, None))
+        );
+        assert_eq!(
+            from_query("utm_campaign=launch"),
+            Some(campaign(NOT_SET, None, Some("launch")))
+        );
+        assert_eq!(
+            from_query("utm_term=rust&edit=1"),
+            None,
+            "term alone isn't recorded"
+        );
+        assert_eq!(from_query("q=utm_source"), None);
+    }
+
+    #[test]
+    fn strip_keeps_the_other_parameters_verbatim() {
+        assert_eq!(
+            strip("edit=1&utm_source=x&q=a%20b&utm_campaign=y").as_deref(),
+            Some("edit=1&q=a%20b")
+        );
+        assert_eq!(strip("utm_source=x&utm_term=y").as_deref(), Some(""));
+        assert_eq!(
+            strip("utm%5Fsource=x").as_deref(),
+            Some(""),
+            "an encoded key too"
+        );
+        assert_eq!(strip("q=utm_source&page=2"), None, "nothing to strip");
+    }
+
+    #[test]
+    fn tagged_urls_carry_the_campaign() {
+        let url = Url::parse("https://hotchkiss.io/pages/resume").unwrap();
+        assert_eq!(
+            tag(url, &campaign("linkedin", None, Some("job-hunt"))),
+            "https://hotchkiss.io/pages/resume?utm_source=linkedin&utm_campaign=job-hunt"
+        );
+        let tagged = tag(
+            Url::parse("https://hotchkiss.io/blog/post").unwrap(),
+            &campaign("newsletter", Some("email"), None),
+        );
+        assert_eq!(
+            from_query(Url::parse(&tagged).unwrap().query().unwrap()),
+            Some(campaign("newsletter", Some("email"), None)),
+            "a minted link parses back to the same campaign"
+        );
+    }
+
+    #[test]
+    fn the_daily_budget_folds_new_campaigns_into_overflow() {
+        let day = "2026-06-01";
+        let seeded = (0..MAX_PER_DAY - 1).map(|i| campaign(&format!("s{i}"), None, None));
+        let mut budget = Budget::new(day.to_string(), seeded);
+
+        let last = campaign("last-one", None, None);
+        assert_eq!(budget.admit(day, last.clone()), last, "the final slot");
+        let late = campaign("late", Some("email"), None);
+        assert_eq!(budget.admit(day, late.clone()).source, OVERFLOW);
+        assert_eq!(
+            budget.admit(day, campaign("s0", None, None)).source,
+            "s0",
+            "already-seen campaigns keep recording"
+        );
+        assert_eq!(
+            budget.admit("2026-06-02", late.clone()),
+            late,
+            "a new day starts a new budget"
+        );
+    }
+}
@@ -1,3 +1,4 @@
+pub mod campaign;
 pub mod category;
 pub mod deserialize;
 pub mod host;
@@ -23,7 +23,8 @@ use chromiumoxide::cdp::browser_protocol::input::{
     DispatchMouseEventParams, DispatchMouseEventType, MouseButton,
 };
 use chromiumoxide::cdp::browser_protocol::web_authn::{
-    AddVirtualAuthenticatorParams, AuthenticatorProtocol, AuthenticatorTransport, EnableParams,
+    AddVirtualAuthenticatorParams, AuthenticatorId, AuthenticatorProtocol,
+    AuthenticatorTransport, EnableParams, RemoveVirtualAuthenticatorParams,
     VirtualAuthenticatorOptions,
 };
 use chromiumoxide::{Browser, BrowserConfig, Page};
@@ -79,7 +80,8 @@ async fn use_mobile_viewport(page: &Page) {
 }
 
 /// Attach a software platform authenticator that auto-completes ceremonies.
-async fn add_virtual_authenticator(page: &Page) {
+/// Returns its id, for a test that swaps one device for another.
+async fn add_virtual_authenticator(page: &Page) -> AuthenticatorId {
     page.execute(EnableParams::default())
         .await
         .expect("WebAuthn.enable");
@@ -94,7 +96,9 @@ async fn add_virtual_authenticator(page: &Page) {
         .expect("VirtualAuthenticatorOptions");
     page.execute(AddVirtualAuthenticatorParams::new(opts))
         .await
-        .expect("WebAuthn.addVirtualAuthenticator");
+        .expect("WebAuthn.addVirtualAuthenticator")
+        .result
+        .authenticator_id
 }
 
 /// Poll the page URL until it no longer contains `/login` (or time out). 45s,
@@ -879,6 +883,182 @@ async fn family_library_gate_login_next_and_entry() {
     drop(server);
 }
 
+/// An invite link carries the whole ceremony: an admin mints a Family invite
+/// (over HTTP, via the test-login seam), a fresh browser registers through
+/// `/login?invite=…` with a real passkey, and the new account lands at Family —
+/// no promotion step. The invite is then spent.
+#[tokio::test]
+async fn invite_link_registration_lands_at_the_invited_role() {
+    let _e2e = e2e_lock().await;
+    let server: TestServer = spawn_test_server().await.expect("spawn harness");
+
+    let admin = reqwest::Client::builder()
+        .cookie_store(true)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    admin.post(server.url("/test/login?role=Admin")).send().await.unwrap();
+    let minted = admin
+        .post(server.url("/admin/invites"))
+        .form(&[("role", "Family"), ("expires_in_days", "1")])
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    let start = minted.find("?invite=").expect("link shown at mint") + "?invite=".len();
+    let token: String = minted[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .collect();
+
+    let (mut browser, handle, profile) = launch().await;
+    let page = browser.new_page("about:blank").await.expect("new page");
+    add_virtual_authenticator(&page).await;
+
+    page.goto(server.url(&format!("/login?invite={token}")))
+        .await
+        .expect("goto invite link");
+    wait_true(&page, "!!document.getElementById('invite-banner')", "invite banner").await;
+    let u = page.find_element("#username").await.expect("#username");
+    u.click().await.expect("focus #username");
+    u.type_str("e2e-grandma").await.expect("type username");
+    click_selector(&page, "button[type=submit]").await;
+    wait_until_left_login(&page).await;
+
+    let role: String =
+        sqlx::query_scalar("SELECT app_role FROM users WHERE display_name = 'e2e-grandma'")
+            .fetch_one(&server.pool)
+            .await
+            .expect("the invited user exists");
+    assert_eq!(role, "Family", "the invite's role must apply at registration");
+    let spent: Option<String> = sqlx::query_scalar("SELECT redeemed_at FROM invites")
+        .fetch_one(&server.pool)
+        .await
+        .unwrap();
+    assert!(spent.is_some(), "the invite is single-use");
+
+    browser.close().await.ok();
+    handle.await.ok();
+    let _ = std::fs::remove_dir_all(&profile);
+    drop(server);
+}
+
+/// Self-service passkeys on /account, end to end: a second device registers a passkey
+/// against the signed-in account, signs in with it (stamping its "last used"), gets
+/// renamed and removed — and the account's last passkey can't be.
+#[tokio::test]
+async fn second_passkey_add_sign_in_rename_remove() {
+    let _e2e = e2e_lock().await;
+    let server: TestServer = spawn_test_server().await.expect("spawn harness");
+    let (mut browser, handle, profile) = launch().await;
+    let page = browser.new_page("about:blank").await.expect("new page");
+
+    let phone = add_virtual_authenticator(&page).await;
+    register_first_admin(&page, &server, "e2e-keys").await;
+    page.goto(server.url("/account")).await.expect("goto /account");
+    let rows_are = |n: usize| format!("document.querySelectorAll('.passkey-row').length === {n}");
+    wait_true(&page, &rows_are(1), "one passkey").await;
+
+    // Swap devices: the second passkey is minted by an authenticator that doesn't hold
+    // the first, as a new laptop or security key would be.
+    page.execute(RemoveVirtualAuthenticatorParams::new(phone))
+        .await
+        .expect("WebAuthn.removeVirtualAuthenticator");
+    add_virtual_authenticator(&page).await;
+    let label = page.find_element("#passkey_label").await.expect("#passkey_label");
+    label.click().await.expect("focus #passkey_label");
+    label.type_str("laptop").await.expect("type label");
+    click_selector(&page, "#add-passkey button[type=submit]").await;
+    wait_true(&page, &rows_are(2), "two passkeys").await;
+
+    let laptop: String =
+        sqlx::query_scalar("SELECT cred_id FROM passkey_meta WHERE label = 'laptop'")
+            .fetch_one(&server.pool)
+            .await
+            .expect("the new passkey is labelled");
+
+    // Sign out, then in again. Only the laptop's authenticator is attached, so the
+    // ceremony (the login page's own endpoints, with a modal get — the autofill prompt
+    // needs a person to pick from it) can only complete with the new passkey.
+    page.goto(server.url("/login/logout")).await.expect("goto /login/logout");
+    page.goto(server.url("/login")).await.expect("goto /login");
+    let signed_in: bool = js(
+        &page,
+        "(async () => {
+            const opts = await (await fetch('/login/get_auth_opts')).json();
+            const cred = await navigator.credentials.get({
+                publicKey: PublicKeyCredential.parseRequestOptionsFromJSON(opts.publicKey),
+            });
+            const done = await fetch('/login/finish_authentication', {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify(cred.toJSON()),
+            });
+            return done.redirected || done.ok;
+        })()",
+    )
+    .await;
+    assert!(signed_in, "signing in with the second passkey");
+    let last_used: Option<String> =
+        sqlx::query_scalar("SELECT last_used_at FROM passkey_meta WHERE cred_id = ?")
+            .bind(&laptop)
+            .fetch_one(&server.pool)
+            .await
+            .unwrap();
+    assert!(last_used.is_some(), "signing in stamps the passkey's last use");
+
+    // Rename it from its row.
+    page.goto(server.url("/account")).await.expect("goto /account");
+    let row = format!(r#"li.passkey-row:has(input[value="{laptop}"])"#);
+    wait_true(&page, &format!("!!document.querySelector('{row}')"), "the laptop's row").await;
+    let renamed: bool = js(
+        &page,
+        &format!(
+            "(function(){{var i=document.querySelector('{row} .passkey-label');if(!i)return false;i.value='work laptop';return true;}})()"
+        ),
+    )
+    .await;
+    assert!(renamed, "the label input is there");
+    click_selector(&page, &format!("{row} button[type=submit]")).await;
+    wait_true(
+        &page,
+        "[...document.querySelectorAll('.passkey-label')].some(i => i.value === 'work laptop')",
+        "the new label",
+    )
+    .await;
+
+    // Remove it (hold to confirm), leaving the first.
+    hold_click(&page, &format!("{row} .passkey-delete")).await;
+    wait_true(&page, &rows_are(1), "one passkey left").await;
+    let left: i64 = sqlx::query_scalar("SELECT json_array_length(keys) FROM users")
+        .fetch_one(&server.pool)
+        .await
+        .unwrap();
+    assert_eq!(left, 1);
+
+    // The last one has no Remove button, and the endpoint refuses it anyway.
+    assert!(!try_bool(&page, "!!document.querySelector('.passkey-delete')").await);
+    let first: String = sqlx::query_scalar("SELECT cred_id FROM passkey_meta")
+        .fetch_one(&server.pool)
+        .await
+        .unwrap();
+    let status: i64 = js(
+        &page,
+        &format!(
+            "fetch('/account/passkeys/delete', {{method: 'POST', headers: {{'Content-Type': 'application/x-www-form-urlencoded'}}, body: 'cred_id=' + encodeURIComponent('{first}')}}).then(r => r.status)"
+        ),
+    )
+    .await;
+    assert_eq!(status, 409, "the last passkey can't be removed");
+
+    browser.close().await.ok();
+    handle.await.ok();
+    let _ = std::fs::remove_dir_all(&profile);
+    drop(server);
+}
+
 /// Phase DV: the foliate-js EPUB reader BOOTS in a real browser — the de-risk the
 /// direct tests can't give. Setup over HTTP (upload a real `.epub` as PUBLIC media +
 /// embed it on a public page), then headless Chrome loads the page: the embed
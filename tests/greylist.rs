@@ -3,8 +3,9 @@
 //! asserts the toll gates the right traffic while letting exempt / cleared / authenticated
 //! requests through.
 
-use hotchkiss_io::test_support::{solve_challenge, spawn_test_server};
+use hotchkiss_io::test_support::{TEST_HONEYPOT, solve_challenge, spawn_test_server};
 use reqwest::redirect::Policy;
+use std::time::{Duration, Instant};
 
 fn client() -> reqwest::Client {
     reqwest::Client::builder()
@@ -209,6 +210,65 @@ async fn admin_pin_and_release_manage_the_greylist() {
     );
 }
 
+/// A CIDR pin tolls every address in the range, shows its listed member IPs on the panel, and
+/// releases through the percent-encoded path; an ISP-sized range is refused.
+#[tokio::test]
+async fn admin_can_pin_and_release_a_whole_range() {
+    let s = spawn_test_server().await.unwrap();
+    let c = client();
+    c.post(format!("{}/test/login?role=Admin", s.base_url))
+        .send()
+        .await
+        .unwrap();
+
+    for ip in ["127.0.0.9/24", "127.0.0.50"] {
+        let pin = c
+            .post(format!("{}/admin/greylist/pin", s.base_url))
+            .form(&[("ip", ip)])
+            .send()
+            .await
+            .unwrap();
+        assert!(pin.status().is_success(), "{ip}");
+    }
+    // Normalized to the network address, and it covers the (anonymous) loopback client.
+    assert_eq!(
+        s.greylist.matching_entry("127.0.0.1").as_deref(),
+        Some("127.0.0.0/24")
+    );
+    let r = reqwest::get(format!("{}/", s.base_url)).await.unwrap();
+    assert_eq!(r.status(), 429, "a sibling of the pinned range is tolled");
+
+    let body = c
+        .get(format!("{}/admin/greylist", s.base_url))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(body.contains("127.0.0.0/24"));
+    assert!(body.contains("Listed members (1)"), "{body}");
+    assert!(body.contains("/admin/greylist/127.0.0.0%2F24/release"));
+
+    c.post(format!("{}/admin/greylist/127.0.0.0%2F24/release", s.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert!(!s.greylist.is_greylisted("127.0.0.1"), "the range is released");
+    assert!(
+        s.greylist.is_greylisted("127.0.0.50"),
+        "the IP entry inside it stays"
+    );
+
+    let wide = c
+        .post(format!("{}/admin/greylist/pin", s.base_url))
+        .form(&[("ip", "127.0.0.0/8")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(wide.status(), 400, "an ISP-sized range is refused");
+}
+
 #[tokio::test]
 async fn admin_greylist_rejects_bad_ip_and_gates_anonymous() {
     let s = spawn_test_server().await.unwrap();
@@ -334,3 +394,186 @@ async fn challenge_ceremony_is_excluded_from_the_request_log() {
             .unwrap();
     assert_eq!(ceremony, 0, "the /challenge ceremony is excluded from the access log");
 }
+
+/// Clear-then-scan escalation: a revoked clearance stops being honored, a blocked IP gets a
+/// bare 403 (even on the toll itself), and the admin's de-escalate sends it back to the toll
+/// with its clearance honored again.
+#[tokio::test]
+async fn an_escalated_client_is_blocked_until_an_admin_de_escalates() {
+    let s = spawn_test_server().await.unwrap();
+    let admin = client();
+    admin
+        .post(format!("{}/test/login?role=Admin", s.base_url))
+        .send()
+        .await
+        .unwrap();
+
+    s.greylist.insert("127.0.0.1");
+    let c = client();
+    let verify = solve_challenge(&s.base_url, "/").await.unwrap();
+    c.get(format!("{}{}", s.base_url, verify)).send().await.unwrap();
+    assert_eq!(c.get(format!("{}/", s.base_url)).send().await.unwrap().status(), 200);
+
+    // What the sweep does on escalation: revoke the clearance and block the IP.
+    let token_id: String = sqlx::query_scalar("SELECT token_id FROM greylist_clearance")
+        .fetch_one(&s.pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE greylist_clearance SET revoked_at = CURRENT_TIMESTAMP")
+        .execute(&s.pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        "INSERT INTO greylist (ip, reason, evidence, manual, expires_at, tier, escalated_at)
+         VALUES ('127.0.0.1', 'escalated', 'signature_hits=3', 0,
+                 datetime('now', '+30 days'), 'Blocked', CURRENT_TIMESTAMP)",
+    )
+    .execute(&s.pool)
+    .await
+    .unwrap();
+    s.greylist.revoke(&token_id);
+    assert_eq!(
+        c.get(format!("{}/", s.base_url)).send().await.unwrap().status(),
+        429,
+        "a revoked clearance is no longer honored"
+    );
+    s.greylist.block("127.0.0.1");
+    for path in ["/", "/challenge/new"] {
+        let r = c.get(format!("{}{path}", s.base_url)).send().await.unwrap();
+        assert_eq!(r.status(), 403, "{path}: a blocked IP gets a bare 403, no toll");
+    }
+
+    // The admin (authenticated, so not blocked) sees the escalation and reverses it.
+    let body = admin
+        .get(format!("{}/admin/greylist", s.base_url))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(body.contains("/admin/greylist/127.0.0.1/de-escalate"));
+    let r = admin
+        .post(format!("{}/admin/greylist/127.0.0.1/de-escalate", s.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert!(r.status().is_success());
+    assert!(!s.greylist.is_blocked("127.0.0.1"));
+    assert!(!s.greylist.is_revoked(&token_id), "the revocation list reloaded");
+    assert_eq!(
+        c.get(format!("{}/", s.base_url)).send().await.unwrap().status(),
+        200,
+        "back to the toll, with the clearance honored again"
+    );
+    let again = admin
+        .post(format!("{}/admin/greylist/127.0.0.1/de-escalate", s.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(again.status(), 404, "nothing left to de-escalate");
+}
+
+/// The test server's tarpit holds two probes for ~1s each; a third, past the cap, gets the
+/// plain cat-404 straight away, and a finished hold frees its slot.
+#[tokio::test]
+async fn signature_probes_are_tarpitted_up_to_the_cap() {
+    let s = spawn_test_server().await.unwrap();
+    let c = client();
+    let probe = format!("{}/wp-login.php", s.base_url);
+
+    let started = Instant::now();
+    let first = c.get(&probe).send().await.unwrap();
+    let second = c.get(&probe).send().await.unwrap();
+    assert_eq!((first.status(), second.status()), (404, 404), "still a 404");
+
+    let plain = c.get(&probe).send().await.unwrap();
+    assert_eq!(plain.status(), 404);
+    let plain = plain.text().await.unwrap();
+    assert!(!plain.starts_with("<!-- -->"), "past the cap: the plain 404");
+
+    for held in [first, second] {
+        let body = held.text().await.unwrap();
+        assert!(body.starts_with("<!-- -->"), "dripped: {body}");
+    }
+    assert!(
+        started.elapsed() >= Duration::from_millis(700),
+        "the held bodies took their time"
+    );
+
+    // The slot is released when the body ends; give the server a moment to drop it.
+    let mut held_again = false;
+    for _ in 0..20 {
+        let body = c.get(&probe).send().await.unwrap().text().await.unwrap();
+        if body.starts_with("<!-- -->") {
+            held_again = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert!(held_again, "a finished hold frees its slot");
+}
+
+#[tokio::test]
+async fn a_honeypot_hit_greylists_the_ip_on_the_spot() {
+    let s = spawn_test_server().await.unwrap();
+    let c = client();
+
+    let home = c.get(format!("{}/", s.base_url)).send().await.unwrap();
+    assert!(
+        home.text()
+            .await
+            .unwrap()
+            .contains(&format!("href=\"{TEST_HONEYPOT}\"")),
+        "every page links the honeypot"
+    );
+    let robots = c
+        .get(format!("{}/robots.txt", s.base_url))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(robots.contains(&format!("Disallow: {TEST_HONEYPOT}")), "{robots}");
+
+    let hit = c
+        .get(format!("{}{TEST_HONEYPOT}", s.base_url))
+        .header("User-Agent", "scrapy/2.11")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(hit.status(), 404, "nothing tells it it was caught");
+    assert!(s.greylist.is_greylisted("127.0.0.1"), "no wait for the sweep");
+    let (reason, evidence): (String, String) =
+        sqlx::query_as("SELECT reason, evidence FROM greylist WHERE ip = '127.0.0.1'")
+            .fetch_one(&s.pool)
+            .await
+            .unwrap();
+    assert!(reason.starts_with("honeypot"), "{reason}");
+    assert!(evidence.contains(TEST_HONEYPOT) && evidence.contains("scrapy"), "{evidence}");
+
+    assert_eq!(
+        c.get(format!("{}/", s.base_url)).send().await.unwrap().status(),
+        429,
+        "tolled from the very next request"
+    );
+}
+
+#[tokio::test]
+async fn a_signed_in_caller_never_trips_a_honeypot() {
+    let s = spawn_test_server().await.unwrap();
+    let admin = client();
+    admin
+        .post(format!("{}/test/login?role=Admin", s.base_url))
+        .send()
+        .await
+        .unwrap();
+    let r = admin
+        .get(format!("{}{TEST_HONEYPOT}", s.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(r.status(), 404);
+    assert!(!s.greylist.is_greylisted("127.0.0.1"));
+}
@@ -219,10 +219,11 @@ async fn read_tools_honor_the_gate_as_the_admin_viewer() {
     assert!(body.contains("Secret Words"), "get_page returns the content: {body}");
 }
 
-/// DI.6: the write tools round-trip create → get → partial-update → delete through the
-/// shared PageWrite service. A partial update must NOT wipe unmentioned fields (the gate).
+/// DI.6: the write tools round-trip create → get → partial-update through the shared
+/// PageWrite service. A partial update must NOT wipe unmentioned fields (the gate). Delete
+/// is not a tool — it needs a passkey step-up, so it stays in the browser.
 #[tokio::test]
-async fn write_tools_create_update_delete_a_page() {
+async fn write_tools_create_and_update_a_page() {
     let server = spawn_test_server().await.expect("test server");
     let key = server
         .seed_admin_api_key("mcp-write")
@@ -264,28 +265,64 @@ async fn write_tools_create_update_delete_a_page() {
     assert!(body.contains("EDITED"), "markdown updated: {body}");
     assert!(body.contains("Family"), "the gate PERSISTED across a partial update: {body}");
 
-    // delete without confirm is refused.
-    let refused = tool_call(
+    // There is no delete tool: page delete is step-up gated, which a key can't pass.
+    let list = post_mcp(
         &client,
         &url,
-        &key,
-        "delete_page",
-        json!({ "path": "blog/agent-post", "confirm": false }),
+        Some(&key),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }),
+    )
+    .await
+    .text()
+    .await
+    .unwrap();
+    assert!(list.contains("update_page"), "{list}");
+    assert!(!list.contains("delete_page"), "delete_page is not on the surface: {list}");
+    post_mcp(
+        &client,
+        &url,
+        Some(&key),
+        json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "tools/call",
+            "params": {
+                "name": "delete_page",
+                "arguments": { "path": "blog/agent-post", "confirm": true }
+            }
+        }),
     )
     .await;
-    assert!(refused.contains("confirm"), "delete without confirm is refused: {refused}");
+    let kept = tool_call(&client, &url, &key, "get_page", json!({ "path": "blog/agent-post" })).await;
+    assert!(kept.contains("EDITED"), "the page is still there: {kept}");
+}
+
+/// MCP writes land in the audit log on the `mcp` channel (not `api_key`, though a
+/// key carried them), and `list_audit_log` reads them back filtered.
+#[tokio::test]
+async fn write_tools_are_audited_on_the_mcp_channel() {
+    let server = spawn_test_server().await.expect("test server");
+    let key = server.seed_admin_api_key("mcp-audit").await.expect("admin key");
+    let client = reqwest::Client::new();
+    let url = server.url("/mcp");
 
-    // delete with confirm removes it.
     tool_call(
         &client,
         &url,
         &key,
-        "delete_page",
-        json!({ "path": "blog/agent-post", "confirm": true }),
+        "create_page",
+        json!({ "parent_path": "blog", "title": "Audited", "markdown": "hi" }),
     )
     .await;
-    let gone = tool_call(&client, &url, &key, "get_page", json!({ "path": "blog/agent-post" })).await;
-    assert!(gone.contains("not found"), "the page is gone: {gone}");
+
+    let body = tool_call(&client, &url, &key, "list_audit_log", json!({ "channel": "mcp" })).await;
+    assert!(body.contains("page.create"), "{body}");
+    assert!(body.contains("page.update"), "the content fill is its own entry: {body}");
+    assert!(body.contains("blog/audited"), "{body}");
+    assert!(body.contains("api-tester"), "the key's owner is the actor: {body}");
+
+    let body = tool_call(&client, &url, &key, "list_audit_log", json!({ "channel": "session" })).await;
+    assert!(!body.contains("page.create"), "nothing was written by a session: {body}");
 }
 
 /// DI.7: the action tools flip scheduled + featured state, and feature is idempotent.
@@ -420,7 +420,7 @@ async fn request_log_middleware_records_requests() {
     let resp = reqwest::get(server.url("/pages/Probe")).await.unwrap();
     assert_eq!(resp.status(), StatusCode::OK);
 
-    // the INSERT is fire-and-forget (tokio::spawn) — poll briefly for it
+    // the INSERT is queued to the batched writer — poll briefly for it
     let mut found = None;
     for _ in 0..100 {
         let rows = sqlx::query("SELECT path, status, ip FROM request_log ORDER BY id DESC")
@@ -444,6 +444,91 @@ async fn request_log_middleware_records_requests() {
     assert_eq!(ip.as_deref(), Some("127.0.0.1"));
 }
 
+#[tokio::test]
+async fn campaign_tags_are_recorded_then_stripped() {
+    let server = spawn_test_server().await.expect("spawn");
+    server
+        .seed_content_page("Tagged", "# tagged page")
+        .await
+        .expect("seed");
+
+    // a tagged GET redirects to the same URL without the tags, other params kept
+    let resp = client()
+        .get(server.url("/pages/Tagged?utm_source=LinkedIn&utm_medium=social&edit=1"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+    assert_eq!(location(&resp).as_deref(), Some("/pages/Tagged?edit=1"));
+
+    // ...and the redirect's row carries the (normalized) campaign
+    let mut found = None;
+    for _ in 0..100 {
+        found = sqlx::query(
+            "SELECT campaign_source, campaign_medium FROM request_log \
+             WHERE path = '/pages/Tagged' AND campaign_source IS NOT NULL",
+        )
+        .fetch_optional(&server.pool)
+        .await
+        .unwrap()
+        .map(|r| {
+            (
+                r.get::<String, _>("campaign_source"),
+                r.get::<Option<String>, _>("campaign_medium"),
+            )
+        });
+        if found.is_some() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    let (source, medium) = found.expect("request_log should record the campaign");
+    assert_eq!(source, "linkedin");
+    assert_eq!(medium.as_deref(), Some("social"));
+
+    // the editor mints tagged links to this site only, and only for admins
+    let form = [
+        ("url", "https://hotchkiss.io/pages/Tagged?utm_source=old"),
+        ("source", "Resume PDF"),
+        ("campaign", "Job Hunt"),
+    ];
+    let resp = client()
+        .post(server.url("/admin/pages/share-link"))
+        .form(&form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let admin = client();
+    admin
+        .post(server.url("/test/login?role=Admin"))
+        .send()
+        .await
+        .unwrap();
+    let resp = admin
+        .post(server.url("/admin/pages/share-link"))
+        .form(&form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = resp.text().await.unwrap();
+    let minted = "https://hotchkiss.io/pages/Tagged?utm_source=resume-pdf&amp;utm_campaign=job-hunt";
+    assert!(
+        body.contains(minted),
+        "minted link replaces the old tags: {body}"
+    );
+
+    let resp = admin
+        .post(server.url("/admin/pages/share-link"))
+        .form(&[("url", "https://evil.example/pages/Tagged"), ("source", "x")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
 #[tokio::test]
 async fn logs_requires_admin_and_renders(/* Phase CO */) {
     let server = spawn_test_server().await.expect("spawn");
@@ -949,6 +1034,51 @@ async fn analytics_surfaces_scanners_and_latency() {
     );
 }
 
+#[tokio::test]
+async fn analytics_breaks_traffic_down_by_country_and_network() {
+    // The test server loads the fixture .mmdbs (tests/fixtures/geoip): 192.0.2.0/24 is US on
+    // AS16509, 198.51.100.0/24 DE on AS64500, and 8.8.8.8 isn't in them at all.
+    let server = spawn_test_server().await.expect("spawn");
+    for ip in ["192.0.2.10", "192.0.2.11", "192.0.2.11", "198.51.100.7", "8.8.8.8"] {
+        sqlx::query(
+            "INSERT INTO request_log (method, path, status, ip, user_agent, duration_ms) \
+             VALUES ('GET', '/pages/home', 200, ?, 'Mozilla/5', 3)",
+        )
+        .bind(ip)
+        .execute(&server.pool)
+        .await
+        .unwrap();
+    }
+
+    let admin = client();
+    admin
+        .post(server.url("/test/login?role=Admin"))
+        .send()
+        .await
+        .unwrap();
+    let body = admin
+        .get(server.url("/admin/analytics?since=30"))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    assert!(body.contains("Traffic by country"), "the country panel renders");
+    assert!(body.contains("Top networks (ASN)"), "the ASN panel renders");
+    assert!(
+        body.contains(
+            r#"<tr><td class="num">3</td><td class="num">2</td><td class="grow">US</td></tr>"#
+        ),
+        "US: three requests from two IPs"
+    );
+    assert!(body.contains(">DE</td>"), "the DE row");
+    assert!(body.contains("AS16509 Amazon.com, Inc."), "the Amazon network row");
+    assert!(body.contains("AS64500 Example Residential"), "the residential network row");
+    assert!(body.contains("(unknown)"), "an IP the databases don't know");
+}
+
 /// Regression guard for the anonymous content-mutation bypass: the `/pages`
 /// mutating handlers must reject anyone who isn't Admin. The old `if let
 /// Authenticated(user) && role != Admin` idiom let Anonymous fall straight
@@ -1346,6 +1476,73 @@ async fn api_key_authenticates_a_mutation() {
     );
 }
 
+/// A keyed request lands in that key's `api_key_usage` trail (and shows on
+/// `/admin/api-keys`); an expired key stops authenticating immediately, before the
+/// hourly auto-revoke sweep ever runs.
+#[tokio::test]
+async fn api_key_usage_is_recorded_and_expiry_bites() {
+    let server = spawn_test_server().await.expect("spawn");
+    let key = server.seed_admin_api_key("ci").await.expect("seed key");
+    let c = client();
+
+    let resp = c
+        .get(server.url("/admin/api-keys"))
+        .header("Authorization", format!("Bearer {key}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // The usage INSERT is fire-and-forget (tokio::spawn) — poll briefly for it.
+    let mut found = None;
+    for _ in 0..100 {
+        let row = sqlx::query("SELECT method, path, status, ip FROM api_key_usage")
+            .fetch_optional(&server.pool)
+            .await
+            .unwrap();
+        if let Some(row) = row {
+            found = Some((
+                row.get::<String, _>("method"),
+                row.get::<String, _>("path"),
+                row.get::<i64, _>("status"),
+                row.get::<Option<String>, _>("ip"),
+            ));
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    let (method, path, status, ip) = found.expect("a keyed request is recorded");
+    assert_eq!(method, "GET");
+    assert_eq!(path, "/admin/api-keys");
+    assert_eq!(status, 200);
+    assert_eq!(ip.as_deref(), Some("127.0.0.1"));
+
+    // The page shows the trail for the key.
+    let body = c
+        .get(server.url("/admin/api-keys"))
+        .header("Authorization", format!("Bearer {key}"))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(body.contains("Recent requests"), "per-key trail rendered");
+
+    // Lapse the key: it no longer authenticates → the admin gate 401s.
+    sqlx::query("UPDATE api_keys SET expires_at = '2000-01-01T00:00:00+00:00'")
+        .execute(&server.pool)
+        .await
+        .unwrap();
+    let resp = c
+        .get(server.url("/admin/api-keys"))
+        .header("Authorization", format!("Bearer {key}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED, "an expired key auths as nobody");
+}
+
 /// Phase F authoring flow: create-by-title auto-slugs the URL; an admin lands on
 /// the clean reader view with an Edit toggle; ?edit reveals the editor; anon sees
 /// neither.
@@ -2896,41 +3093,568 @@ async fn deleted_user_loses_access_on_live_session() {
         .await
         .unwrap();
 
-    // The middleware downgrades a deleted user to Anonymous → 401 (missing identity, DK.2).
-    let denied = admin.get(server.url("/admin/analytics")).send().await.unwrap();
-    assert_eq!(
-        denied.status(),
-        StatusCode::UNAUTHORIZED,
-        "a deleted user must lose access on their live session"
-    );
+    // The middleware downgrades a deleted user to Anonymous → 401 (missing identity, DK.2).
+    let denied = admin.get(server.url("/admin/analytics")).send().await.unwrap();
+    assert_eq!(
+        denied.status(),
+        StatusCode::UNAUTHORIZED,
+        "a deleted user must lose access on their live session"
+    );
+}
+
+#[tokio::test]
+async fn session_cookie_is_http_only_and_samesite() {
+    // The session cookie (the only cookie the app sets) must be HttpOnly +
+    // SameSite. Secure isn't asserted here — the test harness is plain HTTP, so
+    // Secure is off by design in debug (on in release).
+    let server = spawn_test_server().await.expect("spawn");
+    let resp = client()
+        .post(server.url("/test/login?role=Admin"))
+        .send()
+        .await
+        .unwrap();
+    let set_cookie = resp
+        .headers()
+        .get("set-cookie")
+        .expect("login sets a session cookie")
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(
+        set_cookie.to_lowercase().contains("httponly"),
+        "session cookie must be HttpOnly: {set_cookie}"
+    );
+    assert!(
+        set_cookie.to_lowercase().contains("samesite"),
+        "session cookie must set SameSite: {set_cookie}"
+    );
+}
+
+// ───────────────────────── /account: self-service passkeys ─────────────────────────
+
+#[tokio::test]
+async fn account_page_gates_reads_and_self_service_mutations() {
+    let server = spawn_test_server().await.expect("spawn");
+
+    // Anonymous: the page bounces to login (with a way back), the POSTs 401.
+    let anon = client();
+    let resp = anon.get(server.url("/account")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+    assert_eq!(
+        resp.headers().get("location").unwrap(),
+        "/login?next=%2Faccount"
+    );
+    let resp = anon
+        .post(server.url("/account/passkeys/delete"))
+        .form(&[("cred_id", "x")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    // Any signed-in role reaches its own page and the self-service POSTs —
+    // Family included, though it can't mutate anything else.
+    for role in ["Registered", "Family"] {
+        let user = client();
+        user.post(server.url(&format!("/test/login?role={role}")))
+            .send()
+            .await
+            .unwrap();
+        let resp = user.get(server.url("/account")).send().await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK, "{role} reads /account");
+        let body = resp.text().await.unwrap();
+        assert!(body.contains("Passkeys"), "{role}: {body}");
+        assert!(body.contains(r#"data-webauthn-start="/account/passkeys/start""#));
+
+        // Someone else's (or a made-up) credential id: not found, never touched.
+        for path in ["/account/passkeys/delete", "/account/passkeys/rename"] {
+            let resp = user
+                .post(server.url(path))
+                .form(&[("cred_id", "not-my-key"), ("label", "phone")])
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::NOT_FOUND, "{role} {path}");
+        }
+        // A bad label is refused before any lookup.
+        let resp = user
+            .post(server.url("/account/passkeys/rename"))
+            .form(&[("cred_id", "not-my-key"), ("label", "")])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{role} empty label");
+    }
+}
+
+/// A passkey added to an account answers `/step-up` from then on, and removing one
+/// can strip the owner's other devices: both need a fresh assertion, so a stolen
+/// session cookie alone is re-prompted, and an API key is refused.
+#[tokio::test]
+async fn account_passkey_changes_require_a_fresh_step_up() {
+    let server = spawn_test_server().await.expect("spawn");
+    let carol = server.seed_user("carol", "Registered").await.unwrap();
+    let user = client();
+    user.post(server.url(&format!("/test/login?user={carol}"))).send().await.unwrap();
+    user.post(server.url("/test/step-up/clear")).send().await.unwrap();
+
+    let resp = user
+        .get(server.url("/account/passkeys/start/x"))
+        .header("HX-Request", "true")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert!(resp.headers().contains_key("x-step-up-required"));
+    let trigger = resp.headers().get("hx-trigger").unwrap().to_str().unwrap();
+    assert!(trigger.contains("/account/passkeys/start/x"), "{trigger}");
+
+    let resp = user.post(server.url("/account/passkeys/finish")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert!(resp.headers().contains_key("x-step-up-required"));
+    let resp = user
+        .post(server.url("/account/passkeys/delete"))
+        .form(&[("cred_id", "x")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert!(resp.headers().contains_key("x-step-up-required"));
+
+    // Never over an API key.
+    let key = server.seed_registered_api_key("ci").await.unwrap();
+    let resp = client()
+        .get(server.url("/account/passkeys/start/x"))
+        .header("Authorization", format!("Bearer {key}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert!(resp.headers().get("x-step-up-required").is_none());
+
+    // A fresh sign-in is a fresh assertion: the ceremony starts.
+    user.post(server.url(&format!("/test/login?user={carol}"))).send().await.unwrap();
+    let resp = user.get(server.url("/account/passkeys/start/x")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+/// Every `XXXX-XXXX-XXXX-XXXX` recovery code in a page.
+fn recovery_codes(body: &str) -> Vec<String> {
+    body.split(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+        .filter(|w| {
+            let groups: Vec<&str> = w.split('-').collect();
+            groups.len() == 4 && groups.iter().all(|g| g.len() == 4)
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+#[tokio::test]
+async fn recovery_codes_generate_redeem_once_and_unlock_a_passkey_ceremony() {
+    let server = spawn_test_server().await.expect("spawn");
+    let carol = server.seed_user("carol", "Registered").await.unwrap();
+    let user = client();
+    user.post(server.url(&format!("/test/login?user={carol}"))).send().await.unwrap();
+
+    let page = user.get(server.url("/account")).send().await.unwrap().text().await.unwrap();
+    assert!(page.contains("You have no recovery codes."), "{page}");
+
+    // Generating is step-up gated: a lapsed assertion is refused.
+    user.post(server.url("/test/step-up/clear")).send().await.unwrap();
+    let resp = user
+        .post(server.url("/account/recovery-codes/generate"))
+        .header("HX-Request", "true")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert!(resp.headers().contains_key("x-step-up-required"));
+
+    // Never over an API key.
+    let key = server.seed_registered_api_key("ci").await.unwrap();
+    let resp = client()
+        .post(server.url("/account/recovery-codes/generate"))
+        .header("Authorization", format!("Bearer {key}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let minted: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM recovery_codes")
+        .fetch_one(&server.pool)
+        .await
+        .unwrap();
+    assert_eq!(minted, 0);
+
+    user.post(server.url(&format!("/test/login?user={carol}"))).send().await.unwrap();
+    let resp = user
+        .post(server.url("/account/recovery-codes/generate"))
+        .header("HX-Request", "true")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let codes = recovery_codes(&resp.text().await.unwrap());
+    assert_eq!(codes.len(), 10, "{codes:?}");
+    let page = user.get(server.url("/account")).send().await.unwrap().text().await.unwrap();
+    assert!(page.contains("10 unused"), "{page}");
+    assert!(!page.contains(&codes[0]), "codes are shown only at generation");
+
+    // Without a redeemed code there's no ceremony to start.
+    let stranger = client();
+    let resp = stranger.get(server.url("/login/recover/start/phone")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::GONE);
+
+    // A wrong code (or a wrong name) is refused — anonymously reachable, no grant.
+    for (name, code) in [("carol", "AAAA-BBBB-CCCC-DDDD"), ("nobody", codes[0].as_str())] {
+        let resp = stranger
+            .post(server.url("/login/recover"))
+            .form(&[("display_name", name), ("code", code)])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED, "{name} {code}");
+    }
+
+    // The right one grants a passkey registration for carol — not a sign-in.
+    let body = stranger
+        .post(server.url("/login/recover"))
+        .form(&[("display_name", "carol"), ("code", &codes[0].to_lowercase())])
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(body.contains(r#"data-webauthn-start="/login/recover/start""#), "{body}");
+    assert!(body.contains("<strong>carol</strong>"), "{body}");
+    let resp = stranger.get(server.url("/account")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::SEE_OTHER, "still signed out");
+    let resp = stranger.get(server.url("/login/recover/start/phone")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let challenge = resp.text().await.unwrap();
+    assert!(challenge.contains("publicKey"), "{challenge}");
+
+    // Single use.
+    let resp = client()
+        .post(server.url("/login/recover"))
+        .form(&[("display_name", "carol"), ("code", codes[0].as_str())])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let page = user.get(server.url("/account")).send().await.unwrap().text().await.unwrap();
+    assert!(page.contains("9 unused"), "{page}");
+    let actions: Vec<String> = sqlx::query("SELECT action FROM audit_log ORDER BY id")
+        .fetch_all(&server.pool)
+        .await
+        .unwrap()
+        .iter()
+        .map(|r| r.get("action"))
+        .collect();
+    assert_eq!(actions, ["recovery_code.generate", "recovery_code.use"]);
+}
+
+/// Poll until `user_id` has `n` indexed sessions — the index write is
+/// fire-and-forget from the session middleware.
+async fn wait_for_session_rows(pool: &sqlx::SqlitePool, user_id: &str, n: i64) {
+    for _ in 0..100 {
+        let c: i64 = sqlx::query("SELECT COUNT(*) as c FROM user_sessions WHERE user_id = ?1")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+            .get("c");
+        if c == n {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    panic!("expected {n} user_sessions rows for {user_id}");
+}
+
+#[tokio::test]
+async fn sessions_list_and_revoke_from_account_and_admin() {
+    let server = spawn_test_server().await.expect("spawn");
+    let alice = server.seed_user("alice", "Registered").await.unwrap();
+
+    // Two devices signed in as alice; the first authenticated request indexes each.
+    let laptop = client();
+    let phone = client();
+    for c in [&laptop, &phone] {
+        c.post(server.url(&format!("/test/login?user={alice}")))
+            .header("user-agent", "TestBrowser/1.0")
+            .send()
+            .await
+            .unwrap();
+        c.get(server.url("/")).send().await.unwrap();
+    }
+    wait_for_session_rows(&server.pool, &alice.to_string(), 2).await;
+
+    let body = laptop.get(server.url("/account")).send().await.unwrap().text().await.unwrap();
+    assert_eq!(body.matches("session-row").count(), 2, "{body}");
+    assert!(body.contains("this device"));
+    assert!(body.contains("Sign out all other sessions"));
+
+    // Someone else's (or a made-up) session id is a 404.
+    let resp = laptop
+        .post(server.url("/account/sessions/revoke"))
+        .form(&[("session_id", "not-a-session")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    // Sign out everywhere else: the phone is out, the laptop stays in.
+    let resp = laptop
+        .post(server.url("/account/sessions/revoke_others"))
+        .send()
+        .await
+        .unwrap();
+    assert!(resp.status().is_success(), "{}", resp.status());
+    let resp = phone.get(server.url("/account")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::SEE_OTHER, "revoked session must be signed out");
+    let resp = laptop.get(server.url("/account")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // The admin sees alice's remaining session, and a role change ends it.
+    let admin = client();
+    admin.post(server.url("/test/login?role=Admin")).send().await.unwrap();
+    let body = admin.get(server.url("/admin/users")).send().await.unwrap().text().await.unwrap();
+    assert!(body.contains("1 active session"), "{body}");
+    let r = admin
+        .post(server.url(&format!("/admin/users/{alice}/role")))
+        .form(&[("role", "Family")])
+        .send()
+        .await
+        .unwrap();
+    assert!(r.status().is_success(), "{}", r.status());
+    let resp = laptop.get(server.url("/account")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::SEE_OTHER, "a role change must sign the user out");
+
+    // Admin revoke-all works on a fresh session too; non-admins can't reach it.
+    let tablet = client();
+    tablet
+        .post(server.url(&format!("/test/login?user={alice}")))
+        .send()
+        .await
+        .unwrap();
+    tablet.get(server.url("/")).send().await.unwrap();
+    wait_for_session_rows(&server.pool, &alice.to_string(), 1).await;
+    let resp = tablet
+        .post(server.url(&format!("/admin/users/{alice}/sessions/revoke_all")))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let r = admin
+        .post(server.url(&format!("/admin/users/{alice}/sessions/revoke_all")))
+        .send()
+        .await
+        .unwrap();
+    assert!(r.status().is_success(), "{}", r.status());
+    let resp = tablet.get(server.url("/account")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+}
+
+/// Destructive admin actions want a passkey assertion from the last few minutes: a
+/// lapsed session gets 403 + `X-Step-Up-Required` (and an `HX-Trigger` for the
+/// re-prompt script on HTMX requests); bearer API keys are refused; a fresh sign-in
+/// counts as the assertion.
+#[tokio::test]
+async fn destructive_admin_actions_require_a_fresh_step_up() {
+    let server = spawn_test_server().await.expect("spawn");
+    let alice = server.seed_user("alice", "Registered").await.unwrap();
+    let admin = client();
+    admin.post(server.url("/test/login?role=Admin")).send().await.unwrap();
+    let r = admin.post(server.url("/test/step-up/clear")).send().await.unwrap();
+    assert_eq!(r.status(), StatusCode::NO_CONTENT);
+
+    let alice_exists = || async {
+        sqlx::query("SELECT id FROM users WHERE id = ?")
+            .bind(alice.to_string())
+            .fetch_optional(&server.pool)
+            .await
+            .unwrap()
+            .is_some()
+    };
+
+    let resp = admin
+        .delete(server.url(&format!("/admin/users/{alice}")))
+        .header("HX-Request", "true")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert!(resp.headers().contains_key("x-step-up-required"));
+    let trigger = resp.headers().get("hx-trigger").unwrap().to_str().unwrap();
+    assert!(trigger.contains("step-up-required"), "{trigger}");
+    assert!(alice_exists().await);
+
+    // Without HTMX: the same refusal, no client-side trigger.
+    let resp = admin
+        .delete(server.url(&format!("/admin/users/{alice}")))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert!(resp.headers().get("hx-trigger").is_none());
+
+    let resp = admin
+        .post(server.url(&format!("/admin/users/{alice}/role")))
+        .form(&[("role", "Admin")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let resp = admin.delete(server.url("/pages/home")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // The re-prompt can't run for a user with no passkeys (test logins have none).
+    let resp = admin.get(server.url("/step-up/options")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    // A fresh sign-in is a fresh assertion.
+    admin.post(server.url("/test/login?role=Admin")).send().await.unwrap();
+    let resp = admin
+        .post(server.url(&format!("/admin/users/{alice}/role")))
+        .form(&[("role", "Family")])
+        .send()
+        .await
+        .unwrap();
+    assert!(resp.status().is_success(), "{}", resp.status());
+
+    // API keys are refused outright (there's no passkey to assert with), and not told
+    // to step up.
+    let key = server.seed_admin_api_key("ci").await.expect("seed key");
+    let resp = client()
+        .delete(server.url(&format!("/admin/users/{alice}")))
+        .header("Authorization", format!("Bearer {key}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert!(resp.headers().get("x-step-up-required").is_none());
+    assert!(resp.text().await.unwrap().contains("API keys can't"));
+    assert!(alice_exists().await);
+
+    // The HTMX trigger carries the query string, so the replay keeps it.
+    admin.post(server.url("/test/step-up/clear")).send().await.unwrap();
+    let resp = admin
+        .delete(server.url(&format!("/admin/users/{alice}?confirm=1")))
+        .header("HX-Request", "true")
+        .send()
+        .await
+        .unwrap();
+    let trigger = resp.headers().get("hx-trigger").unwrap().to_str().unwrap();
+    assert!(trigger.contains(&format!("/admin/users/{alice}?confirm=1")), "{trigger}");
+}
+
+/// Privileged mutations land in `audit_log` with the actor and how they signed in,
+/// and `/admin/audit` lists and filters them (admin-only).
+#[tokio::test]
+async fn audit_log_records_actor_and_channel() {
+    let server = spawn_test_server().await.expect("spawn");
+    let key = server.seed_admin_api_key("ci").await.expect("seed key");
+    let admin = client();
+    admin.post(server.url("/test/login?role=Admin")).send().await.unwrap();
+
+    // A page create from the browser session…
+    let r = admin
+        .post(server.url("/pages"))
+        .form(&[("page_title", "Audit Me")])
+        .send()
+        .await
+        .unwrap();
+    assert!(r.status().is_redirection() || r.status().is_success(), "{}", r.status());
+    // …and a greylist pin over the API key.
+    let r = client()
+        .post(server.url("/admin/greylist/pin"))
+        .header("Authorization", format!("Bearer {key}"))
+        .form(&[("ip", "203.0.113.9")])
+        .send()
+        .await
+        .unwrap();
+    assert!(r.status().is_success(), "{}", r.status());
+
+    let body = admin.get(server.url("/admin/audit")).send().await.unwrap().text().await.unwrap();
+    assert_eq!(body.matches("audit-row").count(), 2, "{body}");
+    assert!(body.contains("page.create") && body.contains("audit-me"), "{body}");
+    assert!(body.contains("greylist.pin") && body.contains("203.0.113.9"), "{body}");
+    assert!(body.contains("test-Admin") && body.contains("api-tester"), "{body}");
+
+    let body = admin
+        .get(server.url("/admin/audit?channel=api_key&action=&actor=&target="))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(body.matches("audit-row").count(), 1, "{body}");
+    assert!(body.contains("greylist.pin") && !body.contains("page.create"), "{body}");
+
+    let body = admin
+        .get(server.url("/admin/audit?action=page."))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(body.matches("audit-row").count(), 1, "{body}");
+    assert!(body.contains("page.create"), "{body}");
+
+    let viewer = client();
+    viewer.post(server.url("/test/login?role=Family")).send().await.unwrap();
+    let r = viewer.get(server.url("/admin/audit")).send().await.unwrap();
+    assert_eq!(r.status(), StatusCode::FORBIDDEN);
 }
 
+/// Greylist pins and releases keep the entry's state on either side of the change, so the
+/// audit trail shows what a re-pin overwrote and what a release dropped.
 #[tokio::test]
-async fn session_cookie_is_http_only_and_samesite() {
-    // The session cookie (the only cookie the app sets) must be HttpOnly +
-    // SameSite. Secure isn't asserted here — the test harness is plain HTTP, so
-    // Secure is off by design in debug (on in release).
+async fn greylist_audit_rows_carry_before_and_after() {
     let server = spawn_test_server().await.expect("spawn");
-    let resp = client()
-        .post(server.url("/test/login?role=Admin"))
-        .send()
+    let admin = client();
+    admin.post(server.url("/test/login?role=Admin")).send().await.unwrap();
+
+    for _ in 0..2 {
+        let r = admin
+            .post(server.url("/admin/greylist/pin"))
+            .form(&[("ip", "203.0.113.10")])
+            .send()
+            .await
+            .unwrap();
+        assert!(r.status().is_success(), "{}", r.status());
+    }
+    let r = admin.post(server.url("/admin/greylist/203.0.113.10/release")).send().await.unwrap();
+    assert!(r.status().is_success(), "{}", r.status());
+
+    let rows = sqlx::query("SELECT action, before, after FROM audit_log ORDER BY id")
+        .fetch_all(&server.pool)
         .await
         .unwrap();
-    let set_cookie = resp
-        .headers()
-        .get("set-cookie")
-        .expect("login sets a session cookie")
-        .to_str()
-        .unwrap()
-        .to_string();
-    assert!(
-        set_cookie.to_lowercase().contains("httponly"),
-        "session cookie must be HttpOnly: {set_cookie}"
-    );
-    assert!(
-        set_cookie.to_lowercase().contains("samesite"),
-        "session cookie must set SameSite: {set_cookie}"
-    );
+    let state = |r: &sqlx::sqlite::SqliteRow, col: &str| {
+        r.get::<Option<String>, _>(col)
+            .map(|s| serde_json::from_str::<serde_json::Value>(&s).unwrap())
+    };
+    let actions: Vec<String> = rows.iter().map(|r| r.get("action")).collect();
+    assert_eq!(actions, ["greylist.pin", "greylist.pin", "greylist.release"]);
+
+    // First pin: nothing before, a manual never-lapsing entry after.
+    assert_eq!(state(&rows[0], "before"), None);
+    let pinned = state(&rows[0], "after").expect("after");
+    assert_eq!(pinned["reason"], "manual");
+    assert_eq!(pinned["manual"], true);
+    assert!(pinned["expires_at"].is_null(), "{pinned}");
+    assert_eq!(pinned["tier"], "Toll");
+    // Re-pin: the same entry on both sides.
+    assert_eq!(state(&rows[1], "before").as_ref(), Some(&pinned));
+    assert_eq!(state(&rows[1], "after").as_ref(), Some(&pinned));
+    // Release: what was dropped, nothing after.
+    assert_eq!(state(&rows[2], "before").as_ref(), Some(&pinned));
+    assert_eq!(state(&rows[2], "after"), None);
 }
 
 // ───────────────────────── Phase CC: /admin/users management ─────────────────────────
@@ -3047,10 +3771,127 @@ async fn admin_can_delete_a_user() {
     assert_eq!(count, 0, "alice should be gone");
 }
 
+// ───────────────────────── Invite links ─────────────────────────
+
+/// Pull the one-time token out of the freshly-minted invite page.
+fn invite_token(body: &str) -> String {
+    let start = body.find("?invite=").expect("minted page shows the link") + "?invite=".len();
+    body[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .collect()
+}
+
+#[tokio::test]
+async fn invite_links_mint_check_and_revoke() {
+    let server = spawn_test_server().await.expect("spawn");
+    let admin = client();
+    admin.post(server.url("/test/login?role=Admin")).send().await.unwrap();
+
+    // Minting is admin-only.
+    let family = client();
+    family.post(server.url("/test/login?role=Family")).send().await.unwrap();
+    let resp = family
+        .post(server.url("/admin/invites"))
+        .form(&[("role", "Admin"), ("expires_in_days", "7")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // Guards: no Anonymous role, no open-ended expiry.
+    for (role, days) in [("Anonymous", "7"), ("Family", "0"), ("Family", "365")] {
+        let resp = admin
+            .post(server.url("/admin/invites"))
+            .form(&[("role", role), ("expires_in_days", days)])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{role} {days}");
+    }
+
+    let body = admin
+        .post(server.url("/admin/invites"))
+        .form(&[("role", "Family"), ("expires_in_days", "7"), ("note", "grandma")])
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    let token = invite_token(&body);
+    assert!(token.starts_with("inv_"), "{token}");
+
+    // The link's landing page announces the role; a registration ceremony
+    // carrying it starts (the passkey challenge comes back).
+    let guest = client();
+    let page = guest
+        .get(server.url(&format!("/login?invite={token}")))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(page.contains("invited to join as <strong>Family</strong>"), "{page}");
+    let resp = guest
+        .get(server.url(&format!("/login/start_register/grandma?invite={token}")))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // A bogus token is refused up front — before any passkey is minted.
+    let resp = guest
+        .get(server.url("/login/start_register/grandma?invite=inv_bogus"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::GONE);
+
+    // The admin list shows it open; revoke closes it and the link dies.
+    let list = admin.get(server.url("/admin/invites")).send().await.unwrap().text().await.unwrap();
+    assert!(list.contains("grandma") && list.contains("open until"), "{list}");
+    assert!(!list.contains(&token), "the token is shown only at mint");
+    let id: i64 = sqlx::query("SELECT id FROM invites")
+        .fetch_one(&server.pool)
+        .await
+        .unwrap()
+        .get("id");
+    let resp = admin
+        .post(server.url(&format!("/admin/invites/{id}/revoke")))
+        .send()
+        .await
+        .unwrap();
+    assert!(resp.status().is_success(), "{}", resp.status());
+    let page = guest
+        .get(server.url(&format!("/login?invite={token}")))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(page.contains("invite link is invalid"), "{page}");
+    let resp = guest
+        .get(server.url(&format!("/login/start_register/grandma?invite={token}")))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::GONE);
+    // Revoking twice is a 404.
+    let resp = admin
+        .post(server.url(&format!("/admin/invites/{id}/revoke")))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
 // ───────────────────────── Phase CZ: Family role + honest sessions ─────────────────────────
 
-/// Family is a READ tier, not a mutation tier (the role-scoped allowlist ships
-/// EMPTY): a Family session reads public content like anyone, but every
+/// Family is a READ tier, not a mutation tier (the role-scoped allowlist opens
+/// only the `/account` self-service POSTs): a Family session reads public content like anyone, but every
 /// mutation and every `/admin` GET stays admin-only — and the 403 comes from
 /// the layer, BEFORE any handler (a Family DELETE on a nonexistent user is
 /// still 403, never the handler's 404).
@@ -3091,7 +3932,7 @@ async fn family_role_reads_but_cannot_mutate_or_reach_admin() {
         );
     }
 
-    // Mutations: default-DENY holds (the role-scoped table is empty in CZ).
+    // Mutations: default-DENY holds (the role-scoped table only opens /account).
     let resp = family
         .put(server.url("/pages/FamilyProbe"))
         .form(&[
@@ -5995,3 +6836,408 @@ async fn listing_search_skips_body_only_for_hex_queries() {
     let title = reqwest::get(server.url("/blog?q=Alpha")).await.unwrap().text().await.unwrap();
     assert!(title.contains("/blog/alpha"), "the title still matches");
 }
+
+// ───────────────────────── Built-in OIDC provider ─────────────────────────
+
+/// The text of the `<code id="…">` element on an admin page (the once-shown
+/// client id / secret).
+fn code_by_id(body: &str, id: &str) -> String {
+    let start = body.find(&format!("id=\"{id}\"")).expect(id);
+    let open = start + body[start..].find('>').unwrap() + 1;
+    let close = open + body[open..].find("</code>").unwrap();
+    body[open..close].trim().to_string()
+}
+
+/// The relying-party stand-in's token check: verify an RS256 compact JWS against
+/// the provider's JWKS (by `kid`) the way an off-the-shelf OIDC client does, and
+/// return its claims.
+fn verify_with_jwks(jwks: &serde_json::Value, token: &str) -> serde_json::Value {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use openssl::{bn::BigNum, hash::MessageDigest, pkey::PKey, rsa::Rsa, sign::Verifier};
+
+    let parts: Vec<&str> = token.split('.').collect();
+    assert_eq!(parts.len(), 3, "{token}");
+    let header: serde_json::Value =
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(parts[0]).unwrap()).unwrap();
+    assert_eq!(header["alg"], "RS256");
+    let jwk = jwks["keys"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|k| k["kid"] == header["kid"])
+        .expect("token kid is in the JWKS");
+    let component = |name: &str| {
+        BigNum::from_slice(&URL_SAFE_NO_PAD.decode(jwk[name].as_str().unwrap()).unwrap()).unwrap()
+    };
+    let rsa = Rsa::from_public_components(component("n"), component("e")).unwrap();
+    let pkey = PKey::from_rsa(rsa).unwrap();
+    let mut verifier = Verifier::new(MessageDigest::sha256(), &pkey).unwrap();
+    verifier.update(format!("{}.{}", parts[0], parts[1]).as_bytes()).unwrap();
+    assert!(verifier.verify(&URL_SAFE_NO_PAD.decode(parts[2]).unwrap()).unwrap(), "signature");
+    serde_json::from_slice(&URL_SAFE_NO_PAD.decode(parts[1]).unwrap()).unwrap()
+}
+
+/// The `code` query param of a redirect back to the relying party.
+fn code_from(location: &str) -> String {
+    url::Url::parse(location)
+        .unwrap()
+        .query_pairs()
+        .find(|(k, _)| k == "code")
+        .map(|(_, v)| v.into_owned())
+        .expect(location)
+}
+
+#[tokio::test]
+async fn oidc_code_flow_with_pkce_signs_in_a_relying_party() {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+    let server = spawn_test_server().await.expect("spawn");
+    let admin = client();
+    admin.post(server.url("/test/login?role=Admin")).send().await.unwrap();
+
+    // The admin registers the app; the secret is shown once.
+    let r = admin
+        .post(server.url("/admin/oidc-clients"))
+        .form(&[("name", "Photos"), ("redirect_uris", "http://rp.lan/cb\r\n")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(r.status(), StatusCode::OK);
+    let body = r.text().await.unwrap();
+    let client_id = code_by_id(&body, "new-client-id");
+    let secret = code_by_id(&body, "new-client-secret");
+    assert!(client_id.starts_with("oc_") && secret.starts_with("ocs_"), "{body}");
+    let r = admin.get(server.url("/admin/oidc-clients")).send().await.unwrap();
+    let listed = r.text().await.unwrap();
+    assert!(listed.contains(&client_id) && !listed.contains(&secret), "secret shown once");
+
+    // Discovery + JWKS, as the RP would fetch them.
+    let r = reqwest::get(server.url("/.well-known/openid-configuration")).await.unwrap();
+    let discovery: serde_json::Value = r.json().await.unwrap();
+    let issuer = discovery["issuer"].as_str().unwrap().to_string();
+    assert_eq!(discovery["code_challenge_methods_supported"][0], "S256");
+    let jwks: serde_json::Value =
+        reqwest::get(server.url("/oidc/jwks")).await.unwrap().json().await.unwrap();
+
+    let verifier = "rp-stand-in-pkce-verifier-0123456789-abcdefghij";
+    let challenge = URL_SAFE_NO_PAD.encode(openssl::sha::sha256(verifier.as_bytes()));
+    let authorize = |redirect_uri: &'static str| {
+        vec![
+            ("response_type", "code".to_string()),
+            ("client_id", client_id.clone()),
+            ("redirect_uri", redirect_uri.to_string()),
+            ("scope", "openid profile".to_string()),
+            ("state", "st-1".to_string()),
+            ("nonce", "n-1".to_string()),
+            ("code_challenge", challenge.clone()),
+            ("code_challenge_method", "S256".to_string()),
+        ]
+    };
+
+    // An unregistered redirect URI is refused outright — never redirected to.
+    let r = client()
+        .get(server.url("/oidc/authorize"))
+        .query(&authorize("http://evil.lan/cb"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(r.status(), StatusCode::BAD_REQUEST);
+    assert!(location(&r).is_none());
+
+    // Signed out: through the login page, which comes straight back here.
+    let user = client();
+    let sign_in = || user.get(server.url("/oidc/authorize")).query(&authorize("http://rp.lan/cb"));
+    let r = sign_in().send().await.unwrap();
+    assert!(r.status().is_redirection());
+    let to = location(&r).unwrap();
+    assert!(to.starts_with("/login?next=%2Foidc%2Fauthorize%3F"), "{to}");
+
+    // Signed in: the consent screen, then Allow → back to the RP with a code.
+    user.post(server.url("/test/login?role=Family")).send().await.unwrap();
+    let r = sign_in().send().await.unwrap();
+    assert_eq!(r.status(), StatusCode::OK);
+    let body = r.text().await.unwrap();
+    assert!(body.contains("oidc-consent") && body.contains("Photos"), "{body}");
+    assert!(body.contains("rp.lan"), "the consent screen names where it sends you");
+    let r = user
+        .post(server.url("/oidc/authorize"))
+        .form(&[("decision", "allow")])
+        .send()
+        .await
+        .unwrap();
+    let back = location(&r).expect("redirect back to the RP");
+    assert!(back.starts_with("http://rp.lan/cb?code=") && back.contains("state=st-1"), "{back}");
+
+    // A wrong verifier fails — and burns the code, so the right one can't follow.
+    let exchange = |code: String, verifier: &'static str| {
+        client()
+            .post(server.url("/oidc/token"))
+            .basic_auth(&client_id, Some(&secret))
+            .form(&[
+                ("grant_type", "authorization_code".to_string()),
+                ("code", code),
+                ("redirect_uri", "http://rp.lan/cb".to_string()),
+                ("code_verifier", verifier.to_string()),
+            ])
+            .send()
+    };
+    let code = code_from(&back);
+    let wrong = "wrong-verifier-wrong-verifier-wrong-verifier-x";
+    let r = exchange(code.clone(), wrong).await.unwrap();
+    assert_eq!(r.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(r.json::<serde_json::Value>().await.unwrap()["error"], "invalid_grant");
+    let r = exchange(code, verifier).await.unwrap();
+    assert_eq!(r.status(), StatusCode::BAD_REQUEST, "a code is single use");
+
+    // Consent is remembered: the next sign-in skips the screen.
+    let r = sign_in().send().await.unwrap();
+    let back = location(&r).expect("straight back to the RP");
+    let r = exchange(code_from(&back), verifier).await.unwrap();
+    assert_eq!(r.status(), StatusCode::OK);
+    assert_eq!(r.headers().get("cache-control").unwrap(), "no-store");
+    let tokens: serde_json::Value = r.json().await.unwrap();
+
+    // The RP verifies the ID token against the JWKS and reads the role claim.
+    let claims = verify_with_jwks(&jwks, tokens["id_token"].as_str().unwrap());
+    assert_eq!(claims["iss"], issuer.as_str());
+    assert_eq!(claims["aud"], client_id.as_str());
+    assert_eq!(claims["nonce"], "n-1");
+    assert_eq!(claims["role"], "Family");
+    assert_eq!(claims["name"], "test-Family");
+
+    // Userinfo takes the access token — not the ID token.
+    let r = client()
+        .get(server.url("/oidc/userinfo"))
+        .bearer_auth(tokens["access_token"].as_str().unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(r.status(), StatusCode::OK);
+    let info: serde_json::Value = r.json().await.unwrap();
+    assert_eq!(info["sub"], claims["sub"]);
+    assert_eq!(info["role"], "Family");
+    let r = client()
+        .get(server.url("/oidc/userinfo"))
+        .bearer_auth(tokens["id_token"].as_str().unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(r.status(), StatusCode::UNAUTHORIZED);
+    let access_header = tokens["access_token"].as_str().unwrap().split('.').next().unwrap();
+    let header: serde_json::Value =
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(access_header).unwrap()).unwrap();
+    assert_eq!(header["typ"], "at+jwt", "typed apart from the ID token");
+
+    // A wrong client secret is invalid_client; a non-admin can't register apps.
+    let r = client()
+        .post(server.url("/oidc/token"))
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", "occ_x"),
+            ("client_id", client_id.as_str()),
+            ("client_secret", "ocs_wrong"),
+        ])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(r.status(), StatusCode::UNAUTHORIZED);
+    let r = user
+        .post(server.url("/admin/oidc-clients"))
+        .form(&[("name", "Sneaky"), ("redirect_uris", "http://rp.lan/x")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(r.status(), StatusCode::FORBIDDEN);
+
+    // Once the client is unregistered its access tokens stop working, unexpired or not.
+    sqlx::query("DELETE FROM oidc_clients WHERE client_id = ?1")
+        .bind(&client_id)
+        .execute(&server.pool)
+        .await
+        .unwrap();
+    let r = client()
+        .get(server.url("/oidc/userinfo"))
+        .bearer_auth(tokens["access_token"].as_str().unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(r.status(), StatusCode::UNAUTHORIZED);
+}
+
+/// The `nonce-…` value out of a Content-Security-Policy header.
+fn csp_nonce(policy: &str) -> String {
+    let start = policy.find("'nonce-").expect("policy carries a nonce") + "'nonce-".len();
+    let end = start + policy[start..].find('\'').unwrap();
+    policy[start..end].to_string()
+}
+
+#[tokio::test]
+async fn csp_nonces_scripts_and_collects_violation_reports() {
+    let server = spawn_test_server().await.expect("spawn");
+
+    // Every HTML page carries an enforced policy whose fresh nonce is on its scripts.
+    let mut nonces = Vec::new();
+    for _ in 0..2 {
+        let r = reqwest::get(server.url("/")).await.unwrap();
+        let policy = r.headers()["content-security-policy"].to_str().unwrap().to_string();
+        assert!(policy.contains("'strict-dynamic'") && policy.contains("object-src 'none'"));
+        assert!(!policy.contains("wasm-unsafe-eval"), "{policy}");
+        let nonce = csp_nonce(&policy);
+        let body = r.text().await.unwrap();
+        assert!(body.contains(&format!("<script nonce=\"{nonce}\"")), "{body}");
+        nonces.push(nonce);
+    }
+    assert_ne!(nonces[0], nonces[1], "a nonce per response");
+
+    // The WASM editor gets its own family; JSON gets no policy at all.
+    let r = reqwest::get(server.url("/3d/editor")).await.unwrap();
+    let policy = r.headers()["content-security-policy"].to_str().unwrap();
+    assert!(policy.contains("'wasm-unsafe-eval'"), "{policy}");
+    let r = reqwest::get(server.url("/.well-known/openid-configuration")).await.unwrap();
+    assert!(r.headers().get("content-security-policy").is_none());
+
+    // An anonymous browser reports a violation; the stored copy has no query.
+    let report = serde_json::json!({ "csp-report": {
+        "document-uri": "https://hotchkiss.io/login/invite?token=hinv_secret",
+        "effective-directive": "script-src-elem",
+        "blocked-uri": "https://evil.example/x.js?q=1",
+        "disposition": "enforce",
+    }});
+    let r = reqwest::Client::new()
+        .post(server.url("/csp-report"))
+        .header("content-type", "application/csp-report")
+        .body(report.to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(r.status(), StatusCode::NO_CONTENT);
+    let r = reqwest::Client::new()
+        .post(server.url("/csp-report"))
+        .body("not json")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(r.status(), StatusCode::BAD_REQUEST);
+
+    let admin = client();
+    admin.post(server.url("/test/login?role=Admin")).send().await.unwrap();
+    let body = admin
+        .get(server.url("/admin/csp-reports"))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(body.contains("https://evil.example/x.js") && !body.contains("q=1"), "{body}");
+    assert!(body.contains("/login/invite") && !body.contains("hinv_secret"));
+    assert!(body.contains("Enforcing"), "the test harness enforces");
+
+    let family = client();
+    family.post(server.url("/test/login?role=Family")).send().await.unwrap();
+    let r = family.get(server.url("/admin/csp-reports")).send().await.unwrap();
+    assert_eq!(r.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn login_ceremony_bursts_are_rate_limited_per_caller() {
+    let server = spawn_test_server().await.expect("spawn");
+    let anon = client();
+    let beacon = || anon.post(server.url("/login/ceremony_error")).body("{}").send();
+
+    // The shipped login bucket holds 20; the 21st request from this IP is refused.
+    for _ in 0..20 {
+        assert_eq!(beacon().await.unwrap().status(), StatusCode::NO_CONTENT);
+    }
+    let r = beacon().await.unwrap();
+    assert_eq!(r.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry: u64 = r.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+    assert!((1..=60).contains(&retry), "Retry-After {retry}");
+
+    // A signed-in caller has their own budget, even from the same IP.
+    let admin = client();
+    admin.post(server.url("/test/login?role=Admin")).send().await.unwrap();
+    let r = admin.post(server.url("/login/ceremony_error")).body("{}").send().await.unwrap();
+    assert_eq!(r.status(), StatusCode::NO_CONTENT);
+
+    // The refusal is logged as a rate limit (not a toll) and shows in analytics.
+    let mut limited = 0i64;
+    for _ in 0..200 {
+        limited = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM request_log WHERE rate_limited = 1 AND challenged IS NOT 1",
+        )
+        .fetch_one(&server.pool)
+        .await
+        .unwrap();
+        if limited > 0 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(limited, 1);
+    let body = admin
+        .get(server.url("/admin/analytics"))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(body.contains("Rate limiting") && body.contains("requests limited"), "{body}");
+}
+
+/// `/metrics` serves the Prometheus text format to an admin (session or API key) or the
+/// configured scrape token, and nobody else; requests are counted by route template.
+#[tokio::test]
+async fn metrics_are_admin_or_token_gated() {
+    use hotchkiss_io::test_support::TEST_METRICS_TOKEN;
+
+    let server = spawn_test_server().await.expect("spawn");
+    let c = client();
+    let get = |auth: Option<String>| {
+        let mut req = c.get(server.url("/metrics"));
+        if let Some(auth) = auth {
+            req = req.header("Authorization", auth);
+        }
+        req.send()
+    };
+
+    assert_eq!(get(None).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    let wrong = Some("Bearer not-the-token".to_string());
+    assert_eq!(get(wrong).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    let registered = server.seed_registered_api_key("ci").await.expect("seed key");
+    assert_eq!(
+        get(Some(format!("Bearer {registered}"))).await.unwrap().status(),
+        StatusCode::FORBIDDEN,
+        "signed in but not an admin"
+    );
+    let admin = server.seed_admin_api_key("ci").await.expect("seed key");
+    assert_eq!(get(Some(format!("Bearer {admin}"))).await.unwrap().status(), StatusCode::OK);
+
+    for path in ["/robots.txt", "/robots.txt", "/no-such-page-here"] {
+        c.get(server.url(path)).send().await.unwrap();
+    }
+    let resp = get(Some(format!("Bearer {TEST_METRICS_TOKEN}"))).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let content_type = resp.headers()["content-type"].to_str().unwrap().to_string();
+    assert!(content_type.starts_with("text/plain; version=0.0.4"), "{content_type}");
+    let body = resp.text().await.unwrap();
+    for line in [
+        r#"hio_http_requests_total{method="GET",route="/robots.txt",status="200"} 2"#,
+        r#"hio_http_requests_total{method="GET",route="unmatched",status="404"} 1"#,
+        r#"hio_greylist_entries{kind="ip"} 0"#,
+        r#"hio_request_log_dropped_total 0"#,
+    ] {
+        assert!(body.lines().any(|l| l == line), "missing {line:?} in:\n{body}");
+    }
+    for family in [
+        "hio_http_request_duration_seconds",
+        "hio_db_pool_connections",
+        "hio_media_root_present",
+        "hio_tls_certificate_expiry_timestamp_seconds",
+        "hio_task_last_duration_seconds",
+        "hio_request_log_queued",
+    ] {
+        assert!(body.contains(&format!("# TYPE {family} ")), "no {family} in:\n{body}");
+    }
+}